@@ -0,0 +1,75 @@
+//! The cost of rendering a formula, for each output.
+//!
+//! Run with `cargo bench --bench render`. There is no benchmark harness, so this just times
+//! enough rounds over the formulas to take about a second, and prints the average time for one
+//! formula.
+//!
+//! Indexing the builders by node type in one shared registry, instead of rebuilding the
+//! function table wherever it was used and scanning it for each node, took a release build
+//! from about
+//!
+//! | output | before  | after   |
+//! |--------|---------|---------|
+//! | parse  | 110µs   | 73µs    |
+//! | html   | 1.3ms   | 244µs   |
+//! | mathml | 1.0ms   | 92µs    |
+//! | ir     | 139µs   | 80µs    |
+//!
+//! Later changes have made every output faster still, so these are only useful as a comparison.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use aliter::{
+    ir, parse_tree, parser::ParserConfig, render_to_html_tree, render_to_mathml_tree,
+    tree::VirtualNode, Options,
+};
+
+const FORMULAS: &[&str] = &[
+    "x",
+    "x^2 + y^2 = z^2",
+    r"\frac{-b \pm \sqrt{b^2 - 4ac}}{2a}",
+    r"\sum_{i=0}^{n} \binom{n}{i} x^i y^{n-i}",
+    r"\int_0^\infty e^{-x^2} \,dx = \frac{\sqrt{\pi}}{2}",
+    r"\left( \begin{matrix} a & b \\ c & d \end{matrix} \right)",
+    r"\mathbb{R} \to \mathcal{C}, \quad f(x) = \overbrace{x + \cdots + x}^{n}",
+    r"\text{if } x \in A \text{ then } \hat{x} \ne \vec{y}",
+];
+
+fn bench(name: &str, mut render: impl FnMut(&str)) {
+    // Warm up, so the lazily built tables aren't counted
+    for formula in FORMULAS {
+        render(formula);
+    }
+
+    let start = Instant::now();
+    let mut rounds = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        for formula in FORMULAS {
+            render(formula);
+        }
+        rounds += 1;
+    }
+
+    let per_formula = start.elapsed() / (rounds * FORMULAS.len() as u32);
+    println!("{:<8} {:>10.1?} per formula", name, per_formula);
+}
+
+fn main() {
+    bench("parse", |formula| {
+        black_box(parse_tree(formula, ParserConfig::default()).unwrap());
+    });
+    bench("html", |formula| {
+        black_box(render_to_html_tree(formula, ParserConfig::default()).to_markup());
+    });
+    bench("mathml", |formula| {
+        black_box(render_to_mathml_tree(formula, ParserConfig::default()).to_markup());
+    });
+    bench("ir", |formula| {
+        let conf = ParserConfig::default();
+        let tree = parse_tree(formula, conf.clone()).unwrap();
+        black_box(ir::build_ir(&tree, &Options::from_parser_conf(&conf)));
+    });
+}
@@ -0,0 +1,299 @@
+//! Generates the static symbol and font metric tables, so that looking up a symbol or the
+//! metrics of a character is a perfect hash rather than a search, and they need no
+//! initialization at runtime.
+//!
+//! - `symbols.rs` is generated from the definitions in `src/symbols_data.rs`
+//! - `font_metrics_data.rs` is generated from `font_metrics_data/font_metrics.json`, which is
+//!   KaTeX's `fontMetricsData.js` as json
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SYMBOLS_DATA: &str = "src/symbols_data.rs";
+const FONT_METRICS_JSON: &str = "font_metrics_data/font_metrics.json";
+
+fn main() {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={SYMBOLS_DATA}");
+    println!("cargo:rerun-if-changed={FONT_METRICS_JSON}");
+
+    write_symbols(&out_dir.join("symbols.rs"));
+    write_font_metrics(&out_dir.join("font_metrics_data.rs"));
+}
+
+// ==== Symbols ====
+
+/// Mirrors of the types in `src/symbols.rs` that `symbols_data.rs` uses, which are written out
+/// as the Rust source that constructs them
+mod symbols {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Font {
+        Main,
+        Ams,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Atom {
+        Bin,
+        Close,
+        Inner,
+        Open,
+        Punct,
+        Rel,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum NonAtom {
+        AccentToken,
+        MathOrd,
+        OpToken,
+        Spacing,
+        TextOrd,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Group {
+        Atom(Atom),
+        NonAtom(NonAtom),
+    }
+
+    /// The source of each symbol, keyed by name. Like the `HashMap`s these replace, a later
+    /// definition of a name overrides an earlier one.
+    #[derive(Default)]
+    pub struct Symbols {
+        pub math: BTreeMap<&'static str, String>,
+        pub text: BTreeMap<&'static str, String>,
+    }
+    impl Symbols {
+        fn symbol_source(font: Font, group: Group, replace: &str) -> String {
+            let group = match group {
+                Group::Atom(atom) => format!("Group::Atom(Atom::{atom:?})"),
+                Group::NonAtom(non_atom) => format!("Group::NonAtom(NonAtom::{non_atom:?})"),
+            };
+            format!("Symbol {{ font: Font::{font:?}, group: {group}, replace: Some({replace:?}) }}")
+        }
+
+        fn define(
+            table: &mut BTreeMap<&'static str, String>,
+            font: Font,
+            g: Group,
+            r: &'static str,
+            name: &'static str,
+            accept_unicode_char: bool,
+        ) {
+            let symbol = Symbols::symbol_source(font, g, r);
+            table.insert(name, symbol.clone());
+            if accept_unicode_char && !r.is_empty() {
+                table.insert(r, symbol);
+            }
+        }
+
+        /// Common math
+        /// This means `math` and font=`main`
+        fn cmath(
+            &mut self,
+            g: Group,
+            r: &'static str,
+            name: &'static str,
+            accept_unicode_char: bool,
+        ) {
+            Symbols::define(&mut self.math, Font::Main, g, r, name, accept_unicode_char);
+        }
+
+        /// Common text
+        /// This means `text` and font=`main`
+        fn ctext(
+            &mut self,
+            g: Group,
+            r: &'static str,
+            name: &'static str,
+            accept_unicode_char: bool,
+        ) {
+            Symbols::define(&mut self.text, Font::Main, g, r, name, accept_unicode_char);
+        }
+
+        /// Ams math
+        /// This means `math` and font=`ams`
+        fn amath(
+            &mut self,
+            g: Group,
+            r: &'static str,
+            name: &'static str,
+            accept_unicode_char: bool,
+        ) {
+            Symbols::define(&mut self.math, Font::Ams, g, r, name, accept_unicode_char);
+        }
+
+        /// Ams text
+        /// This means `text` and font=`ams`
+        fn atext(
+            &mut self,
+            g: Group,
+            r: &'static str,
+            name: &'static str,
+            accept_unicode_char: bool,
+        ) {
+            Symbols::define(&mut self.text, Font::Ams, g, r, name, accept_unicode_char);
+        }
+    }
+
+    include!("src/symbols_data.rs");
+
+    pub fn symbols() -> Symbols {
+        let mut s = Symbols::default();
+        define_symbols(&mut s);
+        s
+    }
+}
+
+fn write_symbols(path: &Path) {
+    let symbols = symbols::symbols();
+
+    let mut out = String::new();
+    for (name, table) in [
+        ("MATH_SYMBOLS", &symbols.math),
+        ("TEXT_SYMBOLS", &symbols.text),
+    ] {
+        let mut map = phf_codegen::Map::new();
+        for (key, symbol) in table {
+            map.entry(*key, symbol);
+        }
+        writeln!(
+            out,
+            "static {name}: phf::Map<&'static str, Symbol<'static>> = {};",
+            map.build()
+        )
+        .unwrap();
+    }
+
+    fs::write(path, out).unwrap();
+}
+
+// ==== Font Metrics ====
+
+fn write_font_metrics(path: &Path) {
+    let json = fs::read_to_string(FONT_METRICS_JSON).unwrap();
+    let fonts = parse_font_metrics(&json);
+
+    let mut out = String::new();
+    let mut font_map = phf_codegen::Map::new();
+    for (font, metrics) in &fonts {
+        // `Main-Regular` is in `MAIN_REGULAR`
+        let const_name = font.replace('-', "_").to_uppercase();
+
+        let mut map = phf_codegen::Map::new();
+        for (code, metric) in metrics {
+            let metric = metric.iter().map(|v| format!("{v:?}")).collect::<Vec<_>>();
+            map.entry(*code, &format!("[{}]", metric.join(", ")));
+        }
+        writeln!(
+            out,
+            "static {const_name}: phf::Map<u16, MetricData> = {};",
+            map.build()
+        )
+        .unwrap();
+
+        font_map.entry(font.as_str(), &format!("&{const_name}"));
+    }
+    writeln!(
+        out,
+        "static FONTS: phf::Map<&'static str, &'static phf::Map<u16, MetricData>> = {};",
+        font_map.build()
+    )
+    .unwrap();
+
+    fs::write(path, out).unwrap();
+}
+
+/// Parse the font metrics json, which is an object of fonts, each of which is an object from
+/// character codes to an array of five numbers.
+/// This is all the json that the file uses, so there's no need for a full parser.
+fn parse_font_metrics(json: &str) -> BTreeMap<String, BTreeMap<u16, [f64; 5]>> {
+    let mut parser = JsonParser { rest: json };
+    let mut fonts = BTreeMap::new();
+
+    parser.expect('{');
+    while !parser.eat('}') {
+        let font = parser.string();
+        parser.expect(':');
+
+        let mut metrics = BTreeMap::new();
+        parser.expect('{');
+        while !parser.eat('}') {
+            let code = parser.string().parse::<u16>().unwrap();
+            parser.expect(':');
+
+            let mut metric = [0.0; 5];
+            parser.expect('[');
+            for (i, value) in metric.iter_mut().enumerate() {
+                if i != 0 {
+                    parser.expect(',');
+                }
+                *value = parser.number();
+            }
+            parser.expect(']');
+
+            metrics.insert(code, metric);
+            parser.eat(',');
+        }
+
+        fonts.insert(font, metrics);
+        parser.eat(',');
+    }
+
+    fonts
+}
+
+struct JsonParser<'a> {
+    rest: &'a str,
+}
+impl<'a> JsonParser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if let Some(rest) = self.rest.strip_prefix(c) {
+            self.rest = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) {
+        if !self.eat(c) {
+            let found = self.rest.chars().take(20).collect::<String>();
+            panic!("Expected {c:?} in font metrics json, found {found:?}");
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.expect('"');
+        let end = self.rest.find('"').unwrap();
+        let value = self.rest[..end].to_string();
+        self.rest = &self.rest[end + 1..];
+        value
+    }
+
+    fn number(&mut self) -> f64 {
+        self.skip_whitespace();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(self.rest.len());
+        let value = self.rest[..end].parse().unwrap();
+        self.rest = &self.rest[end..];
+        value
+    }
+}
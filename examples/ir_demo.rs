@@ -276,6 +276,7 @@ fn element_type(elem: &ir::MathElement) -> &'static str {
         ir::MathElement::Scripts { .. } => "Scripts",
         ir::MathElement::Radical { .. } => "Radical",
         ir::MathElement::Accent { .. } => "Accent",
+        ir::MathElement::Brace { .. } => "Brace",
         ir::MathElement::Delimited { .. } => "Delimited",
         ir::MathElement::LargeOp { .. } => "LargeOp",
         ir::MathElement::Array { .. } => "Array",
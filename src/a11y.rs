@@ -0,0 +1,854 @@
+//! Spoken descriptions of formulas for screen readers, based on KaTeX's
+//! `contrib/render-a11y-string`.
+//!
+//! [`to_speech`] describes a parse tree in English, marking where each fraction, script and
+//! root starts and ends so that the structure can be followed without seeing it:
+//! ```
+//! # use aliter::{a11y::{to_speech, to_speech_with, SpeechStyle}, parse_tree, parser::ParserConfig};
+//! let tree = parse_tree(r"\frac{a+b}{c^2}", ParserConfig::default()).unwrap();
+//! assert_eq!(
+//!     to_speech(&tree),
+//!     "start fraction, a, plus, b, divided by, c, squared, end fraction"
+//! );
+//! assert_eq!(
+//!     to_speech_with(&tree, SpeechStyle::Terse),
+//!     "fraction a plus b over c squared end fraction"
+//! );
+//! ```
+//!
+//! Setting [`ParserConfig::aria_label`] puts the description in an `aria-label` on the root of
+//! the HTML output.
+
+use crate::prelude::*;
+use crate::{
+    array::ColSeparationType,
+    expander::Mode,
+    parse_node::{ArrayNode, GenFracNode, ParseNode, SupSubNode},
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    symbols::Atom,
+    unicode_text::to_unicode_text,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SpeechStyle {
+    /// Every structure is announced where it starts and ends, like
+    /// `start fraction, 1, divided by, 2, end fraction`
+    #[default]
+    Verbose,
+    /// Shorter phrases that leave out the end of simple structures, like `1 over 2`
+    Terse,
+}
+
+/// Describe the parse tree in English, in the verbose style
+pub fn to_speech(nodes: &[ParseNode]) -> String {
+    to_speech_with(nodes, SpeechStyle::Verbose)
+}
+
+/// Describe the parse tree in English
+pub fn to_speech_with(nodes: &[ParseNode], style: SpeechStyle) -> String {
+    let mut speech = Speech::new(style);
+    speech.build_list(nodes, SymbolClass::Normal);
+    let sep = match style {
+        SpeechStyle::Verbose => ", ",
+        SpeechStyle::Terse => " ",
+    };
+    speech.strings.join(sep)
+}
+
+/// Parse the expression and describe it in English, in the verbose style
+pub fn render_a11y_string(expr: &str, conf: ParserConfig) -> Result<String, ParseError> {
+    let tree = parse_tree(expr, conf)?;
+    Ok(to_speech(&tree))
+}
+
+const STRING_MAP: &[(&str, &str)] = &[
+    ("(", "left parenthesis"),
+    (")", "right parenthesis"),
+    ("[", "open bracket"),
+    ("]", "close bracket"),
+    ("{", "left brace"),
+    ("}", "right brace"),
+    ("\\{", "left brace"),
+    ("\\}", "right brace"),
+    ("\\lbrace", "left brace"),
+    ("\\rbrace", "right brace"),
+    ("\\lvert", "open vertical bar"),
+    ("\\rvert", "close vertical bar"),
+    ("|", "vertical bar"),
+    ("\\vert", "vertical bar"),
+    ("||", "double vertical bar"),
+    ("\\|", "double vertical bar"),
+    ("\\Vert", "double vertical bar"),
+    ("\\uparrow", "up arrow"),
+    ("\\Uparrow", "up arrow"),
+    ("\\downarrow", "down arrow"),
+    ("\\Downarrow", "down arrow"),
+    ("\\updownarrow", "up down arrow"),
+    ("\\leftarrow", "left arrow"),
+    ("\\Leftarrow", "left arrow"),
+    ("\\rightarrow", "right arrow"),
+    ("\\Rightarrow", "right arrow"),
+    ("\\to", "right arrow"),
+    ("\\mapsto", "maps to"),
+    ("\\langle", "open angle"),
+    ("\\rangle", "close angle"),
+    ("⟨", "open angle"),
+    ("⟩", "close angle"),
+    ("\\lfloor", "open floor"),
+    ("\\rfloor", "close floor"),
+    ("\\lceil", "open ceiling"),
+    ("\\rceil", "close ceiling"),
+    ("\\int", "integral"),
+    ("\\intop", "integral"),
+    ("\\iint", "double integral"),
+    ("\\iiint", "triple integral"),
+    ("\\oint", "contour integral"),
+    ("\\lim", "limit"),
+    ("\\ln", "natural log"),
+    ("\\log", "log"),
+    ("\\sin", "sine"),
+    ("\\cos", "cosine"),
+    ("\\tan", "tangent"),
+    ("\\cot", "cotangent"),
+    ("\\sec", "secant"),
+    ("\\csc", "cosecant"),
+    ("\\max", "maximum"),
+    ("\\min", "minimum"),
+    ("\\sum", "sum"),
+    ("\\prod", "product"),
+    ("/", "slash"),
+    (",", "comma"),
+    (".", "point"),
+    ("-", "negative"),
+    ("+", "plus"),
+    ("~", "tilde"),
+    (":", "colon"),
+    (";", "semicolon"),
+    ("!", "factorial"),
+    ("?", "question mark"),
+    ("'", "apostrophe"),
+    ("\\%", "percent"),
+    (" ", "space"),
+    ("\\ ", "space"),
+    ("\\$", "dollar sign"),
+    ("\\angle", "angle"),
+    ("\\degree", "degree"),
+    ("\\circ", "circle"),
+    ("\\triangle", "triangle"),
+    ("\\prime", "prime"),
+    ("\\infty", "infinity"),
+    ("\\partial", "partial"),
+    ("\\nabla", "del"),
+    ("\\ell", "ell"),
+    ("\\hbar", "h bar"),
+    ("\\emptyset", "empty set"),
+    ("\\forall", "for all"),
+    ("\\exists", "there exists"),
+    ("\\neg", "not"),
+    ("\\ldots", "dots"),
+    ("\\cdots", "dots"),
+    ("\\dots", "dots"),
+    ("\\vdots", "vertical dots"),
+    ("\\ddots", "diagonal dots"),
+];
+
+/// Scripts that are read as a single word, like `squared`
+const POWER_MAP: &[(&str, &str)] = &[
+    ("prime", "prime"),
+    ("degree", "degrees"),
+    ("circle", "degrees"),
+    ("2", "squared"),
+    ("3", "cubed"),
+];
+
+const OPEN_MAP: &[(&str, &str)] = &[("|", "open vertical bar"), (".", "")];
+
+const CLOSE_MAP: &[(&str, &str)] = &[("|", "close vertical bar"), (".", "")];
+
+const BIN_MAP: &[(&str, &str)] = &[
+    ("+", "plus"),
+    ("-", "minus"),
+    ("\\pm", "plus minus"),
+    ("\\mp", "minus plus"),
+    ("\\cdot", "dot"),
+    ("*", "times"),
+    ("/", "divided by"),
+    ("\\times", "times"),
+    ("\\div", "divided by"),
+    ("\\circ", "circle"),
+    ("\\bullet", "bullet"),
+    ("\\cup", "union"),
+    ("\\cap", "intersection"),
+    ("\\setminus", "set minus"),
+];
+
+const REL_MAP: &[(&str, &str)] = &[
+    ("=", "equals"),
+    ("\\approx", "approximately equals"),
+    ("≠", "does not equal"),
+    ("\\neq", "does not equal"),
+    ("\\ne", "does not equal"),
+    ("\\equiv", "is equivalent to"),
+    ("\\sim", "is similar to"),
+    ("\\geq", "is greater than or equal to"),
+    ("\\ge", "is greater than or equal to"),
+    ("\\leq", "is less than or equal to"),
+    ("\\le", "is less than or equal to"),
+    (">", "is greater than"),
+    ("<", "is less than"),
+    ("\\in", "is an element of"),
+    ("\\subset", "is a subset of"),
+    ("\\subseteq", "is a subset of or equal to"),
+    ("\\leftarrow", "left arrow"),
+    ("\\Leftarrow", "left arrow"),
+    ("\\rightarrow", "right arrow"),
+    ("\\Rightarrow", "right arrow"),
+    ("\\to", "right arrow"),
+    ("\\iff", "if and only if"),
+    (":", "colon"),
+];
+
+const ACCENT_MAP: &[(&str, &str)] = &[
+    ("\\hat", "hat"),
+    ("\\widehat", "hat"),
+    ("\\check", "check"),
+    ("\\widecheck", "check"),
+    ("\\tilde", "tilde"),
+    ("\\widetilde", "tilde"),
+    ("\\acute", "acute"),
+    ("\\grave", "grave"),
+    ("\\dot", "dot"),
+    ("\\ddot", "double dot"),
+    ("\\dddot", "triple dot"),
+    ("\\bar", "bar"),
+    ("\\breve", "breve"),
+    ("\\mathring", "ring"),
+    ("\\vec", "vector"),
+    ("\\overrightarrow", "right arrow"),
+    ("\\overleftarrow", "left arrow"),
+    ("\\overleftrightarrow", "left-right arrow"),
+    ("\\overgroup", "group"),
+    ("\\overlinesegment", "line segment"),
+];
+
+const ACCENT_UNDER_MAP: &[(&str, &str)] = &[
+    ("\\underleftarrow", "left arrow"),
+    ("\\underrightarrow", "right arrow"),
+    ("\\underleftrightarrow", "left-right arrow"),
+    ("\\undergroup", "group"),
+    ("\\underlinesegment", "line segment"),
+    ("\\utilde", "tilde"),
+];
+
+const GREEK: &[&str] = &[
+    "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa",
+    "lambda", "mu", "nu", "xi", "omicron", "pi", "rho", "sigma", "tau", "upsilon", "phi", "chi",
+    "psi", "omega",
+];
+
+fn lookup(map: &[(&str, &'static str)], text: &str) -> Option<&'static str> {
+    map.iter()
+        .find(|(key, _)| *key == text)
+        .map(|(_, word)| *word)
+}
+
+/// The word for a control sequence that isn't in any of the maps, like `\beta` or `\Gamma`
+fn control_word(text: &str) -> Option<String> {
+    let name = text.strip_prefix('\\')?;
+    let lower = name.to_lowercase();
+    let letter = lower.strip_prefix("var").unwrap_or(&lower);
+    if GREEK.contains(&letter) {
+        let capital = name.starts_with(|c: char| c.is_ascii_uppercase());
+        return Some(if capital {
+            format!("capital {}", letter)
+        } else {
+            letter.to_string()
+        });
+    }
+
+    Some(name.to_string())
+}
+
+/// How a symbol is used, which changes how it is read, like `-` being `minus` as a binary
+/// operator and `negative` otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolClass {
+    Normal,
+    Open,
+    Close,
+    Bin,
+    Rel,
+    Punct,
+    Inner,
+}
+impl SymbolClass {
+    fn from_mclass(mclass: &str) -> SymbolClass {
+        match mclass {
+            "mopen" => SymbolClass::Open,
+            "mclose" => SymbolClass::Close,
+            "mbin" => SymbolClass::Bin,
+            "mrel" => SymbolClass::Rel,
+            "mpunct" => SymbolClass::Punct,
+            "minner" => SymbolClass::Inner,
+            _ => SymbolClass::Normal,
+        }
+    }
+}
+impl From<Atom> for SymbolClass {
+    fn from(atom: Atom) -> SymbolClass {
+        match atom {
+            Atom::Bin => SymbolClass::Bin,
+            Atom::Close => SymbolClass::Close,
+            Atom::Inner => SymbolClass::Inner,
+            Atom::Open => SymbolClass::Open,
+            Atom::Punct => SymbolClass::Punct,
+            Atom::Rel => SymbolClass::Rel,
+        }
+    }
+}
+
+fn symbol_word(text: &str, class: SymbolClass) -> String {
+    let word = match class {
+        SymbolClass::Open => lookup(OPEN_MAP, text),
+        SymbolClass::Close => lookup(CLOSE_MAP, text),
+        SymbolClass::Bin => lookup(BIN_MAP, text),
+        SymbolClass::Rel => lookup(REL_MAP, text),
+        _ => None,
+    };
+
+    match word.or_else(|| lookup(STRING_MAP, text)) {
+        Some(word) => word.to_string(),
+        None => control_word(text).unwrap_or_else(|| text.to_string()),
+    }
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// The words at the start and end of a structure, in each style
+struct Region {
+    verbose: (&'static str, &'static str),
+    terse: (&'static str, &'static str),
+}
+impl Region {
+    const fn new(
+        verbose: (&'static str, &'static str),
+        terse: (&'static str, &'static str),
+    ) -> Self {
+        Region { verbose, terse }
+    }
+}
+
+struct Speech {
+    style: SpeechStyle,
+    strings: Vec<String>,
+    /// The length of `strings` after the last structure, so that numbers on either side of it
+    /// aren't merged together
+    region_end: usize,
+    /// Whether the last string is a word of text that the next letter should be added to
+    in_word: bool,
+    /// Whether the last string was an operator or opening, so that a `-` after it is a sign
+    after_operator: bool,
+    /// Whether there are more nodes after the one being built in its list
+    has_next: bool,
+}
+impl Speech {
+    fn new(style: SpeechStyle) -> Speech {
+        Speech {
+            style,
+            strings: Vec::new(),
+            region_end: 0,
+            in_word: false,
+            after_operator: false,
+            has_next: false,
+        }
+    }
+
+    fn is_terse(&self) -> bool {
+        self.style == SpeechStyle::Terse
+    }
+
+    fn push(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if !text.is_empty() {
+            self.strings.push(text);
+        }
+        self.in_word = false;
+        self.after_operator = false;
+    }
+
+    fn push_symbol(&mut self, text: &str, class: SymbolClass) {
+        // A binary operator with nothing before it is a sign, as in `-1`
+        let is_sign = class == SymbolClass::Bin
+            && (self.after_operator || self.strings.len() == self.region_end);
+        let class = if is_sign { SymbolClass::Normal } else { class };
+        let word = symbol_word(text, class);
+
+        // Digits are read together as a single number
+        let in_region = self.strings.len() > self.region_end;
+        match self.strings.last_mut().filter(|_| in_region) {
+            Some(last) if is_number(&word) && is_number(last) => last.push_str(&word),
+            _ => self.push(word),
+        }
+        self.after_operator = matches!(
+            class,
+            SymbolClass::Open | SymbolClass::Bin | SymbolClass::Rel | SymbolClass::Punct
+        );
+    }
+
+    /// Add a letter of text to the current word
+    fn push_text(&mut self, text: &str) {
+        let is_letter = text.chars().all(char::is_alphanumeric);
+        match self.strings.last_mut() {
+            Some(last) if is_letter && self.in_word => last.push_str(text),
+            _ if is_letter => {
+                self.push(text);
+                self.in_word = true;
+            }
+            _ => self.push_symbol(text, SymbolClass::Normal),
+        }
+    }
+
+    /// Build the strings for a node on their own, to look at before adding them
+    fn render(&self, node: &ParseNode) -> Vec<String> {
+        let mut speech = Speech::new(self.style);
+        speech.build_node(node, SymbolClass::Normal);
+        speech.strings
+    }
+
+    /// Build a structure between its start and end words.
+    /// In the terse style, the end is left out if the structure is a single word.
+    fn region(&mut self, region: Region, build: impl FnOnce(&mut Speech)) {
+        let (start, end) = match self.style {
+            SpeechStyle::Verbose => region.verbose,
+            SpeechStyle::Terse => region.terse,
+        };
+
+        self.push(start);
+        let body_start = self.strings.len();
+        self.region_end = body_start;
+        build(self);
+
+        let is_simple = self.strings.len() - body_start <= 1;
+        if !(self.is_terse() && is_simple) {
+            self.push(end);
+        }
+        self.region_end = self.strings.len();
+    }
+
+    fn build_list(&mut self, nodes: &[ParseNode], class: SymbolClass) {
+        let has_next = self.has_next;
+        for (i, node) in nodes.iter().enumerate() {
+            self.has_next = has_next || i + 1 < nodes.len();
+            self.build_node(node, class);
+        }
+        self.has_next = has_next;
+    }
+
+    fn build_node(&mut self, node: &ParseNode, class: SymbolClass) {
+        match node {
+            ParseNode::Atom(atom) => self.push_symbol(&atom.text, atom.family.into()),
+            ParseNode::TextOrd(ord) if ord.info.mode == Mode::Text => self.push_text(&ord.text),
+            ParseNode::MathOrd(ord) => self.push_symbol(&ord.text, SymbolClass::Normal),
+            ParseNode::TextOrd(ord) => self.push_symbol(&ord.text, class),
+            ParseNode::OpToken(token) => self.push_symbol(&token.text, class),
+            ParseNode::AccentToken(_) | ParseNode::ColorToken(_) => {}
+            ParseNode::Spacing(_) if node.info().mode == Mode::Text => self.in_word = false,
+            ParseNode::Spacing(_) => {
+                if !self.is_terse() {
+                    self.push("space");
+                }
+            }
+            ParseNode::OrdGroup(group) => self.build_list(&group.body, class),
+            ParseNode::SupSub(sup_sub) => self.build_sup_sub(sup_sub, class),
+            ParseNode::GenFrac(frac) => self.build_genfrac(frac, class),
+            ParseNode::Sqrt(sqrt) => {
+                let index = sqrt
+                    .index
+                    .as_ref()
+                    .map(|index| self.render(index).join(","));
+                match index.as_deref() {
+                    None => self.region(
+                        Region::new(
+                            ("square root of", "end square root"),
+                            ("square root of", "end root"),
+                        ),
+                        |speech| speech.build_node(&sqrt.body, class),
+                    ),
+                    Some("3") => self.region(
+                        Region::new(
+                            ("cube root of", "end cube root"),
+                            ("cube root of", "end root"),
+                        ),
+                        |speech| speech.build_node(&sqrt.body, class),
+                    ),
+                    Some(_) => {
+                        self.push("root");
+                        self.region(
+                            Region::new(("start index", "end index"), ("index", "")),
+                            |speech| {
+                                if let Some(index) = &sqrt.index {
+                                    speech.build_node(index, class);
+                                }
+                            },
+                        );
+                        self.region(
+                            Region::new(("of", "end root"), ("of", "end root")),
+                            |speech| speech.build_node(&sqrt.body, class),
+                        );
+                    }
+                }
+            }
+            ParseNode::LeftRight(left_right) => {
+                self.push_symbol(&left_right.left, SymbolClass::Open);
+                self.region_end = self.strings.len();
+                self.build_list(&left_right.body, class);
+                self.push_symbol(&left_right.right, SymbolClass::Close);
+                self.region_end = self.strings.len();
+            }
+            ParseNode::LeftRightRight(right) => self.push_symbol(&right.delim, SymbolClass::Close),
+            ParseNode::Middle(middle) => self.push_symbol(&middle.delim, class),
+            ParseNode::DelimSizing(delim) => {
+                if delim.delim != "." {
+                    self.push_symbol(&delim.delim, SymbolClass::Normal);
+                }
+            }
+            ParseNode::Op(op) => {
+                if let Some(body) = &op.body {
+                    self.build_list(body, class);
+                } else if let Some(name) = &op.name {
+                    self.push_symbol(name, SymbolClass::Normal);
+                }
+            }
+            ParseNode::OperatorName(op) => self.push(to_unicode_text(&op.body)),
+            ParseNode::Accent(accent) => {
+                let word = lookup(ACCENT_MAP, &accent.label)
+                    .map(str::to_string)
+                    .or_else(|| control_word(&accent.label))
+                    .unwrap_or_default();
+                self.build_accent(&accent.base, &word, "on top", class);
+            }
+            ParseNode::AccentUnder(accent) => {
+                let word = lookup(ACCENT_UNDER_MAP, &accent.label)
+                    .map(str::to_string)
+                    .or_else(|| control_word(&accent.label))
+                    .unwrap_or_default();
+                self.build_accent(&accent.base, &word, "underneath", class);
+            }
+            ParseNode::Color(color) => {
+                let name = color.color.to_string();
+                if self.is_terse() {
+                    self.build_list(&color.body, class);
+                } else {
+                    self.push(format!("start color {}", name));
+                    self.build_list(&color.body, class);
+                    self.push(format!("end color {}", name));
+                    self.region_end = self.strings.len();
+                }
+            }
+            ParseNode::Text(text) => {
+                let region = if text.font.as_deref() == Some("\\textbf") {
+                    Region::new(
+                        ("start bold text", "end bold text"),
+                        ("bold text", "end text"),
+                    )
+                } else {
+                    Region::new(("start text", "end text"), ("", ""))
+                };
+                self.region(region, |speech| speech.build_list(&text.body, class));
+            }
+            ParseNode::Font(font) => {
+                let style = match font.font.as_ref() {
+                    "mathbb" => "double-struck",
+                    "mathcal" | "mathscr" => "script",
+                    "mathfrak" => "fraktur",
+                    _ => "",
+                };
+                self.push(style);
+                self.build_node(&font.body, class);
+            }
+            ParseNode::MClass(mclass) => {
+                self.build_list(&mclass.body, SymbolClass::from_mclass(&mclass.m_class))
+            }
+            ParseNode::Overline(overline) => self.region(
+                Region::new(
+                    ("start overline", "end overline"),
+                    ("overline", "end overline"),
+                ),
+                |speech| speech.build_node(&overline.body, class),
+            ),
+            ParseNode::Underline(underline) => self.region(
+                Region::new(
+                    ("start underline", "end underline"),
+                    ("underline", "end underline"),
+                ),
+                |speech| speech.build_node(&underline.body, class),
+            ),
+            ParseNode::HorizBrace(brace) => {
+                let region = if brace.is_over {
+                    Region::new(
+                        ("start overbrace", "end overbrace"),
+                        ("overbrace", "end brace"),
+                    )
+                } else {
+                    Region::new(
+                        ("start underbrace", "end underbrace"),
+                        ("underbrace", "end brace"),
+                    )
+                };
+                self.region(region, |speech| speech.build_node(&brace.base, class));
+            }
+            ParseNode::XArrow(arrow) => {
+                let name = arrow.label.replacen("\\x", "\\", 1);
+                self.push_symbol(&name, SymbolClass::Rel);
+                self.region(
+                    Region::new(("with", "on top"), ("with", "on top")),
+                    |speech| speech.build_node(&arrow.body, class),
+                );
+                if let Some(below) = &arrow.below {
+                    self.region(
+                        Region::new(("with", "underneath"), ("with", "underneath")),
+                        |speech| speech.build_node(below, class),
+                    );
+                }
+            }
+            ParseNode::Enclose(enclose) => {
+                let label = enclose.label.as_str();
+                let region = if label.contains("cancel") {
+                    Region::new(("start cancel", "end cancel"), ("cancel", "end cancel"))
+                } else if label.contains("box") {
+                    Region::new(("start box", "end box"), ("box", "end box"))
+                } else if label.contains("sout") {
+                    Region::new(
+                        ("start strikeout", "end strikeout"),
+                        ("strikeout", "end strikeout"),
+                    )
+                } else if label.contains("phase") {
+                    Region::new(
+                        ("start phase angle", "end phase angle"),
+                        ("phase angle", "end angle"),
+                    )
+                } else if label.contains("angl") {
+                    Region::new(("start angle", "end angle"), ("angle", "end angle"))
+                } else {
+                    Region::new(("", ""), ("", ""))
+                };
+                self.region(region, |speech| speech.build_node(&enclose.body, class));
+            }
+            ParseNode::Array(array) => self.build_array(array, class),
+            ParseNode::Phantom(_) | ParseNode::HPhantom(_) => self.push("empty space"),
+            ParseNode::Rule(rule) => self.push(format!(
+                "rectangle {} by {}",
+                rule.width.to_string(),
+                rule.height.to_string()
+            )),
+            ParseNode::Tag(tag) => {
+                self.build_list(&tag.body, class);
+                self.region(
+                    Region::new(("start tag", "end tag"), ("tag", "end tag")),
+                    |speech| speech.build_list(&tag.tag, class),
+                );
+            }
+            ParseNode::Verb(verb) => self.region(
+                Region::new(
+                    ("start verbatim", "end verbatim"),
+                    ("verbatim", "end verbatim"),
+                ),
+                |speech| speech.push(verb.body.to_string()),
+            ),
+            ParseNode::IncludeGraphics(graphics) => self.push(graphics.alt.clone()),
+            ParseNode::Url(url) => self.push(url.url.clone()),
+            ParseNode::Styling(styling) => self.build_list(&styling.body, class),
+            ParseNode::Sizing(sizing) => self.build_list(&sizing.body, class),
+            ParseNode::Lap(lap) => self.build_node(&lap.body, class),
+            ParseNode::Smash(smash) => self.build_node(&smash.body, class),
+            ParseNode::RaiseBox(raise) => self.build_node(&raise.body, class),
+            ParseNode::VCenter(vcenter) => self.build_node(&vcenter.body, class),
+            ParseNode::HBox(hbox) => self.build_list(&hbox.body, class),
+            ParseNode::Html(html) => self.build_list(&html.body, class),
+            ParseNode::Href(href) => self.build_list(&href.body, class),
+            ParseNode::CdLabel(label) => self.build_node(&label.label, class),
+            ParseNode::CdLabelParentNode(parent) => self.build_node(&parent.fragment, class),
+            ParseNode::MathChoice(choice) => self.build_list(&choice.text, class),
+            ParseNode::HtmlMathml(html_mathml) => match html_mathml.mathml.as_slice() {
+                // The combining slash that negates the following relation, as in `\neq`
+                [ParseNode::TextOrd(ord)] if ord.text == "\u{338}" => self.push("not"),
+                mathml => self.build_list(mathml, class),
+            },
+            ParseNode::VPhantom(_)
+            | ParseNode::Kern(_)
+            | ParseNode::Raw(_)
+            | ParseNode::Size(_)
+            | ParseNode::Cr(_)
+            | ParseNode::Environment(_)
+            | ParseNode::Infix(_)
+            | ParseNode::Internal(_) => {}
+        }
+    }
+
+    fn build_accent(
+        &mut self,
+        base: &ParseNode,
+        word: &str,
+        position: &'static str,
+        class: SymbolClass,
+    ) {
+        if self.is_terse() {
+            self.build_node(base, class);
+            self.push(word);
+        } else {
+            self.build_node(base, class);
+            self.push("with");
+            self.push(word);
+            self.push(position);
+        }
+        self.region_end = self.strings.len();
+    }
+
+    fn build_sup_sub(&mut self, sup_sub: &SupSubNode, class: SymbolClass) {
+        let op = match sup_sub.base.as_deref() {
+            Some(ParseNode::Op(op)) => Some(op),
+            _ => None,
+        };
+
+        // Big operators like sums and integrals, and functions like `\lim`, are read with their
+        // limits
+        if let Some(op) = op.filter(|op| op.limits || op.symbol) {
+            self.build_node(sup_sub.base.as_deref().unwrap(), class);
+            if let Some(sub) = &sup_sub.sub {
+                let word = match (op.name.as_deref(), &sup_sub.sup) {
+                    (Some("\\lim"), _) => "as",
+                    (_, None) => "over",
+                    (_, Some(_)) => "from",
+                };
+                self.push(word);
+                self.build_node(sub, class);
+            }
+            if let Some(sup) = &sup_sub.sup {
+                self.push("to");
+                self.build_node(sup, class);
+            }
+            if self.has_next {
+                self.push("of");
+            }
+            self.region_end = self.strings.len();
+            return;
+        }
+
+        if let Some(base) = &sup_sub.base {
+            self.build_node(base, class);
+        }
+
+        if let Some(sub) = &sup_sub.sub {
+            let is_log = op.and_then(|op| op.name.as_deref()) == Some("\\log");
+            let region = if is_log {
+                Region::new(("start base", "end base"), ("base", "end base"))
+            } else {
+                Region::new(("start subscript", "end subscript"), ("sub", "end sub"))
+            };
+            self.region(region, |speech| speech.build_node(sub, class));
+        }
+
+        if let Some(sup) = &sup_sub.sup {
+            let text = self.render(sup).join(",");
+            if let Some(word) = lookup(POWER_MAP, &text) {
+                self.push(word);
+                self.region_end = self.strings.len();
+            } else {
+                self.region(
+                    Region::new(
+                        ("start superscript", "end superscript"),
+                        ("to the", "end exponent"),
+                    ),
+                    |speech| speech.build_node(sup, class),
+                );
+            }
+        }
+    }
+
+    fn build_genfrac(&mut self, frac: &GenFracNode, class: SymbolClass) {
+        let numer = self.render(&frac.numer);
+        let denom = self.render(&frac.denom);
+        let is_simple = numer.len() == 1 && denom.len() == 1;
+
+        let (region, middle) = match (frac.has_bar_line, self.style) {
+            (true, SpeechStyle::Terse) if is_simple => (Region::new(("", ""), ("", "")), "over"),
+            (true, _) => (
+                Region::new(
+                    ("start fraction", "end fraction"),
+                    ("fraction", "end fraction"),
+                ),
+                if self.is_terse() {
+                    "over"
+                } else {
+                    "divided by"
+                },
+            ),
+            (false, SpeechStyle::Terse) if is_simple => (Region::new(("", ""), ("", "")), "choose"),
+            (false, _) => (
+                Region::new(
+                    ("start binomial", "end binomial"),
+                    ("binomial", "end binomial"),
+                ),
+                "over",
+            ),
+        };
+
+        self.region(region, |speech| {
+            speech.build_node(&frac.numer, class);
+            speech.push(middle);
+            speech.region_end = speech.strings.len();
+            speech.build_node(&frac.denom, class);
+        });
+    }
+
+    fn build_array(&mut self, array: &ArrayNode, class: SymbolClass) {
+        let rows = array
+            .body
+            .iter()
+            .filter(|row| row.iter().any(|cell| !self.render(cell).is_empty()))
+            .collect::<Vec<_>>();
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let is_aligned = matches!(
+            array.col_separation_type,
+            Some(ColSeparationType::Align | ColSeparationType::AlignAt | ColSeparationType::Gather)
+        );
+
+        if is_aligned {
+            let count = format!("{} lines", rows.len());
+            let start = if self.is_terse() {
+                count
+            } else {
+                format!("start {}", count)
+            };
+            self.push(start);
+            for (i, row) in rows.iter().enumerate() {
+                self.push(format!("line {}", i + 1));
+                self.build_list(row, class);
+            }
+            self.push("end lines");
+        } else {
+            let size = format!("{} by {} matrix", rows.len(), columns);
+            let start = if self.is_terse() {
+                size
+            } else {
+                format!("start {}", size)
+            };
+            self.push(start);
+            for (i, row) in rows.iter().enumerate() {
+                self.push(format!("row {}", i + 1));
+                for (j, cell) in row.iter().enumerate() {
+                    if !self.is_terse() {
+                        self.push(format!("column {}", j + 1));
+                    }
+                    self.region_end = self.strings.len();
+                    self.build_node(cell, class);
+                }
+            }
+            self.push("end matrix");
+        }
+        self.region_end = self.strings.len();
+    }
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AlignSpec {
@@ -0,0 +1,763 @@
+//! Reading [AsciiMath](http://asciimath.org/) input.
+//!
+//! AsciiMath is translated into LaTeX, like the reference implementation does, which is then
+//! parsed by the TeX [`Parser`](crate::parser::Parser). So the trees are exactly those of the
+//! equivalent LaTeX, and every backend renders them unchanged:
+//! ```
+//! # use aliter::{asciimath::{asciimath_to_latex, parse_asciimath}, parse_node::EqNoLoc, parse_tree, parser::ParserConfig};
+//! assert_eq!(asciimath_to_latex("sqrt(x+1)/2"), r"\frac{\sqrt{x + 1}}{2}");
+//!
+//! let tree = parse_asciimath("x_1^2 != oo", ParserConfig::default()).unwrap();
+//! let expected = parse_tree(r"x_{1}^{2} \ne \infty", ParserConfig::default()).unwrap();
+//! assert!(tree.eq_no_loc(&expected));
+//! ```
+//!
+//! AsciiMath itself never fails: a bracket without its match is written as a plain symbol, and
+//! a command without its argument gets an empty one.
+//!
+//! [`parse_asciimath`] applies the limits of its [`ParserConfig`] to the LaTeX, and bounds the
+//! depth of the translation by its `max_depth` as well, so it is safe to give untrusted input.
+//! [`asciimath_to_latex`] has no limits, so input that is nested deeply enough overflows the
+//! stack.
+
+use crate::prelude::*;
+use crate::{
+    parse_node::ParseNode,
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+};
+
+/// Parse AsciiMath into the same tree that the equivalent LaTeX parses to
+pub fn parse_asciimath(input: &str, conf: ParserConfig) -> Result<Vec<ParseNode>, ParseError> {
+    let latex = translate(input, conf.max_depth)?;
+    parse_tree(&latex, conf)
+}
+
+/// Translate AsciiMath into LaTeX
+pub fn asciimath_to_latex(input: &str) -> String {
+    // Without a limit on the depth, the translation can't fail
+    translate(input, None).unwrap_or_default()
+}
+
+fn translate(input: &str, max_depth: Option<u32>) -> Result<String, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input),
+        pos: 0,
+        depth: 0,
+        max_depth,
+        too_deep: false,
+    };
+
+    let mut nodes = Vec::new();
+    loop {
+        nodes.extend(parser.parse_expr(false));
+        match parser.next() {
+            // A closing bracket that wasn't opened
+            Some(Token::Right(delim)) => nodes.push(Node::new(plain_delim(delim).to_string())),
+            _ => break,
+        }
+    }
+
+    if parser.too_deep {
+        return Err(ParseError::TooDeeplyNested);
+    }
+
+    Ok(join(&nodes))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Const,
+    /// An opening bracket, with its delimiter for `\left`, where `.` is invisible
+    Left,
+    /// A closing bracket, with its delimiter for `\right`
+    Right,
+    /// A bar, which is a bracket when there is another to match it
+    Bar,
+    /// A command with one argument, where `#` is replaced by the argument
+    Unary,
+    /// A command with two arguments
+    Binary,
+    /// AsciiMath that the symbol is read as instead
+    Definition,
+}
+
+/// The symbols and commands of AsciiMath, with their LaTeX
+const SYMBOLS: &[(&str, Kind, &str)] = &[
+    // Operators
+    ("+", Kind::Const, "+"),
+    ("-", Kind::Const, "-"),
+    ("*", Kind::Const, "\\cdot"),
+    ("**", Kind::Const, "\\ast"),
+    ("***", Kind::Const, "\\star"),
+    ("//", Kind::Const, "/"),
+    ("\\\\", Kind::Const, "\\backslash"),
+    ("setminus", Kind::Const, "\\setminus"),
+    ("xx", Kind::Const, "\\times"),
+    ("|><", Kind::Const, "\\ltimes"),
+    ("><|", Kind::Const, "\\rtimes"),
+    ("|><|", Kind::Const, "\\bowtie"),
+    ("-:", Kind::Const, "\\div"),
+    ("divide", Kind::Const, "\\div"),
+    ("@", Kind::Const, "\\circ"),
+    ("o+", Kind::Const, "\\oplus"),
+    ("ox", Kind::Const, "\\otimes"),
+    ("o.", Kind::Const, "\\odot"),
+    ("sum", Kind::Const, "\\sum"),
+    ("prod", Kind::Const, "\\prod"),
+    ("^^", Kind::Const, "\\wedge"),
+    ("^^^", Kind::Const, "\\bigwedge"),
+    ("vv", Kind::Const, "\\vee"),
+    ("vvv", Kind::Const, "\\bigvee"),
+    ("nn", Kind::Const, "\\cap"),
+    ("nnn", Kind::Const, "\\bigcap"),
+    ("uu", Kind::Const, "\\cup"),
+    ("uuu", Kind::Const, "\\bigcup"),
+    // Relations
+    ("=", Kind::Const, "="),
+    ("!=", Kind::Const, "\\ne"),
+    ("<", Kind::Const, "<"),
+    ("lt", Kind::Const, "<"),
+    (">", Kind::Const, ">"),
+    ("gt", Kind::Const, ">"),
+    ("<=", Kind::Const, "\\le"),
+    ("le", Kind::Const, "\\le"),
+    (">=", Kind::Const, "\\ge"),
+    ("ge", Kind::Const, "\\ge"),
+    ("-<", Kind::Const, "\\prec"),
+    ("-<=", Kind::Const, "\\preceq"),
+    (">-", Kind::Const, "\\succ"),
+    (">-=", Kind::Const, "\\succeq"),
+    ("in", Kind::Const, "\\in"),
+    ("!in", Kind::Const, "\\notin"),
+    ("sub", Kind::Const, "\\subset"),
+    ("sup", Kind::Const, "\\supset"),
+    ("sube", Kind::Const, "\\subseteq"),
+    ("supe", Kind::Const, "\\supseteq"),
+    ("-=", Kind::Const, "\\equiv"),
+    ("~=", Kind::Const, "\\cong"),
+    ("~~", Kind::Const, "\\approx"),
+    ("~", Kind::Const, "\\sim"),
+    ("prop", Kind::Const, "\\propto"),
+    // Logic
+    ("and", Kind::Const, "\\text{ and }"),
+    ("or", Kind::Const, "\\text{ or }"),
+    ("not", Kind::Const, "\\neg"),
+    ("=>", Kind::Const, "\\implies"),
+    ("if", Kind::Const, "\\text{if }"),
+    ("<=>", Kind::Const, "\\iff"),
+    ("iff", Kind::Const, "\\iff"),
+    ("AA", Kind::Const, "\\forall"),
+    ("EE", Kind::Const, "\\exists"),
+    ("_|_", Kind::Const, "\\bot"),
+    ("TT", Kind::Const, "\\top"),
+    ("|--", Kind::Const, "\\vdash"),
+    ("|==", Kind::Const, "\\models"),
+    // Miscellaneous
+    ("int", Kind::Const, "\\int"),
+    ("oint", Kind::Const, "\\oint"),
+    ("del", Kind::Const, "\\partial"),
+    ("grad", Kind::Const, "\\nabla"),
+    ("+-", Kind::Const, "\\pm"),
+    ("-+", Kind::Const, "\\mp"),
+    ("O/", Kind::Const, "\\emptyset"),
+    ("oo", Kind::Const, "\\infty"),
+    ("aleph", Kind::Const, "\\aleph"),
+    ("...", Kind::Const, "\\ldots"),
+    (":.", Kind::Const, "\\therefore"),
+    (":'", Kind::Const, "\\because"),
+    ("/_", Kind::Const, "\\angle"),
+    ("/_\\", Kind::Const, "\\triangle"),
+    ("'", Kind::Const, "'"),
+    ("\\ ", Kind::Const, "\\ "),
+    ("frown", Kind::Const, "\\frown"),
+    ("quad", Kind::Const, "\\quad"),
+    ("qquad", Kind::Const, "\\qquad"),
+    ("cdots", Kind::Const, "\\cdots"),
+    ("vdots", Kind::Const, "\\vdots"),
+    ("ddots", Kind::Const, "\\ddots"),
+    ("diamond", Kind::Const, "\\diamond"),
+    ("square", Kind::Const, "\\square"),
+    ("|__", Kind::Const, "\\lfloor"),
+    ("__|", Kind::Const, "\\rfloor"),
+    ("|~", Kind::Const, "\\lceil"),
+    ("~|", Kind::Const, "\\rceil"),
+    ("CC", Kind::Const, "\\mathbb{C}"),
+    ("NN", Kind::Const, "\\mathbb{N}"),
+    ("QQ", Kind::Const, "\\mathbb{Q}"),
+    ("RR", Kind::Const, "\\mathbb{R}"),
+    ("ZZ", Kind::Const, "\\mathbb{Z}"),
+    ("dx", Kind::Definition, "{:d x:}"),
+    ("dy", Kind::Definition, "{:d y:}"),
+    ("dz", Kind::Definition, "{:d z:}"),
+    ("dt", Kind::Definition, "{:d t:}"),
+    // Functions
+    ("sin", Kind::Const, "\\sin"),
+    ("cos", Kind::Const, "\\cos"),
+    ("tan", Kind::Const, "\\tan"),
+    ("sec", Kind::Const, "\\sec"),
+    ("csc", Kind::Const, "\\csc"),
+    ("cot", Kind::Const, "\\cot"),
+    ("arcsin", Kind::Const, "\\arcsin"),
+    ("arccos", Kind::Const, "\\arccos"),
+    ("arctan", Kind::Const, "\\arctan"),
+    ("sinh", Kind::Const, "\\sinh"),
+    ("cosh", Kind::Const, "\\cosh"),
+    ("tanh", Kind::Const, "\\tanh"),
+    ("sech", Kind::Const, "\\operatorname{sech}"),
+    ("csch", Kind::Const, "\\operatorname{csch}"),
+    ("coth", Kind::Const, "\\coth"),
+    ("exp", Kind::Const, "\\exp"),
+    ("log", Kind::Const, "\\log"),
+    ("ln", Kind::Const, "\\ln"),
+    ("det", Kind::Const, "\\det"),
+    ("dim", Kind::Const, "\\dim"),
+    ("mod", Kind::Const, "\\operatorname{mod}"),
+    ("gcd", Kind::Const, "\\gcd"),
+    ("lcm", Kind::Const, "\\operatorname{lcm}"),
+    ("lub", Kind::Const, "\\operatorname{lub}"),
+    ("glb", Kind::Const, "\\operatorname{glb}"),
+    ("min", Kind::Const, "\\min"),
+    ("max", Kind::Const, "\\max"),
+    ("lim", Kind::Const, "\\lim"),
+    ("Lim", Kind::Const, "\\operatorname*{Lim}"),
+    // Arrows
+    ("uarr", Kind::Const, "\\uparrow"),
+    ("darr", Kind::Const, "\\downarrow"),
+    ("rarr", Kind::Const, "\\rightarrow"),
+    ("->", Kind::Const, "\\to"),
+    (">->", Kind::Const, "\\rightarrowtail"),
+    ("->>", Kind::Const, "\\twoheadrightarrow"),
+    ("|->", Kind::Const, "\\mapsto"),
+    ("larr", Kind::Const, "\\leftarrow"),
+    ("harr", Kind::Const, "\\leftrightarrow"),
+    ("rArr", Kind::Const, "\\Rightarrow"),
+    ("lArr", Kind::Const, "\\Leftarrow"),
+    ("hArr", Kind::Const, "\\Leftrightarrow"),
+    // Greek
+    ("alpha", Kind::Const, "\\alpha"),
+    ("beta", Kind::Const, "\\beta"),
+    ("gamma", Kind::Const, "\\gamma"),
+    ("Gamma", Kind::Const, "\\Gamma"),
+    ("delta", Kind::Const, "\\delta"),
+    ("Delta", Kind::Const, "\\Delta"),
+    ("epsi", Kind::Const, "\\epsilon"),
+    ("epsilon", Kind::Const, "\\epsilon"),
+    ("varepsilon", Kind::Const, "\\varepsilon"),
+    ("zeta", Kind::Const, "\\zeta"),
+    ("eta", Kind::Const, "\\eta"),
+    ("theta", Kind::Const, "\\theta"),
+    ("Theta", Kind::Const, "\\Theta"),
+    ("vartheta", Kind::Const, "\\vartheta"),
+    ("iota", Kind::Const, "\\iota"),
+    ("kappa", Kind::Const, "\\kappa"),
+    ("lambda", Kind::Const, "\\lambda"),
+    ("lamda", Kind::Const, "\\lambda"),
+    ("Lambda", Kind::Const, "\\Lambda"),
+    ("Lamda", Kind::Const, "\\Lambda"),
+    ("mu", Kind::Const, "\\mu"),
+    ("nu", Kind::Const, "\\nu"),
+    ("xi", Kind::Const, "\\xi"),
+    ("Xi", Kind::Const, "\\Xi"),
+    ("pi", Kind::Const, "\\pi"),
+    ("Pi", Kind::Const, "\\Pi"),
+    ("rho", Kind::Const, "\\rho"),
+    ("sigma", Kind::Const, "\\sigma"),
+    ("Sigma", Kind::Const, "\\Sigma"),
+    ("tau", Kind::Const, "\\tau"),
+    ("upsilon", Kind::Const, "\\upsilon"),
+    ("phi", Kind::Const, "\\phi"),
+    ("varphi", Kind::Const, "\\varphi"),
+    ("Phi", Kind::Const, "\\Phi"),
+    ("chi", Kind::Const, "\\chi"),
+    ("psi", Kind::Const, "\\psi"),
+    ("Psi", Kind::Const, "\\Psi"),
+    ("omega", Kind::Const, "\\omega"),
+    ("Omega", Kind::Const, "\\Omega"),
+    // Brackets
+    ("(", Kind::Left, "("),
+    (")", Kind::Right, ")"),
+    ("[", Kind::Left, "["),
+    ("]", Kind::Right, "]"),
+    ("{", Kind::Left, "\\{"),
+    ("}", Kind::Right, "\\}"),
+    ("(:", Kind::Left, "\\langle"),
+    (":)", Kind::Right, "\\rangle"),
+    ("<<", Kind::Left, "\\langle"),
+    (">>", Kind::Right, "\\rangle"),
+    ("{:", Kind::Left, "."),
+    (":}", Kind::Right, "."),
+    ("|", Kind::Bar, "|"),
+    ("||", Kind::Bar, "\\|"),
+    // Commands
+    ("sqrt", Kind::Unary, "\\sqrt{#}"),
+    ("abs", Kind::Unary, "\\left|#\\right|"),
+    ("norm", Kind::Unary, "\\left\\|#\\right\\|"),
+    ("floor", Kind::Unary, "\\left\\lfloor#\\right\\rfloor"),
+    ("ceil", Kind::Unary, "\\left\\lceil#\\right\\rceil"),
+    ("hat", Kind::Unary, "\\hat{#}"),
+    ("bar", Kind::Unary, "\\overline{#}"),
+    ("overline", Kind::Unary, "\\overline{#}"),
+    ("ul", Kind::Unary, "\\underline{#}"),
+    ("underline", Kind::Unary, "\\underline{#}"),
+    ("vec", Kind::Unary, "\\vec{#}"),
+    ("dot", Kind::Unary, "\\dot{#}"),
+    ("ddot", Kind::Unary, "\\ddot{#}"),
+    ("tilde", Kind::Unary, "\\tilde{#}"),
+    ("overarc", Kind::Unary, "\\overparen{#}"),
+    ("overparen", Kind::Unary, "\\overparen{#}"),
+    ("obrace", Kind::Unary, "\\overbrace{#}"),
+    ("overbrace", Kind::Unary, "\\overbrace{#}"),
+    ("ubrace", Kind::Unary, "\\underbrace{#}"),
+    ("underbrace", Kind::Unary, "\\underbrace{#}"),
+    ("cancel", Kind::Unary, "\\cancel{#}"),
+    ("bb", Kind::Unary, "\\mathbf{#}"),
+    ("mathbf", Kind::Unary, "\\mathbf{#}"),
+    ("bbb", Kind::Unary, "\\mathbb{#}"),
+    ("mathbb", Kind::Unary, "\\mathbb{#}"),
+    ("cc", Kind::Unary, "\\mathcal{#}"),
+    ("mathcal", Kind::Unary, "\\mathcal{#}"),
+    ("tt", Kind::Unary, "\\mathtt{#}"),
+    ("mathtt", Kind::Unary, "\\mathtt{#}"),
+    ("fr", Kind::Unary, "\\mathfrak{#}"),
+    ("mathfrak", Kind::Unary, "\\mathfrak{#}"),
+    ("sf", Kind::Unary, "\\mathsf{#}"),
+    ("mathsf", Kind::Unary, "\\mathsf{#}"),
+    ("rm", Kind::Unary, "\\mathrm{#}"),
+    ("mathrm", Kind::Unary, "\\mathrm{#}"),
+    ("frac", Kind::Binary, "\\frac{#}{#}"),
+    ("root", Kind::Binary, "\\sqrt[#]{#}"),
+    ("stackrel", Kind::Binary, "\\stackrel{#}{#}"),
+    ("overset", Kind::Binary, "\\overset{#}{#}"),
+    ("underset", Kind::Binary, "\\underset{#}{#}"),
+];
+
+/// Commands whose argument is read as it was written, rather than as AsciiMath
+const RAW_COMMANDS: &[&str] = &["text", "mbox", "color"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Const(String),
+    Left(&'static str),
+    Right(&'static str),
+    Bar(&'static str),
+    Unary(&'static str),
+    Binary(&'static str),
+    Text(String),
+    Color(String),
+    Frac,
+    Sub,
+    Sup,
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+
+        // Commands with a raw argument, like `text(a b)`
+        let raw = RAW_COMMANDS.iter().find_map(|name| {
+            let arg = rest.strip_prefix(name)?.trim_start().strip_prefix('(')?;
+            let end = matching_paren(arg)?;
+            Some((*name, &arg[..end], &arg[end + 1..]))
+        });
+        if let Some((name, arg, after)) = raw {
+            tokens.push(if name == "color" {
+                Token::Color(arg.trim().to_string())
+            } else {
+                Token::Text(arg.to_string())
+            });
+            rest = after;
+            continue;
+        }
+
+        if c == '"' {
+            let text = &rest[1..];
+            let end = text.find('"').unwrap_or(text.len());
+            tokens.push(Token::Text(text[..end].to_string()));
+            rest = text.get(end + 1..).unwrap_or("");
+            continue;
+        }
+
+        let number = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number = rest[..number].trim_end_matches('.');
+        if !number.is_empty() && c != '.' {
+            // Only one decimal point belongs to a number
+            let len = match number.match_indices('.').nth(1) {
+                Some((i, _)) => i,
+                None => number.len(),
+            };
+            tokens.push(Token::Const(number[..len].to_string()));
+            rest = &rest[len..];
+            continue;
+        }
+
+        // The longest symbol that the input starts with
+        let symbol = SYMBOLS
+            .iter()
+            .filter(|(name, _, _)| rest.starts_with(name))
+            .max_by_key(|(name, _, _)| name.len());
+        if let Some((name, kind, latex)) = symbol {
+            rest = &rest[name.len()..];
+            tokens.push(match kind {
+                Kind::Const => Token::Const(latex.to_string()),
+                Kind::Left => Token::Left(latex),
+                Kind::Right => Token::Right(latex),
+                Kind::Bar => Token::Bar(latex),
+                Kind::Unary => Token::Unary(latex),
+                Kind::Binary => Token::Binary(latex),
+                Kind::Definition => {
+                    tokens.extend(tokenize(latex));
+                    continue;
+                }
+            });
+            continue;
+        }
+
+        tokens.push(match c {
+            '/' => Token::Frac,
+            '_' => Token::Sub,
+            '^' => Token::Sup,
+            '#' | '$' | '%' | '&' => Token::Const(format!("\\{}", c)),
+            c => Token::Const(c.to_string()),
+        });
+        rest = &rest[c.len_utf8()..];
+    }
+
+    tokens
+}
+
+/// The position of the `)` that closes a parenthesis that was just opened
+fn matching_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '{' | '}' | '#' | '$' | '%' | '&' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Fill in the `#`s of a command's LaTeX with its arguments
+fn fill(template: &str, args: &[String]) -> String {
+    let mut out = String::new();
+    for (i, part) in template.split('#').enumerate() {
+        if i > 0 {
+            out.push_str(&args[i - 1]);
+        }
+        out.push_str(part);
+    }
+
+    out
+}
+
+/// A bracket written as a symbol, because it has no match
+fn plain_delim(delim: &str) -> &str {
+    match delim {
+        "." => "",
+        "\\|" => "\\Vert",
+        delim => delim,
+    }
+}
+
+/// Whether the brackets are removed when the group is used as an argument, as in `sqrt(x)`
+fn is_removable(delim: &str) -> bool {
+    matches!(delim, "(" | ")" | "[" | "]" | "\\{" | "\\}" | ".")
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    latex: String,
+    /// The content of a group in brackets, for when it is used as an argument
+    inner: Option<String>,
+    /// The brackets and the comma separated items of a group, for when it is a row of a matrix
+    row: Option<Row>,
+}
+impl Node {
+    fn new(latex: String) -> Node {
+        Node {
+            latex,
+            inner: None,
+            row: None,
+        }
+    }
+
+    fn is_comma(&self) -> bool {
+        self.latex == ","
+    }
+
+    /// The LaTeX of the node as the argument of a command or script
+    fn arg(&self) -> String {
+        self.inner.clone().unwrap_or_else(|| self.latex.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    left: &'static str,
+    right: &'static str,
+    cells: Vec<String>,
+}
+
+fn join(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| node.latex.as_str())
+        .filter(|latex| !latex.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The LaTeX of the matrix, if the nodes are the rows of one, like `(a,b),(c,d)`.
+/// The rows must have the same brackets and number of items.
+fn matrix(nodes: &[Node]) -> Option<String> {
+    if nodes.len() < 3 || nodes.len() % 2 == 0 {
+        return None;
+    }
+
+    let mut rows = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if i % 2 == 1 {
+            if !node.is_comma() {
+                return None;
+            }
+        } else {
+            rows.push(node.row.as_ref()?);
+        }
+    }
+
+    let first = rows[0];
+    let is_matrix = rows.iter().all(|row| {
+        row.left == first.left && row.right == first.right && row.cells.len() == first.cells.len()
+    });
+    if !is_matrix {
+        return None;
+    }
+
+    let body = rows
+        .iter()
+        .map(|row| row.cells.join(" & "))
+        .collect::<Vec<_>>()
+        .join(" \\\\ ");
+    Some(format!("\\begin{{matrix}} {} \\end{{matrix}}", body))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Depth of the expressions being parsed
+    depth: u32,
+    /// The `max_depth` of the LaTeX. Brackets around an argument are dropped, so `abs(x)` is two
+    /// levels here but one in LaTeX, and the translation is allowed twice the depth.
+    max_depth: Option<u32>,
+    /// Whether `max_depth` was reached, after which the rest of the input is skipped
+    too_deep: bool,
+}
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Parse expressions until a closing bracket or the end, or a bar if `in_bars`
+    fn parse_expr(&mut self, in_bars: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Token::Right(_)) => break,
+                Some(Token::Bar(_)) if in_bars => break,
+                _ => {}
+            }
+
+            let mut node = self.parse_intermediate();
+            while self.peek() == Some(&Token::Frac) {
+                self.next();
+                let denom = self.parse_intermediate();
+                node = Node::new(fill("\\frac{#}{#}", &[node.arg(), denom.arg()]));
+            }
+            nodes.push(node);
+        }
+
+        nodes
+    }
+
+    /// Parse a simple expression with its scripts
+    fn parse_intermediate(&mut self) -> Node {
+        let base = self.parse_simple();
+        let mut sub = None;
+        let mut sup = None;
+        loop {
+            match self.peek() {
+                Some(Token::Sub) if sub.is_none() => {
+                    self.next();
+                    sub = Some(self.parse_script());
+                }
+                Some(Token::Sup) if sup.is_none() => {
+                    self.next();
+                    sup = Some(self.parse_script());
+                }
+                _ => break,
+            }
+        }
+
+        if sub.is_none() && sup.is_none() {
+            return base;
+        }
+
+        // A number of several digits is a single base
+        let mut latex =
+            if base.latex.len() > 1 && base.latex.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("{{{}}}", base.latex)
+            } else {
+                base.latex
+            };
+        if let Some(sub) = sub {
+            latex.push_str(&format!("_{{{}}}", sub));
+        }
+        if let Some(sup) = sup {
+            latex.push_str(&format!("^{{{}}}", sup));
+        }
+
+        Node::new(latex)
+    }
+
+    /// Parse the argument of a script, where a minus sign belongs to the expression after it,
+    /// as in `x^-1`
+    fn parse_script(&mut self) -> String {
+        if self.peek() == Some(&Token::Const("-".to_string())) {
+            self.next();
+            return format!("-{}", self.parse_simple().arg());
+        }
+
+        self.parse_simple().arg()
+    }
+
+    fn parse_simple(&mut self) -> Node {
+        // Everything that nests goes through here, so this is where the depth is checked
+        if self.max_depth.is_some_and(|max| self.depth >= max.saturating_mul(2)) {
+            self.too_deep = true;
+            self.pos = self.tokens.len();
+            return Node::new(String::new());
+        }
+
+        self.depth += 1;
+        let node = self.parse_simple_inner();
+        self.depth -= 1;
+        node
+    }
+
+    fn parse_simple_inner(&mut self) -> Node {
+        // A closing bracket ends the group that the command is in, leaving it without argument
+        if matches!(self.peek(), None | Some(Token::Right(_))) {
+            return Node::new(String::new());
+        }
+
+        match self.next().unwrap() {
+            Token::Const(latex) => Node::new(latex),
+            Token::Text(text) => Node::new(format!("\\text{{{}}}", escape_text(&text))),
+            Token::Left(left) => self.parse_group(left),
+            Token::Bar(delim) if self.has_closing_bar() => {
+                let body = self.parse_expr(true);
+                self.next();
+                Node::new(format!("\\left{} {} \\right{}", delim, join(&body), delim))
+            }
+            Token::Bar(delim) => Node::new(plain_delim(delim).to_string()),
+            Token::Unary(template) => {
+                let arg = self.parse_simple();
+                Node::new(fill(template, &[arg.arg()]))
+            }
+            Token::Binary(template) => {
+                let first = self.parse_simple();
+                let second = self.parse_simple();
+                Node::new(fill(template, &[first.arg(), second.arg()]))
+            }
+            Token::Color(color) => {
+                let arg = self.parse_simple();
+                Node::new(format!("\\textcolor{{{}}}{{{}}}", color, arg.arg()))
+            }
+            Token::Frac => Node::new("/".to_string()),
+            // Scripts without a base
+            Token::Sub | Token::Sup => Node::new(String::new()),
+            Token::Right(_) => unreachable!(),
+        }
+    }
+
+    /// Whether there is a bar to close the one that was just read, before the group it is in
+    /// ends
+    fn has_closing_bar(&self) -> bool {
+        let mut depth = 0;
+        for token in &self.tokens[self.pos..] {
+            match token {
+                Token::Left(_) => depth += 1,
+                Token::Right(_) if depth == 0 => return false,
+                Token::Right(_) => depth -= 1,
+                Token::Bar(_) if depth == 0 => return true,
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// Parse a group in brackets, after its opening bracket
+    fn parse_group(&mut self, left: &'static str) -> Node {
+        let body = self.parse_expr(false);
+        let content = join(&body);
+
+        let Some(Token::Right(right)) = self.peek().cloned() else {
+            // A bracket without a match
+            return Node::new(format!("{} {}", plain_delim(left), content));
+        };
+        self.next();
+
+        if let Some(matrix) = matrix(&body) {
+            return Node::new(format!("\\left{} {} \\right{}", left, matrix, right));
+        }
+
+        let latex = if left == "." && right == "." {
+            format!("{{{}}}", content)
+        } else {
+            format!("\\left{} {} \\right{}", left, content, right)
+        };
+        let cells = body.split(Node::is_comma).map(join).collect::<Vec<_>>();
+
+        Node {
+            latex,
+            inner: (is_removable(left) && is_removable(right)).then_some(content),
+            row: Some(Row { left, right, cells }),
+        }
+    }
+}
@@ -0,0 +1,645 @@
+//! The `aliter` command, which renders TeX the same way as KaTeX's `katex` command.
+//!
+//! The TeX is read from stdin or `--input` and the rendered markup is written to stdout or
+//! `--output`. With `--batch` every line of the input is a separate formula, and with
+//! `--jsonl` every line is a JSON string or a JSON object like
+//! `{"tex": "x^2", "displayMode": true, "id": "eq1"}`, which is rendered to a JSON object
+//! with the `output` or the `error` for it.
+
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Write},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use aliter::{
+    ir,
+    macr::MacroReplace,
+    parse_tree,
+    parser::{ParseError, ParserConfig, StrictMode},
+    render_to_string,
+    tree::OutputType,
+    unit::Em,
+    Options,
+};
+
+const USAGE: &str = "\
+Usage: aliter [options]
+
+Render TeX from stdin, or a file, to HTML, MathML, IR JSON or SVG.
+
+Options:
+  -V, --version                 Print the version
+  -d, --display-mode            Render math in display mode
+  -F, --format <format>         The output format: html, mathml, htmlAndMathml (the
+                                default), json or svg
+      --leqno                   Render display math with left-justified tags
+      --fleqn                   Render display math flush left
+  -t, --no-throw-on-error       Render errors in the error color, instead of failing
+  -c, --error-color <color>     The color of errors, as hex like cc0000
+  -b, --color-is-text-color     Make \\color behave like \\textcolor
+  -S, --strict[=<mode>]         Error on features that LaTeX doesn't support, or set the
+                                mode to ignore, warn or error
+  -s, --max-size <n>            The largest user specified size, in ems
+  -e, --max-expand <n>          The most macro expansions, or Infinity
+      --min-rule-thickness <n>  The thinnest rule, in ems
+  -m, --macro <def>             Define a macro, like \\RR:\\mathbb{R}
+  -f, --macro-file <path>       Read macro definitions, one per line, from the file
+  -T, --trust                   Trust the input, allowing commands like \\href
+  -i, --input <path>            Read the TeX from the file instead of stdin
+  -o, --output <path>           Write the output to the file instead of stdout
+      --batch                   Render every line of the input as a separate formula
+      --jsonl                   Render every line of the input as a JSON formula, writing
+                                a JSON object with the output or error for each
+  -h, --help                    Print this help
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Html,
+    Mathml,
+    HtmlAndMathml,
+    /// The IR, see [`ir::to_json`]
+    Json,
+    /// The IR, see [`ir::to_svg`]
+    Svg,
+}
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        Some(match name {
+            "html" => Format::Html,
+            "mathml" => Format::Mathml,
+            "htmlAndMathml" => Format::HtmlAndMathml,
+            "json" => Format::Json,
+            "svg" => Format::Svg,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Batch {
+    Lines,
+    Jsonl,
+}
+
+struct Args {
+    conf: ParserConfig,
+    format: Format,
+    input: Option<String>,
+    output: Option<String>,
+    batch: Option<Batch>,
+}
+
+enum Command {
+    Render(Args),
+    Help,
+    Version,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Render(args)) => args,
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("aliter {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("aliter: {}", err);
+            eprintln!("Try 'aliter --help' for more information.");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("aliter: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Render the input, returning whether every formula rendered
+fn run(args: &Args) -> Result<bool, String> {
+    let input = match &args.input {
+        Some(path) => fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?,
+        None => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|err| format!("stdin: {}", err))?;
+            input
+        }
+    };
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(
+            fs::File::create(path).map_err(|err| format!("{}: {}", path, err))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let success = match args.batch {
+        // The newline that ends the input isn't part of the formula
+        None => match render(input.trim_end(), args.conf.clone(), args.format) {
+            Ok(output) => {
+                writeln!(out, "{}", output).map_err(|err| err.to_string())?;
+                true
+            }
+            Err(err) => {
+                eprintln!("aliter: {:?}", err);
+                false
+            }
+        },
+        Some(Batch::Lines) => render_lines(&input, args, &mut out)?,
+        Some(Batch::Jsonl) => render_jsonl(&input, args, &mut out)?,
+    };
+
+    out.flush().map_err(|err| err.to_string())?;
+
+    Ok(success)
+}
+
+fn render(expr: &str, conf: ParserConfig, format: Format) -> Result<String, ParseError> {
+    let output = match format {
+        Format::Html => OutputType::Html,
+        Format::Mathml => OutputType::Mathml,
+        Format::HtmlAndMathml => OutputType::HtmlAndMathml,
+        Format::Json | Format::Svg => {
+            // The IR has no way to show a parse error, so it is always returned
+            let tree = parse_tree(expr, conf.clone())?;
+            let options = Options::from_parser_conf(&conf);
+            let layout = ir::build_ir(&tree, &options);
+            return Ok(if format == Format::Json {
+                ir::to_json::render(&layout)
+            } else {
+                ir::to_svg::render(&layout)
+            });
+        }
+    };
+
+    render_to_string(expr, conf, output)
+}
+
+/// Render every line as a formula, writing an empty line for those that failed
+fn render_lines(input: &str, args: &Args, out: &mut dyn Write) -> Result<bool, String> {
+    let mut success = true;
+    for (i, line) in input.lines().enumerate() {
+        let output = if line.trim().is_empty() {
+            String::new()
+        } else {
+            match render(line, args.conf.clone(), args.format) {
+                Ok(output) => output,
+                Err(err) => {
+                    eprintln!("aliter: line {}: {:?}", i + 1, err);
+                    success = false;
+                    String::new()
+                }
+            }
+        };
+        writeln!(out, "{}", output).map_err(|err| err.to_string())?;
+    }
+
+    Ok(success)
+}
+
+/// Render every line as a JSON formula, writing a JSON object with the result for each
+fn render_jsonl(input: &str, args: &Args, out: &mut dyn Write) -> Result<bool, String> {
+    let mut success = true;
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut result = format!("{{\"line\":{}", i + 1);
+        let rendered = JsonParser::parse(line).and_then(|formula| {
+            let formula = JsonFormula::from_json(formula)?;
+            if let Some(id) = &formula.id {
+                result.push_str(&format!(",\"id\":{}", id));
+            }
+
+            let mut conf = args.conf.clone();
+            if let Some(display_mode) = formula.display_mode {
+                conf.display_mode = display_mode;
+            }
+            render(&formula.tex, conf, args.format).map_err(|err| format!("{:?}", err))
+        });
+
+        match rendered {
+            // The IR is JSON already
+            Ok(output) if args.format == Format::Json => {
+                result.push_str(&format!(",\"output\":{}", output))
+            }
+            Ok(output) => result.push_str(&format!(",\"output\":{}", ir::to_json::quote(&output))),
+            Err(err) => {
+                eprintln!("aliter: line {}: {}", i + 1, err);
+                success = false;
+                result.push_str(&format!(",\"error\":{}", ir::to_json::quote(&err)));
+            }
+        }
+        result.push('}');
+
+        writeln!(out, "{}", result).map_err(|err| err.to_string())?;
+    }
+
+    Ok(success)
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut conf = ParserConfig::default();
+    // Errors are drawn in an opaque #cc0000, like KaTeX's CLI
+    conf.error_color.a = 0xFF;
+    let mut parsed = Args {
+        conf,
+        format: Format::HtmlAndMathml,
+        input: None,
+        output: None,
+        batch: None,
+    };
+
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        // Values can be given as `--option=value` as well as `--option value`
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || match inline.clone() {
+            Some(value) => Ok(value),
+            None => args
+                .next()
+                .ok_or_else(|| format!("option '{}' needs a value", name)),
+        };
+
+        match name {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-d" | "--display-mode" => parsed.conf.display_mode = true,
+            "-F" | "--format" => {
+                let format = value()?;
+                parsed.format = Format::from_name(&format)
+                    .ok_or_else(|| format!("unknown format '{}'", format))?;
+            }
+            "--leqno" => parsed.conf.leq_no = true,
+            "--fleqn" => parsed.conf.fleqn = true,
+            "-t" | "--no-throw-on-error" => parsed.conf.throw_on_error = false,
+            "-c" | "--error-color" => {
+                let color = value()?;
+                let [r, g, b] =
+                    parse_color(&color).ok_or_else(|| format!("invalid color '{}'", color))?;
+                parsed.conf.error_color.r = r;
+                parsed.conf.error_color.g = g;
+                parsed.conf.error_color.b = b;
+                parsed.conf.error_color.a = 0xFF;
+            }
+            "-b" | "--color-is-text-color" => parsed.conf.color_is_text_color = true,
+            "-S" | "--strict" => {
+                parsed.conf.strict = match inline.as_deref() {
+                    None | Some("error") => StrictMode::Error,
+                    Some("warn") => StrictMode::Warn,
+                    Some("ignore") => StrictMode::Ignore,
+                    Some(mode) => return Err(format!("unknown strict mode '{}'", mode)),
+                }
+            }
+            "-s" | "--max-size" => parsed.conf.max_size = Em(parse_number(name, &value()?)?),
+            "-e" | "--max-expand" => {
+                let max = value()?;
+                parsed.conf.max_expand = if max.eq_ignore_ascii_case("infinity") {
+                    None
+                } else {
+                    Some(
+                        max.parse()
+                            .map_err(|_| format!("invalid number '{}' for '{}'", max, name))?,
+                    )
+                };
+            }
+            "--min-rule-thickness" => {
+                parsed.conf.min_rule_thickness = Em(parse_number(name, &value()?)?)
+            }
+            "-m" | "--macro" => add_macro(&mut parsed.conf, &value()?)?,
+            "-f" | "--macro-file" => {
+                let path = value()?;
+                let file = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?;
+                // Lines without a definition are skipped, like KaTeX does
+                for line in file.lines().filter(|line| line.contains(':')) {
+                    add_macro(&mut parsed.conf, line)?;
+                }
+            }
+            "-T" | "--trust" => parsed.conf.trust = true,
+            "-i" | "--input" => parsed.input = Some(value()?),
+            "-o" | "--output" => parsed.output = Some(value()?),
+            "--batch" => parsed.batch = Some(Batch::Lines),
+            "--jsonl" => parsed.batch = Some(Batch::Jsonl),
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    Ok(Command::Render(parsed))
+}
+
+fn parse_number(name: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number '{}' for '{}'", value, name))
+}
+
+/// Parse a hex color like `cc0000`, `#cc0000` or `c00`
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let color = color.strip_prefix('#').unwrap_or(color);
+    if !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |hex: &str| u8::from_str_radix(hex, 16).ok();
+    match color.len() {
+        6 => Some([
+            channel(&color[0..2])?,
+            channel(&color[2..4])?,
+            channel(&color[4..6])?,
+        ]),
+        3 => {
+            let mut rgb = [0; 3];
+            for (i, c) in color.chars().enumerate() {
+                rgb[i] = channel(&c.to_string())? * 0x11;
+            }
+            Some(rgb)
+        }
+        _ => None,
+    }
+}
+
+/// Add a macro definition like `\RR:\mathbb{R}`, where the name is everything before the first
+/// colon
+fn add_macro(conf: &mut ParserConfig, def: &str) -> Result<(), String> {
+    let (name, expansion) = def
+        .split_once(':')
+        .ok_or_else(|| format!("invalid macro definition '{}'", def))?;
+    let (name, expansion) = (name.trim(), expansion.trim());
+    let replace = Arc::new(MacroReplace::Text(expansion.to_string()));
+
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some('\\'), Some(_)) => conf.macros.insert_back_macro(name, replace),
+        (Some(letter), None) => conf.macros.insert_letter_macro(letter, replace),
+        _ => return Err(format!("invalid macro name '{}'", name)),
+    }
+
+    Ok(())
+}
+
+/// A formula from a line of JSONL
+struct JsonFormula {
+    tex: String,
+    display_mode: Option<bool>,
+    /// The id, as JSON, which is copied into the result
+    id: Option<String>,
+}
+impl JsonFormula {
+    fn from_json(json: Json) -> Result<JsonFormula, String> {
+        let fields = match json {
+            Json::String(tex) => {
+                return Ok(JsonFormula {
+                    tex,
+                    display_mode: None,
+                    id: None,
+                })
+            }
+            Json::Object(fields) => fields,
+            _ => return Err("expected a string or an object".to_string()),
+        };
+
+        let mut formula = JsonFormula {
+            tex: String::new(),
+            display_mode: None,
+            id: None,
+        };
+        let mut has_tex = false;
+        for (key, value) in fields {
+            match (key.as_str(), value) {
+                ("tex", Json::String(tex)) => {
+                    formula.tex = tex;
+                    has_tex = true;
+                }
+                ("displayMode", Json::Bool(display_mode)) => {
+                    formula.display_mode = Some(display_mode)
+                }
+                ("id", Json::String(id)) => formula.id = Some(ir::to_json::quote(&id)),
+                ("id", Json::Number(id)) => formula.id = Some(id),
+                ("tex" | "displayMode" | "id", _) => {
+                    return Err(format!("invalid value for '{}'", key))
+                }
+                // Other fields are left for other tools
+                _ => {}
+            }
+        }
+
+        if has_tex {
+            Ok(formula)
+        } else {
+            Err("missing 'tex'".to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    /// The number as it was written
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Just enough of a JSON parser for JSONL formulas
+struct JsonParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+impl<'a> JsonParser<'a> {
+    fn parse(src: &'a str) -> Result<Json, String> {
+        let mut parser = JsonParser { src, pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != src.len() {
+            return Err(parser.error("expected the end of the line"));
+        }
+        Ok(value)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("invalid JSON at column {}: {}", self.pos + 1, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.next() == Some(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.string().map(Json::String),
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('-' | '0'..='9') => self.number(),
+            Some(_) => {
+                for (word, value) in [
+                    ("null", Json::Null),
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                ] {
+                    if self.src[self.pos..].starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Json::Array(values)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+            self.pos += 1;
+        }
+        let number = &self.src[start..self.pos];
+        if number.parse::<f64>().is_err() {
+            return Err(self.error("invalid number"));
+        }
+        Ok(Json::Number(number.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    text.push(c);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"))
+                }
+                Some(c) => text.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let hex = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code =
+            u32::from_str_radix(hex, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    /// The character of a `\uXXXX` escape, which may be the first half of a surrogate pair
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.src[self.pos..].starts_with("\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+}
@@ -1,16 +1,18 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
+use crate::prelude::*;
 use crate::{
     dom_tree::{
         Anchor, CssStyle, DocumentFragment, HtmlDomNode, HtmlNode, Span, SymbolNode,
         WithHtmlDomNode,
     },
     expander::Mode,
-    font_metrics::{get_character_metrics, CharacterMetrics},
+    font_metrics::{get_character_metrics, get_small_caps_metrics, CharacterMetrics},
     parse_node::ParseNode,
     symbols::{self, Font, LIGATURES},
     tree::{ClassList, EmptyNode},
     unit::{self, calculate_size, make_em, Measurement},
+    functions::text::retrieve_text_font_name,
     util::{char_code_for, find_assoc_data, FontVariant},
     FontShape, FontWeight, Options,
 };
@@ -46,7 +48,16 @@ pub(crate) fn make_symbol(
     options: Option<&Options>,
     classes: ClassList,
 ) -> SymbolNode {
-    let LookupSymbol { value, metrics } = lookup_symbol(value, font, mode);
+    let LookupSymbol { value, mut metrics } = lookup_symbol(value, font, mode);
+
+    let text_shape = options
+        .and_then(|options| options.font_shape)
+        .filter(|_| mode == Mode::Text);
+    if text_shape == Some(FontShape::TextSc) {
+        if let Some(ch) = value.chars().next() {
+            metrics = get_small_caps_metrics(ch, font, mode);
+        }
+    }
 
     let mut symbol_node = if let Some(metrics) = metrics {
         // Apply italic correction for text mode, mathit font, or when using Math-Italic font
@@ -86,6 +97,11 @@ pub(crate) fn make_symbol(
         }
     }
 
+    // The KaTeX fonts have no small caps, so we let the browser synthesize them
+    if text_shape == Some(FontShape::TextSc) {
+        symbol_node.node.style.font_variant = Some(Cow::Borrowed("small-caps"));
+    }
+
     symbol_node
 }
 
@@ -156,9 +172,9 @@ pub(crate) fn make_ord(group: &ParseNode, options: &Options, typ: OrdType) -> Ht
     // Math mode or old font (i.e. \rm)
     let is_font = mode == Mode::Math || (mode == Mode::Text && !options.font.is_empty());
     let font_or_family = if is_font {
-        &options.font
+        options.font
     } else {
-        &options.font_family
+        options.font_family
     };
 
     let text_char = text.chars().nth(0).unwrap();
@@ -193,7 +209,7 @@ pub(crate) fn make_ord(group: &ParseNode, options: &Options, typ: OrdType) -> Ht
                     options
                         .font_shape
                         .as_ref()
-                        .map(FontShape::as_str)
+                        .map(FontShape::class)
                         .unwrap_or("")
                         .to_string(),
                 ],
@@ -250,7 +266,7 @@ pub(crate) fn make_ord(group: &ParseNode, options: &Options, typ: OrdType) -> Ht
                             options
                                 .font_shape
                                 .as_ref()
-                                .map(FontShape::as_str)
+                                .map(FontShape::class)
                                 .unwrap_or("")
                                 .to_string(),
                         ])
@@ -272,7 +288,7 @@ pub(crate) fn make_ord(group: &ParseNode, options: &Options, typ: OrdType) -> Ht
                             options
                                 .font_shape
                                 .as_ref()
-                                .map(FontShape::as_str)
+                                .map(FontShape::class)
                                 .unwrap_or("")
                                 .to_string(),
                         ])
@@ -765,36 +781,6 @@ pub(crate) fn make_glue(measurement: Measurement, options: &Options) -> Span<Htm
     rule
 }
 
-/// Takes font options and returns the appropriate font lookup name
-fn retrieve_text_font_name(
-    font_family: &str,
-    font_weight: Option<FontWeight>,
-    font_shape: Option<FontShape>,
-) -> String {
-    // TODO: we could make these into precomputed static strs or an enum
-    let base_font_name = match font_family {
-        "amsrm" => "AMS",
-        "textrm" => "Main",
-        "textsf" => "SansSerif",
-        "texttt" => "Typewriter",
-        // use fonts added by plugin
-        _ => font_family,
-    };
-
-    let font_styles_name =
-        if font_weight == Some(FontWeight::TextBf) && font_shape == Some(FontShape::TextIt) {
-            "BoldItalic"
-        } else if font_weight == Some(FontWeight::TextBf) {
-            "Bold"
-        } else if font_shape == Some(FontShape::TextIt) {
-            "Italic"
-        } else {
-            "Regular"
-        };
-
-    format!("{}-{}", base_font_name, font_styles_name)
-}
-
 pub(crate) struct FontData {
     pub variant: FontVariant,
     pub font: &'static str,
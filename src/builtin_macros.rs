@@ -1,19 +1,22 @@
-use std::{borrow::Cow, sync::Arc};
-
-use once_cell::sync::Lazy;
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
 use crate::{
     expander::{MacroExpander, Mode},
-    font_metrics_data, functions,
+    font_metrics::get_metric_data,
     lexer::Token,
     macr::{MacroExpansion, MacroReplace, MacroVal, Macros},
     parser::ParseError,
     symbols::{self, Atom, Group},
+    sync::Lazy,
     unit::make_em,
     util::{char_code_for, find_assoc_data},
 };
 
-fn f(
+#[cfg(feature = "std")]
+use crate::functions;
+
+pub(crate) fn f(
     cb: impl for<'a, 'f> Fn(&mut MacroExpander<'a, 'f>) -> Result<MacroVal<'a, 'static>, ParseError>
         + Send
         + Sync
@@ -22,11 +25,12 @@ fn f(
     Arc::new(MacroReplace::Func(Box::new(cb)))
 }
 
-fn text(text: &'static str) -> Arc<MacroReplace> {
+pub(crate) fn text(text: &'static str) -> Arc<MacroReplace> {
     Arc::new(MacroReplace::Text(text.to_owned()))
 }
 
-pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
+/// The builtin macros, which are shared by every parse rather than copied into each
+pub static BUILTIN_MACROS: Lazy<Arc<Macros>> = Lazy::new(|| {
     let mut macros = Macros::default();
 
     // macro tools
@@ -262,6 +266,55 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
     macros.insert_back_macro("\\renewcommand", f(new_command::<true, false>));
     macros.insert_back_macro("\\providecommand", f(new_command::<true, true>));
 
+    // \DeclareMathOperator{\cmd}{text}
+    // \DeclareMathOperator*{\cmd}{text}
+    // Defines \cmd as \operatorname{text}, or \operatorname*{text} for the starred form.
+    // Existing functions and symbols can't be redefined, but macros can, since preambles commonly
+    // declare operators like \argmax that are built in here.
+    fn declare_math_operator<'a, 'f>(
+        exp: &mut MacroExpander<'a, 'f>,
+    ) -> Result<MacroVal<'a, 'static>, ParseError> {
+        let limits = exp.future()?.content == "*";
+        if limits {
+            exp.pop_token()?;
+        }
+
+        let arg = exp.consume_arg()?.tokens;
+        if arg.len() != 1 {
+            return Err(ParseError::NewCommandFirstArgMustBeName);
+        }
+
+        let name = arg[0].content.to_string();
+        if exp.is_defined(&name) && !exp.macros.contains_back_macro(&name) {
+            return Err(ParseError::NewCommandAttemptingToRedefine(name));
+        }
+
+        let body = exp.consume_arg()?.tokens;
+
+        // Tokens are in reverse order
+        let operator = if limits {
+            "\\operatornamewithlimits"
+        } else {
+            "\\operatorname@"
+        };
+        let mut tokens = Vec::with_capacity(body.len() + 3);
+        tokens.push(Token::new_text("}"));
+        tokens.extend(body.into_iter().map(Token::into_owned));
+        tokens.push(Token::new_text("{"));
+        tokens.push(Token::new_text(operator));
+
+        exp.macros.set_back_macro(
+            name,
+            Some(Arc::new(MacroReplace::Expansion(MacroExpansion::new(
+                tokens, 0,
+            )))),
+        );
+
+        Ok(MacroVal::empty_text())
+    }
+
+    macros.insert_back_macro("\\DeclareMathOperator", f(declare_math_operator));
+
     // TODO: ensure these aren't used anywhere because they might not be wanted?
     // terminal (console) tools
     macros.insert_back_macro(
@@ -274,7 +327,10 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
                 .map(|x| x.content)
                 .collect::<Vec<_>>()
                 .join("");
+            #[cfg(feature = "std")]
             println!("{}", arg);
+            #[cfg(not(feature = "std"))]
+            drop(arg);
             Ok(MacroVal::empty_text())
         }),
     );
@@ -287,7 +343,10 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
                 .map(|x| x.content)
                 .collect::<Vec<_>>()
                 .join("");
+            #[cfg(feature = "std")]
             eprintln!("{}", arg);
+            #[cfg(not(feature = "std"))]
+            drop(arg);
             Ok(MacroVal::empty_text())
         }),
     );
@@ -295,14 +354,20 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
         "\\show",
         f(|exp| {
             let tok = exp.pop_token()?;
-            let name = &tok.content;
-
-            let macr = exp.macros.get_back_macro(name);
-            let func = &*functions::FUNCTIONS;
-            let func = func.get(name);
-            let sym_math = symbols::SYMBOLS.get(Mode::Math, name);
-            let sym_text = symbols::SYMBOLS.get(Mode::Text, name);
-            println!("{tok:?} {macr:?} {func:?} {sym_math:?} {sym_text:?}");
+
+            #[cfg(feature = "std")]
+            {
+                let name = &tok.content;
+
+                let macr = exp.macros.get_back_macro(name);
+                let func = &*functions::FUNCTIONS;
+                let func = func.get(name);
+                let sym_math = symbols::SYMBOLS.get(Mode::Math, name);
+                let sym_text = symbols::SYMBOLS.get(Mode::Text, name);
+                println!("{tok:?} {macr:?} {func:?} {sym_math:?} {sym_text:?}");
+            }
+            #[cfg(not(feature = "std"))]
+            drop(tok);
             Ok(MacroVal::empty_text())
         }),
     );
@@ -711,7 +776,18 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
                 return Err(ParseError::MultipleTag);
             }
             let args = exp.consume_args_n::<1>()?;
-            let arg: String = args[0].iter().map(|t| t.content.as_ref()).collect();
+            // The tokens are in stack order, and a control word needs a space to stay separate
+            // from any letters after it
+            let mut arg = String::new();
+            for tok in args[0].iter().rev() {
+                arg.push_str(&tok.content);
+                if tok.content.starts_with('\\')
+                    && tok.content[1..].chars().all(|c| c.is_ascii_alphabetic() || c == '@')
+                    && tok.content.len() > 1
+                {
+                    arg.push(' ');
+                }
+            }
             exp.macros.set_global_back_macro(
                 "\\df@tag".to_string(),
                 Some(Arc::new(MacroReplace::Text(format!("\\text{{{arg}}}")))),
@@ -793,9 +869,8 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
     // boxes, though visually the A appears to extend above slightly).
     // We compute the corresponding \raisebox when A is rendered in \normalsize
     // \scriptstyle, which has a scale factor of 0.7 (see Options.js).
-    let metric = font_metrics_data::get_metric("Main-Regular").unwrap();
-    let t_data = find_assoc_data(metric, char_code_for('T')).unwrap();
-    let a_data = find_assoc_data(metric, char_code_for('A')).unwrap();
+    let t_data = get_metric_data("Main-Regular", 'T').unwrap();
+    let a_data = get_metric_data("Main-Regular", 'A').unwrap();
     let latex_raise_a = make_em(t_data[1] - 0.7 * a_data[1]);
 
     let latex = format!("\\textrm{{\\html@mathml{{L\\kern-.36em\\raisebox{{{latex_raise_a}}}{{\\scriptstyle A}}\\kern-.15em\\TeX}}{{LaTeX}}}}");
@@ -1253,7 +1328,7 @@ pub static BUILTIN_MACROS: Lazy<Macros> = Lazy::new(|| {
         text("\\@ifstar\\operatornamewithlimits\\operatorname@"),
     );
 
-    macros
+    Arc::new(macros)
 });
 
 fn ch_to_digit(ch: char) -> Option<u16> {
@@ -0,0 +1,383 @@
+//! A cache of parse trees and renders, for documents where the same formulas appear many times.
+//!
+//! Entries are keyed by the source and everything else that affects the output: the
+//! [`ParserConfig`] fields, the [version](crate::macr::Macros::version) of its macros, and
+//! whether the font metrics have been changed with
+//! [`set_font_metrics`](crate::font_metrics::set_font_metrics).
+//! Rendering never changes the config's macros, since definitions made by an input (even with
+//! `\gdef` and `global_group`) only last for that render, so a cached result is always the
+//! result that rendering again would give.
+//!
+//! ```
+//! use aliter::{cache::RenderCache, parser::ParserConfig};
+//!
+//! let mut cache = RenderCache::new().with_max_entries(256);
+//! let conf = ParserConfig::default();
+//!
+//! let first = cache.parse("x^2", &conf).unwrap();
+//! let second = cache.parse("x^2", &conf).unwrap();
+//! assert!(std::sync::Arc::ptr_eq(&first, &second));
+//! assert_eq!(cache.stats().hits, 1);
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+    sync::Arc,
+};
+
+#[cfg(all(feature = "html", feature = "mathml"))]
+use crate::tree::OutputType;
+use crate::{
+    a11y::SpeechStyle,
+    font_metrics::font_metrics_version,
+    ir::{build_ir, MathElement, MathLayout, Positioned},
+    parse_node::ParseNode,
+    parse_tree,
+    parser::{ParseError, ParserConfig, StrictMode},
+    Options,
+};
+
+/// The number of entries a cache holds by default
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+/// The number of bytes a cache holds by default, see [`RenderCache::with_max_bytes`]
+pub const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// How many times the cache has been used and what it holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups that found an entry
+    pub hits: u64,
+    /// Lookups that had to parse or render
+    pub misses: u64,
+    /// Entries removed to stay within the limits
+    pub evictions: u64,
+    /// The number of entries currently held
+    pub entries: usize,
+    /// The size of the entries currently held, see [`RenderCache::with_max_bytes`]
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum EntryKind {
+    Parse,
+    Layout,
+    #[cfg(all(feature = "html", feature = "mathml"))]
+    Markup(OutputType),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    kind: EntryKind,
+    source: Box<str>,
+    config: ConfigKey,
+}
+
+/// Everything about the config that can change the output, along with the version of the font
+/// metrics. The floats are kept as their bits, so that they can be hashed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ConfigKey {
+    display_mode: bool,
+    global_group: bool,
+    leq_no: bool,
+    fleqn: bool,
+    throw_on_error: bool,
+    error_color: [u8; 4],
+    macros_version: u64,
+    min_rule_thickness: u64,
+    color_is_text_color: bool,
+    max_size: u64,
+    max_expand: Option<u32>,
+    strict: StrictMode,
+    trust: bool,
+    aria_label: Option<SpeechStyle>,
+    max_depth: Option<u32>,
+    max_nodes: Option<u32>,
+    max_array_cells: Option<u32>,
+    max_output_size: Option<usize>,
+    max_steps: Option<u32>,
+    font_metrics_version: u64,
+}
+
+#[derive(Debug, Clone)]
+enum CachedValue {
+    Parse(Arc<Vec<ParseNode>>),
+    Layout(Arc<MathLayout>),
+    #[cfg(all(feature = "html", feature = "mathml"))]
+    Markup(Arc<str>),
+}
+impl CachedValue {
+    /// The size the value counts as against the cache's byte limit.  
+    /// Trees count as the size of their nodes, leaving out the strings and lists the nodes own,
+    /// so this is an underestimate of what they hold.
+    fn size(&self) -> usize {
+        match self {
+            CachedValue::Parse(tree) => {
+                tree.iter().map(count_nodes).sum::<usize>() * mem::size_of::<ParseNode>()
+            }
+            CachedValue::Layout(layout) => {
+                layout.walk().count() * mem::size_of::<Positioned<MathElement>>()
+            }
+            #[cfg(all(feature = "html", feature = "mathml"))]
+            CachedValue::Markup(markup) => markup.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    value: CachedValue,
+    /// When the entry was last used, the key of the entry in [`RenderCache::recency`]
+    last_used: u64,
+    size: usize,
+}
+
+/// A least recently used cache of parse trees, layouts and rendered markup.
+/// See the [module documentation](self) for how entries are keyed.
+#[derive(Debug, Clone)]
+pub struct RenderCache {
+    entries: HashMap<CacheKey, Entry>,
+    /// The keys of the entries, ordered by when they were last used
+    recency: BTreeMap<u64, CacheKey>,
+    /// Incremented on every use, to order the entries
+    clock: u64,
+    max_entries: usize,
+    max_bytes: usize,
+    stats: CacheStats,
+}
+impl RenderCache {
+    pub fn new() -> RenderCache {
+        RenderCache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_bytes: DEFAULT_MAX_BYTES,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Set the most entries the cache will hold before evicting the least recently used
+    pub fn with_max_entries(mut self, max_entries: usize) -> RenderCache {
+        self.max_entries = max_entries;
+        self.evict();
+        self
+    }
+
+    /// Set the most bytes the cache will hold before evicting the least recently used.
+    /// An entry counts as the length of its source plus the length of its markup, or for parse
+    /// trees and layouts, the size of their nodes.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> RenderCache {
+        self.max_bytes = max_bytes;
+        self.evict();
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Remove every entry, keeping the statistics
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.stats.entries = 0;
+        self.stats.bytes = 0;
+    }
+
+    /// Parse the expression, like [`parse_tree`], or get the tree from a previous parse
+    pub fn parse(
+        &mut self,
+        expr: &str,
+        conf: &ParserConfig,
+    ) -> Result<Arc<Vec<ParseNode>>, ParseError> {
+        let key = CacheKey::new(EntryKind::Parse, expr, conf);
+        if let Some(CachedValue::Parse(tree)) = self.get(&key) {
+            return Ok(tree);
+        }
+
+        let tree = Arc::new(parse_tree(expr, conf.clone())?);
+        self.insert(key, CachedValue::Parse(tree.clone()));
+        Ok(tree)
+    }
+
+    /// Build the layout of the expression, like [`build_ir`], or get it from a previous build
+    pub fn layout(
+        &mut self,
+        expr: &str,
+        conf: &ParserConfig,
+    ) -> Result<Arc<MathLayout>, ParseError> {
+        let key = CacheKey::new(EntryKind::Layout, expr, conf);
+        if let Some(CachedValue::Layout(layout)) = self.get(&key) {
+            return Ok(layout);
+        }
+
+        let tree = self.parse(expr, conf)?;
+        let layout = Arc::new(build_ir(&tree, &Options::from_parser_conf(conf)));
+        self.insert(key, CachedValue::Layout(layout.clone()));
+        Ok(layout)
+    }
+
+    /// Render the expression to markup, like [`render_to_string`](crate::render_to_string), or
+    /// get the markup from a previous render.
+    /// The parse tree is cached too, so rendering the same expression to another output only
+    /// parses it once.
+    #[cfg(all(feature = "html", feature = "mathml"))]
+    pub fn render(
+        &mut self,
+        expr: &str,
+        conf: &ParserConfig,
+        output: OutputType,
+    ) -> Result<Arc<str>, ParseError> {
+        use crate::{render_error, tree::build_tree, tree::VirtualNode};
+
+        let key = CacheKey::new(EntryKind::Markup(output), expr, conf);
+        if let Some(CachedValue::Markup(markup)) = self.get(&key) {
+            return Ok(markup);
+        }
+
+        let markup = self.parse(expr, conf).and_then(|tree| {
+            build_tree(&tree, expr, conf.clone(), output)
+                .to_markup_within(conf.max_output_size)
+                .ok_or(ParseError::OutputTooLarge)
+        });
+        let markup: Arc<str> = match markup {
+            Ok(markup) => markup.into(),
+            Err(err) if conf.throw_on_error => return Err(err),
+            // Running out of time depends on more than the expression, so it may not happen again
+            Err(err @ ParseError::DeadlineExceeded) => {
+                return Ok(render_error(err, expr, conf.clone()).to_markup().into())
+            }
+            Err(err) => render_error(err, expr, conf.clone()).to_markup().into(),
+        };
+        self.insert(key, CachedValue::Markup(markup.clone()));
+        Ok(markup)
+    }
+
+    /// Get the value for the key, marking it as used
+    fn get(&mut self, key: &CacheKey) -> Option<CachedValue> {
+        let Some(entry) = self.entries.get_mut(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+
+        self.clock += 1;
+        let key = self.recency.remove(&entry.last_used).unwrap();
+        self.recency.insert(self.clock, key);
+        entry.last_used = self.clock;
+
+        self.stats.hits += 1;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, value: CachedValue) {
+        let size = key.source.len() + value.size();
+        // An entry that could never fit would only evict everything else
+        if self.max_entries == 0 || size > self.max_bytes {
+            return;
+        }
+
+        self.clock += 1;
+        let entry = Entry {
+            value,
+            last_used: self.clock,
+            size,
+        };
+        self.recency.insert(self.clock, key.clone());
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.recency.remove(&old.last_used);
+            self.stats.bytes -= old.size;
+            self.stats.entries -= 1;
+        }
+        self.stats.bytes += size;
+        self.stats.entries += 1;
+
+        self.evict();
+    }
+
+    /// Remove the least recently used entries until the cache is within its limits
+    fn evict(&mut self) {
+        while self.stats.entries > self.max_entries || self.stats.bytes > self.max_bytes {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            let entry = self.entries.remove(&key).unwrap();
+            self.stats.bytes -= entry.size;
+            self.stats.entries -= 1;
+            self.stats.evictions += 1;
+        }
+    }
+}
+impl Default for RenderCache {
+    fn default() -> Self {
+        RenderCache::new()
+    }
+}
+
+impl CacheKey {
+    fn new(kind: EntryKind, source: &str, conf: &ParserConfig) -> CacheKey {
+        CacheKey {
+            kind,
+            source: source.into(),
+            config: ConfigKey::new(conf),
+        }
+    }
+}
+
+impl ConfigKey {
+    fn new(conf: &ParserConfig) -> ConfigKey {
+        // Destructured so that a new field can't be forgotten here
+        let ParserConfig {
+            display_mode,
+            global_group,
+            leq_no,
+            fleqn,
+            throw_on_error,
+            error_color,
+            macros,
+            min_rule_thickness,
+            color_is_text_color,
+            max_size,
+            max_expand,
+            strict,
+            trust,
+            aria_label,
+            max_depth,
+            max_nodes,
+            max_array_cells,
+            max_output_size,
+            max_steps,
+            // A deadline only decides whether there is an output, and errors from it aren't cached
+            deadline: _,
+        } = conf;
+
+        ConfigKey {
+            display_mode: *display_mode,
+            global_group: *global_group,
+            leq_no: *leq_no,
+            fleqn: *fleqn,
+            throw_on_error: *throw_on_error,
+            error_color: [error_color.r, error_color.g, error_color.b, error_color.a],
+            macros_version: macros.version(),
+            min_rule_thickness: min_rule_thickness.0.to_bits(),
+            color_is_text_color: *color_is_text_color,
+            max_size: max_size.0.to_bits(),
+            max_expand: *max_expand,
+            strict: *strict,
+            trust: *trust,
+            aria_label: *aria_label,
+            max_depth: *max_depth,
+            max_nodes: *max_nodes,
+            max_array_cells: *max_array_cells,
+            max_output_size: *max_output_size,
+            max_steps: *max_steps,
+            font_metrics_version: font_metrics_version(),
+        }
+    }
+}
+
+fn count_nodes(node: &ParseNode) -> usize {
+    let mut count = 1;
+    node.for_each_child(&mut |child| count += count_nodes(child));
+    count
+}
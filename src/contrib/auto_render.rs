@@ -0,0 +1,469 @@
+//! Rendering the math in text, like KaTeX's `auto-render` extension.
+//! <https://katex.org/docs/autorender.html>
+//!
+//! The text is searched for the [`Delimiter`]s, and the math between them is replaced with the
+//! rendered markup. The input can be plain text or HTML, where the content of tags like `<code>`
+//! and `<pre>` is left alone and math can't span tags.
+//! ```
+//! # use aliter::contrib::auto_render::{render_math_in_text, AutoRenderConfig};
+//! let output = render_math_in_text(
+//!     r"<p>Where \(x^2 = 4\):</p><code>\(x\)</code>",
+//!     &AutoRenderConfig::default(),
+//! );
+//! assert!(output.text.starts_with("<p>Where <span class=\"katex\">"));
+//! assert!(output.text.ends_with(":</p><code>\\(x\\)</code>"));
+//! assert!(output.errors.is_empty());
+//! ```
+//!
+//! A delimiter preceded by a backslash, like `\$`, is not the start of math, and is left in the
+//! text without the backslash.
+
+use alloc::borrow::Cow;
+use core::ops::Range;
+
+use crate::{
+    parser::{ParseError, ParserConfig},
+    render_to_string,
+    tree::OutputType,
+};
+
+/// The start and end of math in the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delimiter {
+    pub left: Cow<'static, str>,
+    pub right: Cow<'static, str>,
+    /// Whether the math is rendered in display mode
+    pub display: bool,
+}
+impl Delimiter {
+    pub fn new(
+        left: impl Into<Cow<'static, str>>,
+        right: impl Into<Cow<'static, str>>,
+        display: bool,
+    ) -> Delimiter {
+        Delimiter {
+            left: left.into(),
+            right: right.into(),
+            display,
+        }
+    }
+
+    /// Whether the delimiters are part of the math, as for `\begin{equation}`
+    fn is_environment(&self) -> bool {
+        self.left.starts_with("\\begin{")
+    }
+}
+
+/// The delimiters KaTeX uses by default.
+/// `$…$` isn't included, since it would make every `$` in the text start math, but can be
+/// added with [`Delimiter::new`]. It should be after `$$`, since the first delimiter that
+/// matches is used.
+pub fn default_delimiters() -> Vec<Delimiter> {
+    vec![
+        Delimiter::new("$$", "$$", true),
+        Delimiter::new("\\(", "\\)", false),
+        Delimiter::new("\\begin{equation}", "\\end{equation}", true),
+        Delimiter::new("\\begin{align}", "\\end{align}", true),
+        Delimiter::new("\\begin{alignat}", "\\end{alignat}", true),
+        Delimiter::new("\\begin{gather}", "\\end{gather}", true),
+        Delimiter::new("\\begin{CD}", "\\end{CD}", true),
+        Delimiter::new("\\[", "\\]", true),
+    ]
+}
+
+#[derive(Debug, Clone)]
+pub struct AutoRenderConfig {
+    /// The delimiters to search for, where the first one that matches at a position is used
+    pub delimiters: Vec<Delimiter>,
+    /// Whether the text is HTML.
+    /// If it is, the math can't span tags, entities like `&lt;` in the math are decoded, and the
+    /// content of the [`ignored_tags`](Self::ignored_tags) and
+    /// [`ignored_classes`](Self::ignored_classes) is left alone.
+    pub html: bool,
+    /// The elements whose content isn't searched for math
+    pub ignored_tags: Vec<Cow<'static, str>>,
+    /// Elements with any of these classes aren't searched for math
+    pub ignored_classes: Vec<String>,
+    /// The markup the math is rendered to
+    pub output: OutputType,
+    /// The config used to render the math, where `display_mode` is set by the delimiter.
+    /// If `throw_on_error` is set, math that fails to parse is left as it was and reported in
+    /// [`AutoRenderOutput::errors`], and otherwise the error is rendered.
+    pub conf: ParserConfig,
+}
+impl Default for AutoRenderConfig {
+    fn default() -> Self {
+        AutoRenderConfig {
+            delimiters: default_delimiters(),
+            html: true,
+            ignored_tags: vec![
+                "script".into(),
+                "noscript".into(),
+                "style".into(),
+                "textarea".into(),
+                "pre".into(),
+                "code".into(),
+                "option".into(),
+            ],
+            ignored_classes: Vec::new(),
+            output: OutputType::HtmlAndMathml,
+            conf: ParserConfig::default(),
+        }
+    }
+}
+
+/// Math that failed to render
+#[derive(Debug, Clone)]
+pub struct AutoRenderError {
+    /// The byte range of the math in the input, including the delimiters
+    pub range: Range<usize>,
+    /// The TeX that was rendered
+    pub tex: String,
+    pub display: bool,
+    pub error: ParseError,
+}
+
+#[derive(Debug, Clone)]
+pub struct AutoRenderOutput {
+    /// The text with the math replaced by its markup
+    pub text: String,
+    pub errors: Vec<AutoRenderError>,
+}
+
+/// Render all the math in the text
+pub fn render_math_in_text(text: &str, config: &AutoRenderConfig) -> AutoRenderOutput {
+    let mut renderer = AutoRenderer {
+        config,
+        out: String::with_capacity(text.len()),
+        errors: Vec::new(),
+    };
+
+    if config.html {
+        renderer.render_html(text);
+    } else {
+        renderer.render_text(text, 0);
+    }
+
+    AutoRenderOutput {
+        text: renderer.out,
+        errors: renderer.errors,
+    }
+}
+
+struct AutoRenderer<'c> {
+    config: &'c AutoRenderConfig,
+    out: String,
+    errors: Vec<AutoRenderError>,
+}
+impl<'c> AutoRenderer<'c> {
+    /// Render the math in a run of text, which starts at `offset` in the input
+    fn render_text(&mut self, text: &str, offset: usize) {
+        let mut text_start = 0;
+        let mut i = 0;
+        while i < text.len() {
+            let delim = self
+                .config
+                .delimiters
+                .iter()
+                .find(|delim| !delim.left.is_empty() && text[i..].starts_with(&*delim.left));
+            let delim = match delim {
+                Some(delim) => delim,
+                None => {
+                    i += text[i..].chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+            };
+
+            if text[..i].ends_with('\\') {
+                // Escaped, so the backslash is removed and the delimiter is just text
+                self.out.push_str(&text[text_start..i - 1]);
+                self.out.push_str(&delim.left);
+                i += delim.left.len();
+                text_start = i;
+                continue;
+            }
+
+            let math_start = i + delim.left.len();
+            let end = match find_end_of_math(&delim.right, text, math_start) {
+                Some(end) => end,
+                // Like KaTeX, the rest is text if the math isn't closed
+                None => break,
+            };
+            let math_end = end + delim.right.len();
+
+            self.out.push_str(&text[text_start..i]);
+            let tex = if delim.is_environment() {
+                &text[i..math_end]
+            } else {
+                &text[math_start..end]
+            };
+            let tex = if self.config.html {
+                decode_entities(tex)
+            } else {
+                Cow::Borrowed(tex)
+            };
+            self.render_math(
+                &tex,
+                delim.display,
+                &text[i..math_end],
+                offset + i..offset + math_end,
+            );
+
+            i = math_end;
+            text_start = i;
+        }
+
+        self.out.push_str(&text[text_start..]);
+    }
+
+    fn render_math(&mut self, tex: &str, display: bool, raw: &str, range: Range<usize>) {
+        let mut conf = self.config.conf.clone();
+        conf.display_mode = display;
+
+        match render_to_string(tex, conf, self.config.output) {
+            Ok(markup) => self.out.push_str(&markup),
+            Err(error) => {
+                self.out.push_str(raw);
+                self.errors.push(AutoRenderError {
+                    range,
+                    tex: tex.to_string(),
+                    display,
+                    error,
+                });
+            }
+        }
+    }
+
+    /// Render the math in the text between the tags
+    fn render_html(&mut self, html: &str) {
+        let mut i = 0;
+        while i < html.len() {
+            let text_end = find_markup(html, i);
+            if text_end > i {
+                self.render_text(&html[i..text_end], i);
+                i = text_end;
+                continue;
+            }
+
+            let rest = &html[i..];
+            if rest.starts_with("<!--") {
+                let end = rest.find("-->").map_or(html.len(), |pos| i + pos + 3);
+                self.out.push_str(&html[i..end]);
+                i = end;
+                continue;
+            }
+
+            let tag = Tag::parse(rest).expect("Markup should be a tag or comment");
+            let mut end = i + tag.len;
+            if !tag.is_closing && !tag.is_self_closing && self.is_ignored(&tag, rest) {
+                end = find_closing_tag(html, end, tag.name);
+            }
+            self.out.push_str(&html[i..end]);
+            i = end;
+        }
+    }
+
+    fn is_ignored(&self, tag: &Tag<'_>, src: &str) -> bool {
+        let name = tag.name.to_ascii_lowercase();
+        if self
+            .config
+            .ignored_tags
+            .iter()
+            .any(|ignored| *ignored == name)
+        {
+            return true;
+        }
+
+        !self.config.ignored_classes.is_empty()
+            && tag.classes(src).any(|class| {
+                self.config
+                    .ignored_classes
+                    .iter()
+                    .any(|ignored| ignored == class)
+            })
+    }
+}
+
+/// The position of the next tag or comment from `start`, skipping a `<` that is just text like
+/// in `a < b`
+fn find_markup(html: &str, start: usize) -> usize {
+    let mut i = start;
+    while let Some(pos) = html[i..].find('<') {
+        i += pos;
+        if html[i..].starts_with("<!--") || Tag::parse(&html[i..]).is_some() {
+            return i;
+        }
+        i += 1;
+    }
+
+    html.len()
+}
+
+/// Find where the math that starts at `start` ends, which is the first `delim` outside of braces.
+/// The characters after backslashes are skipped, so `\$` and `\}` don't end the math.
+fn find_end_of_math(delim: &str, text: &str, start: usize) -> Option<usize> {
+    let mut brace_level: isize = 0;
+    let mut chars = text[start..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        let i = start + i;
+        if brace_level <= 0 && text[i..].starts_with(delim) {
+            return Some(i);
+        }
+
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' => brace_level += 1,
+            '}' => brace_level -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// A start or end tag
+struct Tag<'a> {
+    name: &'a str,
+    is_closing: bool,
+    is_self_closing: bool,
+    /// The length of the tag in bytes
+    len: usize,
+}
+impl<'a> Tag<'a> {
+    /// Parse the tag at the start of `src`
+    fn parse(src: &'a str) -> Option<Tag<'a>> {
+        let rest = src.strip_prefix('<')?;
+        let (is_closing, rest) = match rest.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        // Doctypes and processing instructions are treated like tags without a name
+        let is_declaration = rest.starts_with(['!', '?']);
+        if !is_declaration && !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        let name_len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != ':')
+            .unwrap_or(rest.len());
+        let name = if is_declaration {
+            ""
+        } else {
+            &rest[..name_len]
+        };
+
+        // Find the `>`, skipping over quoted attribute values
+        let mut quote = None;
+        for (i, c) in src.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if q == c => quote = None,
+                (None, '>') => {
+                    return Some(Tag {
+                        name,
+                        is_closing,
+                        is_self_closing: src[..i].ends_with('/') || is_declaration,
+                        len: i + 1,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// The classes in the `class` attribute of the tag
+    fn classes(&self, src: &'a str) -> impl Iterator<Item = &'a str> {
+        let tag = &src[..self.len];
+        let value = tag
+            .match_indices("class=")
+            .find(|(i, _)| tag[..*i].ends_with(char::is_whitespace))
+            .and_then(|(i, _)| {
+                let value = &tag[i + "class=".len()..];
+                match value.chars().next() {
+                    Some(q @ ('"' | '\'')) => value[1..].split(q).next(),
+                    _ => value.split([' ', '>', '/']).next(),
+                }
+            })
+            .unwrap_or("");
+        value.split_whitespace()
+    }
+}
+
+/// Find the end of the element with the name whose start tag ends at `start`, counting nested
+/// elements of the same name
+fn find_closing_tag(html: &str, start: usize, name: &str) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while let Some(pos) = html[i..].find('<') {
+        i += pos;
+        match Tag::parse(&html[i..]) {
+            Some(tag) if tag.name.eq_ignore_ascii_case(name) => {
+                if tag.is_closing {
+                    if depth == 0 {
+                        return i + tag.len;
+                    }
+                    depth -= 1;
+                } else if !tag.is_self_closing {
+                    depth += 1;
+                }
+                i += tag.len;
+            }
+            _ => i += 1,
+        }
+    }
+
+    // An element that isn't closed continues to the end
+    html.len()
+}
+
+/// Decode the character references in HTML text, like `&lt;` and `&#x3c;`
+fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = entity.strip_prefix('#')?;
+                    let code = match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => code.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    Cow::Owned(out)
+}
@@ -0,0 +1,97 @@
+//! Optional extensions that are not part of KaTeX's builtin set, mirroring KaTeX's `contrib/`
+//! directory.
+//! Each package is behind its own cargo feature and is enabled per-config by inserting its macros
+//! into [`ParserConfig::macros`](crate::parser::ParserConfig::macros).
+//! [`auto_render`] instead renders the math found in text.
+
+#[cfg(feature = "auto-render")]
+pub mod auto_render;
+#[cfg(feature = "physics")]
+pub mod physics;
+#[cfg(feature = "siunitx")]
+pub mod siunitx;
+
+#[cfg(any(feature = "physics", feature = "siunitx"))]
+use crate::prelude::*;
+#[cfg(any(feature = "physics", feature = "siunitx"))]
+use crate::{expander::MacroExpander, lexer::Token, parser::ParseError};
+
+/// Convert a list of tokens (in reverse order, as they come from the expander) back into source
+/// text that can be used as part of a textual macro expansion.
+#[cfg(any(feature = "physics", feature = "siunitx"))]
+pub(crate) fn tokens_to_string(tokens: &[Token<'_>]) -> String {
+    let mut out = String::new();
+    let mut after_control_word = false;
+    for tok in tokens.iter().rev() {
+        // A control word swallows the letters that follow it, so keep them apart
+        if after_control_word && tok.content.starts_with(|c: char| c.is_alphabetic()) {
+            out.push(' ');
+        }
+        out.push_str(&tok.content);
+
+        after_control_word = tok
+            .content
+            .strip_prefix('\\')
+            .map(|name| !name.is_empty() && name.chars().all(|c| c.is_alphabetic() || c == '@'))
+            .unwrap_or(false);
+    }
+    out
+}
+
+/// Consume a `*` immediately following the macro name, if there is one
+#[cfg(feature = "physics")]
+pub(crate) fn consume_star(exp: &mut MacroExpander<'_, '_>) -> Result<bool, ParseError> {
+    if exp.future()?.content == "*" {
+        exp.pop_token()?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Consume the text between `open` and `close`, if the next token is `open`.
+/// Nested `open`/`close` pairs and braced groups are kept intact, so `\qty((a+b)c)` takes the
+/// outer parentheses.
+/// The result is in the order used by [`tokens_to_string`].
+#[cfg(any(feature = "physics", feature = "siunitx"))]
+pub(crate) fn consume_delimited<'a>(
+    exp: &mut MacroExpander<'a, '_>,
+    open: &str,
+    close: &str,
+) -> Result<Option<Vec<Token<'a>>>, ParseError> {
+    exp.consume_spaces()?;
+    if exp.future()?.content != open {
+        return Ok(None);
+    }
+    exp.pop_token()?;
+
+    let mut tokens = Vec::new();
+    let mut brace_depth: usize = 0;
+    let mut depth: usize = 0;
+    loop {
+        let tok = exp.pop_token()?;
+        if tok.is_eof() {
+            return Err(ParseError::UnexpectedEOF);
+        }
+
+        if tok.content == "{" {
+            brace_depth += 1;
+        } else if tok.content == "}" {
+            brace_depth = brace_depth.checked_sub(1).ok_or(ParseError::Expected)?;
+        } else if brace_depth == 0 {
+            if tok.content == close {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            } else if tok.content == open {
+                depth += 1;
+            }
+        }
+
+        tokens.push(tok);
+    }
+
+    tokens.reverse();
+    Ok(Some(tokens))
+}
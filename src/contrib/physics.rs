@@ -0,0 +1,454 @@
+//! Macros from the LaTeX `physics` package.
+//! <https://ctan.org/pkg/physics>
+//!
+//! These aren't part of KaTeX and some of them shadow builtin commands (`\div` becomes the
+//! divergence rather than `÷`), so they have to be added to the config explicitly:
+//! ```
+//! # use aliter::{contrib::physics, parse_tree, parser::ParserConfig};
+//! let mut conf = ParserConfig::default();
+//! physics::add_macros(&mut conf.macros);
+//! let tree = parse_tree(r"\dv{f}{x} = \abs{\vb{v}}", conf).unwrap();
+//! ```
+//!
+//! Most of the macros accept the same variations as the package: a `*` after the name turns off
+//! automatic delimiter sizing (or selects the inline form of derivatives), a size command such as
+//! `\qty\big(x)` picks a fixed size, and the delimiter following `\qty`/`\mqty` chooses the
+//! brackets used.
+
+use alloc::{borrow::Cow, sync::Arc};
+
+use crate::prelude::*;
+use crate::{
+    builtin_macros::{f, text},
+    expander::MacroExpander,
+    macr::{MacroReplace, MacroVal, Macros},
+    parser::ParseError,
+};
+
+use super::{consume_delimited, consume_star, tokens_to_string};
+
+/// Add the `physics` package macros to `macros`, overwriting any existing macros with the same
+/// name.
+pub fn add_macros(macros: &mut Macros) {
+    //////////////////////////////////////////////////////////////////////
+    // Automatic bracing
+
+    macros.insert_back_macro("\\quantity", f(qty));
+    macros.insert_back_macro("\\qty", f(qty));
+    macros.insert_back_macro("\\pqty", f(|exp| braced(exp, "(", ")")));
+    macros.insert_back_macro("\\bqty", f(|exp| braced(exp, "[", "]")));
+    macros.insert_back_macro("\\Bqty", f(|exp| braced(exp, "\\{", "\\}")));
+    macros.insert_back_macro("\\vqty", f(|exp| braced(exp, "\\lvert", "\\rvert")));
+
+    macros.insert_back_macro(
+        "\\absolutevalue",
+        f(|exp| braced(exp, "\\lvert", "\\rvert")),
+    );
+    macros.insert_back_macro("\\abs", f(|exp| braced(exp, "\\lvert", "\\rvert")));
+    macros.insert_back_macro("\\norm", f(|exp| braced(exp, "\\lVert", "\\rVert")));
+
+    macros.insert_back_macro("\\evaluated", f(eval));
+    macros.insert_back_macro("\\eval", f(eval));
+
+    macros.insert_back_macro(
+        "\\order",
+        f(|exp| {
+            let star = consume_star(exp)?;
+            let sizing = consume_sizing(exp, star)?;
+            exp.consume_spaces()?;
+            let body = if exp.future()?.content == "{" {
+                let arg = tokens_to_string(&exp.consume_arg()?.tokens);
+                sizing.wrap("(", &arg, ")")
+            } else {
+                delimited_body(exp, &sizing)?.ok_or(ParseError::ExpectedGroup)?
+            };
+            Ok(owned(format!("\\mathcal{{O}}{body}")))
+        }),
+    );
+
+    macros.insert_back_macro("\\commutator", f(|exp| pair(exp, "[", "]")));
+    macros.insert_back_macro("\\comm", f(|exp| pair(exp, "[", "]")));
+    macros.insert_back_macro("\\anticommutator", f(|exp| pair(exp, "\\{", "\\}")));
+    macros.insert_back_macro("\\acomm", f(|exp| pair(exp, "\\{", "\\}")));
+    macros.insert_back_macro("\\poissonbracket", f(|exp| pair(exp, "\\{", "\\}")));
+    macros.insert_back_macro("\\pb", f(|exp| pair(exp, "\\{", "\\}")));
+
+    //////////////////////////////////////////////////////////////////////
+    // Vector notation
+
+    macros.insert_back_macro("\\vectorbold", f(|exp| vector(exp, "")));
+    macros.insert_back_macro("\\vb", f(|exp| vector(exp, "")));
+    macros.insert_back_macro("\\vectorarrow", f(|exp| vector(exp, "\\vec")));
+    macros.insert_back_macro("\\va", f(|exp| vector(exp, "\\vec")));
+    macros.insert_back_macro("\\vectorunit", f(|exp| vector(exp, "\\hat")));
+    macros.insert_back_macro("\\vu", f(|exp| vector(exp, "\\hat")));
+
+    macros.insert_back_macro("\\dotproduct", text("\\boldsymbol{\\cdot}"));
+    macros.insert_back_macro("\\vdot", text("\\boldsymbol{\\cdot}"));
+    macros.insert_back_macro("\\crossproduct", text("\\boldsymbol{\\times}"));
+    macros.insert_back_macro("\\cross", text("\\boldsymbol{\\times}"));
+    macros.insert_back_macro("\\cp", text("\\boldsymbol{\\times}"));
+
+    macros.insert_back_macro("\\gradient", with_qty("\\boldsymbol{\\nabla}"));
+    macros.insert_back_macro("\\grad", with_qty("\\boldsymbol{\\nabla}"));
+    macros.insert_back_macro("\\divergence", with_qty("\\boldsymbol{\\nabla}\\cdot"));
+    // Note: This shadows the `\div` symbol (÷), as the package does
+    macros.insert_back_macro("\\div", with_qty("\\boldsymbol{\\nabla}\\cdot"));
+    macros.insert_back_macro("\\curl", with_qty("\\boldsymbol{\\nabla}\\times"));
+    macros.insert_back_macro("\\laplacian", with_qty("\\nabla^2"));
+
+    //////////////////////////////////////////////////////////////////////
+    // Operators
+
+    macros.insert_back_macro("\\trace", with_qty("\\operatorname{tr}"));
+    macros.insert_back_macro("\\tr", with_qty("\\operatorname{tr}"));
+    macros.insert_back_macro("\\Trace", with_qty("\\operatorname{Tr}"));
+    macros.insert_back_macro("\\Tr", with_qty("\\operatorname{Tr}"));
+    macros.insert_back_macro("\\rank", with_qty("\\operatorname{rank}"));
+    macros.insert_back_macro("\\erf", with_qty("\\operatorname{erf}"));
+    macros.insert_back_macro("\\Res", with_qty("\\operatorname{Res}"));
+
+    //////////////////////////////////////////////////////////////////////
+    // Quantum mechanics
+    // Note: `\bra`, `\ket` and `\braket` are already provided by braket.sty
+
+    macros.insert_back_macro("\\expectationvalue", f(expval));
+    macros.insert_back_macro("\\expval", f(expval));
+    macros.insert_back_macro("\\ev", f(expval));
+    macros.insert_back_macro("\\matrixelement", f(mel));
+    macros.insert_back_macro("\\matrixel", f(mel));
+    macros.insert_back_macro("\\mel", f(mel));
+
+    //////////////////////////////////////////////////////////////////////
+    // Matrices
+
+    macros.insert_back_macro("\\matrixquantity", f(mqty));
+    macros.insert_back_macro("\\mqty", f(mqty));
+    macros.insert_back_macro("\\pmqty", f(|exp| matrix(exp, "pmatrix")));
+    macros.insert_back_macro("\\bmqty", f(|exp| matrix(exp, "bmatrix")));
+    macros.insert_back_macro("\\vmqty", f(|exp| matrix(exp, "vmatrix")));
+    macros.insert_back_macro(
+        "\\smallmatrixquantity",
+        f(|exp| {
+            let body = exp.consume_arg()?.tokens;
+            let body = tokens_to_string(&body);
+            Ok(owned(format!(
+                "\\left(\\begin{{smallmatrix}}{body}\\end{{smallmatrix}}\\right)"
+            )))
+        }),
+    );
+    macros.insert_back_macro("\\smqty", text("\\smallmatrixquantity"));
+
+    //////////////////////////////////////////////////////////////////////
+    // Derivatives
+
+    macros.insert_back_macro("\\differential", f(dd));
+    macros.insert_back_macro("\\dd", f(dd));
+    macros.insert_back_macro("\\derivative", f(|exp| derivative(exp, "\\mathrm{d}")));
+    macros.insert_back_macro("\\dv", f(|exp| derivative(exp, "\\mathrm{d}")));
+    macros.insert_back_macro("\\partialderivative", f(|exp| derivative(exp, "\\partial")));
+    macros.insert_back_macro("\\pderivative", f(|exp| derivative(exp, "\\partial")));
+    macros.insert_back_macro("\\pdv", f(|exp| derivative(exp, "\\partial")));
+}
+
+fn owned<'a>(text: String) -> MacroVal<'a, 'static> {
+    MacroVal::Text(Cow::Owned(text))
+}
+
+/// How a pair of delimiters produced by a macro should be sized
+enum Sizing {
+    /// `\left` and `\right`
+    Auto,
+    /// The starred form, the delimiters are left at their normal size
+    Normal,
+    /// An explicit size, like `\big`
+    Manual(String),
+}
+impl Sizing {
+    fn wrap(&self, left: &str, body: &str, right: &str) -> String {
+        match self {
+            Sizing::Auto => format!("\\left{left} {body}\\right{right}"),
+            Sizing::Normal => format!("{left} {body}{right}"),
+            Sizing::Manual(size) => format!("{size}l{left} {body}{size}r{right}"),
+        }
+    }
+
+    fn middle(&self, delim: &str) -> String {
+        match self {
+            Sizing::Auto => format!("\\middle{delim} "),
+            Sizing::Normal => format!("{delim} "),
+            Sizing::Manual(size) => format!("{size}m{delim} "),
+        }
+    }
+}
+
+/// Read the sizing of the delimiters, which is either from the star or a size command after the
+/// name of the macro
+fn consume_sizing(exp: &mut MacroExpander<'_, '_>, star: bool) -> Result<Sizing, ParseError> {
+    if star {
+        return Ok(Sizing::Normal);
+    }
+
+    let next = exp.future()?;
+    if matches!(
+        next.content.as_ref(),
+        "\\big" | "\\Big" | "\\bigg" | "\\Bigg"
+    ) {
+        let size = exp.pop_token()?;
+        Ok(Sizing::Manual(size.content.into_owned()))
+    } else {
+        Ok(Sizing::Auto)
+    }
+}
+
+/// Read a body delimited by `()`, `[]`, `{}` or `||`, and wrap it in the matching delimiters.
+/// Returns `None` if the next token is not one of those.
+fn delimited_body(
+    exp: &mut MacroExpander<'_, '_>,
+    sizing: &Sizing,
+) -> Result<Option<String>, ParseError> {
+    exp.consume_spaces()?;
+    let (left, right) = match exp.future()?.content.as_ref() {
+        "{" => {
+            let body = tokens_to_string(&exp.consume_arg()?.tokens);
+            return Ok(Some(sizing.wrap("\\{", &body, "\\}")));
+        }
+        "(" => ("(", ")"),
+        "[" => ("[", "]"),
+        "|" => ("|", "|"),
+        _ => return Ok(None),
+    };
+
+    // We already know the next token is `left`
+    let body = consume_delimited(exp, left, right)?.unwrap_or_default();
+    let body = tokens_to_string(&body);
+    let (left, right) = if left == "|" {
+        ("\\lvert", "\\rvert")
+    } else {
+        (left, right)
+    };
+
+    Ok(Some(sizing.wrap(left, &body, right)))
+}
+
+/// `\qty(x)`, `\qty[x]`, `\qty{x}`, `\qty|x|`
+fn qty<'a>(exp: &mut MacroExpander<'a, '_>) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let sizing = consume_sizing(exp, star)?;
+    let body = delimited_body(exp, &sizing)?.ok_or(ParseError::ExpectedGroup)?;
+
+    Ok(owned(body))
+}
+
+/// A macro taking a single braced argument wrapped in the given delimiters, like `\abs`
+fn braced<'a>(
+    exp: &mut MacroExpander<'a, '_>,
+    left: &str,
+    right: &str,
+) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let sizing = consume_sizing(exp, star)?;
+    let body = tokens_to_string(&exp.consume_arg()?.tokens);
+
+    Ok(owned(sizing.wrap(left, &body, right)))
+}
+
+/// `\eval{x}_a^b`, `\eval(x)_a^b` and `\eval[x]_a^b`, which only have a closing bar
+fn eval<'a>(exp: &mut MacroExpander<'a, '_>) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let sizing = consume_sizing(exp, star)?;
+    exp.consume_spaces()?;
+
+    let (left, body) = match exp.future()?.content.as_ref() {
+        "(" => ("(", consume_delimited(exp, "(", ")")?.unwrap_or_default()),
+        "[" => ("[", consume_delimited(exp, "[", "]")?.unwrap_or_default()),
+        _ => (".", exp.consume_arg()?.tokens),
+    };
+    let left = match (&sizing, left) {
+        // A `.` is only meaningful to `\left`
+        (Sizing::Normal, ".") => "",
+        (_, left) => left,
+    };
+
+    Ok(owned(sizing.wrap(left, &tokens_to_string(&body), "\\vert")))
+}
+
+/// `\comm{A}{B}` and friends, two arguments separated by a comma
+fn pair<'a>(
+    exp: &mut MacroExpander<'a, '_>,
+    left: &str,
+    right: &str,
+) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let sizing = consume_sizing(exp, star)?;
+    let [a, b] = exp.consume_args_n::<2>()?;
+    let body = format!("{},{}", tokens_to_string(&a), tokens_to_string(&b));
+
+    Ok(owned(sizing.wrap(left, &body, right)))
+}
+
+/// `\vb{a}`, `\va{a}` and `\vu{a}`, where the starred version uses `\boldsymbol` so that greek
+/// letters are also made bold
+fn vector<'a>(
+    exp: &mut MacroExpander<'a, '_>,
+    accent: &str,
+) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let body = tokens_to_string(&exp.consume_arg()?.tokens);
+    let font = if star { "\\boldsymbol" } else { "\\mathbf" };
+
+    Ok(owned(if accent.is_empty() {
+        format!("{font}{{{body}}}")
+    } else {
+        format!("{accent}{{{font}{{{body}}}}}")
+    }))
+}
+
+/// A symbol, like `\grad`, that is followed by an automatically sized `\qty` if it is followed
+/// by parentheses or brackets
+fn with_qty(symbol: &'static str) -> Arc<MacroReplace> {
+    f(move |exp| {
+        exp.consume_spaces()?;
+        let next = exp.future()?.content.as_ref();
+        if next == "(" || next == "[" {
+            Ok(owned(format!("{symbol}\\qty")))
+        } else {
+            Ok(MacroVal::Text(Cow::Borrowed(symbol)))
+        }
+    })
+}
+
+/// `\expval{A}` is `⟨A⟩` and `\expval{A}{\psi}` is `⟨ψ|A|ψ⟩`
+fn expval<'a>(exp: &mut MacroExpander<'a, '_>) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let sizing = consume_sizing(exp, star)?;
+    let op = tokens_to_string(&exp.consume_arg()?.tokens);
+
+    exp.consume_spaces()?;
+    let body = if exp.future()?.content == "{" {
+        let state = tokens_to_string(&exp.consume_arg()?.tokens);
+        let bar = sizing.middle("\\vert");
+        format!("{state}{bar}{op}{bar}{state}")
+    } else {
+        op
+    };
+
+    Ok(owned(sizing.wrap("\\langle", &body, "\\rangle")))
+}
+
+/// `\mel{n}{A}{m}` is `⟨n|A|m⟩`
+fn mel<'a>(exp: &mut MacroExpander<'a, '_>) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let star = consume_star(exp)?;
+    let sizing = consume_sizing(exp, star)?;
+    let [left, op, right] = exp.consume_args_n::<3>()?;
+    let bar = sizing.middle("\\vert");
+    let body = format!(
+        "{}{bar}{}{bar}{}",
+        tokens_to_string(&left),
+        tokens_to_string(&op),
+        tokens_to_string(&right)
+    );
+
+    Ok(owned(sizing.wrap("\\langle", &body, "\\rangle")))
+}
+
+/// `\mqty(a & b)` is a `pmatrix`, `\mqty[a & b]` a `bmatrix`, `\mqty|a & b|` a `vmatrix` and
+/// `\mqty{a & b}` a plain `matrix`
+fn mqty<'a>(exp: &mut MacroExpander<'a, '_>) -> Result<MacroVal<'a, 'static>, ParseError> {
+    exp.consume_spaces()?;
+    let (env, body) = match exp.future()?.content.as_ref() {
+        "(" => ("pmatrix", consume_delimited(exp, "(", ")")?),
+        "[" => ("bmatrix", consume_delimited(exp, "[", "]")?),
+        "|" => ("vmatrix", consume_delimited(exp, "|", "|")?),
+        _ => ("matrix", Some(exp.consume_arg()?.tokens)),
+    };
+    let body = tokens_to_string(&body.unwrap_or_default());
+
+    Ok(owned(format!("\\begin{{{env}}}{body}\\end{{{env}}}")))
+}
+
+/// `\pmqty{a & b}` and friends
+fn matrix<'a>(
+    exp: &mut MacroExpander<'a, '_>,
+    env: &str,
+) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let body = tokens_to_string(&exp.consume_arg()?.tokens);
+
+    Ok(owned(format!("\\begin{{{env}}}{body}\\end{{{env}}}")))
+}
+
+/// `\dd`, `\dd{x}`, `\dd[n]{x}` and `\dd(x)`
+fn dd<'a>(exp: &mut MacroExpander<'a, '_>) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let power = consume_delimited(exp, "[", "]")?.map(|p| tokens_to_string(&p));
+    let d = match power {
+        Some(power) => format!("\\mathrm{{d}}^{{{power}}}"),
+        None => "\\mathrm{d}".to_string(),
+    };
+
+    exp.consume_spaces()?;
+    Ok(owned(match exp.future()?.content.as_ref() {
+        "{" => format!("{d}{{{}}}", tokens_to_string(&exp.consume_arg()?.tokens)),
+        "(" => format!("{d}\\qty"),
+        _ => d,
+    }))
+}
+
+/// `\dv{f}{x}`, `\dv[n]{f}{x}`, `\dv{x}` and the starred inline forms. `\pdv` additionally
+/// accepts a mixed partial derivative, `\pdv{f}{x}{y}`.
+fn derivative<'a>(
+    exp: &mut MacroExpander<'a, '_>,
+    d: &str,
+) -> Result<MacroVal<'a, 'static>, ParseError> {
+    let inline = consume_star(exp)?;
+    let power = consume_delimited(exp, "[", "]")?.map(|p| tokens_to_string(&p));
+    let first = tokens_to_string(&exp.consume_arg()?.tokens);
+
+    let mut vars = Vec::new();
+    exp.consume_spaces()?;
+    while vars.len() < 2 && exp.future()?.content == "{" {
+        vars.push(tokens_to_string(&exp.consume_arg()?.tokens));
+        exp.consume_spaces()?;
+    }
+    // A mixed partial derivative is only meaningful for `\pdv`
+    if d != "\\partial" {
+        vars.truncate(1);
+    }
+
+    let (numer, denom) = match (vars.as_slice(), power) {
+        // `\dv{x}`, the operator on its own
+        ([], power) => {
+            let numer = match &power {
+                Some(power) => format!("{d}^{{{power}}}"),
+                None => d.to_string(),
+            };
+            let denom = match &power {
+                Some(power) => format!("{d}{{{first}}}^{{{power}}}"),
+                None => format!("{d}{{{first}}}"),
+            };
+            (numer, denom)
+        }
+        ([x], Some(power)) => (
+            format!("{d}^{{{power}}}{{{first}}}"),
+            format!("{d}{{{x}}}^{{{power}}}"),
+        ),
+        ([x], None) => (format!("{d}{{{first}}}"), format!("{d}{{{x}}}")),
+        ([x, y], _) => (
+            format!("{d}^{{2}}{{{first}}}"),
+            format!("{d}{{{x}}}{d}{{{y}}}"),
+        ),
+        _ => unreachable!(),
+    };
+
+    let mut res = if inline {
+        format!("{numer}/{denom}")
+    } else {
+        format!("\\frac{{{numer}}}{{{denom}}}")
+    };
+
+    // `\dv{x}(f)` applies the operator to the parenthesized expression
+    if vars.is_empty() && exp.future()?.content == "(" {
+        res.push_str("\\qty");
+    }
+
+    Ok(owned(res))
+}
@@ -0,0 +1,758 @@
+//! Numbers and units from the LaTeX `siunitx` package.
+//! <https://ctan.org/pkg/siunitx>
+//!
+//! Provides `\num`, `\si`/`\unit` and `\SI`/`\qty`. Units can be given either with the unit
+//! macros, `\si{\kilo\metre\per\second\squared}`, or literally, `\si{km/s^2}`.
+//! ```
+//! # use aliter::{contrib::siunitx::{self, PerMode, SiunitxConfig}, parse_tree, parser::ParserConfig};
+//! let mut conf = ParserConfig::default();
+//! siunitx::add_macros_with(
+//!     &mut conf.macros,
+//!     SiunitxConfig {
+//!         per_mode: PerMode::Fraction,
+//!         ..SiunitxConfig::default()
+//!     },
+//! );
+//! let tree = parse_tree(r"\SI{1.23e3}{\kilo\metre\per\second}", conf).unwrap();
+//! ```
+//!
+//! Note that `\qty` is also defined by the `physics` package, so whichever is added last wins.
+
+use alloc::{borrow::Cow, sync::Arc};
+
+use crate::prelude::*;
+use crate::{
+    builtin_macros::f,
+    expander::{MacroExpander, Mode},
+    lexer::Token,
+    macr::{MacroVal, Macros},
+    parser::ParseError,
+};
+
+use super::{consume_delimited, tokens_to_string};
+
+/// How units raised to a negative power (from `\per` or `/`) are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerMode {
+    /// `\mathrm{m}\,\mathrm{s}^{-1}`
+    Power,
+    /// `\frac{\mathrm{m}}{\mathrm{s}}`
+    Fraction,
+    /// `\mathrm{m}/\mathrm{s}`
+    Symbol,
+}
+
+/// Output options for the `siunitx` macros.
+/// These can be overridden for a single use with the optional argument, as in
+/// `\num[output-decimal-marker={,}]{1.5}`.
+#[derive(Debug, Clone)]
+pub struct SiunitxConfig {
+    /// `per-mode`
+    pub per_mode: PerMode,
+    /// `output-decimal-marker`, the marker placed between the integer and decimal parts of a
+    /// number. The input may use either `.` or `,`.
+    pub output_decimal_marker: String,
+    /// `group-digits`, whether to separate long numbers into groups of three digits
+    pub group_digits: bool,
+    /// `group-minimum-digits`, the number of digits needed before a part of a number is grouped
+    pub group_minimum_digits: usize,
+    /// `exponent-product`, the symbol placed between the mantissa and `10^{...}`
+    pub exponent_product: String,
+    /// `separate-uncertainty`, whether `1.23(4)` is shown as `1.23 \pm 0.04`
+    pub separate_uncertainty: bool,
+}
+impl Default for SiunitxConfig {
+    fn default() -> Self {
+        SiunitxConfig {
+            per_mode: PerMode::Power,
+            output_decimal_marker: ".".to_string(),
+            group_digits: true,
+            group_minimum_digits: 5,
+            exponent_product: "\\times".to_string(),
+            separate_uncertainty: false,
+        }
+    }
+}
+impl SiunitxConfig {
+    /// Apply options written as `key=value, key=value`
+    fn apply_options(&mut self, options: &str) -> Result<(), ParseError> {
+        for option in split_top_level(options, ',') {
+            let option = option.trim();
+            if option.is_empty() {
+                continue;
+            }
+
+            let (key, value) = match option.split_once('=') {
+                Some((key, value)) => (key.trim(), strip_braces(value.trim())),
+                // Switches are turned on when given without a value, as in `[separate-uncertainty]`
+                None if matches!(option, "group-digits" | "separate-uncertainty") => {
+                    (option, "true")
+                }
+                None => return Err(ParseError::InvalidSiOption(option.to_string())),
+            };
+            let invalid = || ParseError::InvalidSiOption(option.to_string());
+
+            match key {
+                "per-mode" => {
+                    self.per_mode = match value {
+                        "power" => PerMode::Power,
+                        "fraction" => PerMode::Fraction,
+                        "symbol" => PerMode::Symbol,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "output-decimal-marker" => self.output_decimal_marker = value.to_string(),
+                "group-digits" => {
+                    self.group_digits = match value {
+                        "true" | "all" => true,
+                        "false" | "none" => false,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "group-minimum-digits" => {
+                    self.group_minimum_digits = value.parse().map_err(|_| invalid())?
+                }
+                "exponent-product" => self.exponent_product = value.to_string(),
+                "separate-uncertainty" => {
+                    self.separate_uncertainty = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "uncertainty-mode" => {
+                    self.separate_uncertainty = match value {
+                        "separate" => true,
+                        "compact" => false,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Add the `siunitx` macros to `macros` with the default output options
+pub fn add_macros(macros: &mut Macros) {
+    add_macros_with(macros, SiunitxConfig::default())
+}
+
+/// Add the `siunitx` macros to `macros`, using `conf` for the output options
+pub fn add_macros_with(macros: &mut Macros, conf: SiunitxConfig) {
+    let conf = Arc::new(conf);
+
+    let num_conf = conf.clone();
+    macros.insert_back_macro(
+        "\\num",
+        f(move |exp| {
+            let conf = consume_options(exp, &num_conf)?;
+            let number = tokens_to_string(&exp.consume_arg()?.tokens);
+            let number = format_number(&number, &conf)?;
+            Ok(math_text(exp, number))
+        }),
+    );
+
+    for name in ["\\si", "\\unit"] {
+        let unit_conf = conf.clone();
+        macros.insert_back_macro(
+            name,
+            f(move |exp| {
+                let conf = consume_options(exp, &unit_conf)?;
+                let units = parse_units(&exp.consume_arg()?.tokens)?;
+                Ok(math_text(exp, format_units(&units, &conf)))
+            }),
+        );
+    }
+
+    for name in ["\\SI", "\\qty"] {
+        let qty_conf = conf.clone();
+        macros.insert_back_macro(
+            name,
+            f(move |exp| {
+                let conf = consume_options(exp, &qty_conf)?;
+                let number = tokens_to_string(&exp.consume_arg()?.tokens);
+                let number = format_number(&number, &conf)?;
+                let units = parse_units(&exp.consume_arg()?.tokens)?;
+
+                // Some units, like `\degree`, are placed directly after the number
+                let spaced = !matches!(units.as_slice(), [unit] if !unit.spaced && !unit.per);
+                let sep = if spaced { "\\," } else { "" };
+                let res = format!("{number}{sep}{}", format_units(&units, &conf));
+                Ok(math_text(exp, res))
+            }),
+        );
+    }
+}
+
+/// Read the optional `[...]` argument and apply it on top of the configured options
+fn consume_options(
+    exp: &mut MacroExpander<'_, '_>,
+    conf: &SiunitxConfig,
+) -> Result<SiunitxConfig, ParseError> {
+    let mut conf = conf.clone();
+    if let Some(options) = consume_delimited(exp, "[", "]")? {
+        conf.apply_options(&tokens_to_string(&options))?;
+    }
+
+    Ok(conf)
+}
+
+/// The output is math, so in text mode it has to be switched into math mode
+fn math_text<'a>(exp: &MacroExpander<'a, '_>, text: String) -> MacroVal<'a, 'static> {
+    let text = if exp.mode == Mode::Text {
+        format!("${text}$")
+    } else {
+        text
+    };
+
+    MacroVal::Text(Cow::Owned(text))
+}
+
+/// Split on `sep`, ignoring any within braces
+fn split_top_level(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, ch) in text.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ if ch == sep && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + ch.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+}
+
+fn strip_braces(text: &str) -> &str {
+    text.strip_prefix('{')
+        .and_then(|text| text.strip_suffix('}'))
+        .unwrap_or(text)
+}
+
+//////////////////////////////////////////////////////////////////////
+// Numbers
+
+#[derive(Debug, Default)]
+struct Number<'a> {
+    sign: &'a str,
+    integer: &'a str,
+    decimal: Option<&'a str>,
+    uncertainty: Option<Uncertainty<'a>>,
+    exponent: Option<(&'a str, &'a str)>,
+}
+
+#[derive(Debug)]
+enum Uncertainty<'a> {
+    /// `1.23(4)`, the digits apply to the last places of the number
+    Compact(&'a str),
+    /// `1.23 \pm 0.04`
+    Separate(&'a str),
+}
+
+/// Take the prefix of `text` made of characters matching `pred`
+fn take_while(text: &str, pred: impl Fn(char) -> bool) -> (&str, &str) {
+    let end = text.find(|c: char| !pred(c)).unwrap_or(text.len());
+    text.split_at(end)
+}
+
+fn take_sign(text: &str) -> (&str, &str) {
+    for sign in ["+", "-", "\\pm", "\\mp"] {
+        if let Some(rest) = text.strip_prefix(sign) {
+            return (sign, rest);
+        }
+    }
+
+    ("", text)
+}
+
+fn parse_number(input: &str) -> Option<Number<'_>> {
+    let mut num = Number::default();
+
+    let (sign, rest) = take_sign(input);
+    num.sign = sign;
+
+    let (integer, mut rest) = take_while(rest, |c| c.is_ascii_digit());
+    num.integer = integer;
+    if let Some(after) = rest.strip_prefix(['.', ',']) {
+        let (decimal, after) = take_while(after, |c| c.is_ascii_digit());
+        num.decimal = Some(decimal);
+        rest = after;
+    }
+
+    if let Some(after) = rest.strip_prefix('(') {
+        let (unc, after) = take_while(after, |c| c.is_ascii_digit() || c == '.' || c == ',');
+        num.uncertainty = Some(Uncertainty::Compact(unc));
+        rest = after.strip_prefix(')')?;
+    } else if let Some(after) = rest.strip_prefix("\\pm") {
+        let (unc, after) = take_while(after, |c| c.is_ascii_digit() || c == '.' || c == ',');
+        num.uncertainty = Some(Uncertainty::Separate(unc));
+        rest = after;
+    }
+
+    if let Some(after) = rest.strip_prefix(['e', 'E']) {
+        let (sign, after) = take_sign(after);
+        let (exp, after) = take_while(after, |c| c.is_ascii_digit());
+        if exp.is_empty() {
+            return None;
+        }
+        num.exponent = Some((sign, exp));
+        rest = after;
+    }
+
+    let has_mantissa = !num.integer.is_empty() || num.decimal.is_some_and(|d| !d.is_empty());
+    if !rest.is_empty() || (!has_mantissa && num.exponent.is_none()) {
+        return None;
+    }
+
+    Some(num)
+}
+
+/// Format a number like `-1234.5e3` into `-1\,234.5\times10^{3}`
+fn format_number(input: &str, conf: &SiunitxConfig) -> Result<String, ParseError> {
+    let cleaned: String = input
+        .replace("\\,", "")
+        .replace('~', "")
+        .split_whitespace()
+        .collect();
+    let num =
+        parse_number(&cleaned).ok_or_else(|| ParseError::InvalidSiNumber(input.to_string()))?;
+
+    let marker = if conf.output_decimal_marker == "," {
+        "{,}"
+    } else {
+        conf.output_decimal_marker.as_str()
+    };
+
+    let mut out = String::new();
+    out.push_str(num.sign);
+
+    let has_mantissa = !num.integer.is_empty() || num.decimal.is_some();
+    if has_mantissa {
+        // `.5` is written as `0.5`
+        let integer = if num.integer.is_empty() {
+            "0"
+        } else {
+            num.integer
+        };
+        out.push_str(&group(integer, conf, true));
+
+        if let Some(decimal) = num.decimal.filter(|d| !d.is_empty()) {
+            out.push_str(marker);
+            out.push_str(&group(decimal, conf, false));
+        }
+    }
+
+    match num.uncertainty {
+        Some(Uncertainty::Compact(unc)) if conf.separate_uncertainty => {
+            let places = num.decimal.map_or(0, str::len);
+            let unc = expand_uncertainty(unc, places).replace(['.', ','], marker);
+            out.push_str("\\pm");
+            out.push_str(&unc);
+        }
+        Some(Uncertainty::Compact(unc)) => {
+            out.push('(');
+            out.push_str(&unc.replace(['.', ','], marker));
+            out.push(')');
+        }
+        Some(Uncertainty::Separate(unc)) => {
+            out.push_str("\\pm");
+            out.push_str(&unc.replace(['.', ','], marker));
+        }
+        None => {}
+    }
+
+    if let Some((sign, exp)) = num.exponent {
+        if has_mantissa {
+            out.push_str(&conf.exponent_product);
+        }
+        let sign = if sign == "+" { "" } else { sign };
+        out.push_str(&format!("10^{{{sign}{exp}}}"));
+    }
+
+    Ok(out)
+}
+
+/// Separate digits into groups of three with thin spaces. The integer part is grouped from the
+/// right, the decimal part from the left.
+fn group(digits: &str, conf: &SiunitxConfig, from_right: bool) -> String {
+    if !conf.group_digits || digits.len() < conf.group_minimum_digits {
+        return digits.to_string();
+    }
+
+    let mut out = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        let boundary = if from_right {
+            (digits.len() - i) % 3 == 0
+        } else {
+            i % 3 == 0
+        };
+        if i != 0 && boundary {
+            out.push_str("\\,");
+        }
+        out.push(ch);
+    }
+
+    out
+}
+
+/// Convert the digits of a compact uncertainty, which apply to the last `places` decimal places
+/// of the number, into a plain number: `4` with two places is `0.04`
+fn expand_uncertainty(unc: &str, places: usize) -> String {
+    if unc.contains(['.', ',']) || places == 0 {
+        return unc.to_string();
+    }
+
+    if unc.len() > places {
+        let (integer, decimal) = unc.split_at(unc.len() - places);
+        format!("{integer}.{decimal}")
+    } else {
+        format!("0.{}{unc}", "0".repeat(places - unc.len()))
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+// Units
+
+/// A piece of a unit's symbol
+#[derive(Debug, Clone)]
+enum Sym {
+    /// Upright text, like the `k` and `m` in `km`
+    Text(Cow<'static, str>),
+    /// Math that is already upright, like `\Omega`
+    Math(Cow<'static, str>),
+}
+
+const fn text(text: &'static str) -> Sym {
+    Sym::Text(Cow::Borrowed(text))
+}
+
+const fn math(math: &'static str) -> Sym {
+    Sym::Math(Cow::Borrowed(math))
+}
+
+static PREFIXES: &[(&str, Sym)] = &[
+    ("\\quecto", text("q")),
+    ("\\ronto", text("r")),
+    ("\\yocto", text("y")),
+    ("\\zepto", text("z")),
+    ("\\atto", text("a")),
+    ("\\femto", text("f")),
+    ("\\pico", text("p")),
+    ("\\nano", text("n")),
+    // Units are upright, but the KaTeX fonts only have an italic mu, so this is the micro sign
+    // in the text font
+    ("\\micro", math("\\text{\u{b5}}")),
+    ("\\milli", text("m")),
+    ("\\centi", text("c")),
+    ("\\deci", text("d")),
+    ("\\deca", text("da")),
+    ("\\deka", text("da")),
+    ("\\hecto", text("h")),
+    ("\\kilo", text("k")),
+    ("\\mega", text("M")),
+    ("\\giga", text("G")),
+    ("\\tera", text("T")),
+    ("\\peta", text("P")),
+    ("\\exa", text("E")),
+    ("\\zetta", text("Z")),
+    ("\\yotta", text("Y")),
+    ("\\ronna", text("R")),
+    ("\\quetta", text("Q")),
+];
+
+/// (name, symbol, whether it is separated from the number by a space)
+static UNITS: &[(&str, Sym, bool)] = &[
+    // SI base units
+    ("\\ampere", text("A"), true),
+    ("\\candela", text("cd"), true),
+    ("\\kelvin", text("K"), true),
+    ("\\kilogram", text("kg"), true),
+    ("\\gram", text("g"), true),
+    ("\\metre", text("m"), true),
+    ("\\meter", text("m"), true),
+    ("\\mole", text("mol"), true),
+    ("\\second", text("s"), true),
+    // Derived units
+    ("\\becquerel", text("Bq"), true),
+    ("\\degreeCelsius", math("\\degree\\mathrm{C}"), true),
+    ("\\coulomb", text("C"), true),
+    ("\\farad", text("F"), true),
+    ("\\gray", text("Gy"), true),
+    ("\\hertz", text("Hz"), true),
+    ("\\henry", text("H"), true),
+    ("\\joule", text("J"), true),
+    ("\\katal", text("kat"), true),
+    ("\\lumen", text("lm"), true),
+    ("\\lux", text("lx"), true),
+    ("\\newton", text("N"), true),
+    ("\\ohm", math("\\Omega"), true),
+    ("\\pascal", text("Pa"), true),
+    ("\\radian", text("rad"), true),
+    ("\\siemens", text("S"), true),
+    ("\\sievert", text("Sv"), true),
+    ("\\steradian", text("sr"), true),
+    ("\\tesla", text("T"), true),
+    ("\\volt", text("V"), true),
+    ("\\watt", text("W"), true),
+    ("\\weber", text("Wb"), true),
+    // Non-SI units accepted for use with SI
+    ("\\astronomicalunit", text("au"), true),
+    ("\\bel", text("B"), true),
+    ("\\dalton", text("Da"), true),
+    ("\\day", text("d"), true),
+    ("\\decibel", text("dB"), true),
+    ("\\degree", math("\\degree"), false),
+    ("\\electronvolt", text("eV"), true),
+    ("\\hectare", text("ha"), true),
+    ("\\hour", text("h"), true),
+    ("\\litre", text("L"), true),
+    ("\\liter", text("L"), true),
+    ("\\arcminute", math("\\prime"), false),
+    ("\\minute", text("min"), true),
+    ("\\arcsecond", math("\\prime\\prime"), false),
+    ("\\neper", text("Np"), true),
+    ("\\tonne", text("t"), true),
+    // Others
+    ("\\percent", math("\\%"), true),
+    ("\\angstrom", math("\\text{\\AA}"), true),
+    ("\\bar", text("bar"), true),
+    ("\\barn", text("b"), true),
+    ("\\knot", text("kn"), true),
+    ("\\mmHg", text("mmHg"), true),
+];
+
+#[derive(Debug, Clone)]
+struct Unit {
+    syms: Vec<Sym>,
+    power: Option<String>,
+    /// Whether the unit is in the denominator
+    per: bool,
+    /// Whether the unit is separated from the number by a space
+    spaced: bool,
+}
+impl Unit {
+    fn new(per: bool, power: Option<String>) -> Unit {
+        Unit {
+            syms: Vec::new(),
+            power,
+            per,
+            spaced: true,
+        }
+    }
+
+    /// Render the symbol, merging adjacent text into a single `\mathrm`
+    fn symbol(&self) -> String {
+        let mut out = String::new();
+        let mut text = String::new();
+        for sym in &self.syms {
+            match sym {
+                Sym::Text(t) => text.push_str(t),
+                Sym::Math(m) => {
+                    if !text.is_empty() {
+                        out.push_str(&format!("\\mathrm{{{text}}}"));
+                        text.clear();
+                    }
+                    out.push_str(m);
+                }
+            }
+        }
+        if !text.is_empty() {
+            out.push_str(&format!("\\mathrm{{{text}}}"));
+        }
+
+        out
+    }
+
+    /// Render the unit with its power, where `negate` turns a denominator into a negative power
+    fn render(&self, negate: bool) -> String {
+        let power = match (&self.power, negate) {
+            (Some(power), true) => match power.strip_prefix('-') {
+                Some(positive) => Some(positive.to_string()),
+                None => Some(format!("-{power}")),
+            },
+            (None, true) => Some("-1".to_string()),
+            (power, false) => power.clone(),
+        };
+
+        match power {
+            Some(power) => format!("{}^{{{power}}}", self.symbol()),
+            None => self.symbol(),
+        }
+    }
+}
+
+fn is_letter_token(tok: &str) -> bool {
+    let mut chars = tok.chars();
+    matches!((chars.next(), chars.next()), (Some(c), None) if c.is_alphabetic())
+}
+
+/// Collect the tokens of a braced group or a single token, starting at `i`
+fn group_arg(tokens: &[Token<'_>], i: &mut usize) -> Option<String> {
+    let first = tokens.get(*i)?;
+    *i += 1;
+    if first.content != "{" {
+        return Some(first.content.to_string());
+    }
+
+    let mut depth = 1;
+    let start = *i;
+    while let Some(tok) = tokens.get(*i) {
+        *i += 1;
+        if tok.content == "{" {
+            depth += 1;
+        } else if tok.content == "}" {
+            depth -= 1;
+            if depth == 0 {
+                let mut inner = tokens[start..*i - 1].to_vec();
+                inner.reverse();
+                return Some(tokens_to_string(&inner));
+            }
+        }
+    }
+
+    None
+}
+
+/// Read a power written after `^`, like `2`, `-1` or `{-2}`
+fn power_arg(tokens: &[Token<'_>], i: &mut usize) -> Option<String> {
+    match tokens.get(*i)?.content.as_ref() {
+        "{" => group_arg(tokens, i),
+        "-" | "+" => {
+            let sign = tokens[*i].content.to_string();
+            *i += 1;
+            let digits = power_arg(tokens, i)?;
+            Some(if sign == "-" {
+                format!("-{digits}")
+            } else {
+                digits
+            })
+        }
+        _ => {
+            let mut digits = String::new();
+            while let Some(tok) = tokens
+                .get(*i)
+                .filter(|t| t.content.chars().all(|c| c.is_ascii_digit()))
+            {
+                digits.push_str(&tok.content);
+                *i += 1;
+            }
+            (!digits.is_empty()).then_some(digits)
+        }
+    }
+}
+
+/// Parse a unit, written either with macros (`\kilo\metre\per\second`) or literally (`km/s`).
+/// The tokens are in reverse order, as from the expander.
+fn parse_units(reversed: &[Token<'_>]) -> Result<Vec<Unit>, ParseError> {
+    let invalid = || ParseError::InvalidSiUnit(tokens_to_string(reversed));
+    let tokens: Vec<Token<'_>> = reversed.iter().rev().cloned().collect();
+
+    let mut units: Vec<Unit> = Vec::new();
+    // The unit currently being written literally
+    let mut current: Option<Unit> = None;
+    let mut prefix: Option<Sym> = None;
+    let mut pending_power: Option<String> = None;
+    let mut per = false;
+
+    let mut i = 0;
+    while let Some(tok) = tokens.get(i) {
+        let content = tok.content.as_ref();
+        i += 1;
+
+        if let Some((_, sym)) = PREFIXES.iter().find(|(name, _)| *name == content) {
+            units.extend(current.take());
+            prefix = Some(sym.clone());
+        } else if let Some((_, sym, spaced)) = UNITS.iter().find(|(name, _, _)| *name == content) {
+            units.extend(current.take());
+            let mut unit = Unit::new(core::mem::take(&mut per), pending_power.take());
+            unit.spaced = *spaced;
+            unit.syms.extend(prefix.take());
+            unit.syms.push(sym.clone());
+            units.push(unit);
+        } else {
+            match content {
+                "\\per" | "/" => {
+                    units.extend(current.take());
+                    per = true;
+                }
+                "\\square" => pending_power = Some("2".to_string()),
+                "\\cubic" => pending_power = Some("3".to_string()),
+                "\\raiseto" => {
+                    pending_power = Some(group_arg(&tokens, &mut i).ok_or_else(invalid)?)
+                }
+                "\\squared" | "\\cubed" | "\\tothe" | "^" => {
+                    let power = match content {
+                        "\\squared" => "2".to_string(),
+                        "\\cubed" => "3".to_string(),
+                        "\\tothe" => group_arg(&tokens, &mut i).ok_or_else(invalid)?,
+                        _ => power_arg(&tokens, &mut i).ok_or_else(invalid)?,
+                    };
+                    units.extend(current.take());
+                    let last = units.last_mut().ok_or_else(invalid)?;
+                    last.power = Some(power);
+                }
+                // Separators between units
+                "." | "~" | " " | "\\," | "\\ " | "*" | "\\cdot" => {
+                    units.extend(current.take());
+                }
+                "{" | "}" => {}
+                _ if prefix.is_some() || pending_power.is_some() => return Err(invalid()),
+                _ => {
+                    let unit =
+                        current.get_or_insert_with(|| Unit::new(core::mem::take(&mut per), None));
+                    let content = Cow::Owned(content.to_string());
+                    if is_letter_token(&content) {
+                        unit.syms.push(Sym::Text(content));
+                    } else {
+                        // Anything else, like `\Omega` or `\mu`, is passed through as math
+                        unit.syms.push(Sym::Math(content));
+                    }
+                }
+            }
+        }
+    }
+    units.extend(current.take());
+
+    if units.is_empty() || prefix.is_some() || pending_power.is_some() || per {
+        return Err(invalid());
+    }
+
+    Ok(units)
+}
+
+fn format_units(units: &[Unit], conf: &SiunitxConfig) -> String {
+    let join = |units: &mut dyn Iterator<Item = String>| units.collect::<Vec<_>>().join("\\,");
+
+    let has_per = units.iter().any(|u| u.per);
+    match conf.per_mode {
+        PerMode::Power => join(&mut units.iter().map(|u| u.render(u.per))),
+        _ if !has_per => join(&mut units.iter().map(|u| u.render(false))),
+        PerMode::Fraction | PerMode::Symbol => {
+            let numer = join(&mut units.iter().filter(|u| !u.per).map(|u| u.render(false)));
+            let numer = if numer.is_empty() {
+                "1".to_string()
+            } else {
+                numer
+            };
+            let denoms = units.iter().filter(|u| u.per).count();
+            let denom = join(&mut units.iter().filter(|u| u.per).map(|u| u.render(false)));
+
+            if conf.per_mode == PerMode::Fraction {
+                format!("\\frac{{{numer}}}{{{denom}}}")
+            } else if denoms > 1 {
+                format!("{numer}/({denom})")
+            } else {
+                format!("{numer}/{denom}")
+            }
+        }
+    }
+}
@@ -1,15 +1,14 @@
+use crate::prelude::*;
 use crate::{
     build_common::{self, make_span, VListElem, VListKern, VListParam, VListShiftChild},
     dom_tree::{CssStyle, HtmlNode, PathNode, Span, SvgChildNode, SvgNode, SymbolNode, WithHtmlDomNode},
     expander::Mode,
-    font_metrics::{get_character_metrics, CharacterMetrics},
-    font_metrics_data,
+    font_metrics::{get_character_metrics, get_metric_data, CharacterMetrics},
     style::{StyleId, SCRIPT_SCRIPT_STYLE, SCRIPT_STYLE, TEXT_STYLE},
     svg_geometry,
     symbols,
     tree::ClassList,
     unit::make_em,
-    util::{char_code_for, find_assoc_data},
     Options,
 };
 
@@ -78,6 +77,10 @@ fn delim_swap(delim: &str) -> &str {
     match delim {
         "<" | "\\lt" | "\u{27e8}" => "\\langle",
         ">" | "\\gt" | "\u{27e9}" => "\\rangle",
+        // The parser stores these delimiters by what they look like
+        "{" => "\\{",
+        "}" => "\\}",
+        "||" => "\\|",
         _ => delim,
     }
 }
@@ -376,16 +379,11 @@ fn glyph_span(symbol: &str, font: GlyphFont, mode: Mode) -> VListElem<Span<Span<
 
 fn make_inner(ch: char, height: f64, options: &Options) -> VListElem<Span<HtmlNode>> {
     // Create a span with inline SVG for the inner part of a tall stacked delimiter
-    let size4_metrics = font_metrics_data::get_metric("Size4-Regular").unwrap();
-    let size1_metrics = font_metrics_data::get_metric("Size1-Regular").unwrap();
-
-    let code = char_code_for(ch);
-
-    let width = if let Some(s4) = find_assoc_data(size4_metrics, code) {
+    let width = if let Some(s4) = get_metric_data("Size4-Regular", ch) {
         s4[4]
     } else {
         // TODO: don't unwrap! though the logic in katex doesn't appear to handle this case
-        let s1 = find_assoc_data(size1_metrics, code).unwrap();
+        let s1 = get_metric_data("Size1-Regular", ch).unwrap();
         s1[4]
     };
 
@@ -599,8 +597,8 @@ fn stacked_delim(
         axis_height *= options.size_multiplier();
     }
 
-    assert_eq!(repeat.len(), 1);
-    let repeat = repeat.chars().nth(0).unwrap();
+    // The repeated piece is a single character, though not always a single byte
+    let repeat = repeat.chars().next().unwrap();
 
     // Calculate the depth
     let depth = real_height_total / 2.0 - axis_height;
@@ -1,10 +1,11 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use core::fmt;
 
 use crate::{
     mathml_tree::{MathDomNode, MathmlNode, WithMathDomNode},
     parse_node::Color,
     svg_geometry,
-    tree::{class_attr, Attributes, ClassList, EmptyNode, VirtualNode},
+    tree::{write_class_attr, Attributes, ClassList, EmptyNode, VirtualNode},
     util::{self, find_assoc_data},
     Options,
 };
@@ -22,6 +23,8 @@ pub struct CssStyle {
     pub border_width: Option<Cow<'static, str>>,
     pub bottom: Option<Cow<'static, str>>,
     pub color: Option<Color>,
+    pub font_style: Option<Cow<'static, str>>,
+    pub font_variant: Option<Cow<'static, str>>,
     pub height: Option<Cow<'static, str>>,
     pub left: Option<Cow<'static, str>>,
     pub margin: Option<Cow<'static, str>>,
@@ -36,96 +39,107 @@ pub struct CssStyle {
     pub vertical_align: Option<Cow<'static, str>>,
 }
 impl CssStyle {
+    /// Whether none of the properties are set
+    pub fn is_empty(&self) -> bool {
+        *self == CssStyle::default()
+    }
+
     // Technically could be modified to return just a `Cow<'static, str>` if there was only one set
     /// Convert this into how it would be if in the `style=""` attribute  
     pub fn as_style_attr(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
         let mut res = String::new();
+        // Writing to a `String` can't fail
+        self.write_style_attr(&mut res).unwrap();
+        Some(res)
+    }
 
-        let mut append_field = |field_name: &'static str, val: &Option<Cow<'static, str>>| {
+    /// Write the properties as they would be in the `style=""` attribute
+    pub fn write_style_attr(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let mut append_field = |field_name: &'static str,
+                                val: &Option<Cow<'static, str>>|
+         -> fmt::Result {
             if let Some(val) = val {
-                res.push_str(field_name);
-                res.push_str(": ");
-                res.push_str(val);
-                res.push(';');
+                out.write_str(field_name)?;
+                out.write_str(": ")?;
+                out.write_str(val)?;
+                out.write_char(';')?;
             }
+            Ok(())
         };
 
-        append_field("background-color", &self.background_color);
-        append_field("border-bottom-width", &self.border_bottom_width);
-        append_field("border-color", &self.border_color);
-        append_field("border-right-style", &self.border_right_style);
-        append_field("border-right-width", &self.border_right_width);
-        append_field("border-top-width", &self.border_top_width);
-        append_field("border-style", &self.border_style);
-        append_field("border-width", &self.border_width);
-        append_field("bottom", &self.bottom);
-        append_field("height", &self.height);
-        append_field("left", &self.left);
-        append_field("margin", &self.margin);
-        append_field("margin-left", &self.margin_left);
-        append_field("margin-right", &self.margin_right);
-        append_field("margin-top", &self.margin_top);
-        append_field("min-width", &self.min_width);
-        append_field("padding-left", &self.padding_left);
-        append_field("position", &self.position);
-        append_field("top", &self.top);
-        append_field("width", &self.width);
-        append_field("vertical-align", &self.vertical_align);
+        append_field("background-color", &self.background_color)?;
+        append_field("border-bottom-width", &self.border_bottom_width)?;
+        append_field("border-color", &self.border_color)?;
+        append_field("border-right-style", &self.border_right_style)?;
+        append_field("border-right-width", &self.border_right_width)?;
+        append_field("border-top-width", &self.border_top_width)?;
+        append_field("border-style", &self.border_style)?;
+        append_field("border-width", &self.border_width)?;
+        append_field("bottom", &self.bottom)?;
+        append_field("font-style", &self.font_style)?;
+        append_field("font-variant", &self.font_variant)?;
+        append_field("height", &self.height)?;
+        append_field("left", &self.left)?;
+        append_field("margin", &self.margin)?;
+        append_field("margin-left", &self.margin_left)?;
+        append_field("margin-right", &self.margin_right)?;
+        append_field("margin-top", &self.margin_top)?;
+        append_field("min-width", &self.min_width)?;
+        append_field("padding-left", &self.padding_left)?;
+        append_field("position", &self.position)?;
+        append_field("top", &self.top)?;
+        append_field("width", &self.width)?;
+        append_field("vertical-align", &self.vertical_align)?;
 
         if let Some(color) = &self.color {
-            let color = color.to_string();
-            res.push_str("color: ");
-            res.push_str(&color);
-            res.push(';');
+            out.write_str("color: ")?;
+            out.write_str(&color.to_string())?;
+            out.write_char(';')?;
         }
 
-        if res.is_empty() {
-            None
-        } else {
-            Some(res)
-        }
+        Ok(())
     }
 }
 
-fn to_markup_attr<T: VirtualNode>(
+fn write_markup_attr<T: VirtualNode>(
+    out: &mut dyn fmt::Write,
     node: &HtmlDomNode,
     attributes: &Attributes,
     children: &[T],
     tag_name: &str,
-) -> String {
-    let mut markup = format!("<{tag_name}");
+) -> fmt::Result {
+    out.write_char('<')?;
+    out.write_str(tag_name)?;
 
-    if let Some(classes) = class_attr(&node.classes) {
-        markup.push_str(" class=\"");
-        markup.push_str(&classes);
-        markup.push('"');
-    }
+    write_class_attr(out, &node.classes)?;
 
-    if let Some(style) = node.style.as_style_attr() {
-        markup.push_str(" style=\"");
-        markup.push_str(&style);
-        markup.push('"');
+    if !node.style.is_empty() {
+        out.write_str(" style=\"")?;
+        node.style.write_style_attr(out)?;
+        out.write_char('"')?;
     }
 
     for (name, val) in attributes.iter() {
-        markup.push(' ');
-        markup.push_str(name);
-        markup.push_str("=\"");
-        markup.push_str(&util::escape(val));
-        markup.push('"');
+        out.write_char(' ')?;
+        out.write_str(name)?;
+        out.write_str("=\"")?;
+        util::write_escaped(out, val)?;
+        out.write_char('"')?;
     }
 
-    markup.push('>');
+    out.write_char('>')?;
 
     for child in children {
-        markup.push_str(&child.to_markup());
+        child.write_markup(out)?;
     }
 
-    markup.push_str("</");
-    markup.push_str(tag_name);
-    markup.push('>');
-
-    markup
+    out.write_str("</")?;
+    out.write_str(tag_name)?;
+    out.write_char('>')
 }
 
 // Note: Unlike KaTeX, we have this as a shared structure for use as a field
@@ -182,19 +196,13 @@ impl Default for HtmlDomNode {
     }
 }
 impl VirtualNode for HtmlDomNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         // Bare HtmlDomNode has no tag context; emit an empty span to expose classes/style.
         let mut attrs = Attributes::new();
         if let Some(style) = self.style.as_style_attr() {
             attrs.insert("style".to_string(), style);
         }
-        let dummy = Span {
-            node: self.clone(),
-            children: Vec::<EmptyNode>::new(),
-            attributes: attrs,
-            width: None,
-        };
-        dummy.to_markup()
+        write_markup_attr::<EmptyNode>(out, self, &attrs, &[], "span")
     }
 }
 
@@ -209,15 +217,15 @@ pub enum HtmlNode {
     Svg(SvgNode),
 }
 impl VirtualNode for HtmlNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         match self {
-            HtmlNode::Empty(node) => node.to_markup(),
-            HtmlNode::DocumentFragment(node) => node.to_markup(),
-            HtmlNode::Span(node) => node.to_markup(),
-            HtmlNode::Anchor(node) => node.to_markup(),
-            HtmlNode::Img(node) => node.to_markup(),
-            HtmlNode::Symbol(node) => node.to_markup(),
-            HtmlNode::Svg(node) => node.to_markup(),
+            HtmlNode::Empty(node) => node.write_markup(out),
+            HtmlNode::DocumentFragment(node) => node.write_markup(out),
+            HtmlNode::Span(node) => node.write_markup(out),
+            HtmlNode::Anchor(node) => node.write_markup(out),
+            HtmlNode::Img(node) => node.write_markup(out),
+            HtmlNode::Symbol(node) => node.write_markup(out),
+            HtmlNode::Svg(node) => node.write_markup(out),
         }
     }
 }
@@ -372,8 +380,11 @@ where
     }
 }
 impl<T: VirtualNode> VirtualNode for DocumentFragment<T> {
-    fn to_markup(&self) -> String {
-        self.children.iter().map(|c| c.to_markup()).collect()
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        for child in &self.children {
+            child.write_markup(out)?;
+        }
+        Ok(())
     }
 }
 #[cfg(feature = "html")]
@@ -476,8 +487,8 @@ impl<T: VirtualNode> WithHtmlDomNode for Span<T> {
     }
 }
 impl<T: VirtualNode> VirtualNode for Span<T> {
-    fn to_markup(&self) -> String {
-        to_markup_attr(&self.node, &self.attributes, &self.children, "span")
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        write_markup_attr(out, &self.node, &self.attributes, &self.children, "span")
     }
 }
 
@@ -510,8 +521,8 @@ impl<T: VirtualNode> WithHtmlDomNode for Anchor<T> {
     }
 }
 impl<T: VirtualNode> VirtualNode for Anchor<T> {
-    fn to_markup(&self) -> String {
-        to_markup_attr(&self.node, &self.attributes, &self.children, "a")
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        write_markup_attr(out, &self.node, &self.attributes, &self.children, "a")
     }
 }
 
@@ -537,36 +548,38 @@ impl WithHtmlDomNode for Img {
     }
 }
 impl VirtualNode for Img {
-    fn to_markup(&self) -> String {
-        // Output class attribute (unlike original KaTeX's toMarkup which omits classes)
-        let classes = self.node.classes.join(" ");
-        let mut markup = format!("<img src=\"{}\" alt=\"{}\"", self.src, self.alt);
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        write!(out, "<img src=\"{}\" alt=\"{}\"", self.src, self.alt)?;
 
-        if !classes.is_empty() {
-            markup.push_str(&format!(" class=\"{}\"", classes));
+        // Output class attribute (unlike original KaTeX's toMarkup which omits classes)
+        if self.node.classes.iter().any(|class| !class.is_empty()) {
+            out.write_str(" class=\"")?;
+            for (i, class) in self.node.classes.iter().enumerate() {
+                if i != 0 {
+                    out.write_char(' ')?;
+                }
+                out.write_str(class)?;
+            }
+            out.write_char('"')?;
         }
 
         // Add height and width as HTML attributes if present in style
         if let Some(height) = &self.node.style.height {
-            markup.push_str(&format!(" height=\"{}\"", height));
+            write!(out, " height=\"{}\"", height)?;
         }
         if let Some(width) = &self.node.style.width {
-            markup.push_str(&format!(" width=\"{}\"", width));
+            write!(out, " width=\"{}\"", width)?;
         }
 
-        // Build remaining style attributes (excluding height/width already output)
-        let mut styles = String::new();
-        if let Some(va) = &self.node.style.vertical_align {
-            styles.push_str(&format!("vertical-align:{};", va));
-        }
+        // Write remaining style attributes (excluding height/width already output)
         // Add other style properties as needed
-        if !styles.is_empty() {
-            markup.push_str(&format!(" style=\"{}\"", util::escape(&styles)));
+        if let Some(va) = &self.node.style.vertical_align {
+            out.write_str(" style=\"vertical-align:")?;
+            util::write_escaped(out, va)?;
+            out.write_str(";\"")?;
         }
 
-        markup.push_str("/>");
-
-        markup
+        out.write_str("/>")
     }
 }
 
@@ -650,51 +663,33 @@ impl WithHtmlDomNode for SymbolNode {
     }
 }
 impl VirtualNode for SymbolNode {
-    fn to_markup(&self) -> String {
-        let mut needs_span = false;
-
-        let mut markup = "<span".to_string();
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let has_style = self.italic > 0.0 || !self.node.style.is_empty();
+        let needs_span = !self.node.classes.is_empty() || has_style;
 
-        if let Some(classes) = class_attr(&self.node.classes) {
-            needs_span = true;
-            markup.push_str(" class=\"");
-            markup.push_str(&classes);
-            markup.push('"');
+        if !needs_span {
+            return util::write_escaped(out, &self.text);
         }
 
-        let italic_style = if self.italic > 0.0 {
-            Some(format!("margin-right:{}em;", self.italic))
-        } else {
-            None
-        };
+        out.write_str("<span")?;
 
-        let styles = self.node.style.as_style_attr();
+        write_class_attr(out, &self.node.classes)?;
 
-        if styles.is_some() || italic_style.is_some() {
-            needs_span = true;
-            markup.push_str(" style=\"");
+        if has_style {
+            out.write_str(" style=\"")?;
 
-            if let Some(italic_style) = italic_style {
-                markup.push_str(&italic_style);
+            if self.italic > 0.0 {
+                write!(out, "margin-right:{}em;", self.italic)?;
             }
 
-            if let Some(styles) = styles {
-                markup.push_str(&styles);
-            }
+            self.node.style.write_style_attr(out)?;
 
-            markup.push('"');
+            out.write_char('"')?;
         }
 
-        let escaped = util::escape(&self.text);
-        if needs_span {
-            markup.push('>');
-            markup.push_str(&escaped);
-            markup.push_str("</span>");
-
-            markup
-        } else {
-            escaped.into_owned()
-        }
+        out.write_char('>')?;
+        util::write_escaped(out, &self.text)?;
+        out.write_str("</span>")
     }
 }
 
@@ -723,27 +718,21 @@ impl SvgNode {
     // TODO: to_node
 }
 impl VirtualNode for SvgNode {
-    fn to_markup(&self) -> String {
-        let mut markup = "<svg xmlns=\"http://www.w3.org/2000/svg\"".to_string();
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        out.write_str("<svg xmlns=\"http://www.w3.org/2000/svg\"")?;
 
         // Apply attributes
         for (key, val) in self.attributes.iter() {
-            markup.push(' ');
-            markup.push_str(key);
-            markup.push_str("=\'");
-            markup.push_str(val);
-            markup.push('\'');
+            write!(out, " {}='{}'", key, val)?;
         }
 
-        markup.push('>');
+        out.write_char('>')?;
 
         for child in &self.children {
-            markup.push_str(&child.to_markup());
+            child.write_markup(out)?;
         }
 
-        markup.push_str("</svg>");
-
-        markup
+        out.write_str("</svg>")
     }
 }
 impl WithHtmlDomNode for SvgNode {
@@ -761,11 +750,11 @@ pub enum SvgChildNode {
     Path(PathNode),
     Line(LineNode),
 }
-impl SvgChildNode {
-    pub fn to_markup(&self) -> String {
+impl VirtualNode for SvgChildNode {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         match self {
-            SvgChildNode::Path(path) => path.to_markup(),
-            SvgChildNode::Line(line) => line.to_markup(),
+            SvgChildNode::Path(path) => path.write_markup(out),
+            SvgChildNode::Line(line) => line.write_markup(out),
         }
     }
 }
@@ -786,12 +775,12 @@ impl PathNode {
     // TODO: to_node
 }
 impl VirtualNode for PathNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         if let Some(alternate) = self.alternate.as_deref() {
-            format!("<path d='{}'/>", alternate)
+            write!(out, "<path d='{}'/>", alternate)
         } else {
             let path = find_assoc_data(svg_geometry::PATH, &self.path_name).unwrap();
-            format!("<path d='{}'/>", path)
+            write!(out, "<path d='{}'/>", path)
         }
     }
 }
@@ -806,20 +795,14 @@ impl LineNode {
     }
 }
 impl VirtualNode for LineNode {
-    fn to_markup(&self) -> String {
-        let mut markup = "<line".to_string();
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        out.write_str("<line")?;
 
         // Apply attributes
         for (key, val) in self.attributes.iter() {
-            markup.push(' ');
-            markup.push_str(key);
-            markup.push_str("=\'");
-            markup.push_str(val);
-            markup.push('\'');
+            write!(out, " {}='{}'", key, val)?;
         }
 
-        markup.push_str("/>");
-
-        markup
+        out.write_str("/>")
     }
 }
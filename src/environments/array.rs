@@ -1,5 +1,6 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
 use crate::{
     array::{AlignSpec, ColSeparationType},
     environments::{EnvironmentContext, EnvironmentSpec, Environments},
@@ -106,7 +107,7 @@ fn array_from_opts(
         |parser: &mut Parser, tags: &mut Option<Vec<ArrayTag>>| -> Result<(), ParseError> {
             if let Some(tags_vec) = tags.as_mut() {
                 if parser.gullet.macros.contains_back_macro("\\df@tag") {
-                    let tag = parser.sub_parse(std::iter::once(Token::new_text("\\df@tag")))?;
+                    let tag = parser.sub_parse(core::iter::once(Token::new_text("\\df@tag")))?;
                     tags_vec.push(ArrayTag::Tag(tag));
                     parser
                         .gullet
@@ -141,6 +142,7 @@ fn array_from_opts(
         } else {
             BreakToken::DoubleBackslash
         };
+        parser.count_array_cell()?;
         let cell_body = parser.dispatch_parse_expression(false, Some(break_token))?;
         parser.gullet.end_group();
         parser.gullet.begin_group();
@@ -193,6 +195,7 @@ fn array_from_opts(
             body.push(Vec::new());
             begin_row(parser);
         } else {
+            #[cfg(feature = "std")]
             eprintln!("array_from_opts: unexpected token {}", next);
             return Err(ParseError::Expected);
         }
@@ -619,6 +622,7 @@ fn normalize_arrow_char(c: char) -> char {
 }
 
 fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
+    #[cfg(feature = "std")]
     let dbg_enabled = false;
     let mut parsed_rows: Vec<Vec<ParseNode>> = Vec::new();
     parser.gullet.begin_group();
@@ -629,6 +633,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
     parser.gullet.begin_group();
 
     loop {
+        parser.count_array_cell()?;
         parsed_rows.push(parser.dispatch_parse_expression(false, Some(BreakToken::DoubleBackslash))?);
         parser.gullet.end_group();
         parser.gullet.begin_group();
@@ -641,6 +646,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
             }
             break;
         } else {
+            #[cfg(feature = "std")]
             if dbg_enabled {
                 eprintln!("cd parse: expected & or \\\\, got {}", next);
             }
@@ -665,6 +671,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
             row.push(cell);
             j += 1;
             if j >= row_nodes.len() {
+                #[cfg(feature = "std")]
                 if dbg_enabled {
                     eprintln!("cd parse: arrow at end of row");
                 }
@@ -696,6 +703,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
                             break;
                         }
                         if is_start_of_arrow(&row_nodes[j]) {
+                            #[cfg(feature = "std")]
                             if dbg_enabled {
                                 eprintln!("cd parse: nested arrow found parsing labels");
                             }
@@ -704,6 +712,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
                         labels[label_idx].body.push(row_nodes[j].clone());
                     }
                     if !found {
+                        #[cfg(feature = "std")]
                         if dbg_enabled {
                             eprintln!("cd parse: missing closing {}", arrow_char);
                         }
@@ -711,6 +720,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
                     }
                 }
             } else {
+                #[cfg(feature = "std")]
                 if dbg_enabled {
                     eprintln!("cd parse: unknown arrow char {}", arrow_char);
                 }
@@ -729,7 +739,7 @@ fn parse_cd(parser: &mut Parser) -> Result<ArrayNode, ParseError> {
             row.remove(0);
         }
 
-        body.push(std::mem::take(&mut row));
+        body.push(core::mem::take(&mut row));
     }
 
     parser.gullet.end_group();
@@ -1,5 +1,6 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
 use crate::{
     functions::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions},
     parse_node::{CdLabelNode, CdLabelParentNode, NodeInfo, ParseNode, ParseNodeType},
@@ -1,8 +1,9 @@
-use std::{borrow::Cow, collections::HashMap, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
-use once_cell::sync::Lazy;
+use hashbrown::HashMap;
 
-use crate::{expander::Mode, functions::FunctionPropSpec, parse_node::ParseNode, parser::Parser, parser::ParseError};
+use crate::prelude::*;
+use crate::{expander::Mode, functions::FunctionPropSpec, parse_node::ParseNode, parser::Parser, parser::ParseError, sync::Lazy};
 
 pub mod cd;
 pub mod array;
@@ -1,5 +1,6 @@
-use std::borrow::{Borrow, Cow};
+use alloc::borrow::{Borrow, Cow};
 
+use crate::prelude::*;
 use crate::{
     builtin_macros::BUILTIN_MACROS,
     functions::Functions,
@@ -65,6 +66,10 @@ pub enum TokenList<'a> {
     List(Vec<Token<'a>>),
 }
 
+/// The number of steps between checks of [`ParserConfig::deadline`]
+#[cfg(feature = "std")]
+const DEADLINE_CHECK_INTERVAL: u32 = 64;
+
 pub(crate) const IMPLICIT_COMMANDS: &'static [&'static str] = &["^", "_", "\\limits", "\\nolimits"];
 pub(crate) fn is_implicit_command(text: &str) -> bool {
     IMPLICIT_COMMANDS.iter().any(|cmd| *cmd == text)
@@ -81,23 +86,27 @@ pub struct MacroExpander<'a, 'f> {
 
     /// The number of expansions we've done. Used to make sure that we don't exceed any limits.
     expansion_count: u32,
+    /// The number of tokens we've expanded or passed on, for [`ParserConfig::max_steps`]
+    step_count: u32,
 }
 impl<'a, 'f> MacroExpander<'a, 'f> {
     pub fn new(
         input: &'a str,
-        conf: ParserConfig,
+        mut conf: ParserConfig,
         functions: &'f Functions,
         mode: Mode,
     ) -> MacroExpander<'a, 'f> {
         MacroExpander {
             lexer: Lexer::new(input, LexerConf { strict: conf.strict }),
-            macros: Namespace::new(BUILTIN_MACROS.clone(), conf.macros.clone()),
+            // The macros are only used through the namespace, so they are moved rather than cloned
+            macros: Namespace::new(BUILTIN_MACROS.clone(), core::mem::take(&mut conf.macros)),
             functions,
             conf,
             mode,
             stack: Vec::new(),
 
             expansion_count: 0,
+            step_count: 0,
         }
     }
 
@@ -114,6 +123,7 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
             stack: Vec::new(),
 
             expansion_count: 0,
+            step_count: 0,
         }
     }
 
@@ -146,6 +156,14 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
         Ok(self.stack.last().unwrap())
     }
 
+    /// The position in the input of the next unexpanded token
+    pub(crate) fn position(&self) -> Option<usize> {
+        match self.stack.last() {
+            Some(token) => token.loc.as_ref().map(|loc| loc.0.start),
+            None => Some(self.lexer.pos()),
+        }
+    }
+
     /// Remove and return the next unexpanded token
     pub(crate) fn pop_token(&mut self) -> Result<Token<'a>, ParseError> {
         self.future()?;
@@ -242,8 +260,7 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
                 if let Some(sub_depth) = depth.checked_sub(1) {
                     depth = sub_depth;
                 } else {
-                    // TODO: extra } error
-                    panic!();
+                    return Err(ParseError::ExtraCloseBrace);
                 }
             } else if tok.is_eof() {
                 return Err(ParseError::UnexpectedEOF);
@@ -258,7 +275,7 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
                     matc += 1;
                     if matc == delimiters.len() {
                         // Don't include the delimiters in tokens
-                        // TODO: splice it!
+                        tokens.truncate(tokens.len() - matc);
                         break;
                     }
                 } else {
@@ -271,10 +288,21 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
             }
         }
 
-        let is_start_bracket = start.content == "{";
-        let is_end_bracket = tokens.last().map(|x| x.content == "}").unwrap_or(false);
+        // Only strip the braces if they enclose the whole argument, so that `[{a}{b}]` keeps
+        // both of its groups
+        let mut depth: usize = 0;
+        let is_single_group = start.content == "{"
+            && tokens.last().map(|x| x.content == "}").unwrap_or(false)
+            && tokens[..tokens.len() - 1].iter().all(|tok| {
+                if tok.content == "{" {
+                    depth += 1;
+                } else if tok.content == "}" {
+                    depth -= 1;
+                }
+                depth > 0
+            });
 
-        if is_start_bracket && is_end_bracket {
+        if is_single_group {
             tokens.pop();
             tokens.remove(0);
         }
@@ -356,7 +384,7 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
             }
         }
 
-        let mut args: [Vec<Token>; N] = std::array::from_fn(|_| Vec::new());
+        let mut args: [Vec<Token>; N] = core::array::from_fn(|_| Vec::new());
         for i in 0..N {
             let delims = if !delimiters.is_empty() {
                 delimiters[i + 1].as_slice()
@@ -370,6 +398,28 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
         Ok(args)
     }
 
+    /// Count a step against [`ParserConfig::max_steps`], and check the
+    /// [`ParserConfig::deadline`]
+    fn step(&mut self) -> Result<(), ParseError> {
+        self.step_count += 1;
+        if self.conf.max_steps.is_some_and(|max| self.step_count > max) {
+            return Err(ParseError::TooManySteps);
+        }
+
+        // Getting the time is slow next to a step, so it is only done every so often
+        #[cfg(feature = "std")]
+        if self.step_count % DEADLINE_CHECK_INTERVAL == 1
+            && self
+                .conf
+                .deadline
+                .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+        {
+            return Err(ParseError::DeadlineExceeded);
+        }
+
+        Ok(())
+    }
+
     /// Expand the next token only once if possible.
     ///
     /// If the token is expanded, the resulting tokens will be pushed onto the stack in reverse
@@ -386,6 +436,8 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
         &mut self,
         expandable_only: bool,
     ) -> Result<TokenList<'_>, ParseError> {
+        self.step()?;
+
         let top_token = self.pop_token()?;
         let _macro_name = top_token.content.clone();
 
@@ -422,6 +474,10 @@ impl<'a, 'f> MacroExpander<'a, 'f> {
         }
 
         let mut tokens = expansion.tokens;
+        // The expansion stands in for the macro, so it is located where the macro was
+        for token in tokens.iter_mut() {
+            token.loc = top_token.loc.clone();
+        }
 
         let num_args = expansion.num_args as usize;
         let delimiters = expansion.delimiters.as_deref().unwrap_or(&[]);
@@ -1,6 +1,12 @@
+use core::sync::atomic::Ordering;
+
+use hashbrown::HashMap;
+
+use crate::prelude::*;
 use crate::{
     expander::Mode,
-    font_metrics_data,
+    font_metrics_data::{self, MetricData},
+    sync::{AtomicU64, Lazy, RwLock},
     unicode_scripts::supported_codepoint,
     util::{char_code_for, find_assoc_data},
 };
@@ -283,7 +289,7 @@ pub struct CharacterMetrics {
     pub width: f64,
 }
 impl CharacterMetrics {
-    fn from_metric(metric: [f64; 5]) -> Self {
+    fn from_metric(metric: MetricData) -> Self {
         Self {
             depth: metric[0],
             height: metric[1],
@@ -292,45 +298,91 @@ impl CharacterMetrics {
             width: metric[4],
         }
     }
+
+    fn to_metric(self) -> MetricData {
+        [self.depth, self.height, self.italic, self.skew, self.width]
+    }
 }
 
-// TODO: set_font_metrics
+/// Counts the calls to [`set_font_metrics`], so that lookups can skip locking
+/// [`CUSTOM_METRICS`] when there are none, and so that cached renders can tell when the metrics
+/// they used have changed
+static METRICS_VERSION: AtomicU64 = AtomicU64::new(0);
+/// The metrics set with [`set_font_metrics`], by font and then character.  
+/// Unlike the built in tables, which follow KaTeX in using the first UTF-16 code unit, these are
+/// keyed by the whole character so that characters outside the BMP don't share an entry.
+static CUSTOM_METRICS: Lazy<RwLock<HashMap<String, HashMap<char, MetricData>>>> =
+    Lazy::new(Default::default);
+
+/// Set the metrics of characters in a font, which is either one of the KaTeX fonts, like
+/// `"Main-Regular"`, or a new font.  
+/// These are used over the built in metrics, so this can add characters that the KaTeX fonts
+/// don't have metrics for or change the metrics of existing characters.
+pub fn set_font_metrics(
+    font: &str,
+    metrics: impl IntoIterator<Item = (char, CharacterMetrics)>,
+) {
+    let mut custom = CUSTOM_METRICS.write();
+    let font_metrics = custom.entry(font.to_string()).or_default();
+    for (ch, metric) in metrics {
+        font_metrics.insert(ch, metric.to_metric());
+    }
 
-/// This function is a convenience function for lookuping up information in the [`METRIC_MAP`]
-/// table.  
+    METRICS_VERSION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// The number of times the metrics have been changed with [`set_font_metrics`]
+pub(crate) fn font_metrics_version() -> u64 {
+    METRICS_VERSION.load(Ordering::Acquire)
+}
+
+/// Whether there are any metrics for the font, built in or set with [`set_font_metrics`]
+fn has_font_metrics(font: &str) -> bool {
+    font_metrics_data::get_metric(font).is_some()
+        || (font_metrics_version() != 0
+            && CUSTOM_METRICS.read().contains_key(font))
+}
+
+/// Get the metrics of the character in the font, preferring those set with
+/// [`set_font_metrics`]
+pub(crate) fn get_metric_data(font: &str, ch: char) -> Option<MetricData> {
+    if font_metrics_version() != 0 {
+        let custom = CUSTOM_METRICS.read();
+        if let Some(metric) = custom.get(font).and_then(|metrics| metrics.get(&ch)) {
+            return Some(*metric);
+        }
+    }
+
+    font_metrics_data::get_metric(font)?
+        .get(&char_code_for(ch))
+        .copied()
+}
+
+/// This function is a convenience function for lookuping up information in the font metric
+/// tables.  
 /// Note that you typical callers in KaTeX only pass in the first character of the string.
 pub(crate) fn get_character_metrics(
     character: char,
     font: &str,
     mode: Mode,
 ) -> Option<CharacterMetrics> {
-    let metrics = if let Some(metrics) = font_metrics_data::get_metric(font) {
-        metrics
-    } else {
+    if !has_font_metrics(font) {
         panic!("Font metrics not found for font: {font:?}");
-    };
-
-    let ch = char_code_for(character);
+    }
 
     // TODO: can we flatten this if/else nesting?
-    if let Some(metric) = find_assoc_data(metrics, ch) {
-        Some(CharacterMetrics::from_metric(*metric))
+    if let Some(metric) = get_metric_data(font, character) {
+        Some(CharacterMetrics::from_metric(metric))
     } else {
         let ch = find_assoc_data(EXTRA_CHARACTER_MAP, character);
-        if let Some(ch) = ch {
-            let ch = char_code_for(*ch);
-            if let Some(metric) = find_assoc_data(metrics, ch) {
-                Some(CharacterMetrics::from_metric(*metric))
+        if let Some(&ch) = ch {
+            if let Some(metric) = get_metric_data(font, ch) {
+                Some(CharacterMetrics::from_metric(metric))
             } else if mode == Mode::Text {
-                if supported_codepoint(ch) {
+                if supported_codepoint(char_code_for(ch)) {
                     // We default to using 'M' for characters we don't have metrics for.
-                    let ch = char_code_for('M'); // 77
-                    if let Some(metric) = find_assoc_data(metrics, ch) {
-                        Some(CharacterMetrics::from_metric(*metric))
-                    } else {
-                        // TODO: warn?
-                        None
-                    }
+                    // TODO: warn if there are no metrics?
+                    get_metric_data(font, 'M').map(CharacterMetrics::from_metric)
                 } else {
                     None
                 }
@@ -343,6 +395,34 @@ pub(crate) fn get_character_metrics(
     }
 }
 
+/// The size of lowercase letters in small caps relative to the uppercase letters they are drawn
+/// with. This matches the scale browsers use when synthesizing small caps.
+pub(crate) const SMALL_CAPS_SCALE: f64 = 0.7;
+
+/// Get the metrics of a character set in small caps.  
+/// The KaTeX fonts have no small caps, so lowercase letters are drawn as scaled down uppercase
+/// letters and their metrics are synthesized from those.
+pub(crate) fn get_small_caps_metrics(
+    character: char,
+    font: &str,
+    mode: Mode,
+) -> Option<CharacterMetrics> {
+    let mut upper = character.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) if character.is_lowercase() => {
+            let metrics = get_character_metrics(upper, font, mode)?;
+            Some(CharacterMetrics {
+                depth: metrics.depth * SMALL_CAPS_SCALE,
+                height: metrics.height * SMALL_CAPS_SCALE,
+                italic: metrics.italic * SMALL_CAPS_SCALE,
+                skew: metrics.skew * SMALL_CAPS_SCALE,
+                width: metrics.width * SMALL_CAPS_SCALE,
+            })
+        }
+        _ => get_character_metrics(character, font, mode),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FontSizeIndex {
     Zero,
@@ -359,7 +439,6 @@ impl FontSizeIndex {
     }
 }
 
-// TODO: We don't do a cache like katex does. Is that actually needed? How often is this called?
 fn font_metrics_by_size_index(size_index: FontSizeIndex) -> FontMetrics {
     // TODO: make sure this gets optimized out?
     let css_em_per_mu = find_assoc_data(SIGMAS_AND_XIS, MetricName::Quad).unwrap();
@@ -378,7 +457,16 @@ fn font_metrics_by_size_index(size_index: FontSizeIndex) -> FontMetrics {
     font_metrics
 }
 
-pub(crate) fn get_global_metrics(size: usize) -> FontMetrics {
+/// The metrics for each [`FontSizeIndex`], shared by every [`Options`](crate::Options)
+static GLOBAL_METRICS: Lazy<[FontMetrics; 3]> = Lazy::new(|| {
+    [
+        font_metrics_by_size_index(FontSizeIndex::Zero),
+        font_metrics_by_size_index(FontSizeIndex::One),
+        font_metrics_by_size_index(FontSizeIndex::Two),
+    ]
+});
+
+pub(crate) fn get_global_metrics(size: usize) -> &'static FontMetrics {
     let size_index = if size >= 5 {
         FontSizeIndex::Zero
     } else if size >= 3 {
@@ -387,5 +475,81 @@ pub(crate) fn get_global_metrics(size: usize) -> FontMetrics {
         FontSizeIndex::Two
     };
 
-    font_metrics_by_size_index(size_index)
+    &GLOBAL_METRICS[size_index.as_usize()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_character_metrics() {
+        let x = get_character_metrics('x', "Main-Regular", Mode::Math).unwrap();
+        assert_eq!(x.height, 0.43056);
+        assert_eq!(x.width, 0.52778);
+
+        // Cyrillic falls back to the similar latin letter
+        let a = get_character_metrics('а', "Main-Regular", Mode::Math).unwrap();
+        assert_eq!(a, get_character_metrics('a', "Main-Regular", Mode::Math).unwrap());
+
+        assert_eq!(get_character_metrics('\u{2603}', "Main-Regular", Mode::Math), None);
+    }
+
+    #[test]
+    fn test_set_font_metrics() {
+        let snowman = CharacterMetrics {
+            depth: 0.1,
+            height: 0.7,
+            italic: 0.0,
+            skew: 0.0,
+            width: 0.8,
+        };
+        set_font_metrics("Test-Regular", [('\u{2603}', snowman)]);
+        assert_eq!(
+            get_character_metrics('\u{2603}', "Test-Regular", Mode::Math),
+            Some(snowman)
+        );
+        assert_eq!(get_character_metrics('x', "Test-Regular", Mode::Math), None);
+
+        // Metrics for a built in font extend it
+        let wide_x = CharacterMetrics {
+            width: 2.0,
+            ..snowman
+        };
+        set_font_metrics("Size4-Regular", [('x', wide_x)]);
+        assert_eq!(
+            get_character_metrics('x', "Size4-Regular", Mode::Math),
+            Some(wide_x)
+        );
+        assert!(get_character_metrics('(', "Size4-Regular", Mode::Math).is_some());
+    }
+
+    #[test]
+    fn test_set_font_metrics_astral() {
+        // Both of these start with the same UTF-16 code unit, 0xD835
+        let bold_a = CharacterMetrics {
+            depth: 0.0,
+            height: 0.7,
+            italic: 0.0,
+            skew: 0.0,
+            width: 0.9,
+        };
+        let bold_b = CharacterMetrics {
+            width: 0.5,
+            ..bold_a
+        };
+        set_font_metrics("Astral-Regular", [('\u{1D400}', bold_a), ('\u{1D401}', bold_b)]);
+        assert_eq!(
+            get_character_metrics('\u{1D400}', "Astral-Regular", Mode::Math),
+            Some(bold_a)
+        );
+        assert_eq!(
+            get_character_metrics('\u{1D401}', "Astral-Regular", Mode::Math),
+            Some(bold_b)
+        );
+        assert_eq!(
+            get_character_metrics('\u{1D402}', "Astral-Regular", Mode::Math),
+            None
+        );
+    }
 }
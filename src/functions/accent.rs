@@ -1,16 +1,17 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
-use once_cell::sync::Lazy;
-use regex::Regex;
 
+use crate::prelude::*;
 use crate::{
     expander::Mode,
     parse_node::{AccentNode, NodeInfo, ParseNode, ParseNodeType},
     parser::ParseError,
     util::ArgType,
-    dom_tree::{HtmlNode, WithHtmlDomNode}, // Added this line
 };
 
+#[cfg(feature = "html")]
+use crate::dom_tree::{HtmlNode, WithHtmlDomNode};
+
 use super::{normalize_argument, FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
@@ -98,12 +99,19 @@ fn add_accents(fns: &mut Functions) {
     fns.insert_for_all_str(ACCENT_NAMES.iter().copied(), accent);
 }
 
-static NON_STRETCHY_ACCENT_REGEX: Lazy<Regex> = Lazy::new(|| {
-    const REGEX_TEXT: &str =
-    "\\\\acute|\\\\grave|\\\\ddot|\\\\tilde|\\\\bar|\\\\breve|\\\\check|\\\\hat|\\\\vec|\\\\dot|\\\\mathring";
-
-    Regex::new(REGEX_TEXT).unwrap()
-});
+const NON_STRETCHY_ACCENTS: &[&str] = &[
+    "\\acute",
+    "\\grave",
+    "\\ddot",
+    "\\tilde",
+    "\\bar",
+    "\\breve",
+    "\\check",
+    "\\hat",
+    "\\vec",
+    "\\dot",
+    "\\mathring",
+];
 
 fn accent_handler(
     ctx: FunctionContext,
@@ -112,7 +120,9 @@ fn accent_handler(
 ) -> Result<ParseNode, ParseError> {
     let base = normalize_argument(args[0].clone());
 
-    let is_stretchy = !NON_STRETCHY_ACCENT_REGEX.is_match(&ctx.func_name);
+    let is_stretchy = !NON_STRETCHY_ACCENTS
+        .iter()
+        .any(|accent| ctx.func_name.contains(accent));
     let is_shifty = !is_stretchy
         || ctx.func_name == "\\widehat"
         || ctx.func_name == "\\widetilde"
@@ -209,7 +219,7 @@ fn add_text_mode_accents(fns: &mut Functions) {
 
 #[cfg(feature = "mathml")]
 fn accent_mathml_builder(group: &ParseNode, options: &crate::Options) -> crate::mathml_tree::MathmlNode {
-    use crate::{mathml, mathml_tree::{MathNode, MathNodeType, MathmlNode, TextNode}, stretchy, tree::ClassList};
+    use crate::{mathml, mathml_tree::{MathNode, MathNodeType, MathmlNode}, stretchy, tree::ClassList};
 
     let ParseNode::Accent(group) = group else {
         panic!("Expected Accent node");
@@ -256,4 +266,3 @@ fn text_mode_accent_handler<'a, 'p, 'i, 'f>(
     }))
 }
 
-
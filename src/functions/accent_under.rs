@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::{
     parse_node::{AccentUnderNode, NodeInfo, ParseNode, ParseNodeType},
     parser::ParseError,
@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{NodeInfo, ParseNode, ParseNodeType, XArrowNode};
 use crate::parser::ParseError;
 
@@ -76,7 +77,7 @@ fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
 
     // Build the argument groups in the appropriate style
     // Ref: amsmath.dtx: \hbox{$\scriptstyle\mkern#3mu{#6}\mkern#4mu$}%
-    let new_options = options.having_style(style.sup()).unwrap_or_else(|| options.clone());
+    let new_options = options.having_style(style.sup()).unwrap_or(*options);
     let upper_group = html::build_group(Some(&group.body), &new_options, None);
 
     // Determine arrow prefix for class naming
@@ -92,7 +93,7 @@ fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
 
     // Build lower group if present
     let lower_group = if let Some(below) = &group.below {
-        let new_options_sub = options.having_style(style.sub()).unwrap_or_else(|| options.clone());
+        let new_options_sub = options.having_style(style.sub()).unwrap_or(*options);
         let lower = html::build_group(Some(below), &new_options_sub, None);
         Some(make_span::<HtmlNode>(
             vec![format!("{}-arrow-pad", arrow_prefix)],
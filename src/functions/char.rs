@@ -1,5 +1,7 @@
-use std::{borrow::Cow, char::REPLACEMENT_CHARACTER, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
+use core::char::REPLACEMENT_CHARACTER;
 
+use crate::prelude::*;
 use crate::parse_node::{NodeInfo, ParseNode, ParseNodeType, TextOrdNode};
 use crate::parser::ParseError;
 
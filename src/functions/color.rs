@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::{
     macr::MacroReplace,
     parse_node::{ColorNode, NodeInfo, ParseNode, ParseNodeType},
@@ -85,8 +86,7 @@ fn color_handler(
     // Parse out the implicit body that should be colored
     let body = ctx
         .parser
-        .dispatch_parse_expression(true, ctx.break_on_token_text)
-        .unwrap();
+        .dispatch_parse_expression(true, ctx.break_on_token_text)?;
 
     Ok(ParseNode::Color(ColorNode {
         color,
@@ -1,19 +1,17 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::{
     expander::Mode,
     parse_node::{CrNode, NodeInfo, ParseNode, ParseNodeType, SpacingNode},
     parser::ParseError,
-    util::ArgType,
 };
 
 use super::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
     let cr = Arc::new(FunctionSpec {
-        prop: FunctionPropSpec::new_num_opt_args(ParseNodeType::Cr, 0, 1)
-            .with_allowed_in_text(true)
-            .with_arg_types(&[ArgType::Size] as &[ArgType]),
+        prop: FunctionPropSpec::new_num_args(ParseNodeType::Cr, 0).with_allowed_in_text(true),
         handler: Box::new(cr_handler),
         // TODO:
         #[cfg(feature = "html")]
@@ -29,22 +27,22 @@ pub fn add_functions(fns: &mut Functions) {
 fn cr_handler(
     ctx: FunctionContext,
     _args: &[ParseNode],
-    opt_args: &[Option<ParseNode>],
+    _opt_args: &[Option<ParseNode>],
 ) -> Result<ParseNode, ParseError> {
+    // The size is only read if the `[` comes directly after, so `\\ [x]` is a newline followed
+    // by `[x]`
+    let size = if ctx.parser.gullet.future()?.content == "[" {
+        ctx.parser.parse_size_group(true)?.map(|size| size.value)
+    } else {
+        None
+    };
+
     if ctx.parser.mode() == Mode::Text {
         return Ok(ParseNode::Spacing(SpacingNode {
             text: "\u{a0}".to_string(),
             info: NodeInfo::new_mode(Mode::Text),
         }));
     }
-    let size = opt_args[0].as_ref().map(|arg| {
-        if let ParseNode::Size(size) = arg {
-            size.value.clone()
-        } else {
-            // TODO: don't panic
-            panic!()
-        }
-    });
     // TODO: use strict behavior
     let new_line = !ctx.parser.conf.display_mode;
 
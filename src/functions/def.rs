@@ -1,8 +1,7 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
-use once_cell::sync::Lazy;
-use regex::Regex;
 
+use crate::prelude::*;
 use crate::{
     lexer::Token,
     macr::{MacroExpansion, MacroReplace},
@@ -17,24 +16,21 @@ pub fn add_functions(fns: &mut Functions) {
         prop: FunctionPropSpec::new_num_args(ParseNodeType::Internal, 0).with_allowed_in_text(true),
         handler: Box::new(
             |ctx: FunctionContext, _args: &[ParseNode], _opt_args: &[Option<ParseNode>]| {
-                ctx.parser.consume_spaces().unwrap();
-                let token = ctx.parser.fetch_mut().unwrap();
-
-                if let Some(map) = get_global_map(&token.content) {
-                    if ctx.func_name == "\\global" || ctx.func_name == "\\\\globallong" {
-                        token.content = Cow::Borrowed(map);
-                    }
-
-                    let func = ctx.parser.parse_function(None, None).unwrap();
-                    if let Some(ParseNode::Internal(internal)) = func {
-                        return Ok(ParseNode::Internal(internal));
-                    } else {
-                        panic!()
-                    }
+                ctx.parser.consume_spaces()?;
+                let token = ctx.parser.fetch_mut()?;
+
+                let Some(map) = get_global_map(&token.content) else {
+                    return Err(ParseError::InvalidMacroPrefix);
+                };
+                if ctx.func_name == "\\global" || ctx.func_name == "\\\\globallong" {
+                    token.content = Cow::Borrowed(map);
                 }
 
-                // TODO: don't panic
-                panic!()
+                // A chain of prefixes recurses, so it nests like a group does
+                match ctx.parser.nested(|parser| parser.parse_function(None, None))? {
+                    Some(ParseNode::Internal(internal)) => Ok(ParseNode::Internal(internal)),
+                    _ => Err(ParseError::InvalidMacroPrefix),
+                }
             },
         ),
         // TODO:
@@ -60,54 +56,49 @@ pub fn add_functions(fns: &mut Functions) {
             .with_allowed_in_text(true)
             .with_primitive(true),
         handler: Box::new(|ctx, _, _| {
-            let token = ctx.parser.gullet.pop_token().unwrap();
+            let token = ctx.parser.gullet.pop_token()?;
 
-            if CONTROL_SEQUENCE_REGEX.is_match(&token.content) {
-                panic!();
+            if is_control_sequence(&token.content) {
+                return Err(ParseError::ExpectedControlSequence);
             }
 
             let mut num_args = 0;
             let mut insert = None;
             let mut delimiters = vec![vec![]];
 
-            while ctx.parser.gullet.future().unwrap().content != "{" {
-                let tok = ctx.parser.gullet.pop_token().unwrap();
+            while ctx.parser.gullet.future()?.content != "{" {
+                let tok = ctx.parser.gullet.pop_token()?;
                 if tok.content == "#" {
                     // If the very last character of the <parameter text> is #, so that
                     // this # is immediately followed by {, TeX will behave as if the {
                     // had been inserted at the right end of both the parameter text and the
                     // replacement text
-                    if ctx.parser.gullet.future().unwrap().content == "{" {
-                        insert = Some(ctx.parser.gullet.future().unwrap().clone().into_owned());
+                    if ctx.parser.gullet.future()?.content == "{" {
+                        insert = Some(ctx.parser.gullet.future()?.clone().into_owned());
                         delimiters[num_args].push(Cow::Borrowed("{"));
                         break;
                     }
 
                     // A parameter ,the first appearance of # must be followed by 1
                     // the next by 2, and so on; up to nine #'s are allowed
-                    let tok = ctx.parser.gullet.pop_token().unwrap();
-                    let txt = &tok.content;
-                    // If it is not only a digit of 1-9
-                    if !(txt.len() == 1 && txt.chars().next().unwrap().is_digit(10) && txt != "0") {
-                        panic!();
-                    }
-
-                    let value = txt.parse::<u8>().unwrap();
-                    if value as usize != num_args + 1 {
-                        panic!();
+                    let tok = ctx.parser.gullet.pop_token()?;
+                    // It must be a single digit, the next of 1-9
+                    match tok.content.parse::<u8>() {
+                        Ok(value) if tok.content.len() == 1 && value as usize == num_args + 1 => {}
+                        _ => return Err(ParseError::InvalidArgumentNumber),
                     }
 
                     num_args += 1;
                     delimiters.push(Vec::new());
                 } else if tok.content == "EOF" {
-                    panic!();
+                    return Err(ParseError::UnexpectedEOF);
                 } else {
                     delimiters[num_args].push(tok.content.into_owned().into());
                 }
             }
 
             // replacement text, enclosed in '{' and '}' and properly nested
-            let arg = ctx.parser.gullet.consume_arg().unwrap();
+            let arg = ctx.parser.gullet.consume_arg()?;
             let mut tokens = arg.tokens;
 
             if let Some(insert) = insert {
@@ -115,7 +106,7 @@ pub fn add_functions(fns: &mut Functions) {
             }
 
             let tokens = if ctx.func_name == "\\edef" || ctx.func_name == "\\xdef" {
-                let mut tokens = ctx.parser.gullet.expand_tokens(tokens.into_iter()).unwrap();
+                let mut tokens = ctx.parser.gullet.expand_tokens(tokens.into_iter())?;
                 tokens.reverse();
                 tokens
             } else {
@@ -158,15 +149,15 @@ pub fn add_functions(fns: &mut Functions) {
             .with_allowed_in_text(true)
             .with_primitive(true),
         handler: Box::new(|ctx, _, _| {
-            let token = ctx.parser.gullet.pop_token().unwrap();
+            let token = ctx.parser.gullet.pop_token()?;
             let name = token.content;
-            if CONTROL_SEQUENCE_REGEX.is_match(&name) {
-                panic!();
+            if is_control_sequence(&name) {
+                return Err(ParseError::ExpectedControlSequence);
             }
 
-            ctx.parser.gullet.consume_spaces().unwrap();
+            ctx.parser.gullet.consume_spaces()?;
 
-            let mut tok = get_rhs(ctx.parser).unwrap();
+            let mut tok = get_rhs(ctx.parser)?;
             let global = ctx.func_name == "\\\\globallet";
             let_command(ctx.parser, name.into_owned(), &mut tok, global);
 
@@ -189,15 +180,15 @@ pub fn add_functions(fns: &mut Functions) {
             .with_allowed_in_text(true)
             .with_primitive(true),
         handler: Box::new(|ctx, _, _| {
-            let token = ctx.parser.gullet.pop_token().unwrap();
+            let token = ctx.parser.gullet.pop_token()?;
             let name = token.content;
-            if CONTROL_SEQUENCE_REGEX.is_match(&name) {
-                panic!();
+            if is_control_sequence(&name) {
+                return Err(ParseError::ExpectedControlSequence);
             }
 
-            let middle = ctx.parser.gullet.pop_token().unwrap();
+            let middle = ctx.parser.gullet.pop_token()?;
 
-            let mut tok = ctx.parser.gullet.pop_token().unwrap();
+            let mut tok = ctx.parser.gullet.pop_token()?;
 
             let global = ctx.func_name == "\\\\globalfuture";
             let_command(ctx.parser, name.into_owned(), &mut tok, global);
@@ -235,8 +226,9 @@ fn get_global_map(text: &str) -> Option<&'static str> {
     GLOBAL_MAP.iter().find(|(l, _)| *l == text).map(|(_, r)| *r)
 }
 
-static CONTROL_SEQUENCE_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new("^(?:[\\{}$&#^_]|EOF)$").unwrap());
+fn is_control_sequence(text: &str) -> bool {
+    text == "EOF" || matches!(text, "{" | "}" | "$" | "&" | "#" | "^" | "_")
+}
 
 fn get_rhs<'a, 'f>(parser: &mut Parser<'a, 'f>) -> Result<Token<'a>, ParseError> {
     let mut tok = parser.gullet.pop_token()?;
@@ -1,14 +1,15 @@
-use std::borrow::Cow;
-use std::collections::HashMap;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
-use once_cell::sync::Lazy;
+use hashbrown::HashMap;
 
+use crate::prelude::*;
 use crate::parse_node::{
     DelimSize, DelimSizingNode, LeftRightNode, LeftRightRightNode, MClass, MiddleNode, NodeInfo,
     ParseNode, ParseNodeType,
 };
 use crate::parser::ParseError;
+use crate::sync::Lazy;
 
 use super::{FunctionPropSpec, FunctionSpec, Functions};
 
@@ -114,8 +115,7 @@ pub fn add_functions(fns: &mut Functions) {
     // \right
     let right = Arc::new(FunctionSpec {
         prop: FunctionPropSpec::new_num_args(ParseNodeType::LeftRightRight, 1)
-            .with_primitive(true)
-            .with_allowed_in_argument(true),
+            .with_primitive(true),
         handler: Box::new(|ctx, args, _| {
             let delim = check_delimiter(&args[0])?;
             Ok(ParseNode::LeftRightRight(LeftRightRightNode {
@@ -1,12 +1,21 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{EncloseNode, NodeInfo, ParseNode, ParseNodeType};
 use crate::parser::ParseError;
 use crate::util::ArgType;
 
 use super::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
+#[cfg(feature = "mathml")]
+use crate::{
+    mathml,
+    mathml_tree::{MathNode, MathNodeType, MathmlNode},
+    tree::ClassList,
+    Options,
+};
+
 pub fn add_functions(fns: &mut Functions) {
     // \colorbox
     let colorbox = Arc::new(FunctionSpec {
@@ -17,7 +26,7 @@ pub fn add_functions(fns: &mut Functions) {
         #[cfg(feature = "html")]
         html_builder: None,
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(enclose_mathml_builder)),
     });
     fns.insert(Cow::Borrowed("\\colorbox"), colorbox);
 
@@ -33,7 +42,7 @@ pub fn add_functions(fns: &mut Functions) {
         #[cfg(feature = "html")]
         html_builder: None,
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(enclose_mathml_builder)),
     });
     fns.insert(Cow::Borrowed("\\fcolorbox"), fcolorbox);
 
@@ -46,7 +55,7 @@ pub fn add_functions(fns: &mut Functions) {
         #[cfg(feature = "html")]
         html_builder: None,
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(enclose_mathml_builder)),
     });
     fns.insert(Cow::Borrowed("\\fbox"), fbox);
 
@@ -58,7 +67,7 @@ pub fn add_functions(fns: &mut Functions) {
         #[cfg(feature = "html")]
         html_builder: None,
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(enclose_mathml_builder)),
     });
     fns.insert(Cow::Borrowed("\\boxed"), boxed);
 
@@ -69,7 +78,7 @@ pub fn add_functions(fns: &mut Functions) {
         #[cfg(feature = "html")]
         html_builder: None,
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(enclose_mathml_builder)),
     });
     fns.insert(Cow::Borrowed("\\cancel"), cancel.clone());
     fns.insert(Cow::Borrowed("\\bcancel"), cancel.clone());
@@ -93,7 +102,7 @@ pub fn add_functions(fns: &mut Functions) {
         #[cfg(feature = "html")]
         html_builder: None,
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(enclose_mathml_builder)),
     });
     fns.insert(Cow::Borrowed("\\angl"), angl);
 }
@@ -181,3 +190,53 @@ fn cancel_handler(
         info: NodeInfo::new_mode(ctx.parser.mode()),
     }))
 }
+
+#[cfg(feature = "mathml")]
+fn enclose_mathml_builder(group: &ParseNode, options: &Options) -> MathmlNode {
+    let ParseNode::Enclose(group) = group else {
+        unreachable!()
+    };
+
+    let typ = if group.label.contains("colorbox") {
+        MathNodeType::MPadded
+    } else {
+        MathNodeType::MEnclose
+    };
+    let mut node: MathNode<MathmlNode> = MathNode::new(
+        typ,
+        vec![mathml::build_group(Some(&group.body), options)],
+        ClassList::new(),
+    );
+
+    match group.label.as_str() {
+        "\\cancel" => node.set_attribute("notation", "updiagonalstrike"),
+        "\\bcancel" => node.set_attribute("notation", "downdiagonalstrike"),
+        "\\phase" => node.set_attribute("notation", "phasorangle"),
+        "\\sout" => node.set_attribute("notation", "horizontalstrike"),
+        "\\fbox" | "\\boxed" => node.set_attribute("notation", "box"),
+        "\\angl" => node.set_attribute("notation", "actuarial"),
+        "\\fcolorbox" | "\\colorbox" => {
+            let metrics = options.font_metrics();
+            let fboxsep = metrics.fboxsep * metrics.pt_per_em;
+            node.set_attribute("width", format!("+{}pt", 2.0 * fboxsep));
+            node.set_attribute("height", format!("+{}pt", 2.0 * fboxsep));
+            node.set_attribute("lspace", format!("{}pt", fboxsep));
+            node.set_attribute("voffset", format!("{}pt", fboxsep));
+            if let Some(border_color) = &group.border_color {
+                let thickness = metrics.fboxrule.max(options.min_rule_thickness.0);
+                node.set_attribute(
+                    "style",
+                    format!("border: {}em solid {}", thickness, border_color.to_string()),
+                );
+            }
+        }
+        "\\xcancel" => node.set_attribute("notation", "updiagonalstrike downdiagonalstrike"),
+        _ => {}
+    }
+
+    if let Some(background_color) = &group.background_color {
+        node.set_attribute("mathbackground", background_color.to_string());
+    }
+
+    node.into()
+}
@@ -1,14 +1,17 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
 use crate::{
-    html,
     parse_node::{FontNode, MClassNode, NodeInfo, OrdGroupNode, ParseNode, ParseNodeType},
-    parser::ParseError,
-    util, Options,
+    util,
 };
 
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::Options;
+
 #[cfg(feature = "html")]
-use crate::dom_tree::HtmlNode;
+use crate::{dom_tree::HtmlNode, html};
+
 #[cfg(feature = "mathml")]
 use crate::mathml_tree::MathmlNode;
 
@@ -94,8 +97,7 @@ pub fn add_functions(fns: &mut Functions) {
         handler: Box::new(|ctx, _args, _opt_args| {
             let body = ctx
                 .parser
-                .dispatch_parse_expression(true, ctx.break_on_token_text)
-                .unwrap();
+                .dispatch_parse_expression(true, ctx.break_on_token_text)?;
             let style = format!("math{}", &ctx.func_name[1..]);
 
             Ok(ParseNode::Font(FontNode {
@@ -126,7 +128,7 @@ fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
         panic!();
     };
 
-    let new_options = options.clone().with_font(group.font.clone());
+    let new_options = options.with_font(group.font.clone());
 
     html::build_group(Some(&group.body), &new_options, None)
 }
@@ -139,7 +141,7 @@ fn mathml_builder(group: &ParseNode, options: &Options) -> MathmlNode {
         panic!();
     };
 
-    let new_options = options.clone().with_font(group.font.clone());
+    let new_options = options.with_font(group.font.clone());
 
     mathml::build_group(Some(&group.body), &new_options)
 }
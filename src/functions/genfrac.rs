@@ -1,25 +1,33 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
-#[cfg(feature = "html")]
-use crate::dom_tree::HtmlNode;
-#[cfg(feature = "mathml")]
-use crate::mathml_tree::MathmlNode;
+use crate::prelude::*;
 use crate::{
-    build_common::{make_line_span, make_span, make_v_list, VListElemShift, VListParam},
-    delimiter,
-    dom_tree::CssStyle,
     expander::Mode,
-    html,
     lexer::Token,
     parse_node::{GenFracNode, InfixNode, NodeInfo, ParseNode, ParseNodeType},
     parser::ParseError,
-    style::{StyleId, DISPLAY_STYLE, SCRIPT_SCRIPT_STYLE, SCRIPT_STYLE, TEXT_STYLE},
     symbols::Atom,
-    unit::calculate_size,
     util::{ArgType, Style, StyleAuto},
+};
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::{
+    style::{StyleId, DISPLAY_STYLE, SCRIPT_SCRIPT_STYLE, SCRIPT_STYLE, TEXT_STYLE},
+    unit::calculate_size,
     Options,
 };
 
+#[cfg(feature = "html")]
+use crate::{
+    build_common::{make_line_span, make_span, make_v_list, VListElemShift, VListParam},
+    delimiter,
+    dom_tree::{CssStyle, HtmlNode},
+    html,
+};
+
+#[cfg(feature = "mathml")]
+use crate::mathml_tree::MathmlNode;
+
 use super::{normalize_argument, FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
@@ -366,6 +374,7 @@ fn genfrac_abovefrac_handler(
     }))
 }
 
+#[cfg(any(feature = "html", feature = "mathml"))]
 fn adjust_style(size: &StyleAuto, original_style: StyleId) -> StyleId {
     // Figure out what style this fraction should be in based on the function used
     match size {
@@ -1,8 +1,8 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{HBoxNode, NodeInfo, ParseNode, ParseNodeType};
-use crate::parser::ParseError;
 use crate::util::ArgType;
 
 use super::{FunctionPropSpec, FunctionSpec, Functions};
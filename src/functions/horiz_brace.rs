@@ -1,8 +1,17 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{HorizBraceNode, NodeInfo, ParseNode, ParseNodeType};
 use crate::parser::ParseError;
+use crate::unit::calculate_size;
+use crate::util::ArgType;
+use crate::Options;
+
+#[cfg(feature = "html")]
+use crate::dom_tree::HtmlNode;
+#[cfg(feature = "mathml")]
+use crate::mathml_tree::MathmlNode;
 
 use super::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
@@ -11,97 +20,274 @@ pub fn add_functions(fns: &mut Functions) {
         prop: FunctionPropSpec::new_num_args(ParseNodeType::HorizBrace, 1),
         handler: Box::new(horiz_brace_handler),
         #[cfg(feature = "html")]
-        html_builder: Some(Box::new(|group, options| {
-            use crate::{
-                build_common::{make_span, make_v_list, VListElem, VListKern, VListParam, VListShiftChild},
-                dom_tree::{CssStyle, HtmlNode, WithHtmlDomNode},
-                html,
-                stretchy,
-                style::DISPLAY_STYLE,
-            };
-
-            let ParseNode::HorizBrace(group) = group else {
-                panic!("Expected HorizBrace node");
-            };
-
-            // Build the base group
-            let base_options = options.having_base_style(Some(DISPLAY_STYLE)).unwrap_or_else(|| options.clone());
-            let body = html::build_group(Some(&group.base), &base_options, None);
-
-            // Create the stretchy brace SVG
-            let brace_body = stretchy::svg_span(&group.label, options);
-
-            // Generate the vlist with content and brace
-            let vlist = if group.is_over {
-                make_v_list(
-                    VListParam::FirstBaseLine {
-                        children: vec![
-                            VListShiftChild::Elem(VListElem::new(body)),
-                            VListShiftChild::Kern(VListKern(0.1)),
-                            VListShiftChild::Elem(VListElem::new_with_wrapper_classes(brace_body, vec!["svg-align".to_string()])),
-                        ],
-                    },
-                    options,
-                )
-            } else {
-                let body_depth = body.node().depth;
-                let brace_height = brace_body.node().height;
-                make_v_list(
-                    VListParam::Bottom {
-                        amount: body_depth + 0.1 + brace_height,
-                        children: vec![
-                            VListShiftChild::Elem(VListElem::new_with_wrapper_classes(brace_body, vec!["svg-align".to_string()])),
-                            VListShiftChild::Kern(VListKern(0.1)),
-                            VListShiftChild::Elem(VListElem::new(body)),
-                        ],
-                    },
-                    options,
-                )
-            };
-
-            let mord_class = if group.is_over { "mover" } else { "munder" };
-            make_span::<HtmlNode>(
-                vec!["mord".to_string(), mord_class.to_string()],
-                vec![vlist.into()],
-                Some(options),
-                CssStyle::default(),
-            ).into()
-        })),
+        html_builder: Some(Box::new(html_builder)),
         #[cfg(feature = "mathml")]
-        mathml_builder: Some(Box::new(|group, options| {
-            use crate::{mathml, mathml_tree::{MathNode, MathNodeType}, stretchy, tree::ClassList};
-
-            let ParseNode::HorizBrace(group) = group else {
-                panic!("Expected HorizBrace node");
-            };
-
-            let accent_node = stretchy::mathml_node(&group.label);
-            let base_node = mathml::build_group(Some(&group.base), options);
+        mathml_builder: Some(Box::new(mathml_builder)),
+    });
 
-            let node_type = if group.is_over {
-                MathNodeType::MOver
-            } else {
-                MathNodeType::MUnder
-            };
+    fns.insert(Cow::Borrowed("\\overbrace"), horiz_brace.clone());
+    fns.insert(Cow::Borrowed("\\underbrace"), horiz_brace.clone());
+    fns.insert(Cow::Borrowed("\\overparen"), horiz_brace.clone());
+    fns.insert(Cow::Borrowed("\\underparen"), horiz_brace);
 
-            MathNode::new(node_type, vec![base_node, accent_node.into()], ClassList::new()).into()
-        })),
+    // mathtools' \overbracket[thickness][height]{body}
+    let horiz_bracket = Arc::new(FunctionSpec {
+        prop: FunctionPropSpec::new_num_opt_args(ParseNodeType::HorizBrace, 1, 2)
+            .with_arg_types(&[ArgType::Size, ArgType::Size, ArgType::Original] as &[ArgType]),
+        handler: Box::new(horiz_brace_handler),
+        #[cfg(feature = "html")]
+        html_builder: Some(Box::new(html_builder)),
+        #[cfg(feature = "mathml")]
+        mathml_builder: Some(Box::new(mathml_builder)),
     });
 
-    fns.insert(Cow::Borrowed("\\overbrace"), horiz_brace.clone());
-    fns.insert(Cow::Borrowed("\\underbrace"), horiz_brace);
+    fns.insert(Cow::Borrowed("\\overbracket"), horiz_bracket.clone());
+    fns.insert(Cow::Borrowed("\\underbracket"), horiz_bracket);
 }
 
 fn horiz_brace_handler(
     ctx: FunctionContext,
     args: &[ParseNode],
-    _opt_args: &[Option<ParseNode>],
+    opt_args: &[Option<ParseNode>],
 ) -> Result<ParseNode, ParseError> {
+    let size = |idx: usize| match opt_args.get(idx) {
+        Some(Some(ParseNode::Size(size))) if !size.is_blank => Some(size.value.clone()),
+        _ => None,
+    };
+
     let func_name = ctx.func_name.into_owned();
     Ok(ParseNode::HorizBrace(HorizBraceNode {
         label: func_name.clone(),
         is_over: func_name.starts_with("\\over"),
         base: Box::new(args[0].clone()),
+        thickness: size(0),
+        height: size(1),
         info: NodeInfo::new_mode(ctx.parser.mode()),
     }))
 }
+
+/// Whether the brace is one of the mathtools square brackets
+pub(crate) fn is_bracket(group: &HorizBraceNode) -> bool {
+    matches!(group.label.as_str(), "\\overbracket" | "\\underbracket")
+}
+
+/// The rule thickness and the height of a square bracket, in ems.
+/// These default to the default rule thickness and `0.7ex`.
+pub(crate) fn bracket_size(group: &HorizBraceNode, options: &Options) -> (f64, f64) {
+    let metrics = options.font_metrics();
+    let thickness = group
+        .thickness
+        .as_ref()
+        .map(|size| calculate_size(size, options))
+        .unwrap_or(metrics.default_rule_thickness);
+    let height = group
+        .height
+        .as_ref()
+        .map(|size| calculate_size(size, options))
+        .unwrap_or(0.7 * metrics.x_height);
+
+    (thickness, height.max(thickness))
+}
+
+/// Whether a sup/sub with the given brace as its base should have its script handled as the
+/// label of the brace.
+/// This is the case for a superscript on an over brace or a subscript on an under brace.
+pub(crate) fn has_label(group: &HorizBraceNode, sup: bool, sub: bool) -> bool {
+    sup != sub && sup == group.is_over
+}
+
+/// Split a group into the brace and, if we were passed a sup/sub, the label.
+#[cfg(any(feature = "html", feature = "mathml"))]
+fn split_label(group: &ParseNode) -> (&HorizBraceNode, Option<&ParseNode>) {
+    match group {
+        ParseNode::SupSub(sup_sub) => {
+            let Some(ParseNode::HorizBrace(base)) = sup_sub.base.as_deref() else {
+                panic!("Expected group to be a horizontal brace");
+            };
+
+            (base, sup_sub.sup.as_deref().or(sup_sub.sub.as_deref()))
+        }
+        ParseNode::HorizBrace(group) => (group, None),
+        _ => panic!("Expected group to be a horizontal brace or supsub"),
+    }
+}
+
+#[cfg(feature = "html")]
+fn bracket_span(group: &HorizBraceNode, options: &Options) -> HtmlNode {
+    use crate::{build_common::make_span, dom_tree::CssStyle, unit::make_em};
+
+    let (thickness, height) = bracket_size(group, options);
+    let thickness = make_em(thickness);
+    let border_width = if group.is_over {
+        format!("{0} {0} 0 {0}", thickness)
+    } else {
+        format!("0 {0} {0} {0}", thickness)
+    };
+
+    // The legs of the bracket are drawn with borders, so they stay sharp at any width
+    let mut span = make_span::<HtmlNode>(
+        vec!["stretchy".to_string(), "bracket".to_string()],
+        Vec::new(),
+        Some(options),
+        CssStyle {
+            border_style: Some(Cow::Borrowed("solid")),
+            border_width: Some(Cow::Owned(border_width)),
+            width: Some(Cow::Borrowed("100%")),
+            ..CssStyle::default()
+        },
+    );
+    span.node.height = height;
+    span.node.style.height = Some(Cow::Owned(format!(
+        "calc({} - {})",
+        make_em(height),
+        thickness
+    )));
+    span.into()
+}
+
+#[cfg(feature = "html")]
+pub(crate) fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
+    use crate::{
+        build_common::{make_span, make_v_list, VListElem, VListKern, VListParam, VListShiftChild},
+        dom_tree::{CssStyle, WithHtmlDomNode},
+        html, stretchy,
+        style::DISPLAY_STYLE,
+    };
+
+    let (group, label) = split_label(group);
+    let mord_class = if group.is_over { "mover" } else { "munder" };
+
+    // Build the base group
+    let base_options = options
+        .having_base_style(Some(DISPLAY_STYLE))
+        .unwrap_or(*options);
+    let body = html::build_group(Some(&group.base), &base_options, None);
+
+    // Create the stretchy brace
+    let brace_body = if is_bracket(group) {
+        bracket_span(group, options)
+    } else {
+        stretchy::svg_span(&group.label, options)
+    };
+
+    // Generate the vlist with content and brace
+    let mut vlist: HtmlNode = if group.is_over {
+        make_v_list(
+            VListParam::FirstBaseLine {
+                children: vec![
+                    VListShiftChild::Elem(VListElem::new(body)),
+                    VListShiftChild::Kern(VListKern(0.1)),
+                    VListShiftChild::Elem(VListElem::new_with_wrapper_classes(
+                        brace_body,
+                        vec!["svg-align".to_string()],
+                    )),
+                ],
+            },
+            options,
+        )
+        .into()
+    } else {
+        let body_depth = body.node().depth;
+        let brace_height = brace_body.node().height;
+        make_v_list(
+            VListParam::Bottom {
+                amount: body_depth + 0.1 + brace_height,
+                children: vec![
+                    VListShiftChild::Elem(VListElem::new_with_wrapper_classes(
+                        brace_body,
+                        vec!["svg-align".to_string()],
+                    )),
+                    VListShiftChild::Kern(VListKern(0.1)),
+                    VListShiftChild::Elem(VListElem::new(body)),
+                ],
+            },
+            options,
+        )
+        .into()
+    };
+
+    if let Some(label) = label {
+        // Attach the label above or below the brace
+        let style = if group.is_over {
+            options.style.sup()
+        } else {
+            options.style.sub()
+        };
+        let label_options = options.having_style(style);
+        let label_options = label_options.as_ref().unwrap_or(options);
+        let label = html::build_group(Some(label), label_options, Some(options));
+
+        let brace = make_span::<HtmlNode>(
+            vec!["mord".to_string(), mord_class.to_string()],
+            vec![vlist],
+            Some(options),
+            CssStyle::default(),
+        );
+
+        vlist = if group.is_over {
+            make_v_list(
+                VListParam::FirstBaseLine {
+                    children: vec![
+                        VListShiftChild::Elem(VListElem::new(brace.into())),
+                        VListShiftChild::Kern(VListKern(0.2)),
+                        VListShiftChild::Elem(VListElem::new(label)),
+                    ],
+                },
+                options,
+            )
+            .into()
+        } else {
+            let amount = brace.node.depth + 0.2 + label.node().height + label.node().depth;
+            make_v_list(
+                VListParam::Bottom {
+                    amount,
+                    children: vec![
+                        VListShiftChild::Elem(VListElem::new(label)),
+                        VListShiftChild::Kern(VListKern(0.2)),
+                        VListShiftChild::Elem(VListElem::new(brace.into())),
+                    ],
+                },
+                options,
+            )
+            .into()
+        };
+    }
+
+    make_span::<HtmlNode>(
+        vec!["mord".to_string(), mord_class.to_string()],
+        vec![vlist],
+        Some(options),
+        CssStyle::default(),
+    )
+    .into()
+}
+
+#[cfg(feature = "mathml")]
+pub(crate) fn mathml_builder(group: &ParseNode, options: &Options) -> MathmlNode {
+    use crate::{
+        mathml,
+        mathml_tree::{MathNode, MathNodeType},
+        stretchy,
+        tree::ClassList,
+    };
+
+    let (group, label) = split_label(group);
+
+    let accent_node = stretchy::mathml_node(&group.label);
+    let base_node = mathml::build_group(Some(&group.base), options);
+
+    let node_type = if group.is_over {
+        MathNodeType::MOver
+    } else {
+        MathNodeType::MUnder
+    };
+
+    let node = MathNode::new(node_type, vec![base_node, accent_node.into()], ClassList::new());
+
+    if let Some(label) = label {
+        let label = mathml::build_group(Some(label), options);
+        MathNode::new(node_type, vec![node.into(), label], ClassList::new()).into()
+    } else {
+        node.into()
+    }
+}
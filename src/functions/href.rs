@@ -1,14 +1,24 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
+use crate::{
+    parse_node::{HrefNode, NodeInfo, ParseNode, ParseNodeType, TextNode, TextOrdNode},
+    util::ArgType,
+};
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::tree::ClassList;
+
+#[cfg(feature = "html")]
 use crate::{
     build_common,
     html::{self, RealGroup},
+};
+
+#[cfg(feature = "mathml")]
+use crate::{
     mathml,
     mathml_tree::{MathNode, MathNodeType, MathmlNode},
-    parse_node::{HrefNode, NodeInfo, ParseNode, ParseNodeType, TextNode, TextOrdNode},
-    parser::ParseError,
-    tree::ClassList,
-    util::ArgType,
 };
 
 use super::{ord_argument, FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
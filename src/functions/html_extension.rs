@@ -1,5 +1,8 @@
-use std::{collections::HashMap, sync::Arc};
+use alloc::sync::Arc;
 
+use hashbrown::HashMap;
+
+use crate::prelude::*;
 use crate::{
     parse_node::{HtmlNode, NodeInfo, ParseNode, ParseNodeType},
     parser::ParseError,
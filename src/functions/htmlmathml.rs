@@ -1,10 +1,13 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
-use crate::{
-    build_common, html, mathml,
-    parse_node::{HtmlMathmlNode, NodeInfo, ParseNode, ParseNodeType},
-    parser::ParseError,
-};
+use crate::prelude::*;
+use crate::parse_node::{HtmlMathmlNode, NodeInfo, ParseNode, ParseNodeType};
+
+#[cfg(feature = "html")]
+use crate::{build_common, html};
+
+#[cfg(feature = "mathml")]
+use crate::mathml;
 
 use super::{ord_argument, FunctionPropSpec, FunctionSpec, Functions};
 
@@ -1,11 +1,12 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::{
     parse_node::{IncludeGraphicsNode, NodeInfo, ParseNode, ParseNodeType},
     parser::ParseError,
     unit::{Em, Measurement},
-    util::ArgType,
+    util::{find_size, is_number, ArgType},
 };
 
 #[cfg(feature = "html")]
@@ -195,22 +196,17 @@ fn includegraphics_handler(
 
 /// Parse a size value like "0.9em" or "10pt" into a Measurement
 fn parse_size(s: &str) -> Result<Measurement, ()> {
-    use regex::Regex;
-
     // Check for number-only (default to bp, per graphix package)
-    let num_only = Regex::new(r"^[-+]?\s*(\d+(\.\d*)?|\.\d+)$").unwrap();
-    if num_only.is_match(s) {
+    if is_number(s, char::is_whitespace) {
         let num: f64 = s.trim().parse().map_err(|_| ())?;
         // Convert bp to em (1bp = 1/72 inch, 1em ~= 12pt = 12/72 inch)
         return Ok(Measurement::Em(Em(num / 12.0)));
     }
 
     // Check for number with unit
-    let num_unit = Regex::new(r"([-+]?)\s*(\d+(?:\.\d*)?|\.\d+)\s*([a-z]{2})").unwrap();
-    if let Some(caps) = num_unit.captures(s) {
-        let sign = if &caps[1] == "-" { -1.0 } else { 1.0 };
-        let num: f64 = caps[2].parse().map_err(|_| ())?;
-        let unit = &caps[3];
+    if let Some((sign, magnitude, unit)) = find_size(s, char::is_whitespace) {
+        let sign = if sign == "-" { -1.0 } else { 1.0 };
+        let num: f64 = magnitude.parse().map_err(|_| ())?;
 
         // Convert common units to em
         let em_value = match unit {
@@ -1,14 +1,20 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::{
-    build_common::make_glue,
-    mathml_tree::SpaceNode,
     parse_node::{KernNode, NodeInfo, ParseNode, ParseNodeType},
-    parser::ParseError,
-    unit::Em,
     util::ArgType,
 };
 
+#[cfg(feature = "html")]
+use crate::build_common::make_glue;
+
+#[cfg(feature = "mathml")]
+use crate::{
+    mathml_tree::SpaceNode,
+    unit::{calculate_size, Em},
+};
+
 use super::{FunctionPropSpec, FunctionSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
@@ -32,10 +38,9 @@ pub fn add_functions(fns: &mut Functions) {
             make_glue(group.dimension.clone(), options).into()
         })),
         #[cfg(feature = "mathml")]
-        mathml_builder: Some(Box::new(|group, _| {
+        mathml_builder: Some(Box::new(|group, options| {
             let ParseNode::Kern(group) = group else { unreachable!() };
-            // TODO: KaTeX seems to assume that kern will only ever get Em in mathml?
-            let dim = Em(group.dimension.num());
+            let dim = Em(calculate_size(&group.dimension, options));
             SpaceNode::new(dim).into()
         })),
     });
@@ -1,16 +1,27 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::sync::Arc;
 
+use crate::prelude::*;
+use crate::parse_node::{LapNode, NodeInfo, ParseNode, ParseNodeType};
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::tree::ClassList;
+
+#[cfg(feature = "html")]
+use alloc::borrow::Cow;
+#[cfg(feature = "html")]
 use crate::{
     build_common::{make_span, make_span_s},
     dom_tree::CssStyle,
-    html, mathml,
-    mathml_tree::{MathNode, MathNodeType},
-    parse_node::{LapNode, NodeInfo, ParseNode, ParseNodeType},
-    parser::ParseError,
-    tree::ClassList,
+    html,
     unit::make_em,
 };
 
+#[cfg(feature = "mathml")]
+use crate::{
+    mathml,
+    mathml_tree::{MathNode, MathNodeType},
+};
+
 use super::{FunctionPropSpec, FunctionSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
@@ -1,6 +1,7 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::expander::{BreakToken, Mode};
 use crate::parse_node::{NodeInfo, ParseNode, ParseNodeType, StylingNode};
 use crate::parser::ParseError;
@@ -26,11 +27,10 @@ pub fn add_functions(fns: &mut Functions) {
 
             let body = ctx
                 .parser
-                .dispatch_parse_expression(false, Some(close))
-                .unwrap();
+                .dispatch_parse_expression(false, Some(close))?;
 
             // Consume the closing delimiter
-            ctx.parser.expect(close.as_str(), true).unwrap();
+            ctx.parser.expect(close.as_str(), true)?;
 
             ctx.parser.switch_mode(outer_mode);
 
@@ -54,7 +54,7 @@ pub fn add_functions(fns: &mut Functions) {
             .with_allowed_in_text(true)
             .with_allowed_in_math(false),
         handler: Box::new(|ctx, _, _| {
-            panic!("Mismatched {}", ctx.func_name);
+            Err(ParseError::MismatchedMathDelimiter(ctx.func_name.to_string()))
         }),
         #[cfg(feature = "html")]
         html_builder: None,
@@ -1,18 +1,21 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{MathChoiceNode, NodeInfo, ParseNode, ParseNodeType};
 use crate::parser::ParseError;
-use crate::style::{DISPLAY_STYLE, SCRIPT_SCRIPT_STYLE, SCRIPT_STYLE, TEXT_STYLE};
 
 use super::{ord_argument, FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::style::{DISPLAY_STYLE, SCRIPT_SCRIPT_STYLE, SCRIPT_STYLE, TEXT_STYLE};
 #[cfg(feature = "html")]
 use crate::{build_common, html};
 #[cfg(feature = "mathml")]
 use crate::mathml;
 
 /// Choose the appropriate body based on the current math style
+#[cfg(any(feature = "html", feature = "mathml"))]
 fn choose_math_style<'a>(group: &'a MathChoiceNode, options: &crate::Options) -> &'a [ParseNode] {
     match options.style.size() {
         s if s == DISPLAY_STYLE.size() => &group.display,
@@ -1,16 +1,22 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
 use crate::{
-    build_common::make_span,
-    html,
     parse_node::{MClassNode, NodeInfo, OpNode, ParseNode, ParseNodeType, SupSubNode},
-    parser::ParseError,
     symbols::Atom,
-    util, Options,
+    util,
 };
 
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::Options;
+
 #[cfg(feature = "html")]
-use crate::dom_tree::{CssStyle, HtmlNode};
+use crate::{
+    build_common::make_span,
+    dom_tree::{CssStyle, HtmlNode},
+    html,
+};
+
 #[cfg(feature = "mathml")]
 use crate::mathml_tree::MathmlNode;
 
@@ -97,7 +103,7 @@ pub fn add_functions(fns: &mut Functions) {
                 } else {
                     Some(Box::new(shifted_arg.clone()))
                 },
-                sub: if ctx.func_name == "\\overset" {
+                sub: if ctx.func_name == "\\underset" {
                     Some(Box::new(shifted_arg.clone()))
                 } else {
                     None
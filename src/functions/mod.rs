@@ -1,23 +1,39 @@
-use std::{borrow::Cow, collections::HashMap, sync::Arc};
-
-use once_cell::sync::Lazy;
-
+//! The functions, like `\frac`, and the builders that turn the nodes they parse into HTML and
+//! MathML.
+//!
+//! The builders are kept in a [`NodeTable`] indexed by [`ParseNodeType`] in the shared
+//! [`Functions`] registry, so finding the builder for a node is a single index. A table is needed
+//! there because the builders come from the functions as they are registered, and an extension can
+//! add its own.
+//!
+//! The IR builder in [`crate::ir::builder`] doesn't use the table. It has one builder for each node
+//! type, which is fixed when the crate is compiled, so it dispatches with an exhaustive `match`
+//! and a new node type fails to compile until the IR handles it. With a table, a missing builder
+//! would only be found at runtime. `benches/render.rs` measured no difference between the two.
+
+use alloc::{borrow::Cow, sync::Arc};
+
+use hashbrown::HashMap;
+
+use crate::prelude::*;
 use crate::{
-    build_common::make_span,
     environments::cd,
     expander::BreakToken,
-    html, mathml,
     lexer::Token,
     parse_node::{ParseNode, ParseNodeType},
     parser::{ParseError, Parser},
+    sync::Lazy,
     util::ArgType,
-    Options,
 };
 
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::Options;
+
 #[cfg(feature = "html")]
-use crate::dom_tree::HtmlNode;
+use crate::{build_common::make_span, dom_tree::HtmlNode, html};
+
 #[cfg(feature = "mathml")]
-use crate::mathml_tree::MathmlNode;
+use crate::{mathml, mathml_tree::MathmlNode};
 
 pub mod accent;
 pub mod accent_under;
@@ -32,7 +48,7 @@ mod delimsizing;
 mod enclose;
 mod html_extension;
 mod includegraphics;
-mod horiz_brace;
+pub(crate) mod horiz_brace;
 mod hbox;
 pub mod href;
 mod htmlmathml;
@@ -57,15 +73,13 @@ mod sqrt;
 mod styling;
 mod symbols_op;
 pub mod symbols_ord;
-mod text;
+pub(crate) mod text;
 mod verb;
 
 // TODO: Put specific function groups under features? Eh
-pub(crate) const FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
-    let mut fns = Functions {
-        fns: HashMap::new(),
-        builders: Vec::new(),
-    };
+/// The functions and builders, which are built once and shared by every parse and render
+pub(crate) static FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
+    let mut fns = Functions::default();
 
     accent::add_functions(&mut fns);
     accent_under::add_functions(&mut fns);
@@ -119,6 +133,20 @@ pub(crate) const FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
                 panic!();
             };
 
+            // Operator names with limits handle their own scripts
+            if let Some(ParseNode::OperatorName(base)) = sup_sub.base.as_deref() {
+                if operatorname::has_limits(base, options) {
+                    return operatorname::html_builder(group, options);
+                }
+            }
+
+            // A script on the open side of a horizontal brace is its label
+            if let Some(ParseNode::HorizBrace(base)) = sup_sub.base.as_deref() {
+                if horiz_brace::has_label(base, sup_sub.sup.is_some(), sup_sub.sub.is_some()) {
+                    return horiz_brace::html_builder(group, options);
+                }
+            }
+
             let mut children = Vec::new();
             if let Some(base) = sup_sub.base.as_deref() {
                 children.push(html::build_group(Some(base), options, None));
@@ -162,6 +190,20 @@ pub(crate) const FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
                 panic!();
             };
 
+            // Operator names with limits handle their own scripts
+            if let Some(ParseNode::OperatorName(base)) = sup_sub.base.as_deref() {
+                if operatorname::has_limits(base, options) {
+                    return operatorname::mathml_builder(group, options);
+                }
+            }
+
+            // A script on the open side of a horizontal brace is its label
+            if let Some(ParseNode::HorizBrace(base)) = sup_sub.base.as_deref() {
+                if horiz_brace::has_label(base, sup_sub.sup.is_some(), sup_sub.sub.is_some()) {
+                    return horiz_brace::mathml_builder(group, options);
+                }
+            }
+
             let mut children = Vec::new();
             if let Some(base) = sup_sub.base.as_deref() {
                 children.push(mathml::build_group(Some(base), options));
@@ -185,7 +227,7 @@ pub(crate) const FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
         html_builder: Some(Box::new(|group, options| {
             let ParseNode::Color(color) = group else { panic!() };
             let mut opts = options.clone_alter();
-            opts = opts.with_color(color.color.clone());
+            opts = opts.with_color(&color.color);
             let inner = html::build_expression(&color.body, &opts, html::RealGroup::True, (None, None));
             make_span(
                 vec!["mord".to_string()],
@@ -202,7 +244,7 @@ pub(crate) const FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
 
             let ParseNode::Color(color) = group else { panic!() };
             let mut opts = options.clone_alter();
-            opts = opts.with_color(color.color.clone());
+            opts = opts.with_color(&color.color);
             let inner = mathml::build_expression(&color.body, &opts, None);
 
             // Wrap in mstyle with mathcolor attribute
@@ -317,10 +359,58 @@ pub(crate) const FUNCTIONS: Lazy<Functions> = Lazy::new(|| {
     fns
 });
 
+/// A value for each [`ParseNodeType`], kept in an array indexed by the type so that finding
+/// the value for a node is a single index
 #[derive(Clone)]
+pub struct NodeTable<T> {
+    entries: Vec<Option<T>>,
+}
+impl<T> NodeTable<T> {
+    pub fn new() -> NodeTable<T> {
+        NodeTable {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, typ: ParseNodeType) -> Option<&T> {
+        self.entries.get(typ as usize)?.as_ref()
+    }
+
+    /// Set the value for the type, returning the previous value
+    pub fn insert(&mut self, typ: ParseNodeType, value: T) -> Option<T> {
+        let index = typ as usize;
+        if index >= self.entries.len() {
+            self.entries.resize_with(index + 1, || None);
+        }
+
+        self.entries[index].replace(value)
+    }
+}
+impl<T> Default for NodeTable<T> {
+    fn default() -> Self {
+        NodeTable::new()
+    }
+}
+
+/// Where the builder for a node type is from
+#[cfg(any(feature = "html", feature = "mathml"))]
+#[derive(Clone)]
+enum BuilderSource {
+    Function(Arc<FunctionSpec>),
+    Builder(Arc<BuilderFunctionSpec>),
+}
+
+#[derive(Clone, Default)]
 pub struct Functions {
     fns: HashMap<Cow<'static, str>, Arc<FunctionSpec>>,
-    pub builders: Vec<Arc<BuilderFunctionSpec>>,
+    builders: Vec<Arc<BuilderFunctionSpec>>,
+    /// The builder used for each node type.
+    /// The first function registered with a builder for the type is used, and otherwise the
+    /// first builder registered with [`Functions::insert_builder`].
+    #[cfg(feature = "html")]
+    html_builders: NodeTable<BuilderSource>,
+    #[cfg(feature = "mathml")]
+    mathml_builders: NodeTable<BuilderSource>,
 }
 impl Functions {
     pub fn get(&self, name: &str) -> Option<&Arc<FunctionSpec>> {
@@ -328,7 +418,26 @@ impl Functions {
     }
 
     pub fn insert(&mut self, name: Cow<'static, str>, spec: Arc<FunctionSpec>) {
-        self.fns.insert(name.into(), spec);
+        #[cfg(any(feature = "html", feature = "mathml"))]
+        let typ = spec.prop.typ;
+
+        #[cfg(feature = "html")]
+        if spec.html_builder.is_some()
+            && !matches!(self.html_builders.get(typ), Some(BuilderSource::Function(_)))
+        {
+            self.html_builders
+                .insert(typ, BuilderSource::Function(spec.clone()));
+        }
+
+        #[cfg(feature = "mathml")]
+        if spec.mathml_builder.is_some()
+            && !matches!(self.mathml_builders.get(typ), Some(BuilderSource::Function(_)))
+        {
+            self.mathml_builders
+                .insert(typ, BuilderSource::Function(spec.clone()));
+        }
+
+        self.fns.insert(name, spec);
     }
 
     pub fn insert_for_all_str<I: Iterator<Item = &'static str>>(
@@ -344,41 +453,38 @@ impl Functions {
     /// Use this to register only the HTML/MathML builders for a function.
     pub fn insert_builder(&mut self, spec: Arc<BuilderFunctionSpec>) {
         assert_eq!(spec.prop.num_args, 0);
-        self.builders.push(spec);
-    }
+        #[cfg(any(feature = "html", feature = "mathml"))]
+        let typ = spec.prop.typ;
 
-    pub fn find_html_builder_for_type(&self, typ: ParseNodeType) -> Option<&HtmlBuilderFn> {
-        for spec in self.fns.values() {
-            if spec.prop.typ == typ && spec.html_builder.is_some() {
-                return spec.html_builder.as_ref();
-            }
+        #[cfg(feature = "html")]
+        if self.html_builders.get(typ).is_none() {
+            self.html_builders
+                .insert(typ, BuilderSource::Builder(spec.clone()));
         }
 
-        // Check the builder functions
-        for spec in &self.builders {
-            if spec.prop.typ == typ {
-                return spec.html_builder.as_ref();
-            }
+        #[cfg(feature = "mathml")]
+        if self.mathml_builders.get(typ).is_none() {
+            self.mathml_builders
+                .insert(typ, BuilderSource::Builder(spec.clone()));
         }
 
-        None
+        self.builders.push(spec);
     }
 
-    pub fn find_mathml_builder_for_type(&self, typ: ParseNodeType) -> Option<&MathmlBuilderFn> {
-        for spec in self.fns.values() {
-            if spec.prop.typ == typ && spec.mathml_builder.is_some() {
-                return spec.mathml_builder.as_ref();
-            }
+    #[cfg(feature = "html")]
+    pub fn find_html_builder_for_type(&self, typ: ParseNodeType) -> Option<&HtmlBuilderFn> {
+        match self.html_builders.get(typ)? {
+            BuilderSource::Function(spec) => spec.html_builder.as_ref(),
+            BuilderSource::Builder(spec) => spec.html_builder.as_ref(),
         }
+    }
 
-        // Check the builder functions
-        for spec in &self.builders {
-            if spec.prop.typ == typ {
-                return spec.mathml_builder.as_ref();
-            }
+    #[cfg(feature = "mathml")]
+    pub fn find_mathml_builder_for_type(&self, typ: ParseNodeType) -> Option<&MathmlBuilderFn> {
+        match self.mathml_builders.get(typ)? {
+            BuilderSource::Function(spec) => spec.mathml_builder.as_ref(),
+            BuilderSource::Builder(spec) => spec.mathml_builder.as_ref(),
         }
-
-        None
     }
 }
 
@@ -470,9 +576,9 @@ impl FunctionPropSpec {
 }
 
 #[cfg(feature = "html")]
-pub type HtmlBuilderFn = Box<dyn Fn(&ParseNode, &Options) -> HtmlNode>;
+pub type HtmlBuilderFn = Box<dyn Fn(&ParseNode, &Options) -> HtmlNode + Send + Sync>;
 #[cfg(feature = "mathml")]
-pub type MathmlBuilderFn = Box<dyn Fn(&ParseNode, &Options) -> MathmlNode>;
+pub type MathmlBuilderFn = Box<dyn Fn(&ParseNode, &Options) -> MathmlNode + Send + Sync>;
 
 pub struct FunctionSpec {
     pub prop: FunctionPropSpec,
@@ -483,17 +589,18 @@ pub struct FunctionSpec {
     #[cfg(feature = "mathml")]
     pub mathml_builder: Option<MathmlBuilderFn>,
 }
-impl std::fmt::Debug for FunctionSpec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("FunctionSpec")
-            .field("prop", &self.prop)
-            .field(
-                "handler",
-                &"Box<dyn Fn(FunctionContext, &[ParseNode], &[Option<ParseNode>]) -> Result<ParseNode, ParseError>>",
-            )
-            .field("html_builder", &self.html_builder.is_some())
-            .field("mathml_builder", &self.mathml_builder.is_some())
-            .finish()
+impl core::fmt::Debug for FunctionSpec {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut debug = f.debug_struct("FunctionSpec");
+        debug.field("prop", &self.prop).field(
+            "handler",
+            &"Box<dyn Fn(FunctionContext, &[ParseNode], &[Option<ParseNode>]) -> Result<ParseNode, ParseError>>",
+        );
+        #[cfg(feature = "html")]
+        debug.field("html_builder", &self.html_builder.is_some());
+        #[cfg(feature = "mathml")]
+        debug.field("mathml_builder", &self.mathml_builder.is_some());
+        debug.finish()
     }
 }
 
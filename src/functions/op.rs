@@ -1,21 +1,32 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
+use crate::{
+    parse_node::{NodeInfo, OpNode, ParseNode, ParseNodeType},
+    util::find_assoc_data,
+};
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::{expander::Mode, tree::ClassList, Options};
+
+#[cfg(feature = "html")]
 use crate::{
     build_common::{
         self, make_span, make_span_s, make_symbol, math_sym, VListElem, VListElemShift, VListKern,
         VListParam, VListShiftChild,
     },
-    dom_tree::{CssStyle, DocumentFragment, HtmlNode, WithHtmlDomNode},
-    expander::Mode,
-    html, mathml,
-    mathml_tree::{self, MathNode, MathNodeType, MathmlNode},
-    parse_node::{NodeInfo, OpNode, ParseNode, ParseNodeType},
-    parser::ParseError,
+    dom_tree::{CssStyle, HtmlNode, WithHtmlDomNode},
+    html,
     style::{StyleId, DISPLAY_STYLE},
-    tree::ClassList,
     unit::make_em,
-    util::{self, find_assoc_data},
-    Options,
+    util,
+};
+
+#[cfg(feature = "mathml")]
+use crate::{
+    dom_tree::DocumentFragment,
+    mathml,
+    mathml_tree::{self, MathNode, MathNodeType, MathmlNode},
 };
 
 use super::{ord_argument, FunctionPropSpec, FunctionSpec, Functions};
@@ -390,10 +401,12 @@ fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
     }
 }
 
+#[cfg(feature = "html")]
 struct PartInfo {
     elem: HtmlNode,
     kern: f64,
 }
+#[cfg(feature = "html")]
 pub(crate) fn assemble_sup_sub(
     base: HtmlNode,
     sup_group: Option<&ParseNode>,
@@ -542,6 +555,7 @@ pub(crate) fn assemble_sup_sub(
     .into()
 }
 
+#[cfg(feature = "mathml")]
 fn mathml_builder(group: &ParseNode, options: &Options) -> MathmlNode {
     let ParseNode::Op(group) = group else { unreachable!() };
 
@@ -1,7 +1,28 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{NodeInfo, OperatorNameNode, ParseNode, ParseNodeType};
-use crate::parser::ParseError;
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::Options;
+
+#[cfg(feature = "html")]
+use alloc::borrow::Cow;
+#[cfg(feature = "html")]
+use crate::{
+    build_common::make_span,
+    dom_tree::{CssStyle, HtmlNode},
+    html,
+    parse_node::TextOrdNode,
+};
+#[cfg(feature = "mathml")]
+use crate::{
+    dom_tree::DocumentFragment,
+    expander::Mode,
+    mathml,
+    mathml_tree::{MathNode, MathNodeType, MathmlNode, TextNode},
+    tree::ClassList,
+};
 
 use super::{ord_argument, FunctionPropSpec, FunctionSpec, Functions};
 
@@ -18,9 +39,9 @@ pub fn add_functions(fns: &mut Functions) {
             }))
         }),
         #[cfg(feature = "html")]
-        html_builder: None,
+        html_builder: Some(Box::new(html_builder)),
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(mathml_builder)),
     });
     fns.insert("\\operatorname@".into(), op.clone());
 
@@ -36,9 +57,179 @@ pub fn add_functions(fns: &mut Functions) {
             }))
         }),
         #[cfg(feature = "html")]
-        html_builder: None,
+        html_builder: Some(Box::new(html_builder)),
         #[cfg(feature = "mathml")]
-        mathml_builder: None,
+        mathml_builder: Some(Box::new(mathml_builder)),
     });
     fns.insert("\\operatornamewithlimits".into(), op_limits);
 }
+
+/// Whether the scripts of a sup/sub with the given operator name as its base should be placed
+/// above and below it, rather than to the side.
+#[cfg(any(feature = "html", feature = "mathml"))]
+pub(crate) fn has_limits(group: &OperatorNameNode, options: &Options) -> bool {
+    group.always_handle_sup_sub
+        && (options.style.size() == crate::style::DISPLAY_STYLE.size() || group.limits)
+}
+
+/// Split a group into the operator name and, if we were passed a sup/sub to handle its limits,
+/// its superscript and subscript.
+#[cfg(any(feature = "html", feature = "mathml"))]
+fn split_limits(
+    group: &ParseNode,
+) -> (&OperatorNameNode, Option<&ParseNode>, Option<&ParseNode>, bool) {
+    match group {
+        ParseNode::SupSub(sup_sub) => {
+            let Some(ParseNode::OperatorName(base)) = sup_sub.base.as_deref() else {
+                panic!("Expected group to be an operatorname");
+            };
+
+            (base, sup_sub.sup.as_deref(), sup_sub.sub.as_deref(), true)
+        }
+        ParseNode::OperatorName(op) => (op, None, None, false),
+        _ => panic!("Expected group to be an operatorname or supsub"),
+    }
+}
+
+/// Per the amsopn package, change minus to hyphen and \ast to asterisk
+#[cfg(any(feature = "html", feature = "mathml"))]
+fn amsopn_text(text: &str) -> String {
+    text.replace('\u{2212}', "-").replace('\u{2217}', "*")
+}
+
+#[cfg(feature = "html")]
+pub(crate) fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
+    let (group, sup_group, sub_group, has_limits) = split_limits(group);
+
+    let base = if !group.body.is_empty() {
+        // Consolidate function names into symbol characters.
+        let body = group
+            .body
+            .iter()
+            .map(|child| match child.text() {
+                Some(text) if !matches!(child, ParseNode::Spacing(_)) => {
+                    ParseNode::TextOrd(TextOrdNode {
+                        text: Cow::Owned(text.to_string()),
+                        info: NodeInfo::new_mode(child.info().mode),
+                    })
+                }
+                _ => child.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let font_options = options.with_font("mathrm");
+        let mut expression =
+            html::build_expression(&body, &font_options, html::RealGroup::True, (None, None));
+        for child in expression.iter_mut() {
+            if let HtmlNode::Symbol(symbol) = child {
+                symbol.text = amsopn_text(&symbol.text);
+            }
+        }
+
+        make_span(
+            vec!["mop".to_string()],
+            expression,
+            Some(options),
+            CssStyle::default(),
+        )
+    } else {
+        make_span(
+            vec!["mop".to_string()],
+            Vec::new(),
+            Some(options),
+            CssStyle::default(),
+        )
+    };
+
+    if has_limits {
+        super::op::assemble_sup_sub(
+            base.into(),
+            sup_group,
+            sub_group,
+            options,
+            options.style,
+            0.0,
+            0.0,
+        )
+    } else {
+        base.into()
+    }
+}
+
+#[cfg(feature = "mathml")]
+pub(crate) fn mathml_builder(group: &ParseNode, options: &Options) -> MathmlNode {
+    let (group, sup_group, sub_group, has_limits) = split_limits(group);
+
+    if has_limits {
+        let base = ParseNode::OperatorName(OperatorNameNode {
+            parent_is_sup_sub: true,
+            ..group.clone()
+        });
+        let mut children = vec![mathml::build_group(Some(&base), options)];
+        let typ = match (sup_group, sub_group) {
+            (Some(sup), Some(sub)) => {
+                children.push(mathml::build_group(Some(sub), options));
+                children.push(mathml::build_group(Some(sup), options));
+                MathNodeType::MUnderOver
+            }
+            (Some(sup), None) => {
+                children.push(mathml::build_group(Some(sup), options));
+                MathNodeType::MOver
+            }
+            (None, Some(sub)) => {
+                children.push(mathml::build_group(Some(sub), options));
+                MathNodeType::MUnder
+            }
+            (None, None) => return children.pop().unwrap(),
+        };
+
+        return MathNode::new(typ, children, ClassList::new()).into();
+    }
+
+    // The steps taken here are similar to the html version.
+    let font_options = options.with_font("mathrm");
+    let mut expression = mathml::build_expression(&group.body, &font_options, None);
+
+    // Is expression a string or has it something like a fraction?
+    let mut is_all_string = true;
+    for node in expression.iter_mut() {
+        match node {
+            MathmlNode::Space(_) => {}
+            MathmlNode::Math(node) => match node.typ {
+                MathNodeType::Mi
+                | MathNodeType::Mn
+                | MathNodeType::MSpace
+                | MathNodeType::MText => {}
+                MathNodeType::Mo => match node.children.as_mut_slice() {
+                    [MathmlNode::Text(child)] => child.text = amsopn_text(&child.text),
+                    _ => is_all_string = false,
+                },
+                _ => is_all_string = false,
+            },
+            _ => is_all_string = false,
+        }
+    }
+
+    if is_all_string {
+        // Write a single TextNode instead of multiple nested tags.
+        let word = expression.iter().map(MathmlNode::to_text).collect::<String>();
+        expression = vec![TextNode::new(word).into()];
+    }
+
+    let mut identifier = MathNode::new(MathNodeType::Mi, expression, ClassList::new());
+    identifier.set_attribute("mathvariant", "normal");
+
+    // ⁡ is the same as &ApplyFunction;
+    // ref: https://www.w3.org/TR/REC-MathML/chap3_2.html#sec3.2.4
+    let operator = mathml::make_text("\u{2061}".to_string(), Mode::Text, None);
+    let operator = MathNode::new(MathNodeType::Mo, vec![operator], ClassList::new());
+
+    let identifier = MathmlNode::from(identifier);
+    let operator = MathmlNode::from(operator);
+
+    if group.parent_is_sup_sub {
+        MathNode::new(MathNodeType::MRow, vec![identifier, operator], ClassList::new()).into()
+    } else {
+        DocumentFragment::new(vec![identifier, operator]).into()
+    }
+}
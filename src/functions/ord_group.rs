@@ -1,13 +1,20 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::parse_node::ParseNodeType;
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::parse_node::ParseNode;
+
+#[cfg(feature = "html")]
 use crate::{
     build_common::{make_fragment, make_span},
     dom_tree::CssStyle,
     html::{self, RealGroup},
-    mathml,
-    parse_node::{ParseNode, ParseNodeType},
 };
 
+#[cfg(feature = "mathml")]
+use crate::mathml;
+
 use super::{BuilderFunctionSpec, FunctionPropSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
@@ -1,14 +1,25 @@
-use std::borrow::Cow;
-use std::sync::Arc;
-
-use crate::build_common::{self, make_span, VListElem, VListKern, VListShiftChild};
-use crate::dom_tree::{CssStyle, HtmlNode};
-use crate::html;
-use crate::mathml;
-use crate::mathml_tree::{MathNode, MathNodeType, MathmlNode, TextNode};
-use crate::parse_node::{NodeInfo, OverlineNode, ParseNode, ParseNodeType};
-use crate::parser::ParseError;
-use crate::tree::ClassList;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::{
+    parse_node::{NodeInfo, OverlineNode, ParseNode, ParseNodeType},
+    parser::ParseError,
+};
+
+#[cfg(feature = "html")]
+use crate::{
+    build_common::{self, make_span, VListElem, VListKern, VListShiftChild},
+    dom_tree::{CssStyle, HtmlNode},
+    html,
+};
+
+#[cfg(feature = "mathml")]
+use crate::{
+    mathml,
+    mathml_tree::{MathNode, MathNodeType, MathmlNode, TextNode},
+    tree::ClassList,
+};
 
 use super::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
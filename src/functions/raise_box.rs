@@ -1,14 +1,23 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
+use crate::{
+    parse_node::{NodeInfo, ParseNode, ParseNodeType, RaiseBoxNode},
+    util::ArgType,
+};
+
+#[cfg(feature = "html")]
 use crate::{
     build_common::{self, VListElem, VListParam, VListShiftChild},
-    html, mathml,
+    html,
+    unit::calculate_size,
+};
+
+#[cfg(feature = "mathml")]
+use crate::{
+    mathml,
     mathml_tree::{MathNode, MathNodeType},
-    parse_node::{NodeInfo, ParseNode, ParseNodeType, RaiseBoxNode},
-    parser::ParseError,
     tree::ClassList,
-    unit::calculate_size,
-    util::ArgType,
 };
 
 use super::{FunctionPropSpec, FunctionSpec, Functions};
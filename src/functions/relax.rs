@@ -1,9 +1,7 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
-use crate::{
-    parse_node::{InternalNode, NodeInfo, ParseNode, ParseNodeType},
-    parser::ParseError,
-};
+use crate::prelude::*;
+use crate::parse_node::{InternalNode, NodeInfo, ParseNode, ParseNodeType};
 
 use super::{FunctionPropSpec, FunctionSpec, Functions};
 
@@ -1,8 +1,8 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::{
     parse_node::{NodeInfo, ParseNode, ParseNodeType, RuleNode},
-    parser::ParseError,
     util::ArgType,
 };
 
@@ -42,7 +42,7 @@ pub fn add_functions(fns: &mut Functions) {
         }),
         #[cfg(feature = "html")]
         html_builder: Some(Box::new(|group, options| {
-            use std::borrow::Cow;
+            use alloc::borrow::Cow;
             use crate::{build_common::make_span, dom_tree::{CssStyle, HtmlNode}, unit::{calculate_size, make_em}};
 
             let ParseNode::Rule(group) = group else { unreachable!() };
@@ -1,16 +1,22 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
+use crate::parse_node::{NodeInfo, ParseNode, ParseNodeType, SizingNode};
+
+#[cfg(feature = "html")]
 use crate::{
     build_common::make_fragment,
     dom_tree::{DocumentFragment, HtmlNode},
     html::{self, RealGroup},
+    Options,
+};
+
+#[cfg(feature = "mathml")]
+use crate::{
     mathml,
     mathml_tree::{MathNode, MathNodeType},
-    parse_node::{NodeInfo, ParseNode, ParseNodeType, SizingNode},
-    parser::ParseError,
     tree::ClassList,
     unit::make_em,
-    Options,
 };
 
 use super::{FunctionPropSpec, FunctionSpec, Functions};
@@ -35,8 +41,7 @@ pub fn add_functions(fns: &mut Functions) {
         handler: Box::new(|ctx, _, _| {
             let body = ctx
                 .parser
-                .dispatch_parse_expression(false, ctx.break_on_token_text)
-                .unwrap();
+                .dispatch_parse_expression(false, ctx.break_on_token_text)?;
 
             Ok(ParseNode::Sizing(SizingNode {
                 size: SIZE_FUNCS.iter().position(|&s| s == ctx.func_name).unwrap() + 1,
@@ -71,7 +76,7 @@ pub fn add_functions(fns: &mut Functions) {
     fns.insert_for_all_str(SIZE_FUNCS.iter().copied(), sizing);
 }
 
-// #[cfg(feature = "html")]
+#[cfg(feature = "html")]
 pub(crate) fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
     // Handle sizing operators like \Huge. Real TeX doesn't actually allow
     // these functions inside of math expressions, so we do some special
@@ -83,6 +88,7 @@ pub(crate) fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
     sizing_group(&group.body, new_options, options).into()
 }
 
+#[cfg(feature = "html")]
 pub(crate) fn sizing_group(
     value: &[ParseNode],
     options: &Options,
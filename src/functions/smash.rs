@@ -1,6 +1,7 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::parse_node::{NodeInfo, ParseNode, ParseNodeType, SmashNode};
 use crate::parser::ParseError;
 
@@ -1,22 +1,30 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
+use crate::{
+    parse_node::{NodeInfo, ParseNode, ParseNodeType, SqrtNode},
+    parser::ParseError,
+};
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::tree::ClassList;
+
+#[cfg(feature = "html")]
 use crate::{
     build_common::{self, make_span, VListElem, VListShiftChild},
     delimiter,
     dom_tree::{CssStyle, HtmlNode, WithHtmlDomNode},
-    html, mathml,
-    parse_node::{NodeInfo, ParseNode, ParseNodeType, SqrtNode},
-    parser::ParseError,
-    style,
+    html, style,
     unit::make_em,
 };
 
-use super::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
-
 #[cfg(feature = "mathml")]
-use crate::mathml_tree::{MathNode, MathNodeType, MathmlNode};
-#[cfg(feature = "mathml")]
-use crate::tree::ClassList;
+use crate::{
+    mathml,
+    mathml_tree::{MathNode, MathNodeType, MathmlNode},
+};
+
+use super::{FunctionContext, FunctionPropSpec, FunctionSpec, Functions};
 
 pub fn add_functions(fns: &mut Functions) {
     let sqrt = Arc::new(FunctionSpec {
@@ -1,15 +1,22 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
+use crate::{
+    parse_node::{NodeInfo, ParseNode, ParseNodeType, StylingNode},
+    util::Style,
+};
+
+#[cfg(feature = "mathml")]
 use crate::{
     mathml,
     mathml_tree::{MathNode, MathNodeType},
-    parse_node::{NodeInfo, ParseNode, ParseNodeType, StylingNode},
-    parser::ParseError,
     tree::ClassList,
-    util::Style,
 };
 
-use super::{sizing::sizing_group, FunctionPropSpec, FunctionSpec, Functions};
+use super::{FunctionPropSpec, FunctionSpec, Functions};
+
+#[cfg(feature = "html")]
+use super::sizing::sizing_group;
 
 pub fn add_functions(fns: &mut Functions) {
     let styling = Arc::new(FunctionSpec {
@@ -44,7 +51,7 @@ pub fn add_functions(fns: &mut Functions) {
             let new_style = group.style.into_style_id();
             let new_options = options.having_style(new_style);
             let new_options = new_options.as_ref().unwrap_or(options);
-            let new_options = new_options.clone().with_font("");
+            let new_options = new_options.with_font("");
 
             sizing_group(&group.body, &new_options, options).into()
         })),
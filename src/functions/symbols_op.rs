@@ -1,11 +1,17 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::parse_node::ParseNodeType;
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::{parse_node::ParseNode, symbols::Atom};
+
+#[cfg(feature = "html")]
+use crate::build_common::math_sym;
+
+#[cfg(feature = "mathml")]
 use crate::{
-    build_common::math_sym,
     mathml,
     mathml_tree::{MathNode, MathNodeType},
-    parse_node::{ParseNode, ParseNodeType},
-    symbols::Atom,
     tree::ClassList,
     util::FontVariant,
 };
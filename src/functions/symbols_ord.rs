@@ -1,17 +1,24 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::parse_node::ParseNodeType;
+
+#[cfg(feature = "html")]
+use crate::build_common;
+
+#[cfg(feature = "mathml")]
 use crate::{
-    build_common,
     expander::Mode,
     mathml,
     mathml_tree::{MathNode, MathNodeType},
-    parse_node::{ParseNode, ParseNodeType},
+    parse_node::ParseNode,
     tree::ClassList,
     util::{find_assoc_data, FontVariant},
+    FontShape,
 };
 
 use super::{BuilderFunctionSpec, FunctionPropSpec, Functions};
 
+#[cfg(feature = "mathml")]
 const DEFAULT_VARIANT: &'static [(MathNodeType, FontVariant)] = &[
     (MathNodeType::Mi, FontVariant::Italic),
     (MathNodeType::Mn, FontVariant::Normal),
@@ -83,6 +90,11 @@ pub fn add_functions(fns: &mut Functions) {
                 node.set_attribute("mathvariant", variant.as_str());
             }
 
+            // MathML has no small caps variant, so fall back to CSS
+            if node_type == MathNodeType::MText && options.font_shape == Some(FontShape::TextSc) {
+                node.set_attribute("style", "font-variant: small-caps");
+            }
+
             node.into()
         })),
     });
@@ -1,31 +1,43 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
+use crate::prelude::*;
 use crate::{
-    build_common::make_span,
-    dom_tree::CssStyle,
     expander::Mode,
-    html, mathml,
     parse_node::{NodeInfo, ParseNode, ParseNodeType, TextNode},
-    parser::ParseError,
     util::ArgType,
     FontShape, FontWeight, Options,
 };
 
+#[cfg(feature = "html")]
+use crate::{build_common::make_span, dom_tree::CssStyle, dom_tree::HtmlNode, html};
+#[cfg(feature = "mathml")]
+use crate::{mathml, mathml_tree::MathmlNode};
+
 use super::{ord_argument, FunctionPropSpec, FunctionSpec, Functions};
 
-fn options_with_font(group: &TextNode, options: Options) -> Options {
+pub(crate) fn options_with_font<'a>(group: &TextNode, options: Options<'a>) -> Options<'a> {
     if let Some(font) = group.font.as_deref() {
         match font {
-            "\\textrm" => options.with_text_font_family("textrm"),
-            "\\textsf" => options.with_text_font_family("textsf"),
-            "\\texttt" => options.with_text_font_family("texttt"),
-            "\\textnormal" => options.with_text_font_family("textnormal"),
+            "\\textrm" | "\\rmfamily" => options.with_text_font_family("textrm"),
+            "\\textsf" | "\\sffamily" => options.with_text_font_family("textsf"),
+            "\\texttt" | "\\ttfamily" => options.with_text_font_family("texttt"),
+            "\\textnormal" | "\\normalfont" => options.with_normal_text_font(),
             // TODO: katex includes \\text but sets it to undefind which.. doesn't seem like it does anything?
-            "\\textbf" => options.with_text_font_weight(FontWeight::TextBf),
-            "\\textmd" => options.with_text_font_weight(FontWeight::TextMd),
+            "\\textbf" | "\\bfseries" => options.with_text_font_weight(FontWeight::TextBf),
+            "\\textmd" | "\\mdseries" => options.with_text_font_weight(FontWeight::TextMd),
 
-            "\\textit" => options.with_text_font_shape(FontShape::TextIt),
-            "\\textup" => options.with_text_font_shape(FontShape::TextUp),
+            "\\textit" | "\\itshape" => options.with_text_font_shape(FontShape::TextIt),
+            "\\textup" | "\\upshape" => options.with_text_font_shape(FontShape::TextUp),
+            "\\textsl" | "\\slshape" => options.with_text_font_shape(FontShape::TextSl),
+            "\\textsc" | "\\scshape" => options.with_text_font_shape(FontShape::TextSc),
+            // \\emph switches to italic, or back to upright if the text is already italic
+            "\\emph" | "\\em" => {
+                if options.is_text_italic() {
+                    options.with_text_font_shape(FontShape::TextUp)
+                } else {
+                    options.with_text_font_shape(FontShape::TextIt)
+                }
+            }
 
             // fallback: leave options unchanged
             _ => options,
@@ -35,6 +47,59 @@ fn options_with_font(group: &TextNode, options: Options) -> Options {
     }
 }
 
+/// Takes font options and returns the appropriate font lookup name
+pub(crate) fn retrieve_text_font_name(
+    font_family: &str,
+    font_weight: Option<FontWeight>,
+    font_shape: Option<FontShape>,
+) -> String {
+    // TODO: we could make these into precomputed static strs or an enum
+    let base_font_name = match font_family {
+        "amsrm" => "AMS",
+        "textrm" => "Main",
+        "textsf" => "SansSerif",
+        "texttt" => "Typewriter",
+        // use fonts added by plugin
+        _ => font_family,
+    };
+
+    let is_bold = font_weight == Some(FontWeight::TextBf);
+    let is_italic = font_shape.map(|shape| shape.is_italic()).unwrap_or(false);
+    let font_styles_name = if is_bold && is_italic {
+        "BoldItalic"
+    } else if is_bold {
+        "Bold"
+    } else if is_italic {
+        "Italic"
+    } else {
+        "Regular"
+    };
+
+    format!("{}-{}", base_font_name, font_styles_name)
+}
+
+#[cfg(feature = "html")]
+fn html_builder(group: &ParseNode, options: &Options) -> HtmlNode {
+    let ParseNode::Text(group) = group else { unreachable!() };
+    let options = options_with_font(group, options.clone_alter());
+    let inner = html::build_expression(&group.body, &options, html::RealGroup::True, (None, None));
+
+    make_span(
+        vec!["mord".to_string(), "text".to_string()],
+        inner,
+        Some(&options),
+        CssStyle::default(),
+    )
+    .into()
+}
+
+#[cfg(feature = "mathml")]
+fn mathml_builder(group: &ParseNode, options: &Options) -> MathmlNode {
+    let ParseNode::Text(group) = group else { unreachable!() };
+    let options = options_with_font(group, options.clone_alter());
+    mathml::build_expression_row(&group.body, &options, None)
+}
+
 pub fn add_functions(fns: &mut Functions) {
     let text = Arc::new(FunctionSpec {
         prop: FunctionPropSpec::new_num_args(ParseNodeType::Text, 1)
@@ -50,26 +115,9 @@ pub fn add_functions(fns: &mut Functions) {
             }))
         }),
         #[cfg(feature = "html")]
-        html_builder: Some(Box::new(|group, options| {
-            let ParseNode::Text(group) = group else { unreachable!() };
-            let options = options_with_font(group, options.clone_alter());
-            let inner =
-                html::build_expression(&group.body, &options, html::RealGroup::True, (None, None));
-
-            make_span(
-                vec!["mord".to_string(), "text".to_string()],
-                inner,
-                Some(&options),
-                CssStyle::default(),
-            )
-            .into()
-        })),
+        html_builder: Some(Box::new(html_builder)),
         #[cfg(feature = "mathml")]
-        mathml_builder: Some(Box::new(|group, options| {
-            let ParseNode::Text(group) = group else { unreachable!() };
-            let options = options_with_font(group, options.clone_alter());
-            mathml::build_expression_row(&group.body, &options, None)
-        })),
+        mathml_builder: Some(Box::new(mathml_builder)),
     });
 
     fns.insert_for_all_str(
@@ -86,8 +134,55 @@ pub fn add_functions(fns: &mut Functions) {
             // Font Shapes
             "\\textit",
             "\\textup",
+            "\\textsl",
+            "\\textsc",
+            "\\emph",
         ]
         .into_iter(),
         text,
     );
+
+    // Font switches, which apply to the rest of the group. Like LaTeX, these are only for text,
+    // with `\mathbf` and the like for math.
+    let switch = Arc::new(FunctionSpec {
+        prop: FunctionPropSpec::new_num_args(ParseNodeType::Text, 0)
+            .with_allowed_in_text(true)
+            .with_allowed_in_math(false),
+        handler: Box::new(|ctx, _args, _opt_args| {
+            let body = ctx
+                .parser
+                .dispatch_parse_expression(true, ctx.break_on_token_text)?;
+
+            Ok(ParseNode::Text(TextNode {
+                body,
+                font: Some(Cow::Owned(ctx.func_name.to_string())),
+                info: NodeInfo::new_mode(ctx.parser.mode()),
+            }))
+        }),
+        #[cfg(feature = "html")]
+        html_builder: Some(Box::new(html_builder)),
+        #[cfg(feature = "mathml")]
+        mathml_builder: Some(Box::new(mathml_builder)),
+    });
+
+    fns.insert_for_all_str(
+        [
+            // Font families
+            "\\rmfamily",
+            "\\sffamily",
+            "\\ttfamily",
+            "\\normalfont",
+            // Font weights
+            "\\bfseries",
+            "\\mdseries",
+            // Font shapes
+            "\\itshape",
+            "\\upshape",
+            "\\slshape",
+            "\\scshape",
+            "\\em",
+        ]
+        .into_iter(),
+        switch,
+    );
 }
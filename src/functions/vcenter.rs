@@ -1,13 +1,16 @@
-use std::borrow::Cow;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
+use crate::parse_node::{NodeInfo, ParseNode, ParseNodeType, VCenterNode};
+
+#[cfg(feature = "html")]
+use crate::{build_common, dom_tree::WithHtmlDomNode, html};
+
+#[cfg(feature = "mathml")]
 use crate::{
-    build_common,
-    dom_tree::WithHtmlDomNode,
-    html, mathml,
+    mathml,
     mathml_tree::{MathNode, MathNodeType},
-    parse_node::{NodeInfo, ParseNode, ParseNodeType, VCenterNode},
-    parser::ParseError,
     tree::ClassList,
 };
 
@@ -1,6 +1,6 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
-use crate::parse_node::{ParseNode, ParseNodeType};
+use crate::parse_node::ParseNodeType;
 
 use super::{BuilderFunctionSpec, FunctionPropSpec, Functions};
 
@@ -9,12 +9,13 @@ use crate::{build_common, dom_tree::CssStyle, Mode};
 #[cfg(feature = "mathml")]
 use crate::mathml_tree::{MathNode, MathNodeType, TextNode};
 #[cfg(any(feature = "html", feature = "mathml"))]
-use crate::tree::ClassList;
+use crate::{parse_node::ParseNode, prelude::*, tree::ClassList};
 
 /// Converts verb group into body string.
 ///
 /// \verb* replaces each space with an open box \u2423
 /// \verb replaces each space with a no-break space \xA0
+#[cfg(any(feature = "html", feature = "mathml"))]
 fn make_verb(body: &str, star: bool) -> String {
     if star {
         body.replace(' ', "\u{2423}")
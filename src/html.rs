@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use crate::{
     build_common::{self, make_empty_span, make_span, make_span_s, make_v_list, VListElemShift, VListParam},
@@ -6,7 +6,7 @@ use crate::{
     functions,
     parse_node::{ArrayNode, ArrayTag, ParseNode},
     style::SCRIPT_STYLE,
-    spacing_data::{SPACINGS, TIGHT_SPACINGS},
+    spacing_data::{DomType, SPACINGS, TIGHT_SPACINGS},
     tree::ClassList,
     unit::{calculate_size, make_em, Measurement},
     util::{find_assoc_data, has_class},
@@ -33,46 +33,6 @@ pub(crate) enum RealGroup {
     Root,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum DomType {
-    MOrd,
-    MOp,
-    MBin,
-    MRel,
-    MOpen,
-    MClose,
-    MPunct,
-    MInner,
-}
-impl DomType {
-    pub fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "mord" => Some(DomType::MOrd),
-            "mop" => Some(DomType::MOp),
-            "mbin" => Some(DomType::MBin),
-            "mrel" => Some(DomType::MRel),
-            "mopen" => Some(DomType::MOpen),
-            "mclose" => Some(DomType::MClose),
-            "mpunct" => Some(DomType::MPunct),
-            "minner" => Some(DomType::MInner),
-            _ => None,
-        }
-    }
-
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            DomType::MOrd => "mord",
-            DomType::MOp => "mop",
-            DomType::MBin => "mbin",
-            DomType::MRel => "mrel",
-            DomType::MOpen => "mopen",
-            DomType::MClose => "mclose",
-            DomType::MPunct => "mpunct",
-            DomType::MInner => "minner",
-        }
-    }
-}
-
 /// Take an entire parse tree and build it into an appropriate set of HTML nodes.
 pub(crate) fn build_html(tree: &[ParseNode], options: &Options) -> Span<HtmlNode> {
     // Strip off any outer tag wrapper
@@ -251,9 +211,9 @@ pub(crate) fn build_expression(
         };
 
         // TODO: don't clone
-        opts.unwrap_or_else(|| options.clone())
+        opts.unwrap_or(*options)
     } else {
-        options.clone()
+        *options
     };
 
     // Dummy spans for determining spacings between surrounding atoms.
@@ -784,7 +744,7 @@ pub(crate) fn build_leftright(group: &crate::parse_node::LeftRightNode, options:
         &group.body,
         options,
         RealGroup::True,
-        (Some(crate::html::DomType::MOpen), Some(crate::html::DomType::MClose)),
+        (Some(DomType::MOpen), Some(DomType::MClose)),
     );
 
     let mut inner_height = 0.0f64;
@@ -820,9 +780,9 @@ pub(crate) fn build_leftright(group: &crate::parse_node::LeftRightNode, options:
         make_null_delimiter(options, vec!["mclose".to_string()]).into()
     } else {
         let right_options = if let Some(ref color) = group.right_color {
-            options.clone_alter().with_color(color.clone())
+            options.clone_alter().with_color(color)
         } else {
-            options.clone()
+            *options
         };
         crate::delimiter::left_right_delim(
             &group.right,
@@ -0,0 +1,364 @@
+//! Incremental parsing and layout, for editors that render a formula after every keystroke.
+//!
+//! A [`Formula`] keeps the source, its parse tree and the layouts of its subtrees. An edit
+//! inside a braced group only re-lexes and re-parses the smallest group that encloses it; the
+//! rest of the tree is kept, with its locations moved to match the new source. Laying the
+//! formula out then reuses the layout of every subtree that is unchanged and built with the
+//! same options.
+//!
+//! Definitions (`\def`, `\newcommand`, `\tag`, ...) and the macros of the config can change how
+//! text outside of their group is parsed, so an edit to a formula that uses them, or that adds
+//! them, always parses the whole formula again.
+//!
+//! ```
+//! use aliter::{incremental::{Formula, Reparse, TextEdit}, parser::ParserConfig};
+//!
+//! let mut formula = Formula::new(r"\frac{a}{b+c}", ParserConfig::default());
+//! formula.layout().unwrap();
+//!
+//! // Replace the `c` in the denominator
+//! let reparse = formula.edit(&TextEdit::new(11..12, "d^2"));
+//! assert_eq!(reparse, Reparse::Group(8..15));
+//! assert_eq!(formula.source(), r"\frac{a}{b+d^2}");
+//!
+//! formula.layout().unwrap();
+//! assert_eq!(formula.layout_stats().reused, 1);
+//! ```
+
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::{
+    expander::Mode,
+    ir::{
+        builder::{build_ir_memoized, LayoutMemo},
+        IrBuilderConfig, MathLayout,
+    },
+    lexer::{Lexer, LexerConf},
+    parse_node::ParseNode,
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    util::SourceLocation,
+    Options,
+};
+
+/// Commands whose effects can reach outside of the group they're in
+const DEFINITION_COMMANDS: &[&str] = &[
+    "\\def",
+    "\\gdef",
+    "\\edef",
+    "\\xdef",
+    "\\let",
+    "\\futurelet",
+    "\\global",
+    "\\long",
+    "\\newcommand",
+    "\\renewcommand",
+    "\\providecommand",
+    "\\DeclareMathOperator",
+    "\\tag",
+    "\\notag",
+    "\\nonumber",
+];
+
+/// A replacement of part of the source, like [`String::replace_range`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The byte range of the source to replace
+    pub range: Range<usize>,
+    /// The text to replace it with
+    pub text: String,
+}
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> TextEdit {
+        TextEdit {
+            range,
+            text: text.into(),
+        }
+    }
+}
+
+/// What was parsed again after an edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reparse {
+    /// Only the group at this byte range of the new source, including its braces
+    Group(Range<usize>),
+    /// The whole formula
+    Full,
+}
+
+/// How the last [`Formula::layout`] was built
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutStats {
+    /// Subtrees whose layout was reused from a previous layout
+    pub reused: usize,
+    /// Subtrees whose layout was built and kept for later layouts
+    pub built: usize,
+}
+
+/// A formula that is parsed and laid out again incrementally as it is edited.
+/// See the [module documentation](self).
+#[derive(Debug)]
+pub struct Formula {
+    source: String,
+    conf: ParserConfig,
+    tree: Result<Vec<ParseNode>, ParseError>,
+    /// Whether the source uses definitions or the macros of the config, see
+    /// [`has_definitions`]
+    has_definitions: bool,
+    options: Options<'static>,
+    ir_config: IrBuilderConfig,
+    memo: LayoutMemo,
+}
+impl Formula {
+    pub fn new(source: impl Into<String>, conf: ParserConfig) -> Formula {
+        let source = source.into();
+        let options = Options::from_parser_conf(&conf);
+        let mut formula = Formula {
+            source,
+            conf,
+            tree: Ok(Vec::new()),
+            has_definitions: false,
+            options,
+            ir_config: IrBuilderConfig::default(),
+            memo: LayoutMemo::default(),
+        };
+        formula.parse_full();
+        formula
+    }
+
+    /// Set the configuration used by [`Formula::layout`]
+    pub fn with_ir_config(mut self, ir_config: IrBuilderConfig) -> Formula {
+        self.ir_config = ir_config;
+        self.memo = LayoutMemo::default();
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The parse tree of the current source, with locations into it
+    pub fn tree(&self) -> Result<&[ParseNode], &ParseError> {
+        self.tree.as_deref()
+    }
+
+    /// Apply the edit to the source and update the parse tree.
+    ///
+    /// # Panics
+    /// Like [`String::replace_range`], if the range is out of bounds or does not lie on `char`
+    /// boundaries.
+    pub fn edit(&mut self, edit: &TextEdit) -> Reparse {
+        let group = match &self.tree {
+            Ok(tree) if !self.has_definitions => find_group(tree, &self.source, &edit.range),
+            _ => None,
+        };
+        self.source.replace_range(edit.range.clone(), &edit.text);
+
+        let Some((path, old_range)) = group else {
+            self.parse_full();
+            return Reparse::Full;
+        };
+
+        let new_range = old_range.start..shift(old_range.end, edit);
+        match self.parse_group(&path, new_range.clone(), edit) {
+            Some(()) => Reparse::Group(new_range),
+            None => {
+                self.parse_full();
+                Reparse::Full
+            }
+        }
+    }
+
+    /// Lay the formula out, reusing the layouts of unchanged subtrees from previous layouts
+    pub fn layout(&self) -> Result<MathLayout, ParseError> {
+        let tree = self.tree.as_ref().map_err(Clone::clone)?;
+        Ok(build_ir_memoized(
+            tree,
+            &self.source,
+            &self.options,
+            &self.ir_config,
+            &self.memo,
+        ))
+    }
+
+    pub fn layout_stats(&self) -> LayoutStats {
+        LayoutStats {
+            reused: self.memo.reused.get(),
+            built: self.memo.built.get(),
+        }
+    }
+
+    fn parse_full(&mut self) {
+        self.has_definitions = has_definitions(&self.source, &self.conf);
+        self.tree = parse_tree(&self.source, self.conf.clone());
+    }
+
+    /// Parse the group at `range` of the new source again and put it in the tree at `path`,
+    /// or return `None` if the group can't be parsed on its own
+    fn parse_group(&mut self, path: &[usize], range: Range<usize>, edit: &TextEdit) -> Option<()> {
+        let text = &self.source[range.clone()];
+        if has_definitions(text, &self.conf) {
+            return None;
+        }
+
+        // An edit that unbalances the braces parses as something other than a single group
+        let nodes = parse_tree(text, self.conf.clone()).ok()?;
+        let mut group = match nodes.as_slice() {
+            [node @ ParseNode::OrdGroup(_)]
+                if node.info().mode == Mode::Math
+                    && node.loc() == Some(SourceLocation(0..text.len())) =>
+            {
+                node.clone()
+            }
+            _ => return None,
+        };
+        offset_locs(&mut group, 0, range.start as isize);
+
+        let tree = self.tree.as_mut().ok()?;
+        let delta = edit.text.len() as isize - edit.range.len() as isize;
+        for node in tree.iter_mut() {
+            offset_locs(node, edit.range.end, delta);
+        }
+        *node_at_mut(tree, path) = group;
+
+        Some(())
+    }
+}
+
+/// The position in the new source of a position in the old source that is not in the edit
+fn shift(pos: usize, edit: &TextEdit) -> usize {
+    pos - edit.range.len() + edit.text.len()
+}
+
+/// Whether the source uses commands that can change how text outside of their group is parsed,
+/// or the macros of the config, which can use them.
+/// Source that can't be lexed counts as having definitions, so that it is parsed in full to get
+/// the error.
+fn has_definitions(source: &str, conf: &ParserConfig) -> bool {
+    let mut lexer = Lexer::new(
+        source,
+        LexerConf {
+            strict: conf.strict,
+        },
+    );
+    loop {
+        let token = match lexer.lex() {
+            Ok(token) if token.is_eof() => return false,
+            Ok(token) => token,
+            Err(_) => return true,
+        };
+
+        let content = &*token.content;
+        let mut chars = content.chars();
+        let is_macro = match (chars.next(), chars.next()) {
+            (Some(ch), None) => conf.macros.contains_letter_macro(ch),
+            _ => conf.macros.contains_back_macro(content),
+        };
+        if is_macro || DEFINITION_COMMANDS.contains(&content) {
+            return true;
+        }
+    }
+}
+
+/// Find the smallest group in math mode, written with braces, that contains `edit` without
+/// touching its braces. Returns the indices of the children to follow to get to it (see
+/// [`node_at_mut`]) and its range in the source.
+fn find_group(
+    tree: &[ParseNode],
+    source: &str,
+    edit: &Range<usize>,
+) -> Option<(Vec<usize>, Range<usize>)> {
+    fn visit(
+        node: &ParseNode,
+        source: &str,
+        edit: &Range<usize>,
+        path: &mut Vec<usize>,
+        found: &mut Option<(Vec<usize>, Range<usize>)>,
+    ) {
+        if let Some(range) = group_range(node, source) {
+            let contains = range.start < edit.start && edit.end < range.end;
+            let is_smaller = found.as_ref().is_none_or(|(_, f)| range.len() < f.len());
+            if contains && is_smaller {
+                *found = Some((path.clone(), range));
+            }
+        }
+
+        // The name of an environment is not parsed as math
+        if let ParseNode::Environment(_) = node {
+            return;
+        }
+        let mut index = 0;
+        node.for_each_child(&mut |child| {
+            path.push(index);
+            visit(child, source, edit, path, found);
+            path.pop();
+            index += 1;
+        });
+    }
+
+    let mut found = None;
+    let mut path = Vec::new();
+    for (index, node) in tree.iter().enumerate() {
+        path.push(index);
+        visit(node, source, edit, &mut path, &mut found);
+        path.pop();
+    }
+
+    // Macros can put the same group in the tree more than once, which replacing a single node
+    // would get wrong
+    let (_, range) = found.as_ref()?;
+    let mut uses = 0;
+    for node in tree {
+        count_locs(node, range, &mut uses);
+    }
+    found.filter(|_| uses == 1)
+}
+
+/// The range of the node if it is a group in math mode that is written with braces
+fn group_range(node: &ParseNode, source: &str) -> Option<Range<usize>> {
+    let ParseNode::OrdGroup(group) = node else {
+        return None;
+    };
+    let range = group.info.loc.as_ref()?.0.clone();
+    let text = source.get(range.clone())?;
+    let is_braced = text.len() >= 2 && text.starts_with('{') && text.ends_with('}');
+    (group.info.mode == Mode::Math && is_braced).then_some(range)
+}
+
+/// Count the nodes in the subtree that are at `range`
+fn count_locs(node: &ParseNode, range: &Range<usize>, count: &mut usize) {
+    if node.info().loc.as_ref().map(|loc| &loc.0) == Some(range) {
+        *count += 1;
+    }
+    node.for_each_child(&mut |child| count_locs(child, range, count));
+}
+
+/// Move every location at or after `from` by `delta`
+fn offset_locs(node: &mut ParseNode, from: usize, delta: isize) {
+    if let Some(loc) = &mut node.info_mut().loc {
+        for pos in [&mut loc.0.start, &mut loc.0.end] {
+            if *pos >= from {
+                *pos = pos.checked_add_signed(delta).unwrap();
+            }
+        }
+    }
+    node.for_each_child_mut(&mut |child| offset_locs(child, from, delta));
+}
+
+/// Get the node at the path returned by [`find_group`]
+fn node_at_mut<'a>(tree: &'a mut [ParseNode], path: &[usize]) -> &'a mut ParseNode {
+    let mut node = &mut tree[path[0]];
+    for &index in &path[1..] {
+        let mut child = None;
+        let mut i = 0;
+        node.for_each_child_mut(&mut |c| {
+            if i == index {
+                child = Some(c);
+            }
+            i += 1;
+        });
+        node = child.unwrap();
+    }
+    node
+}
@@ -18,17 +18,29 @@
 //! // layout.root contains MathElement::Fraction with explicit positioning
 //! ```
 
+use alloc::borrow::Cow;
+use core::cell::{Cell, RefCell};
+
+use hashbrown::HashMap;
+
+use crate::prelude::*;
 use crate::array::ColSeparationType;
 use crate::expander::Mode;
-use crate::font_metrics::{get_character_metrics, CharacterMetrics, FontMetrics};
-use crate::html::DomType;
+use crate::font_metrics::{
+    font_metrics_version, get_character_metrics, get_small_caps_metrics, CharacterMetrics,
+    FontMetrics, SMALL_CAPS_SCALE,
+};
+use crate::functions::horiz_brace;
+use crate::functions::text::{options_with_font, retrieve_text_font_name};
 use crate::parse_node::*;
-use crate::spacing_data::{SPACINGS, TIGHT_SPACINGS};
+use crate::spacing_data::{DomType, SPACINGS, TIGHT_SPACINGS};
+use crate::stretchy;
+use crate::svg_geometry;
 use crate::style::{DISPLAY_STYLE, SCRIPT_STYLE, SCRIPT_SCRIPT_STYLE};
 use crate::symbols::{self, Atom};
 use crate::unit::calculate_size;
 use crate::util::find_assoc_data;
-use crate::Options;
+use crate::{FontShape, Options};
 
 use super::types::*;
 
@@ -81,16 +93,19 @@ impl IrBuilderConfig {
 /// propagated through the layout tree.
 pub struct LayoutContext<'a> {
     /// The options for this context (owned to allow style changes)
-    options: Options,
+    options: Options<'a>,
     /// The builder configuration (shared)
     pub config: &'a IrBuilderConfig,
+    /// Layouts from previous builds, see [`build_ir_memoized`]
+    memo: Option<MemoRef<'a>>,
 }
 
 impl<'a> LayoutContext<'a> {
-    pub fn new(options: &Options, config: &'a IrBuilderConfig) -> Self {
+    pub fn new(options: &Options<'a>, config: &'a IrBuilderConfig) -> Self {
         Self {
-            options: options.clone(),
+            options: *options,
             config,
+            memo: None,
         }
     }
 
@@ -141,7 +156,7 @@ impl<'a> LayoutContext<'a> {
     }
 
     /// Get access to the options.
-    pub fn options(&self) -> &Options {
+    pub fn options(&self) -> &Options<'a> {
         &self.options
     }
 
@@ -150,10 +165,11 @@ impl<'a> LayoutContext<'a> {
     /// This properly updates the size multiplier based on the new style.
     pub fn with_style(&self, style: crate::style::StyleId) -> LayoutContext<'a> {
         let new_options = self.options.having_style(style)
-            .unwrap_or_else(|| self.options.clone());
+            .unwrap_or(self.options);
         LayoutContext {
             options: new_options,
             config: self.config,
+            memo: self.memo,
         }
     }
 
@@ -184,10 +200,20 @@ impl<'a> LayoutContext<'a> {
 
     /// Create a child context with a specific font.
     pub fn with_font(&self, font: &str) -> LayoutContext<'a> {
-        let new_options = self.options.clone().with_font(font.to_string());
+        let new_options = self.options.with_font(font.to_string());
         LayoutContext {
             options: new_options,
             config: self.config,
+            memo: self.memo,
+        }
+    }
+
+    /// Create a child context with the given options.
+    pub fn with_options(&self, options: Options<'a>) -> LayoutContext<'a> {
+        LayoutContext {
+            options,
+            config: self.config,
+            memo: self.memo,
         }
     }
 }
@@ -213,6 +239,125 @@ pub fn build_ir_with_config(
     MathLayout::new(root, is_display)
 }
 
+/// Build IR from a parse tree of `source`, reusing the layouts of subtrees that were built by
+/// previous calls with the same memo.
+/// The memo must only be used with one `config`.
+pub(crate) fn build_ir_memoized(
+    tree: &[ParseNode],
+    source: &str,
+    options: &Options,
+    config: &IrBuilderConfig,
+    memo: &LayoutMemo,
+) -> MathLayout {
+    memo.start(source);
+
+    let mut ctx = LayoutContext::new(options, config);
+    ctx.memo = Some(MemoRef { memo, source });
+    let root = build_expression(tree, &ctx);
+    let is_display = options.style.size() == DISPLAY_STYLE.size();
+    MathLayout::new(root, is_display)
+}
+
+// =============================================================================
+// Memoization
+// =============================================================================
+
+/// The most layouts kept for the same source text, which can appear under different styles
+const MAX_MEMO_ENTRIES_PER_SOURCE: usize = 8;
+
+#[derive(Debug)]
+struct MemoEntry {
+    node: ParseNode,
+    /// The options without a color, see [`MemoRef::get_or_build`]
+    options: Options<'static>,
+    element: MathElement,
+}
+
+/// Layouts of subtrees from previous builds, keyed by the source text of the subtree.
+/// A layout is only reused for a subtree that is equal, ignoring locations, and that is built
+/// with equal options, so a subtree that moved within the source is still reused.
+#[derive(Debug, Default)]
+pub(crate) struct LayoutMemo {
+    entries: RefCell<HashMap<Box<str>, Vec<MemoEntry>>>,
+    /// The [`font_metrics_version`] the entries were built with
+    metrics_version: Cell<u64>,
+    /// The number of subtrees reused in the current build
+    pub(crate) reused: Cell<usize>,
+    /// The number of subtrees built and added in the current build
+    pub(crate) built: Cell<usize>,
+}
+impl LayoutMemo {
+    /// Prepare for a build of `source`, dropping the entries that can't be used by it
+    fn start(&self, source: &str) {
+        let mut entries = self.entries.borrow_mut();
+        let version = font_metrics_version();
+        if self.metrics_version.replace(version) != version {
+            entries.clear();
+        }
+        // Only text that is still in the source can have the same subtree
+        entries.retain(|text, _| source.contains(&**text));
+
+        self.reused.set(0);
+        self.built.set(0);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MemoRef<'a> {
+    memo: &'a LayoutMemo,
+    source: &'a str,
+}
+impl<'a> MemoRef<'a> {
+    /// Get the layout of the node from the memo, or build it with `build` and add it
+    fn get_or_build(
+        self,
+        node: &ParseNode,
+        ctx: &LayoutContext,
+        build: fn(&ParseNode, &LayoutContext) -> MathElement,
+    ) -> MathElement {
+        // Leaves are cheap enough to build that they aren't worth a lookup
+        let mut has_children = false;
+        node.for_each_child(&mut |_| has_children = true);
+        let text = node
+            .loc()
+            .and_then(|loc| self.source.get(loc.0))
+            .filter(|_| has_children);
+        // The color of the options is only borrowed for this build, and as layouts never set one
+        // the callers that keep a memo don't give one either
+        let (Some(text), None) = (text, ctx.options.color) else {
+            return build(node, ctx);
+        };
+        let options = Options { color: None, ..ctx.options };
+
+        let found = self.memo.entries.borrow().get(text).and_then(|entries| {
+            entries
+                .iter()
+                .find(|e| e.options == options && e.node.eq_no_loc(node))
+                .map(|e| e.element.clone())
+        });
+        if let Some(element) = found {
+            self.memo.reused.set(self.memo.reused.get() + 1);
+            return element;
+        }
+
+        let element = build(node, ctx);
+        self.memo.built.set(self.memo.built.get() + 1);
+
+        let mut entries = self.memo.entries.borrow_mut();
+        let entries = entries.entry(text.into()).or_default();
+        if entries.len() >= MAX_MEMO_ENTRIES_PER_SOURCE {
+            entries.remove(0);
+        }
+        entries.push(MemoEntry {
+            node: node.clone(),
+            options,
+            element: element.clone(),
+        });
+
+        element
+    }
+}
+
 // =============================================================================
 // Atom Type Helpers
 // =============================================================================
@@ -352,6 +497,13 @@ fn build_expression(nodes: &[ParseNode], ctx: &LayoutContext) -> MathElement {
 
 /// Build a single parse node into an IR element.
 fn build_node(node: &ParseNode, ctx: &LayoutContext) -> MathElement {
+    match ctx.memo {
+        Some(memo) => memo.get_or_build(node, ctx, build_node_uncached),
+        None => build_node_uncached(node, ctx),
+    }
+}
+
+fn build_node_uncached(node: &ParseNode, ctx: &LayoutContext) -> MathElement {
     match node {
         ParseNode::MathOrd(ord) => build_math_ord(ord, ctx),
         ParseNode::TextOrd(ord) => build_text_ord(ord, ctx),
@@ -385,7 +537,7 @@ fn build_node(node: &ParseNode, ctx: &LayoutContext) -> MathElement {
         ParseNode::HBox(hbox) => build_hbox(hbox, ctx),
         ParseNode::XArrow(arrow) => build_xarrow(arrow, ctx),
         ParseNode::Enclose(enclose) => build_enclose(enclose, ctx),
-        ParseNode::HorizBrace(brace) => build_horiz_brace(brace, ctx),
+        ParseNode::HorizBrace(brace) => build_horiz_brace(brace, None, ctx),
         ParseNode::Lap(lap) => build_lap(lap, ctx),
         ParseNode::MathChoice(choice) => build_math_choice(choice, ctx),
         ParseNode::RaiseBox(rbox) => build_raise_box(rbox, ctx),
@@ -398,8 +550,20 @@ fn build_node(node: &ParseNode, ctx: &LayoutContext) -> MathElement {
         ParseNode::Html(html) => build_html_node(html, ctx),
         ParseNode::Tag(tag) => build_tag(tag, ctx),
 
-        // Fallback for unimplemented nodes
-        _ => {
+        // Fallback for unimplemented nodes, listed so that a new node type has to be handled
+        ParseNode::AccentToken(_)
+        | ParseNode::CdLabel(_)
+        | ParseNode::CdLabelParentNode(_)
+        | ParseNode::ColorToken(_)
+        | ParseNode::Cr(_)
+        | ParseNode::Environment(_)
+        | ParseNode::HtmlMathml(_)
+        | ParseNode::Infix(_)
+        | ParseNode::Internal(_)
+        | ParseNode::LeftRightRight(_)
+        | ParseNode::OpToken(_)
+        | ParseNode::Raw(_)
+        | ParseNode::Size(_) => {
             // Return a placeholder element
             // TODO: Implement remaining node types
             MathElement::HBox {
@@ -448,7 +612,19 @@ fn build_symbol(text: &str, ctx: &LayoutContext, math_mode: bool) -> MathElement
 
     // Get metrics for the first character of the replacement text
     let first_char = render_text.chars().next().unwrap_or('?');
-    let metrics = ctx.char_metrics(first_char, font_name, mode);
+    let small_caps = !math_mode && ctx.options.font_shape == Some(FontShape::TextSc);
+    let metrics = if small_caps {
+        get_small_caps_metrics(first_char, &font_name, mode)
+    } else {
+        ctx.char_metrics(first_char, &font_name, mode)
+    };
+
+    // There are no small caps fonts, so lowercase letters are drawn as smaller uppercase letters
+    let (render_text, font_scale) = if small_caps && first_char.is_lowercase() {
+        (render_text.to_uppercase(), SMALL_CAPS_SCALE)
+    } else {
+        (render_text.to_string(), 1.0)
+    };
 
     let (width, height, depth, italic, skew) = if let Some(m) = metrics {
         (m.width, m.height, m.depth, m.italic, m.skew)
@@ -460,10 +636,10 @@ fn build_symbol(text: &str, ctx: &LayoutContext, math_mode: bool) -> MathElement
     let size = ctx.size_multiplier();
 
     MathElement::Text {
-        text: render_text.to_string(),
+        text: render_text,
         style: TextStyle {
-            font: Some(Font::from_name(font_name)),
-            size,
+            font: Some(Font::from_name(&font_name)),
+            size: size * font_scale,
             color: ctx.options.get_color(),
             italic_correction: if math_mode { italic } else { 0.0 },
             skew,
@@ -475,21 +651,25 @@ fn build_symbol(text: &str, ctx: &LayoutContext, math_mode: bool) -> MathElement
 }
 
 /// Determine which font to use for a symbol.
-fn determine_font_for_symbol(text: &str, ctx: &LayoutContext, math_mode: bool) -> &'static str {
+fn determine_font_for_symbol(
+    text: &str,
+    ctx: &LayoutContext,
+    math_mode: bool,
+) -> Cow<'static, str> {
     let mode = if math_mode { Mode::Math } else { Mode::Text };
 
     // Check if there's a font override
     if !ctx.options.font.is_empty() {
-        match ctx.options.font.as_ref() {
-            "mathbf" => return "Main-Bold",
-            "mathit" => return "Main-Italic",
-            "mathrm" => return "Main-Regular",
-            "mathsf" => return "SansSerif-Regular",
-            "mathtt" => return "Typewriter-Regular",
-            "mathcal" => return "Caligraphic-Regular",
-            "mathfrak" => return "Fraktur-Regular",
-            "mathscr" => return "Script-Regular",
-            "boldsymbol" => return "Math-BoldItalic",
+        match ctx.options.font {
+            "mathbf" => return Cow::Borrowed("Main-Bold"),
+            "mathit" => return Cow::Borrowed("Main-Italic"),
+            "mathrm" => return Cow::Borrowed("Main-Regular"),
+            "mathsf" => return Cow::Borrowed("SansSerif-Regular"),
+            "mathtt" => return Cow::Borrowed("Typewriter-Regular"),
+            "mathcal" => return Cow::Borrowed("Caligraphic-Regular"),
+            "mathfrak" => return Cow::Borrowed("Fraktur-Regular"),
+            "mathscr" => return Cow::Borrowed("Script-Regular"),
+            "boldsymbol" => return Cow::Borrowed("Math-BoldItalic"),
             _ => {}
         }
     }
@@ -499,16 +679,25 @@ fn determine_font_for_symbol(text: &str, ctx: &LayoutContext, math_mode: bool) -
         // Check if the symbol is defined in the symbol table
         if let Some(sym) = symbols::SYMBOLS.get(mode, text) {
             match sym.font {
-                symbols::Font::Main => return "Main-Regular",
-                symbols::Font::Ams => return "AMS-Regular",
+                symbols::Font::Main => return Cow::Borrowed("Main-Regular"),
+                symbols::Font::Ams => return Cow::Borrowed("AMS-Regular"),
             }
         }
 
         // Default to Math-Italic for math mode
-        "Math-Italic"
+        Cow::Borrowed("Math-Italic")
     } else {
-        // Text mode uses Main-Regular
-        "Main-Regular"
+        // Text mode uses the current text font, which defaults to Main-Regular
+        let family = if ctx.options.font_family.is_empty() {
+            "textrm"
+        } else {
+            ctx.options.font_family
+        };
+        Cow::Owned(retrieve_text_font_name(
+            family,
+            ctx.options.font_weight,
+            ctx.options.font_shape,
+        ))
     }
 }
 
@@ -667,6 +856,7 @@ fn get_italic_correction(elem: &MathElement) -> f64 {
         | MathElement::Fraction { layout, .. }
         | MathElement::Radical { layout, .. }
         | MathElement::Accent { layout, .. }
+        | MathElement::Brace { layout, .. }
         | MathElement::Delimited { layout, .. }
         | MathElement::LargeOp { layout, .. }
         | MathElement::Array { layout, .. } => get_italic_correction(layout),
@@ -675,6 +865,22 @@ fn get_italic_correction(elem: &MathElement) -> f64 {
 }
 
 fn build_supsub(supsub: &SupSubNode, ctx: &LayoutContext) -> MathElement {
+    // Operator names with limits place their scripts above and below
+    if let Some(ParseNode::OperatorName(opname)) = supsub.base.as_deref() {
+        if opname.always_handle_sup_sub && (ctx.is_display() || opname.limits) {
+            let nucleus = build_operator_name(opname, ctx);
+            return build_limits(nucleus, supsub.sup.as_deref(), supsub.sub.as_deref(), ctx);
+        }
+    }
+
+    // A script on the open side of a horizontal brace is its label
+    if let Some(ParseNode::HorizBrace(brace)) = supsub.base.as_deref() {
+        if horiz_brace::has_label(brace, supsub.sup.is_some(), supsub.sub.is_some()) {
+            let label = supsub.sup.as_deref().or(supsub.sub.as_deref());
+            return build_horiz_brace(brace, label, ctx);
+        }
+    }
+
     let metrics = ctx.metrics();
 
     // Build base
@@ -844,7 +1050,7 @@ fn build_sqrt(sqrt: &SqrtNode, ctx: &LayoutContext) -> MathElement {
 
     // Build the surd path element
     let surd = MathElement::Path {
-        path_data: std::borrow::Cow::Borrowed("surd"), // Placeholder path name
+        path_data: alloc::borrow::Cow::Borrowed("surd"), // Placeholder path name
         width: advance_width,
         height: surd_height,
         shift: 0.0,
@@ -1018,7 +1224,7 @@ fn build_sizing(sizing: &SizingNode, ctx: &LayoutContext) -> MathElement {
     // Apply size override to context
     // Size is 1-based index, where 1 = \tiny, 10 = \Huge, 6 = \normalsize
     let new_options = ctx.options().having_size(sizing.size)
-        .unwrap_or_else(|| ctx.options().clone());
+        .unwrap_or(*ctx.options());
     let sized_ctx = LayoutContext::new(&new_options, ctx.config);
     build_expression(&sizing.body, &sized_ctx)
 }
@@ -1159,7 +1365,7 @@ fn build_stretchy_delimiter(delim: &str, height: f64, ctx: &LayoutContext) -> Ma
     let elem_height = height / 2.0 + shift;
 
     MathElement::Path {
-        path_data: std::borrow::Cow::Owned(format!("delimiter:{}", delim)),
+        path_data: alloc::borrow::Cow::Owned(format!("delimiter:{}", delim)),
         width,
         height: elem_height + depth,
         shift: -depth, // Shift so delimiter is centered on axis
@@ -1233,8 +1439,9 @@ fn build_left_right(lr: &LeftRightNode, ctx: &LayoutContext) -> MathElement {
 }
 
 fn build_text(text: &TextNode, ctx: &LayoutContext) -> MathElement {
-    // Build text mode content
-    build_expression(&text.body, ctx)
+    // Build text mode content with the font it selects
+    let text_ctx = ctx.with_options(options_with_font(text, ctx.options));
+    build_expression(&text.body, &text_ctx)
 }
 
 fn build_mclass(mclass: &MClassNode, ctx: &LayoutContext) -> MathElement {
@@ -1249,6 +1456,72 @@ fn build_operator_name(opname: &OperatorNameNode, ctx: &LayoutContext) -> MathEl
     build_expression(&opname.body, &font_ctx)
 }
 
+/// Build an operator with its limits placed above and below it (TeX Rule 13a).
+fn build_limits(
+    nucleus: MathElement,
+    sup: Option<&ParseNode>,
+    sub: Option<&ParseNode>,
+    ctx: &LayoutContext,
+) -> MathElement {
+    let metrics = ctx.metrics();
+    let (nucleus_width, nucleus_height, nucleus_depth) = nucleus.dimensions();
+
+    let sup = sup.map(|s| build_node(s, &ctx.for_superscript()));
+    let sub = sub.map(|s| build_node(s, &ctx.for_subscript()));
+
+    let width = [&sup, &sub]
+        .into_iter()
+        .flatten()
+        .map(|elem| elem.width())
+        .fold(nucleus_width, f64::max);
+
+    let mut height = nucleus_height;
+    let mut depth = nucleus_depth;
+
+    // Limits are centered over the nucleus
+    let sup_pos = sup.map(|s| {
+        let (sup_width, sup_height, sup_depth) = s.dimensions();
+        let kern = metrics.big_op_spacing1.max(metrics.big_op_spacing3 - sup_depth);
+        let y = nucleus_height + kern + sup_depth;
+        height = y + sup_height + metrics.big_op_spacing5;
+        Positioned::new(s, (width - sup_width) / 2.0, y)
+    });
+    let sub_pos = sub.map(|s| {
+        let (sub_width, sub_height, sub_depth) = s.dimensions();
+        let kern = metrics.big_op_spacing2.max(metrics.big_op_spacing4 - sub_height);
+        let y = -(nucleus_depth + kern + sub_height);
+        depth = -y + sub_depth + metrics.big_op_spacing5;
+        Positioned::new(s, (width - sub_width) / 2.0, y)
+    });
+
+    let mut children = vec![Positioned::new(
+        nucleus.clone(),
+        (width - nucleus_width) / 2.0,
+        0.0,
+    )];
+    children.extend(sup_pos.clone());
+    children.extend(sub_pos.clone());
+
+    let layout = MathElement::VBox {
+        children,
+        width,
+        height,
+        depth,
+    };
+
+    if ctx.config.semantic_mode {
+        MathElement::LargeOp {
+            nucleus: Box::new(nucleus),
+            superscript: sup_pos.map(Box::new),
+            subscript: sub_pos.map(Box::new),
+            limits: true,
+            layout: Box::new(layout),
+        }
+    } else {
+        layout
+    }
+}
+
 fn build_accent_under(accent: &AccentUnderNode, ctx: &LayoutContext) -> MathElement {
     let base = build_node(&accent.base, ctx);
     let accent_sym = build_symbol(&accent.label, ctx, true);
@@ -1411,52 +1684,102 @@ fn build_enclose(enclose: &EncloseNode, ctx: &LayoutContext) -> MathElement {
     }
 }
 
-fn build_horiz_brace(brace: &HorizBraceNode, ctx: &LayoutContext) -> MathElement {
-    // \overbrace, \underbrace
+fn build_horiz_brace(
+    brace: &HorizBraceNode,
+    label: Option<&ParseNode>,
+    ctx: &LayoutContext,
+) -> MathElement {
+    // \overbrace, \underbrace, \overbracket, \overparen, ...
     let base = build_node(&brace.base, ctx);
     let (base_width, base_height, base_depth) = base.dimensions();
 
-    // Build brace symbol
-    let brace_char = match brace.label.as_str() {
-        "\\overbrace" => "⏞",
-        "\\underbrace" => "⏟",
-        "\\overgroup" => "⏠",
-        "\\undergroup" => "⏡",
-        _ => "⏞",
+    // Build the brace as a single path stretched to the width of the base
+    let brace_path = if horiz_brace::is_bracket(brace) {
+        let (thickness, height) = horiz_brace::bracket_size(brace, &ctx.options);
+        MathElement::Path {
+            path_data: Cow::Owned(svg_geometry::bracket_path(
+                base_width * 1000.0,
+                thickness * 1000.0,
+                height * 1000.0,
+                brace.is_over,
+            )),
+            width: base_width,
+            height,
+            shift: 0.0,
+        }
+    } else {
+        let stretched = stretchy::stretched_path(&brace.label, base_width)
+            .unwrap_or_else(|| panic!("No stretchy path for {}", brace.label));
+        MathElement::Path {
+            path_data: Cow::Owned(stretched.path),
+            width: stretched.width,
+            height: stretched.height,
+            shift: 0.0,
+        }
     };
-    let brace_sym = build_symbol(brace_char, ctx, true);
-    let (_, brace_height, brace_depth) = brace_sym.dimensions();
+    let (brace_width, brace_height, _) = brace_path.dimensions();
+
+    // Build the label in script style
+    let label = label.map(|label| {
+        let label_ctx = if brace.is_over {
+            ctx.for_superscript()
+        } else {
+            ctx.for_subscript()
+        };
+        build_node(label, &label_ctx)
+    });
+    let label_width = label.as_ref().map(|l| l.width()).unwrap_or(0.0);
 
     let gap = 0.1; // Kerning between brace and content
+    let label_gap = 0.2; // Kerning between brace and label
 
-    let (children, height, depth) = if brace.is_over {
-        // Brace above content
-        let children = vec![
-            Positioned::new(brace_sym.clone(), 0.0, base_height + gap),
-            Positioned::at_origin(base.clone()),
-        ];
-        (children, base_height + gap + brace_height, base_depth)
+    // Everything is centered over the widest part
+    let width = base_width.max(brace_width).max(label_width);
+    let center = |w: f64| (width - w) / 2.0;
+
+    let brace_y = if brace.is_over {
+        base_height + gap
     } else {
-        // Brace below content
-        let brace_y = -(base_depth + gap + brace_height);
-        let children = vec![
-            Positioned::at_origin(base.clone()),
-            Positioned::new(brace_sym.clone(), 0.0, brace_y),
-        ];
-        (children, base_height, base_depth + gap + brace_height + brace_depth)
+        -(base_depth + gap + brace_height)
     };
+    let label = label.map(|label| {
+        let (label_width, label_height, label_depth) = label.dimensions();
+        let label_y = if brace.is_over {
+            brace_y + brace_height + label_gap + label_depth
+        } else {
+            brace_y - label_gap - label_height
+        };
+        Positioned::new(label, center(label_width), label_y)
+    });
+
+    let (mut height, mut depth) = if brace.is_over {
+        (brace_y + brace_height, base_depth)
+    } else {
+        (base_height, -brace_y)
+    };
+    if let Some(label) = &label {
+        height = height.max(label.y + label.element.height());
+        depth = depth.max(label.element.depth() - label.y);
+    }
+
+    let mut children = vec![
+        Positioned::new(base.clone(), center(base_width), 0.0),
+        Positioned::new(brace_path.clone(), center(brace_width), brace_y),
+    ];
+    children.extend(label.clone());
 
     let layout = MathElement::VBox {
         children,
-        width: base_width,
+        width,
         height,
         depth,
     };
 
     if ctx.config.semantic_mode {
-        MathElement::Accent {
+        MathElement::Brace {
             base: Box::new(base),
-            accent: Box::new(brace_sym),
+            brace: Box::new(brace_path),
+            label: label.map(Box::new),
             is_over: brace.is_over,
             layout: Box::new(layout),
         }
@@ -1468,7 +1791,7 @@ fn build_horiz_brace(brace: &HorizBraceNode, ctx: &LayoutContext) -> MathElement
 fn build_lap(lap: &LapNode, ctx: &LayoutContext) -> MathElement {
     // \llap, \rlap, \clap - overlapping content
     let inner = build_node(&lap.body, ctx);
-    let (width, height, depth) = inner.dimensions();
+    let (_, height, depth) = inner.dimensions();
 
     // Lap elements have zero width but preserve height/depth
     // The alignment class tells the renderer how to position
@@ -1769,7 +2092,7 @@ mod tests {
     use super::*;
     use crate::parser::ParserConfig;
 
-    fn default_options() -> Options {
+    fn default_options() -> Options<'static> {
         Options::from_parser_conf(&ParserConfig::default())
     }
 
@@ -1967,6 +2290,7 @@ mod tests {
             | MathElement::Scripts { layout, .. }
             | MathElement::Radical { layout, .. }
             | MathElement::Accent { layout, .. }
+            | MathElement::Brace { layout, .. }
             | MathElement::Delimited { layout, .. }
             | MathElement::LargeOp { layout, .. }
             | MathElement::Array { layout, .. } => {
@@ -141,7 +141,7 @@ fn convert_node(node: &HtmlNode) -> MathElement {
                     }
                 })
                 .next()
-                .unwrap_or(std::borrow::Cow::Borrowed(""));
+                .unwrap_or(alloc::borrow::Cow::Borrowed(""));
 
             let width = svg.attributes.get("width").and_then(|s| parse_em(s)).unwrap_or(0.0);
             let height = svg.attributes.get("height").and_then(|s| parse_em(s)).unwrap_or(0.0);
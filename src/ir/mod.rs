@@ -84,6 +84,10 @@ pub mod from_html;
 #[cfg(feature = "html")]
 pub mod to_html;
 
+// Serialization and standalone images
+pub mod to_json;
+pub mod to_svg;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,4 +147,35 @@ mod tests {
         });
         assert!(has_text, "Should find text elements in 'x + y'");
     }
+
+    #[test]
+    fn test_ir_to_json() {
+        use crate::{parse_tree, parser::ParserConfig, Options};
+
+        let conf = ParserConfig::default();
+        let tree = parse_tree(r"\frac{a}{b}", conf.clone()).unwrap();
+        let layout = build_ir(&tree, &Options::from_parser_conf(&conf));
+        let json = to_json::render(&layout);
+
+        assert!(json.starts_with("{\"displayMode\":false,"));
+        assert!(json.contains("\"type\":\"fraction\""));
+        assert!(json.contains("\"numerator\":{\"x\":"));
+        assert_eq!(to_json::quote("a\"b\\\n"), "\"a\\\"b\\\\\\n\"");
+    }
+
+    #[test]
+    fn test_ir_to_svg() {
+        use crate::{parse_tree, parser::ParserConfig, Options};
+
+        let conf = ParserConfig::default();
+        let tree = parse_tree(r"\frac{x}{2} < 1", conf.clone()).unwrap();
+        let layout = build_ir(&tree, &Options::from_parser_conf(&conf));
+        let svg = to_svg::render(&layout);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        // The fraction bar
+        assert!(svg.contains("<rect "));
+        assert!(svg.contains(">&lt;</text>"));
+    }
 }
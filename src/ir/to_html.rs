@@ -2,31 +2,39 @@
 //!
 //! This allows roundtrip testing: HtmlNode -> IR -> HTML string
 
+use core::fmt;
+
 use super::types::*;
 use crate::unit::make_em;
 
 /// Render a MathLayout to an HTML string.
 pub fn render(layout: &MathLayout) -> String {
     let mut out = String::new();
+    // Writing to a `String` can't fail
+    write_html(layout, &mut out).unwrap();
+    out
+}
 
+/// Write the HTML of a MathLayout into `out`, like [`render`] but without building a string.
+pub fn write_html(layout: &MathLayout, out: &mut dyn fmt::Write) -> fmt::Result {
     if layout.display_mode {
-        out.push_str("<span class=\"katex-display\">");
+        out.write_str("<span class=\"katex-display\">")?;
     }
-    out.push_str("<span class=\"katex\">");
-    out.push_str("<span class=\"katex-html\" aria-hidden=\"true\">");
+    out.write_str("<span class=\"katex\">")?;
+    out.write_str("<span class=\"katex-html\" aria-hidden=\"true\">")?;
 
-    render_element(&layout.root, &mut out);
+    render_element(&layout.root, out)?;
 
-    out.push_str("</span>");
-    out.push_str("</span>");
+    out.write_str("</span>")?;
+    out.write_str("</span>")?;
     if layout.display_mode {
-        out.push_str("</span>");
+        out.write_str("</span>")?;
     }
 
-    out
+    Ok(())
 }
 
-fn render_element(element: &MathElement, out: &mut String) {
+fn render_element(element: &MathElement, out: &mut dyn fmt::Write) -> fmt::Result {
     match element {
         MathElement::Text { text, style } => {
             let needs_span = style.color.is_some()
@@ -34,139 +42,143 @@ fn render_element(element: &MathElement, out: &mut String) {
                 || style.font.is_some();
 
             if needs_span {
-                out.push_str("<span");
+                out.write_str("<span")?;
 
-                // Build class list
-                let mut classes = Vec::new();
+                // Write class list
                 if let Some(font) = &style.font {
-                    classes.push(font_to_class(font));
-                }
-                if !classes.is_empty() {
-                    out.push_str(" class=\"");
-                    out.push_str(&classes.join(" "));
-                    out.push('"');
+                    write!(out, " class=\"{}\"", font_to_class(font))?;
                 }
 
-                // Build style
-                let mut styles = String::new();
-                if let Some(color) = &style.color {
-                    styles.push_str(&format!("color:{};", color.to_string()));
-                }
-                if style.italic_correction > 0.0 {
-                    styles.push_str(&format!("margin-right:{};", make_em(style.italic_correction)));
-                }
-                if !styles.is_empty() {
-                    out.push_str(" style=\"");
-                    out.push_str(&styles);
-                    out.push('"');
+                // Write style
+                if style.color.is_some() || style.italic_correction > 0.0 {
+                    out.write_str(" style=\"")?;
+                    if let Some(color) = &style.color {
+                        write!(out, "color:{};", color.to_string())?;
+                    }
+                    if style.italic_correction > 0.0 {
+                        write!(out, "margin-right:{};", make_em(style.italic_correction))?;
+                    }
+                    out.write_char('"')?;
                 }
 
-                out.push('>');
-                out.push_str(&html_escape(text));
-                out.push_str("</span>");
+                out.write_char('>')?;
+                html_escape(out, text)?;
+                out.write_str("</span>")?;
             } else {
-                out.push_str(&html_escape(text));
+                html_escape(out, text)?;
             }
         }
 
         MathElement::HBox { children, classes, .. } => {
-            out.push_str("<span");
+            out.write_str("<span")?;
             if !classes.is_empty() {
-                out.push_str(" class=\"");
-                out.push_str(&classes.join(" "));
-                out.push('"');
+                out.write_str(" class=\"")?;
+                for (i, class) in classes.iter().enumerate() {
+                    if i != 0 {
+                        out.write_char(' ')?;
+                    }
+                    out.write_str(class)?;
+                }
+                out.write_char('"')?;
             }
-            out.push('>');
+            out.write_char('>')?;
             for child in children {
-                render_element(&child.element, out);
+                render_element(&child.element, out)?;
             }
-            out.push_str("</span>");
+            out.write_str("</span>")?;
         }
 
         MathElement::VBox { children, .. } => {
-            out.push_str("<span class=\"vlist\">");
+            out.write_str("<span class=\"vlist\">")?;
             for child in children {
-                out.push_str("<span");
+                out.write_str("<span")?;
                 if child.y != 0.0 {
-                    out.push_str(&format!(" style=\"top:{}\"", make_em(-child.y)));
+                    write!(out, " style=\"top:{}\"", make_em(-child.y))?;
                 }
-                out.push('>');
-                render_element(&child.element, out);
-                out.push_str("</span>");
+                out.write_char('>')?;
+                render_element(&child.element, out)?;
+                out.write_str("</span>")?;
             }
-            out.push_str("</span>");
+            out.write_str("</span>")?;
         }
 
         MathElement::Rule { width, height, style: line_style, color, .. } => {
-            out.push_str("<span class=\"");
-            out.push_str(match line_style {
+            out.write_str("<span class=\"")?;
+            out.write_str(match line_style {
                 LineStyle::Solid => "rule",
                 LineStyle::Dashed => "hdashline",
-            });
-            out.push_str("\" style=\"");
-            out.push_str(&format!("width:{};", make_em(*width)));
-            out.push_str(&format!("border-bottom-width:{};", make_em(*height)));
+            })?;
+            out.write_str("\" style=\"")?;
+            write!(out, "width:{};", make_em(*width))?;
+            write!(out, "border-bottom-width:{};", make_em(*height))?;
             if let Some(color) = color {
-                out.push_str(&format!("border-color:{};", color.to_string()));
+                write!(out, "border-color:{};", color.to_string())?;
             }
-            out.push_str("\"></span>");
+            out.write_str("\"></span>")?;
         }
 
         MathElement::Path { path_data, width, height, .. } => {
-            out.push_str(&format!(
+            write!(
+                out,
                 "<svg width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\"><path d=\"{}\"/></svg>",
                 make_em(*width),
                 make_em(*height),
                 width * 1000.0,
                 height * 1000.0,
                 path_data
-            ));
+            )?;
         }
 
         MathElement::Kern { width } => {
             if *width != 0.0 {
-                out.push_str(&format!(
+                write!(
+                    out,
                     "<span class=\"mspace\" style=\"margin-right:{}\"></span>",
                     make_em(*width)
-                ));
+                )?;
             }
         }
 
         MathElement::Phantom { inner } => {
-            out.push_str("<span class=\"mord\" style=\"color:transparent\">");
-            render_element(inner, out);
-            out.push_str("</span>");
+            out.write_str("<span class=\"mord\" style=\"color:transparent\">")?;
+            render_element(inner, out)?;
+            out.write_str("</span>")?;
         }
 
         MathElement::Color { color, inner } => {
-            out.push_str(&format!("<span style=\"color:{}\">", color.to_string()));
-            render_element(inner, out);
-            out.push_str("</span>");
+            write!(out, "<span style=\"color:{}\">", color.to_string())?;
+            render_element(inner, out)?;
+            out.write_str("</span>")?;
         }
 
         MathElement::Link { href, inner } => {
-            out.push_str(&format!("<a href=\"{}\">", html_escape(href)));
-            render_element(inner, out);
-            out.push_str("</a>");
+            out.write_str("<a href=\"")?;
+            html_escape(out, href)?;
+            out.write_str("\">")?;
+            render_element(inner, out)?;
+            out.write_str("</a>")?;
         }
 
         MathElement::Image { src, alt, width, height } => {
-            out.push_str(&format!(
-                "<img src=\"{}\" alt=\"{}\" width=\"{}\" height=\"{}\"/>",
-                html_escape(src),
-                html_escape(alt),
+            out.write_str("<img src=\"")?;
+            html_escape(out, src)?;
+            out.write_str("\" alt=\"")?;
+            html_escape(out, alt)?;
+            write!(
+                out,
+                "\" width=\"{}\" height=\"{}\"/>",
                 make_em(*width),
                 make_em(*height)
-            ));
+            )?;
         }
 
         MathElement::Breakable { children, .. } => {
             // Breakable is similar to HBox for HTML output
-            out.push_str("<span class=\"base\">");
+            out.write_str("<span class=\"base\">")?;
             for child in children {
-                render_element(&child.element, out);
+                render_element(&child.element, out)?;
             }
-            out.push_str("</span>");
+            out.write_str("</span>")?;
         }
 
         // Semantic variants render their layout
@@ -174,12 +186,15 @@ fn render_element(element: &MathElement, out: &mut String) {
         | MathElement::Scripts { layout, .. }
         | MathElement::Radical { layout, .. }
         | MathElement::Accent { layout, .. }
+        | MathElement::Brace { layout, .. }
         | MathElement::Delimited { layout, .. }
         | MathElement::LargeOp { layout, .. }
         | MathElement::Array { layout, .. } => {
-            render_element(layout, out);
+            render_element(layout, out)?;
         }
     }
+
+    Ok(())
 }
 
 fn font_to_class(font: &Font) -> &'static str {
@@ -202,9 +217,17 @@ fn font_to_class(font: &Font) -> &'static str {
     }
 }
 
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
+fn html_escape(out: &mut dyn fmt::Write, s: &str) -> fmt::Result {
+    let mut rest = s;
+    while let Some(i) = rest.find(['&', '<', '>', '"']) {
+        out.write_str(&rest[..i])?;
+        out.write_str(match rest.as_bytes()[i] {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            _ => "&quot;",
+        })?;
+        rest = &rest[i + 1..];
+    }
+    out.write_str(rest)
 }
@@ -0,0 +1,382 @@
+//! Render IR to JSON.
+//!
+//! Every element is an object with a `"type"` field naming its variant, like
+//! `{"type": "text", "text": "x", "style": {...}}`, and the other fields of the variant in
+//! camel case. Positioned children are `{"x": ..., "y": ..., "element": {...}}`.
+
+use super::types::*;
+
+use crate::prelude::*;
+
+/// Render a MathLayout to a JSON string.
+pub fn render(layout: &MathLayout) -> String {
+    let mut out = String::new();
+
+    out.push_str("{\"displayMode\":");
+    out.push_str(if layout.display_mode { "true" } else { "false" });
+    push_number_field(&mut out, "width", layout.width);
+    push_number_field(&mut out, "height", layout.height);
+    push_number_field(&mut out, "depth", layout.depth);
+    out.push_str(",\"root\":");
+    render_element(&layout.root, &mut out);
+    out.push('}');
+
+    out
+}
+
+/// Quote and escape a string as a JSON string literal
+pub fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn push_number(out: &mut String, value: f64) {
+    if value.is_finite() {
+        out.push_str(&value.to_string());
+    } else {
+        // JSON has no infinities
+        out.push_str("null");
+    }
+}
+
+fn push_number_field(out: &mut String, key: &str, value: f64) {
+    out.push_str(&format!(",\"{}\":", key));
+    push_number(out, value);
+}
+
+fn push_string_field(out: &mut String, key: &str, value: &str) {
+    out.push_str(&format!(",\"{}\":", key));
+    out.push_str(&quote(value));
+}
+
+fn push_element_field(out: &mut String, key: &str, element: &MathElement) {
+    out.push_str(&format!(",\"{}\":", key));
+    render_element(element, out);
+}
+
+fn push_optional_element_field(out: &mut String, key: &str, element: Option<&MathElement>) {
+    match element {
+        Some(element) => push_element_field(out, key, element),
+        None => out.push_str(&format!(",\"{}\":null", key)),
+    }
+}
+
+fn push_positioned_field(
+    out: &mut String,
+    key: &str,
+    positioned: Option<&Positioned<MathElement>>,
+) {
+    out.push_str(&format!(",\"{}\":", key));
+    match positioned {
+        Some(positioned) => render_positioned(positioned, out),
+        None => out.push_str("null"),
+    }
+}
+
+fn push_children_field(out: &mut String, children: &[Positioned<MathElement>]) {
+    out.push_str(",\"children\":[");
+    for (i, child) in children.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        render_positioned(child, out);
+    }
+    out.push(']');
+}
+
+fn push_color_field(out: &mut String, key: &str, color: Option<&crate::parse_node::Color>) {
+    match color {
+        Some(color) => push_string_field(out, key, &color.to_string()),
+        None => out.push_str(&format!(",\"{}\":null", key)),
+    }
+}
+
+fn push_bools_field(out: &mut String, key: &str, values: &[bool]) {
+    out.push_str(&format!(",\"{}\":[", key));
+    let values = values
+        .iter()
+        .map(|v| if *v { "true" } else { "false" })
+        .collect::<Vec<_>>();
+    out.push_str(&values.join(","));
+    out.push(']');
+}
+
+fn line_style_name(style: LineStyle) -> &'static str {
+    match style {
+        LineStyle::Solid => "solid",
+        LineStyle::Dashed => "dashed",
+    }
+}
+
+fn render_positioned(positioned: &Positioned<MathElement>, out: &mut String) {
+    out.push_str("{\"x\":");
+    push_number(out, positioned.x);
+    push_number_field(out, "y", positioned.y);
+    push_element_field(out, "element", &positioned.element);
+    out.push('}');
+}
+
+fn render_text_style(style: &TextStyle, out: &mut String) {
+    out.push_str("{\"font\":");
+    match &style.font {
+        Some(font) => out.push_str(&quote(font.as_str())),
+        None => out.push_str("null"),
+    }
+    push_number_field(out, "size", style.size);
+    push_color_field(out, "color", style.color.as_ref());
+    push_number_field(out, "italicCorrection", style.italic_correction);
+    push_number_field(out, "skew", style.skew);
+    for (key, value) in [
+        ("width", style.width),
+        ("height", style.height),
+        ("depth", style.depth),
+    ] {
+        match value {
+            Some(value) => push_number_field(out, key, value),
+            None => out.push_str(&format!(",\"{}\":null", key)),
+        }
+    }
+    out.push('}');
+}
+
+fn render_element(element: &MathElement, out: &mut String) {
+    let typ = match element {
+        MathElement::Text { .. } => "text",
+        MathElement::HBox { .. } => "hbox",
+        MathElement::VBox { .. } => "vbox",
+        MathElement::Rule { .. } => "rule",
+        MathElement::Path { .. } => "path",
+        MathElement::Kern { .. } => "kern",
+        MathElement::Phantom { .. } => "phantom",
+        MathElement::Color { .. } => "color",
+        MathElement::Link { .. } => "link",
+        MathElement::Image { .. } => "image",
+        MathElement::Breakable { .. } => "breakable",
+        MathElement::Fraction { .. } => "fraction",
+        MathElement::Scripts { .. } => "scripts",
+        MathElement::Radical { .. } => "radical",
+        MathElement::Accent { .. } => "accent",
+        MathElement::Brace { .. } => "brace",
+        MathElement::Delimited { .. } => "delimited",
+        MathElement::LargeOp { .. } => "largeOp",
+        MathElement::Array { .. } => "array",
+    };
+    out.push_str("{\"type\":\"");
+    out.push_str(typ);
+    out.push('"');
+
+    match element {
+        MathElement::Text { text, style } => {
+            push_string_field(out, "text", text);
+            out.push_str(",\"style\":");
+            render_text_style(style, out);
+        }
+        MathElement::HBox {
+            children,
+            width,
+            height,
+            depth,
+            classes,
+        } => {
+            push_number_field(out, "width", *width);
+            push_number_field(out, "height", *height);
+            push_number_field(out, "depth", *depth);
+            out.push_str(",\"classes\":[");
+            let classes = classes.iter().map(|c| quote(c)).collect::<Vec<_>>();
+            out.push_str(&classes.join(","));
+            out.push(']');
+            push_children_field(out, children);
+        }
+        MathElement::VBox {
+            children,
+            width,
+            height,
+            depth,
+        }
+        | MathElement::Breakable {
+            children,
+            width,
+            height,
+            depth,
+        } => {
+            push_number_field(out, "width", *width);
+            push_number_field(out, "height", *height);
+            push_number_field(out, "depth", *depth);
+            push_children_field(out, children);
+        }
+        MathElement::Rule {
+            width,
+            height,
+            shift,
+            style,
+            color,
+        } => {
+            push_number_field(out, "width", *width);
+            push_number_field(out, "height", *height);
+            push_number_field(out, "shift", *shift);
+            push_string_field(out, "style", line_style_name(*style));
+            push_color_field(out, "color", color.as_ref());
+        }
+        MathElement::Path {
+            path_data,
+            width,
+            height,
+            shift,
+        } => {
+            push_string_field(out, "pathData", path_data);
+            push_number_field(out, "width", *width);
+            push_number_field(out, "height", *height);
+            push_number_field(out, "shift", *shift);
+        }
+        MathElement::Kern { width } => push_number_field(out, "width", *width),
+        MathElement::Phantom { inner } => push_element_field(out, "inner", inner),
+        MathElement::Color { color, inner } => {
+            push_color_field(out, "color", Some(color));
+            push_element_field(out, "inner", inner);
+        }
+        MathElement::Link { href, inner } => {
+            push_string_field(out, "href", href);
+            push_element_field(out, "inner", inner);
+        }
+        MathElement::Image {
+            src,
+            alt,
+            width,
+            height,
+        } => {
+            push_string_field(out, "src", src);
+            push_string_field(out, "alt", alt);
+            push_number_field(out, "width", *width);
+            push_number_field(out, "height", *height);
+        }
+        MathElement::Fraction {
+            numerator,
+            denominator,
+            bar,
+            layout,
+        } => {
+            push_positioned_field(out, "numerator", Some(numerator));
+            push_positioned_field(out, "denominator", Some(denominator));
+            out.push_str(",\"bar\":");
+            match bar {
+                Some(bar) => {
+                    out.push_str("{\"thickness\":");
+                    push_number(out, bar.thickness);
+                    push_color_field(out, "color", bar.color.as_ref());
+                    push_string_field(out, "style", line_style_name(bar.style));
+                    out.push('}');
+                }
+                None => out.push_str("null"),
+            }
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::Scripts {
+            base,
+            superscript,
+            subscript,
+            layout,
+        } => {
+            push_positioned_field(out, "base", base.as_deref());
+            push_positioned_field(out, "superscript", superscript.as_deref());
+            push_positioned_field(out, "subscript", subscript.as_deref());
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::Radical {
+            radicand,
+            index,
+            layout,
+        } => {
+            push_element_field(out, "radicand", radicand);
+            push_optional_element_field(out, "index", index.as_deref());
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::Accent {
+            base,
+            accent,
+            is_over,
+            layout,
+        } => {
+            push_element_field(out, "base", base);
+            push_element_field(out, "accent", accent);
+            out.push_str(&format!(",\"isOver\":{}", is_over));
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::Brace {
+            base,
+            brace,
+            label,
+            is_over,
+            layout,
+        } => {
+            push_element_field(out, "base", base);
+            push_element_field(out, "brace", brace);
+            push_positioned_field(out, "label", label.as_deref());
+            out.push_str(&format!(",\"isOver\":{}", is_over));
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::Delimited {
+            left,
+            right,
+            body,
+            layout,
+        } => {
+            push_optional_element_field(out, "left", left.as_deref());
+            push_optional_element_field(out, "right", right.as_deref());
+            push_element_field(out, "body", body);
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::LargeOp {
+            nucleus,
+            superscript,
+            subscript,
+            limits,
+            layout,
+        } => {
+            push_element_field(out, "nucleus", nucleus);
+            push_positioned_field(out, "superscript", superscript.as_deref());
+            push_positioned_field(out, "subscript", subscript.as_deref());
+            out.push_str(&format!(",\"limits\":{}", limits));
+            push_element_field(out, "layout", layout);
+        }
+        MathElement::Array {
+            cells,
+            row_lines,
+            col_lines,
+            layout,
+        } => {
+            out.push_str(",\"cells\":[");
+            for (i, row) in cells.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                out.push('[');
+                for (j, cell) in row.iter().enumerate() {
+                    if j != 0 {
+                        out.push(',');
+                    }
+                    render_element(cell, out);
+                }
+                out.push(']');
+            }
+            out.push(']');
+            push_bools_field(out, "rowLines", row_lines);
+            push_bools_field(out, "colLines", col_lines);
+            push_element_field(out, "layout", layout);
+        }
+    }
+
+    out.push('}');
+}
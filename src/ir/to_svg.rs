@@ -0,0 +1,236 @@
+//! Render IR to a standalone SVG image.
+//!
+//! The image is in em units, with one unit of the `viewBox` being one em, so it scales with
+//! the surrounding font size. Text is drawn with `<text>` in the KaTeX fonts, which have to be
+//! available wherever the image is shown.
+
+use super::types::*;
+use crate::prelude::*;
+use crate::{unit::make_em, util::round};
+
+/// Render a MathLayout to an SVG string.
+pub fn render(layout: &MathLayout) -> String {
+    let total = layout.height + layout.depth;
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 {} {} {}\" style=\"vertical-align:{}\">",
+        make_em(layout.width),
+        make_em(total),
+        num(-layout.height),
+        num(layout.width),
+        num(total),
+        make_em(-layout.depth),
+    );
+
+    render_element(&layout.root, 0.0, 0.0, &mut out);
+
+    out.push_str("</svg>");
+    out
+}
+
+/// Format a number the way `make_em` does, without the unit
+fn num(value: f64) -> String {
+    let rounded = round(value * 10000.0) / 10000.0;
+    // Avoid writing `-0`
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+/// The font family and whether it is bold and italic
+fn font_face(font: &Font) -> (String, bool, bool) {
+    let name = font.as_str();
+    let (family, variant) = name.split_once('-').unwrap_or((name, "Regular"));
+    (
+        format!("KaTeX_{}", family),
+        variant.contains("Bold"),
+        variant.contains("Italic"),
+    )
+}
+
+/// The character drawn for a stretchy delimiter, which the IR only names
+fn delimiter_char(delim: &str) -> &str {
+    match delim {
+        "\\langle" | "\\lang" => "⟨",
+        "\\rangle" | "\\rang" => "⟩",
+        "\\{" | "\\lbrace" => "{",
+        "\\}" | "\\rbrace" => "}",
+        "\\vert" | "\\lvert" | "\\rvert" => "|",
+        "\\|" | "\\Vert" | "\\lVert" | "\\rVert" => "‖",
+        "\\backslash" => "\\",
+        "\\lfloor" => "⌊",
+        "\\rfloor" => "⌋",
+        "\\lceil" => "⌈",
+        "\\rceil" => "⌉",
+        "\\uparrow" => "↑",
+        "\\downarrow" => "↓",
+        "\\updownarrow" => "↕",
+        "\\Uparrow" => "⇑",
+        "\\Downarrow" => "⇓",
+        "\\Updownarrow" => "⇕",
+        delim => delim,
+    }
+}
+
+fn render_children(children: &[Positioned<MathElement>], x: f64, y: f64, out: &mut String) {
+    for child in children {
+        render_element(&child.element, x + child.x, y + child.y, out);
+    }
+}
+
+/// Render an element with its left edge at `x` and its baseline at `y`, where `y` is upwards
+/// like in the IR
+fn render_element(element: &MathElement, x: f64, y: f64, out: &mut String) {
+    match element {
+        MathElement::Text { text, style } => {
+            if text.is_empty() {
+                return;
+            }
+
+            let size = if style.size > 0.0 { style.size } else { 1.0 };
+            out.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\"",
+                num(x),
+                num(-y),
+                num(size)
+            ));
+            let font = style.font.as_ref().unwrap_or(&Font::MainRegular);
+            let (family, bold, italic) = font_face(font);
+            out.push_str(&format!(" font-family=\"{}\"", family));
+            if bold {
+                out.push_str(" font-weight=\"bold\"");
+            }
+            if italic {
+                out.push_str(" font-style=\"italic\"");
+            }
+            if let Some(color) = &style.color {
+                out.push_str(&format!(" fill=\"{}\"", color.to_string()));
+            }
+            out.push('>');
+            out.push_str(&xml_escape(text));
+            out.push_str("</text>");
+        }
+
+        MathElement::HBox { children, .. }
+        | MathElement::VBox { children, .. }
+        | MathElement::Breakable { children, .. } => render_children(children, x, y, out),
+
+        MathElement::Rule {
+            width,
+            height,
+            style: line_style,
+            color,
+            ..
+        } => {
+            out.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+                num(x),
+                num(-(y + height)),
+                num(*width),
+                num(*height)
+            ));
+            if *line_style == LineStyle::Dashed {
+                // Dashed rules have no fill, only the dashed top edge
+                out.push_str(&format!(
+                    " fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-dasharray=\"{}\"",
+                    color
+                        .as_ref()
+                        .map_or("currentColor".to_string(), |c| c.to_string()),
+                    num(*height),
+                    num(height * 4.0)
+                ));
+            } else if let Some(color) = color {
+                out.push_str(&format!(" fill=\"{}\"", color.to_string()));
+            }
+            out.push_str("/>");
+        }
+
+        MathElement::Path {
+            path_data,
+            width,
+            height,
+            shift,
+        } => {
+            let top = y + height + shift.max(0.0);
+            if let Some(delim) = path_data.strip_prefix("delimiter:") {
+                // The delimiter is drawn from its character, stretched to the height
+                let scale = height / 1.2;
+                out.push_str(&format!(
+                    "<text transform=\"translate({} {}) scale(1 {})\" y=\"0.95\" font-size=\"1\" font-family=\"KaTeX_Main\">{}</text>",
+                    num(x),
+                    num(-top),
+                    num(scale),
+                    xml_escape(delimiter_char(delim))
+                ));
+            } else if path_data == "surd" {
+                let scale = height / 1.08;
+                out.push_str(&format!(
+                    "<text transform=\"translate({} {}) scale(1 {})\" y=\"0.9\" font-size=\"1\" font-family=\"KaTeX_Main\">√</text>",
+                    num(x),
+                    num(-top),
+                    num(scale)
+                ));
+            } else if *width > 0.0 && *height > 0.0 {
+                // Paths are drawn with 1000 units per em, from the top left corner
+                out.push_str(&format!(
+                    "<path transform=\"translate({} {}) scale(0.001)\" d=\"{}\"/>",
+                    num(x),
+                    num(-top),
+                    xml_escape(path_data)
+                ));
+            }
+        }
+
+        MathElement::Kern { .. } => {}
+
+        // Phantoms take up space without being drawn
+        MathElement::Phantom { .. } => {}
+
+        MathElement::Color { color, inner } => {
+            out.push_str(&format!("<g fill=\"{}\">", color.to_string()));
+            render_element(inner, x, y, out);
+            out.push_str("</g>");
+        }
+
+        MathElement::Link { href, inner } => {
+            out.push_str(&format!("<a href=\"{}\">", xml_escape(href)));
+            render_element(inner, x, y, out);
+            out.push_str("</a>");
+        }
+
+        MathElement::Image {
+            src,
+            alt,
+            width,
+            height,
+        } => {
+            out.push_str(&format!(
+                "<image href=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}</title></image>",
+                xml_escape(src),
+                num(x),
+                num(-(y + height)),
+                num(*width),
+                num(*height),
+                xml_escape(alt)
+            ));
+        }
+
+        // Semantic variants render their layout
+        MathElement::Fraction { layout, .. }
+        | MathElement::Scripts { layout, .. }
+        | MathElement::Radical { layout, .. }
+        | MathElement::Accent { layout, .. }
+        | MathElement::Brace { layout, .. }
+        | MathElement::Delimited { layout, .. }
+        | MathElement::LargeOp { layout, .. }
+        | MathElement::Array { layout, .. } => render_element(layout, x, y, out),
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
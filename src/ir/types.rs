@@ -2,8 +2,9 @@
 //!
 //! All dimensions are in em units unless otherwise specified.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
+use crate::prelude::*;
 use crate::parse_node::Color;
 
 /// A positioned element within its parent's coordinate system.
@@ -324,6 +325,23 @@ pub enum MathElement {
         layout: Box<MathElement>,
     },
 
+    /// A horizontal brace over or under a base expression, with an optional label.
+    ///
+    /// Includes `\overbrace`, `\underbrace`, `\overbracket` and `\overparen`.
+    /// The brace is a `Path` stretched to the width of the base.
+    Brace {
+        /// The base expression being braced
+        base: Box<MathElement>,
+        /// The stretched brace path
+        brace: Box<MathElement>,
+        /// The label beyond the brace, with its y-offset relative to the base (positive = up)
+        label: Option<Box<Positioned<MathElement>>>,
+        /// True if brace is above base, false if below
+        is_over: bool,
+        /// Pre-computed layout
+        layout: Box<MathElement>,
+    },
+
     /// A delimited expression with \left...\right or similar.
     Delimited {
         /// Left delimiter (may be None for \left.)
@@ -407,6 +425,7 @@ impl MathElement {
             MathElement::Scripts { layout, .. } => layout.dimensions(),
             MathElement::Radical { layout, .. } => layout.dimensions(),
             MathElement::Accent { layout, .. } => layout.dimensions(),
+            MathElement::Brace { layout, .. } => layout.dimensions(),
             MathElement::Delimited { layout, .. } => layout.dimensions(),
             MathElement::LargeOp { layout, .. } => layout.dimensions(),
             MathElement::Array { layout, .. } => layout.dimensions(),
@@ -447,6 +466,7 @@ impl MathElement {
                 | MathElement::Scripts { .. }
                 | MathElement::Radical { .. }
                 | MathElement::Accent { .. }
+                | MathElement::Brace { .. }
                 | MathElement::Delimited { .. }
                 | MathElement::LargeOp { .. }
                 | MathElement::Array { .. }
@@ -461,6 +481,7 @@ impl MathElement {
             MathElement::Scripts { layout, .. } => layout.as_ref(),
             MathElement::Radical { layout, .. } => layout.as_ref(),
             MathElement::Accent { layout, .. } => layout.as_ref(),
+            MathElement::Brace { layout, .. } => layout.as_ref(),
             MathElement::Delimited { layout, .. } => layout.as_ref(),
             MathElement::LargeOp { layout, .. } => layout.as_ref(),
             MathElement::Array { layout, .. } => layout.as_ref(),
@@ -551,6 +572,7 @@ impl<'a> Iterator for LayoutWalker<'a> {
             | MathElement::Scripts { layout, .. }
             | MathElement::Radical { layout, .. }
             | MathElement::Accent { layout, .. }
+            | MathElement::Brace { layout, .. }
             | MathElement::Delimited { layout, .. }
             | MathElement::LargeOp { layout, .. }
             | MathElement::Array { layout, .. } => {
@@ -607,7 +629,7 @@ mod tests {
         // Path uses shift to encode baseline position within the element
         // (different semantics from Rule - used for stretchy delimiters)
         let path = MathElement::Path {
-            path_data: std::borrow::Cow::Borrowed("test"),
+            path_data: alloc::borrow::Cow::Borrowed("test"),
             width: 1.0,
             height: 2.0,
             shift: -0.5, // Baseline is 0.5 below center, so depth = 0.5
@@ -619,7 +641,7 @@ mod tests {
 
         // Positive shift means baseline above center
         let path2 = MathElement::Path {
-            path_data: std::borrow::Cow::Borrowed("test"),
+            path_data: alloc::borrow::Cow::Borrowed("test"),
             width: 1.0,
             height: 2.0,
             shift: 0.5, // Baseline is 0.5 above center
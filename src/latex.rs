@@ -0,0 +1,1618 @@
+//! Serializing parse trees back into LaTeX source.
+//!
+//! [`to_latex`] prints a canonical form of the tree: macros are expanded, infix commands are
+//! replaced by their prefix forms (`a \over b` becomes `\frac{a}{b}`, `\choose` becomes
+//! `\binom`), and aliases are replaced by the command they stand for (`\bold` becomes `\mathbf`).
+//! The builtin macros that expand to KaTeX's internal commands or to spacing are the exception,
+//! and are written by name: `\ne` becomes `\neq`, and `\mkern3mu` becomes `\,`.
+//! Parsing the output again gives back the same tree, ignoring source locations:
+//! ```
+//! # use aliter::{latex::to_latex, parse_tree, parse_node::EqNoLoc, parser::ParserConfig};
+//! let tree = parse_tree(r"{a \over b} + \bold x", ParserConfig::default()).unwrap();
+//! let source = to_latex(&tree);
+//! assert_eq!(source, r"{\frac{a}{b}}+\mathbf{x}");
+//! assert!(parse_tree(&source, ParserConfig::default()).unwrap().eq_no_loc(&tree));
+//! ```
+//!
+//! Some trees can't be written back exactly, because the information is lost while parsing:
+//! - A text mode space directly after a control word, as the lexer skips it.
+//! - A sup/sub without a base that isn't the first item of its list.
+//! - An environment given as a script without braces, stretched by a different
+//!   `\arraystretch` than an environment in its base or its other script.
+
+use crate::prelude::*;
+use crate::{
+    array::{AlignSpec, ColSeparationType},
+    builtin_macros::BUILTIN_MACROS,
+    expander::Mode,
+    functions::FUNCTIONS,
+    lexer::{is_combining_mark, is_token_char},
+    macr::MacroReplace,
+    parse_node::{
+        AccentNode, ArrayNode, ArrayTag, Color, ColorNode, DelimSize, EqNoLoc, GenFracNode, LeftRightNode,
+        MClass, MClassNode, ParseNode, SupSubNode,
+    },
+    parse_tree,
+    parser::ParserConfig,
+    symbols::{Group, NonAtom, SYMBOLS},
+    sync::Lazy,
+    unit::Measurement,
+    util::{Style, StyleAuto},
+};
+
+/// The characters that TeX gives a special meaning, rather than reading them as themselves
+const TEX_SPECIALS: &[char] = &['%', '#', '&', '$', '^', '_', '{', '}', '~', '\\'];
+
+/// The text mode font switches, which apply to the rest of the group instead of taking an
+/// argument
+const TEXT_SWITCHES: &[&str] = &[
+    "\\rmfamily",
+    "\\sffamily",
+    "\\ttfamily",
+    "\\normalfont",
+    "\\bfseries",
+    "\\mdseries",
+    "\\itshape",
+    "\\upshape",
+    "\\slshape",
+    "\\scshape",
+    "\\em",
+];
+
+const TEXT_ACCENTS: &[&str] = &[
+    "\\'", "\\`", "\\^", "\\~", "\\=", "\\u", "\\.", "\\\"", "\\c", "\\r", "\\H", "\\v",
+    "\\textcircled",
+];
+
+const SIZE_FUNCS: &[&str] = &[
+    "\\tiny",
+    "\\sixptsize",
+    "\\scriptsize",
+    "\\footnotesize",
+    "\\small",
+    "\\normalsize",
+    "\\large",
+    "\\Large",
+    "\\LARGE",
+    "\\huge",
+    "\\Huge",
+];
+
+/// A builtin macro that takes no arguments, and the nodes it expands to
+struct BuiltinExpansion {
+    name: String,
+    nodes: Vec<ParseNode>,
+    /// Whether the macro can be given as an argument or a script without braces, which takes
+    /// the first node of the expansion
+    bare: bool,
+}
+
+/// The builtin macros that are written back by name, because their expansion uses KaTeX's
+/// internal commands, like `\neq` and `\cdots`, or is spacing, like `\,` and `\iff`.
+/// Where several macros expand to the same nodes, the shortest name is kept. The longest
+/// expansions come first, so that they are found before the single nodes they are made of.
+static BUILTIN_EXPANSIONS: Lazy<Vec<BuiltinExpansion>> = Lazy::new(|| {
+    let mut names = BUILTIN_MACROS
+        .iter_back_macros()
+        .filter(|(name, repl)| {
+            // Aliases like `\ne` are written as the macro they stand for
+            let is_alias =
+                matches!(repl.as_ref(), MacroReplace::Text(text) if is_control_sequence(text));
+            // `\show` prints the token after it rather than expanding to anything
+            is_control_sequence(name) && !name.contains('@') && !is_alias && *name != "\\show"
+        })
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    names.sort_by_key(|name| (name.len(), *name));
+
+    let mut expansions: Vec<BuiltinExpansion> = Vec::new();
+    for name in names {
+        let math = parse_tree(name, ParserConfig::default());
+        let text = parse_tree(&format!("\\text{{{}}}", name), ParserConfig::default()).map(|nodes| {
+            match nodes.as_slice() {
+                [ParseNode::Text(text)] => text.body.clone(),
+                _ => Vec::new(),
+            }
+        });
+        for nodes in [math, text].into_iter().flatten() {
+            if !is_internal_expansion(&nodes)
+                || expansions.iter().any(|expansion| expansion.nodes.eq_no_loc(&nodes))
+            {
+                continue;
+            }
+            let bare = match parse_tree(&format!("x^{}", name), ParserConfig::default()) {
+                Ok(scripted) => match (scripted.as_slice(), nodes.as_slice()) {
+                    ([ParseNode::SupSub(sup_sub)], [node]) => {
+                        sup_sub.sup.as_deref().is_some_and(|sup| sup.eq_no_loc(node))
+                    }
+                    _ => false,
+                },
+                Err(_) => false,
+            };
+            expansions.push(BuiltinExpansion {
+                name: name.to_string(),
+                nodes,
+                bare,
+            });
+        }
+    }
+
+    expansions.sort_by_key(|expansion| core::cmp::Reverse(expansion.nodes.len()));
+    expansions
+});
+
+/// Whether the expansion of a macro can't be written back as it is: spacing, several nodes, or
+/// nodes that are written with internal commands
+fn is_internal_expansion(nodes: &[ParseNode]) -> bool {
+    match nodes {
+        [] => false,
+        [ParseNode::Kern(_)] => true,
+        [node] => {
+            let options = LatexOptions::default();
+            let mut writer = LatexWriter::new(&options);
+            writer.builtin_names = false;
+            writer.write_node(node, true);
+            writer.out.contains('@')
+        }
+        _ => true,
+    }
+}
+
+/// The builtin macro that expands to the node
+fn builtin_expansion(node: &ParseNode) -> Option<&'static BuiltinExpansion> {
+    BUILTIN_EXPANSIONS
+        .iter()
+        .find(|expansion| {
+            matches!(expansion.nodes.as_slice(), [expanded] if expanded.eq_no_loc(node))
+        })
+}
+
+/// The source a tree was parsed from, used to keep the original spelling of commands
+#[derive(Debug, Clone)]
+pub struct LatexSource<'a> {
+    pub input: &'a str,
+    /// The config the input was parsed with
+    pub conf: ParserConfig,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LatexOptions<'a> {
+    /// Drop the braces around arguments and scripts that are a single token, so `\frac{1}{2}`
+    /// is written as `\frac12` and `x^{2}` as `x^2`.
+    /// Other than for scripts, this doesn't change the tree that the output parses to. A
+    /// single token script is stored without its group, so it parses to the token itself.
+    pub minimal_braces: bool,
+    /// If set, a command whose `NodeInfo.loc` points at source that parses to the same node
+    /// is written as it was in the source, keeping aliases like `\bold` and user macros that
+    /// were defined in the config.
+    pub source: Option<LatexSource<'a>>,
+}
+impl<'a> LatexOptions<'a> {
+    pub fn with_minimal_braces(mut self, minimal_braces: bool) -> Self {
+        self.minimal_braces = minimal_braces;
+        self
+    }
+
+    pub fn with_source(mut self, input: &'a str, conf: ParserConfig) -> Self {
+        self.source = Some(LatexSource { input, conf });
+        self
+    }
+}
+
+/// Write the parse tree as LaTeX, with the default options
+pub fn to_latex(nodes: &[ParseNode]) -> String {
+    to_latex_with(nodes, &LatexOptions::default())
+}
+
+/// Write the parse tree as LaTeX
+pub fn to_latex_with(nodes: &[ParseNode], options: &LatexOptions) -> String {
+    let mut writer = LatexWriter::new(options);
+    writer.write_list(nodes);
+    writer.out
+}
+
+/// Whether the text ends with a control word, like `\alpha`, which would absorb any letter
+/// written after it
+fn ends_with_control_word(text: &str) -> bool {
+    let letters = text
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '@')
+        .count();
+    if letters == 0 {
+        return false;
+    }
+
+    let slashes = text[..text.len() - letters]
+        .chars()
+        .rev()
+        .take_while(|c| *c == '\\')
+        .count();
+    slashes % 2 == 1
+}
+
+/// Whether the text is a single control sequence, like `\alpha` or `\{`
+fn is_control_sequence(text: &str) -> bool {
+    let Some(name) = text.strip_prefix('\\') else {
+        return false;
+    };
+
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '@' => {
+            chars.all(|c| c.is_ascii_alphabetic() || c == '@')
+        }
+        Some(_) => chars.next().is_none(),
+        None => false,
+    }
+}
+
+/// Whether the node is a symbol that is written as its text
+fn is_plain_symbol(node: &ParseNode) -> bool {
+    match node {
+        ParseNode::Spacing(spacing) => is_control_sequence(&spacing.text),
+        ParseNode::TextOrd(ord) => !is_char_ord(&ord.text, ord.info.mode),
+        _ => true,
+    }
+}
+
+/// The text of a symbol that lexes to a single token, which can be given as an argument
+/// without braces
+fn arg_token(node: &ParseNode) -> Option<&str> {
+    if !is_plain_symbol(node) {
+        return None;
+    }
+
+    let text = node.text()?;
+    let mut chars = text.chars();
+    let single_alnum = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_alphanumeric());
+    (single_alnum || is_control_sequence(text)).then_some(text)
+}
+
+/// The text of a symbol that can be a script, or a primitive argument, without braces
+fn script_token(node: &ParseNode) -> Option<&str> {
+    if let Some(text) = arg_token(node) {
+        return Some(text);
+    }
+
+    if !is_plain_symbol(node) {
+        return None;
+    }
+
+    let text = node.text()?;
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_whitespace() && !"{}^_&#%$~'\\".contains(c) => Some(text),
+        _ => None,
+    }
+}
+
+fn style_name(style: Style) -> &'static str {
+    match style {
+        Style::Display => "\\displaystyle",
+        Style::Text => "\\textstyle",
+        Style::Script => "\\scriptstyle",
+        Style::ScriptScript => "\\scriptscriptstyle",
+    }
+}
+
+/// Write a delimiter as it is accepted by `\left`, undoing the canonicalization done when it
+/// was parsed
+fn delim_source(delim: &str) -> &str {
+    match delim {
+        "⟨" => "\\langle",
+        "⟩" => "\\rangle",
+        "{" => "\\{",
+        "}" => "\\}",
+        "||" => "\\|",
+        "\\" => "\\backslash",
+        other => other,
+    }
+}
+
+fn escape_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        if "#$%&~_^{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The environments that set their own stretch rather than reading `\arraystretch`
+const FIXED_STRETCH_ENVS: &[&str] = &[
+    "smallmatrix",
+    "subarray",
+    "cases",
+    "dcases",
+    "rcases",
+    "drcases",
+    "CD",
+];
+
+/// Whether the environment's stretch comes from `\arraystretch`
+fn reads_stretch(env: &ArrayEnv) -> bool {
+    !FIXED_STRETCH_ENVS.contains(&env.name.as_str())
+}
+
+/// The stretch of the node, if it is written as an environment that reads it from
+/// `\arraystretch`
+fn env_stretch(node: &ParseNode) -> Option<f64> {
+    let (array, env) = match node {
+        ParseNode::Array(array) => (array, array_env(array, None)),
+        ParseNode::LeftRight(left_right) => match left_right.body.as_slice() {
+            [ParseNode::Array(array)] => {
+                let delims = (left_right.left.as_str(), left_right.right.as_str());
+                // Otherwise it is written with `\left` and `\right` around the array
+                (array, Some(array_env(array, Some(delims))?))
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    // An array without an environment of its own is written as an `{array}`
+    env.is_none_or(|env| reads_stretch(&env))
+        .then_some(array.array_stretch)
+}
+
+/// An environment that an `{array}` node can be written as
+struct ArrayEnv {
+    name: String,
+    /// The arguments written after `\begin{name}`
+    args: String,
+}
+impl ArrayEnv {
+    fn new(name: impl Into<String>) -> ArrayEnv {
+        ArrayEnv {
+            name: name.into(),
+            args: String::new(),
+        }
+    }
+
+    fn with_args(mut self, args: String) -> ArrayEnv {
+        self.args = args;
+        self
+    }
+}
+
+/// The style of the cells in the array
+fn cell_style(array: &ArrayNode) -> Option<Style> {
+    array.body.iter().flatten().find_map(|cell| match cell {
+        ParseNode::Styling(styling) => Some(styling.style),
+        _ => None,
+    })
+}
+
+fn col_spec(cols: &[AlignSpec]) -> String {
+    cols.iter()
+        .map(|col| match col {
+            AlignSpec::Separator(sep) => sep.as_ref(),
+            AlignSpec::Align { align, .. } => align.as_ref(),
+        })
+        .collect()
+}
+
+/// Whether the rows are numbered by default, along with the marker to put on each row
+fn numbered(tags: &[ArrayTag]) -> bool {
+    tags.iter().any(|tag| matches!(tag, ArrayTag::Boolean(true)))
+}
+
+/// Find the environment that produces the array.
+/// `delims` are the delimiters of a `\left...\right` that the array is the only child of, for
+/// the environments that wrap their array in one, like `{pmatrix}` and `{cases}`.
+fn array_env(array: &ArrayNode, delims: Option<(&str, &str)>) -> Option<ArrayEnv> {
+    let style = cell_style(array);
+    let star = |tags: &[ArrayTag]| if numbered(tags) { "" } else { "*" };
+
+    if array.is_cd == Some(true) {
+        return delims.is_none().then(|| ArrayEnv::new("CD"));
+    }
+
+    let env = match (&array.col_separation_type, &array.tags) {
+        (Some(ColSeparationType::Align), None) => ArrayEnv::new("aligned"),
+        (Some(ColSeparationType::Align), Some(tags)) => ArrayEnv::new(format!("align{}", star(tags))),
+        (Some(ColSeparationType::AlignAt), tags) => {
+            let n = array.cols.as_ref().map(Vec::len).unwrap_or(0) / 2;
+            let name = match tags {
+                None => "alignedat".to_string(),
+                Some(tags) => format!("alignat{}", star(tags)),
+            };
+            ArrayEnv::new(name).with_args(format!("{{{}}}", n))
+        }
+        (Some(ColSeparationType::Gather), None) => ArrayEnv::new("gathered"),
+        (Some(ColSeparationType::Gather), Some(tags)) => {
+            ArrayEnv::new(format!("gather{}", star(tags)))
+        }
+        (Some(ColSeparationType::Small), _) => ArrayEnv::new("smallmatrix"),
+        (Some(ColSeparationType::Cd), _) => ArrayEnv::new("CD"),
+        (None, Some(tags)) => ArrayEnv::new(format!("equation{}", star(tags))),
+        (None, None) => return matrix_env(array, style, delims),
+    };
+
+    delims.is_none().then_some(env)
+}
+
+/// Find the environment for an array without column separation, which are the various
+/// matrices, `{array}`, and `{cases}`
+fn matrix_env(
+    array: &ArrayNode,
+    style: Option<Style>,
+    delims: Option<(&str, &str)>,
+) -> Option<ArrayEnv> {
+    let display = style == Some(Style::Display);
+    let cols = array.cols.as_deref();
+
+    match array.h_skip_before_and_after {
+        Some(true) => {
+            let name = if display { "darray" } else { "array" };
+            let spec = col_spec(cols.unwrap_or_default());
+            delims
+                .is_none()
+                .then(|| ArrayEnv::new(name).with_args(format!("{{{}}}", spec)))
+        }
+        Some(false)
+            if array.array_stretch == 0.5
+                && style == Some(Style::Script)
+                && cols.map(<[_]>::len) == Some(1) =>
+        {
+            let spec = col_spec(cols.unwrap_or_default());
+            delims
+                .is_none()
+                .then(|| ArrayEnv::new("subarray").with_args(format!("{{{}}}", spec)))
+        }
+        Some(false) => {
+            let prefix = match delims {
+                None => "",
+                Some(("(", ")")) => "p",
+                Some(("[", "]")) => "b",
+                Some(("\\{", "\\}")) => "B",
+                Some(("|", "|")) => "v",
+                Some(("\\Vert", "\\Vert")) => "V",
+                Some(_) => return None,
+            };
+            let align = cols
+                .and_then(|cols| cols.first())
+                .and_then(|col| match col {
+                    AlignSpec::Align { align, .. } => Some(align.as_ref()),
+                    AlignSpec::Separator(_) => None,
+                })
+                .unwrap_or("c");
+
+            Some(if align == "c" {
+                ArrayEnv::new(format!("{}matrix", prefix))
+            } else {
+                ArrayEnv::new(format!("{}matrix*", prefix)).with_args(format!("[{}]", align))
+            })
+        }
+        None if array.array_stretch == 1.2 => {
+            let d = if display { "d" } else { "" };
+            match delims {
+                Some(("\\{", ".")) => Some(ArrayEnv::new(format!("{}cases", d))),
+                Some((".", "\\}")) => Some(ArrayEnv::new(format!("{}rcases", d))),
+                _ => None,
+            }
+        }
+        None => None,
+    }
+}
+
+struct LatexWriter<'o, 'a> {
+    options: &'o LatexOptions<'a>,
+    out: String,
+    /// Whether the last thing written was a `\\`, which would read a following `[` as the
+    /// start of its optional argument
+    guard_bracket: bool,
+    /// Whether the last thing written was source that ends in a number or a unit, like
+    /// `\char37`, which would read a following letter or digit as part of it
+    guard_alphanumeric: bool,
+    /// Whether nothing has been written since the start of a list, so that a script written
+    /// here has nothing before it to attach to
+    list_start: bool,
+    /// The value of `\arraystretch` at this point of the output
+    array_stretch: f64,
+    /// Whether the expansions of builtin macros are written as the macro
+    builtin_names: bool,
+}
+impl<'o, 'a> LatexWriter<'o, 'a> {
+    fn new(options: &'o LatexOptions<'a>) -> Self {
+        LatexWriter {
+            options,
+            out: String::new(),
+            guard_bracket: false,
+            guard_alphanumeric: false,
+            list_start: false,
+            array_stretch: 1.0,
+            builtin_names: true,
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        let Some(first) = text.chars().next() else {
+            return;
+        };
+
+        if (self.guard_bracket && first == '[')
+            || (self.guard_alphanumeric && first.is_ascii_alphanumeric())
+            || ((first.is_ascii_alphabetic() || first == '@') && ends_with_control_word(&self.out))
+        {
+            self.out.push(' ');
+        }
+
+        self.guard_bracket = false;
+        self.guard_alphanumeric = false;
+        self.list_start = false;
+        self.out.push_str(text);
+    }
+
+    fn push_size(&mut self, size: &Measurement) {
+        self.push("{");
+        self.push(&size.to_string());
+        self.push("}");
+    }
+
+    fn push_color(&mut self, color: &Color) {
+        self.push("{");
+        self.push(&color.to_string());
+        self.push("}");
+    }
+
+    fn write_list(&mut self, nodes: &[ParseNode]) {
+        if let [ParseNode::GenFrac(frac)] = nodes {
+            if let Some(infix) = infix_name(frac) {
+                self.write_infix(frac, infix);
+                return;
+            }
+        }
+
+        let mut i = 0;
+        while i < nodes.len() {
+            self.list_start = i == 0;
+            if let Some(expansion) = self.builtin_expansions(&nodes[i..]) {
+                self.push(&expansion.name);
+                i += expansion.nodes.len();
+                continue;
+            }
+
+            self.write_node(&nodes[i], i + 1 == nodes.len());
+            i += 1;
+        }
+    }
+
+    /// The name of the builtin macro that expands to the node, if it can be given as an
+    /// argument or a script without braces
+    fn builtin_token(&self, node: &ParseNode) -> Option<&'static str> {
+        if !self.builtin_names {
+            return None;
+        }
+        builtin_expansion(node)
+            .filter(|expansion| expansion.bare)
+            .map(|expansion| expansion.name.as_str())
+    }
+
+    /// The builtin macro of several nodes that expands to the start of the list
+    fn builtin_expansions(&self, nodes: &[ParseNode]) -> Option<&'static BuiltinExpansion> {
+        if !self.builtin_names {
+            return None;
+        }
+
+        BUILTIN_EXPANSIONS.iter().find(|expansion| {
+            let len = expansion.nodes.len();
+            // A command that applies to the rest of its group can only end the list
+            let ends_early =
+                expansion.nodes.last().is_some_and(has_implicit_body) && len != nodes.len();
+            len > 1
+                && len <= nodes.len()
+                && !ends_early
+                && nodes
+                    .iter()
+                    .zip(&expansion.nodes)
+                    .all(|(node, expanded)| node.eq_no_loc(expanded))
+        })
+    }
+
+    /// Write a braced group
+    fn write_group(&mut self, nodes: &[ParseNode]) {
+        self.push("{");
+        self.write_list(nodes);
+        self.push("}");
+    }
+
+    /// Write a list that was taken from the body of an argument, which may be a single token
+    /// given without braces
+    fn write_body_arg(&mut self, nodes: &[ParseNode]) {
+        match nodes {
+            [node] if self.options.minimal_braces => {
+                if let Some(token) = self.builtin_token(node).or_else(|| arg_token(node)) {
+                    self.push(token);
+                    return;
+                }
+                self.write_group(nodes);
+            }
+            _ => self.write_group(nodes),
+        }
+    }
+
+    /// Write an argument, which the parser always turns into an ord group
+    fn write_arg(&mut self, node: &ParseNode) {
+        match node {
+            ParseNode::OrdGroup(group) => self.write_body_arg(&group.body),
+            node => self.write_group(core::slice::from_ref(node)),
+        }
+    }
+
+    /// Write an argument that had `normalize_argument` applied to it
+    fn write_normalized_arg(&mut self, node: &ParseNode) {
+        match node {
+            ParseNode::OrdGroup(group) if group.body.len() != 1 => self.write_group(&group.body),
+            node => self.write_body_arg(core::slice::from_ref(node)),
+        }
+    }
+
+    /// Write an argument that has its `\hbox` style wrapping
+    fn write_hbox_arg(&mut self, node: &ParseNode) {
+        match node {
+            ParseNode::Styling(styling) => match styling.body.as_slice() {
+                [ParseNode::OrdGroup(group)] => self.write_group(&group.body),
+                body => self.write_group(body),
+            },
+            node => self.write_arg(node),
+        }
+    }
+
+    /// Whether the node is written after a definition of `\arraystretch`, which can't be part
+    /// of an argument without braces
+    fn sets_stretch(&self, node: &ParseNode) -> bool {
+        env_stretch(node).is_some_and(|stretch| stretch != self.array_stretch)
+    }
+
+    /// Write a node with arguments that may be environments without braces, by defining the
+    /// `\arraystretch` of the first of them before the node
+    fn write_stretched_args(&mut self, args: &[Option<&ParseNode>], write: impl FnOnce(&mut Self)) {
+        let outer_stretch = self.array_stretch;
+        let stretch = args
+            .iter()
+            .flatten()
+            .filter_map(|arg| env_stretch(arg))
+            .find(|&stretch| stretch != outer_stretch);
+        if let Some(stretch) = stretch {
+            self.push(&format!("\\def\\arraystretch{{{}}}", stretch));
+            self.array_stretch = stretch;
+        }
+
+        write(self);
+
+        if stretch.is_some() {
+            self.push(&format!("\\def\\arraystretch{{{}}}", outer_stretch));
+            self.array_stretch = outer_stretch;
+        }
+    }
+
+    /// Write a primitive argument or a script, which is either a single symbol or a group
+    fn write_primitive(&mut self, node: &ParseNode) {
+        match node {
+            ParseNode::OrdGroup(group) => self.write_group(&group.body),
+            node => {
+                if let Some(token) = self.builtin_token(node).or_else(|| script_token(node)) {
+                    self.push(token);
+                } else if is_bare_argument(node) && !self.sets_stretch(node) {
+                    self.write_node(node, false);
+                } else {
+                    self.write_group(core::slice::from_ref(node));
+                }
+            }
+        }
+    }
+
+    fn write_script(&mut self, node: &ParseNode) {
+        if self.options.minimal_braces {
+            if let ParseNode::OrdGroup(group) = node {
+                if let [inner] = group.body.as_slice() {
+                    if let Some(token) = self.builtin_token(inner).or_else(|| script_token(inner)) {
+                        self.push(token);
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.write_primitive(node);
+    }
+
+    /// Write a command that applies to the rest of its group. If it isn't the last node of
+    /// its list, then it is wrapped in braces to end it early.
+    fn write_implicit(&mut self, name: &str, body: &[ParseNode], last: bool) {
+        if !last {
+            self.push("{");
+        }
+        self.push(name);
+        self.write_list(body);
+        if !last {
+            self.push("}");
+        }
+    }
+
+    /// Write the node as it was written in the source, if that parses to the same node
+    fn write_original(&mut self, node: &ParseNode) -> bool {
+        let Some(source) = &self.options.source else {
+            return false;
+        };
+        // The source is checked by parsing it alone, where `\arraystretch` isn't defined
+        if node.info().mode != Mode::Math || self.array_stretch != 1.0 {
+            return false;
+        }
+        let Some(loc) = node.loc() else {
+            return false;
+        };
+        let Some(text) = source.input.get(loc.0) else {
+            return false;
+        };
+
+        let text = text.trim_end();
+        if !text.starts_with('\\') {
+            return false;
+        }
+
+        match parse_tree(text, source.conf.clone()) {
+            Ok(nodes) if nodes.len() == 1 && nodes[0].eq_no_loc(node) => {
+                self.push(text);
+                self.guard_alphanumeric = text.ends_with(|c: char| c.is_ascii_alphanumeric())
+                    && !ends_with_control_word(text);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn write_node(&mut self, node: &ParseNode, last: bool) {
+        // A `\\` has nothing to keep, and its source may be followed by a space that matters
+        let keep_source = !matches!(node, ParseNode::Cr(_))
+            && (last || !has_implicit_body(node));
+        if keep_source && self.write_original(node) {
+            return;
+        }
+        if let Some(expansion) = self.builtin_names.then(|| builtin_expansion(node)).flatten() {
+            self.push(&expansion.name);
+            return;
+        }
+
+        match node {
+            // A `\\` in text mode is a non-breaking space
+            ParseNode::Spacing(spacing) if spacing.text == "\u{a0}" => {
+                self.push("\\\\");
+                self.guard_bracket = true;
+            }
+            // Written as `~`, which unlike the control word doesn't swallow a following space
+            ParseNode::Spacing(spacing) if spacing.text == "\\nobreakspace" => self.push("~"),
+            ParseNode::TextOrd(ord) if is_char_ord(&ord.text, ord.info.mode) => {
+                let c = ord.text.chars().next().unwrap_or_default();
+                if c.is_ascii_alphabetic() || c == '@' {
+                    self.push(&format!("\\char`{}", c));
+                } else if c.is_whitespace() {
+                    self.push(&format!("\\char{}", c as u32));
+                    self.guard_alphanumeric = true;
+                } else {
+                    // Any other character can be given as a control symbol, like `\char`\{`
+                    self.push(&format!("\\char`\\{}", c));
+                }
+            }
+            ParseNode::Atom(_)
+            | ParseNode::MathOrd(_)
+            | ParseNode::Spacing(_)
+            | ParseNode::TextOrd(_)
+            | ParseNode::AccentToken(_)
+            | ParseNode::OpToken(_) => self.push(node.text().unwrap_or_default()),
+            ParseNode::OrdGroup(group) => {
+                if group.semi_simple == Some(true) {
+                    self.push("\\begingroup");
+                    self.write_list(&group.body);
+                    self.push("\\endgroup");
+                } else {
+                    self.write_group(&group.body);
+                }
+            }
+            ParseNode::SupSub(sup_sub) => self.write_sup_sub(sup_sub),
+            ParseNode::Array(array) => self.write_array(array, None),
+            ParseNode::LeftRight(left_right) => self.write_left_right(left_right),
+            ParseNode::LeftRightRight(right) => {
+                self.push("\\right");
+                self.push(delim_source(&right.delim));
+            }
+            ParseNode::Middle(middle) => {
+                self.push("\\middle");
+                self.push(delim_source(&middle.delim));
+            }
+            ParseNode::DelimSizing(delim) => {
+                let size = match delim.size {
+                    DelimSize::One => "\\big",
+                    DelimSize::Two => "\\Big",
+                    DelimSize::Three => "\\bigg",
+                    DelimSize::Four => "\\Bigg",
+                };
+                let class = match delim.m_class {
+                    MClass::Open => "l",
+                    MClass::Close => "r",
+                    MClass::Rel => "m",
+                    MClass::Ord => "",
+                };
+                self.push(&format!("{}{}", size, class));
+                self.push(delim_source(&delim.delim));
+            }
+            ParseNode::CdLabel(label) => self.write_arg(&label.label),
+            ParseNode::CdLabelParentNode(parent) => self.write_arg(&parent.fragment),
+            ParseNode::Color(color) if unsupported_cmd(color).is_some() => {
+                // The arguments aren't kept, and an untrusted command ignores them
+                let name = unsupported_cmd(color).unwrap_or_default();
+                self.push(&name);
+                let num_args = FUNCTIONS.get(&name).map_or(0, |func| func.prop.num_args);
+                for _ in 0..num_args {
+                    self.push("{}");
+                }
+            }
+            ParseNode::Color(color) => {
+                self.push("\\textcolor");
+                self.push_color(&color.color);
+                self.write_body_arg(&color.body);
+            }
+            ParseNode::ColorToken(color) => self.push(&color.color.to_string()),
+            ParseNode::Size(size) => {
+                if !size.is_blank {
+                    self.push(&size.value.to_string());
+                }
+            }
+            ParseNode::Raw(raw) => self.push(&raw.string),
+            ParseNode::Url(url) => self.push(&escape_url(&url.url)),
+            ParseNode::Op(op) => {
+                if let Some(name) = &op.name {
+                    self.push(name);
+                } else {
+                    self.push("\\mathop");
+                    self.write_body_arg(op.body.as_deref().unwrap_or_default());
+                }
+
+                if op.always_handle_sup_sub == Some(true) {
+                    self.push(if op.limits { "\\limits" } else { "\\nolimits" });
+                }
+            }
+            ParseNode::OperatorName(op) => {
+                if op.always_handle_sup_sub {
+                    self.push("\\operatorname*");
+                    self.write_group(&op.body);
+                    if op.limits {
+                        self.push("\\limits");
+                    }
+                } else {
+                    self.push("\\operatorname");
+                    self.write_group(&op.body);
+                }
+            }
+            ParseNode::Styling(styling) => {
+                let is_math_shift = styling.info.mode == Mode::Text
+                    && styling.style == Style::Text
+                    && styling.body.iter().all(|node| node.info().mode == Mode::Math);
+                if is_math_shift {
+                    // Math inside of text, which is written with `$` rather than a style switch
+                    self.push("$");
+                    self.write_list(&styling.body);
+                    self.push("$");
+                } else {
+                    self.write_implicit(style_name(styling.style), &styling.body, last);
+                }
+            }
+            ParseNode::Sizing(sizing) => {
+                let name = SIZE_FUNCS[sizing.size.clamp(1, SIZE_FUNCS.len()) - 1];
+                self.write_implicit(name, &sizing.body, last);
+            }
+            ParseNode::Text(text) => match text.font.as_deref() {
+                Some(font) if TEXT_SWITCHES.contains(&font) => {
+                    self.write_implicit(font, &text.body, last)
+                }
+                font => {
+                    self.push(font.unwrap_or("\\text"));
+                    self.write_body_arg(&text.body);
+                }
+            },
+            ParseNode::Font(font) => {
+                if font.font == "boldsymbol" {
+                    self.push("\\boldsymbol");
+                    self.write_arg(&font.body);
+                } else {
+                    self.push(&format!("\\{}", font.font));
+                    self.write_normalized_arg(&font.body);
+                }
+            }
+            ParseNode::MClass(mclass) => self.write_mclass(mclass),
+            ParseNode::GenFrac(frac) => self.write_genfrac(frac),
+            ParseNode::Infix(infix) => {
+                let name = match infix.replace_with.as_ref() {
+                    "\\frac" => "\\over",
+                    "\\binom" => "\\choose",
+                    "\\\\atopfrac" => "\\atop",
+                    "\\\\bracefrac" => "\\brace",
+                    "\\\\brackfrac" => "\\brack",
+                    _ => "\\above",
+                };
+                self.push(name);
+                if let Some(size) = &infix.size {
+                    self.push_size(size);
+                }
+            }
+            ParseNode::Accent(accent) => self.write_accent(accent),
+            ParseNode::AccentUnder(accent) => {
+                self.push(&accent.label);
+                self.write_arg(&accent.base);
+            }
+            ParseNode::HorizBrace(brace) => {
+                self.push(&brace.label);
+                // An empty size isn't read back, so a height without a thickness is given the
+                // default rule thickness of the text size
+                match (&brace.thickness, &brace.height) {
+                    (Some(thickness), height) => {
+                        self.push(&format!("[{}]", thickness.to_string()));
+                        if let Some(height) = height {
+                            self.push(&format!("[{}]", height.to_string()));
+                        }
+                    }
+                    (None, Some(height)) => {
+                        self.push(&format!("[0.04em][{}]", height.to_string()));
+                    }
+                    (None, None) => {}
+                }
+                self.write_arg(&brace.base);
+            }
+            ParseNode::XArrow(arrow) => {
+                self.push(&arrow.label);
+                if let Some(below) = &arrow.below {
+                    self.push("[");
+                    match below.as_ref() {
+                        ParseNode::OrdGroup(group) => self.write_list(&group.body),
+                        below => self.write_node(below, true),
+                    }
+                    self.push("]");
+                }
+                self.write_arg(&arrow.body);
+            }
+            ParseNode::Sqrt(sqrt) => {
+                if let Some(index) = &sqrt.index {
+                    self.push("\\sqrt[");
+                    let start = self.out.len();
+                    match index.as_ref() {
+                        ParseNode::OrdGroup(group) => self.write_list(&group.body),
+                        index => self.write_node(index, true),
+                    }
+                    // A `]` in the index would end it early, but braces around the whole index
+                    // are dropped when it is parsed
+                    if self.out[start..].contains(']') {
+                        self.out.insert(start, '{');
+                        self.out.push('}');
+                    }
+                    self.push("]");
+                    self.write_arg(&sqrt.body);
+                } else {
+                    // Without an index, the argument is parsed as a primitive
+                    self.write_stretched_args(&[Some(&sqrt.body)], |writer| {
+                        writer.push("\\sqrt");
+                        writer.write_primitive(&sqrt.body);
+                    });
+                }
+            }
+            ParseNode::Enclose(enclose) => {
+                self.push(&enclose.label);
+                match enclose.label.as_str() {
+                    "\\fcolorbox" => {
+                        if let Some(border) = &enclose.border_color {
+                            self.push_color(border);
+                        }
+                        if let Some(background) = &enclose.background_color {
+                            self.push_color(background);
+                        }
+                        self.write_arg(&enclose.body);
+                    }
+                    "\\colorbox" => {
+                        if let Some(background) = &enclose.background_color {
+                            self.push_color(background);
+                        }
+                        self.write_arg(&enclose.body);
+                    }
+                    _ => self.write_hbox_arg(&enclose.body),
+                }
+            }
+            ParseNode::Cr(cr) => {
+                self.push("\\\\");
+                if let Some(size) = &cr.size {
+                    self.push(&format!("[{}]", size.to_string()));
+                } else {
+                    self.guard_bracket = true;
+                }
+            }
+            ParseNode::Environment(env) => self.push(&format!("\\end{{{}}}", env.name)),
+            ParseNode::HBox(hbox) => {
+                self.push("\\hbox");
+                self.write_group(&hbox.body);
+            }
+            ParseNode::Href(href) => self.write_href(&href.href, &href.body),
+            ParseNode::Html(html) => {
+                let mut keys = html.attributes.keys().collect::<Vec<_>>();
+                keys.sort();
+                let (name, value) = match keys.as_slice() {
+                    [key] if key.as_str() == "id" => ("\\htmlId", html.attributes[*key].clone()),
+                    [key] if key.as_str() == "class" => {
+                        ("\\htmlClass", html.attributes[*key].clone())
+                    }
+                    [key] if key.as_str() == "style" => {
+                        ("\\htmlStyle", html.attributes[*key].clone())
+                    }
+                    keys => {
+                        let data = keys
+                            .iter()
+                            .map(|key| {
+                                let name = key.strip_prefix("data-").unwrap_or(key);
+                                format!("{}={}", name, html.attributes[*key])
+                            })
+                            .collect::<Vec<_>>();
+                        ("\\htmlData", data.join(","))
+                    }
+                };
+                self.push(name);
+                self.push("{");
+                self.push(&value);
+                self.push("}");
+                self.write_group(&html.body);
+            }
+            ParseNode::HtmlMathml(html_mathml) => {
+                self.push("\\html@mathml");
+                self.write_group(&html_mathml.html);
+                self.write_group(&html_mathml.mathml);
+            }
+            ParseNode::IncludeGraphics(graphics) => {
+                self.push(&format!(
+                    "\\includegraphics[alt={},width={},height={},totalheight={}]{{{}}}",
+                    graphics.alt,
+                    graphics.width.to_string(),
+                    graphics.height.to_string(),
+                    graphics.total_height.to_string(),
+                    escape_url(&graphics.src),
+                ));
+            }
+            ParseNode::Internal(_) => self.push("\\relax"),
+            ParseNode::Kern(kern) => {
+                let name = if matches!(kern.dimension, Measurement::Mu(_)) {
+                    "\\mkern"
+                } else {
+                    "\\kern"
+                };
+                self.push(name);
+                self.push(&kern.dimension.to_string());
+                self.guard_alphanumeric = true;
+            }
+            ParseNode::Lap(lap) => {
+                self.push(&format!("\\math{}", lap.alignment));
+                self.write_arg(&lap.body);
+            }
+            ParseNode::MathChoice(choice) => {
+                self.push("\\mathchoice");
+                for body in [
+                    &choice.display,
+                    &choice.text,
+                    &choice.script,
+                    &choice.script_script,
+                ] {
+                    self.write_group(body);
+                }
+            }
+            ParseNode::Overline(overline) => {
+                self.push("\\overline");
+                self.write_arg(&overline.body);
+            }
+            ParseNode::Underline(underline) => {
+                self.push("\\underline");
+                self.write_arg(&underline.body);
+            }
+            ParseNode::Phantom(phantom) => {
+                self.push("\\phantom");
+                self.write_body_arg(&phantom.body);
+            }
+            ParseNode::HPhantom(phantom) => {
+                self.push("\\hphantom");
+                self.write_arg(&phantom.body);
+            }
+            ParseNode::VPhantom(phantom) => {
+                self.push("\\vphantom");
+                self.write_arg(&phantom.body);
+            }
+            ParseNode::RaiseBox(raise) => {
+                self.push("\\raisebox");
+                self.push_size(&raise.dy);
+                self.write_hbox_arg(&raise.body);
+            }
+            ParseNode::Rule(rule) => {
+                self.push("\\rule");
+                if let Some(shift) = &rule.shift {
+                    self.push(&format!("[{}]", shift.to_string()));
+                }
+                self.push_size(&rule.width);
+                self.push_size(&rule.height);
+            }
+            ParseNode::Smash(smash) => {
+                self.push("\\smash");
+                match (smash.smash_height, smash.smash_depth) {
+                    (true, true) => {}
+                    (true, false) => self.push("[t]"),
+                    (false, true) => self.push("[b]"),
+                    // Anything other than `t` and `b` smashes neither
+                    (false, false) => self.push("[x]"),
+                }
+                self.write_arg(&smash.body);
+            }
+            ParseNode::VCenter(vcenter) => {
+                self.push("\\vcenter");
+                self.write_arg(&vcenter.body);
+            }
+            ParseNode::Verb(verb) => {
+                let delim = ['|', '!', '+', '/', '=', '"', '\'', ':', ';', ',', '.']
+                    .into_iter()
+                    .find(|c| !verb.body.contains(*c))
+                    .unwrap_or('|');
+                let star = if verb.star { "*" } else { "" };
+                self.push(&format!("\\verb{}{}{}{}", star, delim, verb.body, delim));
+            }
+            ParseNode::Tag(tag) => {
+                self.write_list(&tag.body);
+                self.write_tag(&tag.tag);
+            }
+        }
+    }
+
+    fn write_sup_sub(&mut self, sup_sub: &SupSubNode) {
+        // A definition after the base would take its place as the base of the scripts
+        let args = [
+            sup_sub.base.as_deref(),
+            sup_sub.sup.as_deref(),
+            sup_sub.sub.as_deref(),
+        ];
+        self.write_stretched_args(&args, |writer| {
+            if let Some(base) = &sup_sub.base {
+                writer.write_node(base, false);
+            } else if !writer.list_start {
+                // Scripts without a base would otherwise attach to what was written before them
+                writer.push("\\relax");
+            }
+            if let Some(sup) = &sup_sub.sup {
+                writer.push("^");
+                writer.write_script(sup);
+            }
+            if let Some(sub) = &sup_sub.sub {
+                writer.push("_");
+                writer.write_script(sub);
+            }
+        });
+    }
+
+    fn write_accent(&mut self, accent: &AccentNode) {
+        self.push(&accent.label);
+        if TEXT_ACCENTS.contains(&accent.label.as_ref()) {
+            self.write_primitive(&accent.base);
+        } else {
+            self.write_normalized_arg(&accent.base);
+        }
+    }
+
+    fn write_mclass(&mut self, mclass: &MClassNode) {
+        if let [ParseNode::Font(font)] = mclass.body.as_slice() {
+            if font.font == "boldsymbol" {
+                self.push("\\boldsymbol");
+                self.write_arg(&font.body);
+                return;
+            }
+        }
+
+        // \stackrel, \overset and \underset
+        if let [ParseNode::SupSub(sup_sub)] = mclass.body.as_slice() {
+            if let Some(ParseNode::Op(op)) = sup_sub.base.as_deref() {
+                if let (Some(suppress), None, Some(body)) =
+                    (op.suppress_base_shift, &op.name, &op.body)
+                {
+                    let shifted = match (suppress, &sup_sub.sup, &sup_sub.sub) {
+                        (false, Some(sup), None) => Some(("\\stackrel", sup)),
+                        (true, Some(sup), None) => Some(("\\overset", sup)),
+                        (true, None, Some(sub)) => Some(("\\underset", sub)),
+                        _ => None,
+                    };
+                    if let Some((name, shifted)) = shifted {
+                        self.push(name);
+                        self.write_arg(shifted);
+                        self.write_body_arg(body);
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.push(&format!("\\math{}", mclass.m_class.trim_start_matches('m')));
+        self.write_body_arg(&mclass.body);
+    }
+
+    fn write_genfrac(&mut self, frac: &GenFracNode) {
+        if let Some(infix) = infix_name(frac) {
+            // An infix command takes the whole group, so it needs a group of its own
+            self.push("{");
+            self.write_infix(frac, infix);
+            self.push("}");
+            return;
+        }
+
+        let delims = (frac.left_delim.as_deref(), frac.right_delim.as_deref());
+        let size = match frac.size {
+            StyleAuto::Auto => Some(""),
+            StyleAuto::Style(Style::Display) => Some("d"),
+            StyleAuto::Style(Style::Text) => Some("t"),
+            _ => None,
+        };
+
+        let name = if frac.continued {
+            Some("\\cfrac".to_string())
+        } else if frac.bar_size.is_none() {
+            match (frac.has_bar_line, delims, size) {
+                (true, (None, None), Some(size)) => Some(format!("\\{}frac", size)),
+                (false, (Some("("), Some(")")), Some(size)) => Some(format!("\\{}binom", size)),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if let Some(name) = name {
+            self.push(&name);
+        } else {
+            let style = match frac.size {
+                StyleAuto::Auto => "",
+                StyleAuto::Style(Style::Display) => "0",
+                StyleAuto::Style(Style::Text) => "1",
+                StyleAuto::Style(Style::Script) => "2",
+                StyleAuto::Style(Style::ScriptScript) => "3",
+            };
+            let bar = match &frac.bar_size {
+                Some(size) => size.to_string(),
+                None if frac.has_bar_line => String::new(),
+                None => "0pt".to_string(),
+            };
+            self.push(&format!(
+                "\\genfrac{{{}}}{{{}}}{{{}}}{{{}}}",
+                delims.0.unwrap_or_default(),
+                delims.1.unwrap_or_default(),
+                bar,
+                style
+            ));
+        }
+
+        self.write_arg(&frac.numer);
+        self.write_arg(&frac.denom);
+    }
+
+    fn write_infix(&mut self, frac: &GenFracNode, name: &str) {
+        self.write_infix_side(&frac.numer);
+        self.push(name);
+        self.write_infix_side(&frac.denom);
+    }
+
+    /// Write the numerator or denominator of an infix fraction, which are made into groups by
+    /// the parser unless they were already a single group
+    fn write_infix_side(&mut self, node: &ParseNode) {
+        let needs_group = |group: &[ParseNode]| match group {
+            [ParseNode::OrdGroup(_)] => true,
+            // Another infix command would take over the whole fraction
+            [ParseNode::GenFrac(frac)] => infix_name(frac).is_some(),
+            _ => false,
+        };
+
+        match node {
+            ParseNode::OrdGroup(group) if !needs_group(&group.body) => self.write_list(&group.body),
+            node => self.write_node(node, false),
+        }
+    }
+
+    fn write_left_right(&mut self, left_right: &LeftRightNode) {
+        if let [ParseNode::Array(array)] = left_right.body.as_slice() {
+            let delims = (left_right.left.as_str(), left_right.right.as_str());
+            if let Some(env) = array_env(array, Some(delims)) {
+                self.write_env(array, env);
+                return;
+            }
+        }
+
+        self.push("\\left");
+        self.push(delim_source(&left_right.left));
+        self.write_list(&left_right.body);
+        self.push("\\right");
+        self.push(delim_source(&left_right.right));
+    }
+
+    fn write_array(&mut self, array: &ArrayNode, delims: Option<(&str, &str)>) {
+        let env = array_env(array, delims).unwrap_or_else(|| {
+            // There's no environment that produces exactly this, so use the closest
+            let spec = col_spec(array.cols.as_deref().unwrap_or_default());
+            ArrayEnv::new("array").with_args(format!("{{{}}}", spec))
+        });
+        self.write_env(array, env);
+    }
+
+    fn write_env(&mut self, array: &ArrayNode, env: ArrayEnv) {
+        // The definition is restored after the environment rather than scoped by a group, which
+        // would be read back as an extra node
+        let outer_stretch = self.array_stretch;
+        let stretch = reads_stretch(&env) && array.array_stretch != outer_stretch;
+        if stretch {
+            self.push(&format!("\\def\\arraystretch{{{}}}", array.array_stretch));
+            self.array_stretch = array.array_stretch;
+        }
+
+        self.push(&format!("\\begin{{{}}}", env.name));
+        self.push(&env.args);
+
+        if array.is_cd == Some(true) {
+            self.write_cd_rows(array);
+        } else {
+            self.write_rows(array);
+        }
+
+        self.push(&format!("\\end{{{}}}", env.name));
+
+        if stretch {
+            self.push(&format!("\\def\\arraystretch{{{}}}", outer_stretch));
+            self.array_stretch = outer_stretch;
+        }
+    }
+
+    fn write_rows(&mut self, array: &ArrayNode) {
+        let tags = array.tags.as_deref().unwrap_or_default();
+        let numbered = numbered(tags);
+        let rows = array.body.len().max(tags.len());
+
+        for i in 0..rows {
+            for &dashed in array.h_lines_before_row.get(i).into_iter().flatten() {
+                self.push(if dashed { "\\hdashline" } else { "\\hline" });
+            }
+
+            for (j, cell) in array.body.get(i).into_iter().flatten().enumerate() {
+                if j > 0 {
+                    self.push("&");
+                }
+                match cell {
+                    ParseNode::Styling(styling) => match styling.body.as_slice() {
+                        [ParseNode::OrdGroup(group)] => self.write_list(&group.body),
+                        body => self.write_list(body),
+                    },
+                    cell => self.write_node(cell, true),
+                }
+            }
+
+            match tags.get(i) {
+                Some(ArrayTag::Boolean(false)) if numbered => self.push("\\nonumber"),
+                Some(ArrayTag::Tag(tag)) => self.write_tag(tag),
+                _ => {}
+            }
+
+            if let Some(gap) = array.row_gaps.get(i) {
+                self.push("\\\\");
+                if let Some(gap) = gap {
+                    self.push(&format!("[{}]", gap.to_string()));
+                } else {
+                    self.guard_bracket = true;
+                }
+            }
+        }
+
+        for lines in array.h_lines_before_row.iter().skip(rows.max(1)) {
+            for &dashed in lines {
+                self.push(if dashed { "\\hdashline" } else { "\\hline" });
+            }
+        }
+    }
+
+    fn write_cd_rows(&mut self, array: &ArrayNode) {
+        for (i, row) in array.body.iter().enumerate() {
+            if i > 0 {
+                self.push("\\\\");
+            }
+
+            for (j, cell) in row.iter().enumerate() {
+                let ParseNode::Styling(cell) = cell else {
+                    continue;
+                };
+
+                // Rows alternate between objects and arrows, but the odd rows start with an arrow
+                if (i + j) % 2 == 0 {
+                    self.write_list(cell.body.get(1..).unwrap_or_default());
+                } else if let [ParseNode::OrdGroup(arrow)] = cell.body.as_slice() {
+                    self.write_cd_arrow(&arrow.body);
+                }
+            }
+        }
+    }
+
+    fn write_cd_arrow(&mut self, body: &[ParseNode]) {
+        let Some(pos) = body
+            .iter()
+            .position(|node| !matches!(node, ParseNode::OrdGroup(_)))
+        else {
+            return;
+        };
+        let arrow = body[pos].text().unwrap_or_default().to_string();
+
+        self.push("@");
+        self.push(&arrow);
+        if "<>AV".contains(arrow.as_str()) {
+            let below = cd_label(pos.checked_sub(1).and_then(|i| body.get(i)));
+            let above = cd_label(body.get(pos + 1));
+
+            self.write_list(below);
+            self.push(&arrow);
+            self.write_list(above);
+            self.push(&arrow);
+        }
+    }
+
+    /// Write the `\tag` for a display equation or a row of an `{align}`, which holds the
+    /// parsed `\text{...}` that `\tag` expands to
+    fn write_tag(&mut self, tag: &[ParseNode]) {
+        if let [ParseNode::Text(text)] = tag {
+            if text.font.as_deref() == Some("\\text") {
+                if let [ParseNode::TextOrd(open), ParseNode::OrdGroup(group), ParseNode::TextOrd(close)] =
+                    text.body.as_slice()
+                {
+                    if open.text == "(" && close.text == ")" {
+                        self.push("\\tag");
+                        self.write_group(&group.body);
+                        return;
+                    }
+                }
+
+                self.push("\\tag*");
+                self.write_group(&text.body);
+                return;
+            }
+        }
+
+        self.push("\\tag*");
+        self.write_group(tag);
+    }
+
+    fn write_href(&mut self, href: &str, body: &[ParseNode]) {
+        // \url{...} sets the url in typewriter text
+        if let [ParseNode::Text(text)] = body {
+            let url_chars = href.chars().map(|c| {
+                if c == '~' {
+                    "\\textasciitilde".to_string()
+                } else {
+                    c.to_string()
+                }
+            });
+            let is_url = text.font.as_deref() == Some("\\texttt")
+                && text.body.len() == href.chars().count()
+                && text
+                    .body
+                    .iter()
+                    .zip(url_chars)
+                    .all(|(node, c)| matches!(node, ParseNode::TextOrd(ord) if ord.text == c));
+            if is_url {
+                self.push("\\url");
+                self.push("{");
+                self.push(&escape_url(href));
+                self.push("}");
+                return;
+            }
+        }
+
+        self.push("\\href");
+        self.push("{");
+        self.push(&escape_url(href));
+        self.push("}");
+        self.write_group(body);
+    }
+}
+
+/// The name of the command that an unsupported command node stands for, which is the command
+/// written out in the error color
+fn unsupported_cmd(color: &ColorNode) -> Option<String> {
+    let error_color = ParserConfig::default().error_color;
+    if color.color != Color::RGBA(error_color.into_array()) {
+        return None;
+    }
+
+    let [ParseNode::Text(text)] = color.body.as_slice() else {
+        return None;
+    };
+    let name = text
+        .body
+        .iter()
+        .map(|node| match node {
+            ParseNode::TextOrd(ord) => Some(ord.text.as_ref()),
+            _ => None,
+        })
+        .collect::<Option<String>>()?;
+
+    is_control_sequence(&name).then_some(name)
+}
+
+/// Whether the text of a `textord` would be read as something else, which happens for the
+/// characters made by `\char` and for the ones that are left in the MathML of a macro: a
+/// different kind of symbol, a macro like `≠`, one of TeX's special characters, or a character
+/// that the lexer doesn't read by itself, like a lone combining mark
+fn is_char_ord(text: &str, mode: Mode) -> bool {
+    let mut chars = text.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return false;
+    };
+
+    if TEX_SPECIALS.contains(&c)
+        || is_combining_mark(c)
+        || !is_token_char(c)
+        || BUILTIN_MACROS.contains_back_macro(text)
+    {
+        return true;
+    }
+
+    match SYMBOLS.get(mode, text) {
+        Some(symbol) => symbol.group != Group::NonAtom(NonAtom::TextOrd),
+        None => mode == Mode::Math && c.is_alphabetic(),
+    }
+}
+
+/// The body of a label on a `{CD}` arrow
+fn cd_label(node: Option<&ParseNode>) -> &[ParseNode] {
+    match node {
+        Some(ParseNode::OrdGroup(group)) => &group.body,
+        _ => &[],
+    }
+}
+
+/// The infix command a fraction has to be written with, for those that have no prefix form
+fn infix_name(frac: &GenFracNode) -> Option<&'static str> {
+    if frac.continued
+        || frac.has_bar_line
+        || frac.bar_size.is_some()
+        || frac.size != StyleAuto::Auto
+    {
+        return None;
+    }
+
+    match (frac.left_delim.as_deref(), frac.right_delim.as_deref()) {
+        (None, None) => Some("\\atop"),
+        (Some("\\{"), Some("\\}")) => Some("\\brace"),
+        (Some("\\["), Some("\\]")) => Some("\\brack"),
+        _ => None,
+    }
+}
+
+/// Whether the node is written as a command that applies to the rest of its group
+fn has_implicit_body(node: &ParseNode) -> bool {
+    match node {
+        ParseNode::Styling(styling) => styling.info.mode != Mode::Text,
+        ParseNode::Sizing(_) => true,
+        ParseNode::Text(text) => text
+            .font
+            .as_deref()
+            .map(|font| TEXT_SWITCHES.contains(&font))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Whether the node is written as a command that can be given as a script or a primitive
+/// argument without braces
+fn is_bare_argument(node: &ParseNode) -> bool {
+    match node {
+        ParseNode::Text(_) => !has_implicit_body(node),
+        ParseNode::GenFrac(frac) => infix_name(frac).is_none() && !frac.continued,
+        // A `\relax` in braces would be dropped, leaving an empty group
+        ParseNode::Internal(_) => true,
+        // Environments can also be given without braces
+        ParseNode::Font(_)
+        | ParseNode::MathChoice(_)
+        | ParseNode::LeftRight(_)
+        | ParseNode::Array(_) => true,
+        _ => false,
+    }
+}
@@ -1,31 +1,44 @@
 #![allow(clippy::upper_case_acronyms)]
 
-use std::{borrow::Cow, collections::HashMap};
+use alloc::borrow::Cow;
 
-use once_cell::sync::Lazy;
-use regex::Regex;
+use hashbrown::HashMap;
 
+use crate::prelude::*;
 use crate::{parser::{ParseError, StrictMode}, util::SourceLocation};
 
-static TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // This does not include the verb parts (handled specially in lex())
-    const REGEX_TEXT: &str =
-        "([ \\r\\n\\t]+)|\\\\(\\n|[ \\r\\t]+\\n?)[ \\r\\t]*|([!-\\[\\]-\\u2027\\u202A-\\uD7FF\\uF900-\\uFFFF][\\u0300-\\u036f]*|(\\\\[a-zA-Z@]+)[ \\r\\n\\t]*|\\\\.)";
-    // KaTeX match index to our index mapping
-    // [1] => [1] regular whitespace
-    // [2] => [2] backslash whitespace, \whitespace
-    // [3] 'anything else'
-    //   [4] [5] => nonexistent
-    // [6] => [4] backslash followed by word, excluding any trailing whitespace
-    Regex::new(REGEX_TEXT).unwrap()
-});
-
-// Regex to detect \verb or \verb* at start of input
-static VERB_START_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // Match \verb* followed by any non-newline char (delimiter)
-    // or \verb followed by any char that's not * or a-zA-Z (delimiter)
-    Regex::new(r"^\\verb(\*)?([^a-zA-Z\n])").unwrap()
-});
+/// Whether the character is whitespace, which is lexed as a single space token
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\r' | '\n' | '\t')
+}
+
+/// Whether the character is a combining diacritical mark, which is part of the token of the
+/// character before it
+pub(crate) fn is_combining_mark(ch: char) -> bool {
+    ('\u{0300}'..='\u{036f}').contains(&ch)
+}
+
+/// Whether the character can be a token by itself.  
+/// This is every character except for whitespace, control characters, the backslash, the line
+/// and paragraph separators and the private use area of the basic multilingual plane, as in
+/// KaTeX.
+pub(crate) fn is_token_char(ch: char) -> bool {
+    matches!(ch,
+        '!'..='['
+        | ']'..='\u{2027}'
+        | '\u{202A}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{10FFFF}')
+}
+
+/// Whether the character can be in the name of a control word, like `\frac`
+fn is_control_word_char(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '@'
+}
+
+/// The length of the characters at the start of the text which match the predicate
+fn prefix_len(text: &str, pred: impl Fn(char) -> bool) -> usize {
+    text.find(|ch: char| !pred(ch)).unwrap_or(text.len())
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
@@ -71,13 +84,10 @@ impl<'a> Lexer<'a> {
         let input = &self.input[self.pos..];
 
         // Handle \verb and \verb* specially since they need delimiter matching
-        if let Some(verb_match) = VERB_START_REGEX.captures(input) {
-            // Note: star detection (verb_match.get(1)) is done in parser, not needed here
-            let delimiter_match = verb_match.get(2).unwrap();
-            let delimiter = delimiter_match.as_str().chars().next().unwrap();
+        if let Some(content_start) = Lexer::verb_start(input) {
+            let delimiter = input[..content_start].chars().next_back().unwrap();
 
             // Start searching for closing delimiter after the opening delimiter
-            let content_start = verb_match.get(0).unwrap().end();
             let rest = &input[content_start..];
 
             // Find the closing delimiter (must not be newline)
@@ -104,60 +114,108 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let text = if let Some(capture) = TOKEN_REGEX.captures(input) {
-            let whole_match_end = capture.get(0).unwrap().end();
-            let (text, end) = if let Some(mac) = capture.get(4) {
-                // backslash macro (trailing whitespace is part of the full match)
-                (mac.as_str(), whole_match_end)
-            } else if let Some(mac) = capture.get(3) {
-                // other things
-                (mac.as_str(), whole_match_end)
-            } else if let Some(_mac) = capture.get(2) {
-                // TODO: is this correct?
-                ("\\ ", whole_match_end)
-            } else {
-                // TODO: is this correct?
-                (" ", whole_match_end)
-            };
-
-            self.pos += end;
-
-            // Ignore everything after on the same line as comment character
-            if text.len() == 1 {
-                if let Some(first) = text.chars().next() {
-                    if self.is_comment_character(first) {
-                        // TODO: ensure pos is valid
-                        let dest = &self.input[self.pos..];
-                        if let Some(nl_index) = dest.find('\n') {
-                            self.pos += nl_index + '\n'.len_utf8();
-                        } else {
-                            // Comment without newline - report strict error
-                            if self.conf.strict == StrictMode::Error {
-                                return Err(ParseError::CommentWithoutNewline);
-                            }
-                            // eof
-                            self.pos = self.input.len();
-                        }
-                        return self.lex();
+        let (text, end) = Lexer::scan(input)
+            .ok_or_else(|| ParseError::UnexpectedChar(self.pos, input.chars().next().unwrap()))?;
+        self.pos += end;
+
+        // Ignore everything after on the same line as comment character
+        let mut chars = text.chars();
+        if let (Some(first), None) = (chars.next(), chars.next()) {
+            if self.is_comment_character(first) {
+                let dest = &self.input[self.pos..];
+                if let Some(nl_index) = dest.find('\n') {
+                    self.pos += nl_index + '\n'.len_utf8();
+                } else {
+                    // Comment without newline - report strict error
+                    if self.conf.strict == StrictMode::Error {
+                        return Err(ParseError::CommentWithoutNewline);
                     }
+                    // eof
+                    self.pos = self.input.len();
                 }
+                return self.lex();
             }
-
-            text
-        } else {
-            return Err(ParseError::UnexpectedChar(
-                self.pos,
-                input.chars().nth(self.pos).unwrap(),
-            ));
-        };
+        }
 
         Ok(Token::new(text, SourceLocation(initial_pos..self.pos)))
     }
 
+    /// If the input starts with `\verb` or `\verb*` and its opening delimiter, get the length
+    /// up to and including the delimiter.  
+    /// The delimiter can be any character other than a newline or a letter, so `\verb*` followed
+    /// by a letter is `\verb` delimited by `*`.
+    fn verb_start(input: &str) -> Option<usize> {
+        let rest = input.strip_prefix("\\verb")?;
+        let is_delimiter = |ch: char| ch != '\n' && !ch.is_ascii_alphabetic();
+
+        let mut chars = rest.chars();
+        match (chars.next()?, chars.next()) {
+            ('*', Some(delimiter)) if is_delimiter(delimiter) => {
+                Some("\\verb*".len() + delimiter.len_utf8())
+            }
+            (delimiter, _) if is_delimiter(delimiter) => {
+                Some("\\verb".len() + delimiter.len_utf8())
+            }
+            _ => None,
+        }
+    }
+
+    /// Scan the token at the start of the input, returning its text and the length of the input
+    /// that it consumes, which includes any whitespace skipped after it
+    fn scan(input: &'a str) -> Option<(&'a str, usize)> {
+        let mut chars = input.chars();
+        let first = chars.next()?;
+
+        if is_whitespace(first) {
+            return Some((" ", prefix_len(input, is_whitespace)));
+        }
+
+        if first == '\\' {
+            let rest = &input[1..];
+            let second = rest.chars().next()?;
+
+            // A control space, a backslash followed by whitespace which is on at most one line
+            if second == '\n' || matches!(second, ' ' | '\r' | '\t') {
+                let is_inline_space = |ch: char| matches!(ch, ' ' | '\r' | '\t');
+                let mut end = if second == '\n' {
+                    1
+                } else {
+                    let end = prefix_len(rest, is_inline_space);
+                    end + usize::from(rest[end..].starts_with('\n'))
+                };
+                end += prefix_len(&rest[end..], is_inline_space);
+                return Some(("\\ ", 1 + end));
+            }
+
+            // A control word, which skips the whitespace after it
+            if is_control_word_char(second) {
+                let name_len = 1 + prefix_len(rest, is_control_word_char);
+                let end = name_len + prefix_len(&input[name_len..], is_whitespace);
+                return Some((&input[..name_len], end));
+            }
+
+            // A control symbol
+            let end = 1 + second.len_utf8();
+            return Some((&input[..end], end));
+        }
+
+        if is_token_char(first) {
+            let end = first.len_utf8() + prefix_len(chars.as_str(), is_combining_mark);
+            return Some((&input[..end], end));
+        }
+
+        None
+    }
+
     fn is_comment_character(&self, ch: char) -> bool {
         self.catcode(ch) == Some(CategoryCode::Comment)
     }
 
+    /// The position in the input that the next token will be lexed from
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
     pub(crate) fn set_catcode(&mut self, ch: char, code: CategoryCode) {
         self.catcodes.insert(ch, code);
     }
@@ -252,7 +310,7 @@ impl<'a> Token<'a> {
 mod tests {
     use crate::lexer::Token;
 
-    use crate::parser::StrictMode;
+    use crate::parser::{ParseError, StrictMode};
 
     use super::{Lexer, LexerConf};
 
@@ -294,4 +352,64 @@ mod tests {
         assert_eq!(lexer.lex().unwrap(), Token::new(")", 34..35));
         assert_eq!(lexer.lex().unwrap(), Token::new(" ", 35..36));
     }
+
+    fn lexer(input: &str) -> Lexer<'_> {
+        Lexer::new(input, LexerConf { strict: StrictMode::Warn })
+    }
+
+    #[test]
+    fn control_words_and_symbols() {
+        let mut lex = lexer("\\alpha  \n x\\, \\@foo\\\\");
+        assert_eq!(lex.lex().unwrap(), Token::new("\\alpha", 0..10));
+        assert_eq!(lex.lex().unwrap(), Token::new("x", 10..11));
+        assert_eq!(lex.lex().unwrap(), Token::new("\\,", 11..13));
+        assert_eq!(lex.lex().unwrap(), Token::new(" ", 13..14));
+        assert_eq!(lex.lex().unwrap(), Token::new("\\@foo", 14..19));
+        assert_eq!(lex.lex().unwrap(), Token::new("\\\\", 19..21));
+        assert!(lex.lex().unwrap().is_eof());
+    }
+
+    #[test]
+    fn control_space() {
+        let mut lex = lexer("a\\ \t\n b\\\n\nc");
+        assert_eq!(lex.lex().unwrap(), Token::new("a", 0..1));
+        assert_eq!(lex.lex().unwrap(), Token::new("\\ ", 1..6));
+        assert_eq!(lex.lex().unwrap(), Token::new("b", 6..7));
+        // Only one newline is part of the control space
+        assert_eq!(lex.lex().unwrap(), Token::new("\\ ", 7..9));
+        assert_eq!(lex.lex().unwrap(), Token::new(" ", 9..10));
+        assert_eq!(lex.lex().unwrap(), Token::new("c", 10..11));
+    }
+
+    #[test]
+    fn unicode() {
+        // Combining marks are part of the token of the character before them
+        let mut lex = lexer("e\u{301}\u{308}\u{1D49C}😀");
+        assert_eq!(lex.lex().unwrap(), Token::new("e\u{301}\u{308}", 0..5));
+        assert_eq!(lex.lex().unwrap(), Token::new("\u{1D49C}", 5..9));
+        assert_eq!(lex.lex().unwrap(), Token::new("😀", 9..13));
+        assert!(lex.lex().unwrap().is_eof());
+
+        let mut lex = lexer("x\u{E000}");
+        assert_eq!(lex.lex().unwrap(), Token::new("x", 0..1));
+        assert!(matches!(
+            lex.lex(),
+            Err(ParseError::UnexpectedChar(1, '\u{E000}'))
+        ));
+    }
+
+    #[test]
+    fn verb() {
+        let mut lex = lexer("\\verb|a b|\\verb*+x+\\verb*x*");
+        assert_eq!(lex.lex().unwrap(), Token::new("\\verb|a b|", 0..10));
+        assert_eq!(lex.lex().unwrap(), Token::new("\\verb*+x+", 10..19));
+        // `\verb*` followed by a letter is delimited by the `*`
+        assert_eq!(lex.lex().unwrap(), Token::new("\\verb*x*", 19..27));
+
+        let mut lex = lexer("\\verb|a\nb|");
+        assert!(matches!(lex.lex(), Err(ParseError::VerbEndedByNewline)));
+
+        let mut lex = lexer("\\verbatim");
+        assert_eq!(lex.lex().unwrap(), Token::new("\\verbatim", 0..9));
+    }
 }
@@ -1,22 +1,38 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::upper_case_acronyms)]
 
-use std::borrow::Cow;
+extern crate alloc;
+
+use alloc::{borrow::Cow, sync::Arc};
 
 #[cfg(feature = "html")]
 use dom_tree::Span;
+#[cfg(all(feature = "html", feature = "mathml"))]
+use tree::{build_tree, MlNode};
 use expander::Mode;
 use font_metrics::{get_global_metrics, FontMetrics};
 use lexer::Token;
-use once_cell::sync::OnceCell;
+use macr::Macros;
 use parse_node::{Color, NodeInfo, ParseNode, TagNode};
 use parser::{ParseError, Parser, ParserConfig};
+use prelude::*;
 use style::{StyleId, DISPLAY_STYLE, TEXT_STYLE};
-use tree::{build_tree, ClassList, MlNode, OutputType};
+use tree::ClassList;
+#[cfg(any(feature = "html", feature = "mathml"))]
+use tree::OutputType;
 use unit::Em;
 
+pub mod a11y;
 pub mod array;
+pub mod asciimath;
+#[cfg(feature = "html")]
 pub mod build_common;
 mod builtin_macros;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(any(feature = "physics", feature = "siunitx", feature = "auto-render"))]
+pub mod contrib;
+#[cfg(feature = "html")]
 pub mod delimiter;
 #[cfg(feature = "html")]
 pub mod dom_tree;
@@ -24,27 +40,39 @@ pub mod environments;
 pub mod expander;
 pub mod font_metrics;
 pub mod ir;
+pub mod latex;
 mod font_metrics_data;
 pub mod functions;
 #[cfg(feature = "html")]
 pub mod html;
+pub mod incremental;
 pub mod lexer;
 pub mod macr;
+#[cfg(feature = "mathml")]
 pub mod mathml;
 #[cfg(feature = "mathml")]
+pub mod mathml_core;
+mod mathml_entities;
+pub mod mathml_reader;
+#[cfg(feature = "mathml")]
 pub mod mathml_tree;
 pub mod namespace;
+pub mod omml;
 pub mod parse_node;
 pub mod parser;
+mod prelude;
 mod spacing_data;
 mod spec;
 mod stretchy;
 pub mod style;
+mod sync;
 mod svg_geometry;
 pub mod symbols;
 pub mod tree;
+pub mod typst;
 pub mod unicode;
 pub mod unicode_scripts;
+pub mod unicode_text;
 pub mod unit;
 mod util;
 
@@ -68,14 +96,35 @@ impl FontWeight {
 pub enum FontShape {
     TextIt,
     TextUp,
+    /// Slanted. KaTeX's fonts have no slanted shape, so this is rendered with the italic fonts.
+    TextSl,
+    /// Small caps. KaTeX's fonts have no small caps, so lowercase letters are drawn as scaled
+    /// uppercase letters.
+    TextSc,
 }
 impl FontShape {
     pub fn as_str(&self) -> &'static str {
         match self {
             FontShape::TextIt => "textit",
             FontShape::TextUp => "textup",
+            FontShape::TextSl => "textsl",
+            FontShape::TextSc => "textsc",
         }
     }
+
+    /// The class that selects the font for the shape in the stylesheet
+    pub fn class(&self) -> &'static str {
+        match self {
+            // Slanted text is drawn with the italic fonts, so it is styled like them
+            FontShape::TextSl => "textit",
+            shape => shape.as_str(),
+        }
+    }
+
+    /// Whether the shape is drawn with the italic fonts
+    pub fn is_italic(&self) -> bool {
+        matches!(self, FontShape::TextIt | FontShape::TextSl)
+    }
 }
 
 const SIZE_STYLE_MAP: [[u8; 3]; 11] = [
@@ -109,24 +158,62 @@ fn size_at_style(size: usize, style: StyleId) -> usize {
 }
 
 const BASE_SIZE: usize = 6;
-#[derive(Debug, Clone, PartialEq)]
-pub struct Options {
+
+/// The fonts that options can be set to: the math fonts of the font commands and `\boldsymbol`,
+/// and the text font families. A font is one of these `&'static str`s so that it can be copied
+/// around rather than a string.
+const FONT_NAMES: &[&str] = &[
+    "mathbf",
+    "mathrm",
+    "textit",
+    "mathit",
+    "mathnormal",
+    "boldsymbol",
+    "mathbb",
+    "mathcal",
+    "mathfrak",
+    "mathscr",
+    "mathsf",
+    "mathtt",
+    "textrm",
+    "textsf",
+    "texttt",
+];
+
+/// Find the `&'static str` for a font name built at runtime.  
+/// A name that isn't in [`FONT_NAMES`] is no font, as there is nothing to render it with.
+fn font_name(font: Cow<'static, str>) -> &'static str {
+    match font {
+        Cow::Borrowed(font) => font,
+        Cow::Owned(font) => FONT_NAMES
+            .iter()
+            .find(|name| **name == font)
+            .copied()
+            .unwrap_or(""),
+    }
+}
+
+/// The state that builders pass down the tree.  
+/// This is `Copy`, borrowing the color from the parse tree, so deriving new options for a style,
+/// font or color change is only a copy. The font metrics are shared tables picked by the size.
+///
+/// The fonts are `&'static str`s rather than strings; see [`Options::with_font`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options<'a> {
     pub style: StyleId,
-    pub color: Option<Color>,
+    pub color: Option<&'a Color>,
     // TODO: is this a float? It is used to index an array..
     pub size: usize,
     pub text_size: usize,
     pub phantom: bool,
-    pub font: Cow<'static, str>,
-    pub font_family: Cow<'static, str>,
+    pub font: &'static str,
+    pub font_family: &'static str,
     pub font_weight: Option<FontWeight>,
     pub font_shape: Option<FontShape>,
     pub max_size: Em,
     pub min_rule_thickness: Em,
-    /// This is separate per options instance
-    font_metrics: OnceCell<FontMetrics>,
 }
-impl Options {
+impl<'a> Options<'a> {
     /// Create Options from a ParserConfig.
     ///
     /// This is useful for creating Options with the correct display mode,
@@ -142,23 +229,19 @@ impl Options {
             size: BASE_SIZE,
             text_size: BASE_SIZE,
             phantom: false,
-            font: Cow::Borrowed(""),
-            font_family: Cow::Borrowed(""),
+            font: "",
+            font_family: "",
             font_weight: None,
             font_shape: None,
             max_size: conf.max_size,
             min_rule_thickness: conf.min_rule_thickness,
-            font_metrics: OnceCell::new(),
         }
     }
 
-    // TODO: Can we just do this on normal `Clone`?
-    /// The intended method of cloning the options instance and then altering it. This clears the
-    /// font metrics cache.
-    pub fn clone_alter(&self) -> Options {
-        let mut opts = self.clone();
-        opts.font_metrics = Default::default();
-        opts
+    /// The intended method of cloning the options instance and then altering it.  
+    /// This is a copy, since there is no per-instance state to reset.
+    pub fn clone_alter(&self) -> Options<'a> {
+        *self
     }
 
     pub fn size_multiplier(&self) -> f64 {
@@ -170,7 +253,7 @@ impl Options {
 
     /// Returns an options object with the given style  
     /// Returns `None` if there was no changes needed
-    pub fn having_style(&self, style: StyleId) -> Option<Options> {
+    pub fn having_style(&self, style: StyleId) -> Option<Options<'a>> {
         if self.style == style {
             None
         } else {
@@ -183,13 +266,13 @@ impl Options {
 
     /// Returns an options object with a cramped version of the current style.  
     /// Returns `None` if there was no changes needed.
-    pub fn having_cramped_style(&self) -> Option<Options> {
+    pub fn having_cramped_style(&self) -> Option<Options<'a>> {
         self.having_style(self.style.cramp())
     }
 
     /// Returns an options object with the given size and in at least `\textstyle`.  
     /// Returns `None` if there was no changes needed.
-    pub fn having_size(&self, size: usize) -> Option<Options> {
+    pub fn having_size(&self, size: usize) -> Option<Options<'a>> {
         if self.size == size && self.text_size == size {
             None
         } else {
@@ -204,7 +287,7 @@ impl Options {
     /// Like applying `having_size(BASE_SIZE)` and then `having_style(style)`.  
     /// If there is no `style`, then it changes to at least `\textstyle`  
     /// Returns `None` if there was no changes needed.
-    pub fn having_base_style(&self, style: Option<StyleId>) -> Option<Options> {
+    pub fn having_base_style(&self, style: Option<StyleId>) -> Option<Options<'a>> {
         let style = style.unwrap_or_else(|| self.style.text());
         let want_size = size_at_style(BASE_SIZE, style);
         if self.size == want_size && self.text_size == BASE_SIZE && self.style == style {
@@ -219,7 +302,7 @@ impl Options {
 
     /// Remove the effect of sizing changes such as `\Huge`.  
     /// Keep the effect of the current style, such as `\scriptstyle`.  
-    pub fn having_base_sizing(&self) -> Options {
+    pub fn having_base_sizing(&self) -> Options<'a> {
         let size = match self.style.as_id() {
             // normalsize in scriptstyle
             4 | 5 => 3,
@@ -237,45 +320,53 @@ impl Options {
     }
 
     // These with functions are more in line with typical Rust conventions
-    // TODO: Do we really need to clear the font metrics cache on each of these? They aren't even
-    // used for it in the typical initialization case? Check that it isn't initialized anywhere else.
 
-    pub fn with_color(mut self, color: Color) -> Options {
+    pub fn with_color(mut self, color: &'a Color) -> Options<'a> {
         self.color = Some(color);
-        self.font_metrics = Default::default();
         self
     }
 
-    pub fn with_phantom(mut self) -> Options {
+    pub fn with_phantom(mut self) -> Options<'a> {
         self.phantom = true;
-        self.font_metrics = Default::default();
         self
     }
 
-    pub fn with_font(mut self, font: impl Into<Cow<'static, str>>) -> Options {
-        self.font = font.into();
-        self.font_metrics = Default::default();
+    /// Set the math font.  
+    /// A name built at runtime must be one of the fonts there are metrics for, such as
+    /// `"mathbf"`, or it sets no font.
+    pub fn with_font(mut self, font: impl Into<Cow<'static, str>>) -> Options<'a> {
+        self.font = font_name(font.into());
         self
     }
 
-    pub fn with_text_font_family(mut self, font_family: impl Into<Cow<'static, str>>) -> Options {
-        self.font_family = font_family.into();
-        self.font_metrics = Default::default();
+    pub fn with_text_font_family(mut self, font_family: impl Into<Cow<'static, str>>) -> Options<'a> {
+        self.font_family = font_name(font_family.into());
         self
     }
 
-    pub fn with_text_font_weight(mut self, font_weight: FontWeight) -> Options {
+    pub fn with_text_font_weight(mut self, font_weight: FontWeight) -> Options<'a> {
         self.font_weight = Some(font_weight);
-        self.font_metrics = Default::default();
         self
     }
 
-    pub fn with_text_font_shape(mut self, font_shape: FontShape) -> Options {
+    pub fn with_text_font_shape(mut self, font_shape: FontShape) -> Options<'a> {
         self.font_shape = Some(font_shape);
-        self.font_metrics = Default::default();
         self
     }
 
+    /// Reset the text font to the document font, as `\normalfont` does
+    pub fn with_normal_text_font(mut self) -> Options<'a> {
+        self.font_family = "textrm";
+        self.font_weight = None;
+        self.font_shape = None;
+        self
+    }
+
+    /// Whether text is currently italic, used by `\emph` to decide which way to toggle
+    pub fn is_text_italic(&self) -> bool {
+        self.font_shape.map(|shape| shape.is_italic()).unwrap_or(false)
+    }
+
     /// Returns the CSS sizing classes required to switch from enclosing options `oldOptions` to
     /// `self`.
     pub fn sizing_classes(&self, old_options: &Options) -> ClassList {
@@ -303,26 +394,38 @@ impl Options {
         }
     }
 
-    /// Get the font metrics, initializing if needed.
-    pub fn font_metrics(&self) -> &FontMetrics {
-        let size = self.size;
-        self.font_metrics.get_or_init(|| get_global_metrics(size))
+    /// Get the font metrics for the current size
+    pub fn font_metrics(&self) -> &'static FontMetrics {
+        get_global_metrics(self.size)
     }
 
     pub fn get_color(&self) -> Option<Color> {
         if self.phantom {
             Some(Color::Named(Cow::Borrowed("transparent")))
         } else {
-            self.color.clone()
+            self.color.cloned()
         }
     }
 }
 
 /// For now this is simply exported
 pub fn parse_tree<'a>(input: &'a str, conf: ParserConfig) -> Result<Vec<ParseNode>, ParseError> {
+    parse_tree_shared(input, conf, None)
+}
+
+/// Parse the tree with `shared` macros behind those of the `conf`, which are read from rather
+/// than copied into the parser
+pub(crate) fn parse_tree_shared(
+    input: &str,
+    conf: ParserConfig,
+    shared: Option<Arc<Macros>>,
+) -> Result<Vec<ParseNode>, ParseError> {
     let display_mode = conf.display_mode;
     let functions = &functions::FUNCTIONS;
     let mut parser = Parser::new(input, conf, functions);
+    if let Some(shared) = shared {
+        parser.gullet.macros.set_shared(shared);
+    }
 
     // TODO: Do we actually need to do these deletes as we don't currently use the same
     // macros structures each time?
@@ -344,7 +447,7 @@ pub fn parse_tree<'a>(input: &'a str, conf: ParserConfig) -> Result<Vec<ParseNod
 
         Ok(vec![ParseNode::Tag(TagNode {
             body: tree,
-            tag: parser.sub_parse(std::iter::once(Token::new_text("\\df@tag")))?,
+            tag: parser.sub_parse(core::iter::once(Token::new_text("\\df@tag")))?,
             info: NodeInfo::new_mode(Mode::Text),
         })])
     } else {
@@ -352,6 +455,39 @@ pub fn parse_tree<'a>(input: &'a str, conf: ParserConfig) -> Result<Vec<ParseNod
     }
 }
 
+/// The macros that the parser sets to keep track of an expression, rather than as definitions.
+/// `\df@tag` holds the argument of `\tag` until the end of the expression, `\@eqnsw` whether
+/// `\nonumber` was given for the current row, `\current@color` the color of `\color` for a
+/// `\right` after it, and `\color` is set to `\textcolor` by `color_is_text_color`.  
+/// A preamble leaves these as they were in its config, so that they don't carry into the
+/// expressions that use it.
+const STATE_MACROS: &[&str] = &["\\df@tag", "\\@eqnsw", "\\current@color", "\\color"];
+
+/// Parse a preamble of macro definitions, like `\\newcommand{\\R}{\\mathbb{R}}`, into the macros
+/// it defines, as well as those already in the `conf`.  
+/// These can be used as the `macros` of the config used for rendering expressions, so that the
+/// preamble is only parsed once.
+pub fn parse_preamble(preamble: &str, conf: &ParserConfig) -> Result<Macros, ParseError> {
+    let mut preamble_conf = conf.clone();
+    // The definitions have to outlive the preamble's group
+    preamble_conf.global_group = true;
+
+    let mut parser = Parser::new(preamble, preamble_conf, &functions::FUNCTIONS);
+    parser.dispatch_parse()?;
+
+    let mut macros = parser.gullet.macros.current;
+    for name in STATE_MACROS {
+        match conf.macros.get_back_macro(name) {
+            Some(replace) => macros.insert_back_macro(*name, replace.clone()),
+            None => {
+                macros.take_back_macro(name);
+            }
+        }
+    }
+
+    Ok(macros)
+}
+
 #[cfg(any(feature = "html", feature = "mathml"))]
 pub fn render_error(
     err: ParseError,
@@ -426,11 +562,130 @@ pub fn render_to_mathml_tree(expr: &str, conf: ParserConfig) -> crate::dom_tree:
     }
 }
 
+#[cfg(feature = "mathml")]
+/// Generates and returns the build tree as MathML Core, the subset of MathML that browsers
+/// implement, with no HTML.
+pub fn render_to_mathml_core_tree(
+    expr: &str,
+    conf: ParserConfig,
+) -> crate::dom_tree::Span<crate::mathml_tree::MathmlNode> {
+    use tree::build_mathml_core_tree;
+
+    match parse_tree(expr, conf.clone()) {
+        Ok(tree) => build_mathml_core_tree(&tree, conf),
+        Err(_err) => panic!("Error parsing tree: {:?}", _err),
+    }
+}
+
+#[cfg(all(feature = "html", feature = "mathml"))]
+/// Render the expression to markup in the given output, like KaTeX's `renderToString`.
+/// A parse error is returned if `throw_on_error` is set, and otherwise rendered as the
+/// expression in the `error_color`.
+pub fn render_to_string(
+    expr: &str,
+    conf: ParserConfig,
+    output: OutputType,
+) -> Result<String, ParseError> {
+    let mut renderer = Renderer::new(conf);
+    renderer.render(expr, output)?;
+    Ok(renderer.markup)
+}
+
+#[cfg(all(feature = "html", feature = "mathml"))]
+/// Render each of the expressions to markup in the given output, which gives the same results
+/// as calling [`render_to_string`] on each.  
+/// The config is cloned once for each thread, and its macros, along with the function and macro
+/// tables, are shared by every expression rather than cloned. With the `parallel` feature, the
+/// expressions are rendered on rayon's thread pool.
+pub fn render_batch(
+    exprs: &[&str],
+    conf: &ParserConfig,
+    output: OutputType,
+) -> Vec<Result<String, ParseError>> {
+    let render = |renderer: &mut Renderer, expr: &&str| {
+        renderer.render(expr, output)?;
+        Ok(renderer.markup.as_str().into())
+    };
+
+    let renderer = Renderer::new(conf.clone());
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        // Rayon makes a renderer for each group of expressions that a thread takes
+        exprs
+            .par_iter()
+            .map_init(|| renderer.clone(), render)
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        let mut renderer = renderer;
+        exprs.iter().map(|expr| render(&mut renderer, expr)).collect()
+    }
+}
+
+/// What is kept from one expression to the next when rendering them to markup.  
+/// [`render_batch`] clones this for each thread, which clones the config without its macros.
+#[cfg(all(feature = "html", feature = "mathml"))]
+#[derive(Clone)]
+struct Renderer {
+    /// The config without its macros, which is cloned for each expression
+    conf: ParserConfig,
+    /// The macros of the config, which every expression reads from and defines its own in
+    /// front of. A preamble can have many macros, so these aren't cloned.
+    macros: Arc<Macros>,
+    /// The markup of the last expression rendered. This is reused, so it grows to fit the
+    /// markup once rather than for each expression.
+    markup: String,
+}
+#[cfg(all(feature = "html", feature = "mathml"))]
+impl Renderer {
+    fn new(mut conf: ParserConfig) -> Renderer {
+        let mut macros = core::mem::take(&mut conf.macros);
+        // As `parse_tree` takes it out of the macros it is given
+        macros.take_back_macro("\\df@tag");
+        Renderer {
+            conf,
+            macros: Arc::new(macros),
+            markup: String::new(),
+        }
+    }
+
+    /// Render the expression into [`Renderer::markup`], as [`render_to_string`] does
+    fn render(&mut self, expr: &str, output: OutputType) -> Result<(), ParseError> {
+        use crate::tree::VirtualNode;
+
+        let written = parse_tree_shared(expr, self.conf.clone(), Some(self.macros.clone()))
+            .and_then(|tree| {
+                build_tree(&tree, expr, self.conf.clone(), output)
+                    .markup_into(&mut self.markup, self.conf.max_output_size)
+                    .then_some(())
+                    .ok_or(ParseError::OutputTooLarge)
+            });
+        match written {
+            Ok(()) => Ok(()),
+            Err(err) if self.conf.throw_on_error => Err(err),
+            Err(err) => {
+                render_error(err, expr, self.conf.clone()).markup_into(&mut self.markup, None);
+                Ok(())
+            }
+        }
+    }
+}
+
 // TODO: websys render function
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_tree, parser::ParserConfig, render_to_html_tree, tree::VirtualNode};
+    use core::ptr;
+
+    use crate::{
+        parse_node::Color, parse_tree, parser::ParserConfig, render_to_html_tree,
+        tree::VirtualNode, Options,
+    };
 
     #[test]
     fn test_parse_tree() {
@@ -467,4 +722,28 @@ mod tests {
 
         // panic!();
     }
+
+    #[test]
+    fn test_options() {
+        let options = Options::from_parser_conf(&ParserConfig::default());
+
+        // Fonts built at runtime are the static names, and names without metrics are no font
+        let bold = options.with_font(format!("math{}", "bf"));
+        let bold_again = options.with_font("mathbf".to_string());
+        assert_eq!(bold.font, "mathbf");
+        assert!(ptr::eq(bold.font, bold_again.font));
+        assert_eq!(options.with_font("mathunknown".to_string()).font, "");
+
+        // Colors are borrowed, and derived options share them along with the metrics
+        let color = Color::Named("red".into());
+        let red = options.with_color(&color);
+        let script = red.having_style(crate::style::SCRIPT_STYLE).unwrap();
+        assert!(ptr::eq(red.color.unwrap(), script.color.unwrap()));
+        assert_eq!(script.size, 3);
+        assert!(ptr::eq(
+            script.font_metrics(),
+            red.having_size(4).unwrap().font_metrics()
+        ));
+        assert!(!ptr::eq(script.font_metrics(), red.font_metrics()));
+    }
 }
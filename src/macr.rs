@@ -1,9 +1,14 @@
-use std::{borrow::Cow, collections::HashMap, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
+use core::sync::atomic::Ordering;
 
+use hashbrown::HashMap;
+
+use crate::prelude::*;
 use crate::{
     expander::MacroExpander,
     lexer::{Lexer, LexerConf, Token},
     parser::ParseError,
+    sync::AtomicU64,
 };
 
 /// The set of backslash macros and letter macros
@@ -18,15 +23,31 @@ pub struct Macros<V = Arc<MacroReplace>> {
     // TODO: Logically, most replacements are going to be within typical ascii range, which is very
     // narrow relative to entire unicode range.
     pub(crate) letter_macros: HashMap<char, V>,
+    /// See [`Macros::version`]
+    version: u64,
 }
 impl<V> Macros<V> {
     pub fn new_with(back_macros: HashMap<String, V>) -> Self {
         Macros {
             back_macros,
             letter_macros: HashMap::new(),
+            version: next_macros_version(),
         }
     }
 
+    /// Identifies the contents of the macros. This changes whenever they're modified, so two
+    /// macros with the same version have the same definitions.  
+    /// This is what lets [`RenderCache`](crate::cache::RenderCache) tell macro contexts apart
+    /// without comparing them.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Note that the macros have been modified
+    fn touch(&mut self) {
+        self.version = next_macros_version();
+    }
+
     pub fn contains_back_macro(&self, name: &str) -> bool {
         self.back_macros.contains_key(name)
     }
@@ -40,6 +61,7 @@ impl<V> Macros<V> {
     }
 
     pub fn get_back_macro_mut(&mut self, name: &str) -> Option<&mut V> {
+        self.touch();
         self.back_macros.get_mut(name)
     }
 
@@ -48,22 +70,27 @@ impl<V> Macros<V> {
     }
 
     pub fn get_letter_macro_mut(&mut self, name: char) -> Option<&mut V> {
+        self.touch();
         self.letter_macros.get_mut(&name)
     }
 
     pub fn take_back_macro(&mut self, name: &str) -> Option<(String, V)> {
+        self.touch();
         self.back_macros.remove_entry(name)
     }
 
     pub fn take_letter_macro(&mut self, name: char) -> Option<(char, V)> {
+        self.touch();
         self.letter_macros.remove_entry(&name)
     }
 
     pub fn insert_back_macro(&mut self, name: impl Into<String>, repl: V) {
+        self.touch();
         self.back_macros.insert(name.into(), repl);
     }
 
     pub fn insert_letter_macro(&mut self, name: char, repl: V) {
+        self.touch();
         self.letter_macros.insert(name, repl);
     }
 
@@ -92,6 +119,7 @@ impl<V> Macros<V> {
     }
 
     pub fn iter_back_macros_mut(&mut self) -> impl Iterator<Item = (&'_ String, &'_ mut V)> + '_ {
+        self.touch();
         self.back_macros.iter_mut()
     }
 
@@ -117,10 +145,17 @@ impl<V> Default for Macros<V> {
         Self {
             back_macros: Default::default(),
             letter_macros: Default::default(),
+            // Every empty set of macros is the same
+            version: 0,
         }
     }
 }
 
+fn next_macros_version() -> u64 {
+    static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+    NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum MacroIdentifier<'a> {
     /// "\macro", should include the backslash
@@ -214,8 +249,8 @@ impl MacroReplace {
         }
     }
 }
-impl std::fmt::Debug for MacroReplace {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for MacroReplace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             MacroReplace::Text(text) => f.debug_tuple("Text").field(text).finish(),
             MacroReplace::Expansion(exp) => f.debug_tuple("Expansion").field(exp).finish(),
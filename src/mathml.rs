@@ -1,4 +1,4 @@
-use std::any::{Any, TypeId};
+use core::any::{Any, TypeId};
 
 use crate::{
     build_common::FONT_MAP,
@@ -11,7 +11,7 @@ use crate::{
     symbols::{self, LIGATURES},
     tree::ClassList,
     util::{char_code_for, find_assoc_data, FontVariant},
-    FontShape, FontWeight, Options,
+    FontWeight, Options,
 };
 
 /// Takes a symbol and converts it into a MathML text node after performing optional replacement
@@ -66,35 +66,35 @@ pub(crate) fn get_variant<G: SymbolParseNode + Any + 'static>(
     if options.font_family == "texttt" {
         return Some(FontVariant::Monospace);
     } else if options.font_family == "textsf" {
-        if options.font_shape == Some(FontShape::TextIt)
+        if options.font_shape.is_some_and(|shape| shape.is_italic())
             && options.font_weight == Some(FontWeight::TextBf)
         {
             return Some(FontVariant::SansSerifBoldItalic);
-        } else if options.font_shape == Some(FontShape::TextIt) {
+        } else if options.font_shape.is_some_and(|shape| shape.is_italic()) {
             return Some(FontVariant::SansSerifItalic);
         } else if options.font_weight == Some(FontWeight::TextBf) {
             return Some(FontVariant::BoldSansSerif);
         } else {
             return Some(FontVariant::SansSerif);
         }
-    } else if options.font_shape == Some(FontShape::TextIt)
+    } else if options.font_shape.is_some_and(|shape| shape.is_italic())
         && options.font_weight == Some(FontWeight::TextBf)
     {
         return Some(FontVariant::BoldItalic);
-    } else if options.font_shape == Some(FontShape::TextIt) {
+    } else if options.font_shape.is_some_and(|shape| shape.is_italic()) {
         return Some(FontVariant::Italic);
     } else if options.font_weight == Some(FontWeight::TextBf) {
         return Some(FontVariant::Bold);
     }
 
-    let font = &options.font;
+    let font = options.font;
 
     if font.is_empty() || font == "mathnormal" {
         return None;
     }
 
     // let mode = group.mode;
-    match font.as_ref() {
+    match font {
         "mathit" => return Some(FontVariant::Italic),
         "boldsymbol" => {
             if group.type_id() == TypeId::of::<TextOrdNode>() {
@@ -164,6 +164,7 @@ pub(crate) fn build_expression(
                 if group.typ == MathNodeType::MText
                     && last_group.typ == MathNodeType::MText
                     && group.get_attribute("mathvariant") == last_group.get_attribute("mathvariant")
+                    && group.get_attribute("style") == last_group.get_attribute("style")
                 {
                     // Concatenate adjacent `<mtext`s
                     last_group.children.append(&mut group.children);
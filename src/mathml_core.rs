@@ -0,0 +1,343 @@
+//! Converting MathML into [MathML Core](https://www.w3.org/TR/mathml-core/), the part of
+//! MathML that browsers like Chromium implement.
+//!
+//! The MathML that [`build_mathml`] writes uses features of MathML 3 that MathML Core left
+//! out, which this rewrites into what Core has:
+//! - `mathvariant` is written into the text with the Mathematical Alphanumeric Symbols, like
+//!   `𝔸` for `\mathbb{A}`, keeping only `mathvariant="normal"` on single character `mi`s
+//! - `semantics` is replaced by its first child, without the TeX annotation
+//! - `mstyle` becomes an `mrow`, since its attributes are global in Core
+//! - `menclose` becomes an `mrow` with CSS borders, padding and gradients for the notations
+//! - relative `mpadded` sizes like `width="+0.6em"` become CSS padding, and negative spaces
+//!   become negative margins
+//! - attributes that Core doesn't have are removed
+//!
+//! ```
+//! # use aliter::{render_to_mathml_core_tree, parser::ParserConfig, tree::VirtualNode};
+//! let markup = render_to_mathml_core_tree(r"\mathbb{R}", ParserConfig::default()).to_markup();
+//! assert!(markup.contains("<mi>ℝ</mi>"));
+//! ```
+
+use crate::{
+    dom_tree::Span,
+    mathml::build_mathml,
+    mathml_tree::{EmptyMathNode, MathNode, MathNodeType, MathmlNode, SpaceNode, TextNode},
+    parse_node::ParseNode,
+    unicode_text::MathVariant,
+    unit::make_em,
+    Options,
+};
+
+/// Build MathML Core for the parse tree, in a span like [`build_mathml`]
+pub fn build_mathml_core(
+    tree: &[ParseNode],
+    options: &Options,
+    is_display_mode: bool,
+    for_mathml_only: bool,
+) -> Span<MathmlNode> {
+    build_mathml(tree, "", options, is_display_mode, for_mathml_only).map(to_mathml_core)
+}
+
+/// Convert MathML into MathML Core
+pub fn to_mathml_core(node: MathmlNode) -> MathmlNode {
+    convert(node, None)
+}
+
+/// The attributes that every MathML Core element has
+const GLOBAL_ATTRIBUTES: &[&str] = &[
+    "class",
+    "dir",
+    "displaystyle",
+    "id",
+    "mathbackground",
+    "mathcolor",
+    "mathsize",
+    "scriptlevel",
+    "style",
+    "tabindex",
+];
+
+/// The attributes of specific MathML Core elements
+fn element_attributes(typ: MathNodeType) -> &'static [&'static str] {
+    match typ {
+        MathNodeType::Math => &["display", "xmlns"],
+        MathNodeType::MFrac => &["linethickness"],
+        MathNodeType::Mo => &[
+            "form",
+            "fence",
+            "separator",
+            "lspace",
+            "rspace",
+            "stretchy",
+            "symmetric",
+            "maxsize",
+            "minsize",
+            "largeop",
+            "movablelimits",
+        ],
+        MathNodeType::MOver => &["accent"],
+        MathNodeType::MUnder => &["accentunder"],
+        MathNodeType::MUnderOver => &["accent", "accentunder"],
+        MathNodeType::MSpace => &["width", "height", "depth"],
+        MathNodeType::MPadded => &["width", "height", "depth", "lspace", "voffset"],
+        MathNodeType::MTd => &["columnspan", "rowspan"],
+        MathNodeType::Mi => &["mathvariant"],
+        _ => &[],
+    }
+}
+
+fn is_token(typ: MathNodeType) -> bool {
+    matches!(
+        typ,
+        MathNodeType::Mi | MathNodeType::Mn | MathNodeType::Mo | MathNodeType::MText
+    )
+}
+
+fn add_style(node: &mut MathNode<MathmlNode>, style: &str) {
+    if style.is_empty() {
+        return;
+    }
+
+    let style = match node.get_attribute("style") {
+        Some(existing) => format!("{}; {}", existing.trim_end_matches(';'), style),
+        None => style.to_string(),
+    };
+    node.set_attribute("style", style);
+}
+
+/// Split a length like `+0.6em` into its number and unit
+fn parse_length(value: &str) -> Option<(f64, String)> {
+    let value = value.trim().trim_start_matches('+');
+    let end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != '-')
+        .unwrap_or(value.len());
+    let num = value[..end].parse().ok()?;
+    let unit = &value[end..];
+    matches!(
+        unit,
+        "em" | "ex" | "pt" | "px" | "mm" | "cm" | "in" | "pc" | ""
+    )
+    .then(|| (num, unit.to_string()))
+}
+
+fn length(num: f64, unit: &str) -> String {
+    format!("{}{}", (num * 10000.0).round() / 10000.0, unit)
+}
+
+fn convert(node: MathmlNode, variant: Option<&str>) -> MathmlNode {
+    match node {
+        MathmlNode::Math(node) => convert_math(node, variant),
+        MathmlNode::Space(space) => convert_space(&space).into(),
+        MathmlNode::DocumentFragment(mut fragment) => {
+            fragment.children = core::mem::take(&mut fragment.children)
+                .into_iter()
+                .map(|child| convert(child, variant))
+                .collect();
+            MathmlNode::DocumentFragment(fragment)
+        }
+        node => node,
+    }
+}
+
+/// Spaces are written as `mspace`, since the negative space characters don't exist in Core
+fn convert_space(space: &SpaceNode) -> MathNode<MathmlNode> {
+    let mut node = MathNode::new_empty(MathNodeType::MSpace);
+    if space.width.0 >= 0.0 {
+        node.set_attribute("width", make_em(space.width.0));
+    } else {
+        add_style(
+            &mut node,
+            &format!("margin-inline-start: {}", make_em(space.width.0)),
+        );
+    }
+    node
+}
+
+fn convert_math(mut node: MathNode<MathmlNode>, variant: Option<&str>) -> MathmlNode {
+    match node.typ {
+        MathNodeType::Semantics => {
+            return match node.children.into_iter().next() {
+                Some(child) => convert(child, variant),
+                None => EmptyMathNode::new().into(),
+            };
+        }
+        MathNodeType::Annotation => return EmptyMathNode::new().into(),
+        MathNodeType::MGlyph => {
+            // Images can't be in MathML, so there is only the description of it
+            let alt = node.get_attribute("alt").unwrap_or_default().to_string();
+            return MathNode::new(
+                MathNodeType::MText,
+                vec![MathmlNode::from(TextNode::new(alt))],
+                node.classes,
+            )
+            .into();
+        }
+        _ => {}
+    }
+
+    let own_variant = node.remove_attribute("mathvariant");
+    let variant = own_variant.as_deref().or(variant);
+
+    if is_token(node.typ) {
+        convert_token(&mut node, variant);
+    } else {
+        node.children = core::mem::take(&mut node.children)
+            .into_iter()
+            .map(|child| convert(child, variant))
+            .collect();
+    }
+
+    match node.typ {
+        MathNodeType::MStyle => node.typ = MathNodeType::MRow,
+        MathNodeType::MEnclose => {
+            let notation = node.remove_attribute("notation").unwrap_or_default();
+            node.typ = MathNodeType::MRow;
+            add_style(&mut node, &enclose_style(&notation));
+        }
+        MathNodeType::MPadded => convert_padded(&mut node),
+        MathNodeType::MLabeledTr => {
+            // The label goes in the first cell, but Core has no labels, so it is moved after
+            // the row
+            node.typ = MathNodeType::MTr;
+            if !node.children.is_empty() {
+                let label = node.children.remove(0);
+                node.children.push(label);
+            }
+        }
+        _ => {}
+    }
+
+    remove_non_core_attributes(&mut node);
+
+    node.into()
+}
+
+fn convert_token(node: &mut MathNode<MathmlNode>, variant: Option<&str>) {
+    let text = node.to_text();
+    match variant {
+        // `mi`s with several characters are already upright
+        Some("normal") if node.typ == MathNodeType::Mi && text.chars().count() == 1 => {
+            node.set_attribute("mathvariant", "normal");
+        }
+        Some(variant) => {
+            if let Some(variant) = MathVariant::from_mathvariant(variant) {
+                let text = text.chars().map(|c| variant.apply(c)).collect::<String>();
+                node.children = vec![TextNode::new(text).into()];
+            }
+        }
+        None => {}
+    }
+}
+
+/// The CSS that draws the notations of an `menclose`
+fn enclose_style(notation: &str) -> String {
+    const RULE: &str = "0.04em solid";
+    const PADDING: &str = "0.3em";
+    const STRIKE: &str = "transparent calc(50% - 0.03em), currentColor calc(50% - 0.03em), \
+        currentColor calc(50% + 0.03em), transparent calc(50% + 0.03em)";
+
+    let mut style = Vec::new();
+    let mut strikes = Vec::new();
+    let mut has_border = false;
+    for notation in notation.split_whitespace() {
+        let sides: &[&str] = match notation {
+            "box" | "roundedbox" | "circle" => &["top", "bottom", "left", "right"],
+            "top" => &["top"],
+            "bottom" | "phasorangle" => &["bottom"],
+            "left" => &["left"],
+            "right" => &["right"],
+            "actuarial" => &["top", "right"],
+            "madruwb" => &["bottom", "right"],
+            "longdiv" => &["top", "left"],
+            _ => &[],
+        };
+        for side in sides {
+            style.push(format!("border-{}: {}", side, RULE));
+            has_border = true;
+        }
+
+        match notation {
+            "roundedbox" => style.push("border-radius: 0.3em".to_string()),
+            "circle" => style.push("border-radius: 50%".to_string()),
+            // The middle line of a gradient to a corner goes through the other two corners
+            "updiagonalstrike" => strikes.push(format!("linear-gradient(to top left, {})", STRIKE)),
+            "downdiagonalstrike" => {
+                strikes.push(format!("linear-gradient(to top right, {})", STRIKE))
+            }
+            "horizontalstrike" => strikes.push(format!("linear-gradient({})", STRIKE)),
+            "verticalstrike" => strikes.push(format!("linear-gradient(to right, {})", STRIKE)),
+            _ => {}
+        }
+    }
+
+    if has_border {
+        style.push(format!("padding: {}", PADDING));
+    }
+    if !strikes.is_empty() {
+        style.push(format!("background-image: {}", strikes.join(", ")));
+    }
+
+    style.join("; ")
+}
+
+/// Turn the relative sizes of an `mpadded`, which MathML Core doesn't have, into padding
+fn convert_padded(node: &mut MathNode<MathmlNode>) {
+    let lspace = node.get_attribute("lspace").and_then(parse_length);
+    let voffset = node.get_attribute("voffset").and_then(parse_length);
+
+    let width = node.get_attribute("width").filter(|w| w.starts_with('+'));
+    if let Some(width) = width.map(parse_length) {
+        if let Some((width, unit)) = width {
+            let start = lspace
+                .as_ref()
+                .filter(|(_, u)| *u == unit)
+                .map_or(0.0, |(l, _)| *l);
+            let mut style = format!("padding-inline-end: {}", length(width - start, &unit));
+            if start != 0.0 {
+                style = format!("padding-inline-start: {}; {}", length(start, &unit), style);
+                node.remove_attribute("lspace");
+            }
+            add_style(node, &style);
+        }
+        node.remove_attribute("width");
+    }
+
+    let height = node.get_attribute("height").filter(|h| h.starts_with('+'));
+    if let Some(height) = height.map(parse_length) {
+        if let Some((height, unit)) = height {
+            // The content is raised by the offset, which leaves the rest of the extra height
+            // above it
+            let below = voffset
+                .as_ref()
+                .filter(|(_, u)| *u == unit)
+                .map_or(0.0, |(v, _)| *v);
+            let mut style = format!("padding-block-start: {}", length(height - below, &unit));
+            if below != 0.0 {
+                style = format!("{}; padding-block-end: {}", style, length(below, &unit));
+                node.remove_attribute("voffset");
+            }
+            add_style(node, &style);
+        }
+        node.remove_attribute("height");
+    }
+
+    // Sizes relative to the content, like the `-1width` of `\mathllap`, can't be written
+    for attribute in ["width", "height", "depth", "lspace", "voffset"] {
+        let relative = node.get_attribute(attribute).is_some_and(|value| {
+            value.starts_with('+')
+                || value.ends_with("width")
+                || value.ends_with("height")
+                || value.ends_with("depth")
+        });
+        if relative {
+            node.remove_attribute(attribute);
+        }
+    }
+}
+
+fn remove_non_core_attributes(node: &mut MathNode<MathmlNode>) {
+    let allowed = element_attributes(node.typ);
+    node.retain_attributes(|key| {
+        GLOBAL_ATTRIBUTES.contains(&key) || allowed.contains(&key) || key.starts_with("data-")
+    });
+}
@@ -0,0 +1,1159 @@
+//! Reading Presentation MathML into a parse tree, so that MathML from other programs can be laid
+//! out like LaTeX input.
+//!
+//! [`parse_mathml`] maps each MathML element to the node that the equivalent LaTeX would parse
+//! to, so `<mfrac>` becomes the [`ParseNode::GenFrac`] of `\frac` and a `<mi mathvariant>` becomes
+//! the [`ParseNode::Font`] of `\mathbb` and its relatives:
+//! ```
+//! # use aliter::{mathml_reader::parse_mathml, parse_node::EqNoLoc, parse_tree, parser::ParserConfig};
+//! let tree = parse_mathml("<math><mfrac><mi>a</mi><mn>2</mn></mfrac></math>").unwrap();
+//! let expected = parse_tree(r"\frac{a}{2}", ParserConfig::default()).unwrap();
+//! assert!(tree.eq_no_loc(&expected));
+//! ```
+//!
+//! Namespace prefixes, like the `mml:` of Word's exports, are ignored. Elements that only carry
+//! extra information, like `<semantics>` and `<maction>`, are read through to their content.
+//!
+//! Elements can only be nested [`DEFAULT_MAX_DEPTH`] deep, so that the tree fits on the stack
+//! when it is read and rendered. None of the other limits of a
+//! [`ParserConfig`](crate::parser::ParserConfig) apply, as there are no macros to expand and the
+//! size of the tree is that of the input.
+
+use alloc::borrow::Cow;
+
+use crate::prelude::*;
+use crate::{
+    array::AlignSpec,
+    expander::Mode,
+    mathml_entities::named_entity,
+    parse_node::{
+        AccentNode, AccentUnderNode, ArrayNode, Color, ColorNode, FontNode, GenFracNode,
+        HorizBraceNode, KernNode, LeftRightNode, MClassNode, MiddleNode, NodeInfo, OpNode,
+        OperatorNameNode, OrdGroupNode, OverlineNode, ParseNode, PhantomNode, SqrtNode,
+        StylingNode, SupSubNode, TextNode, TextOrdNode, UnderlineNode,
+    },
+    parser::DEFAULT_MAX_DEPTH,
+    symbols::{Atom, Group, SYMBOLS},
+    unit::Measurement,
+    util::{parse_rgb, Style, StyleAuto},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathmlError {
+    /// The input is not well-formed XML
+    /// (Pos)
+    InvalidXml(usize),
+    /// An entity that isn't a character reference or one of the named entities of MathML
+    /// (name)
+    UnknownEntity(String),
+    /// The closing tag doesn't match the element it closes
+    /// (opening name, closing name)
+    MismatchedTag(String, String),
+    /// An element that has no equivalent parse node
+    /// (name)
+    UnsupportedElement(String),
+    /// Text directly inside an element that only takes other elements
+    /// (element name, text)
+    UnexpectedText(String, String),
+    /// (element name, expected number of children, found number of children)
+    WrongChildCount(String, usize, usize),
+    /// (element name, attribute name, value)
+    InvalidAttribute(String, String, String),
+    /// Elements are nested deeper than [`DEFAULT_MAX_DEPTH`]
+    TooDeeplyNested,
+}
+
+/// Parse a MathML document, or a fragment of one, into a parse tree.
+/// The root is normally a `<math>` element, but any single element is accepted.
+pub fn parse_mathml(source: &str) -> Result<Vec<ParseNode>, MathmlError> {
+    let root = XmlParser {
+        src: source,
+        pos: 0,
+        depth: 0,
+    }
+    .parse_document()?;
+    if root.name == "math" {
+        read_row(&root)
+    } else {
+        read_element(&root)
+    }
+}
+
+// =============================================================================
+// XML
+// =============================================================================
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Content>,
+}
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.trim())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Content::Element(el) => Some(el),
+            Content::Text(_) => None,
+        })
+    }
+
+    /// The text content of a token element, with runs of whitespace collapsed like MathML does
+    fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child {
+                Content::Text(t) => text.push_str(t),
+                // Only `<mglyph>` and `<malignmark>` are allowed here, and neither has text
+                Content::Element(_) => {}
+            }
+        }
+
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn invalid_attr(&self, attr: &str, value: &str) -> MathmlError {
+        MathmlError::InvalidAttribute(self.name.clone(), attr.to_string(), value.to_string())
+    }
+}
+
+#[derive(Debug)]
+enum Content {
+    Element(Element),
+    Text(String),
+}
+
+/// A small XML reader, which supports what appears in MathML: elements, attributes, text,
+/// comments, CDATA sections, and character references.
+/// Processing instructions and the doctype are skipped.
+struct XmlParser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// Depth of the elements being parsed
+    depth: u32,
+}
+impl<'a> XmlParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn error(&self) -> MathmlError {
+        MathmlError::InvalidXml(self.pos)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.rest().starts_with(text) {
+            self.pos += text.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), MathmlError> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Move past the next `end`, returning the text before it
+    fn take_until(&mut self, end: &str) -> Result<&'a str, MathmlError> {
+        let len = self.rest().find(end).ok_or_else(|| self.error())?;
+        let text = &self.rest()[..len];
+        self.pos += len + end.len();
+        Ok(text)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip the declaration, comments, processing instructions and doctype around the root
+    fn skip_misc(&mut self) -> Result<(), MathmlError> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<?") {
+                self.take_until("?>")?;
+            } else if self.eat("<!--") {
+                self.take_until("-->")?;
+            } else if self.eat("<!DOCTYPE") {
+                self.take_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_document(mut self) -> Result<Element, MathmlError> {
+        self.skip_misc()?;
+        self.expect("<")?;
+        let root = self.parse_element()?;
+        self.skip_misc()?;
+        if !self.rest().is_empty() {
+            return Err(self.error());
+        }
+
+        Ok(root)
+    }
+
+    fn parse_name(&mut self) -> Result<String, MathmlError> {
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error());
+        }
+
+        let name = &self.rest()[..len];
+        self.pos += len;
+        // Ignore the namespace prefix
+        Ok(name.rsplit(':').next().unwrap_or(name).to_string())
+    }
+
+    /// Parse an element, after its `<`
+    fn parse_element(&mut self) -> Result<Element, MathmlError> {
+        // Reading, rendering and dropping the elements all recurse as deep as they are nested
+        if self.depth >= DEFAULT_MAX_DEPTH {
+            return Err(MathmlError::TooDeeplyNested);
+        }
+
+        self.depth += 1;
+        let element = self.parse_element_inner();
+        self.depth -= 1;
+        element
+    }
+
+    fn parse_element_inner(&mut self) -> Result<Element, MathmlError> {
+        let name = self.parse_name()?;
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok(Element {
+                    name,
+                    attributes,
+                    children: Vec::new(),
+                });
+            } else if self.eat(">") {
+                break;
+            }
+
+            let attr = self.parse_name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = if self.eat("\"") {
+                "\""
+            } else {
+                self.expect("'")?;
+                "'"
+            };
+            let value = decode_entities(self.take_until(quote)?)?;
+            attributes.push((attr, value));
+        }
+
+        let mut children = Vec::new();
+        loop {
+            if self.eat("</") {
+                let end = self.parse_name()?;
+                self.skip_whitespace();
+                self.expect(">")?;
+                if end != name {
+                    return Err(MathmlError::MismatchedTag(name, end));
+                }
+
+                return Ok(Element {
+                    name,
+                    attributes,
+                    children,
+                });
+            } else if self.eat("<!--") {
+                self.take_until("-->")?;
+            } else if self.eat("<![CDATA[") {
+                let text = self.take_until("]]>")?;
+                children.push(Content::Text(text.to_string()));
+            } else if self.eat("<?") {
+                self.take_until("?>")?;
+            } else if self.eat("<") {
+                children.push(Content::Element(self.parse_element()?));
+            } else {
+                let len = self.rest().find('<').ok_or_else(|| self.error())?;
+                let text = decode_entities(&self.rest()[..len])?;
+                self.pos += len;
+                children.push(Content::Text(text));
+            }
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> Result<String, MathmlError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(';')
+            .ok_or_else(|| MathmlError::UnknownEntity(rest.to_string()))?;
+        let name = &rest[..end];
+        rest = &rest[end + 1..];
+
+        let code = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+            u32::from_str_radix(hex, 16).ok()
+        } else if let Some(dec) = name.strip_prefix('#') {
+            dec.parse().ok()
+        } else {
+            let text = named_entity(name)
+                .ok_or_else(|| MathmlError::UnknownEntity(name.to_string()))?;
+            out.push_str(text);
+            continue;
+        };
+        let ch = code
+            .and_then(char::from_u32)
+            .ok_or_else(|| MathmlError::UnknownEntity(name.to_string()))?;
+        out.push(ch);
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+// =============================================================================
+// MathML
+// =============================================================================
+
+fn math_info() -> NodeInfo {
+    NodeInfo::new_mode(Mode::Math)
+}
+
+fn ord_group(body: Vec<ParseNode>) -> ParseNode {
+    ParseNode::OrdGroup(OrdGroupNode {
+        body,
+        semi_simple: None,
+        info: math_info(),
+    })
+}
+
+/// A group of a single node, without wrapping a group again
+fn as_ord_group(node: ParseNode) -> ParseNode {
+    match node {
+        ParseNode::OrdGroup(_) => node,
+        node => ord_group(vec![node]),
+    }
+}
+
+/// A single node for a row, like the argument of a LaTeX command, which is only a group if it
+/// was written in braces
+fn group(mut body: Vec<ParseNode>) -> ParseNode {
+    if body.len() == 1 {
+        body.pop().unwrap()
+    } else {
+        ord_group(body)
+    }
+}
+
+/// The node for a single character, like the parser makes for it
+fn symbol(mode: Mode, text: &str) -> ParseNode {
+    let info = NodeInfo::new_mode(mode);
+    match SYMBOLS.get(mode, text).map(|symbol| symbol.group) {
+        Some(Group::Atom(family)) => ParseNode::Atom(crate::parse_node::AtomNode {
+            family,
+            text: text.to_string(),
+            info,
+        }),
+        Some(Group::NonAtom(group)) => group.make_parse_node(info, text.to_string()),
+        None => ParseNode::TextOrd(TextOrdNode {
+            text: Cow::Owned(text.to_string()),
+            info: NodeInfo::new_mode(Mode::Text),
+        }),
+    }
+}
+
+fn symbols(mode: Mode, text: &str) -> Vec<ParseNode> {
+    let mut buf = [0; 4];
+    text.chars()
+        .map(|c| symbol(mode, c.encode_utf8(&mut buf)))
+        .collect()
+}
+
+/// The children of an element that takes a row of elements, like `<mrow>`
+fn read_row(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    let mut children = Vec::new();
+    for child in &el.children {
+        match child {
+            Content::Element(child) => children.push(child),
+            Content::Text(text) if text.trim().is_empty() => {}
+            Content::Text(text) => {
+                return Err(MathmlError::UnexpectedText(
+                    el.name.clone(),
+                    text.trim().to_string(),
+                ))
+            }
+        }
+    }
+
+    if let Some(left_right) = read_fenced_row(&children)? {
+        return Ok(vec![left_right]);
+    }
+
+    let mut row = Vec::new();
+    for child in children {
+        row.extend(read_element(child)?);
+    }
+
+    Ok(row)
+}
+
+/// The children of an element that takes a fixed number of arguments, like `<mfrac>`
+fn read_args<const N: usize>(el: &Element) -> Result<[&Element; N], MathmlError> {
+    let children = el.elements().collect::<Vec<_>>();
+    let found = children.len();
+    children
+        .try_into()
+        .map_err(|_| MathmlError::WrongChildCount(el.name.clone(), N, found))
+}
+
+fn read_group(el: &Element) -> Result<ParseNode, MathmlError> {
+    read_element(el).map(group)
+}
+
+fn read_element(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    let body = match el.name.as_str() {
+        "math" | "mrow" | "mpadded" => read_row(el)?,
+        "semantics" | "maction" => match el.elements().next() {
+            Some(child) => read_element(child)?,
+            None => Vec::new(),
+        },
+        "mstyle" => read_style(el)?,
+        "mi" => read_identifier(el)?,
+        "mn" => apply_variant(el, symbols(Mode::Math, &el.text()))?,
+        "mo" => read_operator(el)?,
+        "mtext" | "ms" => read_text(el)?,
+        "mspace" => read_space(el)?,
+        "mfrac" => {
+            let [numer, denom] = read_args(el)?;
+            let has_bar_line = match el.attr("linethickness") {
+                Some(thickness) => !thickness
+                    .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%')
+                    .parse::<f64>()
+                    .map(|n| n == 0.0)
+                    .unwrap_or(false),
+                None => true,
+            };
+            vec![ParseNode::GenFrac(GenFracNode {
+                continued: false,
+                numer: Box::new(ord_group(read_element(numer)?)),
+                denom: Box::new(ord_group(read_element(denom)?)),
+                has_bar_line,
+                left_delim: None,
+                right_delim: None,
+                size: StyleAuto::Auto,
+                bar_size: None,
+                info: math_info(),
+            })]
+        }
+        "msqrt" => vec![ParseNode::Sqrt(SqrtNode {
+            body: Box::new(ord_group(read_row(el)?)),
+            index: None,
+            info: math_info(),
+        })],
+        "mroot" => {
+            let [body, index] = read_args(el)?;
+            vec![ParseNode::Sqrt(SqrtNode {
+                body: Box::new(ord_group(read_element(body)?)),
+                index: Some(Box::new(ord_group(read_element(index)?))),
+                info: math_info(),
+            })]
+        }
+        "msub" => {
+            let [base, sub] = read_args(el)?;
+            vec![sup_sub(read_group(base)?, None, Some(read_group(sub)?))]
+        }
+        "msup" => {
+            let [base, sup] = read_args(el)?;
+            vec![sup_sub(read_group(base)?, Some(read_group(sup)?), None)]
+        }
+        "msubsup" => {
+            let [base, sub, sup] = read_args(el)?;
+            vec![sup_sub(
+                read_group(base)?,
+                Some(read_group(sup)?),
+                Some(read_group(sub)?),
+            )]
+        }
+        "munder" => {
+            let [base, under] = read_args(el)?;
+            vec![read_under_over(base, Some(under), None)?]
+        }
+        "mover" => {
+            let [base, over] = read_args(el)?;
+            vec![read_under_over(base, None, Some(over))?]
+        }
+        "munderover" => {
+            let [base, under, over] = read_args(el)?;
+            vec![read_under_over(base, Some(under), Some(over))?]
+        }
+        "mtable" => vec![read_table(el)?],
+        "mfenced" => vec![read_mfenced(el)?],
+        "mphantom" => vec![ParseNode::Phantom(PhantomNode {
+            body: read_row(el)?,
+            info: math_info(),
+        })],
+        _ => return Err(MathmlError::UnsupportedElement(el.name.clone())),
+    };
+
+    match el.attr("mathcolor") {
+        Some(color) if el.name != "mstyle" => Ok(vec![with_color(color, body)]),
+        _ => Ok(body),
+    }
+}
+
+fn with_color(color: &str, body: Vec<ParseNode>) -> ParseNode {
+    let color = match color.strip_prefix('#').map(parse_rgb) {
+        Some(Ok(rgb)) if color.len() == 7 => Color::RGB(rgb),
+        _ => Color::Named(Cow::Owned(color.to_string())),
+    };
+
+    ParseNode::Color(ColorNode {
+        color,
+        info: math_info(),
+        body,
+    })
+}
+
+fn read_style(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    let mut body = apply_variant(el, read_row(el)?)?;
+
+    let style = match (el.attr("displaystyle"), el.attr("scriptlevel")) {
+        (Some("true"), _) => Some(Style::Display),
+        (Some("false"), _) | (None, Some("0")) => Some(Style::Text),
+        (None, Some("1")) => Some(Style::Script),
+        (None, Some("2")) => Some(Style::ScriptScript),
+        (None, None) => None,
+        (Some(value), _) => return Err(el.invalid_attr("displaystyle", value)),
+        (None, Some(value)) => return Err(el.invalid_attr("scriptlevel", value)),
+    };
+    if let Some(style) = style {
+        body = vec![ParseNode::Styling(StylingNode {
+            style,
+            body,
+            info: math_info(),
+        })];
+    }
+
+    if let Some(color) = el.attr("mathcolor") {
+        body = vec![with_color(color, body)];
+    }
+
+    Ok(body)
+}
+
+/// The font command for a `mathvariant`
+fn variant_font(el: &Element, variant: &str) -> Result<Option<&'static str>, MathmlError> {
+    Ok(Some(match variant {
+        "normal" => "mathrm",
+        "bold" => "mathbf",
+        "italic" => "mathit",
+        "bold-italic" => "boldsymbol",
+        "double-struck" => "mathbb",
+        "script" | "bold-script" => "mathcal",
+        "fraktur" | "bold-fraktur" => "mathfrak",
+        "sans-serif" | "bold-sans-serif" | "sans-serif-italic" | "sans-serif-bold-italic" => {
+            "mathsf"
+        }
+        "monospace" => "mathtt",
+        _ => return Err(el.invalid_attr("mathvariant", variant)),
+    }))
+}
+
+fn apply_variant(el: &Element, body: Vec<ParseNode>) -> Result<Vec<ParseNode>, MathmlError> {
+    let font = match el.attr("mathvariant") {
+        Some(variant) => variant_font(el, variant)?,
+        None => None,
+    };
+
+    Ok(match font {
+        Some(font) if !body.is_empty() => vec![ParseNode::Font(FontNode {
+            font: Cow::Borrowed(font),
+            body: Box::new(group(body)),
+            info: math_info(),
+        })],
+        _ => body,
+    })
+}
+
+fn read_identifier(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    let text = el.text();
+    let body = symbols(Mode::Math, &text);
+    if text.chars().count() > 1 && el.attr("mathvariant").is_none() {
+        // Identifiers of several letters are upright, and are usually function names
+        return Ok(vec![ParseNode::OperatorName(OperatorNameNode {
+            body,
+            always_handle_sup_sub: false,
+            limits: false,
+            parent_is_sup_sub: false,
+            info: math_info(),
+        })]);
+    }
+
+    apply_variant(el, body)
+}
+
+/// The big operators that are written as a single character, and the command for each
+const BIG_OPS: &[(&str, &str)] = &[
+    ("\u{220F}", "\\prod"),
+    ("\u{2210}", "\\coprod"),
+    ("\u{2211}", "\\sum"),
+    ("\u{22c0}", "\\bigwedge"),
+    ("\u{22c1}", "\\bigvee"),
+    ("\u{22c2}", "\\bigcap"),
+    ("\u{22c3}", "\\bigcup"),
+    ("\u{2a00}", "\\bigodot"),
+    ("\u{2a01}", "\\bigoplus"),
+    ("\u{2a02}", "\\bigotimes"),
+    ("\u{2a04}", "\\biguplus"),
+    ("\u{2a06}", "\\bigsqcup"),
+];
+
+const INTEGRALS: &[(&str, &str)] = &[
+    ("\u{222b}", "\\int"),
+    ("\u{222c}", "\\iint"),
+    ("\u{222d}", "\\iiint"),
+    ("\u{222e}", "\\oint"),
+    ("\u{222f}", "\\oiint"),
+    ("\u{2230}", "\\oiiint"),
+];
+
+/// The functions that take their subscript underneath in display mode
+const LIMIT_FUNCTIONS: &[&str] = &[
+    "det", "gcd", "inf", "lim", "liminf", "limsup", "max", "min", "Pr", "sup",
+];
+
+fn op(name: String, limits: bool, symbol: bool) -> ParseNode {
+    ParseNode::Op(OpNode {
+        limits,
+        always_handle_sup_sub: None,
+        suppress_base_shift: None,
+        parent_is_sup_sub: Some(false),
+        symbol,
+        name: Some(Cow::Owned(name)),
+        body: None,
+        info: math_info(),
+    })
+}
+
+fn read_operator(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    let text = el.text();
+    let find = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(ch, _)| *ch == text)
+            .map(|(_, name)| name.to_string())
+    };
+
+    let node =
+        if text.is_empty() || ('\u{2061}'..='\u{2064}').contains(&text.chars().next().unwrap()) {
+            // Function application and invisible times, which only matter to screen readers
+            return Ok(Vec::new());
+        } else if let Some(name) = find(BIG_OPS) {
+            op(name, true, true)
+        } else if let Some(name) = find(INTEGRALS) {
+            op(name, false, true)
+        } else if LIMIT_FUNCTIONS.contains(&text.as_str()) {
+            op(format!("\\{}", text), true, false)
+        } else if text.chars().count() > 1 && text.chars().all(char::is_alphabetic) {
+            ParseNode::OperatorName(OperatorNameNode {
+                body: symbols(Mode::Math, &text),
+                always_handle_sup_sub: false,
+                limits: false,
+                parent_is_sup_sub: false,
+                info: math_info(),
+            })
+        } else {
+            return apply_variant(el, symbols(Mode::Math, &text));
+        };
+
+    Ok(vec![node])
+}
+
+fn read_text(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    let mut content = el.text();
+    if el.name == "ms" {
+        let left = el.attr("lquote").unwrap_or("\"");
+        let right = el.attr("rquote").unwrap_or("\"");
+        content = format!("{}{}{}", left, content, right);
+    }
+
+    let text = |font: &'static str, body: Vec<ParseNode>| {
+        ParseNode::Text(TextNode {
+            body,
+            font: Some(Cow::Borrowed(font)),
+            info: math_info(),
+        })
+    };
+    let body = symbols(Mode::Text, &content);
+    let font = match el.attr("mathvariant") {
+        None | Some("normal") => "\\text",
+        Some("bold") => "\\textbf",
+        Some("italic") => "\\textit",
+        // There is no single command for bold italic text
+        Some("bold-italic") => return Ok(vec![text("\\textit", vec![text("\\textbf", body)])]),
+        Some("sans-serif") => "\\textsf",
+        Some("monospace") => "\\texttt",
+        Some(variant) => return Err(el.invalid_attr("mathvariant", variant)),
+    };
+
+    Ok(vec![text(font, body)])
+}
+
+/// The widths of MathML's named spaces, in eighteenths of an em
+const NAMED_SPACES: &[(&str, f64)] = &[
+    ("veryverythinmathspace", 1.0),
+    ("verythinmathspace", 2.0),
+    ("thinmathspace", 3.0),
+    ("mediummathspace", 4.0),
+    ("thickmathspace", 5.0),
+    ("verythickmathspace", 6.0),
+    ("veryverythickmathspace", 7.0),
+];
+
+fn read_length(el: &Element, attr: &str, value: &str) -> Result<Measurement, MathmlError> {
+    let (negative, name) = match value.strip_prefix("negative") {
+        Some(name) => (true, name),
+        None => (false, value),
+    };
+    if let Some((_, n)) = NAMED_SPACES.iter().find(|(space, _)| *space == name) {
+        let n = if negative { -n } else { *n };
+        return Ok(Measurement::from_unit(n / 18.0, "em").unwrap());
+    }
+
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let unit = if unit.is_empty() && num.trim() == "0" {
+        "em"
+    } else {
+        unit
+    };
+    num.trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|num| Measurement::from_unit(num, unit))
+        .ok_or_else(|| el.invalid_attr(attr, value))
+}
+
+fn read_space(el: &Element) -> Result<Vec<ParseNode>, MathmlError> {
+    match el.attr("width") {
+        Some(width) => Ok(vec![ParseNode::Kern(KernNode {
+            dimension: read_length(el, "width", width)?,
+            info: math_info(),
+        })]),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn sup_sub(base: ParseNode, sup: Option<ParseNode>, sub: Option<ParseNode>) -> ParseNode {
+    ParseNode::SupSub(SupSubNode {
+        base: Some(Box::new(base)),
+        sup: sup.map(Box::new),
+        sub: sub.map(Box::new),
+        info: math_info(),
+    })
+}
+
+/// The accents written above the base with `<mover>`, and the command for a base of a single
+/// character and for a wider base
+const OVER_ACCENTS: &[(&[&str], &str, &str)] = &[
+    (&["^", "\u{2c6}", "\u{302}"], "\\hat", "\\widehat"),
+    (&["~", "\u{2dc}", "\u{303}"], "\\tilde", "\\widetilde"),
+    (&["\u{2c7}", "\u{30c}"], "\\check", "\\widecheck"),
+    (
+        &["\u{af}", "\u{203e}", "\u{304}", "\u{305}"],
+        "\\bar",
+        "\\overline",
+    ),
+    (&["\u{2192}", "\u{20d7}"], "\\vec", "\\overrightarrow"),
+    (&["\u{2190}"], "\\overleftarrow", "\\overleftarrow"),
+    (
+        &["\u{2194}"],
+        "\\overleftrightarrow",
+        "\\overleftrightarrow",
+    ),
+    (&["\u{2d9}", "\u{307}"], "\\dot", "\\dot"),
+    (&["\u{a8}", "\u{308}"], "\\ddot", "\\ddot"),
+    (&["\u{2d8}", "\u{306}"], "\\breve", "\\breve"),
+    (&["\u{b4}", "\u{301}"], "\\acute", "\\acute"),
+    (&["`", "\u{300}"], "\\grave", "\\grave"),
+    (&["\u{2da}", "\u{30a}"], "\\mathring", "\\mathring"),
+    (&["\u{23de}"], "\\overbrace", "\\overbrace"),
+];
+
+/// The accents written below the base with `<munder>`
+const UNDER_ACCENTS: &[(&[&str], &str)] = &[
+    (&["_", "\u{af}", "\u{203e}", "\u{332}"], "\\underline"),
+    (&["\u{2190}"], "\\underleftarrow"),
+    (&["\u{2192}"], "\\underrightarrow"),
+    (&["\u{2194}"], "\\underleftrightarrow"),
+    (&["~", "\u{2dc}", "\u{330}"], "\\utilde"),
+    (&["\u{23df}"], "\\underbrace"),
+];
+
+/// The text of a script that is a lone `<mo>`, which could be an accent
+fn accent_text(el: &Element) -> Option<String> {
+    (el.name == "mo").then(|| el.text())
+}
+
+fn is_single_char(node: &ParseNode) -> bool {
+    matches!(
+        node,
+        ParseNode::MathOrd(_) | ParseNode::TextOrd(_) | ParseNode::Atom(_)
+    )
+}
+
+fn read_over_accent(label: &str, base: ParseNode) -> ParseNode {
+    match label {
+        "\\overline" => ParseNode::Overline(OverlineNode {
+            body: Box::new(as_ord_group(base)),
+            info: math_info(),
+        }),
+        "\\overbrace" => ParseNode::HorizBrace(HorizBraceNode {
+            label: label.to_string(),
+            is_over: true,
+            base: Box::new(as_ord_group(base)),
+            thickness: None,
+            height: None,
+            info: math_info(),
+        }),
+        _ => {
+            let is_stretchy = label.starts_with("\\wide") || label.starts_with("\\over");
+            let is_shifty = !is_stretchy || label.starts_with("\\wide");
+            ParseNode::Accent(AccentNode {
+                label: Cow::Owned(label.to_string()),
+                is_stretchy: Some(is_stretchy),
+                is_shifty: Some(is_shifty),
+                base: Box::new(base),
+                info: math_info(),
+            })
+        }
+    }
+}
+
+fn read_under_accent(label: &str, base: ParseNode) -> ParseNode {
+    match label {
+        "\\underline" => ParseNode::Underline(UnderlineNode {
+            body: Box::new(as_ord_group(base)),
+            info: math_info(),
+        }),
+        "\\underbrace" => ParseNode::HorizBrace(HorizBraceNode {
+            label: label.to_string(),
+            is_over: false,
+            base: Box::new(as_ord_group(base)),
+            thickness: None,
+            height: None,
+            info: math_info(),
+        }),
+        _ => ParseNode::AccentUnder(AccentUnderNode {
+            label: label.to_string(),
+            is_stretchy: None,
+            is_shifty: None,
+            base: Box::new(base),
+            info: math_info(),
+        }),
+    }
+}
+
+fn read_under_over(
+    base: &Element,
+    under: Option<&Element>,
+    over: Option<&Element>,
+) -> Result<ParseNode, MathmlError> {
+    let mut base = read_group(base)?;
+
+    // Accents, like `\hat` and `\underbrace`, which only go on their own
+    if under.is_none() || over.is_none() {
+        if let Some(text) = over.and_then(accent_text) {
+            let accent = OVER_ACCENTS
+                .iter()
+                .find(|(chars, _, _)| chars.contains(&text.as_str()));
+            if let Some((_, narrow, wide)) = accent {
+                let label = if is_single_char(&base) { narrow } else { wide };
+                return Ok(read_over_accent(label, base));
+            }
+        }
+        if let Some(text) = under.and_then(accent_text) {
+            let accent = UNDER_ACCENTS
+                .iter()
+                .find(|(chars, _)| chars.contains(&text.as_str()));
+            if let Some((_, label)) = accent {
+                return Ok(read_under_accent(label, base));
+            }
+        }
+    }
+
+    let under = under.map(read_group).transpose()?;
+    let over = over.map(read_group).transpose()?;
+
+    // Operators take the scripts as their limits
+    match &mut base {
+        ParseNode::Op(OpNode { limits, .. })
+        | ParseNode::OperatorName(OperatorNameNode { limits, .. }) => {
+            *limits = true;
+            return Ok(sup_sub(base, over, under));
+        }
+        _ => {}
+    }
+
+    // Anything else is stacked like `\overset` and `\underset`
+    let m_class = match &base {
+        ParseNode::Atom(atom) if atom.family == Atom::Rel => "mrel",
+        ParseNode::Atom(atom) if atom.family == Atom::Bin => "mbin",
+        _ => "mord",
+    };
+    let base = ParseNode::Op(OpNode {
+        limits: true,
+        always_handle_sup_sub: Some(true),
+        suppress_base_shift: Some(true),
+        parent_is_sup_sub: Some(false),
+        symbol: false,
+        name: None,
+        body: Some(match base {
+            ParseNode::OrdGroup(group) => group.body,
+            base => vec![base],
+        }),
+        info: math_info(),
+    });
+
+    Ok(ParseNode::MClass(MClassNode {
+        m_class: m_class.to_string(),
+        body: vec![sup_sub(
+            base,
+            over.map(as_ord_group),
+            under.map(as_ord_group),
+        )],
+        is_character_box: false,
+        info: math_info(),
+    }))
+}
+
+fn read_table(el: &Element) -> Result<ParseNode, MathmlError> {
+    let mut body = Vec::new();
+    for row in el.elements() {
+        let cells = match row.name.as_str() {
+            "mtr" => row.elements().collect::<Vec<_>>(),
+            // The first cell is the equation label, which there is no place for
+            "mlabeledtr" => row.elements().skip(1).collect(),
+            _ => return Err(MathmlError::UnsupportedElement(row.name.clone())),
+        };
+
+        let mut out = Vec::new();
+        for cell in cells {
+            if cell.name != "mtd" {
+                return Err(MathmlError::UnsupportedElement(cell.name.clone()));
+            }
+
+            out.push(ParseNode::Styling(StylingNode {
+                style: Style::Text,
+                body: vec![ord_group(read_row(cell)?)],
+                info: math_info(),
+            }));
+        }
+        body.push(out);
+    }
+
+    let columns = body.iter().map(Vec::len).max().unwrap_or(0);
+    let aligns = el
+        .attr("columnalign")
+        .unwrap_or("center")
+        .split_whitespace()
+        .map(|align| match align {
+            "left" => Ok("l"),
+            "center" => Ok("c"),
+            "right" => Ok("r"),
+            _ => Err(el.invalid_attr("columnalign", align)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // The last alignment is repeated for the rest of the columns
+    let cols = (0..columns)
+        .map(|i| AlignSpec::Align {
+            align: Cow::Borrowed(aligns.get(i).or(aligns.last()).copied().unwrap_or("c")),
+            pre_gap: None,
+            post_gap: None,
+        })
+        .collect();
+
+    let rows = body.len();
+    Ok(ParseNode::Array(ArrayNode {
+        body,
+        col_separation_type: None,
+        h_skip_before_and_after: Some(false),
+        add_jot: None,
+        cols: Some(cols),
+        array_stretch: 1.0,
+        row_gaps: vec![None; rows.saturating_sub(1)],
+        h_lines_before_row: vec![Vec::new(); rows + 1],
+        tags: None,
+        leq_no: None,
+        is_cd: None,
+        info: math_info(),
+    }))
+}
+
+/// The delimiter that `\left` and `\right` store for a fence character, if it is one
+fn fence_delim(text: &str) -> Option<&'static str> {
+    Some(match text {
+        "" => ".",
+        "(" => "(",
+        ")" => ")",
+        "[" => "[",
+        "]" => "]",
+        "{" => "{",
+        "}" => "}",
+        "|" | "\u{2223}" => "|",
+        "\u{2016}" | "\u{2225}" => "||",
+        "\u{27e8}" | "\u{2329}" => "\u{27e8}",
+        "\u{27e9}" | "\u{232a}" => "\u{27e9}",
+        "\u{230a}" => "\u{230a}",
+        "\u{230b}" => "\u{230b}",
+        "\u{2308}" => "\u{2308}",
+        "\u{2309}" => "\u{2309}",
+        "/" => "/",
+        _ => return None,
+    })
+}
+
+fn is_opening(delim: &str) -> bool {
+    matches!(
+        delim,
+        "(" | "[" | "{" | "\u{27e8}" | "\u{230a}" | "\u{2308}"
+    )
+}
+
+fn is_closing(delim: &str) -> bool {
+    matches!(
+        delim,
+        ")" | "]" | "}" | "\u{27e9}" | "\u{230b}" | "\u{2309}"
+    )
+}
+
+/// The delimiter of a stretchy `<mo>`.
+/// Fences stretch unless they say otherwise, while other operators only stretch if they ask to.
+fn stretchy_delim(el: &Element) -> Option<&'static str> {
+    if el.name != "mo" {
+        return None;
+    }
+
+    let delim = fence_delim(&el.text()).filter(|delim| *delim != ".")?;
+    match el.attr("stretchy") {
+        Some("false") => None,
+        Some("true") => Some(delim),
+        _ if el.attr("fence") == Some("true") || is_opening(delim) || is_closing(delim) => {
+            Some(delim)
+        }
+        _ => Some(delim).filter(|delim| *delim == "|" || *delim == "||"),
+    }
+}
+
+/// Read a row that starts with an opening fence and ends with the matching closing fence, or
+/// that is only opened or closed, as `\left` and `\right`
+fn read_fenced_row(children: &[&Element]) -> Result<Option<ParseNode>, MathmlError> {
+    let [first, .., last] = children else {
+        return Ok(None);
+    };
+    let left = stretchy_delim(first).filter(|delim| !is_closing(delim));
+    let right = stretchy_delim(last).filter(|delim| !is_opening(delim));
+
+    let inner = &children[1..children.len() - 1];
+    let (left, right, body) = match (left, right) {
+        (Some(left), Some(right)) => (left, right, inner),
+        // A lone opening fence, like that of `{cases}`
+        (Some(left), None) if is_opening(left) => (left, ".", &children[1..]),
+        (None, Some(right)) if is_closing(right) => (".", right, &children[..children.len() - 1]),
+        _ => return Ok(None),
+    };
+
+    // The fences must enclose the whole row, so `(a)+(b)` is not one fenced group
+    let delims = body.iter().filter_map(|el| stretchy_delim(el));
+    let mut depth = 0i32;
+    for delim in delims {
+        if is_opening(delim) {
+            depth += 1;
+        } else if is_closing(delim) {
+            depth -= 1;
+            if depth < 0 {
+                return Ok(None);
+            }
+        } else if (delim == left || delim == right) && !(is_opening(left) && is_closing(right)) {
+            return Ok(None);
+        }
+    }
+    if depth != 0 {
+        return Ok(None);
+    }
+
+    let mut nodes = Vec::new();
+    for el in body {
+        // Bars that were asked to stretch inside the fences are `\middle`
+        let middle = stretchy_delim(el).filter(|delim| {
+            el.attr("stretchy") == Some("true") && !is_opening(delim) && !is_closing(delim)
+        });
+        match middle {
+            Some(delim) => nodes.push(ParseNode::Middle(MiddleNode {
+                delim: delim.to_string(),
+                info: math_info(),
+            })),
+            None => nodes.extend(read_element(el)?),
+        }
+    }
+
+    Ok(Some(ParseNode::LeftRight(LeftRightNode {
+        left: left.to_string(),
+        right: right.to_string(),
+        right_color: None,
+        body: nodes,
+        info: math_info(),
+    })))
+}
+
+fn read_mfenced(el: &Element) -> Result<ParseNode, MathmlError> {
+    let fence = |attr: &str, default: &'static str| {
+        let text = el.attr(attr).unwrap_or(default);
+        fence_delim(text).ok_or_else(|| el.invalid_attr(attr, text))
+    };
+    let left = fence("open", "(")?;
+    let right = fence("close", ")")?;
+    let separators = el
+        .attr("separators")
+        .unwrap_or(",")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+
+    let mut body = Vec::new();
+    for (i, child) in el.elements().enumerate() {
+        if i > 0 {
+            if let Some(sep) = separators.get(i - 1).or(separators.last()) {
+                body.push(symbol(Mode::Math, &sep.to_string()));
+            }
+        }
+        body.push(read_group(child)?);
+    }
+
+    Ok(ParseNode::LeftRight(LeftRightNode {
+        left: left.to_string(),
+        right: right.to_string(),
+        right_color: None,
+        body,
+        info: math_info(),
+    }))
+}
@@ -1,6 +1,8 @@
+use core::fmt;
+
 use crate::{
     dom_tree::DocumentFragment,
-    tree::{class_attr, Attributes, ClassList, VirtualNode},
+    tree::{write_class_attr, Attributes, ClassList, VirtualNode},
     unit::{make_em, Em},
     util,
 };
@@ -73,9 +75,9 @@ impl MathNodeType {
 #[derive(Debug, Clone)]
 pub struct MathDomNode {}
 impl VirtualNode for MathDomNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         // Bare MathDomNode has no tag context; emit an empty <mrow/>.
-        "<mrow></mrow>".to_string()
+        out.write_str("<mrow></mrow>")
     }
 }
 
@@ -118,8 +120,8 @@ impl EmptyMathNode {
     }
 }
 impl VirtualNode for EmptyMathNode {
-    fn to_markup(&self) -> String {
-        String::new()
+    fn write_markup(&self, _out: &mut dyn fmt::Write) -> fmt::Result {
+        Ok(())
     }
 }
 impl WithMathDomNode for EmptyMathNode {
@@ -140,14 +142,28 @@ pub enum MathmlNode {
     Space(SpaceNode),
     DocumentFragment(DocumentFragment<MathmlNode>),
 }
+impl MathmlNode {
+    /// Converts the node into a string, similar to innerText
+    pub fn to_text(&self) -> String {
+        match self {
+            MathmlNode::Empty(_) => String::new(),
+            MathmlNode::Math(node) => node.to_text(),
+            MathmlNode::Text(node) => node.to_text(),
+            MathmlNode::Space(node) => node.to_text(),
+            MathmlNode::DocumentFragment(node) => {
+                node.children.iter().map(MathmlNode::to_text).collect()
+            }
+        }
+    }
+}
 impl VirtualNode for MathmlNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         match self {
-            MathmlNode::Empty(node) => node.to_markup(),
-            MathmlNode::Math(node) => node.to_markup(),
-            MathmlNode::Text(node) => node.to_markup(),
-            MathmlNode::Space(node) => node.to_markup(),
-            MathmlNode::DocumentFragment(node) => node.to_markup(),
+            MathmlNode::Empty(node) => node.write_markup(out),
+            MathmlNode::Math(node) => node.write_markup(out),
+            MathmlNode::Text(node) => node.write_markup(out),
+            MathmlNode::Space(node) => node.write_markup(out),
+            MathmlNode::DocumentFragment(node) => node.write_markup(out),
         }
     }
 }
@@ -238,13 +254,27 @@ impl<T: WithMathDomNode> MathNode<T> {
         self.attributes.get(key).map(String::as_str)
     }
 
+    pub fn remove_attribute(&mut self, key: &str) -> Option<String> {
+        self.attributes.remove(key)
+    }
+
+    /// Keep only the attributes whose names the function returns `true` for
+    pub fn retain_attributes(&mut self, mut f: impl FnMut(&str) -> bool) {
+        self.attributes.retain(|key, _| f(key));
+    }
+
     pub fn with_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.set_attribute(key, value);
         self
     }
 
     // TODO: into_node
-    // TODO: to_text
+}
+impl MathNode<MathmlNode> {
+    /// Converts the math node into a string, similar to innerText
+    pub fn to_text(&self) -> String {
+        self.children.iter().map(MathmlNode::to_text).collect()
+    }
 }
 impl<T: WithMathDomNode> MathNode<T>
 where
@@ -261,37 +291,30 @@ where
     }
 }
 impl<T: WithMathDomNode> VirtualNode for MathNode<T> {
-    fn to_markup(&self) -> String {
-        let mut markup = format!("<{}", self.typ.as_str());
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        out.write_char('<')?;
+        out.write_str(self.typ.as_str())?;
 
         // Add the attributes
         for (key, value) in self.attributes.iter() {
-            markup.push(' ');
-            markup.push_str(key);
-            markup.push_str("=\"");
-
-            let escaped_value = util::escape(value);
-            markup.push_str(&escaped_value);
-            markup.push('"');
+            out.write_char(' ')?;
+            out.write_str(key)?;
+            out.write_str("=\"")?;
+            util::write_escaped(out, value)?;
+            out.write_char('"')?;
         }
 
-        if let Some(classes) = class_attr(&self.classes) {
-            markup.push_str(" class=\"");
-            markup.push_str(&classes);
-            markup.push('"');
-        }
+        write_class_attr(out, &self.classes)?;
 
-        markup.push('>');
+        out.write_char('>')?;
 
         for child in self.children.iter() {
-            markup.push_str(&child.to_markup());
+            child.write_markup(out)?;
         }
 
-        markup.push_str("</");
-        markup.push_str(self.typ.as_str());
-        markup.push('>');
-
-        markup
+        out.write_str("</")?;
+        out.write_str(self.typ.as_str())?;
+        out.write_char('>')
     }
 }
 impl<T: WithMathDomNode> WithMathDomNode for MathNode<T> {
@@ -324,8 +347,8 @@ impl TextNode {
     }
 }
 impl VirtualNode for TextNode {
-    fn to_markup(&self) -> String {
-        util::escape(&self.text).into()
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        util::write_escaped(out, &self.text)
     }
 }
 impl WithMathDomNode for TextNode {
@@ -406,16 +429,16 @@ impl SpaceNode {
     }
 }
 impl VirtualNode for SpaceNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         if let Some((ch1, ch2)) = self.character {
             if let Some(ch2) = ch2 {
-                format!("<mtext>{}{}</mtext>", ch1, ch2)
+                write!(out, "<mtext>{}{}</mtext>", ch1, ch2)
             } else {
-                format!("<mtext>{}</mtext>", ch1)
+                write!(out, "<mtext>{}</mtext>", ch1)
             }
         } else {
             let width = make_em(self.width.0);
-            format!("<mspace width=\"{}\"/>", width)
+            write!(out, "<mspace width=\"{}\"/>", width)
         }
     }
 }
@@ -1,22 +1,37 @@
-use std::sync::Arc;
+use alloc::sync::Arc;
 
+use crate::prelude::*;
 use crate::macr::{MacroReplace, Macros};
 
 #[derive(Debug, Clone)]
 pub struct Namespace {
     pub(crate) current: Macros,
-    builtins: Macros,
+    /// Macros shared with other expressions, like those of a preamble in
+    /// [`render_batch`](crate::render_batch), which are read from rather than copied. The
+    /// expression's own definitions go in `current`, in front of them.
+    shared: Option<Arc<Macros>>,
+    builtins: Arc<Macros>,
     undefined_stack: Vec<Macros<Option<Arc<MacroReplace>>>>,
 }
 impl Namespace {
-    pub fn new(builtins: Macros, global_macros: Macros) -> Namespace {
+    pub fn new(builtins: Arc<Macros>, global_macros: Macros) -> Namespace {
         Self {
             current: global_macros,
+            shared: None,
             builtins,
             undefined_stack: Vec::new(),
         }
     }
 
+    /// Put the shared macros behind the expression's own
+    pub(crate) fn set_shared(&mut self, shared: Arc<Macros>) {
+        self.shared = Some(shared);
+    }
+
+    pub(crate) fn shared(&self) -> Option<&Arc<Macros>> {
+        self.shared.as_ref()
+    }
+
     /// Start a new nested group
     pub fn begin_group(&mut self) {
         self.undefined_stack.push(Macros::default());
@@ -43,21 +58,68 @@ impl Namespace {
 
     /// Check whether the macro exists
     pub fn contains_back_macro(&self, name: &str) -> bool {
-        self.current.contains_back_macro(name) || self.builtins.contains_back_macro(name)
+        self.get_back_macro(name).is_some()
     }
 
     pub fn get_back_macro(&self, name: &str) -> Option<&Arc<MacroReplace>> {
-        self.current
-            .get_back_macro(name)
+        self.get_defined_back_macro(name)
             .or_else(|| self.builtins.get_back_macro(name))
     }
 
     pub fn get_letter_macro(&self, name: char) -> Option<&Arc<MacroReplace>> {
-        self.current
-            .get_letter_macro(name)
+        self.get_defined_letter_macro(name)
             .or_else(|| self.builtins.get_letter_macro(name))
     }
 
+    /// The macro as it was defined for this expression or in the shared macros
+    fn get_defined_back_macro(&self, name: &str) -> Option<&Arc<MacroReplace>> {
+        self.current.get_back_macro(name).or_else(|| {
+            self.shared
+                .as_ref()
+                .and_then(|shared| shared.get_back_macro(name))
+        })
+    }
+
+    fn get_defined_letter_macro(&self, name: char) -> Option<&Arc<MacroReplace>> {
+        self.current.get_letter_macro(name).or_else(|| {
+            self.shared
+                .as_ref()
+                .and_then(|shared| shared.get_letter_macro(name))
+        })
+    }
+
+    /// The shared macros, to undefine one of them. They are only copied the first time, and
+    /// only if another expression still uses them.
+    fn shared_mut(&mut self) -> Option<&mut Macros> {
+        self.shared.as_mut().map(Arc::make_mut)
+    }
+
+    fn take_back_macro(&mut self, name: &str) {
+        let _ = self.current.take_back_macro(name);
+        if self
+            .shared
+            .as_ref()
+            .is_some_and(|shared| shared.contains_back_macro(name))
+        {
+            if let Some(shared) = self.shared_mut() {
+                let _ = shared.take_back_macro(name);
+            }
+        }
+    }
+
+    fn take_letter_macro(&mut self, name: char) {
+        let _ = self.current.take_letter_macro(name);
+        if self
+            .shared
+            .as_ref()
+            .is_some_and(|shared| shared.contains_letter_macro(name))
+        {
+            if let Some(shared) = self.shared_mut() {
+                let _ = shared.take_letter_macro(name);
+            }
+        }
+    }
+
     pub fn set_global_back_macro(&mut self, name: String, repl: Option<Arc<MacroReplace>>) {
         // Global set is equivalent to setting in all groups.
         // We can simulate that by removing it from the undefined stack (so that other versions of
@@ -69,7 +131,7 @@ impl Namespace {
         if let Some(repl) = &repl {
             self.current.insert_back_macro(name.clone(), repl.clone());
         } else {
-            let _ = self.current.take_back_macro(&name);
+            self.take_back_macro(&name);
         }
 
         if let Some(undef) = self.undefined_stack.last_mut() {
@@ -82,32 +144,34 @@ impl Namespace {
         // in which case, that older value is the correct one.
         let name: String = name.into();
 
+        let previous = self.get_defined_back_macro(&name).cloned();
         if let Some(undef) = self.undefined_stack.last_mut() {
             if !undef.contains_back_macro(&name) {
-                undef.insert_back_macro(name.clone(), self.current.get_back_macro(&name).cloned());
+                undef.insert_back_macro(name.clone(), previous);
             }
         }
 
         if let Some(repl) = repl {
             self.current.insert_back_macro(name, repl);
         } else {
-            let _ = self.current.take_back_macro(&name);
+            self.take_back_macro(&name);
         }
     }
 
     pub fn set_letter_macro(&mut self, name: char, repl: Option<Arc<MacroReplace>>) {
         // Undo this set at the end of this group, unless an undo already is already in place
         // in which case, that older value is the correct one.
+        let previous = self.get_defined_letter_macro(name).cloned();
         if let Some(undef) = self.undefined_stack.last_mut() {
             if !undef.contains_letter_macro(name) {
-                undef.insert_letter_macro(name, self.current.get_letter_macro(name).cloned());
+                undef.insert_letter_macro(name, previous);
             }
         }
 
         if let Some(repl) = repl {
             self.current.insert_letter_macro(name, repl);
         } else {
-            let _ = self.current.take_letter_macro(name);
+            self.take_letter_macro(name);
         }
     }
 }
@@ -0,0 +1,804 @@
+//! Writing parse trees as Office Math Markup Language, the equation format of Word documents.
+//!
+//! Like [`mathml`](crate::mathml), this walks the parse tree directly, but produces the
+//! `m:oMath` elements that go inside a paragraph of a `.docx` file, so that the equations stay
+//! editable in Word:
+//! ```
+//! # use aliter::{omml::to_omml, parse_tree, parser::ParserConfig};
+//! let tree = parse_tree(r"\frac{1}{2}", ParserConfig::default()).unwrap();
+//! let omml = to_omml(&tree, false);
+//! assert!(omml.contains("<m:f><m:num><m:r><m:t>1</m:t></m:r></m:num>"));
+//! ```
+//!
+//! Large operators become `m:nary` and named functions become `m:func`, both of which take the
+//! terms after them up to the next binary operator or relation as their operand, as Word does
+//! when typing a formula in.
+
+use crate::prelude::*;
+use crate::{
+    array::{AlignSpec, ColSeparationType},
+    expander::Mode,
+    parse_node::{
+        ArrayNode, Color, EncloseNode, GenFracNode, LeftRightNode, ParseNode, SupSubNode,
+    },
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    symbols::Atom,
+    unicode_text::{accent_char, delim_text, kern_space, symbol_text},
+};
+
+const MATH_NAMESPACE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/math";
+const WORD_NAMESPACE: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// Write the parse tree as an `m:oMath` element, or as an `m:oMathPara` if it is in display
+/// mode. The namespaces are declared on the outermost element.
+pub fn to_omml(nodes: &[ParseNode], display_mode: bool) -> String {
+    let mut writer = OmmlWriter::default();
+    writer.write_list(nodes);
+    writer.flush();
+
+    let namespaces = format!(
+        "xmlns:m=\"{}\" xmlns:w=\"{}\"",
+        MATH_NAMESPACE, WORD_NAMESPACE
+    );
+    if display_mode {
+        format!(
+            "<m:oMathPara {}><m:oMath>{}</m:oMath></m:oMathPara>",
+            namespaces, writer.out
+        )
+    } else {
+        format!("<m:oMath {}>{}</m:oMath>", namespaces, writer.out)
+    }
+}
+
+/// Parse the expression and write it as OMML, in display mode if the config says so
+pub fn render_to_omml(expr: &str, conf: ParserConfig) -> Result<String, ParseError> {
+    let display_mode = conf.display_mode;
+    let tree = parse_tree(expr, conf)?;
+    Ok(to_omml(&tree, display_mode))
+}
+
+/// The `m:sty` and `m:scr` values of a font command
+fn font_props(font: &str) -> (Option<&'static str>, Option<&'static str>) {
+    match font.trim_start_matches('\\') {
+        "mathrm" | "textrm" | "rmfamily" | "textup" | "upshape" | "textnormal" => (Some("p"), None),
+        "mathbf" | "textbf" | "bfseries" => (Some("b"), None),
+        "mathit" | "textit" | "itshape" | "emph" | "em" | "mathnormal" => (Some("i"), None),
+        "boldsymbol" | "bm" => (Some("bi"), None),
+        "mathbb" => (None, Some("double-struck")),
+        "mathcal" | "mathscr" => (None, Some("script")),
+        "mathfrak" => (None, Some("fraktur")),
+        "mathsf" | "textsf" | "sffamily" => (None, Some("sans-serif")),
+        "mathtt" | "texttt" | "ttfamily" => (None, Some("monospace")),
+        _ => (None, None),
+    }
+}
+
+/// The `w:color` value of a color, which Word only accepts as hex
+pub(crate) fn color_hex(color: &Color) -> Option<String> {
+    let [r, g, b] = match color {
+        Color::RGB(rgb) => *rgb,
+        Color::RGBA([r, g, b, _]) => [*r, *g, *b],
+        Color::Named(name) => {
+            let hex = match name.to_ascii_lowercase().as_str() {
+                "black" => "000000",
+                "white" => "FFFFFF",
+                "red" => "FF0000",
+                "green" => "008000",
+                "lime" => "00FF00",
+                "blue" => "0000FF",
+                "cyan" => "00FFFF",
+                "magenta" => "FF00FF",
+                "yellow" => "FFFF00",
+                "gray" | "grey" => "808080",
+                "darkgray" | "darkgrey" => "A9A9A9",
+                "lightgray" | "lightgrey" => "D3D3D3",
+                "silver" => "C0C0C0",
+                "orange" => "FFA500",
+                "purple" => "800080",
+                "violet" => "EE82EE",
+                "pink" => "FFC0CB",
+                "brown" => "A52A2A",
+                "maroon" => "800000",
+                "olive" => "808000",
+                "teal" => "008080",
+                "navy" => "000080",
+                _ => return None,
+            };
+            return Some(hex.to_string());
+        }
+    };
+
+    Some(format!("{:02X}{:02X}{:02X}", r, g, b))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The spacing character that an under accent is drawn with
+fn accent_under_chr(label: &str) -> Option<&'static str> {
+    Some(match label {
+        "\\utilde" => "\u{02dc}",
+        "\\underleftarrow" => "←",
+        "\\underrightarrow" => "→",
+        "\\underleftrightarrow" => "↔",
+        _ => return None,
+    })
+}
+
+/// How a node with operands after it is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperatorKind {
+    /// A large operator like `\sum`, as `m:nary`
+    Nary,
+    /// A named function like `\sin`, as `m:func`
+    Function,
+}
+
+fn operator_kind(node: &ParseNode) -> Option<OperatorKind> {
+    match node {
+        ParseNode::SupSub(sup_sub) => sup_sub.base.as_deref().and_then(operator_kind),
+        ParseNode::Op(op) => match &op.name {
+            Some(name) if op.symbol && !symbol_text(name, Mode::Math).is_empty() => {
+                Some(OperatorKind::Nary)
+            }
+            Some(_) => Some(OperatorKind::Function),
+            None => None,
+        },
+        ParseNode::OperatorName(_) => Some(OperatorKind::Function),
+        _ => None,
+    }
+}
+
+/// Whether the node ends the operand of a large operator or function
+fn ends_operand(node: &ParseNode, kind: OperatorKind) -> bool {
+    match node {
+        ParseNode::Atom(atom) => matches!(atom.family, Atom::Bin | Atom::Rel | Atom::Punct),
+        ParseNode::MClass(mclass) => matches!(mclass.m_class.as_str(), "mbin" | "mrel" | "mpunct"),
+        ParseNode::Cr(_) => true,
+        // `\sin x \cos y` is two functions, but `\sum_i \sum_j` is nested
+        node => kind == OperatorKind::Function && operator_kind(node).is_some(),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RunProps {
+    sty: Option<&'static str>,
+    scr: Option<&'static str>,
+    /// Whether the run is normal text rather than math, as in `\text`
+    normal_text: bool,
+    color: Option<String>,
+}
+impl RunProps {
+    /// The `w:rPr` of the run, for the properties that are about the text rather than the math
+    fn word_props(&self) -> String {
+        let mut props = String::new();
+        if self.normal_text {
+            match self.sty {
+                Some("b") => props.push_str("<w:b/>"),
+                Some("i") => props.push_str("<w:i/>"),
+                Some("bi") => props.push_str("<w:b/><w:i/>"),
+                _ => {}
+            }
+        }
+        if let Some(color) = &self.color {
+            props.push_str(&format!("<w:color w:val=\"{}\"/>", color));
+        }
+
+        if props.is_empty() {
+            props
+        } else {
+            format!("<w:rPr>{}</w:rPr>", props)
+        }
+    }
+}
+
+#[derive(Default)]
+struct OmmlWriter {
+    out: String,
+    props: RunProps,
+    /// The run being built, which text with the same properties is added onto
+    run: Option<(RunProps, String)>,
+    /// Whether the next run starts at an alignment point of an `m:eqArr`
+    align_next: bool,
+}
+impl OmmlWriter {
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        match &mut self.run {
+            Some((props, run)) if *props == self.props => run.push_str(text),
+            _ => {
+                self.flush();
+                self.run = Some((self.props.clone(), text.to_string()));
+            }
+        }
+    }
+
+    /// Write out the run being built
+    fn flush(&mut self) {
+        let Some((props, text)) = self.run.take() else {
+            return;
+        };
+
+        self.out.push_str("<m:r>");
+        let mut math_props = String::new();
+        if core::mem::take(&mut self.align_next) {
+            math_props.push_str("<m:aln/>");
+        }
+        if props.normal_text {
+            math_props.push_str("<m:nor/>");
+        }
+        if let Some(scr) = props.scr {
+            math_props.push_str(&format!("<m:scr m:val=\"{}\"/>", scr));
+        }
+        if let Some(sty) = props.sty.filter(|_| !props.normal_text) {
+            math_props.push_str(&format!("<m:sty m:val=\"{}\"/>", sty));
+        }
+        if !math_props.is_empty() {
+            self.out.push_str(&format!("<m:rPr>{}</m:rPr>", math_props));
+        }
+        self.out.push_str(&props.word_props());
+
+        if text.starts_with(char::is_whitespace) || text.ends_with(char::is_whitespace) {
+            self.out.push_str("<m:t xml:space=\"preserve\">");
+        } else {
+            self.out.push_str("<m:t>");
+        }
+        self.out.push_str(&escape(&text));
+        self.out.push_str("</m:t></m:r>");
+    }
+
+    fn raw(&mut self, xml: &str) {
+        self.flush();
+        self.out.push_str(xml);
+    }
+
+    /// Write the properties element of a structure, with the color of its lines and
+    /// delimiters
+    fn props(&mut self, tag: &str, props: &str) {
+        let ctrl = self.props.word_props();
+        if props.is_empty() && ctrl.is_empty() {
+            return;
+        }
+
+        self.raw(&format!("<m:{}Pr>{}", tag, props));
+        if !ctrl.is_empty() {
+            self.raw(&format!("<m:ctrlPr>{}</m:ctrlPr>", ctrl));
+        }
+        self.raw(&format!("</m:{}Pr>", tag));
+    }
+
+    fn element(&mut self, tag: &str, f: impl FnOnce(&mut Self)) {
+        self.raw(&format!("<m:{}>", tag));
+        f(self);
+        self.raw(&format!("</m:{}>", tag));
+    }
+
+    fn arg(&mut self, tag: &str, nodes: &[ParseNode]) {
+        self.element(tag, |w| w.write_list(nodes));
+    }
+
+    fn with_props(&mut self, props: RunProps, f: impl FnOnce(&mut Self)) {
+        let saved = core::mem::replace(&mut self.props, props);
+        f(self);
+        self.props = saved;
+    }
+
+    fn with_font(&mut self, font: &str, f: impl FnOnce(&mut Self)) {
+        let (sty, scr) = font_props(font);
+        let props = RunProps {
+            sty: sty.or(self.props.sty),
+            scr: scr.or(self.props.scr),
+            ..self.props.clone()
+        };
+        self.with_props(props, f);
+    }
+
+    fn write_list(&mut self, nodes: &[ParseNode]) {
+        let mut i = 0;
+        while i < nodes.len() {
+            let node = &nodes[i];
+            i += 1;
+            match operator_kind(node) {
+                Some(kind) => {
+                    let end = nodes[i..]
+                        .iter()
+                        .position(|node| ends_operand(node, kind))
+                        .map_or(nodes.len(), |end| i + end);
+                    self.write_operator(node, kind, &nodes[i..end]);
+                    i = end;
+                }
+                None => self.write_node(node),
+            }
+        }
+    }
+
+    fn write_node(&mut self, node: &ParseNode) {
+        if let Some(kind) = operator_kind(node) {
+            self.write_operator(node, kind, &[]);
+            return;
+        }
+
+        match node {
+            ParseNode::Spacing(spacing) => {
+                let text = match symbol_text(&spacing.text, spacing.info.mode) {
+                    "\u{a0}" if spacing.text != "\\nobreakspace" => " ",
+                    text => text,
+                };
+                self.push_text(text);
+            }
+            ParseNode::Atom(_)
+            | ParseNode::MathOrd(_)
+            | ParseNode::TextOrd(_)
+            | ParseNode::AccentToken(_)
+            | ParseNode::OpToken(_) => {
+                let text = node.text().unwrap_or_default();
+                self.push_text(symbol_text(text, node.info().mode));
+            }
+            ParseNode::OrdGroup(group) => self.write_list(&group.body),
+            ParseNode::SupSub(sup_sub) => self.write_sup_sub(sup_sub),
+            ParseNode::Array(array) => self.write_array(array),
+            ParseNode::LeftRight(left_right) => self.write_left_right(left_right),
+            ParseNode::LeftRightRight(right) => self.push_text(delim_text(&right.delim)),
+            ParseNode::Middle(middle) => self.push_text(delim_text(&middle.delim)),
+            ParseNode::DelimSizing(delim) => self.push_text(delim_text(&delim.delim)),
+            ParseNode::CdLabel(label) => self.write_node(&label.label),
+            ParseNode::CdLabelParentNode(parent) => self.write_node(&parent.fragment),
+            ParseNode::Color(color) => {
+                let props = RunProps {
+                    color: color_hex(&color.color).or_else(|| self.props.color.clone()),
+                    ..self.props.clone()
+                };
+                self.with_props(props, |w| w.write_list(&color.body));
+            }
+            // Operators that aren't large operators or functions, like `\overset`
+            ParseNode::Op(op) => self.write_list(op.body.as_deref().unwrap_or_default()),
+            ParseNode::Styling(styling) => self.write_list(&styling.body),
+            ParseNode::Sizing(sizing) => self.write_list(&sizing.body),
+            ParseNode::Text(text) => {
+                let props = RunProps {
+                    normal_text: true,
+                    ..self.props.clone()
+                };
+                let font = text.font.as_deref().unwrap_or_default();
+                self.with_props(props, |w| w.with_font(font, |w| w.write_list(&text.body)));
+            }
+            ParseNode::Font(font) => self.with_font(&font.font, |w| w.write_node(&font.body)),
+            ParseNode::MClass(mclass) => self.write_list(&mclass.body),
+            ParseNode::GenFrac(frac) => self.write_genfrac(frac),
+            ParseNode::Accent(accent) => match accent_char(&accent.label) {
+                Some(c) => self.element("acc", |w| {
+                    w.props("acc", &format!("<m:chr m:val=\"{}\"/>", c));
+                    w.arg("e", core::slice::from_ref(&accent.base));
+                }),
+                None => self.write_node(&accent.base),
+            },
+            ParseNode::AccentUnder(accent) => match accent_under_chr(&accent.label) {
+                Some(c) => self.element("groupChr", |w| {
+                    w.props(
+                        "groupChr",
+                        &format!("<m:chr m:val=\"{}\"/><m:pos m:val=\"bot\"/>", c),
+                    );
+                    w.arg("e", core::slice::from_ref(&accent.base));
+                }),
+                None => self.write_node(&accent.base),
+            },
+            ParseNode::HorizBrace(brace) => {
+                let c = match brace.label.as_str() {
+                    "\\overbrace" => "⏞",
+                    "\\underbrace" => "⏟",
+                    "\\overbracket" => "⎴",
+                    "\\underbracket" => "⎵",
+                    "\\overparen" => "⏜",
+                    _ => "⏝",
+                };
+                let pos = if brace.is_over {
+                    "<m:pos m:val=\"top\"/><m:vertJc m:val=\"bot\"/>"
+                } else {
+                    "<m:pos m:val=\"bot\"/><m:vertJc m:val=\"top\"/>"
+                };
+                self.element("groupChr", |w| {
+                    w.props("groupChr", &format!("<m:chr m:val=\"{}\"/>{}", c, pos));
+                    w.arg("e", core::slice::from_ref(&brace.base));
+                });
+            }
+            ParseNode::XArrow(arrow) => {
+                let name = arrow.label.replacen("\\x", "\\", 1);
+                let arrow_char = match name.as_str() {
+                    "\\longequal" => "=",
+                    "\\tofrom" => "⇄",
+                    name => match symbol_text(name, Mode::Math) {
+                        "" => "→",
+                        symbol => symbol,
+                    },
+                };
+                let sup = match arrow.body.as_ref() {
+                    ParseNode::OrdGroup(group) if group.body.is_empty() => None,
+                    body => Some(body),
+                };
+                self.write_scripts(
+                    |w| w.push_text(arrow_char),
+                    sup,
+                    arrow.below.as_deref(),
+                    true,
+                );
+            }
+            ParseNode::Sqrt(sqrt) => self.element("rad", |w| match &sqrt.index {
+                Some(index) => {
+                    w.props("rad", "");
+                    w.arg("deg", core::slice::from_ref(index));
+                    w.arg("e", core::slice::from_ref(&sqrt.body));
+                }
+                None => {
+                    w.props("rad", "<m:degHide m:val=\"1\"/>");
+                    w.raw("<m:deg/>");
+                    w.arg("e", core::slice::from_ref(&sqrt.body));
+                }
+            }),
+            ParseNode::Enclose(enclose) => self.write_enclose(enclose),
+            ParseNode::Overline(overline) => self.element("bar", |w| {
+                w.props("bar", "<m:pos m:val=\"top\"/>");
+                w.arg("e", core::slice::from_ref(&overline.body));
+            }),
+            ParseNode::Underline(underline) => self.element("bar", |w| {
+                w.props("bar", "<m:pos m:val=\"bot\"/>");
+                w.arg("e", core::slice::from_ref(&underline.body));
+            }),
+            ParseNode::Phantom(phantom) => {
+                self.write_phantom("<m:show m:val=\"0\"/>", &phantom.body)
+            }
+            ParseNode::HPhantom(phantom) => self.write_phantom(
+                "<m:show m:val=\"0\"/><m:zeroAsc m:val=\"1\"/><m:zeroDesc m:val=\"1\"/>",
+                core::slice::from_ref(&phantom.body),
+            ),
+            ParseNode::VPhantom(phantom) => self.write_phantom(
+                "<m:show m:val=\"0\"/><m:zeroWid m:val=\"1\"/>",
+                core::slice::from_ref(&phantom.body),
+            ),
+            ParseNode::Smash(smash) => {
+                let mut props = String::new();
+                if smash.smash_height {
+                    props.push_str("<m:zeroAsc m:val=\"1\"/>");
+                }
+                if smash.smash_depth {
+                    props.push_str("<m:zeroDesc m:val=\"1\"/>");
+                }
+                self.write_phantom(&props, core::slice::from_ref(&smash.body));
+            }
+            ParseNode::Lap(lap) => self.write_node(&lap.body),
+            ParseNode::RaiseBox(raise) => self.write_node(&raise.body),
+            ParseNode::VCenter(vcenter) => self.write_node(&vcenter.body),
+            ParseNode::HBox(hbox) => self.write_list(&hbox.body),
+            ParseNode::Href(href) => self.write_list(&href.body),
+            ParseNode::Html(html) => self.write_list(&html.body),
+            ParseNode::HtmlMathml(html_mathml) => self.write_list(&html_mathml.mathml),
+            ParseNode::MathChoice(choice) => self.write_list(&choice.text),
+            ParseNode::Kern(kern) => self.push_text(kern_space(&kern.dimension)),
+            ParseNode::IncludeGraphics(graphics) => self.write_normal_text(&graphics.alt),
+            ParseNode::Verb(verb) => self.write_normal_text(&verb.body),
+            ParseNode::Url(url) => self.write_normal_text(&url.url),
+            ParseNode::Raw(raw) => self.write_normal_text(&raw.string),
+            ParseNode::Tag(tag) => {
+                self.write_list(&tag.body);
+                let props = RunProps {
+                    normal_text: true,
+                    ..self.props.clone()
+                };
+                self.with_props(props, |w| {
+                    w.push_text("    (");
+                    w.write_list(&tag.tag);
+                    w.push_text(")");
+                });
+            }
+            // Written by `write_operator`
+            ParseNode::OperatorName(_) => {}
+            // Word breaks equations into lines by itself
+            ParseNode::Cr(_)
+            | ParseNode::ColorToken(_)
+            | ParseNode::Size(_)
+            | ParseNode::Environment(_)
+            | ParseNode::Infix(_)
+            | ParseNode::Internal(_)
+            | ParseNode::Rule(_) => {}
+        }
+    }
+
+    fn write_normal_text(&mut self, text: &str) {
+        let props = RunProps {
+            normal_text: true,
+            ..self.props.clone()
+        };
+        self.with_props(props, |w| w.push_text(text));
+    }
+
+    fn write_phantom(&mut self, props: &str, body: &[ParseNode]) {
+        self.element("phant", |w| {
+            w.props("phant", props);
+            w.arg("e", body);
+        });
+    }
+
+    /// Write the base with its scripts, below and above it if `limits` or otherwise to its
+    /// side
+    fn write_scripts(
+        &mut self,
+        base: impl FnOnce(&mut Self),
+        sup: Option<&ParseNode>,
+        sub: Option<&ParseNode>,
+        limits: bool,
+    ) {
+        let sup = sup.map(core::slice::from_ref);
+        let sub = sub.map(core::slice::from_ref);
+        match (sup, sub, limits) {
+            (None, None, _) => base(self),
+            (sup, Some(sub), true) => {
+                let lower = |w: &mut Self| {
+                    w.element("limLow", |w| {
+                        w.element("e", base);
+                        w.arg("lim", sub);
+                    })
+                };
+                match sup {
+                    Some(sup) => self.element("limUpp", |w| {
+                        w.element("e", lower);
+                        w.arg("lim", sup);
+                    }),
+                    None => lower(self),
+                }
+            }
+            (Some(sup), None, true) => self.element("limUpp", |w| {
+                w.element("e", base);
+                w.arg("lim", sup);
+            }),
+            (Some(sup), None, false) => self.element("sSup", |w| {
+                w.element("e", base);
+                w.arg("sup", sup);
+            }),
+            (None, Some(sub), false) => self.element("sSub", |w| {
+                w.element("e", base);
+                w.arg("sub", sub);
+            }),
+            (Some(sup), Some(sub), false) => self.element("sSubSup", |w| {
+                w.element("e", base);
+                w.arg("sub", sub);
+                w.arg("sup", sup);
+            }),
+        }
+    }
+
+    fn write_sup_sub(&mut self, sup_sub: &SupSubNode) {
+        let base = sup_sub.base.as_deref();
+        let limits = match base {
+            Some(ParseNode::Op(op)) => op.limits,
+            Some(ParseNode::HorizBrace(_)) => true,
+            _ => false,
+        };
+        self.write_scripts(
+            |w| {
+                if let Some(base) = base {
+                    w.write_node(base);
+                }
+            },
+            sup_sub.sup.as_deref(),
+            sup_sub.sub.as_deref(),
+            limits,
+        );
+    }
+
+    /// Write a large operator or function, with the nodes after it that it applies to
+    fn write_operator(&mut self, node: &ParseNode, kind: OperatorKind, operand: &[ParseNode]) {
+        let (op, sup, sub) = match node {
+            ParseNode::SupSub(sup_sub) => (
+                sup_sub.base.as_deref().unwrap_or(node),
+                sup_sub.sup.as_deref(),
+                sup_sub.sub.as_deref(),
+            ),
+            node => (node, None, None),
+        };
+
+        match (kind, op) {
+            (OperatorKind::Nary, ParseNode::Op(op)) => {
+                let name = op.name.as_deref().unwrap_or_default();
+                let mut props = format!("<m:chr m:val=\"{}\"/>", symbol_text(name, Mode::Math));
+                let lim_loc = if op.limits { "undOvr" } else { "subSup" };
+                props.push_str(&format!("<m:limLoc m:val=\"{}\"/>", lim_loc));
+                if sub.is_none() {
+                    props.push_str("<m:subHide m:val=\"1\"/>");
+                }
+                if sup.is_none() {
+                    props.push_str("<m:supHide m:val=\"1\"/>");
+                }
+
+                self.element("nary", |w| {
+                    w.props("nary", &props);
+                    w.arg("sub", sub.map(core::slice::from_ref).unwrap_or_default());
+                    w.arg("sup", sup.map(core::slice::from_ref).unwrap_or_default());
+                    w.arg("e", operand);
+                });
+            }
+            (_, op) => {
+                let limits = match op {
+                    ParseNode::Op(op) => op.limits,
+                    // `\operatorname*` has its limits below and above it, like `\lim`
+                    ParseNode::OperatorName(op) => op.limits || op.always_handle_sup_sub,
+                    _ => false,
+                };
+                let name = |w: &mut Self| {
+                    let props = RunProps {
+                        sty: Some("p"),
+                        ..w.props.clone()
+                    };
+                    w.with_props(props, |w| match op {
+                        ParseNode::Op(op) => {
+                            let name = op.name.as_deref().unwrap_or_default();
+                            w.push_text(name.trim_start_matches('\\'));
+                        }
+                        ParseNode::OperatorName(op) => w.write_list(&op.body),
+                        _ => {}
+                    });
+                };
+
+                self.element("func", |w| {
+                    w.props("func", "");
+                    w.element("fName", |w| w.write_scripts(name, sup, sub, limits));
+                    w.arg("e", operand);
+                });
+            }
+        }
+    }
+
+    fn write_genfrac(&mut self, frac: &GenFracNode) {
+        let left = delim_text(frac.left_delim.as_deref().unwrap_or("."));
+        let right = delim_text(frac.right_delim.as_deref().unwrap_or("."));
+        let fraction = |w: &mut Self| {
+            w.element("f", |w| {
+                if frac.has_bar_line {
+                    w.props("f", "");
+                } else {
+                    w.props("f", "<m:type m:val=\"noBar\"/>");
+                }
+                w.arg("num", core::slice::from_ref(&frac.numer));
+                w.arg("den", core::slice::from_ref(&frac.denom));
+            })
+        };
+
+        if left.is_empty() && right.is_empty() {
+            fraction(self);
+        } else {
+            self.element("d", |w| {
+                w.props("d", &delim_props(left, None, right));
+                w.element("e", fraction);
+            });
+        }
+    }
+
+    fn write_left_right(&mut self, left_right: &LeftRightNode) {
+        // The `\middle` delimiters separate the elements of the `m:d`
+        let mut separator = None;
+        let parts = left_right
+            .body
+            .split(|node| match node {
+                ParseNode::Middle(middle) => {
+                    separator.get_or_insert_with(|| delim_text(&middle.delim).to_string());
+                    true
+                }
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+
+        let left = delim_text(&left_right.left);
+        let right = delim_text(&left_right.right);
+        let props = delim_props(left, separator.as_deref(), right);
+        self.element("d", |w| {
+            w.props("d", &props);
+            for part in parts {
+                w.arg("e", part);
+            }
+        });
+    }
+
+    fn write_array(&mut self, array: &ArrayNode) {
+        let is_aligned = matches!(
+            array.col_separation_type,
+            Some(ColSeparationType::Align | ColSeparationType::AlignAt | ColSeparationType::Gather)
+        );
+
+        if is_aligned {
+            // The columns of `aligned` alternate between right and left aligned, with the
+            // alignment point between them
+            self.element("eqArr", |w| {
+                w.props("eqArr", "");
+                for row in &array.body {
+                    w.element("e", |w| {
+                        for (i, cell) in row.iter().enumerate() {
+                            if i % 2 == 1 {
+                                w.flush();
+                                w.align_next = true;
+                            }
+                            w.write_node(cell);
+                        }
+                    });
+                }
+            });
+            return;
+        }
+
+        let columns = array.body.iter().map(Vec::len).max().unwrap_or(0);
+        let aligns = array
+            .cols
+            .iter()
+            .flatten()
+            .filter_map(|col| match col {
+                AlignSpec::Align { align, .. } => Some(match align.as_ref() {
+                    "l" => "left",
+                    "r" => "right",
+                    _ => "center",
+                }),
+                AlignSpec::Separator(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut props = String::from("<m:mcs>");
+        for i in 0..columns {
+            let align = aligns.get(i).copied().unwrap_or("center");
+            props.push_str(&format!(
+                "<m:mc><m:mcPr><m:count m:val=\"1\"/><m:mcJc m:val=\"{}\"/></m:mcPr></m:mc>",
+                align
+            ));
+        }
+        props.push_str("</m:mcs>");
+
+        self.element("m", |w| {
+            w.props("m", &props);
+            for row in &array.body {
+                w.element("mr", |w| {
+                    for i in 0..columns {
+                        w.arg(
+                            "e",
+                            row.get(i).map(core::slice::from_ref).unwrap_or_default(),
+                        );
+                    }
+                });
+            }
+        });
+    }
+
+    fn write_enclose(&mut self, enclose: &EncloseNode) {
+        const HIDE_BORDERS: &str = "<m:hideTop m:val=\"1\"/><m:hideBot m:val=\"1\"/>\
+            <m:hideLeft m:val=\"1\"/><m:hideRight m:val=\"1\"/>";
+        let props = match enclose.label.as_str() {
+            "\\boxed" | "\\fbox" | "\\fcolorbox" => String::new(),
+            "\\cancel" => format!("{}<m:strikeBLTR m:val=\"1\"/>", HIDE_BORDERS),
+            "\\bcancel" => format!("{}<m:strikeTLBR m:val=\"1\"/>", HIDE_BORDERS),
+            "\\xcancel" => format!(
+                "{}<m:strikeBLTR m:val=\"1\"/><m:strikeTLBR m:val=\"1\"/>",
+                HIDE_BORDERS
+            ),
+            "\\sout" => format!("{}<m:strikeH m:val=\"1\"/>", HIDE_BORDERS),
+            // Backgrounds like `\colorbox` can't be drawn
+            _ => return self.write_node(&enclose.body),
+        };
+
+        self.element("borderBox", |w| {
+            w.props("borderBox", &props);
+            w.arg("e", core::slice::from_ref(&enclose.body));
+        });
+    }
+}
+
+/// The properties of an `m:d`, which has parentheses unless told otherwise
+fn delim_props(left: &str, separator: Option<&str>, right: &str) -> String {
+    let mut props = format!("<m:begChr m:val=\"{}\"/>", escape(left));
+    if let Some(separator) = separator {
+        props.push_str(&format!("<m:sepChr m:val=\"{}\"/>", escape(separator)));
+    }
+    props.push_str(&format!("<m:endChr m:val=\"{}\"/>", escape(right)));
+    props
+}
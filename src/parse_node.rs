@@ -1,5 +1,8 @@
-use std::{borrow::Cow, collections::HashMap};
+use alloc::borrow::Cow;
 
+use hashbrown::HashMap;
+
+use crate::prelude::*;
 use crate::{
     array::{AlignSpec, ColSeparationType},
     expander::Mode,
@@ -9,6 +12,113 @@ use crate::{
     util::{SourceLocation, Style, StyleAuto},
 };
 
+/// Shared by [`ParseNode::for_each_child`] and [`ParseNode::for_each_child_mut`], which only
+/// differ in whether they borrow mutably
+macro_rules! visit_children {
+    ($node:expr, $f:ident, $iter:ident, $as_ref:ident) => {
+        match $node {
+            ParseNode::Array(a) => {
+                a.body.$iter().flatten().for_each(|c| $f(c));
+                for tag in a.tags.$iter().flatten() {
+                    if let ArrayTag::Tag(tag) = tag {
+                        tag.$iter().for_each(|c| $f(c));
+                    }
+                }
+            }
+            ParseNode::CdLabel(a) => $f(a.label.$as_ref()),
+            ParseNode::CdLabelParentNode(a) => $f(a.fragment.$as_ref()),
+            ParseNode::Color(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::Op(a) => a.body.$iter().flatten().for_each(|c| $f(c)),
+            ParseNode::OrdGroup(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::Styling(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::SupSub(a) => {
+                if let Some(c) = a.base.$as_ref() {
+                    $f(c.$as_ref());
+                }
+                if let Some(c) = a.sup.$as_ref() {
+                    $f(c.$as_ref());
+                }
+                if let Some(c) = a.sub.$as_ref() {
+                    $f(c.$as_ref());
+                }
+            }
+            ParseNode::Tag(a) => {
+                a.body.$iter().for_each(|c| $f(c));
+                a.tag.$iter().for_each(|c| $f(c));
+            }
+            ParseNode::Text(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::Accent(a) => $f(a.base.$as_ref()),
+            ParseNode::AccentUnder(a) => $f(a.base.$as_ref()),
+            ParseNode::Enclose(a) => $f(a.body.$as_ref()),
+            ParseNode::Environment(a) => $f(a.name_group.$as_ref()),
+            ParseNode::Font(a) => $f(a.body.$as_ref()),
+            ParseNode::GenFrac(a) => {
+                $f(a.numer.$as_ref());
+                $f(a.denom.$as_ref());
+            }
+            ParseNode::HBox(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::HorizBrace(a) => $f(a.base.$as_ref()),
+            ParseNode::Href(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::Html(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::HtmlMathml(a) => {
+                a.html.$iter().for_each(|c| $f(c));
+                a.mathml.$iter().for_each(|c| $f(c));
+            }
+            ParseNode::Lap(a) => $f(a.body.$as_ref()),
+            ParseNode::LeftRight(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::MathChoice(a) => {
+                a.display.$iter().for_each(|c| $f(c));
+                a.text.$iter().for_each(|c| $f(c));
+                a.script.$iter().for_each(|c| $f(c));
+                a.script_script.$iter().for_each(|c| $f(c));
+            }
+            ParseNode::MClass(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::OperatorName(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::Overline(a) => $f(a.body.$as_ref()),
+            ParseNode::Phantom(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::HPhantom(a) => $f(a.body.$as_ref()),
+            ParseNode::VPhantom(a) => $f(a.body.$as_ref()),
+            ParseNode::RaiseBox(a) => $f(a.body.$as_ref()),
+            ParseNode::Sizing(a) => a.body.$iter().for_each(|c| $f(c)),
+            ParseNode::Smash(a) => $f(a.body.$as_ref()),
+            ParseNode::Sqrt(a) => {
+                if let Some(c) = a.index.$as_ref() {
+                    $f(c.$as_ref());
+                }
+                $f(a.body.$as_ref());
+            }
+            ParseNode::Underline(a) => $f(a.body.$as_ref()),
+            ParseNode::VCenter(a) => $f(a.body.$as_ref()),
+            ParseNode::XArrow(a) => {
+                $f(a.body.$as_ref());
+                if let Some(c) = a.below.$as_ref() {
+                    $f(c.$as_ref());
+                }
+            }
+            ParseNode::ColorToken(_)
+            | ParseNode::Raw(_)
+            | ParseNode::Size(_)
+            | ParseNode::Url(_)
+            | ParseNode::Verb(_)
+            | ParseNode::Atom(_)
+            | ParseNode::MathOrd(_)
+            | ParseNode::Spacing(_)
+            | ParseNode::TextOrd(_)
+            | ParseNode::AccentToken(_)
+            | ParseNode::OpToken(_)
+            | ParseNode::Cr(_)
+            | ParseNode::DelimSizing(_)
+            | ParseNode::IncludeGraphics(_)
+            | ParseNode::Infix(_)
+            | ParseNode::Internal(_)
+            | ParseNode::Kern(_)
+            | ParseNode::LeftRightRight(_)
+            | ParseNode::Middle(_)
+            | ParseNode::Rule(_) => {}
+        }
+    };
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParseNode {
     Array(ArrayNode),
@@ -198,6 +308,18 @@ impl ParseNode {
         self.info().loc.clone()
     }
 
+    /// Call `f` on each of the node's children.  
+    /// The order is fixed for each kind of node, but isn't always the order of the source, as
+    /// a `SupSub` visits its base, then superscript, then subscript however they were written.
+    pub fn for_each_child<'a>(&'a self, f: &mut dyn FnMut(&'a ParseNode)) {
+        visit_children!(self, f, iter, as_ref);
+    }
+
+    /// Call `f` on each of the node's children, in the same order as [`ParseNode::for_each_child`]
+    pub fn for_each_child_mut<'a>(&'a mut self, f: &mut dyn FnMut(&'a mut ParseNode)) {
+        visit_children!(self, f, iter_mut, as_mut);
+    }
+
     pub fn text(&self) -> Option<&str> {
         match self {
             ParseNode::Atom(a) => Some(&a.text),
@@ -647,7 +769,7 @@ impl EqNoLoc for OpNode {
             && self.parent_is_sup_sub == o.parent_is_sup_sub
             && self.symbol == o.symbol
             && self.name == o.name
-            && self.body == o.body
+            && self.body.eq_no_loc(&o.body)
             && self.info.eq_no_loc(&o.info)
     }
 }
@@ -999,6 +1121,10 @@ pub struct HorizBraceNode {
     pub label: String,
     pub is_over: bool,
     pub base: Box<ParseNode>,
+    /// The rule thickness of `\overbracket` and `\underbracket`, if given
+    pub thickness: Option<Measurement>,
+    /// The height of `\overbracket` and `\underbracket`, if given
+    pub height: Option<Measurement>,
     pub info: NodeInfo,
 }
 impl EqNoLoc for HorizBraceNode {
@@ -1006,6 +1132,8 @@ impl EqNoLoc for HorizBraceNode {
         self.label == o.label
             && self.is_over == o.is_over
             && self.base.as_ref().eq_no_loc(o.base.as_ref())
+            && self.thickness == o.thickness
+            && self.height == o.height
             && self.info.eq_no_loc(&o.info)
     }
 }
@@ -1081,7 +1209,7 @@ impl EqNoLoc for InfixNode {
     fn eq_no_loc(&self, o: &InfixNode) -> bool {
         self.replace_with == o.replace_with
             && self.size == o.size
-            && self.token == o.token
+            && self.token.as_ref().map(|t| &t.content) == o.token.as_ref().map(|t| &t.content)
             && self.info.eq_no_loc(&o.info)
     }
 }
@@ -1,9 +1,9 @@
-use std::{borrow::Cow, sync::Arc};
+use alloc::{borrow::Cow, sync::Arc};
 
-use once_cell::sync::Lazy;
-use regex::Regex;
 
+use crate::prelude::*;
 use crate::{
+    a11y::SpeechStyle,
     environments::{self, EnvironmentContext, EnvironmentSpec},
     expander::{is_implicit_command, BreakToken, MacroExpander, Mode},
     functions::{FunctionContext, FunctionSpec, Functions},
@@ -19,31 +19,67 @@ use crate::{
     unicode_scripts,
     unit::{self, Measurement},
     util::{
-        first_ch_str, parse_rgb, parse_rgb_3, parse_rgba, ArgType, SourceLocation, Style, RGBA,
+        find_size, first_ch_str, parse_rgb, parse_rgb_3, parse_rgba, ArgType, SourceLocation, Style, RGBA,
     },
 };
 
-// (?i) must be at the start for Rust's regex engine
-// Matches: #abc (3-digit hex), #aabbcc or aabbcc (6-digit hex), colorname
-// Note: 3-digit hex REQUIRES #, 6-digit hex allows optional #
-static COLOR_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new("(?i)^(?:#[a-f0-9]{3}|#?[a-f0-9]{6}|[a-z]+)$").unwrap()
-});
+/// Whether the text is a color: `#abc`, `#aabbcc` or `aabbcc`, or a color name
+fn is_color(text: &str) -> bool {
+    let is_hex = |text: &str, len: usize| {
+        text.len() == len && text.bytes().all(|b| b.is_ascii_hexdigit())
+    };
+    // Note: 3-digit hex REQUIRES #, 6-digit hex allows optional #
+    if let Some(hex) = text.strip_prefix('#') {
+        is_hex(hex, 3) || is_hex(hex, 6)
+    } else {
+        is_hex(text, 6) || (!text.is_empty() && text.bytes().all(|b| b.is_ascii_alphabetic()))
+    }
+}
 
-// static SIX_HEX_COLOR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^(?i)[0-9a-f]{6}").unwrap());
+/// Whether the text is a (possibly incomplete) size, like `-1.5em` or `+`
+fn is_size_group(text: &str) -> bool {
+    let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let text = text.trim_start_matches(' ');
+    if text.is_empty() {
+        return true;
+    }
 
-static SIZE_GROUP_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new("^[-+]? *(?:$|\\d+|\\d+\\.\\d*|\\.\\d*) *[a-z]{0,2} *$").unwrap());
-static SIZE_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new("([-+]?) *(\\d+(?:\\.\\d*)?|\\.\\d+) *([a-z]{2})").unwrap());
+    let rest = text.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = match rest.strip_prefix('.') {
+        Some(frac) => frac.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None if rest.len() < text.len() => rest,
+        None => return false,
+    };
+    let rest = rest.trim_start_matches(' ');
+    let unit_len = rest.bytes().take_while(u8::is_ascii_lowercase).count();
+    unit_len <= 2 && rest[unit_len..].trim_start_matches(' ').is_empty()
+}
 
-// Global replacement is handled by `replace_all`, so no `g` flag is needed.
-static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\\([#$%&~_^{}])").unwrap());
+/// Remove the backslash from escaped characters in an URL
+fn unescape_url(url: &str) -> String {
+    let mut res = String::with_capacity(url.len());
+    let mut chars = url.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && "#$%&~_^{}".contains(next) => {
+                res.push(next);
+                chars.next();
+            }
+            _ => res.push(c),
+        }
+    }
+    res
+}
 
-static SYMBOL_VERB_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^\\\\verb[^a-zA-Z]").unwrap());
+fn is_symbol_verb(text: &str) -> bool {
+    text.strip_prefix("\\verb")
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| !c.is_ascii_alphabetic())
+}
 
-static COMBINING_DIACRITICAL_MARKS_END_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new("[\u{0300}-\u{036f}]+$").unwrap());
+fn is_combining_diacritical_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036f}').contains(&c)
+}
 
 // TODO: provide more debug information on these
 #[derive(Debug, Clone)]
@@ -51,6 +87,8 @@ pub enum ParseError {
     Expected,
     ExpectedEndOfFile,
     UnexpectedEOF,
+    /// A `}` in an argument without a `{` for it to close
+    ExtraCloseBrace,
     /// (Pos, char)
     UnexpectedChar(usize, char),
     /// (name)
@@ -75,6 +113,10 @@ pub enum ParseError {
 
     IncompletePlaceholder,
     InvalidArgumentNumber,
+    /// `\global` or `\long` without a definition after it
+    InvalidMacroPrefix,
+    /// `\def`, `\let` or `\futurelet` without the name of a command to define
+    ExpectedControlSequence,
 
     ExpectedGroup,
 
@@ -125,8 +167,41 @@ pub enum ParseError {
     MiddleWithoutLeft,
     /// Invalid delimiter (e.g. not a valid delimiter character)
     InvalidDelimiter,
+    /// `\)` or `\]` without math for it to close
+    /// (name)
+    MismatchedMathDelimiter(String),
+
+    /// Groups are nested deeper than [`ParserConfig::max_depth`]
+    TooDeeplyNested,
+    /// The parse tree has more nodes than [`ParserConfig::max_nodes`]
+    TooManyNodes,
+    /// An array has more cells than [`ParserConfig::max_array_cells`]
+    TooManyArrayCells,
+    /// The markup is longer than [`ParserConfig::max_output_size`]
+    OutputTooLarge,
+    /// Parsing took more steps than [`ParserConfig::max_steps`]
+    TooManySteps,
+    /// Parsing did not finish before [`ParserConfig::deadline`]
+    DeadlineExceeded,
+
+    /// `siunitx`: The number could not be parsed
+    InvalidSiNumber(String),
+    /// `siunitx`: The unit could not be parsed
+    InvalidSiUnit(String),
+    /// `siunitx`: Unknown option, or an invalid value for an option
+    InvalidSiOption(String),
 }
 
+/// The default for [`ParserConfig::max_depth`], which is as deep as formulas written by hand
+/// usually go.
+/// The formula itself takes one level, which leaves 15 levels of braces, arguments like those of
+/// `\frac`, `\sqrt` and `x^{...}`, `\left...\right`, or environments like `matrix`. Something that
+/// opens two groups, like `\text{$...$}` or `\color{red}{...}`, can be nested 7 deep.
+/// Rendering takes the most stack per level for arrays and `\underbrace`, and on the 2 MiB
+/// stack of a spawned thread those overflow a debug build at about 20 levels and a release build
+/// at about 75, so this fits either with some room to spare.
+pub const DEFAULT_MAX_DEPTH: u32 = 16;
+
 /// Configuration options for parsing.
 /// Inherits several of the options that KaTeX would generate.
 /// Does not have `output` since that is handled by other parts.
@@ -171,6 +246,34 @@ pub struct ParserConfig {
     /// Whether we should trust the input
     /// This allows things like `\url`, `\includegraphics`, `\htmlClass`, etc.
     pub trust: bool,
+    /// Put a spoken description of the formula, in the given style, in an `aria-label` on the
+    /// root of the HTML output, so that screen readers don't have to rely on the MathML.
+    /// See [`crate::a11y`].
+    pub aria_label: Option<SpeechStyle>,
+    /// Limit how deeply groups can be nested, so that input like `{{{{...}}}}` or
+    /// `\sqrt\sqrt\sqrt...` can't overflow the stack while it is parsed or rendered.
+    /// The formula, each group in braces, argument, cell of an environment and `\left...\right`,
+    /// and what follows a switch like `\color` or `\bf`, counts as a level, so `\sqrt{x}` is two
+    /// levels deep. An argument without braces counts as a level too, so `\sqrt x` is as deep.
+    /// If set to `None`, there is no limit.
+    pub max_depth: Option<u32>,
+    /// Limit the number of groups, commands and symbols that are parsed, which bounds the size
+    /// of the parse tree and of everything built from it.
+    pub max_nodes: Option<u32>,
+    /// Limit the total number of cells in the arrays, matrices and other environments with rows
+    /// and columns. Empty cells don't add to the nodes, so `max_nodes` doesn't bound these.
+    pub max_array_cells: Option<u32>,
+    /// Limit the length, in bytes, of the markup from
+    /// [`render_to_string`](crate::render_to_string) and the functions that use it.
+    pub max_output_size: Option<usize>,
+    /// Limit the number of tokens that the parser takes from the macro expander, whether they
+    /// are expanded or not. Unlike the `deadline`, this gives the same result on every run.
+    pub max_steps: Option<u32>,
+    /// Stop parsing with [`ParseError::DeadlineExceeded`] once this time has passed.
+    /// Only parsing is checked, as building the output from the tree can't stop part way, so
+    /// it is `max_nodes` and `max_array_cells` that bound how long that takes.
+    #[cfg(feature = "std")]
+    pub deadline: Option<std::time::Instant>,
 }
 impl ParserConfig {
     pub fn is_trusted(&self, _command: &str, _url: &str) -> bool {
@@ -190,17 +293,25 @@ impl Default for ParserConfig {
             macros: Macros::default(),
             min_rule_thickness: unit::Em(0.04),
             color_is_text_color: false,
-            max_size: unit::Em(std::f64::INFINITY),
+            max_size: unit::Em(f64::INFINITY),
             max_expand: Some(1000),
             strict: StrictMode::Warn,
             trust: false,
+            aria_label: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_nodes: None,
+            max_array_cells: None,
+            max_output_size: None,
+            max_steps: None,
+            #[cfg(feature = "std")]
+            deadline: None,
         }
     }
 }
 
 /// How strict to be about features that make writing LaTeX convenient but are not actually
 /// supported by it.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StrictMode {
     /// Silently ignore issues
     Ignore,
@@ -219,6 +330,12 @@ pub struct Parser<'a, 'f> {
     next_token: Option<Token<'a>>,
     /// Depth of \left...\right nesting (for \middle validation)
     pub(crate) leftright_depth: usize,
+    /// Depth of the groups being parsed, for [`ParserConfig::max_depth`]
+    depth: u32,
+    /// The number of nodes parsed, for [`ParserConfig::max_nodes`]
+    node_count: u32,
+    /// The number of cells in every array parsed, for [`ParserConfig::max_array_cells`]
+    array_cell_count: u32,
 }
 impl<'a, 'f> Parser<'a, 'f> {
     pub fn new(input: &'a str, conf: ParserConfig, functions: &'f Functions) -> Parser<'a, 'f> {
@@ -227,6 +344,9 @@ impl<'a, 'f> Parser<'a, 'f> {
             conf,
             next_token: None,
             leftright_depth: 0,
+            depth: 0,
+            node_count: 0,
+            array_cell_count: 0,
         }
     }
 
@@ -251,7 +371,7 @@ impl<'a, 'f> Parser<'a, 'f> {
     }
 
     /// Discards the current lookahead token, returning the value it holds
-    pub(crate) fn consume(&mut self) -> Option<Token> {
+    pub(crate) fn consume(&mut self) -> Option<Token<'_>> {
         self.next_token.take()
     }
 
@@ -297,6 +417,20 @@ impl<'a, 'f> Parser<'a, 'f> {
         self.gullet.switch_mode(mode);
     }
 
+    /// Count another cell of an array against [`ParserConfig::max_array_cells`]
+    pub(crate) fn count_array_cell(&mut self) -> Result<(), ParseError> {
+        self.array_cell_count += 1;
+        if self
+            .conf
+            .max_array_cells
+            .is_some_and(|max| self.array_cell_count > max)
+        {
+            return Err(ParseError::TooManyArrayCells);
+        }
+
+        Ok(())
+    }
+
     pub fn dispatch_parse(&mut self) -> Result<Vec<ParseNode>, ParseError> {
         match self.mode() {
             Mode::Math => self.parse::<true>(),
@@ -373,10 +507,27 @@ impl<'a, 'f> Parser<'a, 'f> {
         break_on_infix: bool,
         break_on_token_text: Option<BreakToken>,
     ) -> Result<Vec<ParseNode>, ParseError> {
-        match self.gullet.mode {
-            Mode::Math => self.parse_expression::<true>(break_on_infix, break_on_token_text),
-            Mode::Text => self.parse_expression::<false>(break_on_infix, break_on_token_text),
+        // Every group is parsed as an expression, so this is where its depth is counted
+        self.nested(|parser| match parser.gullet.mode {
+            Mode::Math => parser.parse_expression::<true>(break_on_infix, break_on_token_text),
+            Mode::Text => parser.parse_expression::<false>(break_on_infix, break_on_token_text),
+        })
+    }
+
+    /// Parse something one level deeper, checking [`ParserConfig::max_depth`]
+    pub(crate) fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.conf.max_depth.is_some_and(|max| self.depth >= max) {
+            return Err(ParseError::TooDeeplyNested);
         }
+
+        self.depth += 1;
+        let res = parse(self);
+        self.depth -= 1;
+
+        res
     }
 
     /// Parses an "expression", which is a list of atoms.  
@@ -637,7 +788,7 @@ impl<'a, 'f> Parser<'a, 'f> {
                     None
                 };
 
-                let primes = std::iter::repeat(ParseNode::TextOrd(prime))
+                let primes = core::iter::repeat(ParseNode::TextOrd(prime))
                     .take(prime_count)
                     .chain(sup)
                     .collect::<Vec<_>>();
@@ -652,7 +803,7 @@ impl<'a, 'f> Parser<'a, 'f> {
                 // We treat these similarly to the unicode-math package.
                 // So we render a string of Unicode (sub|super)scripts the same as
                 // as a (sub|super)script of regular characters
-                let is_sub = unicode::SUB_REGEX.is_match(&lex.content);
+                let is_sub = unicode::starts_with_sub(&lex.content);
                 self.consume();
 
                 let mut text = ch.to_string();
@@ -666,7 +817,7 @@ impl<'a, 'f> Parser<'a, 'f> {
                         break;
                     };
 
-                    if unicode::SUB_REGEX.is_match(token) != is_sub {
+                    if unicode::starts_with_sub(token) != is_sub {
                         break;
                     }
 
@@ -675,6 +826,9 @@ impl<'a, 'f> Parser<'a, 'f> {
                 }
 
                 let mut parser = Parser::new(&text, self.conf.clone(), self.gullet.functions);
+                if let Some(shared) = self.gullet.macros.shared() {
+                    parser.gullet.macros.set_shared(shared.clone());
+                }
                 let body = parser.dispatch_parse()?;
 
                 // TODO: Shouldn't this be checking if super/subscript are already set? Katex
@@ -697,6 +851,11 @@ impl<'a, 'f> Parser<'a, 'f> {
         }
 
         Ok(if superscript.is_some() || subscript.is_some() {
+            // A definition like `\def\a{b}^2` leaves nothing for the script to attach to, as in TeX
+            if matches!(base, Some(ParseNode::Internal(_))) {
+                base = None;
+            }
+
             Some(ParseNode::SupSub(SupSubNode {
                 base: base.map(Box::new),
                 // TODO: it seems like super/subscript are maybe only ever ord group nodes?
@@ -738,14 +897,32 @@ impl<'a, 'f> Parser<'a, 'f> {
         let FunctionArguments { args, opt_args } =
             self.parse_arguments(&function_name, function.clone())?;
 
-        self.call_function(
+        let mut node = self.call_function(
             function_name,
             &args,
             &opt_args,
             Some(token.clone()),
             break_on_token_text,
-        )
-        .map(Some)
+        )?;
+
+        // Cover the command and everything it consumed, if the handler didn't set a location
+        if node.info().loc.is_none() {
+            node.info_mut().loc = token
+                .loc
+                .zip(self.position())
+                .map(|(start, end)| SourceLocation(start.0.start..end));
+        }
+
+        Ok(Some(node))
+    }
+
+    /// The position in the input of the next token to be read.
+    /// This is only meaningful when that token came from the input rather than from a macro.
+    fn position(&self) -> Option<usize> {
+        match &self.next_token {
+            Some(token) => token.loc.as_ref().map(|loc| loc.0.start),
+            None => self.gullet.position(),
+        }
     }
 
     /// Call a function handler with a suitable context and arguments
@@ -874,7 +1051,7 @@ impl<'a, 'f> Parser<'a, 'f> {
         &mut self,
         _break_on_token_text: Option<BreakToken>,
     ) -> Result<ParseNode, ParseError> {
-        let _begin_token = self.fetch()?.clone();
+        let begin_token = self.fetch()?.clone();
         self.consume();
 
         let name_group = self
@@ -894,7 +1071,7 @@ impl<'a, 'f> Parser<'a, 'f> {
             parser: self,
         };
 
-        let res = (spec.handler)(ctx, &args, &opt_args)?;
+        let mut res = (spec.handler)(ctx, &args, &opt_args)?;
 
         self.expect("\\end", true)?;
         let end_name_group = self
@@ -905,6 +1082,10 @@ impl<'a, 'f> Parser<'a, 'f> {
             return Err(ParseError::Expected);
         }
 
+        if res.info().loc.is_none() {
+            res.info_mut().loc = SourceLocation::combine(begin_token.loc, end_name_group.loc());
+        }
+
         Ok(res)
     }
 
@@ -956,10 +1137,15 @@ impl<'a, 'f> Parser<'a, 'f> {
                     return Err(ParseError::PrimitiveCantBeOptional);
                 }
 
-                Some(
+                // A braced argument is counted as the group it is, and one without braces as if
+                // it had them
+                let group = if self.fetch()?.content == "{" {
                     self.parse_group(name, None)?
-                        .ok_or(ParseError::ExpectedGroup)?,
-                )
+                } else {
+                    self.nested(|parser| parser.parse_group(name, None))?
+                };
+
+                Some(group.ok_or(ParseError::ExpectedGroup)?)
             }
             ArgType::Mode(mode) => self
                 .parse_argument_group(optional, Some(mode))?
@@ -994,8 +1180,8 @@ impl<'a, 'f> Parser<'a, 'f> {
     }
 
     /// Parses a regex-delimited group: the largest sequence of tokens whose concatenated strings
-    /// match `regex`. Returns the string formed by the tokens plus some position information.
-    fn parse_regex_group(&mut self, regex: &Regex) -> Result<Token<'a>, ParseError> {
+    /// match `is_match`. Returns the string formed by the tokens plus some position information.
+    fn parse_regex_group(&mut self, is_match: fn(&str) -> bool) -> Result<Token<'a>, ParseError> {
         let first_token = self.fetch()?;
         let first_token_loc = first_token.loc.clone();
         let mut last_token_loc = first_token.loc.clone();
@@ -1005,7 +1191,7 @@ impl<'a, 'f> Parser<'a, 'f> {
             let next_token = self.fetch()?;
             // TODO: this is less efficient than it could be?
             let test_text = format!("{}{}", text, next_token.content);
-            if next_token.is_eof() || !regex.is_match(&test_text) {
+            if next_token.is_eof() || !is_match(&test_text) {
                 break;
             }
 
@@ -1032,13 +1218,10 @@ impl<'a, 'f> Parser<'a, 'f> {
         };
 
         // TODO: At a glance this doesn't support rgba?
-        let capture = COLOR_REGEX
-            .captures(&res.content)
-            .ok_or(ParseError::InvalidColor)?;
-
-        // TODO: better error?
-        let color = capture.get(0).ok_or(ParseError::InvalidColor)?;
-        let color = color.as_str();
+        if !is_color(&res.content) {
+            return Err(ParseError::InvalidColor);
+        }
+        let color = &*res.content;
 
         // TODO: This is very ugly
         let color = if color.len() == 6 {
@@ -1086,7 +1269,7 @@ impl<'a, 'f> Parser<'a, 'f> {
         self.gullet.consume_spaces()?;
 
         let res = if !optional && self.gullet.future()?.content != "{" {
-            Some(self.parse_regex_group(&SIZE_GROUP_REGEX).map_err(|e| {
+            Some(self.parse_regex_group(is_size_group).map_err(|e| {
                 // Convert InvalidRegexMode to InvalidSize for better error messages
                 if matches!(e, ParseError::InvalidRegexMode) {
                     ParseError::InvalidSize
@@ -1142,27 +1325,12 @@ impl<'a, 'f> Parser<'a, 'f> {
             content = content[1..content.len() - 1].trim().to_string();
         }
 
-        let captures = SIZE_REGEX
-            .captures(&content)
-            .ok_or(ParseError::InvalidSize)?;
-
-        let sign = captures.get(1);
-        let magnitude = captures.get(2);
-        let num = if let Some((sign, magnitude)) = sign.zip(magnitude) {
-            let sign = sign.as_str();
-            let magnitude = magnitude.as_str();
-            // TODO: option to error on this
-            let magnitude = magnitude.parse::<f64>().unwrap_or(std::f64::NAN);
-            if sign == "-" {
-                -magnitude
-            } else {
-                magnitude
-            }
-        } else {
-            // KaTeX doesn't check for if it is a valid number
-            std::f64::NAN
-        };
-        let unit = captures.get(3).ok_or(ParseError::InvalidUnit)?.as_str();
+        let (sign, magnitude, unit) =
+            find_size(&content, |c| c == ' ').ok_or(ParseError::InvalidSize)?;
+
+        // TODO: option to error on this
+        let magnitude = magnitude.parse::<f64>().unwrap_or(f64::NAN);
+        let num = if sign == "-" { -magnitude } else { magnitude };
 
         let measure = Measurement::from_unit(num, unit).ok_or(ParseError::InvalidUnit)?;
 
@@ -1188,10 +1356,8 @@ impl<'a, 'f> Parser<'a, 'f> {
             return Ok(None);
         };
 
-        let url = URL_REGEX.replace_all(&res.content, "$1");
-
         Ok(Some(UrlNode {
-            url: url.into_owned(),
+            url: unescape_url(&res.content),
             info: NodeInfo::new_mode(self.mode()),
         }))
     }
@@ -1241,6 +1407,23 @@ impl<'a, 'f> Parser<'a, 'f> {
         &mut self,
         name: &str,
         break_on_token_text: Option<BreakToken>,
+    ) -> Result<Option<ParseNode>, ParseError> {
+        // Every node goes through here, so this is where they are counted
+        let group = self.parse_group_inner(name, break_on_token_text)?;
+        if group.is_some() {
+            self.node_count += 1;
+            if self.conf.max_nodes.is_some_and(|max| self.node_count > max) {
+                return Err(ParseError::TooManyNodes);
+            }
+        }
+
+        Ok(group)
+    }
+
+    fn parse_group_inner(
+        &mut self,
+        name: &str,
+        break_on_token_text: Option<BreakToken>,
     ) -> Result<Option<ParseNode>, ParseError> {
         let first_token = self.fetch()?.clone();
 
@@ -1352,10 +1535,7 @@ impl<'a, 'f> Parser<'a, 'f> {
                         text: Cow::Borrowed("---"),
                         info: NodeInfo {
                             mode: Mode::Text,
-                            loc: SourceLocation::combine(
-                                a_loc.clone(),
-                                group.get(i + 2).and_then(ParseNode::loc),
-                            ),
+                            loc: SourceLocation::combine(a_loc.clone(), group[i].loc()),
                         },
                     });
                     n = n.saturating_sub(2);
@@ -1365,10 +1545,7 @@ impl<'a, 'f> Parser<'a, 'f> {
                         text: Cow::Borrowed("--"),
                         info: NodeInfo {
                             mode: Mode::Text,
-                            loc: SourceLocation::combine(
-                                a_loc.clone(),
-                                group.get(i + 1).and_then(ParseNode::loc),
-                            ),
+                            loc: SourceLocation::combine(a_loc.clone(), group[i].loc()),
                         },
                     });
                     n = n.saturating_sub(1);
@@ -1385,12 +1562,10 @@ impl<'a, 'f> Parser<'a, 'f> {
                     text: format!("{text}{text}").into(),
                     info: NodeInfo {
                         mode: Mode::Text,
-                        loc: SourceLocation::combine(
-                            a_loc,
-                            group.get(i + 1).and_then(ParseNode::loc),
-                        ),
+                        loc: SourceLocation::combine(a_loc, group[i].loc()),
                     },
-                })
+                });
+                n = n.saturating_sub(1);
             }
 
             i += 1;
@@ -1403,7 +1578,7 @@ impl<'a, 'f> Parser<'a, 'f> {
         let nucleus = self.fetch()?.clone();
         let nucleus_loc = nucleus.loc.clone();
 
-        if SYMBOL_VERB_REGEX.is_match(&nucleus.content) {
+        if is_symbol_verb(&nucleus.content) {
             self.consume();
 
             let arg = &nucleus.content[5..];
@@ -1442,15 +1617,13 @@ impl<'a, 'f> Parser<'a, 'f> {
         }
 
         // Strip off any combining characters
-        let first_match = COMBINING_DIACRITICAL_MARKS_END_REGEX
-            .captures(&text)
-            .and_then(|c| c.get(0));
-        let transforms = if let Some(first) = &first_match {
+        let accents_start = text.trim_end_matches(is_combining_diacritical_mark).len();
+        let transforms = if accents_start < text.len() {
             // KaTeX does this near the end of this func,
             // but we do it here to minimize clones and the like
 
             let mut transforms = Vec::new();
-            for accent_ch in first.as_str().chars() {
+            for accent_ch in text[accents_start..].chars() {
                 let accent = unicode::get_accent(accent_ch).ok_or(ParseError::UnknownAccent)?;
 
                 let command = accent.get_mode(self.mode());
@@ -1461,11 +1634,10 @@ impl<'a, 'f> Parser<'a, 'f> {
             }
 
             // This is what is normally done
-            let start = first.start();
             text = match text {
-                Cow::Borrowed(bor) => Cow::Borrowed(&bor[0..start]),
+                Cow::Borrowed(bor) => Cow::Borrowed(&bor[0..accents_start]),
                 Cow::Owned(mut own) => {
-                    own.truncate(start);
+                    own.truncate(accents_start);
                     Cow::Owned(own)
                 }
             };
@@ -1493,7 +1665,10 @@ impl<'a, 'f> Parser<'a, 'f> {
                 Group::Atom(atom_g) => ParseNode::Atom(AtomNode {
                     family: atom_g,
                     text: text.into_owned(),
-                    info: NodeInfo::new_mode(self.mode()),
+                    info: NodeInfo {
+                        mode: self.mode(),
+                        loc: nucleus_loc.clone(),
+                    },
                 }),
                 Group::NonAtom(n_atom) => n_atom.make_parse_node(
                     NodeInfo {
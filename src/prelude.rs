@@ -0,0 +1,11 @@
+//! The items of the `std` prelude that come from `alloc`, for modules that are built without
+//! `std`. With `std` these are the same items that the `std` prelude has.
+
+pub(crate) use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
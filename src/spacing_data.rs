@@ -1,6 +1,48 @@
 //! Describes spaces between different classes of atoms.
 
-use crate::{html::DomType, unit::Mu};
+use crate::unit::Mu;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DomType {
+    MOrd,
+    MOp,
+    MBin,
+    MRel,
+    MOpen,
+    MClose,
+    MPunct,
+    MInner,
+}
+impl DomType {
+    #[cfg(feature = "html")]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "mord" => Some(DomType::MOrd),
+            "mop" => Some(DomType::MOp),
+            "mbin" => Some(DomType::MBin),
+            "mrel" => Some(DomType::MRel),
+            "mopen" => Some(DomType::MOpen),
+            "mclose" => Some(DomType::MClose),
+            "mpunct" => Some(DomType::MPunct),
+            "minner" => Some(DomType::MInner),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "html")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DomType::MOrd => "mord",
+            DomType::MOp => "mop",
+            DomType::MBin => "mbin",
+            DomType::MRel => "mrel",
+            DomType::MOpen => "mopen",
+            DomType::MClose => "mclose",
+            DomType::MPunct => "mpunct",
+            DomType::MInner => "minner",
+        }
+    }
+}
 
 const THIN_SPACE: Mu = Mu(3.0);
 const MEDIUM_SPACE: Mu = Mu(4.0);
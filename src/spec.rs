@@ -44,11 +44,11 @@ mod tests {
         conf
     }
 
-    fn default_options() -> Options {
+    fn default_options() -> Options<'static> {
         let mut options = Options::from_parser_conf(&ParserConfig::default());
         options.style = TEXT_STYLE;
         options.size = 5;
-        options.max_size = Em(std::f64::INFINITY);
+        options.max_size = Em(f64::INFINITY);
         options
     }
 
@@ -1986,7 +1986,7 @@ mod tests {
     #[test]
     fn a_macro_expander() {
         use crate::macr::{MacroReplace, Macros};
-        use std::sync::Arc;
+        use alloc::sync::Arc;
 
         let mut conf_base = ParserConfig::default();
 
@@ -2252,7 +2252,7 @@ mod tests {
     #[test]
     fn the_maxexpand_setting() {
         use crate::macr::{MacroReplace, Macros};
-        use std::sync::Arc;
+        use alloc::sync::Arc;
         let mut conf_base = ParserConfig::default();
 
         // should prevent expansion
@@ -4,8 +4,11 @@
 //! overflow: hidden tactic to achieve a stretchy image while avoiding
 //! distortion of arrowheads or brace corners.
 
-use std::borrow::Cow;
+use crate::prelude::*;
+use crate::svg_geometry;
 
+#[cfg(feature = "html")]
+use alloc::borrow::Cow;
 #[cfg(feature = "html")]
 use crate::{
     build_common::{make_span, make_svg_span},
@@ -18,6 +21,7 @@ use crate::{
 use crate::mathml_tree::{MathNode, MathNodeType, MathmlNode, TextNode};
 
 /// Stretchy code points for MathML
+#[cfg(feature = "mathml")]
 pub fn stretchy_code_point(label: &str) -> &'static str {
     let label = label.strip_prefix('\\').unwrap_or(label);
     match label {
@@ -31,6 +35,10 @@ pub fn stretchy_code_point(label: &str) -> &'static str {
         "overbrace" => "\u{23de}",
         "overgroup" => "\u{23e0}",
         "undergroup" => "\u{23e1}",
+        "overparen" => "\u{23dc}",
+        "underparen" => "\u{23dd}",
+        "overbracket" => "\u{23b4}",
+        "underbracket" => "\u{23b5}",
         "overleftrightarrow" | "underleftrightarrow" | "xleftrightarrow" => "\u{2194}",
         "Overrightarrow" | "xRightarrow" => "\u{21d2}",
         "overleftharpoon" | "xleftharpoonup" => "\u{21bc}",
@@ -59,6 +67,7 @@ pub fn stretchy_code_point(label: &str) -> &'static str {
 
 /// SVG path data: (paths, min_width, view_box_height, align)
 /// Height is in SVG units (divide by 1000 for em)
+#[cfg(feature = "html")]
 #[derive(Clone, Copy)]
 pub struct SvgImageData {
     pub paths: &'static [&'static str],
@@ -67,6 +76,7 @@ pub struct SvgImageData {
     pub align: Option<&'static str>,
 }
 
+#[cfg(feature = "html")]
 pub fn get_svg_image_data(label: &str) -> Option<SvgImageData> {
     let label = label.strip_prefix('\\').unwrap_or(label);
     Some(match label {
@@ -227,13 +237,13 @@ pub fn get_svg_image_data(label: &str) -> Option<SvgImageData> {
             view_box_height: 522.0,
             align: None,
         },
-        "overgroup" => SvgImageData {
+        "overgroup" | "overparen" => SvgImageData {
             paths: &["leftgroup", "rightgroup"],
             min_width: 0.888,
             view_box_height: 342.0,
             align: None,
         },
-        "undergroup" => SvgImageData {
+        "undergroup" | "underparen" => SvgImageData {
             paths: &["leftgroupunder", "rightgroupunder"],
             min_width: 0.888,
             view_box_height: 342.0,
@@ -273,6 +283,45 @@ pub fn get_svg_image_data(label: &str) -> Option<SvgImageData> {
     })
 }
 
+/// A stretchy element drawn as a single path stretched to a given width, for renderers that
+/// can't slice svgs like the HTML output does
+#[derive(Debug, Clone)]
+pub struct StretchedPath {
+    /// SVG path data, in thousandths of an em
+    pub path: String,
+    /// Width in ems, which may be larger than requested for very narrow elements
+    pub width: f64,
+    /// Height in ems
+    pub height: f64,
+}
+
+/// Get the path for a stretchy brace or group of the given width (in ems)
+pub fn stretched_path(label: &str, width: f64) -> Option<StretchedPath> {
+    let label = label.strip_prefix('\\').unwrap_or(label);
+    let (path, min_width, height) = match label {
+        "overbrace" | "underbrace" => (
+            svg_geometry::brace_path(width * 1000.0, label == "overbrace"),
+            svg_geometry::BRACE_MIN_WIDTH,
+            0.548,
+        ),
+        "overgroup" | "undergroup" | "overparen" | "underparen" => (
+            svg_geometry::group_path(
+                width * 1000.0,
+                matches!(label, "overgroup" | "overparen"),
+            ),
+            svg_geometry::GROUP_MIN_WIDTH,
+            0.342,
+        ),
+        _ => return None,
+    };
+
+    Some(StretchedPath {
+        path,
+        width: width.max(min_width / 1000.0),
+        height,
+    })
+}
+
 /// Create a MathML node for a stretchy element
 #[cfg(feature = "mathml")]
 pub fn mathml_node(label: &str) -> MathNode<MathmlNode> {
@@ -1,4 +1,8 @@
+use crate::prelude::*;
+use crate::util::round;
+
 // TODO: use enums?
+#[cfg(feature = "html")]
 pub(crate) const PATH: &'static [(&'static str, &'static str)] = &[
     // The doubleleftarrow geometry is from glyph U+21D0 in the font KaTeX Main
     (
@@ -419,6 +423,7 @@ M500 241 v40 H399408 v-40z M500 435 v40 H400000 v-40z"#,
     ),
 ];
 
+#[cfg(feature = "html")]
 fn sqrt_main(extra_viniculum: f64, h_line_pad: f64) -> String {
     format!(
         "M95,{0}c-2.7,0,-7.17,-2.7,-13.5,-8c-5.8,-5.3,-9.5,-10,-9.5,-14c0,-2,0.3,-3.3,1,-4c1.3,-2.7,23.83,-20.7,67.5,-54c44.2,-33.3,65.8,-50.3,66.5,-51c1.3,-1.3,3,-2,5,-2c4.7,0,8.7,3.3,12,10s173,378,173,378c0.7,0,35.3,-71,104,-213c68.7,-142,137.5,-285,206.5,-429c69,-144,104.5,-217.7,106.5,-221l{1} -{2}c5.3,-9.3,12,-14,20,-14H400000v{3}H845.2724s-225.272,467,-225.272,467s-235,486,-235,486c-2.7,4.7,-9,7,-19,7c-6,0,-10,-1,-12,-3s-194,-422,-194,-422s-65,47,-65,47zM{4} {5}h400000v{3}h-400000z",
@@ -431,6 +436,7 @@ fn sqrt_main(extra_viniculum: f64, h_line_pad: f64) -> String {
     )
 }
 
+#[cfg(feature = "html")]
 fn sqrt_size1(extra_viniculum: f64, h_line_pad: f64) -> String {
     format!(
         "M263,{0}c0.7,0,18,39.7,52,119c34,79.3,68.167,158.7,102.5,238c34.3,79.3,51.8,119.3,52.5,120c340,-704.7,510.7,-1060.3,512,-1067l{1} -{2}c4.7,-7.3,11,-11,19,-11H40000v{3}H1012.3s-271.3,567,-271.3,567c-38.7,80.7,-84,175,-136,283c-52,108,-89.167,185.3,-111.5,232c-22.3,46.7,-33.8,70.3,-34.5,71c-4.7,4.7,-12.3,7,-23,7s-12,-1,-12,-1s-109,-253,-109,-253c-72.7,-168,-109.3,-252,-110,-252c-10.7,8,-22,16.7,-34,26c-22,17.3,-33.3,26,-34,26s-26,-26,-26,-26s76,-59,76,-59s76,-60,76,-60zM{4} {5}h400000v{3}h-400000z",
@@ -443,6 +449,7 @@ fn sqrt_size1(extra_viniculum: f64, h_line_pad: f64) -> String {
     )
 }
 
+#[cfg(feature = "html")]
 fn sqrt_size2(extra_viniculum: f64, h_line_pad: f64) -> String {
     format!(
         "M983 {0}l{1} -{2}c4,-6.7,10,-10,18,-10 H400000v{3}H1013.1s-83.4,268,-264.1,840c-180.7,572,-277,876.3,-289,913c-4.7,4.7,-12.7,7,-24,7s-12,0,-12,0c-1.3,-3.3,-3.7,-11.7,-7,-25c-35.3,-125.3,-106.7,-373.3,-214,-744c-10,12,-21,25,-33,39s-32,39,-32,39c-6,-5.3,-15,-14,-27,-26s25,-30,25,-30c26.7,-32.7,52,-63,76,-91s52,-60,52,-60s208,722,208,722c56,-175.3,126.3,-397.3,211,-666c84.7,-268.7,153.8,-488.2,207.5,-658.5c53.7,-170.3,84.5,-266.8,92.5,-289.5zM{4} {5}h400000v{3}h-400000z",
@@ -455,6 +462,7 @@ fn sqrt_size2(extra_viniculum: f64, h_line_pad: f64) -> String {
     )
 }
 
+#[cfg(feature = "html")]
 fn sqrt_size3(extra_viniculum: f64, h_line_pad: f64) -> String {
     format!(
         "M424,{0}c-1.3,-0.7,-38.5,-172,-111.5,-514c-73,-342,-109.8,-513.3,-110.5,-514c0,-2,-10.7,14.3,-32,49c-4.7,7.3,-9.8,15.7,-15.5,25c-5.7,9.3,-9.8,16,-12.5,20s-5,7,-5,7c-4,-3.3,-8.3,-7.7,-13,-13s-13,-13,-13,-13s76,-122,76,-122s77,-121,77,-121s209,968,209,968c0,-2,84.7,-361.7,254,-1079c169.3,-717.3,254.7,-1077.7,256,-1081l{1} -{2}c4,-6.7,10,-10,18,-10 H400000v{3}H1014.6s-87.3,378.7,-272.6,1166c-185.3,787.3,-279.3,1182.3,-282,1185c-2,6,-10,9,-24,9c-8,0,-12,-0.7,-12,-2z M{4} {5}h400000v{3}h-400000z",
@@ -467,6 +475,7 @@ fn sqrt_size3(extra_viniculum: f64, h_line_pad: f64) -> String {
     )
 }
 
+#[cfg(feature = "html")]
 fn sqrt_size4(extra_viniculum: f64, h_line_pad: f64) -> String {
     format!(
         "M473,{0}c339.3,-1799.3,509.3,-2700,510,-2702 l{1} -{2}c3.3,-7.3,9.3,-11,18,-11 H400000v{3}H1017.7s-90.5,478,-276.2,1466c-185.7,988,-279.5,1483,-281.5,1485c-2,6,-10,9,-24,9c-8,0,-12,-0.7,-12,-2c0,-1.3,-5.3,-32,-16,-92c-50.7,-293.3,-119.7,-693.3,-207,-1200c0,-1.3,-5.3,8.7,-16,30c-10.7,21.3,-21.3,42.7,-32,64s-16,33,-16,33s-26,-26,-26,-26s76,-153,76,-153s77,-151,77,-151c0.7,0.7,35.7,202,105,604c67.3,400.7,102,602.7,104,606zM{4} {5}h400000v{3}H1017.7z",
@@ -479,6 +488,7 @@ fn sqrt_size4(extra_viniculum: f64, h_line_pad: f64) -> String {
     )
 }
 
+#[cfg(feature = "html")]
 fn sqrt_tall(extra_viniculum: f64, h_line_pad: f64, view_box_height: f64) -> String {
     let vert_segment = view_box_height - 54.0 - h_line_pad - extra_viniculum;
     format!(
@@ -490,6 +500,7 @@ fn sqrt_tall(extra_viniculum: f64, h_line_pad: f64, view_box_height: f64) -> Str
     )
 }
 
+#[cfg(feature = "html")]
 pub(crate) fn sqrt_path(
     size: &str,
     mut extra_viniculum: f64,
@@ -509,6 +520,7 @@ pub(crate) fn sqrt_path(
     }
 }
 
+#[cfg(feature = "html")]
 pub(crate) fn inner_path(name: char, height: u64) -> String {
     match name {
         '\u{239c}' => format!("M291 0 H417 V{0} H291z M291 0 H417 V{0} H291z", height),
@@ -528,4 +540,73 @@ pub(crate) fn inner_path(name: char, height: u64) -> String {
         ),
         _ => String::new(),
     }
-}
\ No newline at end of file
+}
+/// The narrowest brace that [`brace_path`] can draw without its pieces overlapping
+pub(crate) const BRACE_MIN_WIDTH: f64 = 1964.0;
+/// The narrowest group that [`group_path`] can draw without its pieces overlapping
+pub(crate) const GROUP_MIN_WIDTH: f64 = 870.0;
+
+/// A brace stretched to `width`, in the same units as the `leftbrace`, `midbrace` and
+/// `rightbrace` paths it is assembled from. Rather than slicing those paths with several svgs,
+/// their horizontal bars are cut to length so that the brace fits in a single path.
+pub(crate) fn brace_path(width: f64, is_over: bool) -> String {
+    let w = round(width.max(BRACE_MIN_WIDTH));
+    let c = round(w / 2.0);
+    if is_over {
+        format!(
+            "M6 548l-6-6v-35l6-11c56-104 135.3-181.3 238-232 57.3-28.7 117-45 179-50h{0}v120H403c-43.3 7-81 15-113 26-100.7 33-179.7 91-237 174-2.7 5-6 9-10 13-.7 1-7.3 1-20 1H6z\
+M{1} 334c-100.7-8.3-195.3-44-280-108-55.3-42-101.7-93-139-153l-9-14c-2.7 4-5.7 8.7-9 14-53.3 86.7-123.7 153-211 199-66.7 36-137.3 56.3-212 62V214c178.3-11.7 311.7-78.3 403-201 6-8 9.7-12 11-12 .7-.7 6.7-1 18-1s17.3.3 18 1c1.3 0 5 4 11 12 44.7 59.3 101.3 106.3 170 141s145.3 54.3 229 60v120z\
+M{2} 542l-6 6h-17c-12.7 0-19.3-.3-20-1-4-4-7.3-8.3-10-13-35.3-51.3-80.8-93.8-136.5-127.5s-117.2-55.8-184.5-66.5c-.7 0-2-.3-4-1-18.7-2.7-76-4.3-172-5H{1}V214h{3}l6 1c124.7 8 235 61.7 331 161 31.3 33.3 59.7 72.7 85 118l7 13v35z",
+            c - 855.0,
+            c + 428.0,
+            w,
+            w - c - 857.0,
+        )
+    } else {
+        format!(
+            "M0 6l6-6h17c12.688 0 19.313.3 20 1 4 4 7.313 8.3 10 13 35.313 51.3 80.813 93.8 136.5 127.5 55.688 33.7 117.188 55.8 184.5 66.5.688 0 2 .3 4 1 18.688 2.7 76 4.3 172 5h{0}v120H429l-6-1c-124.688-8-235-61.7-331-161C60.687 138.7 32.312 99.3 7 54L0 41V6z\
+M{1} 214c100.7 8.3 195.3 44 280 108 55.3 42 101.7 93 139 153l9 14c2.7-4 5.7-8.7 9-14 53.3-86.7 123.7-153 211-199 66.7-36 137.3-56.3 212-62v120c-178.3 11.7-311.7 78.3-403 201-6 8-9.7 12-11 12-.7.7-6.7 1-18 1s-17.3-.3-18-1c-1.3 0-5-4-11-12-44.7-59.3-101.3-106.3-170-141s-145.3-54.3-229-60z\
+M{2} 0l6 6v35l-6 11c-56 104-135.3 181.3-238 232-57.3 28.7-117 45-179 50H{3}V214h{4}c43.3-7 81-15 113-26 100.7-33 179.7-91 237-174 2.7-5 6-9 10-13 .7-1 7.3-1 20-1h17z",
+            c - 978.0,
+            c - 428.0,
+            w - 6.0,
+            c + 432.0,
+            w - c - 835.0,
+        )
+    }
+}
+
+/// A group (a paren lying on its side) stretched to `width`, assembled from the `leftgroup` and
+/// `rightgroup` paths like [`brace_path`].
+pub(crate) fn group_path(width: f64, is_over: bool) -> String {
+    let w = round(width.max(GROUP_MIN_WIDTH));
+    let c = round(w / 2.0);
+    if is_over {
+        format!(
+            "M{0} 80H435C64 80 168.3 229.4 21 260c-5.9 1.2-18 0-18 0-2 0-3-1-3-3v-38C76 61 257 0 435 0h{1}z\
+M{0} 80h{2}c371 0 266.7 149.4 414 180 5.9 1.2 18 0 18 0 2 0 3-1 3-3v-38c-76-158-257-219-435-219H{0}z",
+            c,
+            c - 435.0,
+            w - c - 435.0,
+        )
+    } else {
+        format!(
+            "M{0} 262H435C64 262 168.3 112.6 21 82c-5.9-1.2-18 0-18 0-2 0-3 1-3 3v38c76 158 257 219 435 219h{1}z\
+M{0} 262h{2}c371 0 266.7-149.4 414-180 5.9-1.2 18 0 18 0 2 0 3 1 3 3v38c-76 158-257 219-435 219H{0}z",
+            c,
+            c - 435.0,
+            w - c - 435.0,
+        )
+    }
+}
+
+/// A square bracket with the given rule `thickness` whose legs are `height` tall, all in
+/// thousandths of an em.
+pub(crate) fn bracket_path(width: f64, thickness: f64, height: f64, is_over: bool) -> String {
+    let (w, t, h) = (round(width), round(thickness), round(height));
+    if is_over {
+        format!("M0 {2}V0H{0}V{2}H{3}V{1}H{1}V{2}z", w, t, h, w - t)
+    } else {
+        format!("M0 0V{2}H{0}V0H{3}V{4}H{1}V0z", w, t, h, w - t, h - t)
+    }
+}
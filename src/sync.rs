@@ -0,0 +1,70 @@
+//! The lazy statics and locks used by the crate, which come from `std` when it is available and
+//! from `spin` when it isn't.
+
+#[cfg(not(target_has_atomic = "64"))]
+use core::sync::atomic::Ordering;
+
+#[cfg(feature = "std")]
+pub(crate) use once_cell::sync::Lazy;
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::Lazy;
+
+#[cfg(target_has_atomic = "64")]
+pub(crate) use core::sync::atomic::AtomicU64;
+
+/// A reader-writer lock. Unlike [`std::sync::RwLock`], a panic while it is held doesn't poison
+/// it, since everything the crate keeps behind one is valid after any partial update.
+#[derive(Debug, Default)]
+pub(crate) struct RwLock<T> {
+    #[cfg(feature = "std")]
+    inner: std::sync::RwLock<T>,
+    #[cfg(not(feature = "std"))]
+    inner: spin::RwLock<T>,
+}
+#[cfg(feature = "std")]
+impl<T> RwLock<T> {
+    pub(crate) fn read(&self) -> std::sync::RwLockReadGuard<'_, T> {
+        self.inner
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(crate) fn write(&self) -> std::sync::RwLockWriteGuard<'_, T> {
+        self.inner
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+#[cfg(not(feature = "std"))]
+impl<T> RwLock<T> {
+    pub(crate) fn read(&self) -> spin::RwLockReadGuard<'_, T> {
+        self.inner.read()
+    }
+
+    pub(crate) fn write(&self) -> spin::RwLockWriteGuard<'_, T> {
+        self.inner.write()
+    }
+}
+
+/// A stand in for `AtomicU64` on targets that only have smaller atomics, like
+/// `thumbv7em-none-eabi`
+#[cfg(not(target_has_atomic = "64"))]
+#[derive(Debug)]
+pub(crate) struct AtomicU64(spin::RwLock<u64>);
+#[cfg(not(target_has_atomic = "64"))]
+impl AtomicU64 {
+    pub(crate) const fn new(value: u64) -> AtomicU64 {
+        AtomicU64(spin::RwLock::new(value))
+    }
+
+    pub(crate) fn load(&self, _order: Ordering) -> u64 {
+        *self.0.read()
+    }
+
+    pub(crate) fn fetch_add(&self, value: u64, _order: Ordering) -> u64 {
+        let mut current = self.0.write();
+        let previous = *current;
+        *current = previous.wrapping_add(value);
+        previous
+    }
+}
@@ -1,58 +1,120 @@
-use std::{borrow::Cow, collections::HashMap};
+use alloc::collections::BTreeMap;
+use core::fmt;
 
+use crate::prelude::*;
+
+#[cfg(any(feature = "html", feature = "mathml"))]
+use crate::{dom_tree::Span, parse_node::ParseNode, util, Options, ParserConfig};
+
+#[cfg(feature = "html")]
 use crate::{
-    dom_tree::{HtmlDomNode, HtmlNode, Span, WithHtmlDomNode},
+    dom_tree::{HtmlDomNode, HtmlNode, WithHtmlDomNode},
     html::build_html,
-    mathml_tree::MathmlNode,
-    parse_node::ParseNode,
-    util, Options, ParserConfig,
 };
 
+#[cfg(feature = "mathml")]
+use crate::mathml_tree::MathmlNode;
+
 // TODO: Vec of enum for common kinds?
 pub type ClassList = Vec<String>;
 
 // TODO: We could do better by having keys be Cow<'static, str>?
 // Though I think you need a crate for a nicely behaving map type for that
-pub type Attributes = HashMap<String, String>;
+/// The attributes of an element. These are ordered so that the same tree always renders to the
+/// same markup.
+pub type Attributes = BTreeMap<String, String>;
 
-/// Returns the value that should go in `class="{}"`
-pub(crate) fn class_attr(classes: &ClassList) -> Option<String> {
+/// Writes ` class="{}"` with the classes, if there are any
+#[cfg(any(feature = "html", feature = "mathml"))]
+pub(crate) fn write_class_attr(out: &mut dyn fmt::Write, classes: &ClassList) -> fmt::Result {
     if classes.is_empty() {
-        None
-    } else {
-        // TODO: use intersperse instead
-        Some(
-            classes
-                .iter()
-                .filter(|c| !c.is_empty())
-                .map(|class| util::escape(class.as_str()))
-                .collect::<Vec<Cow<'_, str>>>()
-                .join(" "),
-        )
+        return Ok(());
+    }
+
+    out.write_str(" class=\"")?;
+    for (i, class) in classes.iter().filter(|c| !c.is_empty()).enumerate() {
+        if i != 0 {
+            out.write_char(' ')?;
+        }
+        util::write_escaped(out, class)?;
     }
+    out.write_char('"')
 }
 
 pub trait VirtualNode {
     // TODO: We somehow need to support translating into HTML nodes
     // We could use websys?
     // fn into_node(self) -> Node;
-    fn to_markup(&self) -> String;
+
+    /// Write the markup of the node into `out`, children included, so that a whole tree is
+    /// written into one buffer rather than concatenating the markup of each node.
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result;
+
+    fn to_markup(&self) -> String {
+        let mut markup = String::new();
+        // Writing to a `String` can't fail
+        self.write_markup(&mut markup).unwrap();
+        markup
+    }
+
+    /// Get the markup of the node, or `None` if it would be longer than `max_len` bytes.  
+    /// Writing stops as soon as the limit is passed, so the whole markup is never held.
+    fn to_markup_within(&self, max_len: Option<usize>) -> Option<String> {
+        let mut markup = String::new();
+        self.markup_into(&mut markup, max_len).then_some(markup)
+    }
+
+    /// Replace the contents of `markup` with the markup of the node, as
+    /// [`to_markup_within`](VirtualNode::to_markup_within) does, so that one buffer can be used
+    /// for the markup of many nodes.  
+    /// Returns `false` if it would be longer than `max_len` bytes, leaving part of it in `markup`.
+    fn markup_into(&self, markup: &mut String, max_len: Option<usize>) -> bool {
+        markup.clear();
+        let Some(max_len) = max_len else {
+            // Writing to a `String` can't fail
+            self.write_markup(markup).unwrap();
+            return true;
+        };
+
+        let mut out = LimitedWriter { markup, max_len };
+        self.write_markup(&mut out).is_ok()
+    }
+}
+
+/// A `String` writer that fails once the markup would be longer than `max_len`
+struct LimitedWriter<'a> {
+    markup: &'a mut String,
+    max_len: usize,
+}
+impl fmt::Write for LimitedWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.markup.len() + s.len() > self.max_len {
+            return Err(fmt::Error);
+        }
+
+        self.markup.push_str(s);
+        Ok(())
+    }
 }
+
 impl<T: VirtualNode + ?Sized> VirtualNode for Box<T> {
-    fn to_markup(&self) -> String {
-        (**self).to_markup()
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        (**self).write_markup(out)
     }
 }
 
+#[cfg(feature = "html")]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct EmptyNode {
     node: HtmlDomNode,
 }
+#[cfg(feature = "html")]
 impl VirtualNode for EmptyNode {
-    fn to_markup(&self) -> String {
-        String::new()
+    fn write_markup(&self, _out: &mut dyn fmt::Write) -> fmt::Result {
+        Ok(())
     }
 }
+#[cfg(feature = "html")]
 impl WithHtmlDomNode for EmptyNode {
     fn node(&self) -> &HtmlDomNode {
         &self.node
@@ -87,19 +149,32 @@ where
     }
 }
 
+/// Describe the formula on the root node, if requested
+#[cfg(feature = "html")]
+fn add_aria_label<T: VirtualNode>(node: &mut Span<T>, tree: &[ParseNode], conf: &ParserConfig) {
+    use crate::a11y::to_speech_with;
+
+    if let Some(style) = conf.aria_label {
+        let speech = to_speech_with(tree, style);
+        node.attributes.insert("role".to_string(), "img".to_string());
+        node.attributes.insert("aria-label".to_string(), speech);
+    }
+}
+
 #[cfg(feature = "html")]
-pub(crate) fn build_html_tree(tree: &[ParseNode], conf: ParserConfig) -> Span<HtmlNode> {
+pub fn build_html_tree(tree: &[ParseNode], conf: ParserConfig) -> Span<HtmlNode> {
     use crate::{build_common::make_span, dom_tree::CssStyle};
 
     let options = Options::from_parser_conf(&conf);
 
     let html_node = build_html(tree, &options);
-    let katex_node = make_span(
+    let mut katex_node = make_span(
         vec!["katex".to_string()],
         vec![html_node],
         None,
         CssStyle::default(),
     );
+    add_aria_label(&mut katex_node, tree, &conf);
 
     display_wrap(katex_node, conf)
 }
@@ -117,6 +192,15 @@ pub(crate) fn build_mathml_tree(
     build_mathml(tree, expr, &options, conf.display_mode, true)
 }
 
+#[cfg(feature = "mathml")]
+pub(crate) fn build_mathml_core_tree(tree: &[ParseNode], conf: ParserConfig) -> Span<MathmlNode> {
+    use crate::mathml_core::build_mathml_core;
+
+    let options = Options::from_parser_conf(&conf);
+
+    build_mathml_core(tree, &options, conf.display_mode, true)
+}
+
 /// Multi-purpose node that can be either HTML or MathML
 #[cfg(any(feature = "html", feature = "mathml"))]
 pub enum MlNode {
@@ -128,13 +212,13 @@ pub enum MlNode {
 }
 #[cfg(any(feature = "html", feature = "mathml"))]
 impl VirtualNode for MlNode {
-    fn to_markup(&self) -> String {
+    fn write_markup(&self, out: &mut dyn fmt::Write) -> fmt::Result {
         match self {
             #[cfg(feature = "html")]
-            MlNode::Html(node) => node.to_markup(),
+            MlNode::Html(node) => node.write_markup(out),
             #[cfg(feature = "mathml")]
-            MlNode::Mathml(node) => node.to_markup(),
-            MlNode::Span(node) => node.to_markup(),
+            MlNode::Mathml(node) => node.write_markup(out),
+            MlNode::Span(node) => node.write_markup(out),
         }
     }
 }
@@ -151,12 +235,15 @@ impl From<MathmlNode> for MlNode {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OutputType {
     #[cfg(feature = "html")]
     Html,
     #[cfg(feature = "mathml")]
     Mathml,
+    /// MathML restricted to MathML Core, see [`mathml_core`](crate::mathml_core)
+    #[cfg(feature = "mathml")]
+    MathmlCore,
     #[cfg(all(feature = "html", feature = "mathml"))]
     HtmlAndMathml,
 }
@@ -177,18 +264,24 @@ pub(crate) fn build_tree(
         #[cfg(feature = "html")]
         OutputType::Html => {
             let html = build_html(tree, &options);
-            let node = build_common::make_span(
+            let mut node = build_common::make_span(
                 vec!["katex".to_string()],
                 vec![html],
                 None,
                 CssStyle::default(),
             );
+            add_aria_label(&mut node, tree, &conf);
             display_wrap(node, conf).map(MlNode::from)
         }
         #[cfg(feature = "mathml")]
         OutputType::Mathml => {
             build_mathml(tree, expr, &options, conf.display_mode, true).map(MlNode::from)
         }
+        #[cfg(feature = "mathml")]
+        OutputType::MathmlCore => {
+            crate::mathml_core::build_mathml_core(tree, &options, conf.display_mode, true)
+                .map(MlNode::from)
+        }
         #[cfg(all(feature = "html", feature = "mathml"))]
         OutputType::HtmlAndMathml => {
             let html = build_html(tree, &options).map(MlNode::from);
@@ -208,8 +301,21 @@ pub(crate) fn build_tree(
             node.node.height = height;
             node.node.depth = depth;
             node.node.max_font_size = max_font_size;
+            add_aria_label(&mut node, tree, &conf);
+
+            if conf.display_mode {
+                let mut classes = vec!["katex-display".to_string()];
+                if conf.leq_no {
+                    classes.push("leqno".to_string());
+                }
+                if conf.fleqn {
+                    classes.push("fleqn".to_string());
+                }
 
-            node
+                Span::new(classes, vec![MlNode::Span(node)], None, CssStyle::default())
+            } else {
+                node
+            }
         }
     }
 }
@@ -0,0 +1,1319 @@
+//! Writing parse trees as [Typst](https://typst.app) math, for moving formulas over to Typst
+//! documents.
+//!
+//! [`to_typst`] writes the body of a Typst equation, which goes between the `$`s:
+//! ```
+//! # use aliter::{typst::to_typst, parse_tree, parser::ParserConfig};
+//! let tree = parse_tree(r"\sum_{i=0}^n \frac{\alpha_i}{\sqrt{2}}", ParserConfig::default()).unwrap();
+//! let output = to_typst(&tree);
+//! assert_eq!(output.typst, "sum_(i = 0)^n frac(alpha_i, sqrt(2))");
+//! assert!(output.diagnostics.is_empty());
+//! ```
+//!
+//! Letters are separated by spaces, since Typst reads `ab` as a single name, and symbols that
+//! have a short Typst name like `alpha` or `->` are written as that name. The rest are written
+//! as their Unicode characters, which Typst accepts directly.
+//!
+//! Some constructs have no equivalent in Typst, like `\mathchoice` or the numbering of an
+//! `{align}` environment. These are written as the closest thing Typst has, or left out, and
+//! listed in the [`diagnostics`](TypstOutput::diagnostics) of the output.
+
+use crate::prelude::*;
+use crate::{
+    array::{AlignSpec, ColSeparationType},
+    expander::Mode,
+    functions::horiz_brace::has_label,
+    omml::color_hex,
+    parse_node::{
+        ArrayNode, ArrayTag, Color, EncloseNode, GenFracNode, HorizBraceNode, LeftRightNode,
+        OpNode, OperatorNameNode, ParseNode, SupSubNode, TextNode,
+    },
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    symbols::Atom,
+    unicode_text::{delim_text, symbol_text, to_unicode_text},
+    unit::Measurement,
+    util::{round, SourceLocation, Style, StyleAuto},
+    SIZE_MULTIPLIERS,
+};
+
+/// A construct that Typst has no equivalent for
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypstDiagnostic {
+    /// The LaTeX command or environment, like `\mathchoice`
+    pub construct: String,
+    /// What was done with it instead
+    pub message: String,
+    pub loc: Option<SourceLocation>,
+}
+
+/// The Typst source of a formula, along with what couldn't be converted
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypstOutput {
+    pub typst: String,
+    pub diagnostics: Vec<TypstDiagnostic>,
+}
+
+/// Write the parse tree as Typst math
+pub fn to_typst(nodes: &[ParseNode]) -> TypstOutput {
+    let mut writer = TypstWriter::default();
+    writer.write_list(nodes);
+
+    TypstOutput {
+        typst: escape_brackets(&writer.out, false),
+        diagnostics: writer.diagnostics,
+    }
+}
+
+/// Parse the expression and write it as Typst math
+pub fn render_to_typst(expr: &str, conf: ParserConfig) -> Result<TypstOutput, ParseError> {
+    let tree = parse_tree(expr, conf)?;
+    Ok(to_typst(&tree))
+}
+
+/// The Typst names of the symbols that read better as a name than as their character
+fn symbol_name(c: char) -> Option<&'static str> {
+    Some(match c {
+        'α' => "alpha",
+        'β' => "beta",
+        'γ' => "gamma",
+        'δ' => "delta",
+        'ε' => "epsilon",
+        'ϵ' => "epsilon.alt",
+        'ζ' => "zeta",
+        'η' => "eta",
+        'θ' => "theta",
+        'ϑ' => "theta.alt",
+        'ι' => "iota",
+        'κ' => "kappa",
+        'ϰ' => "kappa.alt",
+        'λ' => "lambda",
+        'μ' => "mu",
+        'ν' => "nu",
+        'ξ' => "xi",
+        'ο' => "omicron",
+        'π' => "pi",
+        'ϖ' => "pi.alt",
+        'ρ' => "rho",
+        'ϱ' => "rho.alt",
+        'σ' => "sigma",
+        'ς' => "sigma.alt",
+        'τ' => "tau",
+        'υ' => "upsilon",
+        'φ' => "phi",
+        'ϕ' => "phi.alt",
+        'χ' => "chi",
+        'ψ' => "psi",
+        'ω' => "omega",
+        'Γ' => "Gamma",
+        'Δ' => "Delta",
+        'Θ' => "Theta",
+        'Λ' => "Lambda",
+        'Ξ' => "Xi",
+        'Π' => "Pi",
+        'Σ' => "Sigma",
+        'Υ' => "Upsilon",
+        'Φ' => "Phi",
+        'Ψ' => "Psi",
+        'Ω' => "Omega",
+        '−' => "-",
+        '→' => "->",
+        '←' => "<-",
+        '↔' => "<->",
+        '⇒' => "=>",
+        '⟹' => "==>",
+        '↦' => "|->",
+        '≤' => "<=",
+        '≥' => ">=",
+        '≠' => "!=",
+        '≪' => "<<",
+        '≫' => ">>",
+        '∞' => "infinity",
+        '×' => "times",
+        '⋅' => "dot.op",
+        '±' => "plus.minus",
+        '∓' => "minus.plus",
+        '∈' => "in",
+        '∉' => "in.not",
+        '∀' => "forall",
+        '∃' => "exists",
+        '∇' => "nabla",
+        '≈' => "approx",
+        '≡' => "equiv",
+        '⊂' => "subset",
+        '⊆' => "subset.eq",
+        '…' => "dots.h",
+        '⋯' => "dots.c",
+        '⋮' => "dots.v",
+        '∑' => "sum",
+        '∏' => "product",
+        '∫' => "integral",
+        '∮' => "integral.cont",
+        '∬' => "integral.double",
+        '∭' => "integral.triple",
+        _ => return None,
+    })
+}
+
+/// The operators that Typst has a name for, like `sin`
+const OPERATORS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "ctg", "deg", "det",
+    "dim", "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max",
+    "min", "Pr", "sec", "sin", "sinh", "sup", "tan", "tanh", "tg",
+];
+
+/// The Typst function that an accent command is written as
+fn accent_function(label: &str) -> Option<&'static str> {
+    Some(match label {
+        "\\hat" | "\\widehat" => "hat",
+        "\\tilde" | "\\widetilde" => "tilde",
+        "\\bar" => "macron",
+        "\\acute" => "acute",
+        "\\grave" => "grave",
+        "\\dot" => "dot",
+        "\\ddot" => "dot.double",
+        "\\dddot" => "dot.triple",
+        "\\ddddot" => "dot.quad",
+        "\\breve" => "breve",
+        "\\check" | "\\widecheck" => "caron",
+        "\\mathring" => "circle",
+        "\\vec" | "\\overrightarrow" => "arrow",
+        "\\overleftarrow" => "arrow.l",
+        "\\overleftrightarrow" => "arrow.l.r",
+        "\\overrightharpoon" => "harpoon",
+        "\\overleftharpoon" => "harpoon.lt",
+        _ => return None,
+    })
+}
+
+/// The Typst functions that a font command is written as, from the outside in
+fn font_functions(font: &str) -> &'static [&'static str] {
+    match font.trim_start_matches('\\') {
+        "mathbb" => &["bb"],
+        "mathcal" | "mathscr" => &["cal"],
+        "mathfrak" => &["frak"],
+        "mathsf" => &["sans"],
+        "mathtt" => &["mono"],
+        // `bold` alone keeps letters italic
+        "mathbf" => &["bold", "upright"],
+        "boldsymbol" | "bm" => &["bold"],
+        "mathrm" => &["upright"],
+        "mathit" => &["italic"],
+        _ => &[],
+    }
+}
+
+/// A Typst color for a LaTeX color
+fn color_value(color: &Color) -> Option<String> {
+    color_hex(color).map(|hex| format!("rgb(\"#{}\")", hex))
+}
+
+/// Write a length in the Typst unit closest to its unit
+fn length(dimension: &Measurement, scale: f64) -> String {
+    let (num, unit) = match dimension {
+        Measurement::Em(em) => (em.0, "em"),
+        Measurement::Ex(ex) => (ex.0 * 0.431, "em"),
+        Measurement::Mu(mu) => (mu.0 / 18.0, "em"),
+        Measurement::Mm(mm) => (mm.0, "mm"),
+        Measurement::Cm(cm) => (cm.0, "cm"),
+        Measurement::In(inch) => (inch.0, "in"),
+        dimension => (
+            dimension.num() * dimension.unit_pt_size().unwrap_or(1.0),
+            "pt",
+        ),
+    };
+    format!("{}{}", round(num * scale * 10000.0) / 10000.0, unit)
+}
+
+fn em_size(dimension: &Measurement) -> f64 {
+    match dimension {
+        Measurement::Mu(mu) => mu.0 / 18.0,
+        Measurement::Em(em) => em.0,
+        Measurement::Ex(ex) => ex.0 * 0.431,
+        dimension => dimension.num() * dimension.unit_pt_size().unwrap_or(1.0) / 10.0,
+    }
+}
+
+/// Escape the characters that have a meaning in Typst math
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\/_^&$#\"@".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape the brackets that aren't matched, since they would end the argument they are in, and
+/// if `commas` then the commas and semicolons that would separate the arguments of a function
+fn escape_brackets(text: &str, commas: bool) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut escape = vec![false; chars.len()];
+
+    // Find the unmatched brackets first, since the commas after an unmatched opening bracket
+    // aren't inside of it
+    let mut open = Vec::new();
+    for_each_unescaped(&chars, |i, c| match c {
+        '(' | '[' | '{' => open.push(i),
+        ')' | ']' | '}' => match open.last() {
+            Some(&start) if closing(chars[start]) == c => {
+                open.pop();
+            }
+            _ => escape[i] = true,
+        },
+        _ => {}
+    });
+    for start in open {
+        escape[start] = true;
+    }
+
+    if commas {
+        let mut depth = 0usize;
+        for_each_unescaped(&chars, |i, c| match c {
+            _ if escape[i] => {}
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' | ';' if depth == 0 => escape[i] = true,
+            _ => {}
+        });
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    for (c, escape) in chars.into_iter().zip(escape) {
+        if escape {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Call `f` with the characters that aren't escaped or in a string
+fn for_each_unescaped(chars: &[char], mut f: impl FnMut(usize, char)) {
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '"' => in_string = !in_string,
+            c if !in_string => f(i, c),
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Whether the node is `\not`, which is the combining long solidus in MathML
+fn is_not(node: &ParseNode) -> bool {
+    match node {
+        ParseNode::HtmlMathml(html_mathml) => {
+            matches!(html_mathml.mathml.as_slice(), [node] if node.text() == Some("\u{338}"))
+        }
+        _ => false,
+    }
+}
+
+/// The negated form of a relation, like `≠` for `=`
+fn negate(text: &str) -> Option<char> {
+    Some(match text {
+        "=" => '≠',
+        "∈" => '∉',
+        "∋" => '∌',
+        "<" => '≮',
+        ">" => '≯',
+        "≤" => '≰',
+        "≥" => '≱',
+        "⊂" => '⊄',
+        "⊃" => '⊅',
+        "⊆" => '⊈',
+        "⊇" => '⊉',
+        "≡" => '≢',
+        "∼" => '≁',
+        "≃" => '≄',
+        "≈" => '≉',
+        "∃" => '∄',
+        _ => return None,
+    })
+}
+
+/// Whether the text is a single symbol, number or name, so that it doesn't need parentheses as
+/// a script
+fn is_atom(text: &str) -> bool {
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        return !text[1..text.len() - 1].replace("\\\"", "").contains('"');
+    }
+
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => false,
+        (Some(c), None) => !"\\()[]{},;".contains(c),
+        _ => {
+            text.chars().all(|c| c.is_ascii_digit() || c == '.')
+                || text.chars().all(|c| c.is_ascii_alphabetic() || c == '.')
+        }
+    }
+}
+
+/// Whether scripts can be attached directly after the text, because it is a single thing like
+/// a function call or a string
+fn is_primary(text: &str) -> bool {
+    if is_atom(text) {
+        return true;
+    }
+
+    let text = text.trim_end_matches('\'');
+    // A function call, where the parenthesis after the name is closed at the end
+    let Some(start) = text.find('(') else {
+        return false;
+    };
+    let name = &text[..start];
+    if !name.chars().all(|c| c.is_ascii_alphabetic() || c == '.') || !text.ends_with(')') {
+        return false;
+    }
+    let chars = text[start..].chars().collect::<Vec<_>>();
+    let mut depth = 0usize;
+    let mut closed_at = None;
+    for_each_unescaped(&chars, |i, c| match c {
+        '(' | '[' | '{' => depth += 1,
+        ')' | ']' | '}' => {
+            depth = depth.saturating_sub(1);
+            if depth == 0 && closed_at.is_none() {
+                closed_at = Some(i);
+            }
+        }
+        _ => {}
+    });
+    closed_at == Some(chars.len() - 1)
+}
+
+/// Whether writing `next` directly after `prev` would run them together into something else
+fn needs_space(prev: &str, next: &str) -> bool {
+    let (Some(last), Some(first)) = (prev.chars().next_back(), next.chars().next()) else {
+        return false;
+    };
+
+    // Letters would become a single name, but digits make up a number
+    if last.is_alphanumeric() && first.is_alphanumeric() {
+        return !(last.is_ascii_digit() && first.is_ascii_digit());
+    }
+    // Keep calls, strings and code apart from what comes after them, to make them easier to read
+    if (last.is_alphanumeric() || matches!(last, ')' | ']' | '"'))
+        && (first.is_alphanumeric() || matches!(first, '#' | '"'))
+    {
+        return true;
+    }
+
+    // A name followed by a parenthesis is a function call, and by a dot a modifier like the
+    // one of `arrow.l`
+    let name_length = prev
+        .chars()
+        .rev()
+        .take_while(char::is_ascii_alphabetic)
+        .count();
+    if name_length > 1 && matches!(first, '(' | '[' | '.') {
+        return true;
+    }
+    if matches!(last, ')' | ']') && matches!(first, '(' | '[' | '.') {
+        return true;
+    }
+
+    // Shorthands like `->` and `<=`
+    const SHORTHAND: &str = "<>=-!|:~*+[]";
+    SHORTHAND.contains(last) && SHORTHAND.contains(first)
+}
+
+#[derive(Default)]
+struct TypstWriter {
+    out: String,
+    diagnostics: Vec<TypstDiagnostic>,
+    /// Whether the last thing written was a binary operator, relation or punctuation, which is
+    /// followed by a space
+    space_after: bool,
+}
+impl TypstWriter {
+    fn push(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        if core::mem::take(&mut self.space_after) {
+            if !text.starts_with([')', ']', '}']) {
+                self.out.push(' ');
+            }
+        } else if needs_space(&self.out, text) {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+    }
+
+    /// Write a binary operator or relation with spaces around it. A binary operator at the
+    /// start of a list or after another operator is unary, like the `-` of `-1`.
+    fn push_operator(&mut self, text: &str, is_bin: bool) {
+        let is_start = self.out.is_empty() || self.out.ends_with(['(', '[', '{', ' ']);
+        if is_bin && (self.space_after || is_start) {
+            self.push(text);
+            return;
+        }
+
+        if !is_start && !self.space_after {
+            self.out.push(' ');
+        }
+        self.push(text);
+        self.space_after = true;
+    }
+
+    /// Write a symbol, using its name if it has one
+    fn push_symbol(&mut self, text: &str) {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => match symbol_name(c) {
+                Some(name) => self.push(name),
+                None => self.push(&escape(text)),
+            },
+            _ => self.push(&escape(text)),
+        }
+    }
+
+    fn push_call(&mut self, name: &str, args: &[String]) {
+        let args = args
+            .iter()
+            .map(|arg| match arg.as_str() {
+                "" => "\"\"".to_string(),
+                arg => escape_brackets(arg, true),
+            })
+            .collect::<Vec<_>>();
+        self.push(&format!("{}({})", name, args.join(", ")));
+    }
+
+    /// Write a Typst function that takes markup, with the math inside of it
+    fn push_markup_call(&mut self, call: &str, body: &str) {
+        self.push(&format!("#{}[${}$]", call, escape_brackets(body, false)));
+    }
+
+    fn unsupported(&mut self, node: &ParseNode, construct: &str, message: &str) {
+        self.diagnostics.push(TypstDiagnostic {
+            construct: construct.to_string(),
+            message: message.to_string(),
+            loc: node.loc(),
+        });
+    }
+
+    /// Write into a separate string, sharing the diagnostics
+    fn render(&mut self, f: impl FnOnce(&mut Self)) -> String {
+        let out = core::mem::take(&mut self.out);
+        let space_after = core::mem::take(&mut self.space_after);
+        f(self);
+        self.space_after = space_after;
+        core::mem::replace(&mut self.out, out)
+    }
+
+    fn render_list(&mut self, nodes: &[ParseNode]) -> String {
+        self.render(|w| w.write_list(nodes))
+    }
+
+    fn render_node(&mut self, node: &ParseNode) -> String {
+        self.render(|w| w.write_node(node))
+    }
+
+    /// Render an array cell, without the style that the environment puts around it
+    fn render_cell(&mut self, cell: &ParseNode) -> String {
+        match cell {
+            ParseNode::Styling(styling) => self.render_list(&styling.body),
+            cell => self.render_node(cell),
+        }
+    }
+
+    fn write_list(&mut self, nodes: &[ParseNode]) {
+        let mut i = 0;
+        while i < nodes.len() {
+            // `\not` is a combining slash over the relation after it, which Typst needs as one
+            // character
+            if let (true, Some(next)) = (is_not(&nodes[i]), nodes.get(i + 1)) {
+                let text = next.text().unwrap_or_default();
+                if let Some(negated) = negate(symbol_text(text, next.info().mode)) {
+                    let negated = self.render(|w| w.push_symbol(&negated.to_string()));
+                    self.push_operator(&negated, false);
+                    i += 2;
+                    continue;
+                }
+            }
+
+            self.write_node(&nodes[i]);
+            i += 1;
+        }
+    }
+
+    fn write_node(&mut self, node: &ParseNode) {
+        match node {
+            ParseNode::Spacing(spacing) => match spacing.text.as_str() {
+                "~" | "\\nobreakspace" => self.push("space.nobreak"),
+                _ => self.push("space"),
+            },
+            ParseNode::Atom(atom) => {
+                let text = symbol_text(&atom.text, atom.info.mode);
+                match atom.family {
+                    Atom::Bin | Atom::Rel => {
+                        let text = self.render(|w| w.push_symbol(text));
+                        self.push_operator(&text, atom.family == Atom::Bin);
+                    }
+                    Atom::Punct => {
+                        self.push_symbol(text);
+                        self.space_after = true;
+                    }
+                    _ => self.push_symbol(text),
+                }
+            }
+            ParseNode::MathOrd(_)
+            | ParseNode::TextOrd(_)
+            | ParseNode::AccentToken(_)
+            | ParseNode::OpToken(_) => {
+                let text = node.text().unwrap_or_default();
+                let symbol = symbol_text(text, node.info().mode);
+                if symbol.is_empty() && text.starts_with('\\') {
+                    self.unsupported(node, text, "left out, as it has no character");
+                }
+                self.push_symbol(symbol);
+            }
+            ParseNode::OrdGroup(group) => self.write_list(&group.body),
+            ParseNode::SupSub(sup_sub) => self.write_sup_sub(sup_sub),
+            ParseNode::Array(array) => self.write_array(array, None),
+            ParseNode::LeftRight(left_right) => self.write_left_right(left_right),
+            ParseNode::LeftRightRight(right) => self.push(&escape(delim_text(&right.delim))),
+            ParseNode::Middle(middle) => {
+                let delim = escape(delim_text(&middle.delim));
+                self.push(&format!("mid({})", delim));
+            }
+            ParseNode::DelimSizing(delim) => self.push(&escape(delim_text(&delim.delim))),
+            ParseNode::Color(color) => {
+                let body = self.render_list(&color.body);
+                match color_value(&color.color) {
+                    Some(value) => self.push_markup_call(&format!("text(fill: {})", value), &body),
+                    None => {
+                        self.unsupported(node, "\\color", "color left out, as it is unknown");
+                        self.push(&body);
+                    }
+                }
+            }
+            ParseNode::Op(op) => self.write_op(op),
+            ParseNode::OperatorName(op) => self.write_operator_name(op),
+            ParseNode::Styling(styling) => {
+                let function = match styling.style {
+                    Style::Display => "display",
+                    Style::Text => "inline",
+                    Style::Script => "script",
+                    Style::ScriptScript => "sscript",
+                };
+                let body = self.render_list(&styling.body);
+                self.push_call(function, &[body]);
+            }
+            ParseNode::Sizing(sizing) => {
+                let body = self.render_list(&sizing.body);
+                let size = SIZE_MULTIPLIERS[sizing.size.clamp(1, 11) - 1];
+                self.push_markup_call(&format!("text(size: {}em)", size), &body);
+            }
+            ParseNode::Text(text) => self.write_text(text),
+            ParseNode::Font(font) => {
+                let mut body = self.render_node(&font.body);
+                for function in font_functions(&font.font).iter().rev() {
+                    body = self.render(|w| w.push_call(function, &[body]));
+                }
+                self.push(&body);
+            }
+            ParseNode::MClass(mclass) => {
+                // Like the `\mathrel{\not=}` of `\neq`, which is already a relation
+                let family = match mclass.m_class.as_str() {
+                    "mbin" => Some(Atom::Bin),
+                    "mrel" => Some(Atom::Rel),
+                    _ => None,
+                };
+                let is_same_class = mclass.body.iter().all(|node| match node {
+                    ParseNode::Atom(atom) => Some(atom.family) == family,
+                    node => is_not(node),
+                });
+                if is_same_class {
+                    return self.write_list(&mclass.body);
+                }
+                if let (Some(family), true) = (family, mclass.is_character_box) {
+                    let body = self.render_list(&mclass.body);
+                    return self.push_operator(&body, family == Atom::Bin);
+                }
+
+                let body = self.render_list(&mclass.body);
+                let class = match mclass.m_class.as_str() {
+                    "mbin" => "binary",
+                    "mrel" => "relation",
+                    "mopen" => "opening",
+                    "mclose" => "closing",
+                    "mpunct" => "punctuation",
+                    _ => return self.push(&body),
+                };
+                self.push_call("class", &[format!("\"{}\"", class), body]);
+            }
+            ParseNode::GenFrac(frac) => self.write_genfrac(frac),
+            ParseNode::Accent(accent) => {
+                let base = self.render_node(&accent.base);
+                match accent_function(&accent.label) {
+                    Some(function) => self.push_call(function, &[base]),
+                    None => {
+                        self.unsupported(node, &accent.label, "left out the accent");
+                        self.push(&base);
+                    }
+                }
+            }
+            ParseNode::AccentUnder(accent) => {
+                self.unsupported(node, &accent.label, "left out the accent");
+                self.write_node(&accent.base);
+            }
+            ParseNode::HorizBrace(brace) => self.write_horiz_brace(brace, None),
+            ParseNode::XArrow(arrow) => {
+                let name = arrow.label.replacen("\\x", "\\", 1);
+                let arrow_char = match name.as_str() {
+                    "\\longequal" => "=",
+                    "\\tofrom" => "⇄",
+                    name => match symbol_text(name, Mode::Math) {
+                        "" => "→",
+                        symbol => symbol,
+                    },
+                };
+                let arrow_text = self.render(|w| w.push_symbol(arrow_char));
+                let sup = match arrow.body.as_ref() {
+                    ParseNode::OrdGroup(group) if group.body.is_empty() => None,
+                    body => Some(self.render_node(body)),
+                };
+                let sub = arrow.below.as_ref().map(|below| self.render_node(below));
+                let base = self.render(|w| w.push_call("stretch", &[arrow_text]));
+                self.push_scripts(base, sup, sub);
+            }
+            ParseNode::Sqrt(sqrt) => {
+                let body = self.render_node(&sqrt.body);
+                match &sqrt.index {
+                    Some(index) => {
+                        let index = self.render_node(index);
+                        self.push_call("root", &[index, body]);
+                    }
+                    None => self.push_call("sqrt", &[body]),
+                }
+            }
+            ParseNode::Enclose(enclose) => self.write_enclose(node, enclose),
+            ParseNode::Overline(overline) => {
+                let body = self.render_node(&overline.body);
+                self.push_call("overline", &[body]);
+            }
+            ParseNode::Underline(underline) => {
+                let body = self.render_node(&underline.body);
+                self.push_call("underline", &[body]);
+            }
+            ParseNode::Phantom(phantom) => {
+                let body = self.render_list(&phantom.body);
+                self.push_markup_call("hide", &body);
+            }
+            ParseNode::HPhantom(phantom) => {
+                let body = self.render_node(&phantom.body);
+                self.push_markup_call("hide", &body);
+            }
+            ParseNode::VPhantom(phantom) => {
+                let body = self.render_node(&phantom.body);
+                self.push(&format!(
+                    "#box(width: 0pt)[#hide[${}$]]",
+                    escape_brackets(&body, false)
+                ));
+            }
+            ParseNode::Smash(smash) => {
+                self.unsupported(node, "\\smash", "written without smashing");
+                self.write_node(&smash.body);
+            }
+            ParseNode::Lap(lap) => {
+                let body = self.render_node(&lap.body);
+                if lap.alignment == "rlap" {
+                    self.push_markup_call("box(width: 0pt)", &body);
+                } else {
+                    let construct = format!("\\math{}", lap.alignment);
+                    self.unsupported(node, &construct, "written with its width");
+                    self.push(&body);
+                }
+            }
+            ParseNode::RaiseBox(raise) => {
+                let body = self.render_node(&raise.body);
+                let call = format!("move(dy: {})", length(&raise.dy, -1.0));
+                self.push_markup_call(&call, &body);
+            }
+            ParseNode::VCenter(vcenter) => self.write_node(&vcenter.body),
+            ParseNode::HBox(hbox) => self.write_list(&hbox.body),
+            ParseNode::Href(href) => {
+                let body = self.render_list(&href.body);
+                let call = format!("link(\"{}\")", escape_string(&href.href));
+                self.push_markup_call(&call, &body);
+            }
+            ParseNode::Html(html) => {
+                self.unsupported(node, "\\html", "left out the HTML attributes");
+                self.write_list(&html.body);
+            }
+            ParseNode::HtmlMathml(html_mathml) => self.write_list(&html_mathml.mathml),
+            ParseNode::MathChoice(choice) => {
+                self.unsupported(node, "\\mathchoice", "written as the text style choice");
+                self.write_list(&choice.text);
+            }
+            ParseNode::Kern(kern) => {
+                let em = em_size(&kern.dimension);
+                let space = [
+                    (3.0 / 18.0, "thin"),
+                    (4.0 / 18.0, "med"),
+                    (5.0 / 18.0, "thick"),
+                    (1.0, "quad"),
+                    (2.0, "wide"),
+                ]
+                .into_iter()
+                .find(|(size, _)| (em - size).abs() < 0.001)
+                .map(|(_, name)| name.to_string());
+                let space =
+                    space.unwrap_or_else(|| format!("#h({})", length(&kern.dimension, 1.0)));
+                self.push(&space);
+            }
+            ParseNode::IncludeGraphics(graphics) => {
+                let mut args = format!(
+                    "\"{}\", alt: \"{}\"",
+                    escape_string(&graphics.src),
+                    escape_string(&graphics.alt)
+                );
+                if graphics.height.num() > 0.0 {
+                    args.push_str(&format!(", height: {}", length(&graphics.height, 1.0)));
+                }
+                self.push(&format!("#image({})", args));
+            }
+            ParseNode::Verb(verb) => self.push(&format!("#raw(\"{}\")", escape_string(&verb.body))),
+            ParseNode::Url(url) => self.push(&format!("#link(\"{}\")", escape_string(&url.url))),
+            ParseNode::Raw(raw) => self.push(&format!("\"{}\"", escape_string(&raw.string))),
+            ParseNode::Rule(rule) => {
+                let mut args = format!(
+                    "width: {}, height: {}, fill: black",
+                    length(&rule.width, 1.0),
+                    length(&rule.height, 1.0)
+                );
+                if let Some(shift) = rule.shift.as_ref().filter(|shift| shift.num() != 0.0) {
+                    args.push_str(&format!(", baseline: {}", length(shift, -1.0)));
+                }
+                self.push(&format!("#box({})", args));
+            }
+            ParseNode::Tag(tag) => {
+                self.unsupported(
+                    node,
+                    "\\tag",
+                    "left out, as Typst numbers equations with `math.equation`",
+                );
+                self.write_list(&tag.body);
+            }
+            ParseNode::Cr(_) => {
+                self.push("\\");
+                self.space_after = true;
+            }
+            ParseNode::CdLabel(label) => self.write_node(&label.label),
+            ParseNode::CdLabelParentNode(parent) => self.write_node(&parent.fragment),
+            ParseNode::ColorToken(_)
+            | ParseNode::Size(_)
+            | ParseNode::Environment(_)
+            | ParseNode::Infix(_)
+            | ParseNode::Internal(_) => {}
+        }
+    }
+
+    /// Write text mode content as a string, and the math inside of it as math
+    fn write_text(&mut self, text: &TextNode) {
+        let font = text.font.as_deref().unwrap_or_default();
+        let mut rest = text.body.as_slice();
+        while let Some(first) = rest.first() {
+            match first {
+                ParseNode::Text(text) => {
+                    self.write_text(text);
+                    rest = &rest[1..];
+                }
+                // Math inside of the text, like `\text{for $x > 0$}`
+                ParseNode::Styling(styling) => {
+                    self.write_list(&styling.body);
+                    rest = &rest[1..];
+                }
+                node if node.info().mode == Mode::Math => {
+                    self.write_node(node);
+                    rest = &rest[1..];
+                }
+                _ => {
+                    let end = rest
+                        .iter()
+                        .position(|node| {
+                            matches!(node, ParseNode::Text(_) | ParseNode::Styling(_))
+                                || node.info().mode == Mode::Math
+                        })
+                        .unwrap_or(rest.len());
+                    let string = format!("\"{}\"", escape_string(&to_unicode_text(&rest[..end])));
+                    let string = match font.trim_start_matches('\\') {
+                        "textbf" => format!("#text(weight: \"bold\", {})", string),
+                        "textit" | "emph" => format!("#text(style: \"italic\", {})", string),
+                        "texttt" => format!("#raw({})", string),
+                        "textsf" => format!("sans({})", string),
+                        _ => string,
+                    };
+                    self.push(&string);
+                    rest = &rest[end..];
+                }
+            }
+        }
+    }
+
+    /// Write the base followed by its scripts
+    fn push_scripts(&mut self, base: String, sup: Option<String>, sub: Option<String>) {
+        let base = match base.as_str() {
+            "" => "\"\"".to_string(),
+            _ => base,
+        };
+        let sup = sup.filter(|sup| !sup.is_empty());
+        let sub = sub.filter(|sub| !sub.is_empty());
+
+        if !is_primary(&base) {
+            let mut args = vec![escape_brackets(&base, true)];
+            if let Some(sub) = sub {
+                args.push(format!("b: {}", escape_brackets(&sub, true)));
+            }
+            if let Some(sup) = sup {
+                args.push(format!("t: {}", escape_brackets(&sup, true)));
+            }
+            if args.len() > 1 {
+                self.push(&format!("attach({})", args.join(", ")));
+            } else {
+                self.push(&base);
+            }
+            return;
+        }
+
+        // Primes are written directly after the base, as `x'`
+        let (primes, sup) = match sup {
+            Some(sup) => {
+                let primes = sup.chars().take_while(|&c| c == '′').count();
+                let rest = sup.trim_start_matches('′').trim_start().to_string();
+                (primes, Some(rest).filter(|rest| !rest.is_empty()))
+            }
+            None => (0, None),
+        };
+        let script = |text: String| {
+            if is_atom(&text) {
+                text
+            } else {
+                format!("({})", escape_brackets(&text, false))
+            }
+        };
+
+        let mut text = format!("{}{}", base, "'".repeat(primes));
+        if let Some(sub) = sub {
+            text.push('_');
+            text.push_str(&script(sub));
+        }
+        if let Some(sup) = sup {
+            text.push('^');
+            text.push_str(&script(sup));
+        }
+        self.push(&text);
+    }
+
+    fn write_sup_sub(&mut self, sup_sub: &SupSubNode) {
+        let base = sup_sub.base.as_deref();
+        let sup = sup_sub.sup.as_deref();
+        let sub = sup_sub.sub.as_deref();
+
+        // A script on the open side of a horizontal brace is its label
+        if let Some(ParseNode::HorizBrace(brace)) = base {
+            if has_label(brace, sup.is_some(), sub.is_some()) {
+                self.write_horiz_brace(brace, sup.or(sub));
+                return;
+            }
+        }
+
+        let base = base.map_or_else(String::new, |base| self.render_node(base));
+        let sup = sup.map(|sup| self.render_node(sup));
+        let sub = sub.map(|sub| self.render_node(sub));
+        self.push_scripts(base, sup, sub);
+    }
+
+    /// Wrap an operator in `limits` or `scripts` if `\limits` or `\nolimits` was used on it
+    fn with_limits(&mut self, op: String, limits: bool, explicit: bool) {
+        if explicit {
+            let function = if limits { "limits" } else { "scripts" };
+            self.push_call(function, &[op]);
+        } else {
+            self.push(&op);
+        }
+    }
+
+    fn write_op(&mut self, op: &OpNode) {
+        let explicit = op.always_handle_sup_sub == Some(true);
+        match (&op.name, &op.body) {
+            (Some(name), _) if op.symbol => {
+                let symbol = symbol_text(name, Mode::Math);
+                let symbol = self.render(|w| w.push_symbol(symbol));
+                self.with_limits(symbol, op.limits, explicit);
+            }
+            (Some(name), _) => {
+                let name = name.trim_start_matches('\\');
+                let op_text = if OPERATORS.contains(&name) {
+                    name.to_string()
+                } else {
+                    format!("op(\"{}\")", name)
+                };
+                self.with_limits(op_text, op.limits, explicit);
+            }
+            // Operators made from a body, like `\mathop` and `\overset`
+            (None, Some(body)) => {
+                let body = self.render_list(body);
+                self.with_limits(body, op.limits, op.limits);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn write_operator_name(&mut self, op: &OperatorNameNode) {
+        let is_text = op.body.iter().all(|node| {
+            matches!(
+                node,
+                ParseNode::MathOrd(_)
+                    | ParseNode::TextOrd(_)
+                    | ParseNode::Atom(_)
+                    | ParseNode::Kern(_)
+                    | ParseNode::Spacing(_)
+            )
+        });
+        let name = if is_text {
+            format!("\"{}\"", escape_string(&to_unicode_text(&op.body)))
+        } else {
+            self.render_list(&op.body)
+        };
+
+        let mut args = vec![name];
+        if op.always_handle_sup_sub {
+            args.push("limits: #true".to_string());
+        }
+        self.push(&format!("op({})", args.join(", ")));
+    }
+
+    fn write_genfrac(&mut self, frac: &GenFracNode) {
+        let numer = self.render_node(&frac.numer);
+        let denom = self.render_node(&frac.denom);
+        let left = frac.left_delim.as_deref().unwrap_or(".");
+        let right = frac.right_delim.as_deref().unwrap_or(".");
+
+        let text = self.render(|w| match (frac.has_bar_line, left, right) {
+            (true, ".", ".") => w.push_call("frac", &[numer, denom]),
+            (true, left, right) => {
+                let fraction = w.render(|w| w.push_call("frac", &[numer, denom]));
+                w.push_lr(left, &fraction, right);
+            }
+            (false, "(", ")") => w.push_call("binom", &[numer, denom]),
+            // A stack without a line, like `\atop`
+            (false, left, right) => {
+                let stack =
+                    w.render(|w| w.push_matrix("#none", &[], vec![vec![numer], vec![denom]]));
+                w.push_lr(left, &stack, right);
+            }
+        });
+
+        match frac.size {
+            StyleAuto::Style(Style::Display) => self.push_call("display", &[text]),
+            StyleAuto::Style(Style::Text) => self.push_call("inline", &[text]),
+            _ => self.push(&text),
+        }
+    }
+
+    /// Write the body between the delimiters, which stretch to fit it
+    fn push_lr(&mut self, left: &str, body: &str, right: &str) {
+        let left = delim_text(left);
+        let right = delim_text(right);
+        if left.is_empty() && right.is_empty() {
+            self.push(body);
+            return;
+        }
+
+        // Brackets that aren't a pair would end the call early
+        let is_pair = matches!((left, right), ("(", ")") | ("[", "]") | ("{", "}"));
+        let delim = |delim: &str| {
+            if is_pair {
+                delim.to_string()
+            } else {
+                escape_brackets(&escape(delim), false)
+            }
+        };
+        let parts = [delim(left), escape_brackets(body, false), delim(right)];
+        let inner = parts
+            .iter()
+            .filter(|part| !part.is_empty())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.push(&format!("lr({})", inner));
+    }
+
+    fn write_left_right(&mut self, left_right: &LeftRightNode) {
+        if let [ParseNode::Array(array)] = left_right.body.as_slice() {
+            if self.write_delimited_array(array, &left_right.left, &left_right.right) {
+                return;
+            }
+        }
+
+        let body = self.render_list(&left_right.body);
+        self.push_lr(&left_right.left, &body, &left_right.right);
+    }
+
+    /// Write an array between `\left` and `\right`, like a `{pmatrix}` or `{cases}`, as one
+    /// Typst function. Returns false if it isn't a matrix.
+    fn write_delimited_array(&mut self, array: &ArrayNode, left: &str, right: &str) -> bool {
+        if !matches!(
+            array.col_separation_type,
+            None | Some(ColSeparationType::Small)
+        ) || array.is_cd == Some(true)
+        {
+            return false;
+        }
+
+        let is_cases = array.cols.as_ref().is_some_and(|cols| {
+            cols.iter().all(|col| match col {
+                AlignSpec::Align { align, .. } => align == "l",
+                AlignSpec::Separator(_) => false,
+            })
+        });
+        match (left, right) {
+            ("\\{", ".") if is_cases => self.write_cases(array, false),
+            (".", "\\}") if is_cases => self.write_cases(array, true),
+            ("(", ")") => self.write_array(array, Some("\"(\"")),
+            ("[", "]") => self.write_array(array, Some("\"[\"")),
+            ("\\{", "\\}") => self.write_array(array, Some("\"{\"")),
+            ("|", "|") | ("\\vert", "\\vert") => self.write_array(array, Some("\"|\"")),
+            ("\\|", "\\|") | ("\\Vert", "\\Vert") => self.write_array(array, Some("\"||\"")),
+            (left, right) => {
+                let matrix = self.render(|w| w.write_array(array, None));
+                self.push_lr(left, &matrix, right);
+            }
+        }
+        true
+    }
+
+    fn write_cases(&mut self, array: &ArrayNode, reverse: bool) {
+        let mut args = Vec::new();
+        if reverse {
+            args.push("reverse: #true".to_string());
+        }
+        for row in &array.body {
+            let cells = row
+                .iter()
+                .map(|cell| escape_brackets(&self.render_cell(cell), true))
+                .collect::<Vec<_>>();
+            args.push(cells.join(" & "));
+        }
+        self.push(&format!("cases({})", args.join(", ")));
+    }
+
+    /// Write the array as a matrix with the given `delim`, or as the lines of an equation if it
+    /// is aligned
+    fn write_array(&mut self, array: &ArrayNode, delim: Option<&str>) {
+        if array.is_cd == Some(true) {
+            self.unsupported_array("{CD}", "written as a matrix");
+        }
+        if let Some(tags) = &array.tags {
+            let is_numbered = tags.iter().any(|tag| match tag {
+                ArrayTag::Boolean(numbered) => *numbered,
+                ArrayTag::Tag(_) => true,
+            });
+            if is_numbered {
+                self.unsupported_array(
+                    "equation numbers",
+                    "left out, as Typst numbers equations with `math.equation`",
+                );
+            }
+        }
+
+        let rows = array
+            .body
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| self.render_cell(cell))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let is_aligned = matches!(
+            array.col_separation_type,
+            Some(ColSeparationType::Align | ColSeparationType::AlignAt | ColSeparationType::Gather)
+        );
+        if is_aligned && delim.is_none() {
+            // The columns of `aligned` alternate between right and left aligned, like the
+            // alignment points of Typst
+            let lines = rows
+                .into_iter()
+                .map(|row| escape_brackets(&row.join(" &"), false).trim().to_string())
+                .collect::<Vec<_>>();
+            self.push(&lines.join(" \\ "));
+            return;
+        }
+
+        let mut args = Vec::new();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if let Some(cols) = &array.cols {
+            let aligns = cols
+                .iter()
+                .filter_map(|col| match col {
+                    AlignSpec::Align { align, .. } => Some(align.as_ref()),
+                    AlignSpec::Separator(_) => None,
+                })
+                .collect::<Vec<_>>();
+            match aligns.first() {
+                Some(&first) if aligns.iter().all(|&align| align == first) => match first {
+                    "l" => args.push("align: #left".to_string()),
+                    "r" => args.push("align: #right".to_string()),
+                    _ => {}
+                },
+                Some(_) => self.unsupported_array(
+                    "column alignment",
+                    "centered the columns, as a matrix has one alignment",
+                ),
+                None => {}
+            }
+
+            // The number of columns before each line
+            let mut vlines = Vec::new();
+            let mut column = 0;
+            for col in cols {
+                match col {
+                    AlignSpec::Align { .. } => column += 1,
+                    AlignSpec::Separator(_) if column == 0 || column >= columns => {
+                        self.unsupported_array("outer vertical line", "left out");
+                    }
+                    AlignSpec::Separator(separator) => {
+                        if separator.as_ref() == ":" {
+                            self.unsupported_array("dashed line", "written as a solid line");
+                        }
+                        vlines.push(column);
+                    }
+                }
+            }
+
+            let mut hlines = Vec::new();
+            for (row, lines) in array.h_lines_before_row.iter().enumerate() {
+                if lines.is_empty() {
+                    continue;
+                }
+                if row == 0 || row >= rows.len() {
+                    self.unsupported_array("outer horizontal line", "left out");
+                    continue;
+                }
+                if lines.iter().any(|&dashed| dashed) {
+                    self.unsupported_array("dashed line", "written as a solid line");
+                }
+                hlines.push(row);
+            }
+
+            let lines = |lines: Vec<usize>| match lines.as_slice() {
+                [line] => line.to_string(),
+                lines => format!(
+                    "({})",
+                    lines
+                        .iter()
+                        .map(usize::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            let mut augment = Vec::new();
+            if !hlines.is_empty() {
+                augment.push(format!("hline: {}", lines(hlines)));
+            }
+            if !vlines.is_empty() {
+                augment.push(format!("vline: {}", lines(vlines)));
+            }
+            if !augment.is_empty() {
+                args.push(format!("augment: #({})", augment.join(", ")));
+            }
+        }
+
+        self.push_matrix(delim.unwrap_or("#none"), &args, rows);
+    }
+
+    fn push_matrix(&mut self, delim: &str, args: &[String], rows: Vec<Vec<String>>) {
+        let mut all_args = Vec::new();
+        // Parentheses are the default
+        if delim != "\"(\"" {
+            all_args.push(format!("delim: {}", delim));
+        }
+        all_args.extend(args.iter().cloned());
+
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| match cell.as_str() {
+                        "" => "\"\"".to_string(),
+                        cell => escape_brackets(cell, true),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        if !rows.is_empty() {
+            all_args.push(rows);
+        }
+        self.push(&format!("mat({})", all_args.join(", ")));
+    }
+
+    fn unsupported_array(&mut self, construct: &str, message: &str) {
+        self.diagnostics.push(TypstDiagnostic {
+            construct: construct.to_string(),
+            message: message.to_string(),
+            loc: None,
+        });
+    }
+
+    fn write_horiz_brace(&mut self, brace: &HorizBraceNode, label: Option<&ParseNode>) {
+        let function = brace.label.trim_start_matches('\\');
+        let base = self.render_node(&brace.base);
+        let mut args = vec![base];
+        if let Some(label) = label {
+            args.push(self.render_node(label));
+        }
+        self.push_call(function, &args);
+    }
+
+    fn write_enclose(&mut self, node: &ParseNode, enclose: &EncloseNode) {
+        let body = self.render_node(&enclose.body);
+        match enclose.label.as_str() {
+            "\\cancel" => self.push_call("cancel", &[body]),
+            "\\bcancel" => self.push_call("cancel", &[body, "inverted: #true".to_string()]),
+            "\\xcancel" => self.push_call("cancel", &[body, "cross: #true".to_string()]),
+            "\\sout" => self.push_call("cancel", &[body, "angle: #90deg".to_string()]),
+            "\\boxed" | "\\fbox" => self.push_markup_call("box(stroke: 0.4pt, inset: 3pt)", &body),
+            "\\colorbox" | "\\fcolorbox" => {
+                let mut args = Vec::new();
+                if let Some(color) = enclose.background_color.as_ref().and_then(color_value) {
+                    args.push(format!("fill: {}", color));
+                }
+                if let Some(color) = enclose.border_color.as_ref().and_then(color_value) {
+                    args.push(format!("stroke: 0.4pt + {}", color));
+                }
+                args.push("inset: 3pt".to_string());
+                self.push_markup_call(&format!("box({})", args.join(", ")), &body);
+            }
+            label => {
+                self.unsupported(node, label, "left out the enclosure");
+                self.push(&body);
+            }
+        }
+    }
+}
@@ -1,10 +1,9 @@
-use std::collections::HashMap;
-
-use once_cell::sync::Lazy;
-use regex::Regex;
+use hashbrown::HashMap;
 use unicode_normalization::UnicodeNormalization;
 
+use crate::prelude::*;
 use crate::expander::Mode;
+use crate::sync::Lazy;
 
 pub struct Accent {
     pub name: char,
@@ -101,9 +100,12 @@ pub static SYMBOLS: Lazy<HashMap<char, String>> = Lazy::new(|| {
     res
 });
 
-// TODO: We don't need a regex for this!
-pub static SUB_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new("^[₊₋₌₍₎₀₁₂₃₄₅₆₇₈₉ₐₑₕᵢⱼₖₗₘₙₒₚᵣₛₜᵤᵥₓᵦᵧᵨᵩᵪ]").unwrap());
+const SUB_CHARS: &str = "₊₋₌₍₎₀₁₂₃₄₅₆₇₈₉ₐₑₕᵢⱼₖₗₘₙₒₚᵣₛₜᵤᵥₓᵦᵧᵨᵩᵪ";
+
+/// Whether the text starts with a Unicode subscript character
+pub fn starts_with_sub(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| SUB_CHARS.contains(c))
+}
 
 /// Maps between (Sub, Sup) characters
 pub(crate) const SUBS_AND_SUPS: &'static [(char, char)] = &[
@@ -228,3 +230,12 @@ pub(crate) fn find_sub_map(sub: char) -> Option<char> {
         .find(|(l, _)| *l == sub)
         .map(|(_, r)| *r)
 }
+
+/// Find the subscript or superscript character for a character, the reverse of
+/// [`find_sub_map`]
+pub(crate) fn find_script_char(c: char, is_sub: bool) -> Option<char> {
+    SUBS_AND_SUPS
+        .iter()
+        .find(|(l, r)| *r == c && SUB_CHARS.contains(*l) == is_sub)
+        .map(|(l, _)| *l)
+}
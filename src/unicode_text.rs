@@ -0,0 +1,793 @@
+//! Linearizing parse trees into Unicode text, for search indexing, alt text and terminals.
+//!
+//! [`to_unicode_text`] writes a plain text approximation of the formula, using the Unicode
+//! characters that the symbols stand for, real superscript and subscript characters where
+//! they exist, and the Mathematical Alphanumeric Symbols for fonts like `\mathbb`:
+//! ```
+//! # use aliter::{unicode_text::to_unicode_text, parse_tree, parser::ParserConfig};
+//! let tree = parse_tree(r"\frac{a+b}{c^2} + \sqrt{x} \in \mathbb{R}", ParserConfig::default()).unwrap();
+//! assert_eq!(to_unicode_text(&tree), "(a+b)/c²+√x∈ℝ");
+//! ```
+//!
+//! [`UnicodeTextFormat::UnicodeMath`] instead writes the linear format that Word uses, which
+//! can be built back up into a formula, such as `■(a&b@c&d)` for a matrix and `√(3&x)` for a
+//! cube root.
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::prelude::*;
+use crate::{
+    array::ColSeparationType,
+    expander::Mode,
+    lexer::is_combining_mark,
+    parse_node::{ArrayNode, GenFracNode, ParseNode, SupSubNode},
+    symbols::SYMBOLS,
+    unicode::{find_script_char, ACCENTS},
+    unit::Measurement,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnicodeTextFormat {
+    /// Plain text, like `(a+b)/c²`
+    #[default]
+    Plain,
+    /// The UnicodeMath linear format, like `(a+b)/c^2`
+    UnicodeMath,
+}
+
+/// Write the parse tree as plain Unicode text
+pub fn to_unicode_text(nodes: &[ParseNode]) -> String {
+    to_unicode_text_with(nodes, UnicodeTextFormat::Plain)
+}
+
+/// Write the parse tree as Unicode text in the given format
+pub fn to_unicode_text_with(nodes: &[ParseNode], format: UnicodeTextFormat) -> String {
+    let mut writer = TextWriter::new(format, None);
+    writer.write_list(nodes);
+    // Compose the accents and negations with their bases, like `=` and U+0338 into `≠`
+    writer.finish().nfc().collect()
+}
+
+/// A style from the Mathematical Alphanumeric Symbols block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Some styles are only reached from a MathML `mathvariant`
+#[cfg_attr(not(feature = "mathml"), allow(dead_code))]
+pub(crate) enum MathVariant {
+    Bold,
+    Italic,
+    BoldItalic,
+    Script,
+    BoldScript,
+    Fraktur,
+    BoldFraktur,
+    DoubleStruck,
+    SansSerif,
+    BoldSansSerif,
+    SansSerifItalic,
+    SansSerifBoldItalic,
+    Monospace,
+}
+impl MathVariant {
+    /// The variant of a MathML `mathvariant` value, other than `normal`
+    #[cfg(feature = "mathml")]
+    pub(crate) fn from_mathvariant(name: &str) -> Option<MathVariant> {
+        Some(match name {
+            "bold" => MathVariant::Bold,
+            "italic" => MathVariant::Italic,
+            "bold-italic" => MathVariant::BoldItalic,
+            "script" => MathVariant::Script,
+            "bold-script" => MathVariant::BoldScript,
+            "fraktur" => MathVariant::Fraktur,
+            "bold-fraktur" => MathVariant::BoldFraktur,
+            "double-struck" => MathVariant::DoubleStruck,
+            "sans-serif" => MathVariant::SansSerif,
+            "bold-sans-serif" => MathVariant::BoldSansSerif,
+            "sans-serif-italic" => MathVariant::SansSerifItalic,
+            "sans-serif-bold-italic" => MathVariant::SansSerifBoldItalic,
+            "monospace" => MathVariant::Monospace,
+            _ => return None,
+        })
+    }
+
+    fn from_font(font: &str) -> Option<MathVariant> {
+        Some(match font {
+            "mathbf" | "textbf" | "bfseries" => MathVariant::Bold,
+            "mathit" | "textit" | "itshape" | "emph" | "em" => MathVariant::Italic,
+            "boldsymbol" => MathVariant::BoldItalic,
+            "mathcal" | "mathscr" => MathVariant::Script,
+            "mathfrak" => MathVariant::Fraktur,
+            "mathbb" => MathVariant::DoubleStruck,
+            "mathsf" | "textsf" | "sffamily" => MathVariant::SansSerif,
+            "mathtt" | "texttt" | "ttfamily" => MathVariant::Monospace,
+            _ => return None,
+        })
+    }
+
+    /// The code points of `A`, `a` and `0` in the variant, if it has them
+    fn starts(self) -> (u32, u32, Option<u32>) {
+        match self {
+            MathVariant::Bold => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+            MathVariant::Italic => (0x1D434, 0x1D44E, None),
+            MathVariant::BoldItalic => (0x1D468, 0x1D482, Some(0x1D7CE)),
+            MathVariant::Script => (0x1D49C, 0x1D4B6, None),
+            MathVariant::BoldScript => (0x1D4D0, 0x1D4EA, None),
+            MathVariant::Fraktur => (0x1D504, 0x1D51E, None),
+            MathVariant::BoldFraktur => (0x1D56C, 0x1D586, None),
+            MathVariant::DoubleStruck => (0x1D538, 0x1D552, Some(0x1D7D8)),
+            MathVariant::SansSerif => (0x1D5A0, 0x1D5BA, Some(0x1D7E2)),
+            MathVariant::BoldSansSerif => (0x1D5D4, 0x1D5EE, Some(0x1D7EC)),
+            MathVariant::SansSerifItalic => (0x1D608, 0x1D622, None),
+            MathVariant::SansSerifBoldItalic => (0x1D63C, 0x1D656, None),
+            MathVariant::Monospace => (0x1D670, 0x1D68A, Some(0x1D7F6)),
+        }
+    }
+
+    /// The code points of `Α` and `α` in the variant, if it has Greek letters
+    fn greek_starts(self) -> Option<(u32, u32)> {
+        match self {
+            MathVariant::Bold => Some((0x1D6A8, 0x1D6C2)),
+            MathVariant::Italic => Some((0x1D6E2, 0x1D6FC)),
+            MathVariant::BoldItalic => Some((0x1D71C, 0x1D736)),
+            MathVariant::BoldSansSerif => Some((0x1D756, 0x1D770)),
+            MathVariant::SansSerifBoldItalic => Some((0x1D790, 0x1D7AA)),
+            _ => None,
+        }
+    }
+
+    /// The letters that were already in Unicode before the block, and so are left out of it
+    fn hole(self, c: char) -> Option<char> {
+        Some(match (self, c) {
+            (MathVariant::Italic, 'h') => 'ℎ',
+            (MathVariant::Script, 'B') => 'ℬ',
+            (MathVariant::Script, 'E') => 'ℰ',
+            (MathVariant::Script, 'F') => 'ℱ',
+            (MathVariant::Script, 'H') => 'ℋ',
+            (MathVariant::Script, 'I') => 'ℐ',
+            (MathVariant::Script, 'L') => 'ℒ',
+            (MathVariant::Script, 'M') => 'ℳ',
+            (MathVariant::Script, 'R') => 'ℛ',
+            (MathVariant::Script, 'e') => 'ℯ',
+            (MathVariant::Script, 'g') => 'ℊ',
+            (MathVariant::Script, 'o') => 'ℴ',
+            (MathVariant::Fraktur, 'C') => 'ℭ',
+            (MathVariant::Fraktur, 'H') => 'ℌ',
+            (MathVariant::Fraktur, 'I') => 'ℑ',
+            (MathVariant::Fraktur, 'R') => 'ℜ',
+            (MathVariant::Fraktur, 'Z') => 'ℨ',
+            (MathVariant::DoubleStruck, 'C') => 'ℂ',
+            (MathVariant::DoubleStruck, 'H') => 'ℍ',
+            (MathVariant::DoubleStruck, 'N') => 'ℕ',
+            (MathVariant::DoubleStruck, 'P') => 'ℙ',
+            (MathVariant::DoubleStruck, 'Q') => 'ℚ',
+            (MathVariant::DoubleStruck, 'R') => 'ℝ',
+            (MathVariant::DoubleStruck, 'Z') => 'ℤ',
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn apply(self, c: char) -> char {
+        if let Some(hole) = self.hole(c) {
+            return hole;
+        }
+
+        let (upper, lower, digit) = self.starts();
+        let greek = self.greek_starts();
+        let mapped = match c {
+            'A'..='Z' => Some(upper + (c as u32 - 'A' as u32)),
+            'a'..='z' => Some(lower + (c as u32 - 'a' as u32)),
+            '0'..='9' => digit.map(|digit| digit + (c as u32 - '0' as u32)),
+            'Α'..='Ω' => greek.map(|(upper, _)| upper + (c as u32 - 'Α' as u32)),
+            'α'..='ω' => greek.map(|(_, lower)| lower + (c as u32 - 'α' as u32)),
+            _ => None,
+        };
+        mapped.and_then(char::from_u32).unwrap_or(c)
+    }
+}
+
+/// The combining character that an accent command is written as
+pub(crate) fn accent_char(label: &str) -> Option<char> {
+    let accent = ACCENTS
+        .iter()
+        .find(|accent| accent.text == label || accent.math == Some(label));
+    if let Some(accent) = accent {
+        return Some(accent.name);
+    }
+
+    Some(match label {
+        "\\widehat" => '\u{0302}',
+        "\\widecheck" => '\u{030c}',
+        "\\widetilde" => '\u{0303}',
+        "\\vec" | "\\overrightarrow" => '\u{20d7}',
+        "\\overleftarrow" => '\u{20d6}',
+        "\\overleftrightarrow" => '\u{20e1}',
+        "\\dddot" => '\u{20db}',
+        "\\ddddot" => '\u{20dc}',
+        "\\textcircled" => '\u{20dd}',
+        _ => return None,
+    })
+}
+
+/// The combining character that an under accent command is written as
+pub(crate) fn accent_under_char(label: &str) -> Option<char> {
+    Some(match label {
+        "\\utilde" => '\u{0330}',
+        "\\underleftarrow" => '\u{20ee}',
+        "\\underrightarrow" => '\u{20ef}',
+        "\\underleftrightarrow" => '\u{034d}',
+        _ => return None,
+    })
+}
+
+/// The text of a symbol, from the `replace` field of its entry in [`SYMBOLS`]
+pub(crate) fn symbol_text(text: &str, mode: Mode) -> &str {
+    match SYMBOLS.get(mode, text).and_then(|symbol| symbol.replace) {
+        // `\nobreak` and friends are replaced with a null character
+        Some(replace) if replace != "\u{0}" => replace,
+        Some(_) => "",
+        // Control sequences without a character don't have anything to show
+        None if text.starts_with('\\') => "",
+        None => text,
+    }
+}
+
+pub(crate) fn delim_text(delim: &str) -> &str {
+    match delim {
+        "." => "",
+        "||" => "‖",
+        delim => symbol_text(delim, Mode::Math),
+    }
+}
+
+/// The space that a kern is closest to
+pub(crate) fn kern_space(dimension: &Measurement) -> &'static str {
+    let em = match dimension {
+        Measurement::Mu(mu) => mu.0 / 18.0,
+        Measurement::Em(em) => em.0,
+        Measurement::Ex(ex) => ex.0 * 0.431,
+        dimension => dimension.num() * dimension.unit_pt_size().unwrap_or(1.0) / 10.0,
+    };
+
+    if em <= 0.0 {
+        ""
+    } else if em < 4.0 / 18.0 {
+        "\u{2009}"
+    } else if em < 5.0 / 18.0 {
+        "\u{205f}"
+    } else if em < 1.0 {
+        "\u{2004}"
+    } else {
+        "\u{2003}"
+    }
+}
+
+/// Whether the text is a single symbol or number, so that it doesn't need parentheses around
+/// it when it is a script or the argument of an accent
+fn is_atom(text: &str) -> bool {
+    let mut chars = text.chars().filter(|c| !is_combining(*c));
+    let single = matches!((chars.next(), chars.next()), (Some(_), None));
+    let number = !text.is_empty() && text.chars().all(|c| c.is_ascii_digit() || c == '.');
+    single || number || is_parenthesized(text)
+}
+
+/// Whether the text reads as a single term, so that it doesn't need parentheses around it
+/// when it is a numerator or denominator, like `c²` or `f(x+1)`
+fn is_term(text: &str) -> bool {
+    let body = text.trim_start_matches(['√', '∛', '∜']);
+    let mut depth = 0;
+    let mut chars = body.chars();
+    !body.is_empty()
+        && chars.all(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                _ if depth > 0 => {}
+                c => {
+                    return c.is_alphanumeric()
+                        || is_combining(c)
+                        || is_primes(&c.to_string())
+                        || ".^_".contains(c)
+                }
+            }
+            true
+        })
+        && depth == 0
+}
+
+/// Whether the text is wrapped in a single pair of parentheses
+fn is_parenthesized(text: &str) -> bool {
+    if !text.starts_with('(') || !text.ends_with(')') {
+        return false;
+    }
+
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 && i != text.len() - 1 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+fn is_combining(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036f}' | '\u{20d0}'..='\u{20ff}')
+}
+
+/// Wrap the text in parentheses if it isn't a single symbol
+fn atom(text: &str) -> String {
+    if is_atom(text) {
+        text.to_string()
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// Wrap the text in parentheses if it isn't a single term
+fn term(text: &str) -> String {
+    if is_term(text) {
+        text.to_string()
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// Add the combining character after every character of the text, like an overline
+fn combine_each(text: &str, combining: char) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        // Put it after any combining characters already on the character
+        while let Some(next) = chars.peek().copied().filter(|c| is_combining(*c)) {
+            out.push(next);
+            chars.next();
+        }
+        out.push(combining);
+    }
+    out
+}
+
+/// Write the text as superscript or subscript characters, if they all exist
+fn script_text(text: &str, is_sub: bool) -> Option<String> {
+    if text.is_empty() {
+        return None;
+    }
+
+    text.chars().map(|c| find_script_char(c, is_sub)).collect()
+}
+
+fn is_primes(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| matches!(c, '′' | '″' | '‴' | '⁗'))
+}
+
+struct TextWriter {
+    format: UnicodeTextFormat,
+    out: String,
+    variant: Option<MathVariant>,
+    /// Whether the last thing written was a function name like `sin`, which needs to be
+    /// separated from its argument
+    after_function: bool,
+    /// Whether the last thing written was the limits of an n-ary operator like `∑`, which
+    /// need to be separated from its operand
+    after_limits: bool,
+    /// Combining marks to write after the next character, like the slash of a `\not` that
+    /// comes before the relation it negates
+    marks: String,
+}
+impl TextWriter {
+    fn new(format: UnicodeTextFormat, variant: Option<MathVariant>) -> TextWriter {
+        TextWriter {
+            format,
+            out: String::new(),
+            variant,
+            after_function: false,
+            after_limits: false,
+            marks: String::new(),
+        }
+    }
+
+    /// The text written, with any marks that had nothing after them to go on
+    fn finish(mut self) -> String {
+        self.out.push_str(&self.marks);
+        self.out
+    }
+
+    fn push(&mut self, text: &str) {
+        let Some(first) = text.chars().next() else {
+            return;
+        };
+
+        if self.after_function {
+            self.after_function = false;
+            match self.format {
+                // The function application character
+                UnicodeTextFormat::UnicodeMath => self.out.push('\u{2061}'),
+                UnicodeTextFormat::Plain if first.is_alphanumeric() => self.out.push(' '),
+                UnicodeTextFormat::Plain => {}
+            }
+        }
+
+        if core::mem::take(&mut self.after_limits) {
+            // The n-ary operand character, since the operand would otherwise continue the
+            // last script
+            self.out.push('\u{2592}');
+        }
+
+        if self.marks.is_empty() {
+            self.out.push_str(text);
+        } else {
+            self.out.push(first);
+            self.out.push_str(&core::mem::take(&mut self.marks));
+            self.out.push_str(&text[first.len_utf8()..]);
+        }
+    }
+
+    /// Write a symbol in the current font
+    fn push_symbol(&mut self, text: &str) {
+        match self.variant {
+            Some(variant) => {
+                let text = text.chars().map(|c| variant.apply(c)).collect::<String>();
+                self.push(&text);
+            }
+            None => self.push(text),
+        }
+    }
+
+    /// Write a function name, like `sin`
+    fn push_function(&mut self, name: &str) {
+        self.push(name);
+        self.after_function = true;
+    }
+
+    fn render_list(&self, nodes: &[ParseNode]) -> String {
+        let mut writer = TextWriter::new(self.format, self.variant);
+        writer.write_list(nodes);
+        writer.finish()
+    }
+
+    fn render(&self, node: &ParseNode) -> String {
+        self.render_list(core::slice::from_ref(node))
+    }
+
+    fn render_with(&self, node: &ParseNode, variant: Option<MathVariant>) -> String {
+        let mut writer = TextWriter::new(self.format, variant);
+        writer.write_node(node);
+        writer.finish()
+    }
+
+    fn write_list(&mut self, nodes: &[ParseNode]) {
+        for node in nodes {
+            self.write_node(node);
+        }
+    }
+
+    fn write_node(&mut self, node: &ParseNode) {
+        match node {
+            ParseNode::Spacing(spacing) => {
+                let text = match symbol_text(&spacing.text, spacing.info.mode) {
+                    // Spaces are only non-breaking to stop TeX from collapsing them
+                    "\u{a0}" if spacing.text != "\\nobreakspace" => " ",
+                    text => text,
+                };
+                self.push(text);
+            }
+            ParseNode::Atom(_)
+            | ParseNode::MathOrd(_)
+            | ParseNode::TextOrd(_)
+            | ParseNode::AccentToken(_)
+            | ParseNode::OpToken(_) => {
+                let text = node.text().unwrap_or_default();
+                self.push_symbol(symbol_text(text, node.info().mode));
+            }
+            ParseNode::OrdGroup(group) => self.write_list(&group.body),
+            ParseNode::SupSub(sup_sub) => self.write_sup_sub(sup_sub),
+            ParseNode::Array(array) => self.write_array(array),
+            ParseNode::LeftRight(left_right) => {
+                let left = match (left_right.left.as_str(), self.format) {
+                    // An invisible opening delimiter
+                    (".", UnicodeTextFormat::UnicodeMath) => "├",
+                    (left, _) => delim_text(left),
+                };
+                let right = match (left_right.right.as_str(), self.format) {
+                    (".", UnicodeTextFormat::UnicodeMath) => "┤",
+                    (right, _) => delim_text(right),
+                };
+                self.push(left);
+                self.write_list(&left_right.body);
+                self.push(right);
+            }
+            ParseNode::LeftRightRight(right) => self.push(delim_text(&right.delim)),
+            ParseNode::Middle(middle) => self.push(delim_text(&middle.delim)),
+            ParseNode::DelimSizing(delim) => self.push(delim_text(&delim.delim)),
+            ParseNode::CdLabel(label) => self.write_node(&label.label),
+            ParseNode::CdLabelParentNode(parent) => self.write_node(&parent.fragment),
+            ParseNode::Color(color) => self.write_list(&color.body),
+            ParseNode::Op(op) => {
+                if let Some(name) = &op.name {
+                    let symbol = symbol_text(name, Mode::Math);
+                    if symbol.is_empty() {
+                        // A named function like `\sin`
+                        self.push_function(name.trim_start_matches('\\'));
+                    } else {
+                        self.push(symbol);
+                    }
+                } else if let Some(body) = &op.body {
+                    self.write_list(body);
+                }
+            }
+            ParseNode::OperatorName(op) => {
+                let name = self.render_list(&op.body);
+                self.push_function(&name);
+            }
+            ParseNode::Styling(styling) => self.write_list(&styling.body),
+            ParseNode::Sizing(sizing) => self.write_list(&sizing.body),
+            ParseNode::Text(text) => {
+                let font = text.font.as_deref().unwrap_or_default();
+                let variant = MathVariant::from_font(font.trim_start_matches('\\'));
+                let mut writer = TextWriter::new(self.format, variant.or(self.variant));
+                writer.write_list(&text.body);
+                let body = writer.finish();
+                match self.format {
+                    UnicodeTextFormat::UnicodeMath if text.info.mode == Mode::Math => {
+                        self.push(&format!("\"{}\"", body));
+                    }
+                    _ => self.push(&body),
+                }
+            }
+            ParseNode::Font(font) => {
+                let variant = MathVariant::from_font(&font.font);
+                let text = self.render_with(&font.body, variant);
+                self.push(&text);
+            }
+            ParseNode::MClass(mclass) => self.write_list(&mclass.body),
+            ParseNode::GenFrac(frac) => self.write_genfrac(frac),
+            ParseNode::Accent(accent) => {
+                let base = self.render(&accent.base);
+                match accent_char(&accent.label) {
+                    Some(c) => self.push(&format!("{}{}", atom(&base), c)),
+                    None => self.push(&base),
+                }
+            }
+            ParseNode::AccentUnder(accent) => {
+                let base = self.render(&accent.base);
+                match accent_under_char(&accent.label) {
+                    Some(c) => self.push(&format!("{}{}", atom(&base), c)),
+                    None => self.push(&base),
+                }
+            }
+            ParseNode::HorizBrace(brace) => {
+                let c = match brace.label.as_str() {
+                    "\\overbrace" => "⏞",
+                    "\\underbrace" => "⏟",
+                    "\\overbracket" => "⎴",
+                    "\\underbracket" => "⎵",
+                    "\\overparen" => "⏜",
+                    "\\underparen" => "⏝",
+                    _ => "",
+                };
+                let base = self.render(&brace.base);
+                self.push(c);
+                self.push(&atom(&base));
+            }
+            ParseNode::XArrow(arrow) => {
+                let name = arrow.label.replacen("\\x", "\\", 1);
+                let symbol = match name.as_str() {
+                    "\\longequal" => "=",
+                    "\\tofrom" => "⇄",
+                    name => match symbol_text(name, Mode::Math) {
+                        "" => "→",
+                        symbol => symbol,
+                    },
+                };
+                self.push(symbol);
+                let sup = self.render(&arrow.body);
+                let sub = arrow.below.as_ref().map(|below| self.render(below));
+                self.write_scripts(Some(sup), sub);
+            }
+            ParseNode::Sqrt(sqrt) => {
+                let body = self.render(&sqrt.body);
+                let index = sqrt.index.as_ref().map(|index| self.render(index));
+                let root = match index.as_deref() {
+                    None => format!("√{}", atom(&body)),
+                    Some("3") => format!("∛{}", atom(&body)),
+                    Some("4") => format!("∜{}", atom(&body)),
+                    Some(index) => match self.format {
+                        UnicodeTextFormat::UnicodeMath => format!("√({}&{})", index, body),
+                        UnicodeTextFormat::Plain => match script_text(index, false) {
+                            Some(index) => format!("{}√{}", index, atom(&body)),
+                            None => format!("√[{}]{}", index, atom(&body)),
+                        },
+                    },
+                };
+                self.push(&root);
+            }
+            ParseNode::Enclose(enclose) => {
+                let body = self.render(&enclose.body);
+                let text = match (enclose.label.as_str(), self.format) {
+                    ("\\cancel" | "\\bcancel" | "\\xcancel", _) => combine_each(&body, '\u{0338}'),
+                    ("\\sout", _) => combine_each(&body, '\u{0336}'),
+                    ("\\boxed" | "\\fbox", UnicodeTextFormat::UnicodeMath) => {
+                        format!("▭{}", atom(&body))
+                    }
+                    _ => body,
+                };
+                self.push(&text);
+            }
+            ParseNode::Overline(overline) => {
+                let body = self.render(&overline.body);
+                match self.format {
+                    UnicodeTextFormat::Plain => self.push(&combine_each(&body, '\u{0305}')),
+                    UnicodeTextFormat::UnicodeMath => self.push(&format!("¯{}", atom(&body))),
+                }
+            }
+            ParseNode::Underline(underline) => {
+                let body = self.render(&underline.body);
+                match self.format {
+                    UnicodeTextFormat::Plain => self.push(&combine_each(&body, '\u{0332}')),
+                    UnicodeTextFormat::UnicodeMath => self.push(&format!("▁{}", atom(&body))),
+                }
+            }
+            ParseNode::Phantom(phantom) => {
+                if self.format == UnicodeTextFormat::UnicodeMath {
+                    let body = self.render_list(&phantom.body);
+                    self.push(&format!("⟡{}", atom(&body)));
+                } else if !phantom.body.is_empty() {
+                    self.push(" ");
+                }
+            }
+            ParseNode::HPhantom(phantom) => match self.format {
+                UnicodeTextFormat::UnicodeMath => {
+                    let body = self.render(&phantom.body);
+                    self.push(&format!("⬄{}", atom(&body)));
+                }
+                UnicodeTextFormat::Plain => self.push(" "),
+            },
+            ParseNode::VPhantom(phantom) => {
+                if self.format == UnicodeTextFormat::UnicodeMath {
+                    let body = self.render(&phantom.body);
+                    self.push(&format!("⇳{}", atom(&body)));
+                }
+            }
+            ParseNode::Smash(smash) => {
+                let body = self.render(&smash.body);
+                match self.format {
+                    UnicodeTextFormat::UnicodeMath => self.push(&format!("⬍{}", atom(&body))),
+                    UnicodeTextFormat::Plain => self.push(&body),
+                }
+            }
+            ParseNode::Lap(lap) => self.write_node(&lap.body),
+            ParseNode::RaiseBox(raise) => self.write_node(&raise.body),
+            ParseNode::VCenter(vcenter) => self.write_node(&vcenter.body),
+            ParseNode::HBox(hbox) => self.write_list(&hbox.body),
+            ParseNode::Href(href) => self.write_list(&href.body),
+            ParseNode::Html(html) => self.write_list(&html.body),
+            // The MathML side has the actual characters, like the U+0338 of `\neq`
+            ParseNode::HtmlMathml(html_mathml) => {
+                let text = self.render_list(&html_mathml.mathml);
+                // `\not` is written before the relation it negates, but its slash is a
+                // combining mark that goes after it
+                if !text.is_empty() && text.chars().all(is_combining_mark) {
+                    self.marks.push_str(&text);
+                } else {
+                    self.write_list(&html_mathml.mathml);
+                }
+            }
+            ParseNode::MathChoice(choice) => self.write_list(&choice.text),
+            ParseNode::IncludeGraphics(graphics) => self.push(&graphics.alt),
+            ParseNode::Kern(kern) => self.push(kern_space(&kern.dimension)),
+            ParseNode::Cr(_) => self.push("\n"),
+            ParseNode::Verb(verb) => self.push(&verb.body),
+            ParseNode::Url(url) => self.push(&url.url),
+            ParseNode::Raw(raw) => self.push(&raw.string),
+            ParseNode::Tag(tag) => {
+                self.write_list(&tag.body);
+                let tag = self.render_list(&tag.tag);
+                match self.format {
+                    // An equation number
+                    UnicodeTextFormat::UnicodeMath => self.push(&format!("#({})", tag)),
+                    UnicodeTextFormat::Plain => self.push(&format!("  ({})", tag)),
+                }
+            }
+            ParseNode::ColorToken(_)
+            | ParseNode::Size(_)
+            | ParseNode::Environment(_)
+            | ParseNode::Infix(_)
+            | ParseNode::Internal(_)
+            | ParseNode::Rule(_) => {}
+        }
+    }
+
+    fn write_sup_sub(&mut self, sup_sub: &SupSubNode) {
+        match sup_sub.base.as_deref() {
+            // Groups like `{a+b}^2` and fractions need parentheses to keep the scripts off of
+            // their last symbol
+            Some(base @ (ParseNode::OrdGroup(_) | ParseNode::GenFrac(_))) => {
+                let base = self.render(base);
+                self.push(&term(&base));
+            }
+            Some(base) => self.write_node(base),
+            None => {}
+        }
+
+        // The scripts go on the function name rather than separating it from its argument
+        let after_function = core::mem::take(&mut self.after_function);
+        let sup = sup_sub.sup.as_ref().map(|sup| self.render(sup));
+        let sub = sup_sub.sub.as_ref().map(|sub| self.render(sub));
+        let has_scripts = sup.iter().chain(&sub).any(|script| !script.is_empty());
+        self.write_scripts(sup, sub);
+        self.after_function = after_function;
+
+        let is_nary = matches!(sup_sub.base.as_deref(), Some(ParseNode::Op(op)) if op.symbol);
+        self.after_limits =
+            is_nary && has_scripts && self.format == UnicodeTextFormat::UnicodeMath;
+    }
+
+    /// Write the scripts, with the subscript first as in `xᵢ²`
+    fn write_scripts(&mut self, sup: Option<String>, sub: Option<String>) {
+        if let Some(sub) = sub.filter(|sub| !sub.is_empty()) {
+            match script_text(&sub, true).filter(|_| self.format == UnicodeTextFormat::Plain) {
+                Some(sub) => self.out.push_str(&sub),
+                None => self.out.push_str(&format!("_{}", atom(&sub))),
+            }
+        }
+
+        if let Some(sup) = sup.filter(|sup| !sup.is_empty()) {
+            if is_primes(&sup) {
+                self.out.push_str(&sup);
+                return;
+            }
+
+            match script_text(&sup, false).filter(|_| self.format == UnicodeTextFormat::Plain) {
+                Some(sup) => self.out.push_str(&sup),
+                None => self.out.push_str(&format!("^{}", atom(&sup))),
+            }
+        }
+    }
+
+    fn write_genfrac(&mut self, frac: &GenFracNode) {
+        let numer = self.render(&frac.numer);
+        let denom = self.render(&frac.denom);
+        let left = delim_text(frac.left_delim.as_deref().unwrap_or("."));
+        let right = delim_text(frac.right_delim.as_deref().unwrap_or("."));
+
+        let text = if frac.has_bar_line {
+            format!("{}{}/{}{}", left, term(&numer), term(&denom), right)
+        } else {
+            // A stack without a line, like a binomial coefficient
+            format!("{}{}¦{}{}", left, numer, denom, right)
+        };
+        self.push(&text);
+    }
+
+    fn write_array(&mut self, array: &ArrayNode) {
+        let is_aligned = matches!(
+            array.col_separation_type,
+            Some(ColSeparationType::Align | ColSeparationType::AlignAt | ColSeparationType::Gather)
+        );
+
+        let rows = array
+            .body
+            .iter()
+            .map(|row| row.iter().map(|cell| self.render(cell)).collect::<Vec<_>>());
+
+        let text = match self.format {
+            UnicodeTextFormat::UnicodeMath => {
+                let rows = rows.map(|row| row.join("&")).collect::<Vec<_>>();
+                let op = if is_aligned { "█" } else { "■" };
+                format!("{}({})", op, rows.join("@"))
+            }
+            UnicodeTextFormat::Plain => {
+                let sep = if is_aligned { "" } else { ", " };
+                let rows = rows.map(|row| row.join(sep)).collect::<Vec<_>>();
+                rows.join("; ")
+            }
+        };
+        self.push(&text);
+    }
+}
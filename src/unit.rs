@@ -1,4 +1,5 @@
-use crate::Options;
+use crate::prelude::*;
+use crate::{util::round, Options};
 
 /// Parse an em value from a CSS string like "0.5em" or "1.2em".
 /// Returns None if the string doesn't end with "em" or can't be parsed.
@@ -26,7 +27,7 @@ pub fn make_em(n: f64) -> String {
     if n.abs() < 0.00001 {
         n = 0.0;
     } else {
-        n = (n * 10000.0).round() / 10000.0;
+        n = round(n * 10000.0) / 10000.0;
     }
 
     format!("{}em", n)
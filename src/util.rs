@@ -1,15 +1,17 @@
-use std::{borrow::Cow, fmt::Debug, num::ParseIntError, ops::Range};
-
-use once_cell::sync::Lazy;
-use regex::Regex;
+use core::{fmt::Debug, num::ParseIntError, ops::Range};
 
+use crate::prelude::*;
 use crate::{
     expander::Mode,
     parse_node::ParseNode,
     style::{StyleId, DISPLAY_STYLE, SCRIPT_SCRIPT_STYLE, SCRIPT_STYLE, TEXT_STYLE},
-    tree::ClassList,
 };
 
+#[cfg(any(feature = "html", feature = "mathml"))]
+use core::fmt;
+#[cfg(feature = "html")]
+use crate::tree::ClassList;
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct SourceLocation(pub Range<usize>);
 impl SourceLocation {
@@ -32,7 +34,7 @@ impl From<Range<usize>> for SourceLocation {
 // Custom implementation of debug so that it is a bit less verbose when
 // printed
 impl Debug for SourceLocation {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_fmt(format_args!("SourceLocation({:?})", self.0))
     }
 }
@@ -101,6 +103,7 @@ pub(crate) fn parse_rgba(src: &str) -> Result<[u8; 4], ParseIntError> {
     Ok([red as u8, green as u8, blue as u8, alpha as u8])
 }
 
+#[cfg(feature = "html")]
 pub(crate) fn has_class(classes: &ClassList, class: &str) -> bool {
     classes.iter().any(|c| c == class)
 }
@@ -153,6 +156,7 @@ pub enum ArgType {
 }
 
 /// Math font variants
+#[cfg(any(feature = "html", feature = "mathml"))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum FontVariant {
     Bold,
@@ -168,6 +172,7 @@ pub(crate) enum FontVariant {
     SansSerifItalic,
     Script,
 }
+#[cfg(any(feature = "html", feature = "mathml"))]
 impl FontVariant {
     pub fn as_str(self) -> &'static str {
         match self {
@@ -195,34 +200,25 @@ pub(crate) fn first_ch_str(text: &str) -> Option<&str> {
     Some(&text[0..ch.len_utf8()])
 }
 
-static ESCAPE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    const REGEX_TEXT: &str = r#"[&<>"'']"#;
-
-    Regex::new(REGEX_TEXT).unwrap()
-});
-
 // hyphenate and escape adapted from KaTeX which adapted them from Facebook's React under Apache 2 license
 
-/// Escapes text to prevent scripting attacks
-pub(crate) fn escape(text: &str) -> Cow<'_, str> {
-    ESCAPE_REGEX.replace_all(text, |caps: &regex::Captures| -> &'static str {
-        // I'm skeptical that this is the best method
-        if let Some(first) = caps.iter().next().flatten() {
-            match first.as_str() {
-                "&" => "&amp;",
-                ">" => "&gt;",
-                "<" => "&lt;",
-                "\"" => "&quot;",
-                "'" => "&#x27;",
-                _ => "",
-            }
-        } else {
-            // TODO: Warn that we replaced it with nothing
-            // I'm assuming that getting rid of the part of text entirely is better than keeping it
-            // since this is meant for security filtering
-            ""
-        }
-    })
+/// Writes the text with the characters that are special in markup escaped, to prevent
+/// scripting attacks
+#[cfg(any(feature = "html", feature = "mathml"))]
+pub(crate) fn write_escaped(out: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+    let mut rest = text;
+    while let Some(i) = rest.find(['&', '<', '>', '"', '\'']) {
+        out.write_str(&rest[..i])?;
+        out.write_str(match rest.as_bytes()[i] {
+            b'&' => "&amp;",
+            b'>' => "&gt;",
+            b'<' => "&lt;",
+            b'"' => "&quot;",
+            _ => "&#x27;",
+        })?;
+        rest = &rest[i + 1..];
+    }
+    out.write_str(rest)
 }
 
 fn get_base_elem(group: &ParseNode) -> &ParseNode {
@@ -256,10 +252,66 @@ pub(crate) fn is_character_box(group: &ParseNode) -> bool {
     )
 }
 
-static UPPERCASE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("([A-Z])").unwrap());
-
+#[allow(dead_code)]
 pub(crate) fn hyphenate(text: &str) -> String {
-    UPPERCASE_REGEX.replace_all(text, "-$1").to_lowercase()
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_uppercase() {
+            res.push('-');
+        }
+        res.push(c);
+    }
+    res.to_lowercase()
+}
+
+/// The length of the number at the start of the text, like `12`, `1.5`, `1.` or `.5`
+fn number_len(text: &str) -> usize {
+    let int = text.bytes().take_while(u8::is_ascii_digit).count();
+    let Some(frac) = text[int..].strip_prefix('.') else {
+        return int;
+    };
+    let frac = frac.bytes().take_while(u8::is_ascii_digit).count();
+    if int == 0 && frac == 0 {
+        0
+    } else {
+        int + 1 + frac
+    }
+}
+
+/// Whether the text is only a number, with an optional sign, like `-1.5`
+pub(crate) fn is_number(text: &str, is_space: fn(char) -> bool) -> bool {
+    let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let text = text.trim_start_matches(is_space);
+    !text.is_empty() && number_len(text) == text.len()
+}
+
+/// Find the first size in the text, like `-1.5em`, which may have spaces between its parts.
+/// Returns the sign (which may be empty), the magnitude and the two-letter unit.
+pub(crate) fn find_size(text: &str, is_space: fn(char) -> bool) -> Option<(&str, &str, &str)> {
+    text.char_indices().find_map(|(i, _)| {
+        let text = &text[i..];
+        let (sign, text) = text.split_at(usize::from(text.starts_with(['+', '-'])));
+        let text = text.trim_start_matches(is_space);
+
+        let (magnitude, text) = text.split_at(number_len(text));
+        if magnitude.is_empty() {
+            return None;
+        }
+
+        let text = text.trim_start_matches(is_space);
+        let unit = text
+            .get(..2)
+            .filter(|unit| unit.bytes().all(|b| b.is_ascii_lowercase()))?;
+        Some((sign, magnitude, unit))
+    })
+}
+
+/// Round to the nearest integer, away from zero at halfway, like [`f64::round`] in `std`
+pub(crate) fn round(x: f64) -> f64 {
+    #[cfg(feature = "std")]
+    return x.round();
+    #[cfg(not(feature = "std"))]
+    return libm::round(x);
 }
 
 /// Find the value associated with a key in a slice of tuples. A poor hashmap.
@@ -269,9 +321,13 @@ pub(crate) fn find_assoc_data<K: PartialEq, V>(data: &[(K, V)], key: K) -> Optio
 
 #[cfg(test)]
 mod tests {
-    use crate::util::{char_code_for, hyphenate};
+    use crate::util::{char_code_for, hyphenate, write_escaped};
 
-    use super::escape;
+    fn escape(text: &str) -> String {
+        let mut escaped = String::new();
+        write_escaped(&mut escaped, text).unwrap();
+        escaped
+    }
 
     #[test]
     fn test_text_util() {
@@ -279,6 +335,7 @@ mod tests {
         assert_eq!(escape("abc test"), "abc test");
         assert_eq!(escape("'hello'"), "&#x27;hello&#x27;");
         assert_eq!(escape("test&other"), "test&amp;other");
+        assert_eq!(escape("<a href=\"x\">"), "&lt;a href=&quot;x&quot;&gt;");
 
         // hyphenate
         assert_eq!(hyphenate("testThing"), "test-thing");
@@ -0,0 +1,142 @@
+use aliter::{
+    a11y::{render_a11y_string, to_speech_with, SpeechStyle},
+    parse_node::ParseNode,
+    parse_tree,
+    parser::ParserConfig,
+    render_to_html_tree,
+    tree::VirtualNode,
+};
+
+fn parse(expr: &str) -> Vec<ParseNode> {
+    match parse_tree(expr, ParserConfig::default()) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to parse: {}\nError: {:?}", expr, err),
+    }
+}
+
+fn assert_verbose(expr: &str, expected: &str) {
+    let speech = to_speech_with(&parse(expr), SpeechStyle::Verbose);
+    assert_eq!(speech, expected, "for {}", expr);
+}
+
+fn assert_terse(expr: &str, expected: &str) {
+    let speech = to_speech_with(&parse(expr), SpeechStyle::Terse);
+    assert_eq!(speech, expected, "for {}", expr);
+}
+
+// =============================================================================
+// Verbose
+// =============================================================================
+
+#[test]
+fn a11y_should_describe_symbols() {
+    assert_verbose("123+45", "123, plus, 45");
+    assert_verbose("x=-1", "x, equals, negative, 1");
+    assert_verbose(r"a \le b", "a, is less than or equal to, b");
+    assert_verbose(r"x \ne 0", "x, does not equal, 0");
+    assert_verbose(r"\alpha\Gamma", "alpha, capital gamma");
+    assert_verbose(r"\left\{x\right\}", "left brace, x, right brace");
+}
+
+#[test]
+fn a11y_should_describe_fractions_and_scripts() {
+    assert_verbose(
+        r"\frac{a+b}{c^2}",
+        "start fraction, a, plus, b, divided by, c, squared, end fraction",
+    );
+    assert_verbose(r"\binom{n}{k}", "start binomial, n, over, k, end binomial");
+    assert_verbose(
+        "x^{n+1}",
+        "x, start superscript, n, plus, 1, end superscript",
+    );
+    assert_verbose("y_1", "y, start subscript, 1, end subscript");
+    assert_verbose(r"\log_2 x", "log, start base, 2, end base, x");
+    assert_verbose("f'(x)", "f, prime, left parenthesis, x, right parenthesis");
+    assert_verbose(r"90^\circ", "90, degrees");
+}
+
+#[test]
+fn a11y_should_describe_roots() {
+    assert_verbose(r"\sqrt{x}", "square root of, x, end square root");
+    assert_verbose(r"\sqrt[3]{x}", "cube root of, x, end cube root");
+    assert_verbose(
+        r"\sqrt[n]{x}",
+        "root, start index, n, end index, of, x, end root",
+    );
+}
+
+#[test]
+fn a11y_should_describe_operators_with_limits() {
+    assert_verbose(r"\sum_{i=1}^n i", "sum, from, i, equals, 1, to, n, of, i");
+    assert_verbose(r"\lim_{x\to 0} f", "limit, as, x, right arrow, 0, of, f");
+    assert_verbose(r"\int_0^1", "integral, from, 0, to, 1");
+}
+
+#[test]
+fn a11y_should_describe_arrays() {
+    assert_verbose(
+        r"\begin{matrix}a&b\\c&d\end{matrix}",
+        "start 2 by 2 matrix, row 1, column 1, a, column 2, b, row 2, column 1, c, column 2, d, end matrix",
+    );
+    assert_verbose(
+        r"\begin{aligned}a&=b\\c&=d\end{aligned}",
+        "start 2 lines, line 1, a, equals, b, line 2, c, equals, d, end lines",
+    );
+}
+
+#[test]
+fn a11y_should_describe_accents_colors_and_text() {
+    assert_verbose(r"\hat{x}", "x, with, hat, on top");
+    assert_verbose(r"\underleftarrow{x}", "x, with, left arrow, underneath");
+    assert_verbose(r"\color{red}{x}", "start color red, x, end color red");
+    assert_verbose(r"\text{if } x", "start text, if, end text, x");
+    assert_verbose(r"\textbf{bold}", "start bold text, bold, end bold text");
+    assert_verbose(r"\cancel{x}", "start cancel, x, end cancel");
+}
+
+// =============================================================================
+// Terse
+// =============================================================================
+
+#[test]
+fn a11y_terse_should_shorten_simple_structures() {
+    assert_terse(r"\frac{1}{2}", "1 over 2");
+    assert_terse(
+        r"\frac{a+b}{c^2}",
+        "fraction a plus b over c squared end fraction",
+    );
+    assert_terse(r"\binom{n}{k}", "n choose k");
+    assert_terse("x^{n+1}", "x to the n plus 1 end exponent");
+    assert_terse(r"\sqrt{x}", "square root of x");
+    assert_terse(r"\hat{x}", "x hat");
+    assert_terse(r"\color{red}{x}", "x");
+    assert_terse(r"\text{if } x", "if x");
+    assert_terse(
+        r"\begin{matrix}a&b\\c&d\end{matrix}",
+        "2 by 2 matrix row 1 a b row 2 c d end matrix",
+    );
+}
+
+// =============================================================================
+// aria-label
+// =============================================================================
+
+#[test]
+fn a11y_should_render_from_source() {
+    let speech = render_a11y_string(r"\frac{1}{2}", ParserConfig::default()).unwrap();
+    assert_eq!(speech, "start fraction, 1, divided by, 2, end fraction");
+}
+
+#[test]
+fn a11y_should_add_aria_label() {
+    let conf = ParserConfig {
+        aria_label: Some(SpeechStyle::Terse),
+        ..ParserConfig::default()
+    };
+    let markup = render_to_html_tree(r"\frac{1}{2}", conf).to_markup();
+    assert!(markup.contains(r#"aria-label="1 over 2""#), "{}", markup);
+    assert!(markup.contains(r#"role="img""#), "{}", markup);
+
+    let markup = render_to_html_tree(r"\frac{1}{2}", ParserConfig::default()).to_markup();
+    assert!(!markup.contains("aria-label"), "{}", markup);
+}
@@ -1,4 +1,12 @@
-use aliter::{parse_tree, render_to_html_tree, parser::ParserConfig};
+use aliter::{
+    ir::{build_ir, MathElement},
+    parse_node::{HorizBraceNode, ParseNode},
+    parse_tree,
+    parser::ParserConfig,
+    render_to_html_tree,
+    tree::VirtualNode,
+    Options,
+};
 
 // Helper for basic parsing
 fn assert_parses(expr: &str) {
@@ -395,3 +403,125 @@ fn should_parse_wide_accents_on_long_expressions() {
     assert_parses(r"\widehat{a+b+c+d+e+f}");
     assert_parses(r"\widetilde{abcdefgh}");
 }
+
+// =============================================================================
+// Brackets, Parens and Brace Labels
+// =============================================================================
+
+fn parse_horiz_brace(expr: &str) -> HorizBraceNode {
+    let tree = parse_tree(expr, ParserConfig::default()).unwrap();
+    match tree.into_iter().next() {
+        Some(ParseNode::HorizBrace(brace)) => brace,
+        other => panic!("Expected a horizontal brace, got {:?}", other),
+    }
+}
+
+fn render_html(expr: &str) -> String {
+    render_to_html_tree(expr, ParserConfig::default()).to_markup()
+}
+
+#[cfg(feature = "mathml")]
+fn render_mathml(expr: &str) -> String {
+    aliter::render_to_mathml_tree(expr, ParserConfig::default()).to_markup()
+}
+
+/// The (brace width, base width, label) of the first brace in the IR
+fn ir_brace(expr: &str) -> (f64, f64, Option<f64>) {
+    let conf = ParserConfig::default();
+    let tree = parse_tree(expr, conf.clone()).unwrap();
+    let layout = build_ir(&tree, &Options::from_parser_conf(&conf));
+    layout
+        .walk()
+        .find_map(|item| match item.element {
+            MathElement::Brace {
+                base, brace, label, ..
+            } => Some((brace.width(), base.width(), label.as_ref().map(|l| l.y))),
+            _ => None,
+        })
+        .expect("No brace in IR")
+}
+
+#[test]
+fn should_parse_overbracket_and_underbracket() {
+    let brace = parse_horiz_brace(r"\overbracket{x+y}");
+    assert!(brace.is_over);
+    assert!(brace.thickness.is_none() && brace.height.is_none());
+
+    let brace = parse_horiz_brace(r"\underbracket[1pt][4pt]{x+y}");
+    assert!(!brace.is_over);
+    assert_eq!(brace.thickness.unwrap().num(), 1.0);
+    assert_eq!(brace.height.unwrap().num(), 4.0);
+
+    let brace = parse_horiz_brace(r"\overbracket[2pt]{x}");
+    assert_eq!(brace.thickness.unwrap().num(), 2.0);
+    assert!(brace.height.is_none());
+}
+
+#[test]
+fn should_parse_overparen_and_underparen() {
+    assert!(parse_horiz_brace(r"\overparen{abc}").is_over);
+    assert!(!parse_horiz_brace(r"\underparen{abc}").is_over);
+}
+
+#[test]
+fn should_not_parse_bracket_with_bad_size() {
+    assert_fails(r"\overbracket[foo]{x}");
+}
+
+#[test]
+fn should_build_bracket_with_borders() {
+    let html = render_html(r"\underbracket[1pt][4pt]{abc}");
+    assert!(html.contains("stretchy bracket"), "{}", html);
+    assert!(html.contains("border-width: 0 0.1em 0.1em 0.1em"), "{}", html);
+
+    let html = render_html(r"\overbracket{abc}");
+    assert!(html.contains("border-width: 0.04em 0.04em 0 0.04em"), "{}", html);
+}
+
+#[test]
+fn should_build_brace_label_over_brace() {
+    let html = render_html(r"\overbrace{a+b}^{n}");
+    assert!(html.contains("mord mover"), "{}", html);
+    assert!(!html.contains("supexpr"), "{}", html);
+
+    // A script on the other side isn't a label
+    let html = render_html(r"\overbrace{a+b}_{n}");
+    assert!(html.contains("supexpr"), "{}", html);
+}
+
+#[cfg(feature = "mathml")]
+#[test]
+fn should_build_brace_label_mathml() {
+    let mathml = render_mathml(r"\underbrace{a+b}_{n}");
+    assert!(
+        mathml.contains(r#"<munder><munder><mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow><mo stretchy="true">⏟</mo></munder><mi>n</mi></munder>"#),
+        "{}",
+        mathml
+    );
+
+    let mathml = render_mathml(r"\overparen{ab}");
+    assert!(mathml.contains("⏜"), "{}", mathml);
+}
+
+#[test]
+fn should_stretch_ir_brace_to_base() {
+    let (narrow, _, _) = ir_brace(r"\overbrace{a+b}");
+    let (wide, base_width, _) = ir_brace(r"\overbrace{a+b+c+d+e+f}");
+    assert!(wide > narrow);
+    assert!((wide - base_width).abs() < 1e-3);
+
+    let (bracket, base_width, _) = ir_brace(r"\underbracket{a+b+c}");
+    assert!((bracket - base_width).abs() < 1e-9);
+}
+
+#[test]
+fn should_place_ir_brace_labels() {
+    let (_, _, label) = ir_brace(r"\overbrace{a+b}^{n}");
+    assert!(label.unwrap() > 0.0);
+
+    let (_, _, label) = ir_brace(r"\underbrace{a+b}_{n}");
+    assert!(label.unwrap() < 0.0);
+
+    let (_, _, label) = ir_brace(r"\underbrace{a+b}");
+    assert!(label.is_none());
+}
@@ -0,0 +1,182 @@
+use aliter::{
+    asciimath::{asciimath_to_latex, parse_asciimath},
+    parse_node::EqNoLoc,
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    render_to_html_tree,
+    tree::VirtualNode,
+};
+
+fn assert_translates(asciimath: &str, expected: &str) {
+    assert_eq!(asciimath_to_latex(asciimath), expected, "for {}", asciimath);
+}
+
+/// Assert that the AsciiMath parses to the same tree as the LaTeX does
+fn assert_parses_as(asciimath: &str, latex: &str) {
+    let tree = match parse_asciimath(asciimath, ParserConfig::default()) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to parse: {}\nError: {:?}", asciimath, err),
+    };
+    let expected = parse_tree(latex, ParserConfig::default()).unwrap();
+    assert!(
+        tree.eq_no_loc(&expected),
+        "for {}\nParsed: {:#?}\nExpected: {:#?}",
+        asciimath,
+        tree,
+        expected
+    );
+}
+
+// =============================================================================
+// Symbols
+// =============================================================================
+
+#[test]
+fn asciimath_should_parse_symbols() {
+    assert_parses_as("a+b-c*d", r"a+b-c\cdot d");
+    assert_parses_as("x != oo", r"x \ne \infty");
+    assert_parses_as("a <= b >= c", r"a \le b \ge c");
+    assert_parses_as("alpha xx Gamma", r"\alpha \times \Gamma");
+    assert_parses_as("AA x in RR", r"\forall x \in \mathbb{R}");
+    assert_parses_as("a -> b |-> c", r"a \to b \mapsto c");
+    assert_parses_as("12.5 + 3", "12.5+3");
+    assert_parses_as("# $ %", r"\# \$ \%");
+}
+
+#[test]
+fn asciimath_should_parse_functions() {
+    assert_parses_as("sin x + log y", r"\sin x + \log y");
+    assert_parses_as("lcm(a,b)", r"\operatorname{lcm}\left(a,b\right)");
+    assert_parses_as("lim_(x->0) f", r"\lim_{x\to 0} f");
+}
+
+// =============================================================================
+// Scripts and fractions
+// =============================================================================
+
+#[test]
+fn asciimath_should_parse_scripts() {
+    assert_parses_as("x_1^2", "x_{1}^{2}");
+    assert_parses_as("e^(i pi)", r"e^{i\pi}");
+    assert_parses_as("12^3", "{12}^{3}");
+    assert_parses_as("f^-1", "f^{-1}");
+    assert_parses_as("sum_(i=1)^n i", r"\sum_{i=1}^{n} i");
+}
+
+#[test]
+fn asciimath_should_parse_fractions() {
+    assert_parses_as("a/b", r"\frac{a}{b}");
+    assert_parses_as("(a+b)/c", r"\frac{a+b}{c}");
+    assert_parses_as("1/x^2", r"\frac{1}{x^{2}}");
+    assert_parses_as("a/b/c", r"\frac{\frac{a}{b}}{c}");
+    assert_parses_as("sqrt(x+1)/2", r"\frac{\sqrt{x+1}}{2}");
+    assert_parses_as("frac a b", r"\frac{a}{b}");
+    // Differentials are a single group
+    assert_parses_as("dy/dx", r"\frac{dy}{dx}");
+    assert_translates("dy/dx", r"\frac{d y}{d x}");
+    assert_parses_as("int f(t) dt", r"\int f \left( t \right) {dt}");
+    assert_parses_as("dz^2", r"{dz}^{2}");
+    assert_translates(
+        "sum_(i=1)^n i^3=((n(n+1))/2)^2",
+        r"\sum_{i = 1}^{n} i^{3} = \left( \frac{n \left( n + 1 \right)}{2} \right)^{2}",
+    );
+}
+
+// =============================================================================
+// Brackets
+// =============================================================================
+
+#[test]
+fn asciimath_should_match_brackets() {
+    assert_parses_as("(a, b]", r"\left(a, b\right]");
+    assert_parses_as("(:a, b:)", r"\left\langle a, b\right\rangle");
+    assert_parses_as("{:a:}", "{a}");
+    assert_parses_as("{:a)", r"\left.a\right)");
+    assert_parses_as("|x| + ||y||", r"\left|x\right| + \left\|y\right\|");
+    assert_parses_as("a|b", "a|b");
+}
+
+#[test]
+fn asciimath_should_write_unmatched_brackets_as_symbols() {
+    assert_parses_as("(a", "(a");
+    assert_parses_as("a)", "a)");
+    assert_parses_as("sqrt)", r"\sqrt{})");
+}
+
+#[test]
+fn asciimath_should_parse_matrices() {
+    assert_parses_as(
+        "[(a,b),(c,d)]",
+        r"\left[\begin{matrix}a&b\\c&d\end{matrix}\right]",
+    );
+    assert_parses_as(
+        "{(x, x >= 0),(-x, x < 0):}",
+        r"\left\{\begin{matrix}x&x\ge 0\\-x&x<0\end{matrix}\right.",
+    );
+    // Rows of different lengths aren't a matrix
+    assert_parses_as(
+        "[(a,b),(c)]",
+        r"\left[\left(a,b\right),\left(c\right)\right]",
+    );
+}
+
+// =============================================================================
+// Commands
+// =============================================================================
+
+#[test]
+fn asciimath_should_parse_commands() {
+    assert_parses_as("sqrt x", r"\sqrt{x}");
+    assert_parses_as("root(3)(x)", r"\sqrt[3]{x}");
+    assert_parses_as("hat(ab) bar x", r"\hat{ab}\overline{x}");
+    assert_parses_as("abs(x)", r"\left|x\right|");
+    assert_parses_as("stackrel(def)(=)", r"\stackrel{def}{=}");
+    assert_parses_as("color(red)(x)", r"\textcolor{red}{x}");
+}
+
+#[test]
+fn asciimath_should_parse_fonts() {
+    assert_parses_as("bb(x) bbb R cc A", r"\mathbf{x}\mathbb{R}\mathcal{A}");
+    assert_parses_as(
+        "tt x fr g sf s rm d",
+        r"\mathtt{x}\mathfrak{g}\mathsf{s}\mathrm{d}",
+    );
+}
+
+#[test]
+fn asciimath_should_parse_text() {
+    assert_parses_as(r#"x "if" y"#, r"x\text{if}y");
+    assert_parses_as("text(a_b (c))", r"\text{a\_b (c)}");
+    assert_parses_as("mbox(50%)", r"\text{50\%}");
+}
+
+#[test]
+fn asciimath_output_should_render() {
+    let latex = asciimath_to_latex("[(a,b),(c,d)]");
+    let markup = render_to_html_tree(&latex, ParserConfig::default()).to_markup();
+    assert!(markup.contains("mtable"), "{}", markup);
+}
+
+// =============================================================================
+// Limits
+// =============================================================================
+
+#[test]
+fn asciimath_should_limit_nesting() {
+    // These are too deep to even translate without overflowing the stack
+    for input in ["sqrt ".repeat(100_000), "(".repeat(100_000)] {
+        let result = parse_asciimath(&input, ParserConfig::default());
+        assert!(matches!(result, Err(ParseError::TooDeeplyNested)), "{:?}", result);
+    }
+
+    // The brackets of an argument don't nest in the LaTeX, so this is only three levels deep
+    let conf = |max_depth| ParserConfig {
+        max_depth: Some(max_depth),
+        ..ParserConfig::default()
+    };
+    assert!(parse_asciimath("abs(abs(x))", conf(3)).is_ok());
+    assert!(matches!(
+        parse_asciimath("abs(abs(x))", conf(2)),
+        Err(ParseError::TooDeeplyNested)
+    ));
+}
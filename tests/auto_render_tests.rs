@@ -0,0 +1,206 @@
+#![cfg(feature = "auto-render")]
+
+use aliter::{
+    contrib::auto_render::{
+        default_delimiters, render_math_in_text, AutoRenderConfig, AutoRenderOutput, Delimiter,
+    },
+    parser::{ParseError, ParserConfig},
+    render_to_string,
+    tree::OutputType,
+};
+
+// The HTML output is compared, since the attributes of MathML elements are in any order
+fn html_config() -> AutoRenderConfig {
+    AutoRenderConfig {
+        output: OutputType::Html,
+        ..AutoRenderConfig::default()
+    }
+}
+
+fn dollar_config() -> AutoRenderConfig {
+    let mut config = html_config();
+    config.delimiters.push(Delimiter::new("$", "$", false));
+    config
+}
+
+fn html(tex: &str, display: bool) -> String {
+    let conf = ParserConfig {
+        display_mode: display,
+        ..ParserConfig::default()
+    };
+    render_to_string(tex, conf, OutputType::Html).unwrap()
+}
+
+#[track_caller]
+fn assert_renders(text: &str, config: &AutoRenderConfig, expected: &str) -> AutoRenderOutput {
+    let output = render_math_in_text(text, config);
+    assert_eq!(output.text, expected, "for {}", text);
+    output
+}
+
+// =============================================================================
+// Delimiters
+// =============================================================================
+
+#[test]
+fn test_default_delimiters() {
+    let config = html_config();
+    let expected = format!("a {} b {} c", html("x", false), html("y", true));
+    let output = assert_renders(r"a \(x\) b \[y\] c", &config, &expected);
+    assert!(output.errors.is_empty());
+
+    assert_renders("$$x$$", &config, &html("x", true));
+    // `$` isn't a delimiter by default
+    assert_renders("costs $5", &config, "costs $5");
+}
+
+#[test]
+fn test_environments() {
+    let config = html_config();
+    let tex = r"\begin{equation}x\end{equation}";
+    assert_renders(tex, &config, &html(tex, true));
+    let tex = r"\begin{align}a &= b\end{align}";
+    assert_renders(
+        &format!("see {}.", tex),
+        &config,
+        &format!("see {}.", html(tex, true)),
+    );
+}
+
+#[test]
+fn test_delimiter_order() {
+    let config = dollar_config();
+    let expected = format!("{} and {}", html("x", true), html("y", false));
+    assert_renders("$$x$$ and $y$", &config, &expected);
+
+    // The first delimiter in the list wins
+    let config = AutoRenderConfig {
+        delimiters: vec![
+            Delimiter::new("$", "$", false),
+            Delimiter::new("$$", "$$", true),
+        ],
+        ..html_config()
+    };
+    assert_renders("$x$", &config, &html("x", false));
+}
+
+#[test]
+fn test_braces_and_escapes() {
+    let config = dollar_config();
+    // The `$` in the braces and the escaped `$` don't end the math
+    let expected = format!("{}!", html(r"\text{$a$} + \$", false));
+    assert_renders(r"$\text{$a$} + \$$!", &config, &expected);
+
+    // An escaped delimiter is text without the backslash
+    assert_renders(r"\$5 and \$6", &config, "$5 and $6");
+    let expected = format!(r"\(x\) {}", html("y", false));
+    assert_renders(r"\\(x\) \(y\)", &config, &expected);
+}
+
+#[test]
+fn test_unclosed() {
+    let config = dollar_config();
+    let expected = format!("{} and $y", html("x", false));
+    assert_renders("$x$ and $y", &config, &expected);
+    assert_renders(r"\[x", &config, r"\[x");
+}
+
+// =============================================================================
+// HTML
+// =============================================================================
+
+#[test]
+fn test_ignored_tags() {
+    let config = html_config();
+    let x = html("x", false);
+    let text = r#"<p class="a">\(x\)</p><pre><code>\(x\)</code> <b>\(x\)</b></pre><!-- \(x\) --><br/>\(x\)"#;
+    let expected = format!(
+        r#"<p class="a">{}</p><pre><code>\(x\)</code> <b>\(x\)</b></pre><!-- \(x\) --><br/>{}"#,
+        x, x
+    );
+    assert_renders(text, &config, &expected);
+
+    // Nested elements of the same name
+    let text = r"<code><code>\(x\)</code>\(x\)</code>\(x\)";
+    let expected = format!(r"<code><code>\(x\)</code>\(x\)</code>{}", x);
+    assert_renders(text, &config, &expected);
+}
+
+#[test]
+fn test_ignored_classes() {
+    let config = AutoRenderConfig {
+        ignored_classes: vec!["no-math".to_string()],
+        ..html_config()
+    };
+    let x = html("x", false);
+    let text =
+        r#"<div class="a no-math">\(x\)<div>\(x\)</div>\(x\)</div><span title="<b>">\(x\)</span>"#;
+    let expected = format!(
+        r#"<div class="a no-math">\(x\)<div>\(x\)</div>\(x\)</div><span title="<b>">{}</span>"#,
+        x
+    );
+    assert_renders(text, &config, &expected);
+}
+
+#[test]
+fn test_html_text() {
+    let config = html_config();
+    // Entities are decoded in the math, and a lone `<` is text
+    let expected = format!("1 < 2 {}", html("a < b", false));
+    assert_renders(r"1 < 2 \(a &lt; b\)", &config, &expected);
+
+    // Math can't span tags
+    assert_renders(r"\(a <b>b</b>\)", &config, r"\(a <b>b</b>\)");
+
+    let config = AutoRenderConfig {
+        html: false,
+        ..html_config()
+    };
+    // Which makes the `&` an alignment
+    let output = assert_renders(
+        r"<code>\(a &lt; b\)</code>",
+        &config,
+        r"<code>\(a &lt; b\)</code>",
+    );
+    assert_eq!(output.errors.len(), 1);
+}
+
+// =============================================================================
+// Errors
+// =============================================================================
+
+#[test]
+fn test_errors() {
+    let config = dollar_config();
+    let text = "é $x$ and $\\foo$ and $$y^$$";
+    let expected = format!("é {} and $\\foo$ and $$y^$$", html("x", false));
+    let output = assert_renders(text, &config, &expected);
+
+    assert_eq!(output.errors.len(), 2);
+    assert_eq!(output.errors[0].range, 11..17);
+    assert_eq!(&text[output.errors[0].range.clone()], "$\\foo$");
+    assert_eq!(output.errors[0].tex, "\\foo");
+    assert!(!output.errors[0].display);
+    assert!(matches!(
+        output.errors[0].error,
+        ParseError::UndefinedControlSequence(_)
+    ));
+    assert_eq!(&text[output.errors[1].range.clone()], "$$y^$$");
+    assert!(output.errors[1].display);
+
+    // The error is rendered instead when not throwing
+    let mut config = dollar_config();
+    config.conf.throw_on_error = false;
+    let output = render_math_in_text(r"$x^$", &config);
+    assert!(output.text.contains("katex-error"));
+    assert!(output.errors.is_empty());
+}
+
+#[test]
+fn test_default_config() {
+    let config = AutoRenderConfig::default();
+    assert_eq!(config.delimiters, default_delimiters());
+    let output = render_math_in_text(r"\[x\]", &config);
+    assert!(output.text.starts_with("<span class=\"katex-display\">"));
+    assert!(output.text.contains("katex-html"));
+}
@@ -0,0 +1,276 @@
+#![cfg(all(feature = "html", feature = "mathml"))]
+
+use aliter::{
+    macr::Macros, parse_preamble, parse_tree, parser::ParserConfig, render_batch, render_to_string,
+    tree::OutputType, Options,
+};
+
+const EXPRS: &[&str] = &[
+    "x",
+    "x^2 + y^2 = z^2",
+    r"\frac{-b \pm \sqrt{b^2 - 4ac}}{2a}",
+    r"\sum_{i=0}^{n} \binom{n}{i} x^i y^{n-i}",
+    r"\left( \begin{matrix} a & b \\ c & d \end{matrix} \right)",
+    r"\color{red} a \textcolor{blue}{b}",
+    r"\def\foo{x} \foo + \foo",
+    r"\text{if } x \in A \text{ then } \hat{x} \ne \vec{y}",
+    r"\frac{1}",
+    r"\undefinedcommand",
+    r"\overbrace{x + \cdots + x}^{n}",
+];
+
+/// Render each expression on its own, as render_batch should match
+fn render_sequential(exprs: &[&str], conf: &ParserConfig, output: OutputType) -> Vec<String> {
+    exprs
+        .iter()
+        .map(|expr| match render_to_string(expr, conf.clone(), output) {
+            Ok(markup) => markup,
+            Err(err) => format!("{:?}", err),
+        })
+        .collect()
+}
+
+fn render_batched(exprs: &[&str], conf: &ParserConfig, output: OutputType) -> Vec<String> {
+    render_batch(exprs, conf, output)
+        .into_iter()
+        .map(|result| match result {
+            Ok(markup) => markup,
+            Err(err) => format!("{:?}", err),
+        })
+        .collect()
+}
+
+/// Many copies of the expressions, so that a parallel batch is spread across threads
+fn many_exprs() -> Vec<&'static str> {
+    EXPRS
+        .iter()
+        .copied()
+        .cycle()
+        .take(EXPRS.len() * 20)
+        .collect()
+}
+
+// =============================================================================
+// Batch Rendering Tests
+// =============================================================================
+
+#[test]
+fn batch_matches_sequential() {
+    let exprs = many_exprs();
+    let conf = ParserConfig::default();
+
+    for output in [
+        OutputType::Html,
+        OutputType::Mathml,
+        OutputType::HtmlAndMathml,
+    ] {
+        assert_eq!(
+            render_batched(&exprs, &conf, output),
+            render_sequential(&exprs, &conf, output)
+        );
+    }
+}
+
+#[test]
+fn batch_matches_sequential_without_throwing() {
+    let exprs = many_exprs();
+    let conf = ParserConfig {
+        throw_on_error: false,
+        display_mode: true,
+        ..Default::default()
+    };
+
+    let results = render_batch(&exprs, &conf, OutputType::HtmlAndMathml);
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(
+        render_batched(&exprs, &conf, OutputType::HtmlAndMathml),
+        render_sequential(&exprs, &conf, OutputType::HtmlAndMathml)
+    );
+}
+
+#[test]
+fn batch_keeps_order_and_errors() {
+    let results = render_batch(EXPRS, &ParserConfig::default(), OutputType::Html);
+    assert_eq!(results.len(), EXPRS.len());
+
+    for (expr, result) in EXPRS.iter().zip(&results) {
+        let expected = render_to_string(expr, ParserConfig::default(), OutputType::Html);
+        assert_eq!(result.is_ok(), expected.is_ok(), "{}", expr);
+    }
+    assert!(results[8].is_err());
+    assert!(results[9].is_err());
+}
+
+#[test]
+fn batch_matches_sequential_with_an_output_limit() {
+    // Long markup is cut off part way, which mustn't show up in the markup after it
+    let exprs = many_exprs();
+    for throw_on_error in [true, false] {
+        let conf = ParserConfig {
+            max_output_size: Some(2000),
+            throw_on_error,
+            ..Default::default()
+        };
+
+        let batch = render_batched(&exprs, &conf, OutputType::HtmlAndMathml);
+        assert!(batch.iter().any(|markup| markup == "OutputTooLarge") == throw_on_error);
+        assert_eq!(batch, render_sequential(&exprs, &conf, OutputType::HtmlAndMathml));
+    }
+}
+
+#[test]
+fn batch_of_nothing() {
+    assert!(render_batch(&[], &ParserConfig::default(), OutputType::Html).is_empty());
+}
+
+// =============================================================================
+// Preamble Tests
+// =============================================================================
+
+#[test]
+fn preamble_macros_are_shared() {
+    let preamble = r"\newcommand{\RR}{\mathbb{R}} \def\half{\frac{1}{2}} \gdef\twice#1{#1#1}";
+    let conf = ParserConfig {
+        macros: parse_preamble(preamble, &ParserConfig::default()).unwrap(),
+        ..Default::default()
+    };
+
+    let exprs = ["x \\in \\RR", "\\half", "\\twice{y}"];
+    let batch = render_batched(&exprs, &conf, OutputType::Html);
+    let expanded = render_sequential(
+        &[r"x \in \mathbb{R}", r"\frac{1}{2}", "yy"],
+        &ParserConfig::default(),
+        OutputType::Html,
+    );
+    assert_eq!(batch, expanded);
+}
+
+#[test]
+fn preamble_macros_can_be_redefined() {
+    // An expression's definitions go in front of the preamble's, and don't reach the next one
+    let conf = ParserConfig {
+        macros: parse_preamble(r"\def\half{\frac{1}{2}}", &ParserConfig::default()).unwrap(),
+        ..Default::default()
+    };
+
+    let exprs = [
+        r"\def\half{x} \half",
+        r"{\renewcommand{\half}{y} \half} \half",
+        r"\half",
+        r"\let\half\undefined x",
+        r"\half",
+    ];
+    let expanded = [r"x", r"{y} \frac{1}{2}", r"\frac{1}{2}", "x", r"\frac{1}{2}"];
+    let exprs = exprs.iter().copied().cycle().take(exprs.len() * 20).collect::<Vec<_>>();
+    let expanded = expanded.iter().copied().cycle().take(exprs.len()).collect::<Vec<_>>();
+
+    let batch = render_batched(&exprs, &conf, OutputType::Html);
+    assert_eq!(batch, render_sequential(&expanded, &ParserConfig::default(), OutputType::Html));
+    assert_eq!(batch, render_sequential(&exprs, &conf, OutputType::Html));
+}
+
+#[test]
+fn preamble_keeps_existing_macros() {
+    let mut macros = Macros::default();
+    macros.insert_back_macro(
+        "\\N",
+        std::sync::Arc::new(aliter::macr::MacroReplace::Text("\\mathbb{N}".to_string())),
+    );
+    let conf = ParserConfig {
+        macros,
+        ..Default::default()
+    };
+
+    let macros = parse_preamble(r"\def\Z{\mathbb{Z}}", &conf).unwrap();
+    assert!(macros.contains_back_macro("\\N"));
+    assert!(macros.contains_back_macro("\\Z"));
+}
+
+#[test]
+fn preamble_leaves_out_what_it_sets_while_parsing() {
+    // A color for `\right`, the tag of the expression and whether to number the row are kept in
+    // macros while parsing, which shouldn't carry into the expressions
+    let preamble = r"\color{red} \tag{1} \nonumber \def\Z{\mathbb{Z}}";
+    let conf = ParserConfig {
+        display_mode: true,
+        ..Default::default()
+    };
+    let macros = parse_preamble(preamble, &conf).unwrap();
+    assert!(macros.contains_back_macro("\\Z"));
+    for name in ["\\current@color", "\\df@tag", "\\@eqnsw"] {
+        assert!(!macros.contains_back_macro(name), "{}", name);
+    }
+
+    let exprs = [r"\left( x \right)", r"\begin{equation} x \end{equation}"];
+    let with_preamble = ParserConfig {
+        macros,
+        ..conf.clone()
+    };
+    assert_eq!(
+        render_batched(&exprs, &with_preamble, OutputType::Html),
+        render_sequential(&exprs, &conf, OutputType::Html)
+    );
+}
+
+#[test]
+fn preamble_keeps_what_its_config_sets() {
+    let mut macros = Macros::default();
+    macros.insert_back_macro(
+        "\\color",
+        std::sync::Arc::new(aliter::macr::MacroReplace::Text("\\textcolor".to_string())),
+    );
+    let conf = ParserConfig {
+        macros,
+        color_is_text_color: true,
+        ..Default::default()
+    };
+
+    let macros = parse_preamble(r"\def\Z{\mathbb{Z}}", &conf).unwrap();
+    assert!(macros.contains_back_macro("\\color"));
+    assert!(macros.contains_back_macro("\\Z"));
+}
+
+#[test]
+fn preamble_error() {
+    assert!(parse_preamble(r"\newcommand{\R}", &ParserConfig::default()).is_err());
+}
+
+// =============================================================================
+// Thread Safety Tests
+// =============================================================================
+
+#[test]
+fn shared_types_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<ParserConfig>();
+    assert_send_sync::<Macros>();
+    assert_send_sync::<Options>();
+    assert_send_sync::<aliter::parse_node::ParseNode>();
+    assert_send_sync::<aliter::parser::ParseError>();
+}
+
+#[test]
+fn render_on_threads() {
+    let conf = ParserConfig::default();
+    let expected = render_sequential(EXPRS, &conf, OutputType::Html);
+
+    std::thread::scope(|scope| {
+        let handles = (0..4)
+            .map(|_| scope.spawn(|| render_sequential(EXPRS, &conf, OutputType::Html)))
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    });
+
+    // Parsing alone doesn't need the output features, but should be just as shareable
+    let tree = parse_tree(EXPRS[2], conf.clone()).unwrap();
+    let from_thread = std::thread::scope(|scope| {
+        scope
+            .spawn(|| parse_tree(EXPRS[2], conf.clone()).unwrap())
+            .join()
+            .unwrap()
+    });
+    assert_eq!(format!("{:?}", tree), format!("{:?}", from_thread));
+}
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use aliter::{
+    cache::{CacheStats, RenderCache},
+    macr::MacroReplace,
+    parse_tree,
+    parser::ParserConfig,
+};
+
+fn define(conf: &mut ParserConfig, name: &str, text: &str) {
+    conf.macros
+        .insert_back_macro(name, Arc::new(MacroReplace::Text(text.to_string())));
+}
+
+// =============================================================================
+// Lookup Tests
+// =============================================================================
+
+#[test]
+fn parse_is_cached() {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig::default();
+
+    let first = cache.parse(r"\alpha + x", &conf).unwrap();
+    let second = cache.parse(r"\alpha + x", &conf).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(
+        format!("{:?}", first),
+        format!("{:?}", parse_tree(r"\alpha + x", conf).unwrap())
+    );
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+}
+
+#[test]
+fn layout_is_cached() {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig::default();
+
+    let first = cache.layout(r"\frac{1}{2}", &conf).unwrap();
+    let second = cache.layout(r"\frac{1}{2}", &conf).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    // The layout and its parse tree
+    assert_eq!(cache.stats().entries, 2);
+}
+
+#[test]
+fn errors_are_not_cached() {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig::default();
+
+    assert!(cache.parse(r"\frac{1}", &conf).is_err());
+    assert!(cache.parse(r"\frac{1}", &conf).is_err());
+    assert_eq!(cache.stats().entries, 0);
+    assert_eq!(cache.stats().misses, 2);
+}
+
+#[test]
+fn config_is_part_of_the_key() {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig::default();
+    let display = ParserConfig {
+        display_mode: true,
+        ..Default::default()
+    };
+
+    let inline = cache.layout("x", &conf).unwrap();
+    let block = cache.layout("x", &display).unwrap();
+    assert!(!inline.display_mode);
+    assert!(block.display_mode);
+
+    // Cloning the config keeps the same key
+    cache.layout("x", &conf.clone()).unwrap();
+    assert_eq!(cache.stats().hits, 1);
+}
+
+// =============================================================================
+// Macro Tests
+// =============================================================================
+
+#[test]
+fn changing_macros_misses() {
+    let mut cache = RenderCache::new();
+    let mut conf = ParserConfig::default();
+    define(&mut conf, "\\foo", "a");
+
+    let a = cache.parse("\\foo", &conf).unwrap();
+    define(&mut conf, "\\foo", "b");
+    let b = cache.parse("\\foo", &conf).unwrap();
+
+    assert_ne!(format!("{:?}", a), format!("{:?}", b));
+    assert_eq!(cache.stats().hits, 0);
+}
+
+#[test]
+fn definitions_in_an_input_do_not_leak() {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig {
+        global_group: true,
+        ..Default::default()
+    };
+
+    assert!(cache.parse(r"\gdef\foo{x} \foo", &conf).is_ok());
+    // Rendering doesn't change the config's macros, so `\foo` is still undefined
+    assert!(cache.parse(r"\foo", &conf).is_err());
+    assert!(parse_tree(r"\foo", conf).is_err());
+}
+
+// =============================================================================
+// Eviction Tests
+// =============================================================================
+
+#[test]
+fn least_recently_used_is_evicted() {
+    let mut cache = RenderCache::new().with_max_entries(2);
+    let conf = ParserConfig::default();
+
+    cache.parse("a", &conf).unwrap();
+    cache.parse("b", &conf).unwrap();
+    // Use `a`, so that `b` is the least recently used
+    cache.parse("a", &conf).unwrap();
+    cache.parse("c", &conf).unwrap();
+
+    let stats = cache.stats();
+    assert_eq!((stats.entries, stats.evictions), (2, 1));
+
+    cache.parse("a", &conf).unwrap();
+    cache.parse("c", &conf).unwrap();
+    assert_eq!(cache.stats().hits, 3);
+    cache.parse("b", &conf).unwrap();
+    assert_eq!(cache.stats().misses, 4);
+}
+
+/// The size that an entry for the expression counts as
+fn entry_size(expr: &str, layout: bool) -> usize {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig::default();
+    if layout {
+        cache.layout(expr, &conf).unwrap();
+    } else {
+        cache.parse(expr, &conf).unwrap();
+    }
+    cache.stats().bytes
+}
+
+#[test]
+fn byte_limit() {
+    // Each of these parses to four nodes
+    let size = entry_size("abcd", false);
+    let mut cache = RenderCache::new().with_max_bytes(size * 2 + 1);
+    let conf = ParserConfig::default();
+
+    cache.parse("abcd", &conf).unwrap();
+    cache.parse("efgh", &conf).unwrap();
+    assert_eq!(cache.stats().bytes, size * 2);
+    cache.parse("ijkl", &conf).unwrap();
+    assert_eq!(cache.stats().bytes, size * 2);
+    assert_eq!(cache.stats().evictions, 1);
+
+    // Too big to ever fit, so it isn't kept
+    cache.parse("abcdefghijk", &conf).unwrap();
+    assert_eq!(cache.stats().entries, 2);
+}
+
+#[test]
+fn trees_count_their_nodes() {
+    // Trees are much larger than their source, and grow with it
+    let small = entry_size("x", false);
+    let large = entry_size(r"\frac{a+b}{\sqrt{c}} + \sum_{i=0}^n x_i", false);
+    assert!(small > "x".len());
+    assert!(large > small * 10);
+
+    let small = entry_size("x", true);
+    let large = entry_size(r"\frac{a+b}{\sqrt{c}} + \sum_{i=0}^n x_i", true);
+    assert!(large > small * 10);
+
+    // So a limit that fits plenty of sources fits only some layouts
+    let mut cache = RenderCache::new().with_max_bytes(large * 2);
+    let conf = ParserConfig::default();
+    for i in 0..10 {
+        let expr = format!(r"\frac{{a+b}}{{\sqrt{{c}}}} + \sum_{{i={i}}}^n x_i");
+        cache.layout(&expr, &conf).unwrap();
+    }
+    assert!(cache.stats().bytes <= large * 2);
+    assert!(cache.stats().evictions > 0);
+}
+
+#[test]
+fn shrinking_limits_and_clearing() {
+    let mut cache = RenderCache::new();
+    let conf = ParserConfig::default();
+    for expr in ["a", "b", "c", "d"] {
+        cache.parse(expr, &conf).unwrap();
+    }
+
+    let mut cache = cache.with_max_entries(1);
+    assert_eq!(cache.stats().entries, 1);
+    assert_eq!(cache.stats().evictions, 3);
+
+    cache.clear();
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 0,
+            misses: 4,
+            evictions: 3,
+            entries: 0,
+            bytes: 0,
+        }
+    );
+}
+
+// =============================================================================
+// Markup Tests
+// =============================================================================
+
+#[cfg(all(feature = "html", feature = "mathml"))]
+mod markup {
+    use aliter::{render_to_string, tree::OutputType};
+
+    use super::*;
+
+    #[test]
+    fn markup_matches_render_to_string() {
+        let mut cache = RenderCache::new();
+        let conf = ParserConfig::default();
+
+        for output in [
+            OutputType::Html,
+            OutputType::Mathml,
+            OutputType::HtmlAndMathml,
+        ] {
+            let expected = render_to_string(r"n \in \mathbb{N}", conf.clone(), output).unwrap();
+            assert_eq!(
+                &*cache.render(r"n \in \mathbb{N}", &conf, output).unwrap(),
+                expected
+            );
+            assert_eq!(
+                &*cache.render(r"n \in \mathbb{N}", &conf, output).unwrap(),
+                expected
+            );
+        }
+
+        // Each output was rendered once from a single parse
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 4);
+        assert_eq!(stats.hits, 3 + 2);
+    }
+
+    #[test]
+    fn error_markup() {
+        let mut cache = RenderCache::new();
+        let conf = ParserConfig {
+            throw_on_error: false,
+            ..Default::default()
+        };
+
+        let markup = cache.render(r"x^", &conf, OutputType::Html).unwrap();
+        assert!(markup.contains("katex-error"));
+        assert_eq!(
+            &*markup,
+            render_to_string(r"x^", conf.clone(), OutputType::Html).unwrap()
+        );
+
+        let conf = ParserConfig::default();
+        assert!(cache.render(r"x^", &conf, OutputType::Html).is_err());
+    }
+}
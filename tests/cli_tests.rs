@@ -0,0 +1,190 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn aliter(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aliter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run aliter");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+// =============================================================================
+// Single formulas
+// =============================================================================
+
+#[test]
+fn test_formats() {
+    let output = aliter(&[], "x^2");
+    assert!(output.status.success());
+    let markup = stdout(&output);
+    assert!(markup.contains("katex-html"));
+    assert!(markup.contains("<math"));
+
+    let markup = stdout(&aliter(&["-F", "html"], "x^2"));
+    assert!(markup.contains("katex-html"));
+    assert!(!markup.contains("<math"));
+
+    let markup = stdout(&aliter(&["--format=mathml"], "\\frac12\n"));
+    assert!(!markup.contains("katex-html"));
+    assert!(markup.contains("<mfrac>"));
+    assert!(markup.contains("\\frac12</annotation>"));
+
+    let json = stdout(&aliter(&["--format", "json"], r"\frac12"));
+    assert!(json.starts_with("{\"displayMode\":false,"));
+    assert!(json.contains("\"type\":\"fraction\""));
+
+    let svg = stdout(&aliter(&["-F", "svg"], "x"));
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn test_display_options() {
+    let markup = stdout(&aliter(&["-d", "--leqno", "--fleqn"], "x"));
+    assert!(markup.contains("katex-display leqno fleqn"));
+    assert!(markup.contains("display=\"block\""));
+}
+
+#[test]
+fn test_errors() {
+    let output = aliter(&[], r"\foo");
+    assert!(!output.status.success());
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("UndefinedControlSequence"));
+
+    let output = aliter(&["-t", "-c", "#00ff00", "-F", "html"], r"x^");
+    assert!(output.status.success());
+    let markup = stdout(&output);
+    assert!(markup.contains("katex-error"));
+    assert!(markup.contains("color:#00ff00ff"));
+
+    // Without a color, errors are an opaque red
+    let output = aliter(&["-t", "-F", "html"], r"x^");
+    assert!(output.status.success());
+    let markup = stdout(&output);
+    assert!(markup.contains("katex-error"));
+    assert!(markup.contains("color:#cc0000ff"));
+}
+
+#[test]
+fn test_macros() {
+    let markup = stdout(&aliter(&["-F", "mathml", "-m", r"\RR:\mathbb{R}"], r"\RR"));
+    assert!(markup.contains("mathvariant=\"double-struck\""));
+
+    let path = std::env::temp_dir().join("aliter_cli_test_macros.tex");
+    std::fs::write(&path, "\\RR: \\mathbb{R}\nnot a macro\n\\NN:\\mathbb{N}\n").unwrap();
+    let output = aliter(
+        &["-F", "mathml", "--macro-file", path.to_str().unwrap()],
+        r"\RR \NN",
+    );
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("<mi mathvariant=\"double-struck\">N</mi>"));
+}
+
+#[test]
+fn test_limits() {
+    let output = aliter(&["-e", "5"], r"\def\a{\a}\a");
+    assert!(stderr(&output).contains("TooManyExpansions"));
+
+    let markup = stdout(&aliter(&["-F", "html"], r"\href{https://a.b}{x}"));
+    assert!(!markup.contains("<a href"));
+    let markup = stdout(&aliter(
+        &["-F", "html", "--trust"],
+        r"\href{https://a.b}{x}",
+    ));
+    assert!(markup.contains("<a href=\"https://a.b\""));
+
+    let output = aliter(&["--strict"], "é");
+    assert!(!output.status.success());
+    let output = aliter(&["--strict=ignore"], "é");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_bad_arguments() {
+    let output = aliter(&["--format", "png"], "x");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown format 'png'"));
+
+    let output = aliter(&["--max-size"], "x");
+    assert!(stderr(&output).contains("needs a value"));
+
+    let output = aliter(&["--frobnicate"], "x");
+    assert!(stderr(&output).contains("unknown option"));
+
+    assert!(stdout(&aliter(&["--help"], "")).contains("--macro-file"));
+}
+
+// =============================================================================
+// Batches
+// =============================================================================
+
+#[test]
+fn test_batch_lines() {
+    let output = aliter(&["--batch", "-F", "mathml"], "x\n\\foo\n\ny\n");
+    assert!(!output.status.success());
+
+    let out = stdout(&output);
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].contains("<mi>x</mi>"));
+    assert_eq!(lines[1], "");
+    assert_eq!(lines[2], "");
+    assert!(lines[3].contains("<mi>y</mi>"));
+
+    assert!(stderr(&output).contains("line 2: UndefinedControlSequence"));
+}
+
+#[test]
+fn test_batch_jsonl() {
+    let input = concat!(
+        "\"x\"\n",
+        "{\"tex\": \"\\\\frac{1}{2}\", \"displayMode\": true, \"id\": \"half\"}\n",
+        "\n",
+        "{\"tex\": \"\\\\foo\", \"id\": 4}\n",
+        "{\"math\": \"x\"}\n",
+        "[1,\n",
+    );
+    let output = aliter(&["--jsonl", "-F", "mathml"], input);
+    assert!(!output.status.success());
+
+    let out = stdout(&output);
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("{\"line\":1,\"output\":\"<span class=\\\"katex\\\">"));
+    assert!(lines[1].starts_with("{\"line\":2,\"id\":\"half\",\"output\":"));
+    assert!(lines[1].contains("display=\\\"block\\\""));
+    assert_eq!(
+        lines[2],
+        "{\"line\":4,\"id\":4,\"error\":\"UndefinedControlSequence(\\\"\\\\\\\\foo\\\")\"}"
+    );
+    assert_eq!(lines[3], "{\"line\":5,\"error\":\"missing 'tex'\"}");
+    assert!(lines[4].starts_with("{\"line\":6,\"error\":\"invalid JSON"));
+
+    // The IR is embedded as JSON rather than as a string
+    let out = stdout(&aliter(&["--jsonl", "-F", "json"], "\"x\"\n"));
+    assert!(out.starts_with("{\"line\":1,\"output\":{\"displayMode\":false,"));
+}
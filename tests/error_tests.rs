@@ -204,6 +204,20 @@ fn rejects_incorrectly_scoped_right() {
     assert_fails(r"{\left(1+2}\right)");
 }
 
+#[test]
+fn rejects_extra_close_brace_in_an_argument() {
+    // \frac{}}x}{y}
+    assert_parse_error(r"\frac{}}x}{y}", ParseError::ExtraCloseBrace);
+    assert_parse_error(r"\def\a#1{#1}\a}", ParseError::ExtraCloseBrace);
+}
+
+#[test]
+fn rejects_right_as_an_argument() {
+    // x^\right)
+    assert_parse_error(r"x^\right)", ParseError::FunctionNoArguments);
+    assert_parse_error(r"\sqrt\right)", ParseError::FunctionNoArguments);
+}
+
 #[test]
 fn complains_about_missing_opening_brace_for_color() {
     // \textcolor#ffffff{text}
@@ -300,6 +314,26 @@ fn reject_group_closing_delimiters() {
     assert_fails(r"\bigl(1+2\bigr{)}3");
 }
 
+#[test]
+fn reject_unclosed_math_in_text() {
+    // \text{$x}
+    assert_parse_error(r"\text{$x}", ParseError::Expected);
+    assert_parse_error(r"\text{\(x}", ParseError::Expected);
+}
+
+#[test]
+fn reject_unopened_math_in_text() {
+    // \text{x\)}
+    assert_parse_error(
+        r"\text{x\)}",
+        ParseError::MismatchedMathDelimiter(r"\)".to_string()),
+    );
+    assert_parse_error(
+        r"\text{x\]}",
+        ParseError::MismatchedMathDelimiter(r"\]".to_string()),
+    );
+}
+
 #[test]
 fn reject_invalid_environment_names() {
     // \begin x\end y
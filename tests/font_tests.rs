@@ -1,4 +1,11 @@
-use aliter::{parse_tree, render_to_html_tree, parser::ParserConfig};
+use aliter::{
+    ir::{build_ir, MathElement},
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    render_to_html_tree,
+    tree::VirtualNode,
+    Options,
+};
 
 // Helper for basic parsing
 fn assert_parses(expr: &str) {
@@ -287,3 +294,127 @@ fn should_parse_colored_fonts() {
 fn should_build_colored_fonts() {
     assert_builds(r"\color{red}\mathbf{RED BOLD}");
 }
+
+// =============================================================================
+// Text Font Shapes and Switches
+// =============================================================================
+
+fn render_html(expr: &str) -> String {
+    render_to_html_tree(expr, ParserConfig::default()).to_markup()
+}
+
+#[cfg(feature = "mathml")]
+fn render_mathml(expr: &str) -> String {
+    aliter::render_to_mathml_tree(expr, ParserConfig::default()).to_markup()
+}
+
+fn ir_text_fonts(expr: &str) -> Vec<(String, String, f64)> {
+    let conf = ParserConfig::default();
+    let tree = parse_tree(expr, conf.clone()).unwrap();
+    let layout = build_ir(&tree, &Options::from_parser_conf(&conf));
+    layout
+        .walk()
+        .filter_map(|item| match item.element {
+            MathElement::Text { text, style } => Some((
+                text.clone(),
+                style.font.as_ref().unwrap().as_str().to_string(),
+                style.size,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn should_parse_text_font_shapes() {
+    assert_parses(r"\emph{emphasis}");
+    assert_parses(r"\textsc{Small Caps}");
+    assert_parses(r"\textsl{slanted}");
+    assert_parses(r"\textnormal{\textbf{a}}");
+    assert_parses(r"\text{\emph{a \emph{b}}}");
+}
+
+#[test]
+fn should_parse_text_font_switches() {
+    assert_parses(r"\text{a \itshape b}");
+    assert_parses(r"\text{\bfseries\scshape a} b");
+    assert_parses(r"\text{\slshape a \upshape b \normalfont c}");
+    assert_parses(r"\text{\rmfamily a \sffamily b \ttfamily c \mdseries d}");
+    assert_parses(r"\text{a {\em b} c}");
+    // Including in math nested in text
+    assert_parses(r"\text{\bfseries $x$ a}");
+}
+
+#[test]
+fn should_not_parse_text_font_switches_in_math() {
+    let conf = ParserConfig::default();
+    for expr in [r"\itshape x", r"x {\bfseries y}", r"\text{$\scshape x$}", r"\em x"] {
+        assert!(
+            matches!(
+                parse_tree(expr, conf.clone()),
+                Err(ParseError::FunctionUnusableMathMode)
+            ),
+            "Expected {} to fail in math mode",
+            expr
+        );
+    }
+}
+
+#[test]
+fn should_build_text_font_shapes() {
+    let html = render_html(r"\textsc{ab}");
+    assert!(html.contains("textsc"));
+    assert!(html.contains("font-variant: small-caps"));
+
+    // Slanted text is drawn with the italic font alone, rather than also being slanted by CSS
+    let html = render_html(r"\textsl{a}");
+    assert!(!html.contains("oblique"));
+    assert_eq!(html, render_html(r"\textit{a}"));
+    assert_eq!(render_html(r"\text{\slshape a}"), render_html(r"\text{\itshape a}"));
+}
+
+#[test]
+fn should_toggle_nested_emph() {
+    assert_eq!(render_html(r"\emph{a}"), render_html(r"\textit{a}"));
+    assert_eq!(render_html(r"\emph{\emph{a}}"), render_html(r"\textit{\textup{a}}"));
+    assert_eq!(render_html(r"\textit{\emph{a}}"), render_html(r"\textit{\textup{a}}"));
+}
+
+#[test]
+fn should_scope_text_font_switches() {
+    assert_eq!(
+        render_html(r"\text{a {\itshape b} c}"),
+        render_html(r"\text{a {\textit{b}} c}")
+    );
+    assert_eq!(render_html(r"\text{\bfseries a}"), render_html(r"\text{\textbf{a}}"));
+    assert_eq!(
+        render_html(r"\textbf{\textnormal{a}}"),
+        render_html(r"\text{\textnormal{a}}")
+    );
+}
+
+#[test]
+#[cfg(feature = "mathml")]
+fn should_build_mathml_text_font_shapes() {
+    assert!(render_mathml(r"\textsl{a}").contains(r#"<mtext mathvariant="italic">a</mtext>"#));
+    assert!(render_mathml(r"\emph{a}").contains(r#"<mtext mathvariant="italic">a</mtext>"#));
+    assert!(render_mathml(r"\emph{\emph{a}}").contains("<mtext>a</mtext>"));
+    assert!(render_mathml(r"\textsc{a}")
+        .contains(r#"<mtext style="font-variant: small-caps">a</mtext>"#));
+}
+
+#[test]
+fn should_use_text_fonts_in_ir() {
+    let fonts = ir_text_fonts(r"\textbf{a}\textit{b}\textsl{c}\text{\sffamily d}");
+    let names: Vec<_> = fonts.iter().map(|(_, font, _)| font.as_str()).collect();
+    assert_eq!(names, ["Main-Bold", "Main-Italic", "Main-Italic", "SansSerif-Regular"]);
+}
+
+#[test]
+fn should_synthesize_small_caps_in_ir() {
+    let fonts = ir_text_fonts(r"\textsc{Ab}");
+    assert_eq!(fonts.len(), 2);
+    assert_eq!(fonts[0].0, "A");
+    assert_eq!(fonts[1].0, "B");
+    assert!(fonts[1].2 < fonts[0].2);
+}
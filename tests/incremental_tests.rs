@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use aliter::{
+    incremental::{Formula, Reparse, TextEdit},
+    ir::build_ir,
+    macr::MacroReplace,
+    parse_tree,
+    parser::ParserConfig,
+    Options,
+};
+
+/// Apply the edit and check that the tree is the same as parsing the new source from scratch
+#[track_caller]
+fn edit(formula: &mut Formula, range: std::ops::Range<usize>, text: &str) -> Reparse {
+    let reparse = formula.edit(&TextEdit::new(range, text));
+    let expected = parse_tree(formula.source(), ParserConfig::default());
+    match (formula.tree(), expected) {
+        (Ok(tree), Ok(expected)) => assert_eq!(tree, expected, "{}", formula.source()),
+        (Err(_), Err(_)) => {}
+        (tree, expected) => panic!("{}: {:?} != {:?}", formula.source(), tree, expected),
+    }
+    reparse
+}
+
+// =============================================================================
+// Parse Tests
+// =============================================================================
+
+#[test]
+fn edit_reparses_enclosing_group() {
+    let mut formula = Formula::new(r"a + \frac{x}{y + {z}} - b", ParserConfig::default());
+
+    assert_eq!(edit(&mut formula, 18..19, "w^2"), Reparse::Group(17..22));
+    assert_eq!(formula.source(), r"a + \frac{x}{y + {w^2}} - b");
+
+    assert_eq!(edit(&mut formula, 10..11, r"\alpha"), Reparse::Group(9..17));
+    assert_eq!(edit(&mut formula, 19..19, "2"), Reparse::Group(17..29));
+    assert_eq!(formula.source(), r"a + \frac{\alpha}{y2 + {w^2}} - b");
+}
+
+#[test]
+fn typing_into_group() {
+    let mut formula = Formula::new(r"\sqrt{}", ParserConfig::default());
+    for (i, ch) in "x + yz - 1".char_indices() {
+        let at = 6 + i;
+        let reparse = edit(&mut formula, at..at, &ch.to_string());
+        assert_eq!(reparse, Reparse::Group(5..at + 2));
+    }
+    assert_eq!(formula.source(), r"\sqrt{x + yz - 1}");
+
+    for end in (7..formula.source().len()).rev() {
+        let reparse = edit(&mut formula, end - 1..end, "");
+        assert_eq!(reparse, Reparse::Group(5..end));
+    }
+    assert_eq!(formula.source(), r"\sqrt{}");
+
+    // Typing through an incomplete command, which fails to parse until it is finished
+    for (i, ch) in r"\beta".char_indices() {
+        edit(&mut formula, 6 + i..6 + i, &ch.to_string());
+    }
+    assert_eq!(formula.source(), r"\sqrt{\beta}");
+    assert_eq!(edit(&mut formula, 11..11, "2"), Reparse::Group(5..13));
+}
+
+#[test]
+fn edit_outside_group_is_full() {
+    let mut formula = Formula::new(r"x + {y}", ParserConfig::default());
+    assert_eq!(edit(&mut formula, 0..1, "z"), Reparse::Full);
+    // Touching a brace of the group
+    assert_eq!(edit(&mut formula, 4..5, "("), Reparse::Full);
+    assert_eq!(formula.source(), "z + (y}");
+    assert!(formula.tree().is_err());
+}
+
+#[test]
+fn unbalanced_group_is_full() {
+    let mut formula = Formula::new(r"{a} + {b}", ParserConfig::default());
+    assert_eq!(edit(&mut formula, 1..2, "a} + {c"), Reparse::Full);
+    assert_eq!(formula.source(), r"{a} + {c} + {b}");
+
+    assert_eq!(edit(&mut formula, 7..8, "c}"), Reparse::Full);
+    assert!(formula.tree().is_err());
+
+    // Fixing the error parses in full, and groups are reparsed again after
+    assert_eq!(edit(&mut formula, 7..9, "d"), Reparse::Full);
+    assert_eq!(edit(&mut formula, 7..8, "e"), Reparse::Group(6..9));
+}
+
+#[test]
+fn definitions_are_full() {
+    let mut formula = Formula::new(r"\def\x{a} {\x} + {b}", ParserConfig::default());
+    assert_eq!(edit(&mut formula, 18..19, "c"), Reparse::Full);
+
+    let mut formula = Formula::new(r"{b} + \x", ParserConfig::default());
+    assert_eq!(edit(&mut formula, 1..2, r"\gdef\x{y}"), Reparse::Full);
+    assert_eq!(edit(&mut formula, 10..11, "z"), Reparse::Full);
+
+    let mut formula = Formula::new(r"{b} + {c}", ParserConfig::default());
+    assert_eq!(edit(&mut formula, 1..2, r"b\tag{1}"), Reparse::Full);
+}
+
+#[test]
+fn config_macros_are_full() {
+    let mut conf = ParserConfig::default();
+    conf.macros
+        .insert_back_macro("\\x", Arc::new(MacroReplace::Text("y".to_string())));
+
+    let mut formula = Formula::new(r"{a} + {b}", conf);
+    assert_eq!(formula.edit(&TextEdit::new(1..2, r"\x")), Reparse::Full);
+    assert_eq!(formula.edit(&TextEdit::new(7..8, "c")), Reparse::Full);
+}
+
+#[test]
+fn repeated_group_is_full() {
+    // `\pmb` puts its argument in the tree twice
+    let mut formula = Formula::new(r"\pmb{{a}}", ParserConfig::default());
+    assert_eq!(edit(&mut formula, 6..7, "b"), Reparse::Full);
+}
+
+// =============================================================================
+// Layout Tests
+// =============================================================================
+
+#[test]
+fn layout_matches_full_build() {
+    let conf = ParserConfig::default();
+    let options = Options::from_parser_conf(&conf);
+    let mut formula = Formula::new(r"\frac{a^2}{b} + \sqrt{x + \frac{1}{2}}", conf.clone());
+
+    let edits = [(22..23, "y"), (8..9, "3"), (12..12, "c"), (0..0, "{q}")];
+    for (range, text) in edits {
+        formula.layout().unwrap();
+        edit(&mut formula, range, text);
+
+        let tree = parse_tree(formula.source(), conf.clone()).unwrap();
+        assert_eq!(
+            formula.layout().unwrap(),
+            build_ir(&tree, &options),
+            "{}",
+            formula.source()
+        );
+    }
+}
+
+#[test]
+fn layout_reuses_unchanged_subtrees() {
+    let mut formula = Formula::new(
+        r"\frac{a+b}{c} + \sqrt{x} + \frac{a+b}{c}",
+        ParserConfig::default(),
+    );
+    formula.layout().unwrap();
+    let first = formula.layout_stats();
+    // The second fraction is the same as the first
+    assert!(first.reused > 0, "{:?}", first);
+
+    edit(&mut formula, 22..23, "y");
+    formula.layout().unwrap();
+    let stats = formula.layout_stats();
+    // Both fractions are reused, only the square root and its body are built
+    assert_eq!(stats.reused, 2, "{:?}", stats);
+    assert_eq!(stats.built, 2, "{:?}", stats);
+
+    // Nothing changed, so the whole layout is reused
+    formula.layout().unwrap();
+    assert_eq!(formula.layout_stats().built, 0);
+}
+
+#[test]
+fn layout_does_not_reuse_other_styles() {
+    let formula = Formula::new(r"{a+b} + x^{a+b}", ParserConfig::default());
+    formula.layout().unwrap();
+    // The superscript is the same text, but in script style
+    assert_eq!(formula.layout_stats().reused, 0);
+}
+
+#[test]
+fn layout_error() {
+    let formula = Formula::new(r"\frac{a}", ParserConfig::default());
+    assert!(formula.tree().is_err());
+    assert!(formula.layout().is_err());
+}
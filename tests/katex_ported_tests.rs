@@ -6,7 +6,10 @@
 
 use std::panic::{self, AssertUnwindSafe};
 
-use aliter::{parse_tree, parser::ParserConfig, parser::StrictMode, unit::Em};
+use aliter::{
+    parse_node::ParseNode, parse_tree, parser::ParseError, parser::ParserConfig,
+    parser::StrictMode, unit::Em,
+};
 
 // =============================================================================
 // Helper Functions
@@ -717,6 +720,40 @@ fn tag_star_works() {
     assert_parses_with_config(r"a=b \tag*{eq. 1}", conf);
 }
 
+/// The text of every `TextOrd` under `node`, in tree order
+fn text_of(node: &ParseNode, out: &mut String) {
+    match node {
+        ParseNode::TextOrd(ord) => out.push_str(&ord.text),
+        ParseNode::Text(text) => text.body.iter().for_each(|node| text_of(node, out)),
+        ParseNode::OrdGroup(group) => group.body.iter().for_each(|node| text_of(node, out)),
+        _ => {}
+    }
+}
+
+#[test]
+fn tag_keeps_its_argument_in_order() {
+    let mut conf = ParserConfig::default();
+    conf.display_mode = true;
+    let tree = parse_tree(r"x\tag{abc}", conf).unwrap();
+    let ParseNode::Tag(tag) = &tree[0] else {
+        panic!("Expected a tag, got {:?}", tree[0]);
+    };
+    let mut text = String::new();
+    for node in &tag.tag {
+        text_of(node, &mut text);
+    }
+    assert_eq!(text, "(abc)");
+}
+
+#[test]
+fn tag_keeps_control_words_apart_from_letters() {
+    let mut conf = ParserConfig::default();
+    conf.display_mode = true;
+    // `\S a` must not become `\Sa`
+    assert_parses_with_config(r"x\tag{\S a}", conf.clone());
+    assert_parses_with_config(r"x\tag*{\S\,1}", conf);
+}
+
 // =============================================================================
 // Binrel Automatic Class (katex-spec.js:3695-3710)
 // =============================================================================
@@ -1457,12 +1494,35 @@ fn macro_text_or_math_works() {
 
 #[test]
 fn macro_global_requires_def_or_prefix() {
-    assert_fails(r"\global\foo");
+    for expr in [r"\global\foo", r"\global", r"\global x", r"\global\relax"] {
+        let result = parse_tree(expr, ParserConfig::default());
+        assert!(matches!(result, Err(ParseError::InvalidMacroPrefix)), "{}: {:?}", expr, result);
+    }
+    assert_parses(r"\global\long\def\foo{x}\foo");
 }
 
 #[test]
 fn macro_long_requires_def_or_prefix() {
-    assert_fails(r"\long\foo");
+    for expr in [r"\long\foo", r"\long"] {
+        let result = parse_tree(expr, ParserConfig::default());
+        assert!(matches!(result, Err(ParseError::InvalidMacroPrefix)), "{}: {:?}", expr, result);
+    }
+}
+
+#[test]
+fn macro_definitions_require_a_name() {
+    for expr in [r"\def{x}", r"\let&x", r"\futurelet$ab"] {
+        let result = parse_tree(expr, ParserConfig::default());
+        assert!(matches!(result, Err(ParseError::ExpectedControlSequence)), "{}: {:?}", expr, result);
+    }
+    for expr in [r"\def\foo#0{x}", r"\def\foo#2{x}", r"\def\foo#a{x}"] {
+        let result = parse_tree(expr, ParserConfig::default());
+        assert!(matches!(result, Err(ParseError::InvalidArgumentNumber)), "{}: {:?}", expr, result);
+    }
+    assert!(matches!(
+        parse_tree(r"\def\foo#1", ParserConfig::default()),
+        Err(ParseError::UnexpectedEOF)
+    ));
 }
 
 // =============================================================================
@@ -88,6 +88,25 @@ fn render_delims() {
     );
 }
 
+#[test]
+fn render_tall_delims() {
+    // Tall enough that the delimiters are stacked from pieces
+    let tall = r"\begin{matrix}a\\b\\c\\d\\e\\f\end{matrix}";
+    for (open, close) in [
+        ("(", ")"),
+        (r"\{", r"\}"),
+        (r"\lceil", r"\rceil"),
+        (r"\uparrow", r"\Downarrow"),
+        (r"\|", r"\rVert"),
+    ] {
+        let html = render(&format!(r"\left{open}{tall}\right{close}"));
+        assert!(html.contains("<svg"), "expected stacked delimiters for {open} in {html}");
+    }
+    // Small double bars are looked up by the symbol they are written as
+    let html = render(r"\left\| x \right\|");
+    assert!(html.contains("∥"), "expected double bars in {html}");
+}
+
 #[test]
 fn render_rule() {
     let html = render("\\rule{1em}{2em}");
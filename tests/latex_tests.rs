@@ -0,0 +1,461 @@
+use aliter::{
+    latex::{to_latex, to_latex_with, LatexOptions},
+    macr::MacroReplace,
+    parse_node::{EqNoLoc, ParseNode},
+    parse_tree,
+    parser::ParserConfig,
+};
+use proptest::prelude::*;
+use std::sync::Arc;
+
+fn conf() -> ParserConfig {
+    ParserConfig {
+        display_mode: true,
+        trust: true,
+        ..ParserConfig::default()
+    }
+}
+
+fn parse(expr: &str) -> Vec<ParseNode> {
+    match parse_tree(expr, conf()) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to parse: {}\nError: {:?}", expr, err),
+    }
+}
+
+// Helper to check that the written source parses back to the same tree
+fn assert_round_trips(expr: &str) {
+    let tree = parse(expr);
+    for options in [
+        LatexOptions::default(),
+        LatexOptions::default().with_source(expr, conf()),
+    ] {
+        let source = to_latex_with(&tree, &options);
+        let reparsed = parse_tree(&source, conf());
+        assert!(
+            reparsed.as_ref().is_ok_and(|reparsed| reparsed.eq_no_loc(&tree)),
+            "{} was written as {}, which parsed to {:?}",
+            expr,
+            source,
+            reparsed
+        );
+    }
+}
+
+fn assert_writes(expr: &str, expected: &str) {
+    assert_eq!(to_latex(&parse(expr)), expected, "for {}", expr);
+}
+
+fn assert_writes_minimal(expr: &str, expected: &str) {
+    let options = LatexOptions::default().with_minimal_braces(true);
+    assert_eq!(to_latex_with(&parse(expr), &options), expected, "for {}", expr);
+}
+
+// =============================================================================
+// Round trips
+// =============================================================================
+
+#[test]
+fn latex_should_round_trip_symbols() {
+    for expr in [
+        r"x+1=y",
+        r"\alpha x",
+        r"\{\}\_\%\#\&",
+        r"a\,b\quad c~d\;e\!f\ g",
+        r"\lbrace\lt\gt",
+        r"中",
+        r"\char`\A\char'52",
+        r#"\char37 x\char'45\char`\%\char"23\char92"#,
+        r"x\ne0\neq a\not=b\not<c\notin d≠∉",
+        r"\minuso\text{\char126}",
+        r"\text{a \ae x \_ ``b'' --- c~d \textbackslash}",
+    ] {
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_round_trip_scripts() {
+    for expr in [
+        r"x^2_i",
+        r"x^{2}",
+        r"{}^2",
+        r"^2",
+        r"x^{^2}",
+        r"f''",
+        r"f'^2",
+        r"x²",
+        r"x^\frac12",
+        r"x_\text{a}",
+        r"x^\mathbf a",
+        r"x^{\left(a\right)}",
+        r"\sum\limits_i^n",
+        r"\int\nolimits_0",
+        r"\lim_{x\to0}",
+        r"\det\limits",
+        r"\mathop{x}\limits^2",
+        r"\operatorname*{f}\limits_x",
+        r"\underbrace{x}_2",
+        r"\begin{matrix}a\end{matrix}^2",
+    ] {
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_keep_scripts_without_a_base_apart() {
+    // A definition or `\relax` leaves the scripts after it without a base
+    for expr in [
+        r"x\def\a{b}^2",
+        r"\quad_x",
+        r"x\relax^2_i",
+        r"\begin{matrix}a&\relax_x\end{matrix}",
+    ] {
+        assert_round_trips(expr);
+    }
+    assert_writes(r"x\relax^2", r"x\relax^2");
+    assert_writes(r"\relax^2", r"^2");
+}
+
+#[test]
+fn latex_should_round_trip_fractions() {
+    for expr in [
+        r"\frac12",
+        r"\dfrac{a}{b}\tfrac{a}{b}",
+        r"\binom{a}{b}\dbinom{a}{b}\tbinom{a}{b}",
+        r"\cfrac{1}{2+\cfrac{1}{3}}",
+        r"a \over b",
+        r"{a \choose b}+1",
+        r"a\atop b",
+        r"a\brace b",
+        r"a\brack b",
+        r"a\above2pt b",
+        r"\genfrac(]{1pt}{2}{a}{b}",
+        r"\genfrac{}{}{}{}ab",
+        r"\genfrac\{.{0pt}{0}ab",
+        r"{a \over b}^2",
+    ] {
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_round_trip_functions() {
+    for expr in [
+        r"\sqrt x\sqrt\alpha\sqrt{x}\sqrt[3]{x}\sqrt[{a}{b}]{x}\sqrt\frac12",
+        r"\mathbf{ab}\mathit{\Gamma}\boldsymbol{x}\bm x",
+        r"\mathrel{=}\mathbin{+}\mathord{x}\mathopen(\mathclose)\mathpunct,\mathinner{x}",
+        r"\stackrel{a}{=}\overset{a}{b}\underset{a}{b}",
+        r"\operatorname{sin}x\sin\max",
+        r"\text{hi there}\textbf{a}\textit{b}\emph{c}",
+        r"\text{{\bfseries a}b\itshape c}",
+        r"\color{red} x",
+        r"\textcolor{red}{x}\textcolor{#f00}{y}",
+        r"{\tiny x}y\Huge z",
+        r"a{\scriptstyle b}\displaystyle c",
+        r"\text{$x$}",
+        r"\left(x\middle| y\right\rangle",
+        r"\left\{x\right.\left\|x\right\backslash",
+        r"\bigl(\Bigr)\biggm|\Bigg\{",
+        r"\hat x\hat{ab}\widehat{ab}\vec{x}",
+        r#"\text{\^a \'e \"{o}}"#,
+        r"\underbrace{x}\overbracket[1pt][2pt]{x}\underbracket{x}",
+        r"\overbracket[2pt]{x}\underbracket[0pt]{x}",
+        r"\underline{x}\overline{x}\utilde{x}",
+        r"\xrightarrow[a]{b}\xleftarrow{c}",
+        r"\boxed{x}\fbox{a}\colorbox{red}{x}\fcolorbox{red}{blue}{x}",
+        r"\cancel{x}\bcancel{y}\xcancel{z}\sout{w}",
+        r"\mathllap{x}\mathrlap{y}\mathclap{z}",
+        r"\phantom{x}\hphantom{x}\vphantom{x}",
+        r"\smash[t]{x}\smash[b]{x}\smash{x}",
+        r"\rule[1pt]{2em}{3ex}",
+        r"\kern1em\mkern3mu\hskip2pt",
+        r"\raisebox{1em}{x}",
+        r"\vcenter{x}",
+        r"\verb|x+y|\verb*!a b!",
+        r"\mathchoice{a}{b}{c}{d}",
+        r"\html@mathml{a}{b}",
+        r"\begingroup x\endgroup",
+        r"\relax x",
+        r"a\\b\\[2pt]c\\ [d]",
+    ] {
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_round_trip_trusted_functions() {
+    for expr in [
+        r"\href{http://a.b/~c#d}{x}",
+        r"\url{http://a.b/~c_d}",
+        r"\htmlId{a}{x}\htmlClass{b}{y}\htmlStyle{color: red}{z}",
+        r"\htmlData{foo=a,bar=b}{x}",
+        r"\includegraphics[height=1em]{a.png}",
+    ] {
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_round_trip_untrusted_functions() {
+    let conf = ParserConfig {
+        trust: false,
+        ..conf()
+    };
+    for expr in [
+        r"\href{http://a.b/~c#d}{x}",
+        r"\url{http://a.b/~c_d}",
+        r"\htmlId{a}{x}\htmlClass{b}{y}",
+        r"\includegraphics[height=1em]{a.png}",
+    ] {
+        let tree = parse_tree(expr, conf.clone()).unwrap();
+        let source = to_latex(&tree);
+        let reparsed = parse_tree(&source, conf.clone()).unwrap();
+        assert!(reparsed.eq_no_loc(&tree), "{} was written as {}", expr, source);
+    }
+}
+
+#[test]
+fn latex_should_round_trip_environments() {
+    for expr in [
+        r"\begin{matrix}a&b\\c&d\end{matrix}",
+        r"\begin{pmatrix}a\\b\end{pmatrix}\begin{bmatrix*}[r]a\end{bmatrix*}",
+        r"\begin{Bmatrix}a\end{Bmatrix}\begin{vmatrix}a\end{vmatrix}\begin{Vmatrix}a\end{Vmatrix}",
+        r"\begin{smallmatrix}a&b\end{smallmatrix}",
+        r"\begin{array}{c|l:r}\hline a&b&c\\\hdashline d\\\hline\end{array}",
+        r"\begin{darray}{cc}a&b\end{darray}",
+        r"\sum_{\begin{subarray}{l}a\\b\end{subarray}}",
+        r"\begin{cases}a&b\\c\end{cases}\begin{dcases}a\end{dcases}",
+        r"\begin{rcases}a\end{rcases}\begin{drcases}a\end{drcases}",
+        r"\begin{align}a&=b\\c&=d\nonumber\end{align}",
+        r"\begin{align*}a&=b\tag{1}\\c\end{align*}",
+        r"\begin{aligned}a&=b\\[2pt]c\end{aligned}",
+        r"\begin{alignat}{2}a&b&c&d\end{alignat}",
+        r"\begin{alignedat}{1}a&b\end{alignedat}",
+        r"\begin{gather}a\\b\end{gather}\begin{gathered}a\end{gathered}",
+        r"\begin{equation}a\end{equation}\begin{equation*}a\end{equation*}",
+        r"\begin{CD}A @>a>b> B\\@VVV @AAA\\C @= D\end{CD}",
+        r"\begin{matrix}a\\\end{matrix}",
+        r"\begin{matrix}\end{matrix}",
+        r"\def\arraystretch{1.5}\begin{array}{c}a\\b\end{array}",
+        r"{\def\arraystretch{2}\begin{pmatrix}a\end{pmatrix}}\begin{matrix}b\end{matrix}",
+        r"\def\arraystretch{2}\begin{matrix}\def\arraystretch{1}\begin{matrix}a\end{matrix}\end{matrix}",
+        r"\def\arraystretch{2}\begin{cases}a\end{cases}\begin{smallmatrix}b\end{smallmatrix}",
+    ] {
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_round_trip_tags() {
+    assert_round_trips(r"\tag{1} x");
+    assert_round_trips(r"\tag*{hi} x");
+    assert_round_trips(r"x\tag{a\ b}");
+    assert_writes(r"\tag{1} x", r"x\tag{1}");
+}
+
+// =============================================================================
+// Canonical output
+// =============================================================================
+
+#[test]
+fn latex_should_write_infix_as_prefix() {
+    assert_writes(r"a \over b", r"\frac{a}{b}");
+    assert_writes(r"1 + a \choose b", r"\binom{1+a}{b}");
+    assert_writes(r"x^{a\over b}", r"x^{\frac{a}{b}}");
+    assert_writes(r"a\above{2pt}b", r"\genfrac{}{}{2pt}{}{a}{b}");
+}
+
+#[test]
+fn latex_should_expand_macros() {
+    assert_writes(r"\bold x\Bbb R", r"\mathbf{x}\mathbb{R}");
+    assert_writes(r"\def\foo#1{#1^2}\foo{y}", r"y^2");
+    assert_writes(r"\operatorname{f}", r"\operatorname{f}");
+
+    let mut conf = conf();
+    conf.macros.insert_back_macro(
+        "\\half".to_string(),
+        Arc::new(MacroReplace::Text("\\frac12".to_string())),
+    );
+    let tree = parse_tree(r"\half+x", conf).unwrap();
+    assert_eq!(to_latex(&tree), r"\frac{1}{2}+x");
+}
+
+#[test]
+fn latex_should_write_builtin_macros_by_name() {
+    for (expr, expected) in [
+        (r"a\neq b", r"a\neq b"),
+        (r"a\ne b", r"a\neq b"),
+        (r"\not= \notin", r"\not=\notin"),
+        (r"1+\cdots+n", r"1+\cdots+n"),
+        (r"a\iff b \implies c", r"a\iff b\implies c"),
+        (r"f\colon A", r"f\colon A"),
+        (r"a\bmod b", r"a\bmod b"),
+        (r"\TeX\text{ and \LaTeX}", r"\TeX\text{ and \LaTeX}"),
+        (r"x^{\neq}_\cdots", r"x^{\neq}_\cdots"),
+    ] {
+        assert_writes(expr, expected);
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_write_spacing_commands() {
+    for (expr, expected) in [
+        (r"a\,b\:c\;d\!e", r"a\,b\:c\;d\!e"),
+        (r"a\thinspace b\medspace c\> d", r"a\,b\:c\:d"),
+        (r"a\quad b\qquad c", r"a\quad b\qquad c"),
+        (r"\mkern3mu\kern1em", r"\,\quad"),
+        (r"\text{a\,b\kern.1667em c}", r"\text{a\,b\,c}"),
+        (r"a\mkern7mu b\kern-2pt c", r"a\mkern7mu b\kern-2pt c"),
+        (r"\mkern7mu 1\kern2pt 2", r"\mkern7mu 1\kern2pt 2"),
+    ] {
+        assert_writes(expr, expected);
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_write_characters_with_char() {
+    for (expr, expected) in [
+        (r"\char`\{", r"\char`\{"),
+        (r"\char123 1", r"\char`\{1"),
+        (r"\char`\%\char`a", r"\char`\%\char`a"),
+        (r"\text{\char`\&}", r"\text{\char`\&}"),
+        (r"\char32 1", r"\char32 1"),
+    ] {
+        assert_writes(expr, expected);
+        assert_round_trips(expr);
+    }
+}
+
+#[test]
+fn latex_should_separate_control_words() {
+    assert_writes(r"\alpha x", r"\alpha x");
+    assert_writes(r"\alpha{}x", r"\alpha{}x");
+    assert_writes(r"\alpha 1", r"\alpha1");
+    assert_writes(r"a\\ [b]", r"a\\ [b]");
+}
+
+#[test]
+fn latex_should_drop_redundant_braces() {
+    assert_writes_minimal(r"\frac{1}{2}", r"\frac12");
+    assert_writes_minimal(r"\frac{\alpha}{ab}", r"\frac\alpha{ab}");
+    assert_writes_minimal(r"x^{2}_{i}", r"x^2_i");
+    assert_writes_minimal(r"x^{\alpha}", r"x^\alpha");
+    assert_writes_minimal(r"x^{ab}", r"x^{ab}");
+    assert_writes_minimal(r"\mathbf{x}", r"\mathbf x");
+    // The argument of `\sqrt` is kept as it was parsed
+    assert_writes_minimal(r"\sqrt{x}", r"\sqrt{x}");
+    assert_writes_minimal(r"\sqrt x", r"\sqrt x");
+}
+
+#[test]
+fn latex_should_keep_source_macros() {
+    let expr = r"\bold x + \N\cdot a \over b";
+    let tree = parse(expr);
+    let options = LatexOptions::default().with_source(expr, conf());
+    assert_eq!(to_latex_with(&tree, &options), r"\frac{\bold x+\N\cdot a}{b}");
+
+    let mut conf = conf();
+    conf.macros.insert_back_macro(
+        "\\half".to_string(),
+        Arc::new(MacroReplace::Text("\\frac12".to_string())),
+    );
+    let expr = r"\half+x";
+    let tree = parse_tree(expr, conf.clone()).unwrap();
+    let options = LatexOptions::default().with_source(expr, conf);
+    assert_eq!(to_latex_with(&tree, &options), r"\half+x");
+
+    // Without the macro, the source can't be used
+    let options = LatexOptions::default().with_source(expr, ParserConfig::default());
+    assert_eq!(to_latex_with(&tree, &options), r"\frac{1}{2}+x");
+}
+
+// =============================================================================
+// Property tests
+// =============================================================================
+
+fn atom() -> impl Strategy<Value = String> {
+    prop::sample::select(vec![
+        "x", "1", "+", "=", "(", ")", "|", ",", "'", r"\alpha", r"\infty", r"\sum", r"\int",
+        r"\sin", r"\lim", r"\cdot", r"\{", r"\}", r"\,", r"\quad", "~", r"\prime", r"\%",
+        r"\lbrace", r"\bold x", r"\R", r"\char`\A", "α", "中", r"\\", r"\ne", r"\not=",
+        r"\notin", r"\char37", r"\char`\&", r"\overbracket[2pt]{x}", r"\overbracket[1pt][2pt]{x}",
+        r"\minuso", r"\cdots", r"\iff", r"\colon", r"\bmod", r"\TeX", r"\!", r"\kern2pt",
+        r"\mkern7mu", r"\text{a\,\TeX}",
+    ])
+    .prop_map(str::to_string)
+}
+
+fn fragment() -> impl Strategy<Value = String> {
+    atom().prop_recursive(4, 48, 4, |inner| {
+        let list = prop::collection::vec(inner.clone(), 1..4).prop_map(|parts| parts.join(" "));
+        prop_oneof![
+            list.clone().prop_map(|a| format!("{{{}}}", a)),
+            (inner.clone(), list.clone()).prop_map(|(a, b)| format!("{}^{{{}}}", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("{}_{}", a, b)),
+            (list.clone(), list.clone()).prop_map(|(a, b)| format!(r"\frac{{{}}}{{{}}}", a, b)),
+            (list.clone(), list.clone()).prop_map(|(a, b)| format!(r"{{{} \over {}}}", a, b)),
+            (list.clone(), list.clone()).prop_map(|(a, b)| format!(r"{{{} \choose {}}}", a, b)),
+            (list.clone(), list.clone()).prop_map(|(a, b)| format!(r"{{{} \atop {}}}", a, b)),
+            inner.clone().prop_map(|a| format!(r"\sqrt {}", a)),
+            (list.clone(), list.clone()).prop_map(|(a, b)| format!(r"\sqrt[{}]{{{}}}", a, b)),
+            list.clone().prop_map(|a| format!(r"\left( {} \right]", a)),
+            list.clone().prop_map(|a| format!(r"\mathbf{{{}}}", a)),
+            list.clone().prop_map(|a| format!(r"\hat{{{}}}", a)),
+            list.clone().prop_map(|a| format!(r"\overbrace{{{}}}^x", a)),
+            list.clone().prop_map(|a| format!(r"\text{{a {} b}}", a)),
+            list.clone().prop_map(|a| format!(r"\textcolor{{blue}}{{{}}}", a)),
+            list.clone().prop_map(|a| format!(r"{{\color{{red}} {}}}", a)),
+            list.clone().prop_map(|a| format!(r"{{\scriptstyle {}}}", a)),
+            list.clone().prop_map(|a| format!(r"{{\large {}}} x", a)),
+            list.clone().prop_map(|a| format!(r"\mathrel{{{}}}", a)),
+            list.clone().prop_map(|a| format!(r"\operatorname*{{{}}}\limits_x", a)),
+            (list.clone(), list.clone()).prop_map(|(a, b)| format!(r"\stackrel{{{}}}{{{}}}", a, b)),
+            (list.clone(), list.clone())
+                .prop_map(|(a, b)| format!(r"\begin{{pmatrix}}{}&{}\\x\end{{pmatrix}}", a, b)),
+            list.clone().prop_map(|a| {
+                format!(r"{{\def\arraystretch{{1.5}} \begin{{array}}{{c}}{}\\x\end{{array}}}}", a)
+            }),
+            (list.clone(), list)
+                .prop_map(|(a, b)| format!(r"\begin{{aligned}}{}&={}\\[1pt]x\end{{aligned}}", a, b)),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn latex_should_round_trip_parse_trees(expr in fragment()) {
+        let tree = parse_tree(&expr, conf());
+        prop_assume!(tree.is_ok());
+        let tree = tree.unwrap();
+
+        let source = to_latex(&tree);
+        let reparsed = parse_tree(&source, conf());
+        prop_assert!(
+            reparsed.as_ref().is_ok_and(|reparsed| reparsed.eq_no_loc(&tree)),
+            "{} was written as {}", expr, source
+        );
+
+        let options = LatexOptions::default().with_source(&expr, conf());
+        let source = to_latex_with(&tree, &options);
+        let reparsed = parse_tree(&source, conf());
+        prop_assert!(
+            reparsed.as_ref().is_ok_and(|reparsed| reparsed.eq_no_loc(&tree)),
+            "{} was written from source as {}", expr, source
+        );
+    }
+
+    #[test]
+    fn latex_minimal_braces_should_be_stable(expr in fragment()) {
+        let tree = parse_tree(&expr, conf());
+        prop_assume!(tree.is_ok());
+
+        let options = LatexOptions::default().with_minimal_braces(true);
+        let source = to_latex_with(&tree.unwrap(), &options);
+        let reparsed = parse_tree(&source, conf());
+        prop_assert!(reparsed.is_ok(), "{} was written as {}", expr, source);
+        prop_assert_eq!(to_latex_with(&reparsed.unwrap(), &options), source.clone(), "for {}", expr);
+    }
+}
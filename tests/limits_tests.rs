@@ -0,0 +1,562 @@
+use std::{thread, time::Instant};
+
+use aliter::{
+    cache::RenderCache,
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    render_to_string,
+    tree::OutputType,
+};
+use proptest::prelude::*;
+
+/// The stack of a thread spawned without a size, which the default depth fits in even in a
+/// debug build
+const STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// Render the expression on a thread with a bounded stack, so that a stack overflow in the
+/// parser or builders aborts the test rather than passing by luck on a large stack
+fn render_on_thread(expr: String, conf: ParserConfig) -> Result<String, ParseError> {
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || render_to_string(&expr, conf, OutputType::HtmlAndMathml))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+/// `open` repeated `n` times, then `x`, then `close` repeated `n` times
+fn nested(open: &str, close: &str, n: usize) -> String {
+    open.repeat(n) + "x" + &close.repeat(n)
+}
+
+fn assert_too_deep(expr: String) {
+    let result = render_on_thread(expr.clone(), ParserConfig::default());
+    assert!(
+        matches!(result, Err(ParseError::TooDeeplyNested)),
+        "Expected TooDeeplyNested for {}..., got {:?}",
+        &expr[..40],
+        result.map(|markup| markup.len())
+    );
+}
+
+// =============================================================================
+// Nesting Depth Tests
+// =============================================================================
+
+#[test]
+fn nesting_depth_counts_groups_and_arguments() {
+    let conf = |max_depth| ParserConfig {
+        max_depth: Some(max_depth),
+        ..ParserConfig::default()
+    };
+
+    assert!(parse_tree(r"\sqrt{x}", conf(2)).is_ok());
+    assert!(matches!(
+        parse_tree(r"\sqrt{x}", conf(1)),
+        Err(ParseError::TooDeeplyNested)
+    ));
+    assert!(matches!(
+        parse_tree("{{{x}}}", conf(3)),
+        Err(ParseError::TooDeeplyNested)
+    ));
+
+    // An argument is one level, whether its command takes it as a primitive or not
+    assert!(parse_tree(r"\sqrt{\sqrt{x}}", conf(3)).is_ok());
+    assert!(parse_tree(r"\frac{1}{\frac{1}{x}}", conf(3)).is_ok());
+    assert!(parse_tree(r"{\sqrt x}", conf(3)).is_ok());
+    assert!(matches!(
+        parse_tree(r"{\sqrt x}", conf(2)),
+        Err(ParseError::TooDeeplyNested)
+    ));
+    assert!(matches!(
+        parse_tree(r"\sqrt{\sqrt{x}}", conf(2)),
+        Err(ParseError::TooDeeplyNested)
+    ));
+
+    // Siblings don't add to the depth
+    assert!(parse_tree(&"{x}".repeat(1000), conf(2)).is_ok());
+}
+
+#[test]
+fn nesting_depth_can_be_unlimited() {
+    let conf = ParserConfig {
+        max_depth: None,
+        ..ParserConfig::default()
+    };
+    assert!(render_on_thread(nested("{", "}", 50), conf).is_ok());
+}
+
+#[test]
+fn default_depth_allows_reasonable_nesting() {
+    assert!(render_on_thread(nested(r"\frac{1}{", "}", 10), ParserConfig::default()).is_ok());
+    assert!(render_on_thread(nested(r"\sqrt{", "}", 10), ParserConfig::default()).is_ok());
+    assert!(render_on_thread(nested(r"\left(", r"\right)", 10), ParserConfig::default()).is_ok());
+}
+
+#[test]
+fn default_depth_is_the_same_for_every_group() {
+    let openers = [
+        ("{", "}"),
+        (r"\sqrt{", "}"),
+        (r"\sqrt[3]{", "}"),
+        (r"\frac{1}{", "}"),
+        ("x^{", "}"),
+        (r"\left(", r"\right)"),
+        (r"\begin{matrix}", r"\end{matrix}"),
+        (r"\textcolor{red}{", "}"),
+        (r"\begingroup ", r"\endgroup "),
+    ];
+    for (open, close) in openers {
+        assert!(parse_tree(&nested(open, close, 15), ParserConfig::default()).is_ok(), "{}", open);
+        assert!(
+            matches!(
+                parse_tree(&nested(open, close, 16), ParserConfig::default()),
+                Err(ParseError::TooDeeplyNested)
+            ),
+            "{}",
+            open
+        );
+    }
+}
+
+#[test]
+fn default_depth_fits_in_a_thread_stack() {
+    // The constructs that take the most stack for each level
+    let openers = [
+        (r"\begin{pmatrix}", r"\end{pmatrix}"),
+        (r"\begin{cases}", r"\end{cases}"),
+        (r"\underbrace{", "}_{a}"),
+        (r"\overbrace{", "}^{a}"),
+        (r"\xrightarrow{", "}"),
+        (r"\frac{1}{", "}"),
+        (r"\sqrt{", "}"),
+        ("{", "}"),
+    ];
+    for (open, close) in openers {
+        // As many levels as the default depth accepts
+        let levels = (1..)
+            .take_while(|&n| parse_tree(&nested(open, close, n), ParserConfig::default()).is_ok())
+            .last()
+            .unwrap();
+        let result = render_on_thread(nested(open, close, levels), ParserConfig::default());
+        assert!(result.is_ok(), "{} nested {} times: {:?}", open, levels, result.err());
+    }
+}
+
+#[test]
+fn deep_braces_do_not_overflow() {
+    assert_too_deep(nested("{", "}", 100_000));
+    // Unbalanced, so nothing would ever close them
+    assert_too_deep("{".repeat(100_000));
+}
+
+#[test]
+fn deep_commands_do_not_overflow() {
+    assert_too_deep(nested(r"\sqrt{", "}", 10_000));
+    assert_too_deep(r"\sqrt{".repeat(10_000));
+    assert_too_deep(nested(r"\frac{", "}{y}", 10_000));
+    assert_too_deep(nested(r"\underbrace{", "}_{x}", 10_000));
+    assert_too_deep(nested("x^{", "}", 10_000));
+    assert_too_deep(nested(r"\left(", r"\right)", 10_000));
+    assert_too_deep(nested(r"\text{", "}", 10_000));
+    // Prefixes, which nest without braces
+    assert_too_deep(r"\global".repeat(10_000) + r"\def\a{x}");
+}
+
+#[test]
+fn deep_environments_do_not_overflow() {
+    assert_too_deep(nested(r"\begin{matrix}", r"\end{matrix}", 10_000));
+    assert_too_deep(nested(r"\begin{array}{c}", r"\end{array}", 10_000));
+}
+
+#[test]
+fn deep_macros_do_not_overflow() {
+    assert_too_deep(r"\def\a#1{\sqrt{#1}}".to_string() + &nested(r"\a{", "}", 10_000));
+}
+
+#[test]
+fn too_deep_is_rendered_when_not_thrown() {
+    let conf = ParserConfig {
+        throw_on_error: false,
+        ..ParserConfig::default()
+    };
+    let markup = render_on_thread(nested("{", "}", 100_000), conf).unwrap();
+    assert!(markup.contains("katex-error"));
+    assert!(markup.contains("TooDeeplyNested"));
+}
+
+/// A piece of input that nests whatever comes after it, and what closes it again
+fn opener() -> impl Strategy<Value = (&'static str, &'static str)> {
+    prop::sample::select(vec![
+        ("{", "}"),
+        (r"\sqrt{", "}"),
+        (r"\frac{", "}{y}"),
+        (r"\frac{y}{", "}"),
+        (r"\underbrace{", "}"),
+        (r"\overbrace{", "}^{z}"),
+        ("x^{", "}"),
+        ("x_{", "}"),
+        (r"\left(", r"\right)"),
+        (r"\left\{", r"\middle| y \right."),
+        (r"\text{$", "$}"),
+        (r"\color{red}{", "}"),
+        (r"\mathbf{", "}"),
+        (r"\hat{", "}"),
+        (r"\xrightarrow{", "}"),
+        (r"\begin{matrix}", r"\end{matrix}"),
+        (r"\begin{array}{c}", r"\end{array}"),
+        (r"\textbf{\(", r"\)}"),
+        (r"\sum_{", "}"),
+    ])
+}
+
+/// A piece of input that may close what is open, switch modes, or be out of place.
+/// `\\`, `\hbox` and `\middle` are left out, as they aren't rendered yet.
+fn fragment() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![
+        "}",
+        "}{y}",
+        "}^{z}",
+        "$",
+        "$}",
+        r"\(",
+        r"\)",
+        r"\)}",
+        r"\[",
+        r"\]",
+        r"\text{",
+        r"\textbf{",
+        r"\right)",
+        r"\right.",
+        r"\end{matrix}",
+        r"\end{array}",
+        r"\global",
+        r"\long",
+        r"\global\foo",
+        r"\def",
+        r"\def\a#0",
+        r"\let",
+        r"\futurelet",
+        "&",
+        "^",
+        "_",
+        "x",
+    ])
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn adversarial_nesting_does_not_overflow(
+        openers in prop::collection::vec(opener(), 100..2000),
+        balanced in any::<bool>(),
+    ) {
+        let mut expr: String = openers.iter().map(|(open, _)| *open).collect();
+        expr.push('x');
+        if balanced {
+            expr.extend(openers.iter().rev().map(|(_, close)| *close));
+        }
+
+        // Every opener nests by at least one level, though unbalanced input may be rejected
+        // for running out before the parser gets that deep
+        let result = render_on_thread(expr.clone(), ParserConfig::default());
+        prop_assert!(
+            matches!(result, Err(ParseError::TooDeeplyNested)) || (!balanced && result.is_err()),
+            "Expected TooDeeplyNested for {}, got {:?}", expr, result.map(|markup| markup.len())
+        );
+    }
+}
+
+proptest! {
+    // These are shallow, so they are cheap enough to try many more of
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn malformed_nesting_does_not_overflow(
+        levels in prop::collection::vec(
+            (
+                opener().prop_filter("\\middle isn't rendered yet", |(_, close)| {
+                    !close.contains(r"\middle")
+                }),
+                prop::collection::vec(fragment(), 0..3),
+                prop::option::weighted(0.3, prop::option::of(fragment())),
+            ),
+            1..12,
+        ),
+        throw_on_error in any::<bool>(),
+    ) {
+        // Each level is an opener with some fragments after it, and its close may be missing
+        // or replaced by another fragment. This stays shallower than the default depth, so
+        // that the parser gets as far as the closes
+        let mut expr = String::new();
+        for ((open, _), inside, _) in &levels {
+            expr.push_str(open);
+            expr.extend(inside.iter().copied());
+        }
+        expr.push('x');
+        for ((_, close), _, wrong) in levels.iter().rev() {
+            match wrong {
+                Some(wrong) => expr.push_str(wrong.unwrap_or("")),
+                None => expr.push_str(close),
+            }
+        }
+
+        let conf = ParserConfig {
+            throw_on_error,
+            ..ParserConfig::default()
+        };
+
+        // Whatever is wrong with the input, rendering returns rather than panicking or
+        // overflowing
+        let result = render_on_thread(expr.clone(), conf);
+        prop_assert!(throw_on_error || result.is_ok(), "{}: {:?}", expr, result);
+    }
+}
+
+#[test]
+fn scripts_after_definitions_are_rendered() {
+    // Found by `malformed_nesting_does_not_overflow`, where the definition was left as the base
+    // of the scripts
+    for expr in [
+        r"x\def\a{b}^2",
+        r"{\futurelet\sum_{x}}",
+        r"{\def\overbrace{x}^{z}}",
+        r"\let\a=b'",
+        r"x\global\def\a{b}_1^2\a",
+    ] {
+        for throw_on_error in [false, true] {
+            let conf = ParserConfig {
+                throw_on_error,
+                ..ParserConfig::default()
+            };
+            let result = render_on_thread(expr.to_string(), conf);
+            assert!(result.is_ok(), "{}: {:?}", expr, result);
+        }
+    }
+}
+
+// =============================================================================
+// Node Count Tests
+// =============================================================================
+
+#[test]
+fn node_count_is_limited() {
+    let conf = |max_nodes| ParserConfig {
+        max_nodes: Some(max_nodes),
+        ..ParserConfig::default()
+    };
+
+    assert!(parse_tree("abc", conf(3)).is_ok());
+    assert!(matches!(
+        parse_tree("abcd", conf(3)),
+        Err(ParseError::TooManyNodes)
+    ));
+    // The nodes inside groups and arguments count too
+    assert!(parse_tree(r"\frac{a}{b}", conf(3)).is_ok());
+    assert!(matches!(
+        parse_tree(r"\frac{a}{bc}", conf(3)),
+        Err(ParseError::TooManyNodes)
+    ));
+    assert!(matches!(
+        parse_tree(&"x".repeat(100_000), conf(10_000)),
+        Err(ParseError::TooManyNodes)
+    ));
+}
+
+#[test]
+fn node_count_includes_macro_expansions() {
+    let conf = ParserConfig {
+        max_nodes: Some(1000),
+        ..ParserConfig::default()
+    };
+    let expr = r"\def\a{xxxxxxxxxx}\def\b{\a\a\a\a\a\a\a\a\a\a}\def\c{\b\b\b\b\b\b\b\b\b\b}\c\c";
+    assert!(matches!(
+        parse_tree(expr, conf),
+        Err(ParseError::TooManyNodes)
+    ));
+}
+
+// =============================================================================
+// Array Cell Tests
+// =============================================================================
+
+#[test]
+fn array_cells_are_limited() {
+    let conf = |max_array_cells| ParserConfig {
+        max_array_cells: Some(max_array_cells),
+        ..ParserConfig::default()
+    };
+
+    let matrix = r"\begin{matrix} a & b \\ c & d \end{matrix}";
+    assert!(parse_tree(matrix, conf(4)).is_ok());
+    assert!(matches!(
+        parse_tree(matrix, conf(3)),
+        Err(ParseError::TooManyArrayCells)
+    ));
+}
+
+#[test]
+fn empty_array_cells_are_limited() {
+    let conf = ParserConfig {
+        max_array_cells: Some(10_000),
+        ..ParserConfig::default()
+    };
+
+    let wide = format!(r"\begin{{matrix}}{}\end{{matrix}}", "&".repeat(100_000));
+    assert!(matches!(
+        parse_tree(&wide, conf.clone()),
+        Err(ParseError::TooManyArrayCells)
+    ));
+    let tall = format!(r"\begin{{matrix}}{}\end{{matrix}}", r"\\".repeat(100_000));
+    assert!(matches!(
+        parse_tree(&tall, conf.clone()),
+        Err(ParseError::TooManyArrayCells)
+    ));
+    let cd = format!(r"\begin{{CD}}{}\end{{CD}}", r"\\".repeat(100_000));
+    let conf = ParserConfig {
+        display_mode: true,
+        ..conf
+    };
+    assert!(matches!(
+        parse_tree(&cd, conf),
+        Err(ParseError::TooManyArrayCells)
+    ));
+}
+
+#[test]
+fn array_cells_are_counted_across_arrays() {
+    let conf = ParserConfig {
+        max_array_cells: Some(5),
+        ..ParserConfig::default()
+    };
+
+    let inner = r"\begin{matrix} a & b \end{matrix}";
+    let outer = format!(r"\begin{{matrix}} {inner} & {inner} \end{{matrix}}");
+    assert!(matches!(
+        parse_tree(&outer, conf),
+        Err(ParseError::TooManyArrayCells)
+    ));
+}
+
+// =============================================================================
+// Output Size Tests
+// =============================================================================
+
+#[test]
+fn output_size_is_limited() {
+    let unlimited = render_to_string("x^2", ParserConfig::default(), OutputType::Html).unwrap();
+
+    let conf = |max_output_size| ParserConfig {
+        max_output_size: Some(max_output_size),
+        ..ParserConfig::default()
+    };
+    let markup = render_to_string("x^2", conf(unlimited.len()), OutputType::Html).unwrap();
+    assert_eq!(markup, unlimited);
+    assert!(matches!(
+        render_to_string("x^2", conf(unlimited.len() - 1), OutputType::Html),
+        Err(ParseError::OutputTooLarge)
+    ));
+}
+
+#[test]
+fn output_too_large_is_rendered_when_not_thrown() {
+    let conf = ParserConfig {
+        throw_on_error: false,
+        max_output_size: Some(1000),
+        ..ParserConfig::default()
+    };
+    let markup = render_to_string(&"x".repeat(1000), conf, OutputType::HtmlAndMathml).unwrap();
+    assert!(markup.contains("katex-error"));
+    assert!(markup.contains("OutputTooLarge"));
+}
+
+#[test]
+fn output_size_is_limited_when_cached() {
+    let conf = ParserConfig {
+        max_output_size: Some(100),
+        ..ParserConfig::default()
+    };
+    let mut cache = RenderCache::new();
+    assert!(matches!(
+        cache.render("x^2", &conf, OutputType::Html),
+        Err(ParseError::OutputTooLarge)
+    ));
+    // The limit is part of the key
+    assert!(cache
+        .render("x^2", &ParserConfig::default(), OutputType::Html)
+        .is_ok());
+}
+
+// =============================================================================
+// Step Budget Tests
+// =============================================================================
+
+#[test]
+fn steps_are_limited() {
+    let conf = |max_steps| ParserConfig {
+        max_steps: Some(max_steps),
+        ..ParserConfig::default()
+    };
+
+    assert!(parse_tree("x+y", conf(100)).is_ok());
+    assert!(matches!(
+        parse_tree(&"x".repeat(1000), conf(100)),
+        Err(ParseError::TooManySteps)
+    ));
+}
+
+#[test]
+fn steps_include_unexpanded_macros() {
+    // \relax doesn't make any nodes or expansions, but each one is still a step
+    let conf = ParserConfig {
+        max_steps: Some(1000),
+        max_expand: None,
+        ..ParserConfig::default()
+    };
+    assert!(matches!(
+        parse_tree(&r"\relax".repeat(10_000), conf),
+        Err(ParseError::TooManySteps)
+    ));
+}
+
+// =============================================================================
+// Deadline Tests
+// =============================================================================
+
+#[test]
+fn deadline_stops_parsing() {
+    let conf = ParserConfig {
+        deadline: Some(Instant::now()),
+        ..ParserConfig::default()
+    };
+    assert!(matches!(
+        parse_tree("x", conf),
+        Err(ParseError::DeadlineExceeded)
+    ));
+
+    let conf = ParserConfig {
+        deadline: Some(Instant::now() + std::time::Duration::from_secs(60)),
+        ..ParserConfig::default()
+    };
+    assert!(parse_tree("x", conf).is_ok());
+}
+
+#[test]
+fn deadline_errors_are_not_cached() {
+    let conf = ParserConfig {
+        throw_on_error: false,
+        deadline: Some(Instant::now()),
+        ..ParserConfig::default()
+    };
+    let mut cache = RenderCache::new();
+    let markup = cache.render("x", &conf, OutputType::Html).unwrap();
+    assert!(markup.contains("DeadlineExceeded"));
+
+    let conf = ParserConfig {
+        deadline: None,
+        ..conf
+    };
+    let markup = cache.render("x", &conf, OutputType::Html).unwrap();
+    assert!(!markup.contains("katex-error"));
+}
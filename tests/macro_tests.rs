@@ -1,4 +1,13 @@
-use aliter::{parse_tree, parser::ParserConfig, macr::{Macros, MacroReplace}};
+use aliter::{
+    ir::{build_ir, MathElement},
+    macr::{MacroReplace, Macros},
+    parse_node::{EqNoLoc, ParseNode},
+    parse_tree,
+    parser::ParserConfig,
+    render_to_html_tree, render_to_mathml_tree,
+    tree::VirtualNode,
+    Options,
+};
 use std::sync::Arc;
 
 // Helper to check parsing succeeds
@@ -210,6 +219,107 @@ fn providecommand_combinations() {
     assert_parses(r"\providecommand{\foo}{1}\foo\providecommand{\foo}{2}\foo");
 }
 
+// =============================================================================
+// \DeclareMathOperator Tests
+// =============================================================================
+
+// Helper to check that an expression parses to the same tree as another
+fn assert_parses_like(expr: &str, expected: &str) {
+    let actual = parse_tree(expr, ParserConfig::default()).unwrap();
+    let expected_tree = parse_tree(expected, ParserConfig::default()).unwrap();
+    assert!(
+        actual.eq_no_loc(&expected_tree),
+        "Expected {:?} to parse like {:?}",
+        expr,
+        expected
+    );
+}
+
+#[test]
+fn declare_math_operator_should_define_operators() {
+    assert_parses_like(r"\DeclareMathOperator{\Tr}{Tr}\Tr x", r"\operatorname{Tr} x");
+    assert_parses_like(r"\DeclareMathOperator\Tr{Tr}\Tr^2", r"\operatorname{Tr}^2");
+    assert_parses_like(
+        r"\DeclareMathOperator*{\argmax}{arg\,max}\argmax_x f",
+        r"\operatorname*{arg\,max}_x f",
+    );
+}
+
+#[test]
+fn declare_math_operator_should_support_limits_controls() {
+    assert_parses_like(
+        r"\DeclareMathOperator*{\esssup}{ess\,sup}\esssup\limits_x",
+        r"\operatorname*{ess\,sup}\limits_x",
+    );
+}
+
+#[test]
+fn declare_math_operator_should_not_redefine_commands() {
+    assert_fails(r"\DeclareMathOperator{\sin}{sin}");
+    assert_fails(r"\DeclareMathOperator{\alpha}{a}");
+    assert_fails(r"\DeclareMathOperator{Tr}{Tr}");
+}
+
+#[test]
+fn declare_math_operator_should_redefine_macros() {
+    assert_parses_like(
+        r"\DeclareMathOperator*{\argmax}{argmax}\argmax_x",
+        r"\operatorname*{argmax}_x",
+    );
+    assert_parses_like(
+        r"\DeclareMathOperator{\Tr}{Tr}\DeclareMathOperator{\Tr}{tr}\Tr",
+        r"\operatorname{tr}",
+    );
+}
+
+#[test]
+fn declare_math_operator_should_render() {
+    let html = render_to_html_tree(r"\DeclareMathOperator{\Tr}{Tr}\Tr x", ParserConfig::default())
+        .to_markup();
+    assert!(html.contains(r#"<span class="mop"><span class="mord mathrm">Tr</span></span>"#));
+
+    let mathml =
+        render_to_mathml_tree(r"\DeclareMathOperator{\Tr}{Tr}\Tr x", ParserConfig::default())
+            .to_markup();
+    assert!(mathml.contains(r#"<mi mathvariant="normal">Tr</mi><mo>⁡</mo>"#));
+}
+
+#[test]
+fn declare_math_operator_star_should_render_limits() {
+    let expr = r"\DeclareMathOperator*{\argmax}{arg\,max}\displaystyle\argmax_x f";
+
+    let html = render_to_html_tree(expr, ParserConfig::default()).to_markup();
+    assert!(html.contains("op-limits"));
+
+    let mathml = render_to_mathml_tree(expr, ParserConfig::default()).to_markup();
+    assert!(mathml.contains(
+        "<munder><mrow><mi mathvariant=\"normal\">arg\u{2009}max</mi><mo>\u{2061}</mo></mrow><mi>x</mi></munder>"
+    ));
+
+    // Outside of display style the limits go to the side
+    let mathml = render_to_mathml_tree(
+        r"\DeclareMathOperator*{\argmax}{arg\,max}\argmax_x f",
+        ParserConfig::default(),
+    )
+    .to_markup();
+    assert!(!mathml.contains("<munder>"));
+}
+
+#[test]
+fn declare_math_operator_star_should_build_ir_limits() {
+    let conf = ParserConfig::default();
+    let tree = parse_tree(
+        r"\DeclareMathOperator*{\argmax}{arg\,max}\displaystyle\argmax_x f",
+        conf.clone(),
+    )
+    .unwrap();
+    let layout = build_ir(&tree, &Options::from_parser_conf(&conf));
+    assert!(layout.walk().any(|item| matches!(
+        item.element,
+        MathElement::LargeOp { limits: true, subscript: Some(_), .. }
+    )));
+}
+
 // =============================================================================
 // Macro Scope Tests
 // =============================================================================
@@ -234,6 +344,22 @@ fn newcommand_should_accept_number_of_arguments() {
     assert_parses(r"\newcommand\foo[9]{#1#2#3#4#5#6#7#8#9}\foo 123456789");
 }
 
+#[test]
+fn def_should_not_include_delimiters_in_arguments() {
+    assert_parses_like(r"\def\a#1.{(#1)}\a x.", "(x)");
+    assert_parses_like(r"\def\a#1.{(#1)}\a xy.z", "(xy)z");
+    assert_parses_like(r"\def\a#1#2.{#2#1}\a x yz.", "yzx");
+    assert_parses_like(r"\def\a#1\end{(#1)}\a x\end", "(x)");
+}
+
+#[test]
+fn def_should_only_strip_braces_around_a_whole_delimited_argument() {
+    assert_parses_like(r"\def\a#1.{(#1)}\a {x}.", "(x)");
+    assert_parses_like(r"\def\a[#1]{(#1)}\a[{ab}]", "(ab)");
+    assert_parses_like(r"\def\a[#1]{(#1)}\a[{a}{b}]", "({a}{b})");
+    assert_parses_like(r"\def\a#1.{(#1)}\a {x}y{z}.", "({x}y{z})");
+}
+
 #[test]
 fn newcommand_should_reject_invalid_argument_counts() {
     // Invalid argument specifications
@@ -380,6 +506,24 @@ fn should_build_overset_underset() {
     assert_parses(r"\underset{f}{\rightarrow} Y");
 }
 
+#[test]
+fn overset_and_underset_should_put_their_argument_above_and_below() {
+    for (expr, above) in [
+        (r"\overset{f}{\rightarrow}", true),
+        (r"\underset{f}{\rightarrow}", false),
+    ] {
+        let tree = parse_tree(expr, ParserConfig::default()).unwrap();
+        let ParseNode::MClass(mclass) = &tree[0] else {
+            panic!("Expected an mclass, got {:?}", tree[0]);
+        };
+        let ParseNode::SupSub(sup_sub) = &mclass.body[0] else {
+            panic!("Expected scripts, got {:?}", mclass.body[0]);
+        };
+        assert_eq!(sup_sub.sup.is_some(), above, "for {}", expr);
+        assert_eq!(sup_sub.sub.is_some(), !above, "for {}", expr);
+    }
+}
+
 #[test]
 fn should_build_logical_operators() {
     assert_parses(r"X \iff Y");
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use aliter::{
+    ir, parse_tree, parser::ParserConfig, render_to_html_tree, render_to_mathml_tree,
+    tree::VirtualNode, Options,
+};
+
+const EXPRS: &[&str] = &[
+    "x",
+    r"\frac{-b \pm \sqrt{b^2 - 4ac}}{2a}",
+    r"\begin{matrix} a & b \\ c & d \end{matrix}",
+    r#"\text{a < b \& "c"}"#,
+    r"\color{red} \hat{x} \overbrace{a + b}^{n}",
+    r"\href{https://example.com/?a=1&b=2}{x}",
+];
+
+/// A writer that fails once it has been given `limit` bytes
+struct Limited {
+    written: String,
+    limit: usize,
+}
+impl fmt::Write for Limited {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.written.len() + s.len() > self.limit {
+            return Err(fmt::Error);
+        }
+        self.written.push_str(s);
+        Ok(())
+    }
+}
+
+fn trusted() -> ParserConfig {
+    ParserConfig {
+        trust: true,
+        ..Default::default()
+    }
+}
+
+// =============================================================================
+// Writer Tests
+// =============================================================================
+
+#[test]
+fn write_markup_matches_to_markup() {
+    for expr in EXPRS {
+        let html = render_to_html_tree(expr, trusted());
+        let mut written = String::new();
+        html.write_markup(&mut written).unwrap();
+        assert_eq!(written, html.to_markup(), "{}", expr);
+
+        let mathml = render_to_mathml_tree(expr, trusted());
+        let mut written = String::new();
+        mathml.write_markup(&mut written).unwrap();
+        assert_eq!(written, mathml.to_markup(), "{}", expr);
+    }
+}
+
+#[test]
+fn write_markup_appends() {
+    let html = render_to_html_tree("x", ParserConfig::default());
+
+    let mut out = "<p>".to_string();
+    html.write_markup(&mut out).unwrap();
+    html.write_markup(&mut out).unwrap();
+    assert_eq!(out, format!("<p>{0}{0}", html.to_markup()));
+}
+
+#[test]
+fn write_markup_stops_at_error() {
+    let html = render_to_html_tree(EXPRS[1], ParserConfig::default());
+    let markup = html.to_markup();
+
+    let mut out = Limited {
+        written: String::new(),
+        limit: 100,
+    };
+    assert!(html.write_markup(&mut out).is_err());
+    assert!(markup.starts_with(&out.written));
+}
+
+#[test]
+fn markup_is_escaped() {
+    let markup = render_to_mathml_tree(EXPRS[3], ParserConfig::default()).to_markup();
+    assert!(
+        markup.contains(r"\text{a &lt; b \&amp; &quot;c&quot;}</annotation>"),
+        "{}",
+        markup
+    );
+
+    let markup = render_to_html_tree(EXPRS[5], trusted()).to_markup();
+    assert!(
+        markup.contains("href=\"https://example.com/?a=1&amp;b=2\""),
+        "{}",
+        markup
+    );
+}
+
+// =============================================================================
+// IR Tests
+// =============================================================================
+
+#[test]
+fn write_html_matches_render() {
+    for expr in EXPRS {
+        let conf = trusted();
+        let tree = parse_tree(expr, conf.clone()).unwrap();
+        let layout = ir::build_ir(&tree, &Options::from_parser_conf(&conf));
+
+        let mut written = String::new();
+        ir::to_html::write_html(&layout, &mut written).unwrap();
+        assert_eq!(written, ir::to_html::render(&layout), "{}", expr);
+    }
+}
@@ -0,0 +1,119 @@
+use aliter::{parser::ParserConfig, render_to_mathml_core_tree, tree::VirtualNode};
+
+fn core(expr: &str) -> String {
+    render_to_mathml_core_tree(expr, ParserConfig::default()).to_markup()
+}
+
+fn core_display(expr: &str) -> String {
+    let conf = ParserConfig {
+        display_mode: true,
+        ..ParserConfig::default()
+    };
+    render_to_mathml_core_tree(expr, conf).to_markup()
+}
+
+fn assert_core_contains(expr: &str, expected: &str) {
+    let markup = core(expr);
+    assert!(markup.contains(expected), "for {}\n{}", expr, markup);
+}
+
+fn assert_core_lacks(expr: &str, unexpected: &str) {
+    let markup = core(expr);
+    assert!(!markup.contains(unexpected), "for {}\n{}", expr, markup);
+}
+
+// =============================================================================
+// Elements
+// =============================================================================
+
+#[test]
+fn test_no_semantics() {
+    let markup = core(r"\frac{a}{b}");
+    assert!(!markup.contains("<semantics>"), "{}", markup);
+    assert!(!markup.contains("<annotation"), "{}", markup);
+    assert!(
+        markup.contains("<mi>a</mi><mi>b</mi></mfrac>"),
+        "{}",
+        markup
+    );
+}
+
+#[test]
+fn test_mstyle_becomes_mrow() {
+    assert_core_lacks(r"\displaystyle x", "<mstyle");
+    assert_core_contains(r"\displaystyle x", r#"displaystyle="true""#);
+}
+
+#[test]
+fn test_labeled_row() {
+    let markup = core_display(r"\begin{align}a\tag{1}\end{align}");
+    assert!(!markup.contains("<mlabeledtr"), "{}", markup);
+    assert!(markup.contains("<mtr>"), "{}", markup);
+}
+
+// =============================================================================
+// Math variants
+// =============================================================================
+
+#[test]
+fn test_mathvariant_characters() {
+    assert_core_contains(r"\mathbb{R}", "<mi>ℝ</mi>");
+    assert_core_contains(r"\mathfrak{A}", "<mi>𝔄</mi>");
+    assert_core_contains(r"\mathcal{B}", "<mi>ℬ</mi>");
+    assert_core_contains(r"\mathtt{1}", "<mn>𝟷</mn>");
+    assert_core_contains(r"\boldsymbol{\alpha}", "<mi>𝜶</mi>");
+    assert_core_contains(r"\text{\textsf{ab}}", "<mtext>𝖺𝖻</mtext>");
+    assert_core_lacks(r"\mathbf{x}", r#"mathvariant="bold""#);
+}
+
+#[test]
+fn test_mathvariant_normal() {
+    assert_core_contains(r"\mathrm{x}", r#"<mi mathvariant="normal">x</mi>"#);
+}
+
+// =============================================================================
+// Enclosures
+// =============================================================================
+
+#[test]
+fn test_enclose_borders() {
+    assert_core_lacks(r"\fbox{a}", "<menclose");
+    assert_core_contains(r"\fbox{a}", "border-top: 0.04em solid");
+    assert_core_contains(r"\fbox{a}", "border-right: 0.04em solid");
+    assert_core_contains(r"\phase{x}", "border-bottom: 0.04em solid");
+}
+
+#[test]
+fn test_enclose_strikes() {
+    assert_core_contains(r"\cancel{x}", "linear-gradient(to top left");
+    assert_core_contains(r"\bcancel{x}", "linear-gradient(to top right");
+    let markup = core(r"\xcancel{x}");
+    assert_eq!(markup.matches("linear-gradient").count(), 2, "{}", markup);
+}
+
+// =============================================================================
+// Spacing
+// =============================================================================
+
+#[test]
+fn test_mpadded_relative_width() {
+    let markup = core(r"\xrightarrow{abc}");
+    assert!(markup.contains("padding-inline-start: 0.3em"), "{}", markup);
+    assert!(markup.contains("padding-inline-end: 0.3em"), "{}", markup);
+    assert!(!markup.contains("+0.6em"), "{}", markup);
+    assert!(!markup.contains("lspace=\"0.3em\""), "{}", markup);
+}
+
+#[test]
+fn test_colorbox_padding() {
+    let markup = core(r"\colorbox{red}{x}");
+    assert!(markup.contains(r#"mathbackground="red""#), "{}", markup);
+    assert!(markup.contains("padding-block-end: 3pt"), "{}", markup);
+    assert!(!markup.contains("+6pt"), "{}", markup);
+}
+
+#[test]
+fn test_spaces() {
+    assert_core_contains(r"a\,b", r#"<mspace width="0.1667em"></mspace>"#);
+    assert_core_contains(r"a\!b", "margin-inline-start: -0.1667em");
+}
@@ -0,0 +1,329 @@
+use std::thread;
+
+use aliter::{
+    ir::build_ir,
+    mathml_reader::{parse_mathml, MathmlError},
+    parse_node::{EqNoLoc, ParseNode},
+    parse_tree,
+    parser::ParserConfig,
+    tree::{build_html_tree, VirtualNode},
+    Options,
+};
+
+fn read(mathml: &str) -> Vec<ParseNode> {
+    match parse_mathml(mathml) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to read: {}\nError: {:?}", mathml, err),
+    }
+}
+
+/// Assert that the MathML reads to the same tree as the LaTeX parses to
+fn assert_reads_as(mathml: &str, latex: &str) {
+    let tree = read(mathml);
+    let expected = parse_tree(latex, ParserConfig::default()).unwrap();
+    assert!(
+        tree.eq_no_loc(&expected),
+        "for {}\nRead: {:#?}\nExpected: {:#?}",
+        mathml,
+        tree,
+        expected
+    );
+}
+
+fn assert_error(mathml: &str, expected: MathmlError) {
+    assert_eq!(parse_mathml(mathml), Err(expected), "for {}", mathml);
+}
+
+// =============================================================================
+// Elements
+// =============================================================================
+
+#[test]
+fn mathml_reader_should_read_tokens() {
+    assert_reads_as("<math><mi>x</mi><mo>+</mo><mn>12</mn></math>", "x+12");
+    assert_reads_as("<math><mi>α</mi><mo>≤</mo><mi>β</mi></math>", "α ≤ β");
+    assert_reads_as(
+        "<math><mi>sin</mi><mi>x</mi></math>",
+        r"\operatorname{sin} x",
+    );
+    assert_reads_as(
+        "<math><mi mathvariant=\"double-struck\">R</mi></math>",
+        r"\mathbb{R}",
+    );
+    assert_reads_as(
+        "<math><mi mathvariant=\"normal\">d</mi></math>",
+        r"\mathrm{d}",
+    );
+    assert_reads_as(
+        "<math><mi mathvariant=\"bold\">xy</mi></math>",
+        r"\mathbf{xy}",
+    );
+    assert_reads_as("<math><mtext>if x</mtext></math>", r"\text{if x}");
+    assert_reads_as("<math><mspace width=\"1em\"/></math>", r"\kern1em");
+    assert_reads_as("<math><mo>&lt;</mo><mo>&#x2211;</mo></math>", r"<\sum");
+}
+
+#[test]
+fn mathml_reader_should_read_named_entities() {
+    assert_reads_as(
+        "<math><mi>&alpha;</mi><mo>&le;</mo><mi>&varphi;</mi></math>",
+        "α≤ϕ",
+    );
+    assert_reads_as(
+        "<math><munderover><mo>&Sum;</mo><mi>&Omega;</mi><mi>&infin;</mi></munderover></math>",
+        r"\sum_Ω^∞",
+    );
+    assert_reads_as(
+        "<math><mi>x</mi><mo>&PlusMinus;</mo><mi>y</mi><mo>&rightarrow;</mo><mi>&hbar;</mi></math>",
+        "x±y→ℏ",
+    );
+    assert_reads_as(
+        "<math><mi>f</mi><mo>&ApplyFunction;</mo><mi>x</mi><mo>&it;</mo><mi>y</mi></math>",
+        "fxy",
+    );
+    // Entities can stand for more than one character
+    assert!(parse_mathml("<math><mo>&NotSubset;</mo></math>").is_ok());
+    // Names are case sensitive, and need their semicolon
+    assert_error(
+        "<math><mi>&ALPHA;</mi></math>",
+        MathmlError::UnknownEntity("ALPHA".to_string()),
+    );
+    assert_error(
+        "<math><mi>&alpha</mi></math>",
+        MathmlError::UnknownEntity("alpha".to_string()),
+    );
+}
+
+#[test]
+fn mathml_reader_should_read_fractions_and_roots() {
+    assert_reads_as(
+        "<math><mfrac><mrow><mi>a</mi><mo>+</mo><mi>b</mi></mrow><mi>c</mi></mfrac></math>",
+        r"\frac{a+b}{c}",
+    );
+    assert_reads_as(
+        "<math><mfrac linethickness=\"0\"><mi>n</mi><mi>k</mi></mfrac></math>",
+        r"n \atop k",
+    );
+    assert_reads_as("<math><msqrt><mi>x</mi></msqrt></math>", r"\sqrt{x}");
+    assert_reads_as(
+        "<math><mroot><mi>x</mi><mn>3</mn></mroot></math>",
+        r"\sqrt[3]{x}",
+    );
+}
+
+#[test]
+fn mathml_reader_should_read_scripts() {
+    assert_reads_as("<math><msup><mi>x</mi><mn>2</mn></msup></math>", "x^2");
+    assert_reads_as(
+        "<math><msub><mi>x</mi><mrow><mi>i</mi><mi>j</mi></mrow></msub></math>",
+        "x_{ij}",
+    );
+    assert_reads_as(
+        "<math><msubsup><mo>∫</mo><mn>0</mn><mn>1</mn></msubsup></math>",
+        r"\int_0^1",
+    );
+    assert_reads_as(
+        "<math><munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover></math>",
+        r"\sum_{i=1}^n",
+    );
+    assert_reads_as(
+        "<math><munder><mo>lim</mo><mi>x</mi></munder></math>",
+        r"\lim_x",
+    );
+    assert_reads_as(
+        "<math><mover><mo>=</mo><mi>a</mi></mover></math>",
+        r"\overset{a}{=}",
+    );
+}
+
+#[test]
+fn mathml_reader_should_read_accents() {
+    assert_reads_as(
+        "<math><mover><mi>x</mi><mo>^</mo></mover></math>",
+        r"\hat{x}",
+    );
+    assert_reads_as(
+        "<math><mover><mrow><mi>x</mi><mi>y</mi></mrow><mo>^</mo></mover></math>",
+        r"\widehat{xy}",
+    );
+    assert_reads_as(
+        "<math><mover><mi>v</mi><mo>→</mo></mover></math>",
+        r"\vec{v}",
+    );
+    assert_reads_as(
+        "<math><mover><mrow><mi>A</mi><mi>B</mi></mrow><mo>¯</mo></mover></math>",
+        r"\overline{AB}",
+    );
+    assert_reads_as(
+        "<math><munder><mi>x</mi><mo>_</mo></munder></math>",
+        r"\underline{x}",
+    );
+    assert_reads_as(
+        "<math><munder><mi>x</mi><mo>⏟</mo></munder></math>",
+        r"\underbrace{x}",
+    );
+}
+
+#[test]
+fn mathml_reader_should_read_fences() {
+    assert_reads_as(
+        "<math><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></math>",
+        r"\left(x\right)",
+    );
+    assert_reads_as(
+        "<math><mrow><mo>(</mo><mi>a</mi><mo>)</mo><mo>+</mo><mo>(</mo><mi>b</mi><mo>)</mo></mrow></math>",
+        "(a)+(b)",
+    );
+    assert_reads_as(
+        "<math><mrow><mo stretchy=\"false\">(</mo><mi>x</mi><mo stretchy=\"false\">)</mo></mrow></math>",
+        "(x)",
+    );
+    assert_reads_as(
+        "<math><mrow><mo>⟨</mo><mi>a</mi><mo stretchy=\"true\">|</mo><mi>b</mi><mo>⟩</mo></mrow></math>",
+        r"\left\langle a \middle| b \right\rangle",
+    );
+    assert_reads_as(
+        "<math><mfenced><mi>a</mi><mi>b</mi></mfenced></math>",
+        r"\left(a,b\right)",
+    );
+}
+
+#[test]
+fn mathml_reader_should_read_tables() {
+    assert_reads_as(
+        "<math><mrow><mo>(</mo><mtable>\
+            <mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr>\
+            <mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr>\
+        </mtable><mo>)</mo></mrow></math>",
+        r"\begin{pmatrix}a&b\\c&d\end{pmatrix}",
+    );
+}
+
+#[test]
+fn mathml_reader_should_read_styles() {
+    assert_reads_as(
+        "<math><mstyle displaystyle=\"true\"><mi>x</mi></mstyle></math>",
+        r"\displaystyle x",
+    );
+    assert_reads_as(
+        "<math><mstyle mathcolor=\"red\"><mi>x</mi></mstyle></math>",
+        r"\color{red}x",
+    );
+    assert_reads_as(
+        "<math><mi mathcolor=\"#ff0000\">x</mi></math>",
+        r"\color{#ff0000}x",
+    );
+}
+
+// =============================================================================
+// Documents
+// =============================================================================
+
+#[test]
+fn mathml_reader_should_read_documents() {
+    assert_reads_as(
+        "<?xml version=\"1.0\"?>\n<!-- exported -->\n\
+        <mml:math xmlns:mml=\"http://www.w3.org/1998/Math/MathML\">\
+            <mml:semantics><mml:mi>x</mml:mi><mml:annotation>x</mml:annotation></mml:semantics>\
+        </mml:math>",
+        "x",
+    );
+    assert_reads_as(
+        "<math><mi>x</mi><mo>&InvisibleTimes;</mo><mi>y</mi></math>",
+        "xy",
+    );
+    assert_reads_as("<mrow><mi>x</mi></mrow>", "x");
+}
+
+#[test]
+fn mathml_reader_should_report_errors() {
+    assert_error(
+        "<math><mglyph/></math>",
+        MathmlError::UnsupportedElement("mglyph".to_string()),
+    );
+    assert_error(
+        "<math><mfrac><mi>a</mi></mfrac></math>",
+        MathmlError::WrongChildCount("mfrac".to_string(), 2, 1),
+    );
+    assert_error(
+        "<math><mi>x</mo></math>",
+        MathmlError::MismatchedTag("mi".to_string(), "mo".to_string()),
+    );
+    assert_error(
+        "<math><mi mathvariant=\"wavy\">x</mi></math>",
+        MathmlError::InvalidAttribute(
+            "mi".to_string(),
+            "mathvariant".to_string(),
+            "wavy".to_string(),
+        ),
+    );
+    assert_error(
+        "<math><mi>&foo;</mi></math>",
+        MathmlError::UnknownEntity("foo".to_string()),
+    );
+    assert_error("<math><mi>x</mi>", MathmlError::InvalidXml(16));
+    assert_error(
+        "<math>x</math>",
+        MathmlError::UnexpectedText("math".to_string(), "x".to_string()),
+    );
+}
+
+#[test]
+fn mathml_reader_output_should_render() {
+    let tree = read("<math><mfrac><mi>a</mi><msup><mi>b</mi><mn>2</mn></msup></mfrac></math>");
+    let conf = ParserConfig::default();
+    let markup = build_html_tree(&tree, conf.clone()).to_markup();
+    assert!(markup.contains("mfrac"), "{}", markup);
+
+    let layout = build_ir(&tree, &Options::from_parser_conf(&conf));
+    assert!(layout.width > 0.0);
+}
+
+// =============================================================================
+// Limits
+// =============================================================================
+
+/// `open` repeated `n` times, then `<mi>x</mi>`, then `close` repeated `n` times
+fn nested(open: &str, close: &str, n: usize) -> String {
+    open.repeat(n) + "<mi>x</mi>" + &close.repeat(n)
+}
+
+#[test]
+fn mathml_reader_should_limit_nesting() {
+    let result = parse_mathml(&nested("<mrow>", "</mrow>", 100_000));
+    assert!(matches!(result, Err(MathmlError::TooDeeplyNested)));
+    let result = parse_mathml(&"<mrow>".repeat(100_000));
+    assert!(matches!(result, Err(MathmlError::TooDeeplyNested)));
+}
+
+#[test]
+fn mathml_reader_depth_should_fit_in_a_thread_stack() {
+    // The elements that take the most stack to render for each level
+    let elements = [
+        ("<mtable><mtr><mtd>", "</mtd></mtr></mtable>"),
+        ("<munder><mrow>", "</mrow><mo>&#x23DF;</mo></munder>"),
+        ("<mover><mrow>", "</mrow><mo>&#x2192;</mo></mover>"),
+        ("<mfrac><mn>1</mn><mrow>", "</mrow></mfrac>"),
+        ("<msqrt>", "</msqrt>"),
+    ];
+    for (open, close) in elements {
+        // As many levels as the reader accepts
+        let levels = (1..)
+            .take_while(|&n| parse_mathml(&nested(open, close, n)).is_ok())
+            .last()
+            .unwrap();
+        let tree = read(&nested(open, close, levels));
+
+        // On the stack of a thread spawned without a size
+        thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || {
+                let conf = ParserConfig::default();
+                build_html_tree(&tree, conf.clone()).to_markup();
+                build_ir(&tree, &Options::from_parser_conf(&conf));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
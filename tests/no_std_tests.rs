@@ -0,0 +1,45 @@
+use std::{path::Path, process::Command};
+
+/// A target that has no `std`, only `core` and `alloc`
+const TARGET: &str = "thumbv7em-none-eabi";
+
+/// Whether the standard library for the target is installed, as with
+/// `rustup target add thumbv7em-none-eabi`
+fn has_target() -> bool {
+    let Ok(output) = Command::new("rustc").args(["--print", "sysroot"]).output() else {
+        return false;
+    };
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(TARGET)
+        .exists()
+}
+
+// =============================================================================
+// Build Tests
+// =============================================================================
+
+#[test]
+#[ignore = "needs rustup target add thumbv7em-none-eabi"]
+fn builds_without_std() {
+    assert!(
+        has_target(),
+        "The {} target is not installed, run `rustup target add {}`",
+        TARGET,
+        TARGET
+    );
+
+    let output = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["build", "--lib", "--no-default-features", "--features"])
+        .arg("physics siunitx")
+        .args(["--target", TARGET, "--target-dir"])
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std"))
+        .output()
+        .expect("Failed to run cargo");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    // Code that only the renderers use should be left out rather than warned about
+    assert!(!stderr.contains("warning"), "{}", stderr);
+}
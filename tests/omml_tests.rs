@@ -0,0 +1,208 @@
+use aliter::{
+    omml::{render_to_omml, to_omml},
+    parse_tree,
+    parser::ParserConfig,
+};
+
+/// The OMML of the expression, without the `m:oMath` element around it
+fn omml(expr: &str) -> String {
+    let tree = match parse_tree(expr, ParserConfig::default()) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to parse: {}\nError: {:?}", expr, err),
+    };
+    let omml = to_omml(&tree, false);
+    let (_, body) = omml.split_once('>').unwrap();
+    body.strip_suffix("</m:oMath>").unwrap().to_string()
+}
+
+fn assert_omml(expr: &str, expected: &str) {
+    assert_eq!(omml(expr), expected, "for {}", expr);
+}
+
+fn assert_omml_contains(expr: &str, expected: &str) {
+    let omml = omml(expr);
+    assert!(omml.contains(expected), "for {}\n{}", expr, omml);
+}
+
+// =============================================================================
+// Runs
+// =============================================================================
+
+#[test]
+fn omml_should_merge_runs() {
+    assert_omml("x+12", "<m:r><m:t>x+12</m:t></m:r>");
+    assert_omml("a<b", "<m:r><m:t>a&lt;b</m:t></m:r>");
+    assert_omml(r"\alpha\le\infty", "<m:r><m:t>α≤∞</m:t></m:r>");
+}
+
+#[test]
+fn omml_should_write_fonts() {
+    assert_omml(
+        r"\mathbb{R}",
+        r#"<m:r><m:rPr><m:scr m:val="double-struck"/></m:rPr><m:t>R</m:t></m:r>"#,
+    );
+    assert_omml(
+        r"\mathbf{v}",
+        r#"<m:r><m:rPr><m:sty m:val="b"/></m:rPr><m:t>v</m:t></m:r>"#,
+    );
+    assert_omml(
+        r"\text{ if }",
+        r#"<m:r><m:rPr><m:nor/></m:rPr><m:t xml:space="preserve"> if </m:t></m:r>"#,
+    );
+    assert_omml(
+        r"\textbf{b}",
+        "<m:r><m:rPr><m:nor/></m:rPr><w:rPr><w:b/></w:rPr><m:t>b</m:t></m:r>",
+    );
+}
+
+#[test]
+fn omml_should_write_colors() {
+    assert_omml(
+        r"\color{red}{x}",
+        r#"<m:r><w:rPr><w:color w:val="FF0000"/></w:rPr><m:t>x</m:t></m:r>"#,
+    );
+    assert_omml_contains(
+        r"\textcolor{#00ff80}{\frac{1}{2}}",
+        r#"<m:fPr><m:ctrlPr><w:rPr><w:color w:val="00FF80"/></w:rPr></m:ctrlPr></m:fPr>"#,
+    );
+}
+
+// =============================================================================
+// Structures
+// =============================================================================
+
+#[test]
+fn omml_should_write_fractions_and_roots() {
+    assert_omml(
+        r"\frac{a}{b}",
+        "<m:f><m:num><m:r><m:t>a</m:t></m:r></m:num><m:den><m:r><m:t>b</m:t></m:r></m:den></m:f>",
+    );
+    assert_omml_contains(
+        r"\binom{n}{k}",
+        r#"<m:d><m:dPr><m:begChr m:val="("/><m:endChr m:val=")"/></m:dPr><m:e><m:f><m:fPr><m:type m:val="noBar"/></m:fPr>"#,
+    );
+    assert_omml(
+        r"\sqrt{x}",
+        r#"<m:rad><m:radPr><m:degHide m:val="1"/></m:radPr><m:deg/><m:e><m:r><m:t>x</m:t></m:r></m:e></m:rad>"#,
+    );
+    assert_omml(
+        r"\sqrt[3]{x}",
+        "<m:rad><m:deg><m:r><m:t>3</m:t></m:r></m:deg><m:e><m:r><m:t>x</m:t></m:r></m:e></m:rad>",
+    );
+}
+
+#[test]
+fn omml_should_write_scripts() {
+    assert_omml(
+        "x^2",
+        "<m:sSup><m:e><m:r><m:t>x</m:t></m:r></m:e><m:sup><m:r><m:t>2</m:t></m:r></m:sup></m:sSup>",
+    );
+    assert_omml(
+        "x_i^2",
+        "<m:sSubSup><m:e><m:r><m:t>x</m:t></m:r></m:e><m:sub><m:r><m:t>i</m:t></m:r></m:sub><m:sup><m:r><m:t>2</m:t></m:r></m:sup></m:sSubSup>",
+    );
+    assert_omml(
+        r"\overset{a}{=}",
+        "<m:limUpp><m:e><m:r><m:t>=</m:t></m:r></m:e><m:lim><m:r><m:t>a</m:t></m:r></m:lim></m:limUpp>",
+    );
+}
+
+#[test]
+fn omml_should_write_large_operators() {
+    assert_omml(
+        r"\sum_{i=1}^n i = 1",
+        r#"<m:nary><m:naryPr><m:chr m:val="∑"/><m:limLoc m:val="undOvr"/></m:naryPr><m:sub><m:r><m:t>i=1</m:t></m:r></m:sub><m:sup><m:r><m:t>n</m:t></m:r></m:sup><m:e><m:r><m:t>i</m:t></m:r></m:e></m:nary><m:r><m:t>=1</m:t></m:r>"#,
+    );
+    assert_omml_contains(
+        r"\int f",
+        r#"<m:naryPr><m:chr m:val="∫"/><m:limLoc m:val="subSup"/><m:subHide m:val="1"/><m:supHide m:val="1"/></m:naryPr>"#,
+    );
+}
+
+#[test]
+fn omml_should_write_functions() {
+    assert_omml(
+        r"\sin x + y",
+        r#"<m:func><m:fName><m:r><m:rPr><m:sty m:val="p"/></m:rPr><m:t>sin</m:t></m:r></m:fName><m:e><m:r><m:t>x</m:t></m:r></m:e></m:func><m:r><m:t>+y</m:t></m:r>"#,
+    );
+    assert_omml_contains(
+        r"\lim_{x\to 0} f",
+        r#"<m:fName><m:limLow><m:e><m:r><m:rPr><m:sty m:val="p"/></m:rPr><m:t>lim</m:t></m:r></m:e><m:lim><m:r><m:t>x→0</m:t></m:r></m:lim></m:limLow></m:fName><m:e><m:r><m:t>f</m:t></m:r></m:e>"#,
+    );
+    assert_omml_contains(r"\operatorname{sn} x", "<m:t>sn</m:t>");
+    assert_omml_contains(
+        r"\operatorname*{argmax}_x f",
+        r#"<m:fName><m:limLow><m:e><m:r><m:rPr><m:sty m:val="p"/></m:rPr><m:t>argmax</m:t></m:r></m:e><m:lim><m:r><m:t>x</m:t></m:r></m:lim></m:limLow></m:fName>"#,
+    );
+    assert_omml_contains(
+        r"\operatorname*{argmax}_x^y f",
+        r#"<m:fName><m:limUpp><m:e><m:limLow>"#,
+    );
+    // Without the star, the scripts are beside the name
+    assert_omml_contains(
+        r"\operatorname{sn}_x f",
+        r#"<m:fName><m:sSub><m:e><m:r><m:rPr><m:sty m:val="p"/></m:rPr><m:t>sn</m:t></m:r></m:e>"#,
+    );
+}
+
+#[test]
+fn omml_should_write_delimiters() {
+    assert_omml(
+        r"\left[x\middle|y\right.",
+        r#"<m:d><m:dPr><m:begChr m:val="["/><m:sepChr m:val="∣"/><m:endChr m:val=""/></m:dPr><m:e><m:r><m:t>x</m:t></m:r></m:e><m:e><m:r><m:t>y</m:t></m:r></m:e></m:d>"#,
+    );
+}
+
+#[test]
+fn omml_should_write_matrices() {
+    assert_omml_contains(
+        r"\begin{pmatrix}a&b\\c&d\end{pmatrix}",
+        r#"<m:mcJc m:val="center"/></m:mcPr></m:mc></m:mcs></m:mPr><m:mr><m:e><m:r><m:t>a</m:t></m:r></m:e><m:e><m:r><m:t>b</m:t></m:r></m:e></m:mr>"#,
+    );
+    assert_omml(
+        r"\begin{aligned}a&=b\end{aligned}",
+        "<m:eqArr><m:e><m:r><m:t>a</m:t></m:r><m:r><m:rPr><m:aln/></m:rPr><m:t>=b</m:t></m:r></m:e></m:eqArr>",
+    );
+}
+
+#[test]
+fn omml_should_write_accents_and_enclosures() {
+    assert_omml_contains(
+        r"\hat{x}",
+        "<m:acc><m:accPr><m:chr m:val=\"\u{302}\"/></m:accPr>",
+    );
+    assert_omml_contains(
+        r"\overline{x}",
+        r#"<m:bar><m:barPr><m:pos m:val="top"/></m:barPr>"#,
+    );
+    assert_omml_contains(
+        r"\overbrace{x}",
+        r#"<m:groupChrPr><m:chr m:val="⏞"/><m:pos m:val="top"/>"#,
+    );
+    assert_omml_contains(r"\cancel{x}", r#"<m:strikeBLTR m:val="1"/>"#);
+    assert_omml_contains(r"\boxed{x}", "<m:borderBox><m:e>");
+    assert_omml_contains(
+        r"\phantom{x}",
+        r#"<m:phant><m:phantPr><m:show m:val="0"/></m:phantPr>"#,
+    );
+}
+
+// =============================================================================
+// Documents
+// =============================================================================
+
+#[test]
+fn omml_should_declare_namespaces() {
+    let omml = render_to_omml("x", ParserConfig::default()).unwrap();
+    assert!(omml.starts_with(
+        r#"<m:oMath xmlns:m="http://schemas.openxmlformats.org/officeDocument/2006/math" xmlns:w="#
+    ));
+
+    let conf = ParserConfig {
+        display_mode: true,
+        ..ParserConfig::default()
+    };
+    let omml = render_to_omml("x", conf).unwrap();
+    assert!(omml.starts_with("<m:oMathPara "), "{}", omml);
+    assert!(omml.ends_with("</m:oMath></m:oMathPara>"), "{}", omml);
+}
@@ -1,4 +1,6 @@
-use aliter::{parse_tree, parser::ParserConfig};
+use std::ops::Range;
+
+use aliter::{parse_node::ParseNode, parse_tree, parser::ParserConfig};
 
 // Helper to check that parsing succeeds
 fn assert_parses(expr: &str) {
@@ -7,6 +9,12 @@ fn assert_parses(expr: &str) {
     assert!(result.is_ok(), "Failed to parse: {}\nError: {:?}", expr, result.err());
 }
 
+// Helper to get the source range of each top level node
+fn locs(expr: &str) -> Vec<Option<Range<usize>>> {
+    let tree = parse_tree(expr, ParserConfig::default()).unwrap();
+    tree.iter().map(|node| node.loc().map(|loc| loc.0)).collect()
+}
+
 // Helper with strict settings
 fn assert_parses_strict(expr: &str) {
     let mut conf = ParserConfig::default();
@@ -569,3 +577,89 @@ fn verb_parser_should_not_fail() {
     assert_parses(r"\verb|x^2|");
     assert_parses(r"\verb!x_3!");
 }
+
+// =============================================================================
+// Newline Parser Tests
+// =============================================================================
+
+#[test]
+fn newline_should_read_a_size_right_after_it() {
+    let tree = parse_tree(r"a\\[1em]b", ParserConfig::default()).unwrap();
+    assert_eq!(tree.len(), 3);
+    let ParseNode::Cr(cr) = &tree[1] else {
+        panic!("Expected a newline, got {:?}", tree[1]);
+    };
+    assert!(cr.size.is_some());
+}
+
+#[test]
+fn newline_should_not_read_a_size_after_a_space() {
+    // Like amsmath, `\\ [x]` is a newline followed by `[x]`
+    let tree = parse_tree(r"a\\ [x]", ParserConfig::default()).unwrap();
+    assert_eq!(tree.len(), 5);
+    let ParseNode::Cr(cr) = &tree[1] else {
+        panic!("Expected a newline, got {:?}", tree[1]);
+    };
+    assert!(cr.size.is_none());
+}
+
+// =============================================================================
+// Source Location Tests
+// =============================================================================
+
+#[test]
+fn functions_should_cover_their_arguments() {
+    assert_eq!(locs(r"x+\frac{a}{b}"), [Some(0..1), Some(1..2), Some(2..13)]);
+    assert_eq!(locs(r"\sqrt[3]{x} y"), [Some(0..11), Some(12..13)]);
+}
+
+#[test]
+fn environments_should_cover_begin_to_end() {
+    assert_eq!(locs(r"\begin{matrix}a\end{matrix} x"), [Some(0..27), Some(28..29)]);
+}
+
+#[test]
+fn macro_expansions_should_be_located_at_the_macro() {
+    assert_eq!(
+        locs(r"\def\a{xy}\a z"),
+        [Some(10..13), Some(10..13), Some(13..14)]
+    );
+}
+
+#[test]
+fn ligatures_should_cover_their_characters() {
+    let tree = parse_tree(r"\text{a--b---c``d}", ParserConfig::default()).unwrap();
+    let ParseNode::Text(text) = &tree[0] else {
+        panic!("Expected text, got {:?}", tree[0]);
+    };
+    let locs: Vec<_> = text
+        .body
+        .iter()
+        .map(|node| (node.text().unwrap(), node.loc().map(|loc| loc.0)))
+        .collect();
+    assert_eq!(
+        locs,
+        [
+            ("a", Some(6..7)),
+            ("--", Some(7..9)),
+            ("b", Some(9..10)),
+            ("---", Some(10..13)),
+            ("c", Some(13..14)),
+            ("``", Some(14..16)),
+            ("d", Some(16..17)),
+        ]
+    );
+}
+
+#[test]
+fn ligatures_should_form_at_the_end_of_a_group() {
+    let tree = parse_tree(r"\text{a--}\text{---}\text{--''}", ParserConfig::default()).unwrap();
+    let texts: Vec<Vec<&str>> = tree
+        .iter()
+        .map(|node| match node {
+            ParseNode::Text(text) => text.body.iter().map(|node| node.text().unwrap()).collect(),
+            _ => panic!("Expected text, got {:?}", node),
+        })
+        .collect();
+    assert_eq!(texts, [vec!["a", "--"], vec!["---"], vec!["--", "''"]]);
+}
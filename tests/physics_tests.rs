@@ -0,0 +1,238 @@
+#![cfg(feature = "physics")]
+
+use aliter::{
+    contrib::physics, parse_node::EqNoLoc, parse_tree, parser::ParserConfig, render_to_html_tree,
+    tree::VirtualNode,
+};
+
+fn physics_conf() -> ParserConfig {
+    let mut conf = ParserConfig::default();
+    physics::add_macros(&mut conf.macros);
+    conf
+}
+
+// Helper to check that a physics macro parses to the same tree as its expansion
+#[track_caller]
+fn assert_parses_like(expr: &str, expected: &str) {
+    let actual_tree = parse_tree(expr, physics_conf())
+        .unwrap_or_else(|err| panic!("Failed to parse: {}\nError: {:?}", expr, err));
+    let expected_tree = parse_tree(expected, physics_conf())
+        .unwrap_or_else(|err| panic!("Failed to parse: {}\nError: {:?}", expected, err));
+
+    assert!(
+        actual_tree.eq_no_loc(&expected_tree),
+        "Expected {:?} to parse like {:?}\nActual: {:?}\nExpected: {:?}",
+        expr,
+        expected,
+        actual_tree,
+        expected_tree
+    );
+}
+
+#[track_caller]
+fn assert_fails(expr: &str) {
+    let result = parse_tree(expr, physics_conf());
+    assert!(result.is_err(), "Expected parse error for: {}", expr);
+}
+
+// =============================================================================
+// Automatic bracing
+// =============================================================================
+
+#[test]
+fn physics_qty_picks_delimiters() {
+    assert_parses_like(r"\qty(x+y)", r"\left(x+y\right)");
+    assert_parses_like(r"\qty[x+y]", r"\left[x+y\right]");
+    assert_parses_like(r"\qty{x+y}", r"\left\{x+y\right\}");
+    assert_parses_like(r"\qty|x+y|", r"\left\lvert x+y\right\rvert");
+}
+
+#[test]
+fn physics_qty_keeps_nested_delimiters() {
+    assert_parses_like(r"\qty((a+b)c)", r"\left((a+b)c\right)");
+    assert_parses_like(r"\qty(\frac{(a}{b})", r"\left(\frac{(a}{b}\right)");
+}
+
+#[test]
+fn physics_qty_sizing() {
+    assert_parses_like(r"\qty*(x)", r"(x)");
+    assert_parses_like(r"\qty\big(x)", r"\bigl(x\bigr)");
+    assert_parses_like(r"\qty\Bigg[x]", r"\Biggl[x\Biggr]");
+}
+
+#[test]
+fn physics_qty_requires_delimiter() {
+    assert_fails(r"\qty x");
+    assert_fails(r"\qty(x");
+}
+
+#[test]
+fn physics_abs_and_norm() {
+    assert_parses_like(r"\abs{x}", r"\left\lvert x\right\rvert");
+    assert_parses_like(r"\abs*{x}", r"\lvert x\rvert");
+    assert_parses_like(r"\abs\big{x}", r"\bigl\lvert x\bigr\rvert");
+    assert_parses_like(r"\norm{\alpha x}", r"\left\lVert\alpha x\right\rVert");
+}
+
+#[test]
+fn physics_eval() {
+    assert_parses_like(r"\eval{x^2}_0^1", r"\left.x^2\right\vert_0^1");
+    assert_parses_like(r"\eval(x^2)_0^1", r"\left(x^2\right\vert_0^1");
+    assert_parses_like(r"\eval*{x^2}_0^1", r"x^2\vert_0^1");
+}
+
+#[test]
+fn physics_order() {
+    assert_parses_like(r"\order{x^2}", r"\mathcal{O}\left(x^2\right)");
+    assert_parses_like(r"\order(x^2)", r"\mathcal{O}\left(x^2\right)");
+}
+
+#[test]
+fn physics_commutators() {
+    assert_parses_like(r"\comm{A}{B}", r"\left[A,B\right]");
+    assert_parses_like(r"\acomm{A}{B}", r"\left\{A,B\right\}");
+    assert_parses_like(r"\pb{A}{B}", r"\left\{A,B\right\}");
+    assert_parses_like(r"\comm*{A}{B}", r"[A,B]");
+}
+
+// =============================================================================
+// Vector notation
+// =============================================================================
+
+#[test]
+fn physics_vectors() {
+    assert_parses_like(r"\vb{a}", r"\mathbf{a}");
+    assert_parses_like(r"\vb*{\alpha}", r"\boldsymbol{\alpha}");
+    assert_parses_like(r"\va{a}", r"\vec{\mathbf{a}}");
+    assert_parses_like(r"\va*{a}", r"\vec{\boldsymbol{a}}");
+    assert_parses_like(r"\vu{a}", r"\hat{\mathbf{a}}");
+    assert_parses_like(
+        r"\vb{a}\vdot\vb{b}",
+        r"\mathbf{a}\boldsymbol{\cdot}\mathbf{b}",
+    );
+}
+
+#[test]
+fn physics_vector_operators() {
+    assert_parses_like(r"\grad\Psi", r"\boldsymbol{\nabla}\Psi");
+    assert_parses_like(r"\grad(\Psi)", r"\boldsymbol{\nabla}\left(\Psi\right)");
+    assert_parses_like(r"\div\vb{E}", r"\boldsymbol{\nabla}\cdot\mathbf{E}");
+    assert_parses_like(
+        r"\curl[\vb{B}]",
+        r"\boldsymbol{\nabla}\times\left[\mathbf{B}\right]",
+    );
+    assert_parses_like(r"\laplacian\phi", r"\nabla^2\phi");
+}
+
+// =============================================================================
+// Operators
+// =============================================================================
+
+#[test]
+fn physics_trace() {
+    assert_parses_like(r"\tr\rho", r"\operatorname{tr}\rho");
+    assert_parses_like(r"\Tr(\rho)", r"\operatorname{Tr}\left(\rho\right)");
+}
+
+// =============================================================================
+// Quantum mechanics
+// =============================================================================
+
+#[test]
+fn physics_expval() {
+    assert_parses_like(r"\expval{A}", r"\left\langle A\right\rangle");
+    assert_parses_like(
+        r"\expval{A}{\psi}",
+        r"\left\langle\psi\middle\vert A\middle\vert\psi\right\rangle",
+    );
+    assert_parses_like(r"\expval*{A}{\psi}", r"\langle\psi\vert A\vert\psi\rangle");
+}
+
+#[test]
+fn physics_mel() {
+    assert_parses_like(
+        r"\mel{n}{H}{m}",
+        r"\left\langle n\middle\vert H\middle\vert m\right\rangle",
+    );
+}
+
+// =============================================================================
+// Matrices
+// =============================================================================
+
+#[test]
+fn physics_mqty() {
+    assert_parses_like(
+        r"\mqty(a & b \\ c & d)",
+        r"\begin{pmatrix}a & b \\ c & d\end{pmatrix}",
+    );
+    assert_parses_like(
+        r"\mqty[a & b \\ c & d]",
+        r"\begin{bmatrix}a & b \\ c & d\end{bmatrix}",
+    );
+    assert_parses_like(
+        r"\mqty|a & b \\ c & d|",
+        r"\begin{vmatrix}a & b \\ c & d\end{vmatrix}",
+    );
+    assert_parses_like(
+        r"\mqty{a & b \\ c & d}",
+        r"\begin{matrix}a & b \\ c & d\end{matrix}",
+    );
+    assert_parses_like(r"\pmqty{a & b}", r"\begin{pmatrix}a & b\end{pmatrix}");
+    assert_parses_like(
+        r"\smqty{a & b}",
+        r"\left(\begin{smallmatrix}a & b\end{smallmatrix}\right)",
+    );
+}
+
+// =============================================================================
+// Derivatives
+// =============================================================================
+
+#[test]
+fn physics_dd() {
+    assert_parses_like(r"\dd", r"\mathrm{d}");
+    assert_parses_like(r"\int f\dd{x}", r"\int f\mathrm{d}{x}");
+    assert_parses_like(r"\dd[3]{x}", r"\mathrm{d}^{3}{x}");
+    assert_parses_like(r"\dd(\cos\theta)", r"\mathrm{d}\left(\cos\theta\right)");
+}
+
+#[test]
+fn physics_dv() {
+    assert_parses_like(r"\dv{f}{x}", r"\frac{\mathrm{d}{f}}{\mathrm{d}{x}}");
+    assert_parses_like(
+        r"\dv[2]{f}{x}",
+        r"\frac{\mathrm{d}^{2}{f}}{\mathrm{d}{x}^{2}}",
+    );
+    assert_parses_like(r"\dv{x}", r"\frac{\mathrm{d}}{\mathrm{d}{x}}");
+    assert_parses_like(
+        r"\dv{x}(x^2)",
+        r"\frac{\mathrm{d}}{\mathrm{d}{x}}\left(x^2\right)",
+    );
+    assert_parses_like(r"\dv*{f}{x}", r"\mathrm{d}{f}/\mathrm{d}{x}");
+}
+
+#[test]
+fn physics_pdv() {
+    assert_parses_like(r"\pdv{f}{x}", r"\frac{\partial{f}}{\partial{x}}");
+    assert_parses_like(r"\pdv[n]{f}{x}", r"\frac{\partial^{n}{f}}{\partial{x}^{n}}");
+    assert_parses_like(
+        r"\pdv{f}{x}{y}",
+        r"\frac{\partial^{2}{f}}{\partial{x}\partial{y}}",
+    );
+}
+
+#[test]
+fn physics_macros_render() {
+    let html = render_to_html_tree(r"\pdv{f}{x} = \abs{\vb{v}}", physics_conf()).to_markup();
+    assert!(html.contains("katex"));
+}
+
+#[test]
+fn physics_macros_are_opt_in() {
+    // Without the package, `\div` is still the division symbol
+    let default_tree = parse_tree(r"\div", ParserConfig::default()).unwrap();
+    let physics_tree = parse_tree(r"\div", physics_conf()).unwrap();
+    assert_eq!(default_tree.len(), 1);
+    assert!(!default_tree.eq_no_loc(&physics_tree));
+}
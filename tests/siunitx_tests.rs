@@ -0,0 +1,236 @@
+#![cfg(feature = "siunitx")]
+
+use aliter::{
+    contrib::siunitx::{self, PerMode, SiunitxConfig},
+    parse_node::EqNoLoc,
+    parse_tree,
+    parser::{ParseError, ParserConfig},
+    render_to_html_tree,
+    tree::VirtualNode,
+};
+
+fn siunitx_conf_with(si_conf: SiunitxConfig) -> ParserConfig {
+    let mut conf = ParserConfig::default();
+    siunitx::add_macros_with(&mut conf.macros, si_conf);
+    conf
+}
+
+fn siunitx_conf() -> ParserConfig {
+    siunitx_conf_with(SiunitxConfig::default())
+}
+
+// Helper to check that a siunitx macro parses to the same tree as its expansion
+#[track_caller]
+fn assert_parses_like_with(expr: &str, expected: &str, conf: ParserConfig) {
+    let actual_tree = parse_tree(expr, conf.clone())
+        .unwrap_or_else(|err| panic!("Failed to parse: {}\nError: {:?}", expr, err));
+    let expected_tree = parse_tree(expected, conf)
+        .unwrap_or_else(|err| panic!("Failed to parse: {}\nError: {:?}", expected, err));
+
+    assert!(
+        actual_tree.eq_no_loc(&expected_tree),
+        "Expected {:?} to parse like {:?}\nActual: {:?}\nExpected: {:?}",
+        expr,
+        expected,
+        actual_tree,
+        expected_tree
+    );
+}
+
+#[track_caller]
+fn assert_parses_like(expr: &str, expected: &str) {
+    assert_parses_like_with(expr, expected, siunitx_conf());
+}
+
+// =============================================================================
+// Numbers
+// =============================================================================
+
+#[test]
+fn siunitx_num_plain() {
+    assert_parses_like(r"\num{42}", r"42");
+    assert_parses_like(r"\num{-3.5}", r"-3.5");
+    assert_parses_like(r"\num{.5}", r"0.5");
+    assert_parses_like(r"\num{1,5}", r"1.5");
+}
+
+#[test]
+fn siunitx_num_groups_digits() {
+    assert_parses_like(r"\num{1234}", r"1234");
+    assert_parses_like(r"\num{12345}", r"12\,345");
+    assert_parses_like(r"\num{1234567.891011}", r"1\,234\,567.891\,011");
+    assert_parses_like(r"\num{12 345}", r"12\,345");
+}
+
+#[test]
+fn siunitx_num_exponent() {
+    assert_parses_like(r"\num{1.23e3}", r"1.23\times10^{3}");
+    assert_parses_like(r"\num{4E-5}", r"4\times10^{-5}");
+    assert_parses_like(r"\num{e+7}", r"10^{7}");
+}
+
+#[test]
+fn siunitx_num_uncertainty() {
+    assert_parses_like(r"\num{1.23(4)}", r"1.23(4)");
+    assert_parses_like(r"\num{1.23 \pm 0.04}", r"1.23\pm0.04");
+    assert_parses_like(r"\num[separate-uncertainty=true]{1.23(4)}", r"1.23\pm0.04");
+    assert_parses_like(r"\num[uncertainty-mode=separate]{123(45)}", r"123\pm45");
+    assert_parses_like(r"\num[separate-uncertainty=true]{1.2(34)}", r"1.2\pm3.4");
+    // Either decimal marker in the number or the uncertainty is written the same way
+    assert_parses_like(r"\num[separate-uncertainty]{1,23(4)}", r"1.23\pm0.04");
+    assert_parses_like(r"\num[separate-uncertainty]{1,2(3,4)}", r"1.2\pm3.4");
+    assert_parses_like(r"\num[separate-uncertainty]{1.2(3,4)}", r"1.2\pm3.4");
+    assert_parses_like(
+        r"\num[separate-uncertainty, output-decimal-marker={,}]{1,2(3.4)}",
+        r"1{,}2\pm3{,}4",
+    );
+}
+
+#[test]
+fn siunitx_num_options() {
+    assert_parses_like(r"\num[output-decimal-marker={,}]{1.5}", r"1{,}5");
+    assert_parses_like(r"\num[group-digits=false]{12345}", r"12345");
+    assert_parses_like(r"\num[exponent-product=\cdot]{2e3}", r"2\cdot10^{3}");
+
+    let conf = siunitx_conf_with(SiunitxConfig {
+        output_decimal_marker: ",".to_string(),
+        group_minimum_digits: 4,
+        ..SiunitxConfig::default()
+    });
+    assert_parses_like_with(r"\num{1234.5}", r"1\,234{,}5", conf);
+}
+
+#[test]
+fn siunitx_num_invalid() {
+    let conf = siunitx_conf();
+    assert!(matches!(
+        parse_tree(r"\num{1.2.3}", conf.clone()),
+        Err(ParseError::InvalidSiNumber(_))
+    ));
+    assert!(matches!(
+        parse_tree(r"\num{abc}", conf.clone()),
+        Err(ParseError::InvalidSiNumber(_))
+    ));
+    assert!(matches!(
+        parse_tree(r"\num[bogus=1]{1}", conf.clone()),
+        Err(ParseError::InvalidSiOption(_))
+    ));
+    // Only switches can be given without a value
+    assert!(matches!(
+        parse_tree(r"\num[per-mode]{1}", conf),
+        Err(ParseError::InvalidSiOption(_))
+    ));
+}
+
+// =============================================================================
+// Units
+// =============================================================================
+
+#[test]
+fn siunitx_unit_macros() {
+    assert_parses_like(r"\si{\metre}", r"\mathrm{m}");
+    assert_parses_like(r"\si{\kilo\gram}", r"\mathrm{kg}");
+    assert_parses_like(r"\si{\newton\metre}", r"\mathrm{N}\,\mathrm{m}");
+    assert_parses_like(r"\unit{\micro\metre}", r"\text{µ}\mathrm{m}");
+    assert_parses_like(r"\si{\kilo\ohm}", r"\mathrm{k}\Omega");
+}
+
+#[test]
+fn siunitx_units_are_upright() {
+    // `mathnormal` is the class of the Math-Italic font
+    let markup = render_to_html_tree(r"\si{\micro\metre}", siunitx_conf()).to_markup();
+    assert!(markup.contains("µ"), "{}", markup);
+    assert!(!markup.contains("mathnormal"), "{}", markup);
+}
+
+#[test]
+fn siunitx_unit_powers() {
+    assert_parses_like(
+        r"\si{\kilo\metre\per\second\squared}",
+        r"\mathrm{km}\,\mathrm{s}^{-2}",
+    );
+    assert_parses_like(r"\si{\square\metre}", r"\mathrm{m}^{2}");
+    assert_parses_like(r"\si{\metre\cubed}", r"\mathrm{m}^{3}");
+    assert_parses_like(r"\si{\metre\tothe{4}}", r"\mathrm{m}^{4}");
+    assert_parses_like(r"\si{\raiseto{5}\metre}", r"\mathrm{m}^{5}");
+}
+
+#[test]
+fn siunitx_unit_literal() {
+    assert_parses_like(r"\si{km/s^2}", r"\mathrm{km}\,\mathrm{s}^{-2}");
+    assert_parses_like(r"\si{kg.m^2}", r"\mathrm{kg}\,\mathrm{m}^{2}");
+    assert_parses_like(r"\si{m s^-1}", r"\mathrm{m}\,\mathrm{s}^{-1}");
+    assert_parses_like(r"\si{\mu m}", r"\mu\mathrm{m}");
+}
+
+#[test]
+fn siunitx_unit_per_mode() {
+    let fraction = siunitx_conf_with(SiunitxConfig {
+        per_mode: PerMode::Fraction,
+        ..SiunitxConfig::default()
+    });
+    assert_parses_like_with(
+        r"\si{\kilo\metre\per\second\squared}",
+        r"\frac{\mathrm{km}}{\mathrm{s}^{2}}",
+        fraction.clone(),
+    );
+    assert_parses_like_with(r"\si{\per\second}", r"\frac{1}{\mathrm{s}}", fraction);
+
+    assert_parses_like(
+        r"\si[per-mode=symbol]{\joule\per\kilogram\per\kelvin}",
+        r"\mathrm{J}/(\mathrm{kg}\,\mathrm{K})",
+    );
+    assert_parses_like(r"\si[per-mode=symbol]{m/s}", r"\mathrm{m}/\mathrm{s}");
+}
+
+#[test]
+fn siunitx_unit_invalid() {
+    let conf = siunitx_conf();
+    assert!(matches!(
+        parse_tree(r"\si{\kilo}", conf.clone()),
+        Err(ParseError::InvalidSiUnit(_))
+    ));
+    assert!(matches!(
+        parse_tree(r"\si{\metre\per}", conf.clone()),
+        Err(ParseError::InvalidSiUnit(_))
+    ));
+    assert!(matches!(
+        parse_tree(r"\si{^2}", conf),
+        Err(ParseError::InvalidSiUnit(_))
+    ));
+}
+
+// =============================================================================
+// Quantities
+// =============================================================================
+
+#[test]
+fn siunitx_quantities() {
+    assert_parses_like(r"\SI{10}{\metre}", r"10\,\mathrm{m}");
+    assert_parses_like(
+        r"\qty{9.81}{\metre\per\second\squared}",
+        r"9.81\,\mathrm{m}\,\mathrm{s}^{-2}",
+    );
+    assert_parses_like(
+        r"\SI{1.23e3}{km/s}",
+        r"1.23\times10^{3}\,\mathrm{km}\,\mathrm{s}^{-1}",
+    );
+    assert_parses_like(r"\SI{25}{\degreeCelsius}", r"25\,\degree\mathrm{C}");
+}
+
+#[test]
+fn siunitx_quantity_unspaced_units() {
+    assert_parses_like(r"\SI{90}{\degree}", r"90\degree");
+    assert_parses_like(
+        r"\SI{90}{\degree\per\second}",
+        r"90\,\degree\,\mathrm{s}^{-1}",
+    );
+}
+
+#[test]
+fn siunitx_in_text_mode() {
+    assert_parses_like(
+        r"\text{about \SI{5}{\metre}}",
+        r"\text{about $5\,\mathrm{m}$}",
+    );
+}
@@ -0,0 +1,180 @@
+use aliter::{
+    parse_tree,
+    parser::ParserConfig,
+    typst::{render_to_typst, to_typst, TypstOutput},
+};
+
+fn typst_output(expr: &str) -> TypstOutput {
+    let tree = match parse_tree(expr, ParserConfig::default()) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to parse: {}\nError: {:?}", expr, err),
+    };
+    to_typst(&tree)
+}
+
+fn assert_typst(expr: &str, expected: &str) {
+    let output = typst_output(expr);
+    assert_eq!(output.typst, expected, "for {}", expr);
+    assert!(
+        output.diagnostics.is_empty(),
+        "for {}\n{:?}",
+        expr,
+        output.diagnostics
+    );
+}
+
+// =============================================================================
+// Symbols
+// =============================================================================
+
+#[test]
+fn test_letters_and_numbers() {
+    assert_typst("ab", "a b");
+    assert_typst("12x", "12 x");
+    assert_typst(r"\alpha\beta", "alpha beta");
+    assert_typst("x^2 + y_1 = -1", "x^2 + y_1 = -1");
+}
+
+#[test]
+fn test_symbol_names() {
+    assert_typst(r"x \to \infty", "x -> infinity");
+    assert_typst(r"a \leq b \neq c", "a <= b != c");
+    assert_typst(r"a \not\in B", "a in.not B");
+    assert_typst(r"\ldots", "dots.h");
+}
+
+#[test]
+fn test_escapes() {
+    assert_typst("a/b", r"a\/b");
+    assert_typst("[0,1)", r"\[0, 1\)");
+    assert_typst(r"\frac{[0,1)}{2}", r"frac(\[0\, 1\), 2)");
+}
+
+// =============================================================================
+// Functions
+// =============================================================================
+
+#[test]
+fn test_fractions_and_roots() {
+    assert_typst(r"\frac{a+b}{2}", "frac(a + b, 2)");
+    assert_typst(r"\dfrac{a}{b}", "display(frac(a, b))");
+    assert_typst(r"\binom{n}{k}", "binom(n, k)");
+    assert_typst(r"\sqrt{x}", "sqrt(x)");
+    assert_typst(r"\sqrt[3]{x}", "root(3, x)");
+}
+
+#[test]
+fn test_scripts() {
+    assert_typst(r"\sum_{i=0}^n i", "sum_(i = 0)^n i");
+    assert_typst("e^{i\\pi}", "e^(i pi)");
+    assert_typst("f'(x)", "f'(x)");
+    assert_typst("x_{\\text{max}}", "x_\"max\"");
+    assert_typst("{a+b}^2", "attach(a + b, t: 2)");
+    assert_typst(r"\int\limits_0^1", "limits(integral)_0^1");
+}
+
+#[test]
+fn test_operators() {
+    assert_typst(r"\sin x", "sin x");
+    assert_typst(r"\lim_{x \to 0}", "lim_(x -> 0)");
+    assert_typst(r"\operatorname{Tr} A", "op(\"Tr\") A");
+    assert_typst(
+        r"\operatorname*{argmax}_x",
+        "op(\"argmax\", limits: #true)_x",
+    );
+}
+
+#[test]
+fn test_fonts() {
+    assert_typst(r"\mathbb{R}", "bb(R)");
+    assert_typst(r"\mathcal{A}", "cal(A)");
+    assert_typst(r"\mathbf{x}", "bold(upright(x))");
+    assert_typst(r"\text{if } x", "\"if \" x");
+    assert_typst(r"\textbf{a}", "#text(weight: \"bold\", \"a\")");
+}
+
+#[test]
+fn test_delimiters() {
+    assert_typst(r"\left(\frac{a}{b}\right)", "lr(( frac(a, b) ))");
+    assert_typst(r"\left[0,1\right)", r"lr(\[ 0, 1 \))");
+}
+
+// =============================================================================
+// Environments
+// =============================================================================
+
+#[test]
+fn test_matrices() {
+    assert_typst(r"\begin{pmatrix}a&b\\c&d\end{pmatrix}", "mat(a, b; c, d)");
+    assert_typst(
+        r"\begin{bmatrix}1,2\end{bmatrix}",
+        r#"mat(delim: "[", 1\, 2)"#,
+    );
+    assert_typst(r"\begin{vmatrix}a\end{vmatrix}", r#"mat(delim: "|", a)"#);
+    assert_typst(
+        r"\begin{array}{c|c}1&2\\\hline3&4\end{array}",
+        "mat(delim: #none, augment: #(hline: 1, vline: 1), 1, 2; 3, 4)",
+    );
+}
+
+#[test]
+fn test_cases() {
+    assert_typst(
+        r"f(x)=\begin{cases}1 & x>0\\0&\text{otherwise}\end{cases}",
+        "f(x) = cases(1 & x > 0, 0 & \"otherwise\")",
+    );
+}
+
+#[test]
+fn test_aligned() {
+    assert_typst(
+        r"\begin{aligned}a&=b\\c&=d\end{aligned}",
+        r"a &= b \ c &= d",
+    );
+}
+
+// =============================================================================
+// Styling
+// =============================================================================
+
+#[test]
+fn test_color() {
+    assert_typst(
+        r"\color{red}{x+y}",
+        r##"#text(fill: rgb("#FF0000"))[$x + y$]"##,
+    );
+}
+
+#[test]
+fn test_enclosures() {
+    assert_typst(r"\cancel{x}", "cancel(x)");
+    assert_typst(r"\xcancel{x}", "cancel(x, cross: #true)");
+    assert_typst(r"\boxed{x}", "#box(stroke: 0.4pt, inset: 3pt)[$x$]");
+}
+
+#[test]
+fn test_spaces() {
+    assert_typst(r"a\,b\quad c", "a thin b quad c");
+    assert_typst(r"a\!b", "a #h(-0.1667em) b");
+}
+
+// =============================================================================
+// Diagnostics
+// =============================================================================
+
+#[test]
+fn test_diagnostics() {
+    let output = typst_output(r"\mathchoice{a}{b}{c}{d}");
+    assert_eq!(output.typst, "b");
+    assert_eq!(output.diagnostics.len(), 1);
+    assert_eq!(output.diagnostics[0].construct, r"\mathchoice");
+    assert!(output.diagnostics[0].loc.is_some());
+
+    let conf = ParserConfig {
+        display_mode: true,
+        ..ParserConfig::default()
+    };
+    let output = render_to_typst(r"\begin{align}a&=b\end{align}", conf).unwrap();
+    assert_eq!(output.typst, "a &= b");
+    assert_eq!(output.diagnostics[0].construct, "equation numbers");
+}
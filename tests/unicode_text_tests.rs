@@ -0,0 +1,152 @@
+use aliter::{
+    parse_node::ParseNode,
+    parse_tree,
+    parser::ParserConfig,
+    unicode_text::{to_unicode_text, to_unicode_text_with, UnicodeTextFormat},
+};
+
+fn parse(expr: &str) -> Vec<ParseNode> {
+    match parse_tree(expr, ParserConfig::default()) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Failed to parse: {}\nError: {:?}", expr, err),
+    }
+}
+
+fn assert_text(expr: &str, expected: &str) {
+    assert_eq!(to_unicode_text(&parse(expr)), expected, "for {}", expr);
+}
+
+fn assert_unicode_math(expr: &str, expected: &str) {
+    let text = to_unicode_text_with(&parse(expr), UnicodeTextFormat::UnicodeMath);
+    assert_eq!(text, expected, "for {}", expr);
+}
+
+// =============================================================================
+// Plain text
+// =============================================================================
+
+#[test]
+fn unicode_text_should_replace_symbols() {
+    assert_text(r"\alpha\beta\Gamma", "αβΓ");
+    assert_text(r"a \le b \to \infty", "a≤b→∞");
+    assert_text(r"\{x\} \cdots", "{x}⋯");
+    assert_text(r"x \ne 0 \notin", "x≠0∉");
+    assert_text(r"\left\langle x \right|", "⟨x∣");
+}
+
+#[test]
+fn unicode_text_should_put_negations_on_what_they_negate() {
+    assert_text(r"a \not= b", "a≠b");
+    assert_text(r"x \not\in A", "x∉A");
+    assert_text(r"a \not\equiv b", "a≢b");
+    assert_text(r"a \not< b", "a≮b");
+    assert_unicode_math(r"a \not= b", "a≠b");
+    // Without a precomposed character, the slash still follows its base
+    assert_text(r"a \not\approxeq b", "a≊\u{338}b");
+    // A negation with nothing after it keeps its slash
+    assert_text(r"a \not", "a\u{338}");
+}
+
+#[test]
+fn unicode_text_should_write_scripts() {
+    assert_text("x^2", "x²");
+    assert_text("x_{ij}^{(k)}", "xᵢⱼ⁽ᵏ⁾");
+    assert_text(r"\sum_{i=1}^n i^2", "∑ᵢ₌₁ⁿi²");
+    assert_text("f'(x)", "f′(x)");
+    // There are no superscript characters for these
+    assert_text(r"e^{i\pi}", "e^(iπ)");
+    assert_text(r"x^\alpha", "x^α");
+    assert_text("{a+b}^2", "(a+b)²");
+}
+
+#[test]
+fn unicode_text_should_write_fractions_and_roots() {
+    assert_text(r"\frac{a+b}{c^2}", "(a+b)/c²");
+    assert_text(r"\frac{1}{2}", "1/2");
+    assert_text(r"\frac{x}{y}^2", "(x/y)²");
+    assert_text(r"\frac{f(x+1)}{-2}", "f(x+1)/(−2)");
+    assert_text(r"\binom{n}{k}", "(n¦k)");
+    assert_text(r"\sqrt{x}", "√x");
+    assert_text(r"\sqrt{x+1}", "√(x+1)");
+    assert_text(r"\sqrt[3]{x}", "∛x");
+    assert_text(r"\sqrt[n]{x}", "ⁿ√x");
+}
+
+#[test]
+fn unicode_text_should_use_math_alphanumerics() {
+    assert_text(r"\mathbb{R}^n", "ℝⁿ");
+    assert_text(r"\mathbb{N}\mathbb{1}", "ℕ𝟙");
+    assert_text(r"\mathbf{v}_1", "𝐯₁");
+    assert_text(r"\mathcal{H}\mathcal{A}", "ℋ𝒜");
+    assert_text(r"\mathfrak{g}", "𝔤");
+    assert_text(r"\boldsymbol\alpha", "𝜶");
+    assert_text(r"\mathrm{d}x", "dx");
+    assert_text(r"\textbf{ab}", "𝐚𝐛");
+}
+
+#[test]
+fn unicode_text_should_write_accents() {
+    assert_text(r"\hat a + \vec v", "â+v⃗");
+    assert_text(r"\bar{xy}", "(xy)̄");
+    assert_text(r"\overline{AB}", "A̅B̅");
+    assert_text(r"\cancel{x}", "x̸");
+}
+
+#[test]
+fn unicode_text_should_write_functions_and_text() {
+    assert_text(r"\sin^2 x + \cos(x)", "sin² x+cos(x)");
+    assert_text(r"\operatorname{tr} A", "tr A");
+    assert_text(r"\text{if } x > 0", "if x>0");
+    assert_text(r"a\,b\quad c", "a\u{2009}b\u{2003}c");
+}
+
+#[test]
+fn unicode_text_should_write_arrays() {
+    assert_text(r"\begin{pmatrix}a&b\\c&d\end{pmatrix}", "(a, b; c, d)");
+    assert_text(r"\begin{aligned}a&=b\\c&=d\end{aligned}", "a=b; c=d");
+}
+
+// =============================================================================
+// UnicodeMath
+// =============================================================================
+
+#[test]
+fn unicode_math_should_write_scripts_and_fractions() {
+    assert_unicode_math(r"\frac{a+b}{c^2}", "(a+b)/c^2");
+    assert_unicode_math("x_i^{n+1}", "x_i^(n+1)");
+    assert_unicode_math(r"\int_0^\infty f", "∫_0^∞▒f");
+    assert_unicode_math(r"\binom{n}{k}", "(n¦k)");
+}
+
+#[test]
+fn unicode_math_should_keep_operands_apart_from_limits() {
+    assert_unicode_math(r"\sum_{i=1}^n i", "∑_(i=1)^n▒i");
+    assert_unicode_math(r"\prod\limits_{k} a_k^2", "∏_k▒a_k^2");
+    assert_unicode_math(r"\sum_{i=1}^n i^2 + 1", "∑_(i=1)^n▒i^2+1");
+    // Without limits or an operand there is nothing to separate
+    assert_unicode_math(r"\sum i", "∑i");
+    assert_unicode_math(r"\sum_{i=1}^n", "∑_(i=1)^n");
+}
+
+#[test]
+fn unicode_math_should_write_roots() {
+    assert_unicode_math(r"\sqrt{x+1}", "√(x+1)");
+    assert_unicode_math(r"\sqrt[n]{x}", "√(n&x)");
+}
+
+#[test]
+fn unicode_math_should_write_arrays() {
+    assert_unicode_math(r"\begin{pmatrix}a&b\\c&d\end{pmatrix}", "(■(a&b@c&d))");
+    assert_unicode_math(
+        r"\begin{cases}x & x>0\\-x & x\le 0\end{cases}",
+        "{■(x&x>0@−x&x≤0)┤",
+    );
+    assert_unicode_math(r"\begin{aligned}a&=b\\c&=d\end{aligned}", "█(a&=b@c&=d)");
+}
+
+#[test]
+fn unicode_math_should_write_functions_and_text() {
+    assert_unicode_math(r"\sin x", "sin\u{2061}x");
+    assert_unicode_math(r"\text{if } x", "\"if \"x");
+    assert_unicode_math(r"\overline{AB} \boxed{x}", "¯(AB)▭x");
+}